@@ -0,0 +1,37 @@
+//! Checks the golden files against this crate's implementations:
+//!
+//! ```text
+//! cargo run --example golden --features golden [DIR]
+//! ```
+//!
+//! `DIR` defaults to `golden/`. Exits with failure if any value is out of tolerance.
+//! `cargo test --features golden` checks `golden/` the same way.
+
+use std::process::ExitCode;
+use time_series_utils::{GoldenHarness, Tolerance};
+
+fn main() -> ExitCode {
+    let dir = std::env::args().nth(1).unwrap_or_else(|| "golden".to_string());
+    let harness = GoldenHarness::new()
+        .with_tolerance(Tolerance::new(1e-9, 1e-9))
+        .with_operation_tolerance("stl", Tolerance::new(1e-6, 1e-6));
+    let report = match harness.check_dir(&dir) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("golden: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    for case in &report.unsupported {
+        println!("skipped {}: operation not implemented", case);
+    }
+    for mismatch in &report.mismatches {
+        println!("MISMATCH {}", mismatch);
+    }
+    println!("{} values matched, {} mismatched", report.passed, report.mismatches.len());
+    if report.is_ok() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,109 @@
+#!/usr/bin/env python3
+"""Regenerates the golden files checked by `time_series_utils::golden`.
+
+Run from the repository root:
+
+    python3 golden/generate.py
+
+Descriptive statistics come from pandas, and the STL and ACF cases from statsmodels;
+both must be installed, as golden values computed any other way would only check the
+crate against a reimplementation:
+
+    pip install pandas statsmodels
+
+The versions used are recorded in each file's `source`.
+"""
+
+import json
+import math
+import os
+
+import pandas as pd
+import statsmodels
+from statsmodels.tsa.seasonal import STL
+from statsmodels.tsa.stattools import acf
+
+OUT = os.path.dirname(os.path.abspath(__file__))
+
+
+def lcg_noise(n, seed=12345):
+    """Deterministic noise in [-1, 1), independent of any library's RNG."""
+    state = seed
+    out = []
+    for _ in range(n):
+        state = (1103515245 * state + 12345) % 2**31
+        out.append(state / 2**30 - 1)
+    return out
+
+
+def inputs():
+    n = 96
+    noise = lcg_noise(n)
+    monthly = [10 + 0.05 * t + 2 * math.sin(2 * math.pi * t / 12) + 0.5 * noise[t] for t in range(n)]
+    walk = []
+    level = 100.0
+    for e in lcg_noise(200, seed=7):
+        level += e
+        walk.append(level)
+    return {"monthly": monthly, "random_walk": walk, "small": [3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0]}
+
+
+STAT_KEYS = ["count", "sum", "mean", "var", "std", "min", "max", "median"]
+QUANTILES = [0.1, 0.25, 0.75, 0.9]
+
+
+def stats_cases(data):
+    source = "pandas " + pd.__version__
+
+    def compute(values):
+        s = pd.Series(values)
+        out = {"count": float(s.count()), "sum": s.sum(), "mean": s.mean(), "var": s.var(),
+               "std": s.std(), "min": s.min(), "max": s.max(), "median": s.median()}
+        out.update({"quantile_%s" % q: s.quantile(q) for q in QUANTILES})
+        return out
+
+    cases = [{"name": name, "operation": "stats", "input": values, "params": {},
+              "expected": {k: float(v) for k, v in compute(values).items()}}
+             for name, values in data.items()]
+    return source, cases
+
+
+def statsmodels_cases(data):
+    source = "statsmodels " + statsmodels.__version__
+    monthly = data["monthly"]
+    cases = {"stl": [], "acf": []}
+    for robust in (False, True):
+        params = {"period": 12, "seasonal": 7, "robust": robust}
+        # statsmodels' default iterations, recorded for the harness.
+        iterations = {"inner_iter": 2 if robust else 5, "outer_iter": 15 if robust else 0}
+        stl = STL(monthly, **params)
+        res = stl.fit(**iterations)
+        params.update(iterations, trend=int(stl.config["trend"]))
+        cases["stl"].append({"name": "monthly_robust" if robust else "monthly", "operation": "stl",
+                             "input": monthly, "params": params,
+                             "expected": {"trend": list(res.trend), "seasonal": list(res.seasonal),
+                                          "resid": list(res.resid)}})
+    for name in ("monthly", "random_walk"):
+        cases["acf"].append({"name": name, "operation": "acf", "input": data[name], "params": {"nlags": 24},
+                             "expected": {"acf": list(acf(data[name], nlags=24))}})
+    return source, cases
+
+
+def write(name, source, cases):
+    path = os.path.join(OUT, name + ".json")
+    with open(path, "w") as f:
+        json.dump({"source": source, "cases": cases}, f, indent=1)
+        f.write("\n")
+    print("wrote", path)
+
+
+def main():
+    data = inputs()
+    write("stats", *stats_cases(data))
+    source, cases = statsmodels_cases(data)
+    for name, group in cases.items():
+        write(name, source, group)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,175 @@
+//! User-defined mergeable reducers for bucketing, rolling, expanding and chunked
+//! summaries.
+//!
+//! An [`Aggregator`] folds values into a state, merges two states, and turns a state
+//! into its result. Any aggregator can be used by [`Aggregate`] over plain series and by
+//! [`TimestampedSeries::resample_with`] over time buckets. The built-in statistics of
+//! [`Agg`] are aggregators over `f64` too.
+
+use crate::{Agg, TimeSeries, TimestampedSeries};
+
+/// A mergeable reduction over values of type `T`.
+///
+/// Windows may be folded in any order and combined from partial states, so the result
+/// must not depend on the order of `update` and `merge` calls.
+pub trait Aggregator<T> {
+    type State: Clone;
+    type Output;
+
+    /// The state of an empty input.
+    fn init(&self) -> Self::State;
+    fn update(&self, state: &mut Self::State, value: &T);
+    /// Folds `other` into `state`, as if `state` had seen its values too.
+    fn merge(&self, state: &mut Self::State, other: &Self::State);
+    fn finish(&self, state: &Self::State) -> Self::Output;
+
+    /// The state after folding every value of `values`.
+    fn fold(&self, values: &[T]) -> Self::State {
+        let mut state = self.init();
+        for value in values {
+            self.update(&mut state, value);
+        }
+        state
+    }
+}
+
+/// Running moments and extremes, the state behind [`Agg`] as an aggregator.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Moments {
+    pub count: usize,
+    pub sum: f64,
+    pub mean: f64,
+    /// Sum of squared deviations from the mean.
+    pub m2: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Default for Moments {
+    fn default() -> Self {
+        Self { count: 0, sum: 0., mean: 0., m2: 0., min: f64::INFINITY, max: f64::NEG_INFINITY }
+    }
+}
+
+impl Aggregator<f64> for Agg {
+    type State = Moments;
+    type Output = f64;
+
+    fn init(&self) -> Moments {
+        Moments::default()
+    }
+
+    fn update(&self, state: &mut Moments, &value: &f64) {
+        state.count += 1;
+        state.sum += value;
+        let delta = value - state.mean;
+        state.mean += delta / state.count as f64;
+        state.m2 += delta * (value - state.mean);
+        state.min = state.min.min(value);
+        state.max = state.max.max(value);
+    }
+
+    fn merge(&self, state: &mut Moments, other: &Moments) {
+        if other.count == 0 {
+            return;
+        }
+        let count = state.count + other.count;
+        let delta = other.mean - state.mean;
+        state.m2 += other.m2 + delta * delta * (state.count * other.count) as f64 / count as f64;
+        state.mean += delta * other.count as f64 / count as f64;
+        state.count = count;
+        state.sum += other.sum;
+        state.min = state.min.min(other.min);
+        state.max = state.max.max(other.max);
+    }
+
+    /// NaN where the statistic is undefined: an empty input, or fewer than two values
+    /// for the variance.
+    fn finish(&self, state: &Moments) -> f64 {
+        let var = if state.count > 1 { state.m2 / (state.count - 1) as f64 } else { f64::NAN };
+        match (self, state.count) {
+            (Agg::Sum, _) => state.sum,
+            (_, 0) => f64::NAN,
+            (Agg::Mean, _) => state.mean,
+            (Agg::Var, _) => var,
+            (Agg::Std, _) => var.sqrt(),
+            (Agg::Min, _) => state.min,
+            (Agg::Max, _) => state.max,
+        }
+    }
+}
+
+pub trait Aggregate<T> {
+    /// Result over the whole series.
+    fn aggregate<A: Aggregator<T>>(&self, agg: &A) -> A::Output;
+    /// Result over each trailing window of `window` points; `n - window + 1` outputs.
+    /// Uses two stacks of partial states, so each step costs O(1) merges amortized even
+    /// for aggregators that cannot remove values.
+    fn rolling_with<A: Aggregator<T>>(&self, window: usize, agg: &A) -> TimeSeries<A::Output>;
+    /// Result over every prefix of the series.
+    fn expanding_with<A: Aggregator<T>>(&self, agg: &A) -> TimeSeries<A::Output>;
+    /// States of consecutive chunks of `size` points (the last may be shorter), to be
+    /// merged later or elsewhere, e.g. per-file summaries of a larger dataset.
+    fn chunk_states<A: Aggregator<T>>(&self, size: usize, agg: &A) -> Vec<A::State>;
+}
+
+impl<T> Aggregate<T> for TimeSeries<T> {
+    fn aggregate<A: Aggregator<T>>(&self, agg: &A) -> A::Output {
+        agg.finish(&agg.fold(self.as_slice()))
+    }
+
+    fn rolling_with<A: Aggregator<T>>(&self, window: usize, agg: &A) -> TimeSeries<A::Output> {
+        assert!(window > 0, "window must be positive");
+        let values = self.as_slice();
+        // `front` holds states of the oldest values, each covering itself and every newer
+        // value below it; `back` folds the values pushed since the last refill.
+        let mut front: Vec<A::State> = Vec::new();
+        let mut back = agg.init();
+        let mut back_start = 0;
+        let mut out = TimeSeries(Vec::new());
+        for (i, value) in values.iter().enumerate() {
+            agg.update(&mut back, value);
+            if i + 1 < window {
+                continue;
+            }
+            if front.is_empty() {
+                let mut suffix = agg.init();
+                for value in values[back_start..=i].iter().rev() {
+                    agg.update(&mut suffix, value);
+                    front.push(suffix.clone());
+                }
+                back = agg.init();
+                back_start = i + 1;
+            }
+            let mut state = front.pop().expect("front was refilled");
+            agg.merge(&mut state, &back);
+            out.push(agg.finish(&state));
+        }
+        out
+    }
+
+    fn expanding_with<A: Aggregator<T>>(&self, agg: &A) -> TimeSeries<A::Output> {
+        let mut state = agg.init();
+        TimeSeries(
+            self.as_slice()
+                .iter()
+                .map(|value| {
+                    agg.update(&mut state, value);
+                    agg.finish(&state)
+                })
+                .collect(),
+        )
+    }
+
+    fn chunk_states<A: Aggregator<T>>(&self, size: usize, agg: &A) -> Vec<A::State> {
+        assert!(size > 0, "chunk size must be positive");
+        self.as_slice().chunks(size).map(|chunk| agg.fold(chunk)).collect()
+    }
+}
+
+impl<T: Clone> TimestampedSeries<T> {
+    /// [`resample`](Self::resample) with an [`Aggregator`] per bucket.
+    pub fn resample_with<A: Aggregator<T>>(&self, step: i64, agg: &A) -> TimestampedSeries<A::Output> {
+        self.resample(step, |values| agg.finish(&agg.fold(values)))
+    }
+}
@@ -0,0 +1,180 @@
+//! Batch alignment of many timestamped series to a shared time range in one call, and
+//! arithmetic between series sampled at different frequencies.
+
+use crate::{Agg, Statistics, TimeSeries, TimestampedSeries};
+use std::collections::BTreeSet;
+
+/// How [`align_all`] fills timestamps a series lacks under [`AlignPolicy::Union`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fill<T> {
+    Value(T),
+    /// The last earlier value of the same series, or its first value before it starts.
+    Forward,
+}
+
+/// Target range of [`align_all`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum AlignPolicy<T> {
+    /// Trims every series to the range all of them cover, from the latest first timestamp
+    /// to the earliest last one. Timestamps inside the range are not changed.
+    Overlap,
+    /// Puts every series on the union of all timestamps, filling the ones it lacks.
+    Union(Fill<T>),
+}
+
+/// What [`align_all`] changed, with one entry per series in input order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AlignReport {
+    /// First and last timestamp of the aligned range; `None` when it is empty.
+    pub range: Option<(i64, i64)>,
+    /// Timestamps removed from each series.
+    pub dropped: Vec<Vec<i64>>,
+    /// Timestamps added to each series.
+    pub filled: Vec<Vec<i64>>,
+}
+
+impl AlignReport {
+    pub fn dropped_count(&self) -> usize {
+        self.dropped.iter().map(Vec::len).sum()
+    }
+
+    pub fn filled_count(&self) -> usize {
+        self.filled.iter().map(Vec::len).sum()
+    }
+}
+
+/// Aligns every series of `series` in place according to `policy`. Under
+/// [`AlignPolicy::Overlap`] an empty series, or series that do not overlap, leave every
+/// series empty.
+pub fn align_all<T: Clone>(series: &mut [TimestampedSeries<T>], policy: AlignPolicy<T>) -> AlignReport {
+    let mut report = AlignReport {
+        range: None,
+        dropped: vec![Vec::new(); series.len()],
+        filled: vec![Vec::new(); series.len()],
+    };
+    match policy {
+        AlignPolicy::Overlap => {
+            let bounds: Option<Vec<(i64, i64)>> =
+                series.iter().map(|s| Some((*s.timestamps().first()?, *s.timestamps().last()?))).collect();
+            let range = bounds
+                .and_then(|b| Some((b.iter().map(|r| r.0).max()?, b.iter().map(|r| r.1).min()?)))
+                .filter(|(start, end)| start <= end);
+            report.range = range;
+            let inside = |t: i64| range.is_some_and(|(start, end)| t >= start && t <= end);
+            for (s, dropped) in series.iter_mut().zip(&mut report.dropped) {
+                dropped.extend(s.timestamps().iter().copied().filter(|&t| !inside(t)));
+                if !dropped.is_empty() {
+                    *s = s.iter().filter(|&(t, _)| inside(t)).map(|(t, v)| (t, v.clone())).collect();
+                }
+            }
+        }
+        AlignPolicy::Union(fill) => {
+            let union: BTreeSet<i64> = series.iter().flat_map(|s| s.timestamps().iter().copied()).collect();
+            report.range = union.first().zip(union.last()).map(|(&a, &b)| (a, b));
+            for (s, filled) in series.iter_mut().zip(&mut report.filled) {
+                *s = fill_to(s, &union, &fill, filled);
+            }
+        }
+    }
+    report
+}
+
+/// `series` on every timestamp of `union`, recording the ones filled in `filled`.
+fn fill_to<T: Clone>(
+    series: &TimestampedSeries<T>,
+    union: &BTreeSet<i64>,
+    fill: &Fill<T>,
+    filled: &mut Vec<i64>,
+) -> TimestampedSeries<T> {
+    let mut out = TimestampedSeries::new();
+    let mut points = series.iter().peekable();
+    let mut last: Option<&T> = None;
+    for &t in union {
+        let mut present = false;
+        while let Some((_, v)) = points.next_if(|&(u, _)| u == t) {
+            out.push(t, v.clone());
+            last = Some(v);
+            present = true;
+        }
+        if present {
+            continue;
+        }
+        let value = match fill {
+            Fill::Value(v) => Some(v),
+            Fill::Forward => last.or_else(|| series.values().as_slice().first()),
+        };
+        // An empty series has nothing to carry forward and stays empty.
+        if let Some(v) = value {
+            out.push(t, v.clone());
+            filled.push(t);
+        }
+    }
+    out
+}
+
+/// How [`TimestampedSeries::combine_aligned`] matches points of series sampled at
+/// different frequencies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrequencyRule {
+    /// Keeps the timestamps of the left operand, pairing each with the latest right-hand
+    /// value at or before it, e.g. a monthly value stamped at the start of its month
+    /// applies to every day of that month. Points before the first right-hand value are
+    /// NaN.
+    Broadcast,
+    /// Aggregates both operands into buckets of `step` (as in
+    /// [`TimestampedSeries::resample`]) and pairs the buckets present in both.
+    Aggregate { step: i64, agg: Agg },
+}
+
+fn aggregate(agg: Agg, values: &[f64]) -> f64 {
+    let values = TimeSeries(values.to_vec());
+    match agg {
+        Agg::Sum => values.sum(),
+        Agg::Mean => values.mean(),
+        Agg::Var => values.var(),
+        Agg::Std => values.std(),
+        Agg::Min => values.min(),
+        Agg::Max => values.max(),
+    }
+}
+
+impl TimestampedSeries<f64> {
+    /// Applies `f` to pairs of points of `self` and `other` matched by `rule`.
+    pub fn combine_aligned<F>(&self, other: &Self, rule: FrequencyRule, f: F) -> Self
+    where
+        F: Fn(f64, f64) -> f64,
+    {
+        match rule {
+            FrequencyRule::Broadcast => {
+                self.iter().map(|(t, &x)| (t, other.asof(t).map_or(f64::NAN, |&y| f(x, y)))).collect()
+            }
+            FrequencyRule::Aggregate { step, agg } => {
+                let left = self.resample(step, |v| aggregate(agg, v));
+                let right = other.resample(step, |v| aggregate(agg, v));
+                let mut right = right.iter().peekable();
+                left.iter()
+                    .filter_map(|(t, &x)| {
+                        while right.next_if(|&(u, _)| u < t).is_some() {}
+                        right.next_if(|&(u, _)| u == t).map(|(_, &y)| (t, f(x, y)))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    pub fn add_aligned(&self, other: &Self, rule: FrequencyRule) -> Self {
+        self.combine_aligned(other, rule, |x, y| x + y)
+    }
+
+    pub fn sub_aligned(&self, other: &Self, rule: FrequencyRule) -> Self {
+        self.combine_aligned(other, rule, |x, y| x - y)
+    }
+
+    pub fn mul_aligned(&self, other: &Self, rule: FrequencyRule) -> Self {
+        self.combine_aligned(other, rule, |x, y| x * y)
+    }
+
+    pub fn div_aligned(&self, other: &Self, rule: FrequencyRule) -> Self {
+        self.combine_aligned(other, rule, |x, y| x / y)
+    }
+}
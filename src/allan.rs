@@ -0,0 +1,34 @@
+//! Allan variance of rate-like data (fractional frequency, angular rate, ...) sampled at a
+//! fixed interval.
+
+use crate::TimeSeries;
+
+pub trait AllanVariance {
+    /// Non-overlapping Allan variance for each averaging interval in `taus`, given in
+    /// samples: half the mean squared difference of consecutive `tau`-point chunk means.
+    /// Intervals leaving fewer than two full chunks are NaN.
+    fn allan_variance(&self, taus: &[usize]) -> TimeSeries<f64>;
+    /// Square root of [`allan_variance`](AllanVariance::allan_variance).
+    fn allan_deviation(&self, taus: &[usize]) -> TimeSeries<f64>;
+}
+
+impl AllanVariance for TimeSeries<f64> {
+    fn allan_variance(&self, taus: &[usize]) -> TimeSeries<f64> {
+        taus.iter()
+            .map(|&tau| {
+                assert!(tau > 0, "averaging interval must be positive");
+                let means: Vec<f64> =
+                    self.as_slice().chunks_exact(tau).map(|c| c.iter().sum::<f64>() / tau as f64).collect();
+                if means.len() < 2 {
+                    return f64::NAN;
+                }
+                let sum: f64 = means.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum();
+                sum / (2. * (means.len() - 1) as f64)
+            })
+            .collect()
+    }
+
+    fn allan_deviation(&self, taus: &[usize]) -> TimeSeries<f64> {
+        self.allan_variance(taus).map(|v| v.sqrt())
+    }
+}
@@ -0,0 +1,182 @@
+//! Unsupervised anomaly scoring of series.
+
+use crate::fft::{fft, ifft};
+use crate::special::student_t_ppf;
+use crate::stats::quantile_sorted;
+use crate::{Complex, Decompose, Stl, TimeSeries};
+
+/// Per-point anomaly scores and the points flagged as anomalous.
+#[derive(Clone, Debug)]
+pub struct AnomalyScores {
+    pub scores: TimeSeries<f64>,
+    pub flags: TimeSeries<bool>,
+}
+
+/// Spectral residual saliency (Ren et al., 2019): points stand out where the log
+/// amplitude spectrum deviates from its local average, which suppresses regular
+/// seasonal patterns.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpectralResidual {
+    /// Width of the moving average over the log amplitude spectrum.
+    pub spectrum_window: usize,
+    /// Number of preceding saliency values a point is compared with.
+    pub score_window: usize,
+    /// Points extrapolated past the end so the last real points are not at the boundary
+    /// of the transform.
+    pub extension: usize,
+    /// Score above which a point is flagged.
+    pub threshold: f64,
+}
+
+impl Default for SpectralResidual {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpectralResidual {
+    /// The settings of the original paper: windows 3 and 21, 5 extrapolated points, and
+    /// threshold 3.
+    pub fn new() -> Self {
+        Self { spectrum_window: 3, score_window: 21, extension: 5, threshold: 3. }
+    }
+
+    pub fn with_threshold(self, threshold: f64) -> Self {
+        Self { threshold, ..self }
+    }
+
+    /// Saliency map: magnitude of the inverse transform of the spectral residual.
+    fn saliency(&self, x: &[f64]) -> Vec<f64> {
+        let n = x.len();
+        let mut buf: Vec<Complex> = x.iter().map(|&v| Complex::new(v, 0.)).collect();
+        fft(&mut buf, false);
+        let log_amplitude: Vec<f64> = buf.iter().map(|c| c.abs().max(1e-12).ln()).collect();
+        let half = self.spectrum_window / 2;
+        for (k, c) in buf.iter_mut().enumerate() {
+            let lo = k.saturating_sub(half);
+            let hi = (k + half + 1).min(n);
+            let average = log_amplitude[lo..hi].iter().sum::<f64>() / (hi - lo) as f64;
+            *c = Complex::from_polar((log_amplitude[k] - average).exp(), c.arg());
+        }
+        ifft(&mut buf);
+        buf.iter().map(Complex::abs).collect()
+    }
+
+    pub fn detect(&self, series: &TimeSeries<f64>) -> AnomalyScores {
+        let x = series.as_slice();
+        let n = x.len();
+        if n < 2 {
+            return AnomalyScores { scores: TimeSeries(vec![0.; n]), flags: TimeSeries(vec![false; n]) };
+        }
+        // Extend with the last value moved along the average slope towards it.
+        let m = self.extension.min(n - 1);
+        let mut extended = x.to_vec();
+        if m > 0 {
+            let last = x[n - 1];
+            let gradient = (1..=m).map(|i| (last - x[n - 1 - i]) / i as f64).sum::<f64>() / m as f64;
+            let estimate = x[n - m] + gradient * m as f64;
+            extended.extend(std::iter::repeat_n(estimate, m));
+        }
+        let saliency = self.saliency(&extended);
+        let scores: Vec<f64> = (0..n)
+            .map(|i| {
+                let lo = i.saturating_sub(self.score_window);
+                if lo == i {
+                    return 0.;
+                }
+                let average = saliency[lo..i].iter().sum::<f64>() / (i - lo) as f64;
+                if average > 0. {
+                    (saliency[i] - average) / average
+                } else {
+                    0.
+                }
+            })
+            .collect();
+        let flags = scores.iter().map(|&s| s > self.threshold).collect();
+        AnomalyScores { scores: TimeSeries(scores), flags: TimeSeries(flags) }
+    }
+}
+
+/// Outcome of a (generalized) ESD test.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EsdResult {
+    /// Indices of the detected anomalies, most extreme first.
+    pub anomalies: Vec<usize>,
+    /// Test statistic `R_i` of each of the `max_anomalies` removal steps.
+    pub statistics: Vec<f64>,
+    /// Critical value `lambda_i` of each step.
+    pub critical_values: Vec<f64>,
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    quantile_sorted(&sorted, 0.5)
+}
+
+/// Generalized ESD (Rosner, 1983) over `(index, value)` pairs, with the mean and standard
+/// deviation, or with the median and MAD when `robust`.
+fn esd(points: Vec<(usize, f64)>, max_anomalies: usize, alpha: f64, robust: bool) -> EsdResult {
+    let n = points.len();
+    let mut remaining = points;
+    let mut removed = Vec::new();
+    let mut statistics = Vec::new();
+    let mut critical_values = Vec::new();
+    let mut detected = 0;
+    for i in 1..=max_anomalies.min(n.saturating_sub(2)) {
+        let values: Vec<f64> = remaining.iter().map(|p| p.1).collect();
+        let (center, scale) = if robust {
+            let m = median(&values);
+            let deviations: Vec<f64> = values.iter().map(|v| (v - m).abs()).collect();
+            (m, 1.4826 * median(&deviations))
+        } else {
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+            (mean, var.sqrt())
+        };
+        if scale <= 0. {
+            break;
+        }
+        let (position, r) = remaining
+            .iter()
+            .enumerate()
+            .map(|(k, p)| (k, (p.1 - center).abs() / scale))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .expect("at least two points remain");
+        let m = (n - i + 1) as f64;
+        let t = student_t_ppf(1. - alpha / (2. * m), m - 2.);
+        let lambda = (m - 1.) * t / ((m - 2. + t * t) * m).sqrt();
+        removed.push(remaining.remove(position).0);
+        statistics.push(r);
+        critical_values.push(lambda);
+        if r > lambda {
+            detected = i;
+        }
+    }
+    removed.truncate(detected);
+    EsdResult { anomalies: removed, statistics, critical_values }
+}
+
+/// Extreme studentized deviate tests for multiple outliers.
+pub trait Esd {
+    /// Generalized ESD test for up to `max_anomalies` outliers at significance `alpha`,
+    /// assuming the remaining values are roughly normal.
+    fn generalized_esd(&self, max_anomalies: usize, alpha: f64) -> EsdResult;
+    /// Seasonal hybrid ESD (Hochenbaum et al., 2017): removes the STL seasonal component
+    /// and the median, then runs a generalized ESD with the median and MAD, which stays
+    /// reliable when many points are anomalous.
+    fn seasonal_hybrid_esd(&self, period: usize, max_anomalies: usize, alpha: f64) -> EsdResult;
+}
+
+impl Esd for TimeSeries<f64> {
+    fn generalized_esd(&self, max_anomalies: usize, alpha: f64) -> EsdResult {
+        esd(self.as_slice().iter().copied().enumerate().collect(), max_anomalies, alpha, false)
+    }
+
+    fn seasonal_hybrid_esd(&self, period: usize, max_anomalies: usize, alpha: f64) -> EsdResult {
+        let decomposition = self.stl(&Stl::new(period));
+        let m = median(self.as_slice());
+        let residuals = self.as_slice().iter().zip(decomposition.seasonal.as_slice()).map(|(x, s)| x - s - m);
+        esd(residuals.enumerate().collect(), max_anomalies, alpha, true)
+    }
+}
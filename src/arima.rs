@@ -0,0 +1,556 @@
+//! Seasonal ARIMA models and automatic order selection.
+//!
+//! An ARIMA(p,d,q)(P,D,Q)s model differences the series `d` times and seasonally `D`
+//! times at period `s`, then fits a multiplicative ARMA model to what remains. Models
+//! are fitted by conditional sum of squares, taking the differenced series to start at
+//! its mean with no past shocks, and estimates are kept stationary and invertible.
+//!
+//! [`auto_arima`] chooses the order the way Hyndman and Khandakar's stepwise algorithm
+//! does: the differencing from a KPSS test and the seasonal strength, then the ARMA
+//! orders by a local search on an information criterion.
+
+use crate::forecast::gaussian_quantiles;
+use crate::optimize::nelder_mead_monitored;
+use crate::{Error, Forecaster, Monitor, Stl, TimeSeries};
+use std::collections::HashSet;
+
+/// The orders of an ARIMA(p,d,q)(P,D,Q)s model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ArimaOrder {
+    pub p: usize,
+    pub d: usize,
+    pub q: usize,
+    pub seasonal_p: usize,
+    pub seasonal_d: usize,
+    pub seasonal_q: usize,
+    /// Season length; 1 for a non-seasonal model.
+    pub period: usize,
+}
+
+impl ArimaOrder {
+    /// A non-seasonal ARIMA(p,d,q).
+    pub fn new(p: usize, d: usize, q: usize) -> Self {
+        Self { p, d, q, seasonal_p: 0, seasonal_d: 0, seasonal_q: 0, period: 1 }
+    }
+
+    /// # Panics
+    /// Panics if `period` is less than 2.
+    pub fn with_seasonal(self, p: usize, d: usize, q: usize, period: usize) -> Self {
+        assert!(period > 1, "seasonal period must be at least 2");
+        Self { seasonal_p: p, seasonal_d: d, seasonal_q: q, period, ..self }
+    }
+
+    fn is_seasonal(&self) -> bool {
+        self.period > 1
+    }
+
+    /// Whether the model has a constant: the mean of a stationary series, or the drift
+    /// of a once-differenced one.
+    fn has_constant(&self) -> bool {
+        self.d + self.seasonal_d <= 1
+    }
+
+    fn arma_parameters(&self) -> usize {
+        self.p + self.q + self.seasonal_p + self.seasonal_q
+    }
+
+    /// The lag of each differencing, seasonal ones first.
+    fn differencing_lags(&self) -> Vec<usize> {
+        let seasonal = std::iter::repeat_n(self.period, if self.is_seasonal() { self.seasonal_d } else { 0 });
+        seasonal.chain(std::iter::repeat_n(1, self.d)).collect()
+    }
+}
+
+/// Criterion for comparing models fitted to the same differenced series.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InformationCriterion {
+    #[default]
+    Aic,
+    Bic,
+}
+
+/// Product of two polynomials in the backshift operator, lowest power first.
+fn poly_mul(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut out = vec![0.; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            out[i + j] += x * y;
+        }
+    }
+    out
+}
+
+/// `1 + sign * (c_1 B^lag + c_2 B^(2 lag) + ...)`.
+fn lag_poly(coefficients: &[f64], lag: usize, sign: f64) -> Vec<f64> {
+    let mut out = vec![0.; coefficients.len() * lag + 1];
+    out[0] = 1.;
+    for (i, c) in coefficients.iter().enumerate() {
+        out[(i + 1) * lag] = sign * c;
+    }
+    out
+}
+
+/// Whether `x_t = c_1 x_{t-1} + ... + c_k x_{t-k}` is stationary, by stepping down to
+/// the partial autocorrelations, which must all lie inside `(-1, 1)`.
+fn is_stationary(coefficients: &[f64]) -> bool {
+    let mut a = coefficients.to_vec();
+    while let Some(&r) = a.last() {
+        if r.abs() >= 1. || r.is_nan() {
+            return false;
+        }
+        let k = a.len() - 1;
+        a = (0..k).map(|j| (a[j] + r * a[k - 1 - j]) / (1. - r * r)).collect();
+    }
+    true
+}
+
+fn difference(series: &[f64], lag: usize) -> Vec<f64> {
+    series.iter().skip(lag).zip(series).map(|(x, y)| x - y).collect()
+}
+
+/// Residuals of the ARMA model with lag coefficients `ar` and `ma` (lag 1 first) around
+/// `mean`, taking deviations and shocks before the start as zero.
+fn residuals(w: &[f64], ar: &[f64], ma: &[f64], mean: f64) -> Vec<f64> {
+    let mut e = Vec::with_capacity(w.len());
+    for t in 0..w.len() {
+        let ar_part: f64 = ar.iter().zip((0..t).rev()).map(|(a, i)| a * (w[i] - mean)).sum();
+        let ma_part: f64 = ma.iter().zip((0..t).rev()).map(|(m, i)| m * e[i]).sum();
+        e.push(w[t] - mean - ar_part - ma_part);
+    }
+    e
+}
+
+/// A seasonal ARIMA model.
+#[derive(Clone, Debug)]
+pub struct Arima {
+    order: ArimaOrder,
+    /// φ, θ, Φ and Θ, in that order.
+    parameters: Vec<f64>,
+    mean: f64,
+    sigma2: f64,
+    log_likelihood: f64,
+    /// The series after each differencing, the original first.
+    levels: Vec<Vec<f64>>,
+    residuals: Vec<f64>,
+}
+
+impl Arima {
+    pub fn new(order: ArimaOrder) -> Self {
+        Self {
+            order,
+            parameters: Vec::new(),
+            mean: f64::NAN,
+            sigma2: f64::NAN,
+            log_likelihood: f64::NAN,
+            levels: Vec::new(),
+            residuals: Vec::new(),
+        }
+    }
+
+    pub fn order(&self) -> ArimaOrder {
+        self.order
+    }
+
+    fn split(&self) -> (&[f64], &[f64], &[f64], &[f64]) {
+        let o = &self.order;
+        if self.parameters.is_empty() {
+            return (&[], &[], &[], &[]);
+        }
+        let (ar, rest) = self.parameters.split_at(o.p);
+        let (ma, rest) = rest.split_at(o.q);
+        let (sar, sma) = rest.split_at(o.seasonal_p);
+        (ar, ma, sar, sma)
+    }
+
+    /// φ, lag 1 first; empty before fitting.
+    pub fn ar_coefficients(&self) -> &[f64] {
+        self.split().0
+    }
+
+    /// θ, lag 1 first.
+    pub fn ma_coefficients(&self) -> &[f64] {
+        self.split().1
+    }
+
+    /// Φ, lag `period` first.
+    pub fn seasonal_ar_coefficients(&self) -> &[f64] {
+        self.split().2
+    }
+
+    /// Θ, lag `period` first.
+    pub fn seasonal_ma_coefficients(&self) -> &[f64] {
+        self.split().3
+    }
+
+    /// The constant of the differenced series: its mean, or zero for models without one.
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Variance of the one-step errors.
+    pub fn sigma2(&self) -> f64 {
+        self.sigma2
+    }
+
+    /// The residuals on the differenced series.
+    pub fn residuals(&self) -> TimeSeries<f64> {
+        TimeSeries(self.residuals.clone())
+    }
+
+    fn parameter_count(&self) -> usize {
+        self.order.arma_parameters() + self.order.has_constant() as usize + 1
+    }
+
+    pub fn aic(&self) -> f64 {
+        -2. * self.log_likelihood + 2. * self.parameter_count() as f64
+    }
+
+    pub fn bic(&self) -> f64 {
+        -2. * self.log_likelihood + (self.residuals.len() as f64).ln() * self.parameter_count() as f64
+    }
+
+    pub fn criterion(&self, criterion: InformationCriterion) -> f64 {
+        match criterion {
+            InformationCriterion::Aic => self.aic(),
+            InformationCriterion::Bic => self.bic(),
+        }
+    }
+
+    /// The lag coefficients of the multiplicative AR and MA parts, lag 1 first.
+    fn expanded(order: &ArimaOrder, parameters: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        let (ar, rest) = parameters.split_at(order.p);
+        let (ma, rest) = rest.split_at(order.q);
+        let (sar, sma) = rest.split_at(order.seasonal_p);
+        let s = order.period;
+        let ar = poly_mul(&lag_poly(ar, 1, -1.), &lag_poly(sar, s, -1.));
+        let ma = poly_mul(&lag_poly(ma, 1, 1.), &lag_poly(sma, s, 1.));
+        (ar[1..].iter().map(|c| -c).collect(), ma[1..].to_vec())
+    }
+
+    fn admissible(order: &ArimaOrder, parameters: &[f64]) -> bool {
+        let (ar, rest) = parameters.split_at(order.p);
+        let (ma, rest) = rest.split_at(order.q);
+        let (sar, sma) = rest.split_at(order.seasonal_p);
+        let negated = |c: &[f64]| c.iter().map(|x| -x).collect::<Vec<_>>();
+        is_stationary(ar) && is_stationary(sar) && is_stationary(&negated(ma)) && is_stationary(&negated(sma))
+    }
+}
+
+impl Arima {
+    /// [`Forecaster::fit`], reporting the progress of the least-squares search to
+    /// `monitor`. Leaves the model untouched if cancelled.
+    pub fn fit_monitored(&mut self, series: &TimeSeries<f64>, monitor: &mut Monitor) -> Result<(), Error> {
+        let order = self.order;
+        trace_span!("fit", model = "arima", p = order.p, d = order.d, q = order.q, n = series.len());
+        let lags = order.differencing_lags();
+        let k = order.arma_parameters();
+        let required = lags.iter().sum::<usize>() + k + order.has_constant() as usize + 3;
+        if series.len() < required {
+            return Err(Error::InsufficientData { required, found: series.len() });
+        }
+        if series.as_slice().iter().any(|x| !x.is_finite()) {
+            return Err(Error::InvalidInput("ARIMA needs finite values".to_string()));
+        }
+        let mut levels = vec![series.as_slice().to_vec()];
+        for &lag in &lags {
+            let next = difference(&levels[levels.len() - 1], lag);
+            levels.push(next);
+        }
+        let w = &levels[levels.len() - 1];
+        let mean = if order.has_constant() { w.iter().sum::<f64>() / w.len() as f64 } else { 0. };
+        let sse = |params: &[f64]| {
+            if !Self::admissible(&order, params) {
+                return f64::INFINITY;
+            }
+            let (ar, ma) = Self::expanded(&order, params);
+            residuals(w, &ar, &ma, mean).iter().map(|e| e * e).sum::<f64>()
+        };
+        let parameters = if k == 0 {
+            monitor.finish();
+            Vec::new()
+        } else {
+            nelder_mead_monitored(sse, &vec![0.; k], 0.1, 2000, monitor)
+        };
+        monitor.check()?;
+        let (ar, ma) = Self::expanded(&order, &parameters);
+        let e = residuals(w, &ar, &ma, mean);
+        let n = e.len() as f64;
+        let sigma2 = e.iter().map(|x| x * x).sum::<f64>() / n;
+        self.log_likelihood = -0.5 * n * ((2. * std::f64::consts::PI * sigma2).ln() + 1.);
+        self.parameters = parameters;
+        self.mean = mean;
+        self.sigma2 = sigma2;
+        self.residuals = e;
+        self.levels = levels;
+        Ok(())
+    }
+}
+
+impl Forecaster for Arima {
+    fn fit(&mut self, series: &TimeSeries<f64>) -> Result<(), Error> {
+        self.fit_monitored(series, &mut Monitor::new())
+    }
+
+    fn predict(&self, horizon: usize) -> TimeSeries<f64> {
+        let Some(w) = self.levels.last() else {
+            return TimeSeries(vec![f64::NAN; horizon]);
+        };
+        let (ar, ma) = Self::expanded(&self.order, &self.parameters);
+        let mut w = w.clone();
+        let mut e = self.residuals.clone();
+        for _ in 0..horizon {
+            let t = w.len();
+            let ar_part: f64 = ar.iter().zip((0..t).rev()).map(|(a, i)| a * (w[i] - self.mean)).sum();
+            let ma_part: f64 = ma.iter().zip((0..t).rev()).map(|(m, i)| m * e[i]).sum();
+            w.push(self.mean + ar_part + ma_part);
+            e.push(0.);
+        }
+        let mut forecast = w.split_off(w.len() - horizon);
+        for (level, lag) in self.levels.iter().zip(self.order.differencing_lags()).rev() {
+            let mut extended = level.clone();
+            for f in &forecast {
+                let back = extended[extended.len() - lag];
+                extended.push(f + back);
+            }
+            forecast = extended.split_off(level.len());
+        }
+        TimeSeries(forecast)
+    }
+
+    /// Gaussian quantiles from the ψ-weights of the model with its differencing.
+    fn predict_quantiles(&self, horizon: usize, quantiles: &[f64]) -> Vec<TimeSeries<f64>> {
+        if self.levels.is_empty() {
+            return vec![TimeSeries(vec![f64::NAN; horizon]); quantiles.len()];
+        }
+        let (ar, ma) = Self::expanded(&self.order, &self.parameters);
+        let mut full = lag_poly(&ar, 1, -1.);
+        for lag in self.order.differencing_lags() {
+            full = poly_mul(&full, &lag_poly(&[1.], lag, -1.));
+        }
+        let phi: Vec<f64> = full[1..].iter().map(|c| -c).collect();
+        let mut psi = vec![1.];
+        for j in 1..horizon {
+            let ar_part: f64 = (1..=j.min(phi.len())).map(|i| phi[i - 1] * psi[j - i]).sum();
+            psi.push(ma.get(j - 1).copied().unwrap_or(0.) + ar_part);
+        }
+        let mut cumulative = Vec::with_capacity(horizon);
+        let mut acc = 0.;
+        for w in &psi {
+            acc += w * w;
+            cumulative.push(acc);
+        }
+        gaussian_quantiles(&self.predict(horizon), quantiles, |h| (self.sigma2 * cumulative[h - 1]).sqrt())
+    }
+}
+
+/// KPSS statistic for level stationarity, with the Newey–West long-run variance at
+/// `trunc(4 (n / 100)^(1/4))` lags.
+fn kpss(series: &[f64]) -> f64 {
+    let n = series.len();
+    let mean = series.iter().sum::<f64>() / n as f64;
+    let e: Vec<f64> = series.iter().map(|x| x - mean).collect();
+    let lags = (4. * (n as f64 / 100.).powf(0.25)) as usize;
+    let mut variance = e.iter().map(|x| x * x).sum::<f64>() / n as f64;
+    for k in 1..=lags.min(n - 1) {
+        let gamma: f64 = e[k..].iter().zip(&e).map(|(a, b)| a * b).sum::<f64>() / n as f64;
+        variance += 2. * (1. - k as f64 / (lags + 1) as f64) * gamma;
+    }
+    let mut partial = 0.;
+    let mut sum_sq = 0.;
+    for x in &e {
+        partial += x;
+        sum_sq += partial * partial;
+    }
+    sum_sq / (n as f64 * n as f64 * variance)
+}
+
+/// 5% critical value of the KPSS level-stationarity test.
+const KPSS_CRITICAL: f64 = 0.463;
+
+/// Seasonal strength above which a seasonal difference is taken, as in Wang, Smith and
+/// Hyndman (2006).
+const SEASONAL_STRENGTH_THRESHOLD: f64 = 0.64;
+
+fn variance(x: &[f64]) -> f64 {
+    let mean = x.iter().sum::<f64>() / x.len() as f64;
+    x.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / x.len() as f64
+}
+
+/// Settings of the stepwise order search of [`auto_arima`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoArima {
+    period: usize,
+    max_p: usize,
+    max_q: usize,
+    max_seasonal_p: usize,
+    max_seasonal_q: usize,
+    max_d: usize,
+    max_seasonal_d: usize,
+    criterion: InformationCriterion,
+    max_models: usize,
+}
+
+impl Default for AutoArima {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AutoArima {
+    /// Non-seasonal search with p, q up to 5, d up to 2, by AIC, over at most 94 models.
+    pub fn new() -> Self {
+        Self {
+            period: 1,
+            max_p: 5,
+            max_q: 5,
+            max_seasonal_p: 2,
+            max_seasonal_q: 2,
+            max_d: 2,
+            max_seasonal_d: 1,
+            criterion: InformationCriterion::Aic,
+            max_models: 94,
+        }
+    }
+
+    /// Searches seasonal models too, with P, Q up to 2 and D up to 1.
+    ///
+    /// # Panics
+    /// Panics if `period` is zero.
+    pub fn with_period(self, period: usize) -> Self {
+        assert!(period > 0, "period must be positive");
+        Self { period, ..self }
+    }
+
+    pub fn with_max_order(self, p: usize, q: usize) -> Self {
+        Self { max_p: p, max_q: q, ..self }
+    }
+
+    pub fn with_max_seasonal_order(self, p: usize, q: usize) -> Self {
+        Self { max_seasonal_p: p, max_seasonal_q: q, ..self }
+    }
+
+    pub fn with_max_differences(self, d: usize, seasonal_d: usize) -> Self {
+        Self { max_d: d, max_seasonal_d: seasonal_d, ..self }
+    }
+
+    pub fn with_criterion(self, criterion: InformationCriterion) -> Self {
+        Self { criterion, ..self }
+    }
+
+    pub fn with_max_models(self, max_models: usize) -> Self {
+        Self { max_models, ..self }
+    }
+
+    /// Seasonal differences: one if the seasonal strength of an STL decomposition
+    /// exceeds 0.64.
+    fn seasonal_differences(&self, series: &[f64]) -> usize {
+        if self.period < 2 || self.max_seasonal_d == 0 || series.len() < 2 * self.period + 1 {
+            return 0;
+        }
+        let parts = Stl::new(self.period).decompose(&TimeSeries(series.to_vec()));
+        let detrended: Vec<f64> = parts.seasonal.as_slice().iter().zip(parts.remainder.as_slice()).map(|(s, r)| s + r).collect();
+        let strength = 1. - variance(parts.remainder.as_slice()) / variance(&detrended);
+        (strength > SEASONAL_STRENGTH_THRESHOLD) as usize
+    }
+
+    /// Regular differences: taken while the KPSS test rejects stationarity at 5%.
+    fn differences(&self, mut series: Vec<f64>) -> usize {
+        let mut d = 0;
+        while d < self.max_d && series.len() > 2 && kpss(&series) > KPSS_CRITICAL {
+            series = difference(&series, 1);
+            d += 1;
+        }
+        d
+    }
+
+    fn neighbors(&self, o: ArimaOrder) -> Vec<ArimaOrder> {
+        let step = |x: usize, by: isize, max: usize| x.checked_add_signed(by).filter(|&v| v <= max);
+        let mut out = Vec::new();
+        for (dp, dq) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, 1)] {
+            if let (Some(p), Some(q)) = (step(o.p, dp, self.max_p), step(o.q, dq, self.max_q)) {
+                out.push(ArimaOrder { p, q, ..o });
+            }
+            if o.is_seasonal() {
+                let p = step(o.seasonal_p, dp, self.max_seasonal_p);
+                let q = step(o.seasonal_q, dq, self.max_seasonal_q);
+                if let (Some(seasonal_p), Some(seasonal_q)) = (p, q) {
+                    out.push(ArimaOrder { seasonal_p, seasonal_q, ..o });
+                }
+            }
+        }
+        out
+    }
+
+    /// Chooses the differencing, then searches the ARMA orders stepwise from the usual
+    /// four starting models, moving to any neighboring order (one AR or MA order up or
+    /// down, or both together) that lowers the criterion.
+    ///
+    /// Fails if the series is too short for any model.
+    pub fn fit(&self, series: &TimeSeries<f64>) -> Result<Arima, Error> {
+        self.fit_monitored(series, &mut Monitor::new())
+    }
+
+    /// [`fit`](Self::fit), reporting the models fitted against the `max_models` bound to
+    /// `monitor`. Fails with [`Error::Cancelled`] if cancelled, including mid-fit.
+    pub fn fit_monitored(&self, series: &TimeSeries<f64>, monitor: &mut Monitor) -> Result<Arima, Error> {
+        trace_span!("auto_arima", n = series.len(), period = self.period);
+        if series.as_slice().iter().any(|x| !x.is_finite()) {
+            return Err(Error::InvalidInput("ARIMA needs finite values".to_string()));
+        }
+        let seasonal_d = self.seasonal_differences(series.as_slice());
+        let mut deseasoned = series.as_slice().to_vec();
+        for _ in 0..seasonal_d {
+            deseasoned = difference(&deseasoned, self.period);
+        }
+        let d = self.differences(deseasoned);
+        let base = ArimaOrder::new(0, d, 0);
+        let base = if self.period > 1 { base.with_seasonal(0, seasonal_d, 0, self.period) } else { base };
+        let (sp, sq) = if self.period > 1 { (self.max_seasonal_p.min(1), self.max_seasonal_q.min(1)) } else { (0, 0) };
+        let starts =
+            [(2, 2, sp, sq), (0, 0, 0, 0), (1, 0, sp, 0), (0, 1, 0, sq)].map(|(p, q, seasonal_p, seasonal_q)| {
+                ArimaOrder { p: p.min(self.max_p), q: q.min(self.max_q), seasonal_p, seasonal_q, ..base }
+            });
+
+        let mut visited = HashSet::new();
+        let mut best: Option<(f64, Arima)> = None;
+        let mut last_error = None;
+        let mut consider = |order: ArimaOrder, best: &mut Option<(f64, Arima)>| -> bool {
+            if monitor.is_cancelled() || visited.len() >= self.max_models || !visited.insert(order) {
+                return false;
+            }
+            let mut model = Arima::new(order);
+            let fitted = model.fit_monitored(series, &mut monitor.cancellation_only());
+            monitor.report(visited.len(), self.max_models);
+            if let Err(e) = fitted {
+                last_error = Some(e);
+                return false;
+            }
+            let score = model.criterion(self.criterion);
+            let better = score.is_finite() && best.as_ref().is_none_or(|(s, _)| score < *s);
+            if better {
+                *best = Some((score, model));
+            }
+            better
+        };
+        for order in starts {
+            consider(order, &mut best);
+        }
+        while let Some(current) = best.as_ref().map(|(_, m)| m.order()) {
+            if !self.neighbors(current).into_iter().any(|order| consider(order, &mut best)) {
+                break;
+            }
+        }
+        monitor.check()?;
+        monitor.finish();
+        match best {
+            Some((_, model)) => Ok(model),
+            None => Err(last_error.unwrap_or(Error::InsufficientData { required: 3, found: series.len() })),
+        }
+    }
+}
+
+/// Fits the ARIMA model chosen by [`AutoArima`]'s default non-seasonal search; use
+/// [`AutoArima::with_period`] for seasonal data.
+pub fn auto_arima(series: &TimeSeries<f64>) -> Result<Arima, Error> {
+    AutoArima::new().fit(series)
+}
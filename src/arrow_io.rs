@@ -0,0 +1,194 @@
+//! Arrow interchange for frames: conversion to and from record batches, and Arrow IPC
+//! streams for moving large frames between processes in row batches.
+//!
+//! A frame maps to a batch with an `Int64` `timestamp` column holding the index followed
+//! by one `Float64` column per frame column, built over the frame's own buffers by
+//! [`TimeSeriesFrame::into_record_batch`]. The [`flight`](crate::flight) module serves
+//! such batches over Arrow Flight.
+//! With the `parquet` feature, Parquet files with the same layout are read and written
+//! too.
+
+use crate::{Error, TimeSeries, TimeSeriesFrame, TimestampedSeries};
+use arrow_array::{Array, ArrayRef, Float64Array, Int64Array, RecordBatch};
+use arrow_ipc::reader::StreamReader;
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+#[cfg(feature = "parquet")]
+use parquet::arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ArrowWriter};
+#[cfg(feature = "parquet")]
+use parquet::errors::ParquetError;
+#[cfg(feature = "parquet")]
+use std::fs::File;
+use std::io::{Read, Write};
+#[cfg(feature = "parquet")]
+use std::path::Path;
+use std::sync::Arc;
+
+/// Name of the index column.
+pub const TIMESTAMP_COLUMN: &str = "timestamp";
+
+fn arrow_error(e: ArrowError) -> Error {
+    Error::InvalidInput(e.to_string())
+}
+
+impl TimeSeriesFrame {
+    /// The frame as a record batch over its own buffers: the index and columns move
+    /// into Arrow arrays without being copied.
+    pub fn into_record_batch(self) -> Result<RecordBatch, Error> {
+        let (index, columns) = self.into_parts();
+        let mut fields = vec![Field::new(TIMESTAMP_COLUMN, DataType::Int64, false)];
+        let mut arrays: Vec<ArrayRef> = vec![Arc::new(Int64Array::from(index))];
+        for (name, column) in columns {
+            fields.push(Field::new(name, DataType::Float64, true));
+            arrays.push(Arc::new(Float64Array::from(column.into_vec())));
+        }
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays).map_err(arrow_error)
+    }
+
+    /// A copy of the frame as a record batch; see [`into_record_batch`](Self::into_record_batch).
+    pub fn to_record_batch(&self) -> Result<RecordBatch, Error> {
+        self.clone().into_record_batch()
+    }
+
+    /// Reads a batch laid out as [`to_record_batch`](Self::to_record_batch) writes it.
+    /// Without a `timestamp` column the rows are indexed by position. Nulls become NaN.
+    pub fn from_record_batch(batch: &RecordBatch) -> Result<Self, Error> {
+        let schema = batch.schema();
+        let index = match schema.index_of(TIMESTAMP_COLUMN) {
+            Ok(i) => batch
+                .column(i)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .ok_or_else(|| Error::InvalidInput("`timestamp` must be Int64".to_string()))?
+                .values()
+                .to_vec(),
+            Err(_) => (0..batch.num_rows() as i64).collect(),
+        };
+        let mut frame = Self::new(index);
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            if field.name() == TIMESTAMP_COLUMN {
+                continue;
+            }
+            let values = column
+                .as_any()
+                .downcast_ref::<Float64Array>()
+                .ok_or_else(|| Error::InvalidInput(format!("column `{}` must be Float64", field.name())))?;
+            let series: TimeSeries<f64> =
+                (0..values.len()).map(|i| if values.is_null(i) { f64::NAN } else { values.value(i) }).collect();
+            frame.add_column(field.name().as_str(), series)?;
+        }
+        Ok(frame)
+    }
+
+    /// Writes the frame as an Arrow IPC stream of batches of at most `batch_rows` rows.
+    pub fn write_ipc<W: Write>(&self, writer: W, batch_rows: usize) -> Result<(), Error> {
+        assert!(batch_rows > 0, "batch size must be positive");
+        let batch = self.to_record_batch()?;
+        let mut stream = StreamWriter::try_new(writer, &batch.schema()).map_err(arrow_error)?;
+        for slice in row_slices(&batch, batch_rows) {
+            stream.write(&slice).map_err(arrow_error)?;
+        }
+        stream.finish().map_err(arrow_error)
+    }
+
+    /// Reads an Arrow IPC stream, concatenating its batches into one frame.
+    pub fn read_ipc<R: Read>(reader: R) -> Result<Self, Error> {
+        let stream = StreamReader::try_new(reader, None).map_err(arrow_error)?;
+        Self::from_batches(stream.schema(), stream)
+    }
+
+    /// Concatenates batches of `schema` into one frame.
+    pub(crate) fn from_batches<I>(schema: SchemaRef, batches: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = Result<RecordBatch, ArrowError>>,
+    {
+        let head = Self::from_record_batch(&RecordBatch::new_empty(schema))?;
+        let mut index = Vec::new();
+        let mut columns = vec![Vec::new(); head.width()];
+        for batch in batches {
+            let part = Self::from_record_batch(&batch.map_err(arrow_error)?)?;
+            index.extend_from_slice(part.index());
+            for (column, (_, values)) in columns.iter_mut().zip(part.columns()) {
+                column.extend_from_slice(values.as_slice());
+            }
+        }
+        let mut frame = Self::new(index);
+        for (name, column) in head.column_names().iter().zip(columns) {
+            frame.add_column(name.as_str(), TimeSeries(column))?;
+        }
+        Ok(frame)
+    }
+}
+
+/// Consecutive slices of at most `rows` rows of `batch`, sharing its buffers.
+pub(crate) fn row_slices(batch: &RecordBatch, rows: usize) -> impl Iterator<Item = RecordBatch> + '_ {
+    (0..batch.num_rows()).step_by(rows).map(move |start| batch.slice(start, rows.min(batch.num_rows() - start)))
+}
+
+impl TimestampedSeries<f64> {
+    /// A batch with `timestamp` and `value` columns over the series' own buffers.
+    pub fn into_record_batch(self) -> Result<RecordBatch, Error> {
+        let (timestamps, values) = self.into_parts();
+        TimeSeriesFrame::new(timestamps).with_column("value", values)?.into_record_batch()
+    }
+
+    /// A copy of the series as a record batch; see [`into_record_batch`](Self::into_record_batch).
+    pub fn to_record_batch(&self) -> Result<RecordBatch, Error> {
+        self.clone().into_record_batch()
+    }
+}
+
+#[cfg(feature = "parquet")]
+fn parquet_error(e: ParquetError) -> Error {
+    Error::InvalidInput(e.to_string())
+}
+
+#[cfg(feature = "parquet")]
+impl TimeSeriesFrame {
+    /// Reads a Parquet file laid out as [`from_record_batch`](Self::from_record_batch)
+    /// expects, concatenating its row groups into one frame.
+    pub fn read_parquet(file: File) -> Result<Self, Error> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).map_err(parquet_error)?;
+        let schema = builder.schema().clone();
+        Self::from_batches(schema, builder.build().map_err(parquet_error)?)
+    }
+
+    pub fn load_parquet<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| Error::InvalidInput(format!("{}: {}", path.display(), e)))?;
+        Self::read_parquet(file)
+    }
+
+    /// Writes the frame as a Parquet file in row groups of at most `batch_rows` rows.
+    pub fn write_parquet<W: Write + Send>(&self, writer: W, batch_rows: usize) -> Result<(), Error> {
+        assert!(batch_rows > 0, "batch size must be positive");
+        let batch = self.to_record_batch()?;
+        let mut file = ArrowWriter::try_new(writer, batch.schema(), None).map_err(parquet_error)?;
+        for slice in row_slices(&batch, batch_rows) {
+            file.write(&slice).map_err(parquet_error)?;
+            file.flush().map_err(parquet_error)?;
+        }
+        file.close().map(|_| ()).map_err(parquet_error)
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl TimestampedSeries<f64> {
+    /// Reads a Parquet file with a `timestamp` column and either a `value` column or a
+    /// single other column.
+    ///
+    /// Fails if the columns are missing, ambiguous or of other types, or if the
+    /// timestamps are not sorted.
+    pub fn load_parquet<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let frame = TimeSeriesFrame::load_parquet(path)?;
+        let values = match frame.column("value") {
+            Some(values) => values.clone(),
+            None if frame.width() == 1 => frame.columns().map(|(_, values)| values.clone()).next().expect("one column"),
+            None => return Err(Error::InvalidInput("expected a `value` column or a single value column".to_string())),
+        };
+        if !frame.index().windows(2).all(|w| w[0] <= w[1]) {
+            return Err(Error::InvalidInput("timestamps are not sorted".to_string()));
+        }
+        Ok(TimestampedSeries::from_parts(frame.index().to_vec(), values))
+    }
+}
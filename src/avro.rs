@@ -0,0 +1,284 @@
+//! Avro encoding of series and frames for Kafka pipelines that enforce a schema
+//! registry.
+//!
+//! A row is a record with a `timestamp` long followed by one double per column (a
+//! single `value` for a series). The timestamp carries the `timestamp-millis`,
+//! `timestamp-micros` or `timestamp-nanos` logical type when the unit is known; Avro has
+//! no logical type for seconds, so those stay a plain long. Records are written one per
+//! message in the Confluent wire format, Avro single-object encoding, or bare.
+
+use crate::lineage::json_string;
+use crate::{Error, TimeSeries, TimeSeriesFrame, TimeUnit, TimestampedSeries};
+
+/// Name of the index field.
+pub const TIMESTAMP_FIELD: &str = "timestamp";
+
+/// How each encoded record is prefixed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// The bare Avro datum; the reader must know the schema.
+    Raw,
+    /// `C3 01` and the schema's little-endian Rabin [`fingerprint`](AvroSchema::fingerprint).
+    SingleObject,
+    /// A zero magic byte and the big-endian id the schema registry assigned to the schema.
+    Confluent(u32),
+}
+
+/// A record schema of a timestamp and named double fields. Equal schemas always render
+/// to the same JSON, so registering them is idempotent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AvroSchema {
+    name: String,
+    namespace: String,
+    fields: Vec<String>,
+    unit: Option<TimeUnit>,
+}
+
+fn is_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl AvroSchema {
+    /// A record `name` in the `time_series` namespace with one double per field.
+    ///
+    /// Fails if a name is not a valid Avro name, or a field is named `timestamp` or
+    /// repeated.
+    pub fn new<N: Into<String>, F: Into<String>>(name: N, fields: Vec<F>) -> Result<Self, Error> {
+        let name = name.into();
+        let fields: Vec<String> = fields.into_iter().map(Into::into).collect();
+        if !is_name(&name) {
+            return Err(Error::InvalidInput(format!("`{}` is not a valid Avro name", name)));
+        }
+        for (i, field) in fields.iter().enumerate() {
+            if !is_name(field) || field == TIMESTAMP_FIELD || fields[..i].contains(field) {
+                return Err(Error::InvalidInput(format!("`{}` is not a valid field name", field)));
+            }
+        }
+        Ok(Self { name, namespace: "time_series".to_string(), fields, unit: None })
+    }
+
+    pub fn with_namespace<S: Into<String>>(self, namespace: S) -> Result<Self, Error> {
+        let namespace = namespace.into();
+        if !namespace.split('.').all(is_name) {
+            return Err(Error::InvalidInput(format!("`{}` is not a valid Avro namespace", namespace)));
+        }
+        Ok(Self { namespace, ..self })
+    }
+
+    /// Tags the timestamp with the logical type of `unit`.
+    pub fn with_unit(self, unit: TimeUnit) -> Self {
+        Self { unit: Some(unit), ..self }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    pub fn unit(&self) -> Option<TimeUnit> {
+        self.unit
+    }
+
+    fn full_name(&self) -> String {
+        format!("{}.{}", self.namespace, self.name)
+    }
+
+    fn render(&self, timestamp_type: &str) -> String {
+        let mut fields = vec![format!("{{\"name\":{},\"type\":{}}}", json_string(TIMESTAMP_FIELD), timestamp_type)];
+        fields.extend(self.fields.iter().map(|f| format!("{{\"name\":{},\"type\":\"double\"}}", json_string(f))));
+        format!("{{\"name\":{},\"type\":\"record\",\"fields\":[{}]}}", json_string(&self.full_name()), fields.join(","))
+    }
+
+    /// The schema as JSON, to register with a schema registry.
+    pub fn to_json(&self) -> String {
+        let logical = match self.unit {
+            Some(TimeUnit::Millis) => "timestamp-millis",
+            Some(TimeUnit::Micros) => "timestamp-micros",
+            Some(TimeUnit::Nanos) => "timestamp-nanos",
+            Some(TimeUnit::Seconds) | None => return self.render("\"long\""),
+        };
+        self.render(&format!("{{\"type\":\"long\",\"logicalType\":\"{}\"}}", logical))
+    }
+
+    /// The Parsing Canonical Form, which drops logical types.
+    pub fn canonical_form(&self) -> String {
+        self.render("\"long\"")
+    }
+
+    /// The CRC-64-AVRO fingerprint of the [canonical form](Self::canonical_form).
+    pub fn fingerprint(&self) -> u64 {
+        const EMPTY: u64 = 0xc15d_213a_a4d7_a795;
+        let mut table = [0u64; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let mut fp = i as u64;
+            for _ in 0..8 {
+                fp = (fp >> 1) ^ (EMPTY & (fp & 1).wrapping_neg());
+            }
+            *entry = fp;
+        }
+        self.canonical_form().bytes().fold(EMPTY, |fp, b| (fp >> 8) ^ table[((fp ^ b as u64) & 0xff) as usize])
+    }
+
+    /// Encodes one record.
+    ///
+    /// # Panics
+    /// Panics if `values` does not hold one value per field.
+    pub fn encode(&self, timestamp: i64, values: &[f64], framing: Framing) -> Vec<u8> {
+        assert_eq!(values.len(), self.fields.len(), "one value per field");
+        let mut out = Vec::with_capacity(10 + 10 + 8 * values.len());
+        match framing {
+            Framing::Raw => {}
+            Framing::SingleObject => {
+                out.extend_from_slice(&[0xc3, 0x01]);
+                out.extend_from_slice(&self.fingerprint().to_le_bytes());
+            }
+            Framing::Confluent(id) => {
+                out.push(0);
+                out.extend_from_slice(&id.to_be_bytes());
+            }
+        }
+        let mut zigzag = ((timestamp << 1) ^ (timestamp >> 63)) as u64;
+        while zigzag >= 0x80 {
+            out.push(zigzag as u8 | 0x80);
+            zigzag >>= 7;
+        }
+        out.push(zigzag as u8);
+        for v in values {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out
+    }
+
+    /// Decodes one record written with this schema and `framing`.
+    pub fn decode(&self, bytes: &[u8], framing: Framing) -> Result<(i64, Vec<f64>), Error> {
+        let invalid = |what: &str| Error::InvalidInput(format!("avro: {}", what));
+        let body = match framing {
+            Framing::Raw => bytes,
+            Framing::SingleObject => match bytes {
+                [0xc3, 0x01, rest @ ..] if rest.len() >= 8 => {
+                    if rest[..8] != self.fingerprint().to_le_bytes() {
+                        return Err(invalid("record was written with another schema"));
+                    }
+                    &rest[8..]
+                }
+                _ => return Err(invalid("missing single-object header")),
+            },
+            Framing::Confluent(id) => match bytes {
+                [0, rest @ ..] if rest.len() >= 4 => {
+                    if rest[..4] != id.to_be_bytes() {
+                        return Err(invalid("record was written with another schema id"));
+                    }
+                    &rest[4..]
+                }
+                _ => return Err(invalid("missing Confluent header")),
+            },
+        };
+        let mut zigzag = 0u64;
+        let mut read = 0;
+        loop {
+            let byte = *body.get(read).ok_or_else(|| invalid("truncated timestamp"))?;
+            if read == 9 && byte > 1 {
+                return Err(invalid("timestamp overflows a long"));
+            }
+            zigzag |= ((byte & 0x7f) as u64) << (7 * read);
+            read += 1;
+            if byte < 0x80 {
+                break;
+            }
+        }
+        let timestamp = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+        let rest = &body[read..];
+        if rest.len() != 8 * self.fields.len() {
+            return Err(invalid("record does not match the schema"));
+        }
+        let values = rest.chunks(8).map(|c| f64::from_le_bytes(c.try_into().expect("chunks of 8"))).collect();
+        Ok((timestamp, values))
+    }
+}
+
+impl TimestampedSeries<f64> {
+    /// A `Point` schema with a single `value` field, in the series' unit.
+    pub fn avro_schema(&self) -> AvroSchema {
+        let schema = AvroSchema::new("Point", vec!["value"]).expect("valid names");
+        match self.unit() {
+            Some(unit) => schema.with_unit(unit),
+            None => schema,
+        }
+    }
+
+    /// One encoded record per point, in order.
+    ///
+    /// # Panics
+    /// Panics if `schema` does not have exactly one field.
+    pub fn to_avro(&self, schema: &AvroSchema, framing: Framing) -> Vec<Vec<u8>> {
+        self.iter().map(|(t, &v)| schema.encode(t, &[v], framing)).collect()
+    }
+
+    /// Reads records written by [`to_avro`](Self::to_avro), tagging the series with the
+    /// schema's unit. Fails on records out of timestamp order.
+    pub fn from_avro<I>(schema: &AvroSchema, records: I, framing: Framing) -> Result<Self, Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        if schema.fields().len() != 1 {
+            return Err(Error::InvalidInput("a series schema has one field".to_string()));
+        }
+        let mut series = TimestampedSeries::default();
+        if let Some(unit) = schema.unit() {
+            series = series.with_unit(unit);
+        }
+        for record in records {
+            let (t, values) = schema.decode(record.as_ref(), framing)?;
+            if series.timestamps().last().is_some_and(|&last| t < last) {
+                return Err(Error::InvalidInput("avro: records are not in timestamp order".to_string()));
+            }
+            series.push(t, values[0]);
+        }
+        Ok(series)
+    }
+}
+
+impl TimeSeriesFrame {
+    /// A schema named `name` with one field per column, in column order.
+    pub fn avro_schema(&self, name: &str) -> Result<AvroSchema, Error> {
+        AvroSchema::new(name, self.column_names().to_vec())
+    }
+
+    /// One encoded record per row. Fails unless the schema's fields are the frame's
+    /// columns in order.
+    pub fn to_avro(&self, schema: &AvroSchema, framing: Framing) -> Result<Vec<Vec<u8>>, Error> {
+        if schema.fields() != self.column_names() {
+            return Err(Error::InvalidInput("schema fields do not match the frame's columns".to_string()));
+        }
+        Ok((0..self.len()).map(|i| schema.encode(self.index()[i], &self.row(i), framing)).collect())
+    }
+
+    /// Reads records written by [`to_avro`](Self::to_avro) into a frame with the
+    /// schema's fields as columns.
+    pub fn from_avro<I>(schema: &AvroSchema, records: I, framing: Framing) -> Result<Self, Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let mut index = Vec::new();
+        let mut columns = vec![Vec::new(); schema.fields().len()];
+        for record in records {
+            let (t, values) = schema.decode(record.as_ref(), framing)?;
+            index.push(t);
+            for (column, v) in columns.iter_mut().zip(values) {
+                column.push(v);
+            }
+        }
+        let mut frame = Self::new(index);
+        for (name, column) in schema.fields().iter().zip(columns) {
+            frame.add_column(name.as_str(), TimeSeries(column))?;
+        }
+        Ok(frame)
+    }
+}
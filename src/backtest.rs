@@ -0,0 +1,61 @@
+//! Vectorized backtesting of a position series against a price series.
+
+use crate::TimeSeries;
+
+/// Trading frictions, each expressed as a fraction of the traded notional.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CostModel {
+    pub commission: f64,
+    pub slippage: f64,
+}
+
+impl CostModel {
+    fn rate(&self) -> f64 {
+        self.commission + self.slippage
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BacktestResult {
+    /// Net return of each period, after costs.
+    pub returns: TimeSeries<f64>,
+    /// Equity after each period, starting from the initial capital.
+    pub equity: TimeSeries<f64>,
+    /// Absolute change in position at each point.
+    pub turnover: TimeSeries<f64>,
+}
+
+/// Computes the equity curve of holding `positions[t]` (a fraction of equity, negative for
+/// short) from the close of bar `t` to the close of bar `t + 1`.
+///
+/// Costs are charged on the bar where the position changes. The returned series have the
+/// common length of `prices` and `positions`; the result's `equity` and `returns` feed
+/// directly into [`Drawdown`](crate::Drawdown) and [`Performance`](crate::Performance).
+pub fn equity_curve(
+    prices: &TimeSeries<f64>,
+    positions: &TimeSeries<f64>,
+    costs: &CostModel,
+    initial_capital: f64,
+) -> BacktestResult {
+    let n = prices.len().min(positions.len());
+    trace_span!("backtest", n);
+    let mut returns = Vec::with_capacity(n);
+    let mut equity = Vec::with_capacity(n);
+    let mut turnover = Vec::with_capacity(n);
+    let mut capital = initial_capital;
+    for t in 0..n {
+        let previous = if t == 0 { 0. } else { positions[t - 1] };
+        let price_return = if t == 0 { 0. } else { prices[t] / prices[t - 1] - 1. };
+        let traded = (positions[t] - previous).abs();
+        let r = previous * price_return - traded * costs.rate();
+        capital *= 1. + r;
+        returns.push(r);
+        equity.push(capital);
+        turnover.push(traded);
+    }
+    BacktestResult {
+        returns: TimeSeries(returns),
+        equity: TimeSeries(equity),
+        turnover: TimeSeries(turnover),
+    }
+}
@@ -0,0 +1,50 @@
+//! Triple-barrier labelling of events on a price series (López de Prado).
+
+use crate::TimeSeries;
+
+/// Outcome of one event.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BarrierLabel {
+    /// Index of the event.
+    pub start: usize,
+    /// Index at which the first barrier was touched; usable as the label end for
+    /// [`PurgedKFold::split`](crate::PurgedKFold::split).
+    pub end: usize,
+    /// +1 for the profit-taking barrier, -1 for the stop-loss barrier, 0 when the holding
+    /// period ran out first.
+    pub label: i8,
+    /// Return from the event to `end`.
+    pub ret: f64,
+}
+
+/// Labels each event index by which barrier the price hits first: a return of
+/// `profit_take` above the event price, `stop_loss` below it, or `max_horizon` points
+/// later (cut short at the end of the series). Pass `f64::INFINITY` to disable a
+/// horizontal barrier. Events outside the series are skipped.
+pub fn triple_barrier_labels(
+    prices: &TimeSeries<f64>,
+    events: &[usize],
+    profit_take: f64,
+    stop_loss: f64,
+    max_horizon: usize,
+) -> Vec<BarrierLabel> {
+    let n = prices.len();
+    events
+        .iter()
+        .filter(|&&start| start < n)
+        .map(|&start| {
+            let entry = prices[start];
+            let last = (start + max_horizon).min(n - 1);
+            for t in start + 1..=last {
+                let ret = prices[t] / entry - 1.;
+                if ret >= profit_take {
+                    return BarrierLabel { start, end: t, label: 1, ret };
+                }
+                if ret <= -stop_loss {
+                    return BarrierLabel { start, end: t, label: -1, ret };
+                }
+            }
+            BarrierLabel { start, end: last, label: 0, ret: prices[last] / entry - 1. }
+        })
+        .collect()
+}
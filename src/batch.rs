@@ -0,0 +1,118 @@
+//! Fitting and forecasting every series of a [`SeriesSet`], such as one per product,
+//! in one call.
+//!
+//! Each series gets its own model from a factory, so one bad series only loses its own
+//! forecast: fit errors and panics alike are recorded against its name and the rest go
+//! on. With the `parallel` feature the series are spread over rayon's thread pool.
+
+use crate::{Error, Forecaster, SeriesSet, TimeSeries};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// The outcome of [`forecast_many`], keyed by series name.
+#[derive(Clone, Debug, Default)]
+pub struct BatchForecast {
+    pub forecasts: BTreeMap<String, TimeSeries<f64>>,
+    pub failures: BTreeMap<String, Error>,
+}
+
+/// A short name for the variant of `error`, to group failures by.
+fn kind(error: &Error) -> &'static str {
+    match error {
+        Error::MissingSeries(_) => "missing series",
+        Error::LengthMismatch { .. } => "length mismatch",
+        Error::InsufficientData { .. } => "insufficient data",
+        Error::Singular => "singular matrix",
+        Error::InvalidInput(_) => "invalid input",
+        Error::Cancelled => "cancelled",
+    }
+}
+
+impl BatchForecast {
+    pub fn len(&self) -> usize {
+        self.forecasts.len() + self.failures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Share of the series that failed; NaN for an empty batch.
+    pub fn failure_rate(&self) -> f64 {
+        self.failures.len() as f64 / self.len() as f64
+    }
+
+    /// Names of the failed series grouped by kind of error, such as `"insufficient
+    /// data"`, largest group first.
+    pub fn failures_by_kind(&self) -> Vec<(&'static str, Vec<&str>)> {
+        let mut groups: BTreeMap<&'static str, Vec<&str>> = BTreeMap::new();
+        for (name, error) in &self.failures {
+            groups.entry(kind(error)).or_default().push(name);
+        }
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        groups.sort_by_key(|(_, names)| std::cmp::Reverse(names.len()));
+        groups
+    }
+}
+
+impl fmt::Display for BatchForecast {
+    /// One line such as `998 of 1000 series forecast; 2 failed (insufficient data: 2)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {} series forecast", self.forecasts.len(), self.len())?;
+        if !self.failures.is_empty() {
+            let groups: Vec<String> =
+                self.failures_by_kind().iter().map(|(kind, names)| format!("{}: {}", kind, names.len())).collect();
+            write!(f, "; {} failed ({})", self.failures.len(), groups.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Fits a fresh model from `factory` on the values of each series and forecasts
+/// `horizon` points. A panic in the model counts as an [`Error::InvalidInput`] failure
+/// of that series, though the panic message is still printed by the panic hook.
+pub fn forecast_many<F, M>(set: &SeriesSet<f64>, factory: F, horizon: usize) -> BatchForecast
+where
+    F: Fn() -> M + Sync,
+    M: Forecaster,
+{
+    trace_span!("forecast_many", series = set.len(), horizon);
+    let one = |series: &TimeSeries<f64>| -> Result<TimeSeries<f64>, Error> {
+        catch_unwind(AssertUnwindSafe(|| {
+            let mut model = factory();
+            model.fit(series)?;
+            Ok(model.predict(horizon))
+        }))
+        .unwrap_or_else(|panic| {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Err(Error::InvalidInput(format!("model panicked: {}", message)))
+        })
+    };
+    #[cfg(feature = "parallel")]
+    let results: Vec<(&String, Result<TimeSeries<f64>, Error>)> = {
+        use rayon::prelude::*;
+        let series: Vec<_> = set.iter().collect();
+        series.into_par_iter().map(|(name, s)| (name, one(s.values()))).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let results: Vec<(&String, Result<TimeSeries<f64>, Error>)> =
+        set.iter().map(|(name, s)| (name, one(s.values()))).collect();
+    let mut out = BatchForecast::default();
+    for (name, result) in results {
+        match result {
+            Ok(forecast) => {
+                out.forecasts.insert(name.clone(), forecast);
+            }
+            Err(error) => {
+                trace_event!(series = name.as_str(), error = %error, "forecast failed");
+                out.failures.insert(name.clone(), error);
+            }
+        }
+    }
+    out
+}
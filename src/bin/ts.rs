@@ -0,0 +1,226 @@
+//! `ts`: quick command-line analysis of a `timestamp,value` CSV series, or of a Parquet
+//! file with `timestamp` and `value` columns when built with the `parquet` feature.
+//!
+//! Operations run in the order given, then the result is written as CSV, summarized
+//! with `--stats` or drawn with `--plot` or `--sparkline`:
+//!
+//! ```text
+//! ts prices.csv --resample 3600:last --diff 1 --rolling-mean 24 --plot
+//! ```
+
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+use time_series_utils::{
+    DiffOp, EwmaOp, RollingMeanOp, SpectralResidual, Statistics, StreamOp, TimeSeries, TimestampedSeries, ZScoreOp,
+};
+
+const USAGE: &str = "usage: ts [INPUT|-] [OPTIONS]
+
+Reads `timestamp,value` CSV from INPUT or stdin. An INPUT ending in `.parquet` is read
+as Parquet with `timestamp` and `value` columns (needs the `parquet` feature).
+
+Operations, applied in order:
+  --resample STEP[:AGG]   bucket by STEP time units; AGG = mean|sum|min|max|first|last|count
+  --diff K                difference with the point K steps earlier
+  --rolling-mean N        trailing mean over N points
+  --ewma ALPHA            exponentially weighted moving average
+  --zscore N              z-score against the trailing N points
+  --anomalies             replace values by spectral-residual anomaly flags (1 or 0)
+
+Output (default: CSV to stdout):
+  -o, --output PATH       write CSV to PATH
+  --stats                 print summary statistics instead
+  --plot                  draw a text chart instead
+  --sparkline             print a one-line chart instead
+  -h, --help              show this help";
+
+enum Output {
+    Csv(Option<String>),
+    Stats,
+    Plot,
+    Sparkline,
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value.parse().map_err(|_| format!("invalid value `{}` for {}", value, flag))
+}
+
+/// Runs a streaming operator over the values; outputs are aligned to the latest
+/// timestamps since operators only drop warm-up points from the front.
+fn apply(series: &TimestampedSeries<f64>, mut op: impl StreamOp) -> TimestampedSeries<f64> {
+    let values = op.process(series.values());
+    let skip = series.len() - values.len();
+    TimestampedSeries::from_parts(series.timestamps()[skip..].to_vec(), values)
+}
+
+fn resample(series: &TimestampedSeries<f64>, spec: &str) -> Result<TimestampedSeries<f64>, String> {
+    let (step, agg) = spec.split_once(':').unwrap_or((spec, "mean"));
+    let step: i64 = step.parse().ok().filter(|&s| s > 0).ok_or("resample step must be a positive integer")?;
+    let agg: fn(&[f64]) -> f64 = match agg {
+        "mean" => |v| v.iter().sum::<f64>() / v.len() as f64,
+        "sum" => |v| v.iter().sum(),
+        "min" => |v| v.iter().copied().fold(f64::INFINITY, f64::min),
+        "max" => |v| v.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        "first" => |v| v[0],
+        "last" => |v| v[v.len() - 1],
+        "count" => |v| v.len() as f64,
+        other => return Err(format!("unknown aggregate `{}`", other)),
+    };
+    Ok(series.resample(step, agg))
+}
+
+fn stats(series: &TimestampedSeries<f64>) -> String {
+    let values = series.values();
+    format!(
+        "count  {}\nmean   {}\nstd    {}\nmin    {}\nmedian {}\nmax    {}\n",
+        values.len(),
+        values.mean(),
+        values.std(),
+        values.min(),
+        values.median(),
+        values.max()
+    )
+}
+
+/// A text chart of at most 72 columns by 16 rows; wider series are averaged per column.
+fn plot(series: &TimestampedSeries<f64>) -> String {
+    const WIDTH: usize = 72;
+    const HEIGHT: usize = 16;
+    let values = series.values().as_slice();
+    if values.is_empty() {
+        return "(empty series)\n".to_string();
+    }
+    let columns = values.len().min(WIDTH);
+    let points: Vec<f64> = (0..columns)
+        .map(|c| {
+            let bin = &values[c * values.len() / columns..(c + 1) * values.len() / columns];
+            bin.iter().sum::<f64>() / bin.len() as f64
+        })
+        .collect();
+    let finite = TimeSeries::from_vec(points.iter().copied().filter(|v| v.is_finite()).collect());
+    let (lo, hi) = (finite.min(), finite.max());
+    let span = if hi > lo { hi - lo } else { 1. };
+    let mut grid = vec![vec![' '; columns]; HEIGHT];
+    for (c, v) in points.iter().enumerate().filter(|(_, v)| v.is_finite()) {
+        let row = ((hi - v) / span * (HEIGHT - 1) as f64).round() as usize;
+        grid[row][c] = '*';
+    }
+    let mut out = String::new();
+    for (r, line) in grid.iter().enumerate() {
+        let label = match r {
+            0 => format!("{:>12.4}", hi),
+            r if r == HEIGHT - 1 => format!("{:>12.4}", lo),
+            _ => " ".repeat(12),
+        };
+        out.push_str(&format!("{} |{}\n", label, line.iter().collect::<String>()));
+    }
+    let first = series.timestamps()[0].to_string();
+    let last = series.timestamps()[series.len() - 1].to_string();
+    let gap = columns.saturating_sub(first.len() + last.len()).max(1);
+    let margin = " ".repeat(12);
+    out.push_str(&format!("{} +{}\n", margin, "-".repeat(columns)));
+    out.push_str(&format!("{}  {}{}{}\n", margin, first, " ".repeat(gap), last));
+    out
+}
+
+fn is_parquet(path: &str) -> bool {
+    std::path::Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("parquet"))
+}
+
+#[cfg(feature = "parquet")]
+fn load_parquet(path: &str) -> Result<TimestampedSeries<f64>, String> {
+    TimestampedSeries::<f64>::load_parquet(path).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "parquet"))]
+fn load_parquet(path: &str) -> Result<TimestampedSeries<f64>, String> {
+    Err(format!("{}: reading Parquet needs `ts` built with the `parquet` feature", path))
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let mut args = args.into_iter();
+    let mut input: Option<String> = None;
+    let mut output = Output::Csv(None);
+    let mut steps: Vec<(String, Option<String>)> = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            "-o" | "--output" => output = Output::Csv(Some(parse("--output", args.next())?)),
+            "--stats" => output = Output::Stats,
+            "--plot" => output = Output::Plot,
+            "--sparkline" => output = Output::Sparkline,
+            "--anomalies" => steps.push((arg, None)),
+            "--resample" | "--diff" | "--rolling-mean" | "--ewma" | "--zscore" => {
+                let value = args.next();
+                steps.push((arg, value));
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option `{}`", flag)),
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    let mut series = match input.as_deref() {
+        None | Some("-") => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text).map_err(|e| e.to_string())?;
+            TimestampedSeries::<f64>::read_csv(text.as_bytes()).map_err(|e| e.to_string())
+        }
+        Some(path) if is_parquet(path) => load_parquet(path),
+        Some(path) => TimestampedSeries::<f64>::load_csv(path).map_err(|e| e.to_string()),
+    }?;
+
+    for (flag, value) in steps {
+        series = match flag.as_str() {
+            "--resample" => resample(&series, &parse::<String>(&flag, value)?)?,
+            "--diff" => apply(&series, DiffOp::new(parse(&flag, value)?)),
+            "--rolling-mean" => {
+                let window: usize = parse(&flag, value)?;
+                if window == 0 {
+                    return Err("--rolling-mean needs a positive window".to_string());
+                }
+                apply(&series, RollingMeanOp::new(window))
+            }
+            "--ewma" => {
+                let alpha: f64 = parse(&flag, value)?;
+                if !(alpha > 0. && alpha <= 1.) {
+                    return Err("--ewma needs alpha in (0, 1]".to_string());
+                }
+                apply(&series, EwmaOp::new(alpha))
+            }
+            "--zscore" => {
+                let window: usize = parse(&flag, value)?;
+                if window < 2 {
+                    return Err("--zscore needs a window of at least 2".to_string());
+                }
+                apply(&series, ZScoreOp::new(window))
+            }
+            _ => {
+                let flags = SpectralResidual::new().detect(series.values()).flags;
+                TimestampedSeries::from_parts(series.timestamps().to_vec(), flags.map(|&f| if f { 1. } else { 0. }))
+            }
+        };
+    }
+
+    match output {
+        Output::Csv(None) => series.write_csv(io::stdout().lock()).map_err(|e| e.to_string()),
+        Output::Csv(Some(path)) => series.save_csv(path).map_err(|e| e.to_string()),
+        Output::Stats => io::stdout().write_all(stats(&series).as_bytes()).map_err(|e| e.to_string()),
+        Output::Plot => io::stdout().write_all(plot(&series).as_bytes()).map_err(|e| e.to_string()),
+        Output::Sparkline => writeln!(io::stdout(), "{}", series.sparkline_width(72)).map_err(|e| e.to_string()),
+    }
+}
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("ts: {} (see `ts --help`)", message);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Boolean series packed one bit per point, for signal masks over long series that are
+//! combined with AND/OR/NOT, counted and scanned for runs.
+
+use crate::TimeSeries;
+use std::ops::{BitAnd, BitOr, BitXor, Not, Range};
+
+const BITS: usize = u64::BITS as usize;
+
+/// A boolean series stored in 64-bit words. Bits past the end of the series are kept
+/// clear, so counts and comparisons never see them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BitmapSeries {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl BitmapSeries {
+    /// `len` points, all `false`.
+    pub fn new(len: usize) -> Self {
+        Self { len, words: vec![0; len.div_ceil(BITS)] }
+    }
+
+    /// `len` points, all `true`.
+    pub fn ones(len: usize) -> Self {
+        let mut bitmap = Self { len, words: vec![u64::MAX; len.div_ceil(BITS)] };
+        bitmap.clear_tail();
+        bitmap
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        (index < self.len).then(|| self.words[index / BITS] >> (index % BITS) & 1 == 1)
+    }
+
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < self.len, "index {} out of bounds for length {}", index, self.len);
+        let mask = 1 << (index % BITS);
+        if value {
+            self.words[index / BITS] |= mask;
+        } else {
+            self.words[index / BITS] &= !mask;
+        }
+    }
+
+    pub fn push(&mut self, value: bool) {
+        if self.words.len() * BITS == self.len {
+            self.words.push(0);
+        }
+        self.len += 1;
+        self.set(self.len - 1, value);
+    }
+
+    /// Number of `true` points.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    pub fn any(&self) -> bool {
+        self.words.iter().any(|&w| w != 0)
+    }
+
+    pub fn all(&self) -> bool {
+        self.count_ones() == self.len
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|i| self.words[i / BITS] >> (i % BITS) & 1 == 1)
+    }
+
+    /// Positions of the `true` points, skipping empty words.
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(w, &word)| {
+            let mut rest = word;
+            std::iter::from_fn(move || {
+                (rest != 0).then(|| {
+                    let bit = rest.trailing_zeros() as usize;
+                    rest &= rest - 1;
+                    w * BITS + bit
+                })
+            })
+        })
+    }
+
+    /// Maximal runs of consecutive `true` points, in order.
+    pub fn runs(&self) -> Vec<Range<usize>> {
+        let mut runs: Vec<Range<usize>> = Vec::new();
+        for i in self.indices() {
+            match runs.last_mut() {
+                Some(run) if run.end == i => run.end += 1,
+                _ => runs.push(i..i + 1),
+            }
+        }
+        runs
+    }
+
+    pub fn to_time_series(&self) -> TimeSeries<bool> {
+        TimeSeries(self.iter().collect())
+    }
+
+    fn clear_tail(&mut self) {
+        let used = self.len % BITS;
+        if let Some(last) = self.words.last_mut().filter(|_| used > 0) {
+            *last &= (1 << used) - 1;
+        }
+    }
+
+    /// Combines two bitmaps word by word over their common length.
+    fn zip_words<F: Fn(u64, u64) -> u64>(&self, other: &Self, f: F) -> Self {
+        let len = self.len.min(other.len);
+        let words = self.words.iter().zip(&other.words).take(len.div_ceil(BITS)).map(|(&a, &b)| f(a, b)).collect();
+        let mut bitmap = Self { len, words };
+        bitmap.clear_tail();
+        bitmap
+    }
+}
+
+impl FromIterator<bool> for BitmapSeries {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bitmap = Self::default();
+        for value in iter {
+            bitmap.push(value);
+        }
+        bitmap
+    }
+}
+
+impl From<&TimeSeries<bool>> for BitmapSeries {
+    fn from(series: &TimeSeries<bool>) -> Self {
+        series.as_slice().iter().copied().collect()
+    }
+}
+
+impl From<&BitmapSeries> for TimeSeries<bool> {
+    fn from(bitmap: &BitmapSeries) -> Self {
+        bitmap.to_time_series()
+    }
+}
+
+/// Point-wise logic over the common length, owned or borrowed.
+macro_rules! bitmap_ops {
+    ($($tr:ident $m:ident $op:tt),*) => {$(
+        impl $tr for &BitmapSeries {
+            type Output = BitmapSeries;
+            fn $m(self, other: Self) -> Self::Output {
+                self.zip_words(other, |a, b| a $op b)
+            }
+        }
+
+        impl $tr for BitmapSeries {
+            type Output = BitmapSeries;
+            fn $m(self, other: Self) -> Self::Output {
+                (&self).$m(&other)
+            }
+        }
+    )*};
+}
+
+bitmap_ops!(BitAnd bitand &, BitOr bitor |, BitXor bitxor ^);
+
+impl Not for &BitmapSeries {
+    type Output = BitmapSeries;
+    fn not(self) -> Self::Output {
+        let mut bitmap = BitmapSeries { len: self.len, words: self.words.iter().map(|w| !w).collect() };
+        bitmap.clear_tail();
+        bitmap
+    }
+}
+
+impl Not for BitmapSeries {
+    type Output = BitmapSeries;
+    fn not(self) -> Self::Output {
+        !&self
+    }
+}
@@ -0,0 +1,84 @@
+//! Burst detection on count series (events per interval), e.g. requests per second.
+
+use crate::TimeSeries;
+
+/// Expected event count against which a window is compared.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Baseline {
+    /// Poisson counts at the mean rate of all intervals before the window; the spread is
+    /// the square root of the expected count.
+    Poisson,
+    /// Exponentially weighted mean and variance of the intervals before the window, which
+    /// adapts to slow drifts in traffic. Both start from the mean and sample variance of
+    /// the first `ceil(1 / alpha)` intervals, at least 2, and windows starting earlier
+    /// are not flagged.
+    Ewma { alpha: f64 },
+}
+
+pub trait BurstDetect {
+    /// Flags each window of `window` consecutive intervals whose total count exceeds the
+    /// expectation from the preceding intervals by more than `threshold` standard
+    /// deviations. The result has one flag per full window, aligned to the window's end;
+    /// windows without enough history before them for the baseline are never flagged.
+    fn burst_detect(&self, window: usize, threshold: f64, baseline: Baseline) -> TimeSeries<bool>;
+}
+
+impl BurstDetect for TimeSeries<f64> {
+    fn burst_detect(&self, window: usize, threshold: f64, baseline: Baseline) -> TimeSeries<bool> {
+        assert!(window > 0, "window must be positive");
+        let x = self.as_slice();
+        if x.len() < window {
+            return TimeSeries::new();
+        }
+        // Per-interval (mean, variance) of the history before each window start.
+        let mut history: Vec<Option<(f64, f64)>> = Vec::with_capacity(x.len());
+        match baseline {
+            Baseline::Poisson => {
+                let mut sum = 0.;
+                for (i, &c) in x.iter().enumerate() {
+                    history.push((i > 0).then(|| (sum / i as f64, sum / i as f64)));
+                    sum += c;
+                }
+            }
+            Baseline::Ewma { alpha } => {
+                assert!(alpha > 0. && alpha <= 1., "alpha must be in (0, 1]");
+                // A variance from a single interval would be 0 and flag any deviation, so the
+                // baseline is seeded from a warm-up spanning about the EWMA's memory.
+                let warmup = ((1. / alpha).ceil() as usize).max(2);
+                let (mut mean, mut m2) = (0., 0.);
+                let mut state: Option<(f64, f64)> = None;
+                for (i, &c) in x.iter().enumerate() {
+                    history.push(state);
+                    match state {
+                        None => {
+                            let d = c - mean;
+                            mean += d / (i + 1) as f64;
+                            m2 += d * (c - mean);
+                            if i + 1 == warmup {
+                                state = Some((mean, m2 / i as f64));
+                            }
+                        }
+                        Some((mean, var)) => {
+                            let d = c - mean;
+                            state = Some((mean + alpha * d, (1. - alpha) * (var + alpha * d * d)));
+                        }
+                    }
+                }
+            }
+        }
+        let mut total: f64 = x[..window - 1].iter().sum();
+        (window - 1..x.len())
+            .map(|end| {
+                total += x[end];
+                let start = end + 1 - window;
+                let observed = total;
+                total -= x[start];
+                history[start].is_some_and(|(mean, var)| {
+                    let expected = mean * window as f64;
+                    let sd = (var * window as f64).sqrt();
+                    observed > expected && (observed - expected) > threshold * sd
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,119 @@
+//! Memoized derived series (rolling statistics, indicators) over a growing base series.
+//!
+//! Entries are keyed by a caller-chosen string naming the operation and its parameters,
+//! e.g. `"sma:20"`. Appending to the base does not recompute them: on the next lookup
+//! each entry is extended by recomputing only the tail the new points affect.
+
+use crate::{StreamOp, TimeSeries};
+use std::collections::HashMap;
+
+struct Entry {
+    output: TimeSeries<f64>,
+    /// Number of base points the output reflects.
+    synced: usize,
+    /// State of a streaming entry, fed only the new points.
+    op: Option<Box<dyn StreamOp + Send>>,
+}
+
+/// A base series plus the derived series computed from it so far.
+pub struct SeriesCache<T> {
+    base: TimeSeries<T>,
+    entries: HashMap<String, Entry>,
+}
+
+impl<T: Clone> SeriesCache<T> {
+    pub fn new(base: TimeSeries<T>) -> Self {
+        Self { base, entries: HashMap::new() }
+    }
+
+    pub fn base(&self) -> &TimeSeries<T> {
+        &self.base
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.base.push(value);
+    }
+
+    pub fn extend<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        self.base.extend(values);
+    }
+
+    /// Swaps in a different base series, dropping every cached entry.
+    pub fn replace(&mut self, base: TimeSeries<T>) {
+        self.base = base;
+        self.entries.clear();
+    }
+
+    /// The cached output of `compute` under `key`, brought up to date with the base.
+    ///
+    /// `compute` must produce output aligned to the end of its input, one new point per
+    /// new input point (full-length or `n - window + 1` outputs both qualify), where each
+    /// output depends on at most `lookback` preceding inputs: `window - 1` for a rolling
+    /// window. After an append only the last `lookback + new` base points are passed to
+    /// it. Recursive operations (EWMA, cumulative sums) depend on all history; pass
+    /// `usize::MAX` to recompute them in full, or use [`get_or_stream`](Self::get_or_stream).
+    pub fn get_or_compute<F>(&mut self, key: &str, lookback: usize, compute: F) -> &TimeSeries<f64>
+    where
+        F: Fn(&TimeSeries<T>) -> TimeSeries<f64>,
+    {
+        let n = self.base.len();
+        let entry = self
+            .entries
+            .entry(key.to_string())
+            .or_insert_with(|| Entry { output: compute(&self.base), synced: n, op: None });
+        if entry.synced < n {
+            if entry.synced >= lookback {
+                let tail = compute(&self.base.slice(entry.synced - lookback..n));
+                let new = (n - entry.synced).min(tail.len());
+                entry.output.extend(tail.as_slice()[tail.len() - new..].iter().cloned());
+            } else {
+                entry.output = compute(&self.base);
+            }
+            entry.synced = n;
+        }
+        &entry.output
+    }
+
+    /// Drops the entry under `key`, returning whether there was one.
+    pub fn invalidate(&mut self, key: &str) -> bool {
+        self.entries.remove(key).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Keys of the cached entries, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+}
+
+impl SeriesCache<f64> {
+    /// The cached output of a streaming operator under `key`. The operator built by
+    /// `make` on first use keeps its state, so later appends cost O(new points) even for
+    /// recursive operations.
+    ///
+    /// # Panics
+    /// Panics if `key` is already cached by [`get_or_compute`](Self::get_or_compute).
+    pub fn get_or_stream<O, F>(&mut self, key: &str, make: F) -> &TimeSeries<f64>
+    where
+        O: StreamOp + Send + 'static,
+        F: FnOnce() -> O,
+    {
+        let base = &self.base;
+        let entry = self.entries.entry(key.to_string()).or_insert_with(|| Entry {
+            output: TimeSeries::new(),
+            synced: 0,
+            op: Some(Box::new(make())),
+        });
+        let op = entry.op.as_mut().expect("key was first cached as a computed entry, not a streaming one");
+        for &x in &base.as_slice()[entry.synced..] {
+            if let Some(y) = op.push(x) {
+                entry.output.push(y);
+            }
+        }
+        entry.synced = base.len();
+        &entry.output
+    }
+}
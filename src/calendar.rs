@@ -0,0 +1,236 @@
+//! Calendar arithmetic on Unix timestamps in seconds (UTC), without external dependencies.
+
+use crate::{Error, TimestampedSeries};
+use std::time::Duration;
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// A calendar period used to step timestamps back and forth.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalendarPeriod {
+    Day,
+    Week,
+    /// Same day of the month, clamped to the month's last day (Mar 31 → Feb 28).
+    Month,
+    Quarter,
+    /// Same calendar date, with Feb 29 mapped to Feb 28 in common years.
+    Year,
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date (Hinnant's algorithm).
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Gregorian `(year, month, day)` of a day count since 1970-01-01.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+pub fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn add_months(timestamp: i64, months: i64) -> i64 {
+    let days = timestamp.div_euclid(SECONDS_PER_DAY);
+    let time_of_day = timestamp.rem_euclid(SECONDS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    let total = year * 12 + (month as i64 - 1) + months;
+    let (year, month) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+    let day = day.min(days_in_month(year, month));
+    days_from_civil(year, month, day) * SECONDS_PER_DAY + time_of_day
+}
+
+impl CalendarPeriod {
+    /// Start of the period containing `timestamp`: midnight, Monday, or the first day of
+    /// the month, quarter or year.
+    pub fn floor(&self, timestamp: i64) -> i64 {
+        let days = timestamp.div_euclid(SECONDS_PER_DAY);
+        let start_day = match self {
+            CalendarPeriod::Day => days,
+            // 1970-01-01 was a Thursday, three days after a Monday.
+            CalendarPeriod::Week => days - (days + 3).rem_euclid(7),
+            CalendarPeriod::Month | CalendarPeriod::Quarter | CalendarPeriod::Year => {
+                let (year, month, _) = civil_from_days(days);
+                let month = match self {
+                    CalendarPeriod::Month => month,
+                    CalendarPeriod::Quarter => (month - 1) / 3 * 3 + 1,
+                    _ => 1,
+                };
+                days_from_civil(year, month, 1)
+            }
+        };
+        start_day * SECONDS_PER_DAY
+    }
+
+    /// Moves `timestamp` by `n` periods (negative to go back).
+    pub fn shift(&self, timestamp: i64, n: i64) -> i64 {
+        match self {
+            CalendarPeriod::Day => timestamp + n * SECONDS_PER_DAY,
+            CalendarPeriod::Week => timestamp + 7 * n * SECONDS_PER_DAY,
+            CalendarPeriod::Month => add_months(timestamp, n),
+            CalendarPeriod::Quarter => add_months(timestamp, 3 * n),
+            CalendarPeriod::Year => add_months(timestamp, 12 * n),
+        }
+    }
+}
+
+/// Midnight UTC of an ISO 8601 calendar date `YYYY-MM-DD`, in Unix seconds.
+pub fn parse_date(date: &str) -> Result<i64, Error> {
+    let invalid = || Error::InvalidInput(format!("expected a `YYYY-MM-DD` date, got `{}`", date));
+    let mut parts = date.trim().splitn(3, '-');
+    let mut field = |len: usize| {
+        let part = parts.next().filter(|p| p.len() == len && p.bytes().all(|b| b.is_ascii_digit()));
+        part.and_then(|p| p.parse::<i64>().ok())
+    };
+    let (year, month, day): (i64, u32, u32) = match (field(4), field(2), field(2)) {
+        (Some(y), Some(m), Some(d)) => (y, m as u32, d as u32),
+        _ => return Err(invalid()),
+    };
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return Err(invalid());
+    }
+    Ok(days_from_civil(year, month, day) * SECONDS_PER_DAY)
+}
+
+/// A duration written as ISO 8601 (`P2W`, `P1DT12H`, `PT15M`, `PT0.5S`) or as a number
+/// with a unit suffix `s`, `m`, `h`, `d` or `w` (`90s`, `15m`, `3d`). Months and years
+/// have no fixed length and are rejected.
+pub fn parse_duration(text: &str) -> Result<Duration, Error> {
+    let invalid = || Error::InvalidInput(format!("invalid duration `{}`", text));
+    let unit_seconds = |unit: char, in_time: bool| -> Option<f64> {
+        Some(match unit {
+            'W' | 'w' => 7. * 86_400.,
+            'D' | 'd' => 86_400.,
+            'H' | 'h' => 3_600.,
+            'M' if in_time => 60.,
+            'm' => 60.,
+            'S' | 's' => 1.,
+            _ => return None,
+        })
+    };
+    let text = text.trim();
+    let seconds = if let Some(iso) = text.strip_prefix('P') {
+        let (date, time) = iso.split_once('T').unwrap_or((iso, ""));
+        if (date.is_empty() && time.is_empty()) || (iso.contains('T') && time.is_empty()) {
+            return Err(invalid());
+        }
+        let mut total = 0.;
+        for (part, in_time) in [(date, false), (time, true)] {
+            let mut number = String::new();
+            for c in part.chars() {
+                if c.is_ascii_digit() || c == '.' {
+                    number.push(c);
+                    continue;
+                }
+                let unit = unit_seconds(c, in_time).filter(|_| in_time != matches!(c, 'D' | 'W')).ok_or_else(invalid)?;
+                total += number.parse::<f64>().map_err(|_| invalid())? * unit;
+                number.clear();
+            }
+            if !number.is_empty() {
+                return Err(invalid());
+            }
+        }
+        total
+    } else {
+        let unit = text.chars().last().ok_or_else(invalid)?;
+        let number: f64 = text[..text.len() - unit.len_utf8()].parse().map_err(|_| invalid())?;
+        number * unit_seconds(unit, false).filter(|_| unit != 'M').ok_or_else(invalid)?
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| invalid())
+}
+
+/// Range selectors for series stamped in Unix seconds. Relative ranges are anchored at
+/// the last point of the series, so results do not depend on when they are computed.
+impl<T: Clone> TimestampedSeries<T> {
+    /// Points within `duration` of the last point: `t > last - duration`.
+    pub fn last(&self, duration: Duration) -> Self {
+        match self.timestamps().last() {
+            Some(&last) => self.between(last.saturating_sub(duration.as_secs() as i64) + 1, i64::MAX),
+            None => self.clone(),
+        }
+    }
+
+    /// Points from midnight of `start` through the end of `end`, both `YYYY-MM-DD`.
+    pub fn between_dates(&self, start: &str, end: &str) -> Result<Self, Error> {
+        Ok(self.between(parse_date(start)?, parse_date(end)? + SECONDS_PER_DAY))
+    }
+
+    /// Points from the start of the `period` containing the last point.
+    pub fn period_to_date(&self, period: CalendarPeriod) -> Self {
+        match self.timestamps().last() {
+            Some(&last) => self.between(period.floor(last), i64::MAX),
+            None => self.clone(),
+        }
+    }
+
+    /// Year to date.
+    pub fn ytd(&self) -> Self {
+        self.period_to_date(CalendarPeriod::Year)
+    }
+
+    /// Quarter to date.
+    pub fn qtd(&self) -> Self {
+        self.period_to_date(CalendarPeriod::Quarter)
+    }
+
+    /// Month to date.
+    pub fn mtd(&self) -> Self {
+        self.period_to_date(CalendarPeriod::Month)
+    }
+}
+
+/// Comparisons of each point with its counterpart one calendar period earlier.
+pub trait CalendarComparison {
+    /// Value at exactly one `period` before each timestamp, if there is a point there.
+    fn previous_period(&self, period: CalendarPeriod) -> TimestampedSeries<Option<f64>>;
+    /// Fractional change against the same point one period earlier (YoY, MoM, WoW, DoD).
+    /// Points without a counterpart are NaN.
+    fn period_over_period(&self, period: CalendarPeriod) -> TimestampedSeries<f64>;
+}
+
+impl CalendarComparison for TimestampedSeries<f64> {
+    fn previous_period(&self, period: CalendarPeriod) -> TimestampedSeries<Option<f64>> {
+        let timestamps = self.timestamps();
+        self.iter()
+            .map(|(t, _)| {
+                let target = period.shift(t, -1);
+                let i = timestamps.partition_point(|&x| x < target);
+                let value = (timestamps.get(i) == Some(&target)).then(|| self.values()[i]);
+                (t, value)
+            })
+            .collect()
+    }
+
+    fn period_over_period(&self, period: CalendarPeriod) -> TimestampedSeries<f64> {
+        let previous = self.previous_period(period);
+        self.iter()
+            .zip(previous.values())
+            .map(|((t, x), prev)| (t, prev.map_or(f64::NAN, |p| x / p - 1.)))
+            .collect()
+    }
+}
@@ -0,0 +1,414 @@
+//! Causal impact of an intervention, such as a release, a price change or an incident,
+//! on a metric series.
+//!
+//! Each estimator builds a counterfactual of what the series would have done without the
+//! intervention from the points before it, and reads the effect off the points from
+//! `intervention` on:
+//! - [`causal_impact`] regresses the series on control series the intervention did not
+//!   affect, or on time alone without controls, and projects the fit forward;
+//! - [`interrupted_time_series`] fits one line before the intervention and another
+//!   after, and reports the jumps in level and slope;
+//! - [`difference_in_differences`] compares the change in the mean of a treated series
+//!   with the change in the mean of a control series;
+//! - [`synthetic_control`] matches the treated series with a weighted average of donor
+//!   series and judges the gap against placebo runs on the donors.
+//!
+//! Intervals use Student's t with the residual degrees of freedom. Residuals are taken
+//! to be independent, so autocorrelated residuals make the intervals too narrow.
+
+use crate::linalg::{least_squares, solve};
+use crate::special::{student_t_ppf, student_t_two_sided};
+use crate::{Error, Interval, SeriesSet, TimeSeries, TimestampedSeries};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An estimated effect with its uncertainty.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    pub value: f64,
+    pub std_error: f64,
+    pub interval: Interval,
+    /// Two-sided p-value of no effect.
+    pub p_value: f64,
+}
+
+impl Estimate {
+    fn new(value: f64, std_error: f64, df: f64, level: f64) -> Self {
+        let half_width = student_t_ppf(0.5 + level / 2., df) * std_error;
+        Self {
+            value,
+            std_error,
+            interval: Interval::new(value - half_width, value + half_width),
+            p_value: student_t_two_sided(value / std_error, df),
+        }
+    }
+}
+
+/// An ordinary least-squares fit with what its uncertainty needs.
+struct Ols {
+    coefficients: Vec<f64>,
+    /// `X'X` of the design.
+    gram: Vec<Vec<f64>>,
+    /// Residual variance.
+    sigma2: f64,
+    df: f64,
+}
+
+impl Ols {
+    fn fit(x: &[Vec<f64>], y: &[f64]) -> Result<Self, Error> {
+        let p = x[0].len();
+        let coefficients = least_squares(x, y).ok_or(Error::Singular)?;
+        let gram = (0..p).map(|i| (0..p).map(|j| x.iter().map(|row| row[i] * row[j]).sum()).collect()).collect();
+        let df = (y.len() - p) as f64;
+        let sigma2 = x.iter().zip(y).map(|(row, yi)| (yi - dot(row, &coefficients)).powi(2)).sum::<f64>() / df;
+        Ok(Self { coefficients, gram, sigma2, df })
+    }
+
+    fn predict(&self, row: &[f64]) -> f64 {
+        dot(row, &self.coefficients)
+    }
+
+    /// Variance of `v · β̂`.
+    fn variance(&self, v: &[f64]) -> f64 {
+        let w = solve(self.gram.clone(), v.to_vec()).expect("the design has full rank");
+        self.sigma2 * dot(v, &w)
+    }
+
+    fn coefficient(&self, i: usize, level: f64) -> Estimate {
+        let mut unit = vec![0.; self.coefficients.len()];
+        unit[i] = 1.;
+        Estimate::new(self.coefficients[i], self.variance(&unit).sqrt(), self.df, level)
+    }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn check(series: &[&TimeSeries<f64>], intervention: usize, level: f64) -> Result<(), Error> {
+    assert!(level > 0. && level < 1., "level must be between 0 and 1");
+    let n = series[0].len();
+    if let Some(other) = series.iter().find(|s| s.len() != n) {
+        return Err(Error::LengthMismatch { expected: n, found: other.len() });
+    }
+    if let Some(i) = series.iter().position(|s| s.as_slice().iter().any(|x| !x.is_finite())) {
+        return Err(Error::InvalidInput(format!("series {} has non-finite values", i)));
+    }
+    if intervention == 0 || intervention >= n {
+        return Err(Error::InvalidInput(format!("intervention at {} leaves no points on one side", intervention)));
+    }
+    Ok(())
+}
+
+/// The outcome of [`causal_impact`].
+#[derive(Clone, Debug)]
+pub struct CausalImpact {
+    /// The fit before the intervention and its projection after, at every point.
+    pub counterfactual: TimeSeries<f64>,
+    /// Prediction intervals of the counterfactual at every point.
+    pub bands: TimeSeries<Interval>,
+    /// Observed minus counterfactual at each point from the intervention on.
+    pub pointwise: TimeSeries<f64>,
+    /// The mean of `pointwise`.
+    pub average: Estimate,
+    /// The sum of `pointwise`.
+    pub cumulative: Estimate,
+}
+
+/// Fits `series` before `intervention` by least squares on an intercept and `controls`,
+/// or on an intercept and a linear trend without controls, and compares the points from
+/// `intervention` on with the fit's projection. Intervals at confidence `level` account
+/// for both the noise and the uncertainty of the fit.
+///
+/// Fails if the lengths differ, a value is not finite, `intervention` leaves no points
+/// on either side, there are no more points before it than coefficients, or the
+/// regressors are collinear.
+///
+/// # Panics
+/// Panics unless `0 < level < 1`.
+pub fn causal_impact(
+    series: &TimeSeries<f64>,
+    controls: &[&TimeSeries<f64>],
+    intervention: usize,
+    level: f64,
+) -> Result<CausalImpact, Error> {
+    check(&[&[series], controls].concat(), intervention, level)?;
+    let rows: Vec<Vec<f64>> = (0..series.len())
+        .map(|t| match controls {
+            [] => vec![1., t as f64],
+            _ => std::iter::once(1.).chain(controls.iter().map(|c| c.as_slice()[t])).collect(),
+        })
+        .collect();
+    let p = rows[0].len();
+    if intervention <= p {
+        return Err(Error::InsufficientData { required: p + 1, found: intervention });
+    }
+    let fit = Ols::fit(&rows[..intervention], &series.as_slice()[..intervention])?;
+    let counterfactual: TimeSeries<f64> = rows.iter().map(|row| fit.predict(row)).collect();
+    let half_width = student_t_ppf(0.5 + level / 2., fit.df);
+    let bands = rows
+        .iter()
+        .zip(counterfactual.as_slice())
+        .map(|(row, &c)| {
+            let se = (fit.sigma2 + fit.variance(row)).sqrt();
+            Interval::new(c - half_width * se, c + half_width * se)
+        })
+        .collect();
+    let pointwise: TimeSeries<f64> =
+        series.as_slice()[intervention..].iter().zip(&counterfactual.as_slice()[intervention..]).map(|(y, c)| y - c).collect();
+    let m = pointwise.len() as f64;
+    let mean_row: Vec<f64> = (0..p).map(|j| rows[intervention..].iter().map(|row| row[j]).sum::<f64>() / m).collect();
+    let average = pointwise.as_slice().iter().sum::<f64>() / m;
+    let se = (fit.sigma2 / m + fit.variance(&mean_row)).sqrt();
+    Ok(CausalImpact {
+        counterfactual,
+        bands,
+        pointwise,
+        average: Estimate::new(average, se, fit.df, level),
+        cumulative: Estimate::new(average * m, se * m, fit.df, level),
+    })
+}
+
+/// The outcome of [`interrupted_time_series`].
+#[derive(Clone, Debug)]
+pub struct SegmentedRegression {
+    /// The jump of the fitted line at the intervention.
+    pub level_change: Estimate,
+    /// The change of its slope per step.
+    pub slope_change: Estimate,
+    /// The fitted lines at every point.
+    pub fitted: TimeSeries<f64>,
+    /// The line before the intervention, extended over every point.
+    pub counterfactual: TimeSeries<f64>,
+}
+
+/// Segmented regression `y = a + b t + c D + d (t - intervention) D`, with `D` 1 from
+/// `intervention` on: `c` is the change in level and `d` the change in slope.
+///
+/// Fails if a value is not finite, there are fewer than two points on either side of
+/// the intervention or fewer than five in all, or the design is singular.
+///
+/// # Panics
+/// Panics unless `0 < level < 1`.
+pub fn interrupted_time_series(
+    series: &TimeSeries<f64>,
+    intervention: usize,
+    level: f64,
+) -> Result<SegmentedRegression, Error> {
+    check(&[series], intervention, level)?;
+    if intervention < 2 || series.len() - intervention < 2 {
+        return Err(Error::InvalidInput("segmented regression needs two points on each side".to_string()));
+    }
+    if series.len() < 5 {
+        return Err(Error::InsufficientData { required: 5, found: series.len() });
+    }
+    let rows: Vec<Vec<f64>> = (0..series.len())
+        .map(|t| {
+            let after = if t >= intervention { 1. } else { 0. };
+            vec![1., t as f64, after, (t as f64 - intervention as f64) * after]
+        })
+        .collect();
+    let fit = Ols::fit(&rows, series.as_slice())?;
+    Ok(SegmentedRegression {
+        level_change: fit.coefficient(2, level),
+        slope_change: fit.coefficient(3, level),
+        fitted: rows.iter().map(|row| fit.predict(row)).collect(),
+        counterfactual: rows.iter().map(|row| fit.predict(&row[..2])).collect(),
+    })
+}
+
+/// The change in the mean of `treated` from before `intervention` to after, minus the
+/// same change in `control`, from the regression on group, period and their interaction.
+/// It assumes the two series would have moved in parallel without the intervention.
+///
+/// Fails if the lengths differ, a value is not finite, `intervention` leaves no points
+/// on either side, or there are fewer than three points per series.
+///
+/// # Panics
+/// Panics unless `0 < level < 1`.
+pub fn difference_in_differences(
+    treated: &TimeSeries<f64>,
+    control: &TimeSeries<f64>,
+    intervention: usize,
+    level: f64,
+) -> Result<Estimate, Error> {
+    check(&[treated, control], intervention, level)?;
+    if treated.len() < 3 {
+        return Err(Error::InsufficientData { required: 3, found: treated.len() });
+    }
+    let (mut rows, mut y) = (Vec::new(), Vec::new());
+    for (group, series) in [(1., treated), (0., control)] {
+        for (t, &v) in series.as_slice().iter().enumerate() {
+            let post = if t >= intervention { 1. } else { 0. };
+            rows.push(vec![1., group, post, group * post]);
+            y.push(v);
+        }
+    }
+    Ok(Ols::fit(&rows, &y)?.coefficient(3, level))
+}
+
+/// The outcome of [`synthetic_control`].
+#[derive(Clone, Debug)]
+pub struct SyntheticControl {
+    /// The weight of every donor, nonnegative and summing to 1.
+    pub weights: BTreeMap<String, f64>,
+    /// The weighted donors at the timestamps shared by every series.
+    pub synthetic: TimestampedSeries<f64>,
+    /// The treated series minus the synthetic one.
+    pub gap: TimestampedSeries<f64>,
+    /// Root mean squared gap before the intervention and from it on.
+    pub pre_rmspe: f64,
+    pub post_rmspe: f64,
+    /// The mean gap from the intervention on.
+    pub average_effect: f64,
+    /// `post_rmspe / pre_rmspe` of each donor fitted from the other donors as if it had
+    /// been treated.
+    pub placebo_ratios: BTreeMap<String, f64>,
+    /// The share of the treated series and the placebos with a ratio at least the treated
+    /// one's: the permutation p-value of no effect.
+    pub p_value: f64,
+}
+
+impl SyntheticControl {
+    /// `post_rmspe / pre_rmspe`, large when the fit breaks down after the intervention.
+    pub fn ratio(&self) -> f64 {
+        self.post_rmspe / self.pre_rmspe
+    }
+}
+
+/// Euclidean projection of `v` onto the probability simplex.
+fn project_simplex(v: &mut [f64]) {
+    let mut sorted = v.to_vec();
+    sorted.sort_by(|a, b| b.total_cmp(a));
+    let (mut sum, mut shift) = (0., 0.);
+    for (i, &x) in sorted.iter().enumerate() {
+        sum += x;
+        let t = (sum - 1.) / (i + 1) as f64;
+        if x > t {
+            shift = t;
+        }
+    }
+    v.iter_mut().for_each(|x| *x = (*x - shift).max(0.));
+}
+
+/// Weights on the simplex minimizing `|y - Σ w_j donors_j|²`, by accelerated projected
+/// gradient descent.
+fn simplex_weights(y: &[f64], donors: &[&[f64]]) -> Vec<f64> {
+    let k = donors.len();
+    let gram: Vec<Vec<f64>> = donors.iter().map(|a| donors.iter().map(|b| dot(a, b)).collect()).collect();
+    let xty: Vec<f64> = donors.iter().map(|d| dot(d, y)).collect();
+    // The largest eigenvalue of the Gram matrix by power iteration, for the step size.
+    let mut v = vec![1.; k];
+    let mut lambda = 0.;
+    for _ in 0..50 {
+        let w: Vec<f64> = gram.iter().map(|row| dot(row, &v)).collect();
+        lambda = dot(&w, &w).sqrt();
+        if lambda == 0. {
+            break;
+        }
+        v = w.iter().map(|x| x / lambda).collect();
+    }
+    let step = if lambda > 0. { 1. / lambda } else { 1. };
+    let mut weights = vec![1. / k as f64; k];
+    let mut momentum = weights.clone();
+    let mut t: f64 = 1.;
+    for _ in 0..10_000 {
+        let gradient: Vec<f64> = gram.iter().zip(&xty).map(|(row, b)| dot(row, &momentum) - b).collect();
+        let mut next: Vec<f64> = momentum.iter().zip(&gradient).map(|(m, g)| m - step * g).collect();
+        project_simplex(&mut next);
+        let t_next = (1. + (1. + 4. * t * t).sqrt()) / 2.;
+        let change = next.iter().zip(&weights).map(|(a, b)| (a - b).abs()).fold(0., f64::max);
+        momentum = next.iter().zip(&weights).map(|(a, b)| a + (t - 1.) / t_next * (a - b)).collect();
+        weights = next;
+        t = t_next;
+        if change < 1e-12 {
+            break;
+        }
+    }
+    weights
+}
+
+/// The synthetic series of `y` from `donors` fitted on the first `pre` points, with its
+/// weights and pre- and post-intervention root mean squared gaps.
+fn fit_synthetic(y: &[f64], donors: &[&[f64]], pre: usize) -> (Vec<f64>, Vec<f64>, f64, f64) {
+    let pre_donors: Vec<&[f64]> = donors.iter().map(|d| &d[..pre]).collect();
+    let weights = simplex_weights(&y[..pre], &pre_donors);
+    let synthetic: Vec<f64> = (0..y.len()).map(|t| donors.iter().zip(&weights).map(|(d, w)| w * d[t]).sum()).collect();
+    let rmspe = |range: std::ops::Range<usize>| {
+        let n = range.len() as f64;
+        (range.map(|t| (y[t] - synthetic[t]).powi(2)).sum::<f64>() / n).sqrt()
+    };
+    let (pre_rmspe, post_rmspe) = (rmspe(0..pre), rmspe(pre..y.len()));
+    (weights, synthetic, pre_rmspe, post_rmspe)
+}
+
+/// Fits the convex combination of `donors` closest to `treated` before the timestamp
+/// `intervention`, and measures the gap from then on. Every donor in turn is then
+/// treated as a placebo, fitted from the other donors, to see how often a gap as large
+/// relative to the pre-intervention fit arises by chance. Series are compared at the
+/// timestamps present in all of them.
+///
+/// Fails if there are no donors, a value is not finite, or no shared timestamps fall on
+/// one side of the intervention.
+pub fn synthetic_control(
+    treated: &TimestampedSeries<f64>,
+    donors: &SeriesSet<f64>,
+    intervention: i64,
+) -> Result<SyntheticControl, Error> {
+    if donors.is_empty() {
+        return Err(Error::InvalidInput("no donor series".to_string()));
+    }
+    let unit = treated.unit();
+    let donors = donors.align();
+    let in_donors: BTreeSet<i64> = donors.iter().flat_map(|(_, d)| d.timestamps().iter().copied()).collect();
+    let treated: TimestampedSeries<f64> =
+        treated.iter().filter(|(t, _)| in_donors.contains(t)).map(|(t, &v)| (t, v)).collect();
+    let shared: BTreeSet<i64> = treated.timestamps().iter().copied().collect();
+    let donors = donors.apply_all(|d| d.iter().filter(|(t, _)| shared.contains(t)).map(|(t, &v)| (t, v)).collect());
+    let names: Vec<&String> = donors.iter().map(|(name, _)| name).collect();
+    let columns: Vec<&[f64]> = donors.iter().map(|(_, d)| d.values().as_slice()).collect();
+    let y = treated.values().as_slice();
+    if let Some(column) = columns.iter().find(|c| c.len() != y.len()) {
+        return Err(Error::LengthMismatch { expected: y.len(), found: column.len() });
+    }
+    let non_finite = |values: &[f64]| values.iter().any(|x| !x.is_finite());
+    if non_finite(y) {
+        return Err(Error::InvalidInput("the treated series has non-finite values".to_string()));
+    }
+    if let Some((name, _)) = names.iter().zip(&columns).find(|(_, c)| non_finite(c)) {
+        return Err(Error::InvalidInput(format!("donor `{}` has non-finite values", name)));
+    }
+    let pre = treated.timestamps().partition_point(|&t| t < intervention);
+    if pre == 0 || pre == y.len() {
+        return Err(Error::InvalidInput("no shared timestamps on one side of the intervention".to_string()));
+    }
+    let (weights, synthetic, pre_rmspe, post_rmspe) = fit_synthetic(y, &columns, pre);
+    let ratio = post_rmspe / pre_rmspe;
+    let mut placebo_ratios = BTreeMap::new();
+    if columns.len() > 1 {
+        for (j, name) in names.iter().enumerate() {
+            let pool: Vec<&[f64]> = columns.iter().enumerate().filter(|&(i, _)| i != j).map(|(_, c)| *c).collect();
+            let (_, _, pre, post) = fit_synthetic(columns[j], &pool, pre);
+            placebo_ratios.insert(name.to_string(), post / pre);
+        }
+    }
+    let as_extreme = placebo_ratios.values().filter(|&&r| r >= ratio).count();
+    let gap: TimeSeries<f64> = y.iter().zip(&synthetic).map(|(a, b)| a - b).collect();
+    let average_effect = gap.as_slice()[pre..].iter().sum::<f64>() / (y.len() - pre) as f64;
+    let series = |values: TimeSeries<f64>| {
+        let out = TimestampedSeries::from_parts(treated.timestamps().to_vec(), values);
+        match unit {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    };
+    Ok(SyntheticControl {
+        weights: names.into_iter().cloned().zip(weights).collect(),
+        synthetic: series(TimeSeries(synthetic)),
+        gap: series(gap),
+        pre_rmspe,
+        post_rmspe,
+        average_effect,
+        p_value: (1 + as_extreme) as f64 / (1 + placebo_ratios.len()) as f64,
+        placebo_ratios,
+    })
+}
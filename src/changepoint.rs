@@ -0,0 +1,110 @@
+//! Bayesian online changepoint detection (Adams & MacKay, 2007).
+//!
+//! Each observation updates the posterior over the current run length, the number of
+//! points since the last changepoint, under a Gaussian model with unknown mean and
+//! variance (normal-gamma prior) and a constant hazard rate.
+
+use crate::special::ln_gamma;
+use crate::TimeSeries;
+
+/// Run lengths whose posterior probability falls below this are dropped.
+const PRUNE_BELOW: f64 = 1e-10;
+
+/// Normal-gamma sufficient statistics of one run-length hypothesis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Posterior {
+    mu: f64,
+    kappa: f64,
+    alpha: f64,
+    beta: f64,
+}
+
+impl Posterior {
+    /// Log density of `x` under the Student t posterior predictive.
+    fn log_predictive(&self, x: f64) -> f64 {
+        let df = 2. * self.alpha;
+        let scale2 = self.beta * (self.kappa + 1.) / (self.alpha * self.kappa);
+        let z2 = (x - self.mu).powi(2) / scale2;
+        ln_gamma((df + 1.) / 2.) - ln_gamma(df / 2.)
+            - 0.5 * (df * std::f64::consts::PI * scale2).ln()
+            - (df + 1.) / 2. * (1. + z2 / df).ln()
+    }
+
+    fn update(&self, x: f64) -> Self {
+        let kappa = self.kappa + 1.;
+        Self {
+            mu: (self.kappa * self.mu + x) / kappa,
+            kappa,
+            alpha: self.alpha + 0.5,
+            beta: self.beta + self.kappa * (x - self.mu).powi(2) / (2. * kappa),
+        }
+    }
+}
+
+/// Streaming detector; feed it one observation at a time with [`OnlineChangepoint::push`].
+#[derive(Clone, Debug)]
+pub struct OnlineChangepoint {
+    hazard: f64,
+    prior: Posterior,
+    /// Posterior probability of each run length (index = run length) and its model.
+    run_lengths: Vec<(f64, Posterior)>,
+}
+
+impl OnlineChangepoint {
+    /// Detector expecting a changepoint every `expected_run_length` points on average,
+    /// with a weak prior centred on `prior_mean` with scale `prior_std`.
+    pub fn new(expected_run_length: f64, prior_mean: f64, prior_std: f64) -> Self {
+        assert!(expected_run_length >= 1., "expected run length must be at least 1");
+        let prior = Posterior { mu: prior_mean, kappa: 1., alpha: 1., beta: prior_std * prior_std };
+        Self { hazard: 1. / expected_run_length, prior, run_lengths: Vec::new() }
+    }
+
+    /// Absorbs the next observation and returns the probability that a changepoint
+    /// occurred within the last `window` points (`P(run length < window)`).
+    pub fn push(&mut self, x: f64, window: usize) -> f64 {
+        if self.run_lengths.is_empty() {
+            self.run_lengths.push((1., self.prior.update(x)));
+            return 1.;
+        }
+        let predictive: Vec<f64> = self.run_lengths.iter().map(|(p, m)| p * m.log_predictive(x).exp()).collect();
+        let changepoint: f64 = predictive.iter().sum::<f64>() * self.hazard;
+        let mut next = Vec::with_capacity(self.run_lengths.len() + 1);
+        next.push((changepoint, self.prior.update(x)));
+        for ((_, model), growth) in self.run_lengths.iter().zip(&predictive) {
+            next.push((growth * (1. - self.hazard), model.update(x)));
+        }
+        let evidence: f64 = next.iter().map(|(p, _)| p).sum();
+        for (p, _) in next.iter_mut() {
+            *p /= evidence;
+        }
+        while next.len() > 1 && next.last().is_some_and(|(p, _)| *p < PRUNE_BELOW) {
+            next.pop();
+        }
+        self.run_lengths = next;
+        self.changepoint_probability(window)
+    }
+
+    /// `P(run length < window)` under the current posterior.
+    pub fn changepoint_probability(&self, window: usize) -> f64 {
+        self.run_lengths.iter().take(window).map(|(p, _)| p).sum()
+    }
+
+    /// Posterior probability of each run length, starting at 0.
+    pub fn run_length_distribution(&self) -> Vec<f64> {
+        self.run_lengths.iter().map(|(p, _)| *p).collect()
+    }
+
+    pub fn most_likely_run_length(&self) -> usize {
+        self.run_lengths
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1 .0.total_cmp(&b.1 .0))
+            .map_or(0, |(r, _)| r)
+    }
+
+    /// Runs the detector over a whole series, returning the changepoint probability after
+    /// each point.
+    pub fn run(&mut self, series: &TimeSeries<f64>, window: usize) -> TimeSeries<f64> {
+        series.as_slice().iter().map(|&x| self.push(x, window)).collect()
+    }
+}
@@ -0,0 +1,477 @@
+//! On-disk archive of [`ChunkedSeries`] with content-addressed chunks.
+//!
+//! Each chunk is stored once under the SHA-256 hash of its points, in `chunks/`, and each
+//! series is a manifest in `series/` listing its chunks with their time span and summary.
+//! Series names are percent-encoded into file names, so any name stays inside the store,
+//! `plant/line1` included. Saving
+//! the same history again, or several series sharing their first chunks, writes no new
+//! chunk files; [`ChunkStore::gc`] deletes the chunks no manifest refers to any more.
+//!
+//! With the `encryption` feature, [`ChunkStore::with_encryption`] seals every file the
+//! store writes, and names chunk files by a MAC of their points under the store's key
+//! ([`Encryption::mac`]) instead of their hash, so that the names reveal nothing of the
+//! contents without the key. Equal chunks saved under the same key id still share a file.
+//!
+//! Files are replaced by renaming a complete temporary file, so a crash leaves either the
+//! old or the new version. Garbage collection must not run while another process saves,
+//! since a save writes its chunks before the manifest that references them.
+
+#[cfg(feature = "encryption")]
+use crate::Encryption;
+use crate::io::{decode_file_name, encode_file_name};
+use crate::sha256::sha256;
+use crate::{ChunkSummary, ChunkedSeries, TimeUnit};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const CHUNK_DIR: &str = "chunks";
+const SERIES_DIR: &str = "series";
+const METADATA_DIR: &str = "metadata";
+/// Extension of sealed chunk files, kept apart from plain ones of the same content.
+#[cfg(feature = "encryption")]
+const SEALED_EXTENSION: &str = "sealed";
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// SHA-256 of a chunk's bytes, so that two different chunks cannot share a file, or their
+/// MAC in an encrypted store.
+pub type ChunkHash = [u8; 32];
+
+fn hex(hash: &ChunkHash) -> String {
+    hash.iter().fold(String::with_capacity(64), |mut s, b| {
+        write!(s, "{:02x}", b).expect("writing to a string");
+        s
+    })
+}
+
+fn parse_hex(text: &str) -> Option<ChunkHash> {
+    if text.len() != 64 || !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut hash = [0; 32];
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&text[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(hash)
+}
+
+/// Little-endian point count, then the timestamps, then the bits of the values.
+fn encode_chunk(timestamps: &[i64], values: &[f64]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(8 + 16 * timestamps.len());
+    bytes.extend((timestamps.len() as u64).to_le_bytes());
+    timestamps.iter().for_each(|t| bytes.extend(t.to_le_bytes()));
+    values.iter().for_each(|v| bytes.extend(v.to_bits().to_le_bytes()));
+    bytes
+}
+
+fn decode_chunk(bytes: &[u8]) -> Option<Vec<(i64, f64)>> {
+    let word = |i: usize| Some(u64::from_le_bytes(bytes.get(8 * i..8 * i + 8)?.try_into().ok()?));
+    let n = usize::try_from(word(0)?).ok()?;
+    if bytes.len() != 8 + 16 * n {
+        return None;
+    }
+    (0..n).map(|i| Some((word(1 + i)? as i64, f64::from_bits(word(1 + n + i)?)))).collect()
+}
+
+fn unit_name(unit: TimeUnit) -> &'static str {
+    match unit {
+        TimeUnit::Seconds => "s",
+        TimeUnit::Millis => "ms",
+        TimeUnit::Micros => "us",
+        TimeUnit::Nanos => "ns",
+    }
+}
+
+fn parse_unit(name: &str) -> Option<TimeUnit> {
+    [TimeUnit::Seconds, TimeUnit::Millis, TimeUnit::Micros, TimeUnit::Nanos].into_iter().find(|&u| unit_name(u) == name)
+}
+
+/// Writes `contents` to `path` through a temporary file in the same directory.
+fn replace_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// A chunk as listed in a series manifest.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChunkRef {
+    pub hash: ChunkHash,
+    /// Timestamps of the first and last point.
+    pub first: i64,
+    pub last: i64,
+    pub points: usize,
+    pub summary: ChunkSummary,
+}
+
+/// The chunks of a stored series, in time order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Manifest {
+    pub chunk_size: usize,
+    pub unit: Option<TimeUnit>,
+    pub chunks: Vec<ChunkRef>,
+}
+
+impl Manifest {
+    fn to_text(&self) -> String {
+        let mut text = format!("chunk_size {}\n", self.chunk_size);
+        if let Some(unit) = self.unit {
+            writeln!(text, "unit {}", unit_name(unit)).expect("writing to a string");
+        }
+        for c in &self.chunks {
+            let s = &c.summary;
+            writeln!(
+                text,
+                "{} {} {} {} {} {} {} {}",
+                hex(&c.hash), c.first, c.last, c.points, s.count, s.sum, s.min, s.max
+            )
+            .expect("writing to a string");
+        }
+        text
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        let chunk_size = lines.next()?.strip_prefix("chunk_size ")?.parse().ok()?;
+        let mut manifest = Manifest { chunk_size, unit: None, chunks: Vec::new() };
+        for line in lines {
+            if let Some(unit) = line.strip_prefix("unit ") {
+                manifest.unit = Some(parse_unit(unit)?);
+                continue;
+            }
+            let fields: Vec<&str> = line.split(' ').collect();
+            let [hash, first, last, points, count, sum, min, max] = fields[..] else { return None };
+            manifest.chunks.push(ChunkRef {
+                hash: parse_hex(hash)?,
+                first: first.parse().ok()?,
+                last: last.parse().ok()?,
+                points: points.parse().ok()?,
+                summary: ChunkSummary {
+                    count: count.parse().ok()?,
+                    sum: sum.parse().ok()?,
+                    min: min.parse().ok()?,
+                    max: max.parse().ok()?,
+                },
+            });
+        }
+        Some(manifest)
+    }
+
+    /// Number of points of the series.
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(|c| c.points).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+
+/// Space taken by a store, from [`ChunkStore::usage`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StoreUsage {
+    /// Chunk files on disk and their total size.
+    pub chunks: usize,
+    pub bytes: u64,
+    /// Size of all series' chunks as if each series stored its own copies.
+    pub logical_bytes: u64,
+}
+
+impl StoreUsage {
+    /// How many times smaller the chunks are on disk than without deduplication; 1 for
+    /// an empty store.
+    pub fn dedup_ratio(&self) -> f64 {
+        if self.bytes == 0 {
+            1.
+        } else {
+            self.logical_bytes as f64 / self.bytes as f64
+        }
+    }
+}
+
+/// Chunks removed by [`ChunkStore::gc`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GcReport {
+    pub removed: usize,
+    pub bytes: u64,
+}
+
+/// A directory of deduplicated chunked series, stored by name. Any non-empty name can be
+/// used.
+#[derive(Clone, Debug)]
+pub struct ChunkStore {
+    root: PathBuf,
+    #[cfg(feature = "encryption")]
+    encryption: Option<Encryption>,
+}
+
+impl ChunkStore {
+    /// Opens the store in `root`, creating the directories if needed.
+    pub fn open<P: AsRef<Path>>(root: P) -> io::Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        std::fs::create_dir_all(root.join(CHUNK_DIR))?;
+        std::fs::create_dir_all(root.join(SERIES_DIR))?;
+        Ok(Self {
+            root,
+            #[cfg(feature = "encryption")]
+            encryption: None,
+        })
+    }
+
+    /// Seals the chunks and manifests written from now on, and requires the ones read to
+    /// be sealed, so that a plain file put in place of a sealed one is rejected. Plain
+    /// chunks already in the store are not reused; series saved before have to be saved
+    /// again to be readable.
+    #[cfg(feature = "encryption")]
+    pub fn with_encryption(self, encryption: Encryption) -> Self {
+        Self { encryption: Some(encryption), ..self }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn chunk_path(&self, hash: &ChunkHash) -> PathBuf {
+        let path = self.root.join(CHUNK_DIR).join(hex(hash));
+        #[cfg(feature = "encryption")]
+        if self.encryption.is_some() {
+            return path.with_extension(SEALED_EXTENSION);
+        }
+        path
+    }
+
+    /// The name of a chunk's file: the hash of its bytes, keyed if the store is encrypted.
+    fn chunk_hash(&self, bytes: &[u8]) -> io::Result<ChunkHash> {
+        #[cfg(feature = "encryption")]
+        if let Some(encryption) = &self.encryption {
+            return encryption.mac(bytes);
+        }
+        Ok(sha256(bytes))
+    }
+
+    /// Writes `contents` to `path`, sealed with `context` if the store is encrypted.
+    #[cfg_attr(not(feature = "encryption"), allow(unused_variables))]
+    fn write_file(&self, path: &Path, context: &str, contents: &[u8]) -> io::Result<()> {
+        #[cfg(feature = "encryption")]
+        if let Some(encryption) = &self.encryption {
+            return replace_file(path, &encryption.seal(contents, context.as_bytes())?);
+        }
+        replace_file(path, contents)
+    }
+
+    #[cfg_attr(not(feature = "encryption"), allow(unused_variables))]
+    fn read_file(&self, path: &Path, context: &str) -> io::Result<Vec<u8>> {
+        let bytes = std::fs::read(path)?;
+        #[cfg(feature = "encryption")]
+        if let Some(encryption) = &self.encryption {
+            return encryption.open(&bytes, context.as_bytes());
+        }
+        Ok(bytes)
+    }
+
+    /// Fails for the empty name.
+    fn manifest_path(&self, name: &str) -> io::Result<PathBuf> {
+        Ok(self.root.join(SERIES_DIR).join(format!("{}.manifest", encode_file_name(name)?)))
+    }
+
+    /// Stores `series` as `name`, replacing any previous version, and returns the number
+    /// of chunks that were not already in the store. The chunks of the previous version
+    /// stay on disk until [`ChunkStore::gc`].
+    ///
+    /// Fails for the empty name, before anything is written.
+    pub fn save(&self, name: &str, series: &ChunkedSeries) -> io::Result<usize> {
+        self.save_from(name, series, i64::MIN)
+    }
+
+    /// [`ChunkStore::save`] without the chunks whose last point is earlier than `from`.
+    pub(crate) fn save_from(&self, name: &str, series: &ChunkedSeries, from: i64) -> io::Result<usize> {
+        let manifest_path = self.manifest_path(name)?;
+        let mut written = 0;
+        let mut chunks = Vec::with_capacity(series.chunk_count());
+        let kept = series.chunk_points().zip(series.chunk_summaries()).filter(|((t, _), _)| t[t.len() - 1] >= from);
+        for ((timestamps, values), summary) in kept {
+            let bytes = encode_chunk(timestamps, values);
+            let hash = self.chunk_hash(&bytes)?;
+            let path = self.chunk_path(&hash);
+            if !path.exists() {
+                self.write_file(&path, &format!("{}/{}", CHUNK_DIR, hex(&hash)), &bytes)?;
+                written += 1;
+            }
+            chunks.push(ChunkRef {
+                hash,
+                first: timestamps[0],
+                last: timestamps[timestamps.len() - 1],
+                points: timestamps.len(),
+                summary: *summary,
+            });
+        }
+        let manifest = Manifest { chunk_size: series.chunk_size(), unit: series.unit(), chunks };
+        self.write_manifest(&manifest_path, name, &manifest)?;
+        Ok(written)
+    }
+
+    fn write_manifest(&self, path: &Path, name: &str, manifest: &Manifest) -> io::Result<()> {
+        self.write_file(path, &format!("{}/{}", SERIES_DIR, name), manifest.to_text().as_bytes())
+    }
+
+    /// Drops from `name` the chunks whose last point is earlier than `timestamp`, e.g. to
+    /// apply a retention period a chunk at a time, and returns how many points they held.
+    /// Their files stay on disk until [`ChunkStore::gc`].
+    ///
+    /// Fails if there is no such series or its manifest is malformed.
+    pub fn drop_chunks_before(&self, name: &str, timestamp: i64) -> io::Result<usize> {
+        let mut manifest = self.manifest(name)?;
+        let expired = manifest.chunks.partition_point(|c| c.last < timestamp);
+        if expired == 0 {
+            return Ok(0);
+        }
+        let dropped = manifest.chunks.drain(..expired).map(|c| c.points).sum();
+        self.write_manifest(&self.manifest_path(name)?, name, &manifest)?;
+        Ok(dropped)
+    }
+
+    /// The manifest of `name`, without reading its chunks.
+    ///
+    /// Fails if there is no such series or its manifest is malformed.
+    pub fn manifest(&self, name: &str) -> io::Result<Manifest> {
+        let bytes = self.read_file(&self.manifest_path(name)?, &format!("{}/{}", SERIES_DIR, name))?;
+        let text = String::from_utf8(bytes).map_err(|_| invalid_data(format!("malformed manifest of `{}`", name)))?;
+        Manifest::parse(&text).ok_or_else(|| invalid_data(format!("malformed manifest of `{}`", name)))
+    }
+
+    /// The points of one chunk, checked against its hash, or in an encrypted store
+    /// authenticated under its name, which may have been keyed with an older key.
+    ///
+    /// Fails if the chunk is missing or its contents do not match the hash.
+    pub fn read_chunk(&self, hash: ChunkHash) -> io::Result<Vec<(i64, f64)>> {
+        let bytes = self.read_file(&self.chunk_path(&hash), &format!("{}/{}", CHUNK_DIR, hex(&hash)))?;
+        #[cfg(feature = "encryption")]
+        let sealed = self.encryption.is_some();
+        #[cfg(not(feature = "encryption"))]
+        let sealed = false;
+        if !sealed && sha256(&bytes) != hash {
+            return Err(invalid_data(format!("chunk {} is corrupt", hex(&hash))));
+        }
+        decode_chunk(&bytes).ok_or_else(|| invalid_data(format!("chunk {} is malformed", hex(&hash))))
+    }
+
+    /// Reads `name` back.
+    ///
+    /// Fails if there is no such series or one of its files is missing or corrupt.
+    pub fn load(&self, name: &str) -> io::Result<ChunkedSeries> {
+        let manifest = self.manifest(name)?;
+        let mut series = ChunkedSeries::new(manifest.chunk_size);
+        for chunk in &manifest.chunks {
+            for (t, v) in self.read_chunk(chunk.hash)? {
+                series.push(t, v);
+            }
+        }
+        Ok(match manifest.unit {
+            Some(unit) => series.with_unit(unit),
+            None => series,
+        })
+    }
+
+    /// Stores `contents` under `key` beside the series, e.g. settings of the code using the
+    /// store, sealed like the other files if the store is encrypted. Keys are encoded like
+    /// series names.
+    ///
+    /// Fails for the empty key.
+    pub fn save_metadata(&self, key: &str, contents: &[u8]) -> io::Result<()> {
+        let path = self.metadata_path(key)?;
+        std::fs::create_dir_all(self.root.join(METADATA_DIR))?;
+        self.write_file(&path, &format!("{}/{}", METADATA_DIR, key), contents)
+    }
+
+    fn metadata_path(&self, key: &str) -> io::Result<PathBuf> {
+        Ok(self.root.join(METADATA_DIR).join(encode_file_name(key)?))
+    }
+
+    /// The contents saved under `key`, or `None` if there are none.
+    ///
+    /// Fails if the file cannot be read or, in an encrypted store, opened.
+    pub fn load_metadata(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        match self.read_file(&self.metadata_path(key)?, &format!("{}/{}", METADATA_DIR, key)) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            result => result.map(Some),
+        }
+    }
+
+    /// Deletes the manifest of `name`; its chunks are reclaimed by [`ChunkStore::gc`].
+    pub fn remove(&self, name: &str) -> io::Result<()> {
+        std::fs::remove_file(self.manifest_path(name)?)
+    }
+
+    /// The names of the stored series, sorted.
+    pub fn names(&self) -> io::Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(self.root.join(SERIES_DIR))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "manifest") {
+                if let Some(name) = path.file_stem().and_then(|s| s.to_str()).and_then(decode_file_name) {
+                    names.push(name);
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// The hashes of every chunk a manifest refers to.
+    fn referenced(&self) -> io::Result<BTreeSet<ChunkHash>> {
+        let mut referenced = BTreeSet::new();
+        for name in self.names()? {
+            referenced.extend(self.manifest(&name)?.chunks.iter().map(|c| c.hash));
+        }
+        Ok(referenced)
+    }
+
+    /// The chunk files on disk, plain or sealed, with their hash if the name is one.
+    fn chunk_files(&self) -> io::Result<Vec<(PathBuf, Option<ChunkHash>, u64)>> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(self.root.join(CHUNK_DIR))? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            #[cfg(feature = "encryption")]
+            let name = name.strip_suffix(SEALED_EXTENSION).and_then(|n| n.strip_suffix('.')).unwrap_or(name);
+            let hash = parse_hex(name);
+            files.push((path, hash, entry.metadata()?.len()));
+        }
+        Ok(files)
+    }
+
+    pub fn usage(&self) -> io::Result<StoreUsage> {
+        let files = self.chunk_files()?;
+        let mut usage = StoreUsage {
+            chunks: files.iter().filter(|(_, hash, _)| hash.is_some()).count(),
+            bytes: files.iter().filter(|(_, hash, _)| hash.is_some()).map(|(_, _, len)| len).sum(),
+            logical_bytes: 0,
+        };
+        for name in self.names()? {
+            usage.logical_bytes += self.manifest(&name)?.chunks.iter().map(|c| 8 + 16 * c.points as u64).sum::<u64>();
+        }
+        Ok(usage)
+    }
+
+    /// Deletes the chunks no series refers to, and temporary files left by interrupted
+    /// saves.
+    ///
+    /// Fails if a manifest cannot be read, before anything is deleted.
+    pub fn gc(&self) -> io::Result<GcReport> {
+        let referenced = self.referenced()?;
+        let mut report = GcReport::default();
+        for (path, hash, len) in self.chunk_files()? {
+            if hash.is_none_or(|h| !referenced.contains(&h)) {
+                std::fs::remove_file(path)?;
+                report.removed += 1;
+                report.bytes += len;
+            }
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,197 @@
+//! Timestamped series stored in fixed-size chunks, each carrying a summary of its values.
+//!
+//! Aggregations over the whole history combine the chunk summaries without touching the
+//! points, and range queries scan only the chunks cut by the ends of the range, so both
+//! cost about one step per chunk rather than per point. NaN values are stored but left
+//! out of every summary.
+
+use crate::{TimeUnit, TimestampedSeries};
+
+/// Count, sum and extremes of the non-NaN values of a chunk or range.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChunkSummary {
+    pub count: usize,
+    pub sum: f64,
+    /// NaN when `count` is zero, as is `max`.
+    pub min: f64,
+    pub max: f64,
+}
+
+impl ChunkSummary {
+    pub(crate) fn empty() -> Self {
+        Self { count: 0, sum: 0., min: f64::NAN, max: f64::NAN }
+    }
+
+    pub(crate) fn add(&mut self, value: f64) {
+        if !value.is_nan() {
+            self.count += 1;
+            self.sum += value;
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+    }
+
+    pub(crate) fn merge(&mut self, other: &Self) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// NaN when `count` is zero.
+    pub fn mean(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Chunk {
+    timestamps: Vec<i64>,
+    values: Vec<f64>,
+    summary: ChunkSummary,
+}
+
+impl Chunk {
+    fn first(&self) -> i64 {
+        self.timestamps[0]
+    }
+
+    fn last(&self) -> i64 {
+        self.timestamps[self.timestamps.len() - 1]
+    }
+}
+
+/// A `f64` series in chunks of `chunk_size` points, for long histories that are
+/// aggregated more often than they are read point by point.
+#[derive(Clone, Debug)]
+pub struct ChunkedSeries {
+    chunk_size: usize,
+    chunks: Vec<Chunk>,
+    unit: Option<TimeUnit>,
+}
+
+impl ChunkedSeries {
+    /// # Panics
+    /// Panics if `chunk_size` is zero.
+    pub fn new(chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        Self { chunk_size, chunks: Vec::new(), unit: None }
+    }
+
+    /// # Panics
+    /// Panics if `chunk_size` is zero.
+    pub fn from_series(series: &TimestampedSeries<f64>, chunk_size: usize) -> Self {
+        let mut out = Self::new(chunk_size);
+        for (t, &v) in series.iter() {
+            out.push(t, v);
+        }
+        out.unit = series.unit();
+        out
+    }
+
+    pub fn with_unit(self, unit: TimeUnit) -> Self {
+        Self { unit: Some(unit), ..self }
+    }
+
+    pub fn unit(&self) -> Option<TimeUnit> {
+        self.unit
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// # Panics
+    /// Panics if `timestamp` is earlier than the last one pushed.
+    pub fn push(&mut self, timestamp: i64, value: f64) {
+        if let Some(last) = self.chunks.last() {
+            assert!(timestamp >= last.last(), "timestamps must be pushed in order");
+        }
+        if self.chunks.last().is_none_or(|c| c.timestamps.len() == self.chunk_size) {
+            self.chunks.push(Chunk {
+                timestamps: Vec::with_capacity(self.chunk_size),
+                values: Vec::with_capacity(self.chunk_size),
+                summary: ChunkSummary::empty(),
+            });
+        }
+        let chunk = self.chunks.last_mut().expect("a chunk was just ensured");
+        chunk.timestamps.push(timestamp);
+        chunk.values.push(value);
+        chunk.summary.add(value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(|c| c.timestamps.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// The summary of each chunk, in time order.
+    pub fn chunk_summaries(&self) -> impl Iterator<Item = &ChunkSummary> {
+        self.chunks.iter().map(|c| &c.summary)
+    }
+
+    /// The timestamps and values of each chunk, in time order.
+    pub(crate) fn chunk_points(&self) -> impl Iterator<Item = (&[i64], &[f64])> {
+        self.chunks.iter().map(|c| (c.timestamps.as_slice(), c.values.as_slice()))
+    }
+
+    /// The summary of the whole series, from the chunk summaries alone.
+    pub fn summary(&self) -> ChunkSummary {
+        self.chunks.iter().fold(ChunkSummary::empty(), |mut acc, c| {
+            acc.merge(&c.summary);
+            acc
+        })
+    }
+
+    /// The summary of the points in `[start, end)`. Chunks inside the range contribute
+    /// their summaries; only the chunks at its ends are scanned.
+    pub fn summary_between(&self, start: i64, end: i64) -> ChunkSummary {
+        let mut acc = ChunkSummary::empty();
+        let first = self.chunks.partition_point(|c| c.last() < start);
+        for chunk in self.chunks[first..].iter().take_while(|c| c.first() < end) {
+            if chunk.first() >= start && chunk.last() < end {
+                acc.merge(&chunk.summary);
+            } else {
+                let from = chunk.timestamps.partition_point(|&t| t < start);
+                let to = chunk.timestamps.partition_point(|&t| t < end);
+                chunk.values[from..to].iter().for_each(|&v| acc.add(v));
+            }
+        }
+        acc
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.summary().sum
+    }
+
+    pub fn min(&self) -> f64 {
+        self.summary().min
+    }
+
+    pub fn max(&self) -> f64 {
+        self.summary().max
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.summary().mean()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (i64, f64)> + '_ {
+        self.chunks.iter().flat_map(|c| c.timestamps.iter().copied().zip(c.values.iter().copied()))
+    }
+
+    pub fn to_series(&self) -> TimestampedSeries<f64> {
+        let out: TimestampedSeries<f64> = self.iter().collect();
+        match self.unit {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+}
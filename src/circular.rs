@@ -0,0 +1,236 @@
+//! Angles and orientations, for which ordinary arithmetic is wrong: the mean of 359° and
+//! 1° is 0°, not 180°.
+//!
+//! [`Circular`] gives statistics of angles in radians and unwrapping of phase series.
+//! [`Quaternion`] orientations are interpolated by slerp, the constant-speed rotation
+//! between two orientations, which is what resampling an orientation series needs.
+
+use crate::{TimeSeries, TimestampedSeries};
+use std::f64::consts::{PI, TAU};
+use std::ops::Mul;
+
+/// Statistics of angles in radians. Undefined results are NaN.
+pub trait Circular {
+    /// Direction of the mean resultant vector, in `(-π, π]`.
+    fn circular_mean(&self) -> f64;
+    /// Length of the mean resultant vector, from 0 (spread out) to 1 (all equal).
+    fn mean_resultant_length(&self) -> f64;
+    /// `1 - R`, where `R` is the [mean resultant length](Circular::mean_resultant_length).
+    fn circular_variance(&self) -> f64;
+    /// `sqrt(-2 ln R)`, which approaches the ordinary standard deviation for
+    /// concentrated angles.
+    fn circular_std(&self) -> f64;
+    /// Every angle wrapped into `(-π, π]`.
+    fn wrap_angles(&self) -> TimeSeries<f64>;
+    /// Removes the jumps of `2π` from a wrapped phase by adding multiples of `2π`
+    /// wherever consecutive angles differ by more than `π`.
+    fn unwrap_phase(&self) -> TimeSeries<f64>;
+}
+
+fn resultant(angles: &[f64]) -> (f64, f64) {
+    let n = angles.len() as f64;
+    let (s, c) = angles.iter().fold((0., 0.), |(s, c), a| (s + a.sin(), c + a.cos()));
+    (s / n, c / n)
+}
+
+/// Wraps `angle` into `(-π, π]`.
+pub fn wrap_angle(angle: f64) -> f64 {
+    let wrapped = (angle + PI).rem_euclid(TAU) - PI;
+    if wrapped == -PI {
+        PI
+    } else {
+        wrapped
+    }
+}
+
+impl Circular for TimeSeries<f64> {
+    fn circular_mean(&self) -> f64 {
+        if self.as_slice().is_empty() {
+            return f64::NAN;
+        }
+        let (s, c) = resultant(self.as_slice());
+        s.atan2(c)
+    }
+
+    fn mean_resultant_length(&self) -> f64 {
+        let (s, c) = resultant(self.as_slice());
+        s.hypot(c)
+    }
+
+    fn circular_variance(&self) -> f64 {
+        1. - self.mean_resultant_length()
+    }
+
+    fn circular_std(&self) -> f64 {
+        (-2. * self.mean_resultant_length().ln()).sqrt()
+    }
+
+    fn wrap_angles(&self) -> TimeSeries<f64> {
+        self.map(|&a| wrap_angle(a))
+    }
+
+    fn unwrap_phase(&self) -> TimeSeries<f64> {
+        let mut out = Vec::with_capacity(self.as_slice().len());
+        let mut offset = 0.;
+        for (i, &a) in self.as_slice().iter().enumerate() {
+            if i > 0 {
+                let step = a - self.as_slice()[i - 1];
+                offset += wrap_angle(step) - step;
+            }
+            out.push(a + offset);
+        }
+        TimeSeries(out)
+    }
+}
+
+/// A rotation as the unit quaternion `w + xi + yj + zk`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }
+    }
+
+    /// No rotation.
+    pub fn identity() -> Self {
+        Self::new(1., 0., 0., 0.)
+    }
+
+    /// The rotation by `angle` radians about `axis`, which need not be normalized.
+    pub fn from_axis_angle(axis: [f64; 3], angle: f64) -> Self {
+        let norm = axis.iter().map(|a| a * a).sum::<f64>().sqrt();
+        let (s, c) = (angle / 2.).sin_cos();
+        Self::new(c, s * axis[0] / norm, s * axis[1] / norm, s * axis[2] / norm)
+    }
+
+    pub fn norm(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalized(&self) -> Self {
+        let n = self.norm();
+        Self::new(self.w / n, self.x / n, self.y / n, self.z / n)
+    }
+
+    /// The inverse rotation, for a unit quaternion.
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    pub fn dot(&self, other: &Self) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// The angle in radians of the rotation taking `self` to `other`, in `[0, π]`.
+    pub fn angle_to(&self, other: &Self) -> f64 {
+        2. * self.dot(other).abs().min(1.).acos()
+    }
+
+    /// Spherical linear interpolation, `self` at `t = 0` and `other` at `t = 1`, along
+    /// the shorter of the two arcs.
+    pub fn slerp(&self, other: &Self, t: f64) -> Self {
+        let mut dot = self.dot(other);
+        // `q` and `-q` are the same rotation; flip one to take the shorter arc.
+        let other = if dot < 0. {
+            dot = -dot;
+            Self::new(-other.w, -other.x, -other.y, -other.z)
+        } else {
+            *other
+        };
+        let (a, b) = if dot > 0.9995 {
+            // Nearly equal: the arc is flat enough for linear interpolation.
+            (1. - t, t)
+        } else {
+            let theta = dot.acos();
+            let sin = theta.sin();
+            (((1. - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        Self::new(
+            a * self.w + b * other.w,
+            a * self.x + b * other.x,
+            a * self.y + b * other.y,
+            a * self.z + b * other.z,
+        )
+        .normalized()
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    /// The Hamilton product: the rotation `rhs` followed by `self`.
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+        )
+    }
+}
+
+impl TimestampedSeries<Quaternion> {
+    /// The orientation at `timestamp`, slerped between the neighbouring points, or
+    /// `None` outside the series.
+    pub fn slerp_at(&self, timestamp: i64) -> Option<Quaternion> {
+        let ts = self.timestamps();
+        let values = self.values().as_slice();
+        let i = ts.partition_point(|&t| t <= timestamp);
+        if i == 0 || (i == ts.len() && ts[i - 1] != timestamp) {
+            return None;
+        }
+        if ts[i - 1] == timestamp {
+            return Some(values[i - 1]);
+        }
+        let t = (timestamp - ts[i - 1]) as f64 / (ts[i] - ts[i - 1]) as f64;
+        Some(values[i - 1].slerp(&values[i], t))
+    }
+
+    /// Orientations every `step` from the first timestamp to the last, slerped between
+    /// the original points.
+    ///
+    /// # Panics
+    /// Panics if `step` is not positive.
+    pub fn resample_slerp(&self, step: i64) -> Self {
+        assert!(step > 0, "step must be positive");
+        let mut out = TimestampedSeries::new();
+        if let (Some(&first), Some(&last)) = (self.timestamps().first(), self.timestamps().last()) {
+            let mut t = first;
+            while t <= last {
+                out.push(t, self.slerp_at(t).expect("within the series"));
+                t = match t.checked_add(step) {
+                    Some(next) => next,
+                    None => break,
+                };
+            }
+        }
+        match self.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+}
+
+impl TimestampedSeries<f64> {
+    /// Angles in radians every `step` from the first timestamp to the last,
+    /// interpolated along the shorter arc and wrapped into `(-π, π]`.
+    ///
+    /// # Panics
+    /// Panics if `step` is not positive.
+    pub fn resample_angles(&self, step: i64) -> Self {
+        let rotations = self.map(|&a| Quaternion::from_axis_angle([0., 0., 1.], a));
+        rotations.resample_slerp(step).map(|q| wrap_angle(2. * q.z.atan2(q.w)))
+    }
+}
@@ -0,0 +1,97 @@
+//! Cohort retention: users grouped by the calendar period they were first seen in, and
+//! the share of each cohort active again in every later period.
+//!
+//! Events come as `(user, timestamp)` pairs in Unix seconds, as in a long event table,
+//! or as one series per user in a [`SeriesSet`]. Periods are calendar days, weeks,
+//! months, quarters or years.
+
+use crate::reshape::{pivot, LongRecord};
+use crate::{CalendarPeriod, SeriesSet, TimeSeriesFrame};
+use std::collections::{BTreeSet, HashMap};
+
+/// The retention matrix of [`cohort_retention`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CohortRetention {
+    /// Start of each cohort's period, oldest first.
+    pub cohorts: Vec<i64>,
+    /// Number of users first seen in each cohort.
+    pub sizes: Vec<usize>,
+    /// For each cohort, the users active `k` periods after it, for every `k` up to the
+    /// last period with events; the first entry is the cohort size.
+    pub active: Vec<Vec<usize>>,
+}
+
+impl CohortRetention {
+    /// Active users as a fraction of each cohort's size.
+    pub fn rates(&self) -> Vec<Vec<f64>> {
+        self.active
+            .iter()
+            .zip(&self.sizes)
+            .map(|(row, &size)| row.iter().map(|&a| a as f64 / size as f64).collect())
+            .collect()
+    }
+
+    /// The rates as a frame indexed by cohort start, with a `size` column and then one
+    /// column per period offset (`"0"`, `"1"`, ...). Offsets past the last period of a
+    /// cohort are NaN.
+    pub fn to_frame(&self) -> TimeSeriesFrame {
+        let records = self.cohorts.iter().zip(&self.sizes).zip(self.rates()).flat_map(|((&cohort, &size), rates)| {
+            let size = LongRecord { timestamp: cohort, key: "size".to_string(), value: size as f64 };
+            let rates = rates.into_iter().enumerate().map(move |(k, value)| LongRecord {
+                timestamp: cohort,
+                key: k.to_string(),
+                value,
+            });
+            std::iter::once(size).chain(rates)
+        });
+        pivot(records)
+    }
+}
+
+/// Builds the retention matrix of `events` by `period`. A user belongs to the cohort of
+/// their earliest event, and is active in every period with at least one event.
+pub fn cohort_retention<I, S>(events: I, period: CalendarPeriod) -> CohortRetention
+where
+    I: IntoIterator<Item = (S, i64)>,
+    S: Into<String>,
+{
+    let mut periods_by_user: HashMap<String, BTreeSet<i64>> = HashMap::new();
+    for (user, timestamp) in events {
+        periods_by_user.entry(user.into()).or_default().insert(period.floor(timestamp));
+    }
+    let (Some(&first), Some(&last)) = (
+        periods_by_user.values().filter_map(|p| p.first()).min(),
+        periods_by_user.values().filter_map(|p| p.last()).max(),
+    ) else {
+        return CohortRetention::default();
+    };
+    // Every period start from the first to the last, so offsets count empty periods too.
+    let starts: Vec<i64> =
+        (0..).map(|k| period.shift(first, k)).map(|t| period.floor(t)).take_while(|&t| t <= last).collect();
+    let position = |t: i64| starts.partition_point(|&s| s < t);
+    let mut active: Vec<Vec<usize>> = (0..starts.len()).map(|c| vec![0; starts.len() - c]).collect();
+    let mut sizes = vec![0; starts.len()];
+    for periods in periods_by_user.values() {
+        let cohort = position(*periods.first().expect("users have at least one event"));
+        sizes[cohort] += 1;
+        for &p in periods {
+            active[cohort][position(p) - cohort] += 1;
+        }
+    }
+    let keep: Vec<usize> = (0..starts.len()).filter(|&c| sizes[c] > 0).collect();
+    CohortRetention {
+        cohorts: keep.iter().map(|&c| starts[c]).collect(),
+        sizes: keep.iter().map(|&c| sizes[c]).collect(),
+        active: keep.iter().map(|&c| std::mem::take(&mut active[c])).collect(),
+    }
+}
+
+impl<T> SeriesSet<T> {
+    /// [`cohort_retention`] with one user per series, active at the series' timestamps.
+    pub fn cohort_retention(&self, period: CalendarPeriod) -> CohortRetention {
+        cohort_retention(
+            self.iter().flat_map(|(user, series)| series.timestamps().iter().map(move |&t| (user.as_str(), t))),
+            period,
+        )
+    }
+}
@@ -0,0 +1,169 @@
+//! Regularity and complexity measures of a series.
+
+use crate::{Error, Regression, TimeSeries};
+use std::collections::HashMap;
+
+/// Result of a detrended fluctuation analysis.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dfa {
+    /// Slope of `log F(s)` against `log s`: about 0.5 for white noise, 1 for 1/f noise and
+    /// 1.5 for a random walk.
+    pub exponent: f64,
+    /// Fluctuation `F(s)` for each usable box size `s`.
+    pub fluctuations: Vec<(usize, f64)>,
+}
+
+impl Dfa {
+    /// Roughly log-spaced box sizes from 4 points to a quarter of the series length.
+    pub fn default_box_sizes(len: usize) -> Vec<usize> {
+        let mut sizes = Vec::new();
+        let mut s = 4.;
+        while s <= len as f64 / 4. {
+            let size = s as usize;
+            if sizes.last() != Some(&size) {
+                sizes.push(size);
+            }
+            s *= 2f64.powf(0.25);
+        }
+        sizes
+    }
+}
+
+pub trait Complexity {
+    /// Sample entropy (Richman & Moorman): the negative log of the conditional probability
+    /// that runs matching for `m` points within tolerance `r` (Chebyshev distance, in the
+    /// units of the series; `0.2 * std` is customary) also match at the next point.
+    /// Self-matches are excluded. Infinite when no run extends, NaN when none match.
+    fn sample_entropy(&self, m: usize, r: f64) -> f64;
+    /// Approximate entropy (Pincus). Like sample entropy but counting self-matches, which
+    /// biases it towards regularity for short series.
+    fn approximate_entropy(&self, m: usize, r: f64) -> f64;
+    /// Permutation entropy (Bandt & Pompe) of the ordinal patterns of `order` points spaced
+    /// `delay` apart, normalised by `ln(order!)` to lie in `[0, 1]`.
+    fn permutation_entropy(&self, order: usize, delay: usize) -> f64;
+    /// Detrended fluctuation analysis (first order) over the given box sizes. Sizes below
+    /// 3 points or above the series length are skipped; at least two must remain.
+    fn dfa(&self, box_sizes: &[usize]) -> Result<Dfa, Error>;
+}
+
+/// Root mean square residual of a least-squares line through each full box of `profile`.
+fn fluctuation(profile: &[f64], size: usize) -> f64 {
+    let t = TimeSeries((0..size).map(|i| i as f64).collect());
+    let boxes = profile.len() / size;
+    let squares: f64 = profile
+        .chunks_exact(size)
+        .map(|chunk| {
+            let y = TimeSeries(chunk.to_vec());
+            let fit = y.linear_fit(&t).expect("box has at least three points");
+            chunk
+                .iter()
+                .enumerate()
+                .map(|(i, y)| (y - fit.intercept - fit.slope * i as f64).powi(2))
+                .sum::<f64>()
+        })
+        .sum();
+    (squares / (boxes * size) as f64).sqrt()
+}
+
+fn matches(x: &[f64], i: usize, j: usize, m: usize, r: f64) -> bool {
+    (0..m).all(|k| (x[i + k] - x[j + k]).abs() <= r)
+}
+
+fn phi(x: &[f64], m: usize, r: f64) -> f64 {
+    let count = x.len() - m + 1;
+    (0..count)
+        .map(|i| {
+            let c = (0..count).filter(|&j| matches(x, i, j, m, r)).count();
+            (c as f64 / count as f64).ln()
+        })
+        .sum::<f64>()
+        / count as f64
+}
+
+impl Complexity for TimeSeries<f64> {
+    fn sample_entropy(&self, m: usize, r: f64) -> f64 {
+        let x = self.as_slice();
+        assert!(m > 0, "m must be positive");
+        if x.len() <= m + 1 {
+            return f64::NAN;
+        }
+        // Both counts use the same n - m templates so that they are comparable.
+        let templates = x.len() - m;
+        let (mut b, mut a) = (0usize, 0usize);
+        for i in 0..templates {
+            for j in i + 1..templates {
+                if matches(x, i, j, m, r) {
+                    b += 1;
+                    if (x[i + m] - x[j + m]).abs() <= r {
+                        a += 1;
+                    }
+                }
+            }
+        }
+        if b == 0 {
+            return f64::NAN;
+        }
+        -(a as f64 / b as f64).ln()
+    }
+
+    fn approximate_entropy(&self, m: usize, r: f64) -> f64 {
+        assert!(m > 0, "m must be positive");
+        if self.as_slice().len() <= m + 1 {
+            return f64::NAN;
+        }
+        phi(self.as_slice(), m, r) - phi(self.as_slice(), m + 1, r)
+    }
+
+    fn permutation_entropy(&self, order: usize, delay: usize) -> f64 {
+        assert!(order >= 2, "order must be at least 2");
+        assert!(delay > 0, "delay must be positive");
+        let x = self.as_slice();
+        let span = (order - 1) * delay;
+        if x.len() <= span {
+            return f64::NAN;
+        }
+        let mut counts: HashMap<Vec<usize>, usize> = HashMap::new();
+        for start in 0..x.len() - span {
+            let mut pattern: Vec<usize> = (0..order).collect();
+            // Stable sort, so ties are ranked by position.
+            pattern.sort_by(|&a, &b| x[start + a * delay].total_cmp(&x[start + b * delay]));
+            *counts.entry(pattern).or_insert(0) += 1;
+        }
+        let total = (x.len() - span) as f64;
+        let entropy: f64 = counts
+            .values()
+            .map(|&c| {
+                let p = c as f64 / total;
+                -p * p.ln()
+            })
+            .sum();
+        let max_entropy: f64 = (2..=order).map(|k| (k as f64).ln()).sum();
+        entropy / max_entropy
+    }
+
+    fn dfa(&self, box_sizes: &[usize]) -> Result<Dfa, Error> {
+        let n = self.len();
+        let mean = self.as_slice().iter().sum::<f64>() / n as f64;
+        let profile: Vec<f64> = self
+            .as_slice()
+            .iter()
+            .scan(0., |acc, x| {
+                *acc += x - mean;
+                Some(*acc)
+            })
+            .collect();
+        let fluctuations: Vec<(usize, f64)> = box_sizes
+            .iter()
+            .filter(|&&s| s >= 3 && s <= n)
+            .map(|&s| (s, fluctuation(&profile, s)))
+            .filter(|(_, f)| *f > 0.)
+            .collect();
+        if fluctuations.len() < 2 {
+            return Err(Error::InsufficientData { required: 2, found: fluctuations.len() });
+        }
+        let log_s = TimeSeries(fluctuations.iter().map(|(s, _)| (*s as f64).ln()).collect());
+        let log_f = TimeSeries(fluctuations.iter().map(|(_, f)| f.ln()).collect());
+        let exponent = log_f.linear_fit(&log_s).map_or(f64::NAN, |fit| fit.slope);
+        Ok(Dfa { exponent, fluctuations })
+    }
+}
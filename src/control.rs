@@ -0,0 +1,121 @@
+//! Statistical process control charts.
+
+use crate::stats::quantile_sorted;
+use crate::{Statistics, TimeSeries};
+
+/// Tabular CUSUM chart.
+#[derive(Clone, Debug)]
+pub struct CusumChart {
+    /// Upper cumulative sum `C+`, in standard deviations.
+    pub upper: TimeSeries<f64>,
+    /// Lower cumulative sum `C-`, in standard deviations (non-negative).
+    pub lower: TimeSeries<f64>,
+    /// Points where either sum exceeded the decision interval.
+    pub alarms: Vec<usize>,
+}
+
+/// EWMA chart with exact (time-varying) control limits.
+#[derive(Clone, Debug)]
+pub struct EwmaChart {
+    pub ewma: TimeSeries<f64>,
+    pub upper_limit: TimeSeries<f64>,
+    pub lower_limit: TimeSeries<f64>,
+    /// Points where the EWMA left the control limits.
+    pub alarms: Vec<usize>,
+}
+
+/// In-control process mean and standard deviation the charts measure deviations from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InControl {
+    pub mean: f64,
+    pub std: f64,
+}
+
+impl InControl {
+    /// Sample mean and standard deviation of a reference (phase I) period.
+    pub fn estimate(reference: &TimeSeries<f64>) -> Self {
+        Self { mean: reference.mean(), std: reference.std() }
+    }
+
+    /// Median and scaled median absolute deviation, which a shift affecting a minority of
+    /// the points barely moves.
+    pub fn robust(reference: &TimeSeries<f64>) -> Self {
+        let mut sorted = reference.as_slice().to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let median = quantile_sorted(&sorted, 0.5);
+        let mut deviations: Vec<f64> = sorted.iter().map(|v| (v - median).abs()).collect();
+        deviations.sort_by(|a, b| a.total_cmp(b));
+        Self { mean: median, std: 1.4826 * quantile_sorted(&deviations, 0.5) }
+    }
+}
+
+/// Charts without an explicit baseline estimate it from the whole series with
+/// [`InControl::robust`]; pass a baseline from a known-good period when one exists.
+pub trait ControlChart {
+    /// Tabular CUSUM with allowance `k` and decision interval `h`, both in standard
+    /// deviations (`k = 0.5`, `h = 4` or `5` detect one-sigma shifts). Both sums restart
+    /// from zero after an alarm, so a persisting shift keeps raising alarms every few
+    /// points.
+    fn cusum(&self, k: f64, h: f64) -> CusumChart;
+    fn cusum_against(&self, baseline: &InControl, k: f64, h: f64) -> CusumChart;
+    /// EWMA chart with smoothing `lambda` in `(0, 1]` and limits at `l` standard
+    /// deviations of the EWMA (`lambda = 0.2`, `l = 3` is customary).
+    fn ewma_chart(&self, lambda: f64, l: f64) -> EwmaChart;
+    fn ewma_chart_against(&self, baseline: &InControl, lambda: f64, l: f64) -> EwmaChart;
+}
+
+impl ControlChart for TimeSeries<f64> {
+    fn cusum(&self, k: f64, h: f64) -> CusumChart {
+        self.cusum_against(&InControl::robust(self), k, h)
+    }
+
+    fn cusum_against(&self, baseline: &InControl, k: f64, h: f64) -> CusumChart {
+        let InControl { mean, std } = *baseline;
+        let (mut hi, mut lo) = (0f64, 0f64);
+        let mut upper = Vec::with_capacity(self.len());
+        let mut lower = Vec::with_capacity(self.len());
+        let mut alarms = Vec::new();
+        for (i, x) in self.as_slice().iter().enumerate() {
+            let z = (x - mean) / std;
+            hi = (hi + z - k).max(0.);
+            lo = (lo - z - k).max(0.);
+            upper.push(hi);
+            lower.push(lo);
+            if hi > h || lo > h {
+                alarms.push(i);
+                hi = 0.;
+                lo = 0.;
+            }
+        }
+        CusumChart { upper: TimeSeries(upper), lower: TimeSeries(lower), alarms }
+    }
+
+    fn ewma_chart(&self, lambda: f64, l: f64) -> EwmaChart {
+        self.ewma_chart_against(&InControl::robust(self), lambda, l)
+    }
+
+    fn ewma_chart_against(&self, baseline: &InControl, lambda: f64, l: f64) -> EwmaChart {
+        assert!(lambda > 0. && lambda <= 1., "lambda must be in (0, 1]");
+        let InControl { mean, std } = *baseline;
+        let mut z = mean;
+        let mut decay = 1.;
+        let mut chart = EwmaChart {
+            ewma: TimeSeries::new(),
+            upper_limit: TimeSeries::new(),
+            lower_limit: TimeSeries::new(),
+            alarms: Vec::new(),
+        };
+        for (i, x) in self.as_slice().iter().enumerate() {
+            z = lambda * x + (1. - lambda) * z;
+            decay *= (1. - lambda) * (1. - lambda);
+            let width = l * std * (lambda / (2. - lambda) * (1. - decay)).sqrt();
+            chart.ewma.push(z);
+            chart.upper_limit.push(mean + width);
+            chart.lower_limit.push(mean - width);
+            if (z - mean).abs() > width {
+                chart.alarms.push(i);
+            }
+        }
+        chart
+    }
+}
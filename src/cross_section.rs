@@ -0,0 +1,63 @@
+//! Cross-sectional reductions across the columns of a frame at each time step, as used in
+//! factor research to compare instruments against each other. NaN entries are ignored.
+
+use crate::{TimeSeries, TimeSeriesFrame};
+
+pub trait CrossSectional {
+    /// Mean across columns at each row.
+    fn cs_mean(&self) -> TimeSeries<f64>;
+    /// Sample standard deviation across columns at each row.
+    fn cs_std(&self) -> TimeSeries<f64>;
+    /// Rank (1 = smallest, ties averaged) of each value within its row.
+    fn cs_rank(&self) -> TimeSeriesFrame;
+    /// Each value standardized by its row's cross-sectional mean and standard deviation.
+    fn cs_zscore(&self) -> TimeSeriesFrame;
+}
+
+fn mean_std(row: &[f64]) -> (f64, f64) {
+    let valid: Vec<f64> = row.iter().copied().filter(|x| !x.is_nan()).collect();
+    let n = valid.len() as f64;
+    let mean = valid.iter().sum::<f64>() / n;
+    let var = valid.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.);
+    (mean, var.sqrt())
+}
+
+fn rank(row: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..row.len()).filter(|&i| !row[i].is_nan()).collect();
+    order.sort_by(|&a, &b| row[a].total_cmp(&row[b]));
+    let mut ranks = vec![f64::NAN; row.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && row[order[end]] == row[order[start]] {
+            end += 1;
+        }
+        let average = (start + end + 1) as f64 / 2.;
+        for &i in &order[start..end] {
+            ranks[i] = average;
+        }
+        start = end;
+    }
+    ranks
+}
+
+impl CrossSectional for TimeSeriesFrame {
+    fn cs_mean(&self) -> TimeSeries<f64> {
+        (0..self.len()).map(|i| mean_std(&self.row(i)).0).collect()
+    }
+
+    fn cs_std(&self) -> TimeSeries<f64> {
+        (0..self.len()).map(|i| mean_std(&self.row(i)).1).collect()
+    }
+
+    fn cs_rank(&self) -> TimeSeriesFrame {
+        self.map_rows(rank)
+    }
+
+    fn cs_zscore(&self) -> TimeSeriesFrame {
+        self.map_rows(|row| {
+            let (mean, std) = mean_std(row);
+            row.iter().map(|x| (x - mean) / std).collect()
+        })
+    }
+}
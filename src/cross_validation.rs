@@ -0,0 +1,62 @@
+//! Cross-validation folds for labelled series whose labels span several points, with
+//! purging and embargo (López de Prado) to keep label overlap from leaking into training.
+
+use std::ops::Range;
+
+/// One fold: training indices and a contiguous test block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fold {
+    pub train: Vec<usize>,
+    pub test: Range<usize>,
+}
+
+/// K contiguous test blocks; the training set is everything else, minus purged and
+/// embargoed samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PurgedKFold {
+    pub n_splits: usize,
+    /// Samples right after each test block dropped from training, covering serial
+    /// correlation that outlives the labels themselves.
+    pub embargo: usize,
+}
+
+impl PurgedKFold {
+    pub fn new(n_splits: usize) -> Self {
+        assert!(n_splits >= 2, "need at least two splits");
+        Self { n_splits, embargo: 0 }
+    }
+
+    pub fn with_embargo(self, embargo: usize) -> Self {
+        Self { embargo, ..self }
+    }
+
+    /// Folds for samples whose label at index `i` is determined by points `i..=label_ends[i]`.
+    /// Training samples whose label span overlaps the span of any test label are purged.
+    pub fn split(&self, label_ends: &[usize]) -> Vec<Fold> {
+        let n = label_ends.len();
+        (0..self.n_splits)
+            .filter_map(|k| {
+                let test = k * n / self.n_splits..(k + 1) * n / self.n_splits;
+                if test.is_empty() {
+                    return None;
+                }
+                let test_span_end = label_ends[test.clone()].iter().copied().max().unwrap_or(test.end - 1);
+                let embargo_end = test.end + self.embargo;
+                let train = (0..n)
+                    .filter(|&i| {
+                        let overlaps = i <= test_span_end && label_ends[i] >= test.start;
+                        let embargoed = i >= test.end && i < embargo_end;
+                        !test.contains(&i) && !overlaps && !embargoed
+                    })
+                    .collect();
+                Some(Fold { train, test })
+            })
+            .collect()
+    }
+
+    /// Folds for `len` samples whose labels all look `horizon` points ahead.
+    pub fn split_with_horizon(&self, len: usize, horizon: usize) -> Vec<Fold> {
+        let label_ends: Vec<usize> = (0..len).map(|i| (i + horizon).min(len.saturating_sub(1))).collect();
+        self.split(&label_ends)
+    }
+}
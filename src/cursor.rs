@@ -0,0 +1,89 @@
+//! Lockstep iteration over several timestamped series, for event-driven loops such as
+//! backtests and simulations over feeds that tick at different times.
+//!
+//! A [`SyncCursor`] visits the union of the series' timestamps in order. At each one it
+//! yields the latest value of every series at or before that time (hold-last), and which
+//! series ticked there. Feeds of different types can be mapped to one enum first.
+
+use crate::TimestampedSeries;
+
+/// One instant of a [`SyncCursor`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncStep<'a, T> {
+    pub timestamp: i64,
+    /// The latest value of each series, `None` before its first point.
+    pub values: Vec<Option<&'a T>>,
+    /// Whether each series has a point at `timestamp`.
+    pub updated: Vec<bool>,
+}
+
+impl<'a, T> SyncStep<'a, T> {
+    /// # Panics
+    /// Panics if `series` is out of range.
+    pub fn value(&self, series: usize) -> Option<&'a T> {
+        self.values[series]
+    }
+
+    /// # Panics
+    /// Panics if `series` is out of range.
+    pub fn is_updated(&self, series: usize) -> bool {
+        self.updated[series]
+    }
+
+    /// Whether every series has started.
+    pub fn is_complete(&self) -> bool {
+        self.values.iter().all(Option::is_some)
+    }
+}
+
+/// Iterates several series together by time, holding each one's last value.
+#[derive(Clone, Debug)]
+pub struct SyncCursor<'a, T> {
+    series: Vec<&'a TimestampedSeries<T>>,
+    /// Index of the next unread point of each series.
+    positions: Vec<usize>,
+}
+
+impl<'a, T> SyncCursor<'a, T> {
+    pub fn new<I>(series: I) -> Self
+    where
+        I: IntoIterator<Item = &'a TimestampedSeries<T>>,
+    {
+        let series: Vec<_> = series.into_iter().collect();
+        Self { positions: vec![0; series.len()], series }
+    }
+
+    /// The timestamp of the next step, if any.
+    pub fn peek_timestamp(&self) -> Option<i64> {
+        self.series.iter().zip(&self.positions).filter_map(|(s, &p)| s.timestamps().get(p).copied()).min()
+    }
+
+    /// Skips the steps before `timestamp` without yielding them. The values held by the
+    /// next step still reflect the skipped points.
+    pub fn skip_until(&mut self, timestamp: i64) {
+        for (s, p) in self.series.iter().zip(&mut self.positions) {
+            *p = (*p).max(s.timestamps().partition_point(|&t| t < timestamp));
+        }
+    }
+
+    fn held(&self) -> Vec<Option<&'a T>> {
+        self.series.iter().zip(&self.positions).map(|(s, &p)| p.checked_sub(1).map(|i| &s.values().as_slice()[i])).collect()
+    }
+}
+
+impl<'a, T> Iterator for SyncCursor<'a, T> {
+    type Item = SyncStep<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let timestamp = self.peek_timestamp()?;
+        let mut updated = vec![false; self.series.len()];
+        for ((s, p), u) in self.series.iter().zip(&mut self.positions).zip(&mut updated) {
+            // Several points at one timestamp collapse to the last of them.
+            while s.timestamps().get(*p) == Some(&timestamp) {
+                *p += 1;
+                *u = true;
+            }
+        }
+        Some(SyncStep { timestamp, values: self.held(), updated })
+    }
+}
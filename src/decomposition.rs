@@ -0,0 +1,222 @@
+//! Seasonal-trend decomposition using LOESS (STL), following Cleveland et al. (1990).
+//!
+//! With robustness iterations enabled, each outer pass down-weights points with large
+//! remainders (bisquare weights), so a handful of extreme values no longer distort the
+//! trend and seasonal estimates.
+
+use crate::TimeSeries;
+
+/// STL parameters. Window lengths are numbers of points and are rounded up to odd values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stl {
+    pub period: usize,
+    /// Span of the LOESS smoothing each cycle-subseries (at least 7 is recommended).
+    pub seasonal_window: usize,
+    /// Span of the trend LOESS; `None` picks the usual default from the period.
+    pub trend_window: Option<usize>,
+    /// Passes of the inner (seasonal/trend) loop.
+    pub inner_iterations: usize,
+    /// Robustness passes; 0 disables robustness weighting.
+    pub robust_iterations: usize,
+    /// Keep the final robustness weights in the result for inspection.
+    pub return_weights: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Decomposition {
+    pub trend: TimeSeries<f64>,
+    pub seasonal: TimeSeries<f64>,
+    pub remainder: TimeSeries<f64>,
+    /// Robustness weight of each point in `[0, 1]`, when requested.
+    pub weights: Option<TimeSeries<f64>>,
+}
+
+impl Stl {
+    /// Non-robust STL with two inner passes.
+    pub fn new(period: usize) -> Self {
+        assert!(period >= 2, "period must be at least 2");
+        Self {
+            period,
+            seasonal_window: 7,
+            trend_window: None,
+            inner_iterations: 2,
+            robust_iterations: 0,
+            return_weights: false,
+        }
+    }
+
+    /// Robust STL: one inner pass per outer pass and 15 robustness passes.
+    pub fn robust(self) -> Self {
+        Self { inner_iterations: 1, robust_iterations: 15, ..self }
+    }
+
+    pub fn with_weights(self) -> Self {
+        Self { return_weights: true, ..self }
+    }
+
+    pub fn decompose(&self, series: &TimeSeries<f64>) -> Decomposition {
+        let y = series.as_slice();
+        let n = y.len();
+        if n == 0 {
+            return Decomposition {
+                trend: TimeSeries::new(),
+                seasonal: TimeSeries::new(),
+                remainder: TimeSeries::new(),
+                weights: self.return_weights.then(TimeSeries::new),
+            };
+        }
+        let p = self.period;
+        let ns = odd(self.seasonal_window.max(3));
+        let nl = odd(p);
+        let nt = odd(self.trend_window.unwrap_or_else(|| {
+            (1.5 * p as f64 / (1. - 1.5 / ns as f64)).ceil() as usize
+        }));
+        let mut trend = vec![0.; n];
+        let mut seasonal = vec![0.; n];
+        let mut rho = vec![1.; n];
+        for outer in 0..=self.robust_iterations {
+            for _ in 0..self.inner_iterations.max(1) {
+                let detrended: Vec<f64> = y.iter().zip(&trend).map(|(y, t)| y - t).collect();
+                let cycle = smooth_subseries(&detrended, &rho, p, ns);
+                let low = low_pass(&cycle, p, nl);
+                seasonal = cycle[p..p + n].iter().zip(&low).map(|(c, l)| c - l).collect();
+                let deseasonalized: Vec<f64> = y.iter().zip(&seasonal).map(|(y, s)| y - s).collect();
+                trend = (0..n)
+                    .map(|i| loess(&deseasonalized, &rho, nt, i as f64).unwrap_or(deseasonalized[i]))
+                    .collect();
+            }
+            if outer < self.robust_iterations {
+                rho = robustness_weights(y, &trend, &seasonal);
+            }
+        }
+        let remainder = (0..n).map(|i| y[i] - trend[i] - seasonal[i]).collect();
+        Decomposition {
+            trend: TimeSeries(trend),
+            seasonal: TimeSeries(seasonal),
+            remainder: TimeSeries(remainder),
+            weights: self.return_weights.then_some(TimeSeries(rho)),
+        }
+    }
+}
+
+pub trait Decompose {
+    /// Decomposes the series into trend, seasonal and remainder with STL.
+    fn stl(&self, options: &Stl) -> Decomposition;
+}
+
+impl Decompose for TimeSeries<f64> {
+    fn stl(&self, options: &Stl) -> Decomposition {
+        options.decompose(self)
+    }
+}
+
+fn odd(x: usize) -> usize {
+    if x.is_multiple_of(2) {
+        x + 1
+    } else {
+        x
+    }
+}
+
+fn tricube(u: f64) -> f64 {
+    if u >= 1. {
+        0.
+    } else {
+        (1. - u.powi(3)).powi(3)
+    }
+}
+
+/// Local linear LOESS estimate at position `x` from points at positions `0..y.len()`,
+/// using the `span` nearest points and robustness weights `rho`.
+pub(crate) fn loess(y: &[f64], rho: &[f64], span: usize, x: f64) -> Option<f64> {
+    let n = y.len();
+    if n == 0 {
+        return None;
+    }
+    let q = span.min(n);
+    let mut lo = (x.round() as isize - (q / 2) as isize).clamp(0, (n - q) as isize) as usize;
+    // Slide the window to the q nearest neighbours of x.
+    while lo + q < n && x - lo as f64 > (lo + q) as f64 - x {
+        lo += 1;
+    }
+    while lo > 0 && (lo + q - 1) as f64 - x > x - (lo - 1) as f64 {
+        lo -= 1;
+    }
+    let mut h = (x - lo as f64).max((lo + q - 1) as f64 - x);
+    if span > n {
+        h += ((span - n) / 2) as f64;
+    }
+    let h = h.max(1e-12);
+    let (mut sw, mut swx, mut swy, mut swxx, mut swxy) = (0., 0., 0., 0., 0.);
+    for j in lo..lo + q {
+        let w = tricube((j as f64 - x).abs() / (h * 1.000001)) * rho[j];
+        let xj = j as f64;
+        sw += w;
+        swx += w * xj;
+        swy += w * y[j];
+        swxx += w * xj * xj;
+        swxy += w * xj * y[j];
+    }
+    if sw <= 0. {
+        return None;
+    }
+    let mean_x = swx / sw;
+    let mean_y = swy / sw;
+    let var_x = swxx / sw - mean_x * mean_x;
+    let slope = if var_x > 1e-12 * h * h { (swxy / sw - mean_x * mean_y) / var_x } else { 0. };
+    Some(mean_y + slope * (x - mean_x))
+}
+
+/// Smooths each cycle-subseries and extends it by one period on both ends, giving
+/// `n + 2 * period` values.
+fn smooth_subseries(y: &[f64], rho: &[f64], period: usize, span: usize) -> Vec<f64> {
+    let n = y.len();
+    let mut cycle = vec![0.; n + 2 * period];
+    for phase in 0..period {
+        let sub: Vec<f64> = y.iter().skip(phase).step_by(period).copied().collect();
+        let sub_rho: Vec<f64> = rho.iter().skip(phase).step_by(period).copied().collect();
+        let m = sub.len();
+        for k in 0..m + 2 {
+            let x = k as f64 - 1.;
+            let fallback = sub.get(k.saturating_sub(1).min(m.saturating_sub(1))).copied().unwrap_or(0.);
+            let position = k * period + phase;
+            if position < cycle.len() {
+                cycle[position] = loess(&sub, &sub_rho, span, x).unwrap_or(fallback);
+            }
+        }
+    }
+    cycle
+}
+
+fn moving_average(x: &[f64], window: usize) -> Vec<f64> {
+    x.windows(window).map(|w| w.iter().sum::<f64>() / window as f64).collect()
+}
+
+/// Low-pass filter of the cycle-subseries: moving averages of length `period`, `period`
+/// and 3, then a LOESS of span `span`.
+fn low_pass(cycle: &[f64], period: usize, span: usize) -> Vec<f64> {
+    let smoothed = moving_average(&moving_average(&moving_average(cycle, period), period), 3);
+    let ones = vec![1.; smoothed.len()];
+    (0..smoothed.len())
+        .map(|i| loess(&smoothed, &ones, span, i as f64).unwrap_or(smoothed[i]))
+        .collect()
+}
+
+/// Bisquare weights of the remainders, scaled by six times their median absolute value.
+fn robustness_weights(y: &[f64], trend: &[f64], seasonal: &[f64]) -> Vec<f64> {
+    let abs_remainder: Vec<f64> = (0..y.len()).map(|i| (y[i] - trend[i] - seasonal[i]).abs()).collect();
+    let mut sorted = abs_remainder.clone();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let h = 6. * crate::stats::quantile_sorted(&sorted, 0.5);
+    abs_remainder
+        .iter()
+        .map(|r| {
+            let u = if h > 0. { r / h } else { 0. };
+            if u < 1. {
+                (1. - u * u).powi(2)
+            } else {
+                0.
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,123 @@
+//! Non-parametric estimates of the distribution of a series' values.
+
+use crate::stats::quantile_sorted;
+use crate::{Statistics, TimeSeries};
+
+/// Kernel bandwidth selection for [`Density::kde`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Bandwidth {
+    /// Silverman's rule of thumb, `0.9 min(std, IQR / 1.34) n^(-1/5)`.
+    #[default]
+    Silverman,
+    Fixed(f64),
+}
+
+/// Gaussian kernel density estimate over a sample.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kde {
+    samples: Vec<f64>,
+    bandwidth: f64,
+}
+
+/// A density sampled on an evenly spaced grid of values.
+#[derive(Clone, Debug)]
+pub struct DensityGrid {
+    pub values: TimeSeries<f64>,
+    pub density: TimeSeries<f64>,
+}
+
+impl Kde {
+    pub fn bandwidth(&self) -> f64 {
+        self.bandwidth
+    }
+
+    /// Density at `x`.
+    pub fn evaluate(&self, x: f64) -> f64 {
+        let norm = 1. / ((2. * std::f64::consts::PI).sqrt() * self.bandwidth * self.samples.len() as f64);
+        self.samples.iter().map(|s| (-0.5 * ((x - s) / self.bandwidth).powi(2)).exp()).sum::<f64>() * norm
+    }
+
+    /// Log density at each point of `series`, e.g. for likelihood-based anomaly scoring.
+    pub fn log_density(&self, series: &TimeSeries<f64>) -> TimeSeries<f64> {
+        series.map(|&x| self.evaluate(x).ln())
+    }
+
+    /// Density on `points` evenly spaced values spanning the sample padded by three
+    /// bandwidths on either side.
+    pub fn grid(&self, points: usize) -> DensityGrid {
+        assert!(points >= 2, "grid needs at least two points");
+        let min = self.samples.iter().copied().fold(f64::INFINITY, f64::min) - 3. * self.bandwidth;
+        let max = self.samples.iter().copied().fold(f64::NEG_INFINITY, f64::max) + 3. * self.bandwidth;
+        let step = (max - min) / (points - 1) as f64;
+        let values: TimeSeries<f64> = (0..points).map(|i| min + step * i as f64).collect();
+        let density = values.map(|&x| self.evaluate(x));
+        DensityGrid { values, density }
+    }
+}
+
+/// Empirical cumulative distribution function of a sample.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ecdf {
+    sorted: Vec<f64>,
+}
+
+impl Ecdf {
+    /// Fraction of the sample at or below `x`; NaN for NaN or an empty sample.
+    pub fn evaluate(&self, x: f64) -> f64 {
+        if x.is_nan() || self.sorted.is_empty() {
+            return f64::NAN;
+        }
+        self.sorted.partition_point(|&s| s <= x) as f64 / self.sorted.len() as f64
+    }
+
+    pub fn len(&self) -> usize {
+        self.sorted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+}
+
+pub trait Density {
+    /// Gaussian kernel density estimate of the finite values. Returns `None` when there are
+    /// fewer than two of them or the bandwidth comes out non-positive.
+    fn kde(&self, bandwidth: Bandwidth) -> Option<Kde>;
+    /// Empirical CDF of the non-NaN values.
+    fn ecdf(&self) -> Ecdf;
+    /// Probability integral transform: each value mapped to the empirical CDF of
+    /// `reference` at that value, in `[0, 1]`. If the values follow the reference
+    /// distribution the result is roughly uniform.
+    fn pit(&self, reference: &Self) -> TimeSeries<f64>;
+}
+
+impl Density for TimeSeries<f64> {
+    fn kde(&self, bandwidth: Bandwidth) -> Option<Kde> {
+        let mut samples: Vec<f64> = self.as_slice().iter().copied().filter(|x| x.is_finite()).collect();
+        if samples.len() < 2 {
+            return None;
+        }
+        samples.sort_by(|a, b| a.total_cmp(b));
+        let bandwidth = match bandwidth {
+            Bandwidth::Fixed(h) => h,
+            Bandwidth::Silverman => {
+                let std = TimeSeries(samples.clone()).std();
+                let iqr = quantile_sorted(&samples, 0.75) - quantile_sorted(&samples, 0.25);
+                let spread = if iqr > 0. { std.min(iqr / 1.34) } else { std };
+                0.9 * spread * (samples.len() as f64).powf(-0.2)
+            }
+        };
+        (bandwidth > 0.).then_some(Kde { samples, bandwidth })
+    }
+
+    fn ecdf(&self) -> Ecdf {
+        let mut sorted: Vec<f64> = self.as_slice().iter().copied().filter(|x| !x.is_nan()).collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        Ecdf { sorted }
+    }
+
+    fn pit(&self, reference: &Self) -> TimeSeries<f64> {
+        let ecdf = reference.ecdf();
+        self.map(|&x| ecdf.evaluate(x))
+    }
+}
@@ -0,0 +1,229 @@
+//! Serial dependence and inference under it: autocorrelations, the effective number of
+//! independent points, and the block bootstrap with a data-driven block length.
+//!
+//! Positively autocorrelated data carries less information than its length suggests, so
+//! standard errors computed as if the points were independent are too small. The block
+//! bootstrap resamples runs of consecutive points instead, keeping the dependence within
+//! each run; [`SerialDependence::optimal_block_length`] picks the run length with the
+//! Politis–White rule (as corrected by Patton, Politis and White) instead of a hand-picked
+//! constant.
+
+use crate::TimeSeries;
+#[cfg(feature = "random")]
+use crate::{stats::quantile_sorted, Error, Interval};
+#[cfg(feature = "random")]
+use rand::Rng;
+
+/// How the block bootstrap cuts the series.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BlockScheme {
+    /// Blocks of random, geometrically distributed length, which keeps the resampled
+    /// series stationary.
+    #[default]
+    Stationary,
+    /// Blocks of a fixed length, wrapping around the end of the series.
+    Circular,
+}
+
+/// Biased (denominator `n`) autocovariances of `x` at lags `0..=max_lag`.
+fn autocovariances(x: &[f64], max_lag: usize) -> Vec<f64> {
+    let n = x.len() as f64;
+    let mean = x.iter().sum::<f64>() / n;
+    (0..=max_lag.min(x.len().saturating_sub(1)))
+        .map(|k| x.iter().zip(&x[k..]).map(|(a, b)| (a - mean) * (b - mean)).sum::<f64>() / n)
+        .collect()
+}
+
+/// The flat-top lag window of Politis and Romano.
+fn flat_top(t: f64) -> f64 {
+    match t.abs() {
+        t if t <= 0.5 => 1.,
+        t if t <= 1. => 2. * (1. - t),
+        _ => 0.,
+    }
+}
+
+pub trait SerialDependence {
+    /// Sample autocorrelations at lags `0..=max_lag`, from the biased autocovariances;
+    /// lags past the end of the series are left out. NaN for a constant series.
+    fn autocorrelations(&self, max_lag: usize) -> Vec<f64>;
+    /// The number of independent points carrying as much information about the mean,
+    /// `n / (1 + 2 Σ ρ_k)`. The sum is truncated with Geyer's initial positive sequence:
+    /// pairs of consecutive autocorrelations are added while their sum is positive.
+    /// Exceeds `n` for negatively correlated data; NaN for fewer than two points or a
+    /// constant series.
+    fn effective_sample_size(&self) -> f64;
+    /// The block length minimizing the mean squared error of the bootstrap variance of
+    /// the mean under `scheme`, by the Politis–White rule, between 1 and
+    /// `min(3 sqrt(n), n / 3)`. NaN for fewer than two points or a constant series.
+    fn optimal_block_length(&self, scheme: BlockScheme) -> f64;
+}
+
+impl SerialDependence for TimeSeries<f64> {
+    fn autocorrelations(&self, max_lag: usize) -> Vec<f64> {
+        let acv = autocovariances(self.as_slice(), max_lag);
+        acv.iter().map(|c| c / acv[0]).collect()
+    }
+
+    fn effective_sample_size(&self) -> f64 {
+        let n = self.len();
+        if n < 2 {
+            return f64::NAN;
+        }
+        let rho = self.autocorrelations(n - 1);
+        let mut tau = -1.;
+        for pair in rho.chunks_exact(2) {
+            let gamma = pair[0] + pair[1];
+            if gamma.is_nan() {
+                return f64::NAN;
+            }
+            if gamma <= 0. {
+                break;
+            }
+            tau += 2. * gamma;
+        }
+        n as f64 / tau
+    }
+
+    fn optimal_block_length(&self, scheme: BlockScheme) -> f64 {
+        let n = self.len();
+        if n < 2 {
+            return f64::NAN;
+        }
+        let nf = n as f64;
+        // Lags needed to call the correlation negligible, and the largest window considered.
+        let kn = (nf.log10().sqrt().ceil() as usize).max(5);
+        let m_max = nf.sqrt().ceil() as usize + kn;
+        let acv = autocovariances(self.as_slice(), m_max + kn);
+        if acv[0] == 0. || acv[0].is_nan() {
+            return f64::NAN;
+        }
+        // The window is twice the first lag after which `kn` correlations in a row are
+        // insignificant at roughly the 5% level.
+        let threshold = 2. * (nf.log10() / nf).sqrt();
+        let insignificant = |k: usize| acv.get(k).is_none_or(|c| (c / acv[0]).abs() < threshold);
+        let m_hat = (0..=m_max).find(|&m| (m + 1..=m + kn).all(insignificant)).unwrap_or(m_max);
+        let m = (2 * m_hat).min(m_max).min(acv.len() - 1);
+        let (mut g, mut long_run) = (0., acv[0]);
+        for (k, c) in acv.iter().enumerate().take(m + 1).skip(1) {
+            let w = flat_top(k as f64 / m as f64);
+            g += 2. * w * k as f64 * c;
+            long_run += 2. * w * c;
+        }
+        let d = match scheme {
+            BlockScheme::Stationary => 2. * long_run * long_run,
+            BlockScheme::Circular => 4. / 3. * long_run * long_run,
+        };
+        let b_max = (3. * nf.sqrt()).min(nf / 3.).ceil().max(1.);
+        ((2. * g * g / d).cbrt() * nf.cbrt()).clamp(1., b_max)
+    }
+}
+
+/// Bootstrap resampling of blocks of consecutive points, for confidence intervals of
+/// statistics over dependent data.
+#[cfg(feature = "random")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockBootstrap {
+    scheme: BlockScheme,
+    block_length: Option<f64>,
+    resamples: usize,
+}
+
+#[cfg(feature = "random")]
+impl Default for BlockBootstrap {
+    fn default() -> Self {
+        Self::new(BlockScheme::default())
+    }
+}
+
+#[cfg(feature = "random")]
+impl BlockBootstrap {
+    /// 1000 resamples, with the block length chosen from the data.
+    pub fn new(scheme: BlockScheme) -> Self {
+        Self { scheme, block_length: None, resamples: 1000 }
+    }
+
+    /// Uses blocks of `block_length` points (the mean length for the stationary scheme)
+    /// instead of [`SerialDependence::optimal_block_length`].
+    ///
+    /// # Panics
+    /// Panics if `block_length` is less than 1.
+    pub fn with_block_length(self, block_length: f64) -> Self {
+        assert!(block_length >= 1., "block length must be at least 1");
+        Self { block_length: Some(block_length), ..self }
+    }
+
+    /// # Panics
+    /// Panics if `resamples` is zero.
+    pub fn with_resamples(self, resamples: usize) -> Self {
+        assert!(resamples > 0, "resamples must be positive");
+        Self { resamples, ..self }
+    }
+
+    /// The block length used for `series`: the configured one, or the optimal one, or 1
+    /// for a constant series.
+    pub fn block_length(&self, series: &TimeSeries<f64>) -> f64 {
+        self.block_length.unwrap_or_else(|| {
+            let optimal = series.optimal_block_length(self.scheme);
+            if optimal.is_nan() {
+                1.
+            } else {
+                optimal
+            }
+        })
+    }
+
+    fn draw<R: Rng + ?Sized>(&self, series: &TimeSeries<f64>, block_length: f64, rng: &mut R) -> TimeSeries<f64> {
+        let n = series.len();
+        let fixed = (block_length.round() as usize).max(1);
+        let mut i = rng.gen_range(0..n);
+        let mut out = Vec::with_capacity(n);
+        for t in 1..=n {
+            out.push(series.as_slice()[i]);
+            let restart = match self.scheme {
+                BlockScheme::Stationary => rng.gen::<f64>() < 1. / block_length,
+                BlockScheme::Circular => t % fixed == 0,
+            };
+            i = if restart { rng.gen_range(0..n) } else { (i + 1) % n };
+        }
+        TimeSeries(out)
+    }
+
+    /// One bootstrap replicate of `series`, of the same length.
+    ///
+    /// # Panics
+    /// Panics if `series` is empty.
+    pub fn resample<R: Rng + ?Sized>(&self, series: &TimeSeries<f64>, rng: &mut R) -> TimeSeries<f64> {
+        assert!(!series.is_empty(), "cannot resample an empty series");
+        self.draw(series, self.block_length(series), rng)
+    }
+
+    /// The percentile interval of `statistic` at confidence `level` over the resamples.
+    ///
+    /// Fails with [`Error::InsufficientData`] for fewer than two points.
+    ///
+    /// # Panics
+    /// Panics unless `0 < level < 1`.
+    pub fn confidence_interval<R, F>(
+        &self,
+        series: &TimeSeries<f64>,
+        statistic: F,
+        level: f64,
+        rng: &mut R,
+    ) -> Result<Interval, Error>
+    where
+        R: Rng + ?Sized,
+        F: Fn(&TimeSeries<f64>) -> f64,
+    {
+        assert!(level > 0. && level < 1., "level must be between 0 and 1");
+        if series.len() < 2 {
+            return Err(Error::InsufficientData { required: 2, found: series.len() });
+        }
+        let block_length = self.block_length(series);
+        let mut values: Vec<f64> =
+            (0..self.resamples).map(|_| statistic(&self.draw(series, block_length, rng))).collect();
+        values.sort_by(|a, b| a.total_cmp(b));
+        let alpha = (1. - level) / 2.;
+        Ok(Interval::new(quantile_sorted(&values, alpha), quantile_sorted(&values, 1. - alpha)))
+    }
+}
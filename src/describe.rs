@@ -0,0 +1,193 @@
+//! Summaries of a series or of every frame column at a glance, as pandas' `describe`:
+//! count, mean, spread, quartiles, missing values and the sampling frequency.
+//!
+//! Missing values are NaN and are left out of every statistic. With the `plot` feature,
+//! `report` writes the summary together with a chart of every series into a standalone
+//! HTML file, for people who read results in a browser rather than in code.
+
+use crate::{Statistics, TimeSeries, TimeSeriesFrame, TimeUnit, TimestampedSeries};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// The summary of one series.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Description {
+    /// Number of values that are not missing.
+    pub count: usize,
+    pub missing: usize,
+    pub mean: f64,
+    /// Sample standard deviation.
+    pub std: f64,
+    pub min: f64,
+    pub q25: f64,
+    pub median: f64,
+    pub q75: f64,
+    pub max: f64,
+    /// The inferred step between timestamps (see [`infer_frequency`]), `None` for values
+    /// without timestamps.
+    pub frequency: Option<i64>,
+    /// Unit of the timestamps and of `frequency`, if known.
+    pub unit: Option<TimeUnit>,
+}
+
+impl Description {
+    fn of(values: &[f64], timestamps: Option<&[i64]>, unit: Option<TimeUnit>) -> Self {
+        let present: TimeSeries<f64> = values.iter().copied().filter(|v| !v.is_nan()).collect();
+        Description {
+            count: present.len(),
+            missing: values.len() - present.len(),
+            mean: present.mean(),
+            std: present.std(),
+            min: present.min(),
+            q25: present.quantile(0.25),
+            median: present.median(),
+            q75: present.quantile(0.75),
+            max: present.max(),
+            frequency: timestamps.and_then(infer_frequency),
+            unit,
+        }
+    }
+}
+
+/// The most common step between consecutive timestamps, the smallest on ties, which
+/// gaps and the odd late point do not change; `None` with fewer than two distinct
+/// timestamps.
+pub fn infer_frequency(timestamps: &[i64]) -> Option<i64> {
+    let mut steps: BTreeMap<i64, usize> = BTreeMap::new();
+    for pair in timestamps.windows(2).filter(|pair| pair[1] > pair[0]) {
+        *steps.entry(pair[1] - pair[0]).or_default() += 1;
+    }
+    steps.into_iter().max_by_key(|&(step, n)| (n, Reverse(step))).map(|(step, _)| step)
+}
+
+impl TimeSeries<f64> {
+    pub fn describe(&self) -> Description {
+        Description::of(self.as_slice(), None, None)
+    }
+}
+
+impl TimestampedSeries<f64> {
+    pub fn describe(&self) -> Description {
+        Description::of(self.values().as_slice(), Some(self.timestamps()), self.unit())
+    }
+}
+
+impl TimeSeriesFrame {
+    /// The summary of every column in order, with the frequency of the index.
+    pub fn describe(&self) -> Vec<(String, Description)> {
+        self.columns()
+            .map(|(name, column)| (name.to_string(), Description::of(column.as_slice(), Some(self.index()), None)))
+            .collect()
+    }
+}
+
+#[cfg(feature = "plot")]
+mod report {
+    use super::Description;
+    use crate::{Chart, Error, TimeSeriesFrame, TimestampedSeries};
+    use std::fmt::Write as _;
+    use std::path::Path;
+
+    /// A row label of the summary table and how each cell shows its statistic.
+    type Row = (&'static str, fn(&Description) -> String);
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+    }
+
+    fn cell(value: f64) -> String {
+        if value.is_nan() {
+            "–".to_string()
+        } else {
+            format!("{:.6}", value).trim_end_matches('0').trim_end_matches('.').to_string()
+        }
+    }
+
+    /// A standalone page with the summary table, one column per series, and a chart per
+    /// series below it.
+    fn html(title: &str, series: &[(String, TimestampedSeries<f64>)]) -> Result<String, Error> {
+        let descriptions: Vec<Description> = series.iter().map(|(_, s)| s.describe()).collect();
+        let mut page = String::new();
+        let _ = write!(
+            page,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n<style>\n\
+             body {{ font-family: sans-serif; margin: 2em; }}\n\
+             table {{ border-collapse: collapse; margin-bottom: 2em; }}\n\
+             th, td {{ border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: right; }}\n\
+             </style>\n</head>\n<body>\n<h1>{0}</h1>\n<table>\n<tr><th></th>",
+            escape(title)
+        );
+        for (name, _) in series {
+            let _ = write!(page, "<th>{}</th>", escape(name));
+        }
+        page.push_str("</tr>\n");
+        let rows: [Row; 10] = [
+            ("count", |d| d.count.to_string()),
+            ("missing", |d| d.missing.to_string()),
+            ("mean", |d| cell(d.mean)),
+            ("std", |d| cell(d.std)),
+            ("min", |d| cell(d.min)),
+            ("25%", |d| cell(d.q25)),
+            ("50%", |d| cell(d.median)),
+            ("75%", |d| cell(d.q75)),
+            ("max", |d| cell(d.max)),
+            ("frequency", |d| match (d.frequency, d.unit) {
+                (Some(step), Some(unit)) => format!("{} {}", step, unit.suffix()),
+                (Some(step), None) => step.to_string(),
+                (None, _) => "–".to_string(),
+            }),
+        ];
+        for (label, value) in rows {
+            let _ = write!(page, "<tr><th>{}</th>", label);
+            for d in &descriptions {
+                let _ = write!(page, "<td>{}</td>", value(d));
+            }
+            page.push_str("</tr>\n");
+        }
+        page.push_str("</table>\n");
+        for (name, s) in series {
+            let _ = writeln!(page, "<div>{}</div>", Chart::new(name.as_str()).line(name.as_str(), s).to_svg()?);
+        }
+        page.push_str("</body>\n</html>\n");
+        Ok(page)
+    }
+
+    fn write<P: AsRef<Path>>(path: P, page: String) -> Result<(), Error> {
+        std::fs::write(path, page).map_err(|e| Error::InvalidInput(e.to_string()))
+    }
+
+    impl TimestampedSeries<f64> {
+        /// The summary and a chart of the series as an HTML page.
+        pub fn report_html(&self, title: &str) -> Result<String, Error> {
+            html(title, &[(title.to_string(), self.clone())])
+        }
+
+        /// Writes [`report_html`](Self::report_html) to `path`, titled after the file.
+        pub fn report<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+            write(&path, self.report_html(&title(path.as_ref()))?)
+        }
+    }
+
+    impl TimeSeriesFrame {
+        /// The summary of every column and a chart of each as an HTML page.
+        pub fn report_html(&self, title: &str) -> Result<String, Error> {
+            let columns: Vec<(String, TimestampedSeries<f64>)> = self
+                .columns()
+                .map(|(name, column)| {
+                    (name.to_string(), TimestampedSeries::from_parts(self.index().to_vec(), column.clone()))
+                })
+                .collect();
+            html(title, &columns)
+        }
+
+        /// Writes [`report_html`](Self::report_html) to `path`, titled after the file.
+        pub fn report<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+            write(&path, self.report_html(&title(path.as_ref()))?)
+        }
+    }
+
+    fn title(path: &Path) -> String {
+        path.file_stem().map_or_else(|| "Summary".to_string(), |stem| stem.to_string_lossy().into_owned())
+    }
+}
@@ -0,0 +1,173 @@
+//! Differentially private count, sum and mean of event series over time buckets, with a
+//! budget accountant that refuses queries once the allotted epsilon is spent.
+//!
+//! Queries take one event series per individual and keep at most a bound of events from
+//! each, so the bound on any one individual's influence holds whatever the input.
+//! Every bucket of the requested range is released, including empty ones, since
+//! omitting empty buckets would itself reveal where events occurred. Buckets are
+//! disjoint, so one query over all of them costs its epsilon once.
+
+use crate::random::laplace;
+use crate::{Error, TimestampedSeries};
+use rand::Rng;
+
+/// Total epsilon available for queries on one dataset, and what has been spent on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrivacyBudget {
+    total: f64,
+    spent: f64,
+    ledger: Vec<(String, f64)>,
+}
+
+impl PrivacyBudget {
+    pub fn new(total_epsilon: f64) -> Self {
+        assert!(total_epsilon > 0., "budget must be positive");
+        Self { total: total_epsilon, spent: 0., ledger: Vec::new() }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+
+    pub fn spent(&self) -> f64 {
+        self.spent
+    }
+
+    pub fn remaining(&self) -> f64 {
+        (self.total - self.spent).max(0.)
+    }
+
+    /// Queries charged so far, as `(query, epsilon)`.
+    pub fn ledger(&self) -> &[(String, f64)] {
+        &self.ledger
+    }
+
+    /// Charges `epsilon` for `query`, or fails without charging if it would exceed the
+    /// budget.
+    pub fn spend(&mut self, query: &str, epsilon: f64) -> Result<(), Error> {
+        assert!(epsilon > 0., "epsilon must be positive");
+        // Tolerate rounding when a budget is split into equal parts.
+        if self.spent + epsilon > self.total * (1. + 1e-12) {
+            return Err(Error::InvalidInput(format!(
+                "privacy budget exhausted: {} needs {}, {} left",
+                query,
+                epsilon,
+                self.remaining()
+            )));
+        }
+        self.spent += epsilon;
+        self.ledger.push((query.to_string(), epsilon));
+        Ok(())
+    }
+}
+
+/// Bucketed aggregate queries over the event series of many individuals.
+///
+/// Sensitivity comes from the contribution bound: only the first `max_contributions`
+/// events of each individual in range are aggregated, and values are clamped to
+/// `[lower, upper]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DpAggregator {
+    pub step: i64,
+    /// Released buckets cover `[start, end)` in steps of `step` from `start`.
+    pub start: i64,
+    pub end: i64,
+    pub lower: f64,
+    pub upper: f64,
+    pub max_contributions: usize,
+}
+
+impl DpAggregator {
+    /// Buckets of `step` over `[start, end)`, values bounded to `[0, 1]`, one event per
+    /// individual.
+    pub fn new(step: i64, start: i64, end: i64) -> Self {
+        assert!(step > 0, "step must be positive");
+        assert!(start < end, "start must precede end");
+        Self { step, start, end, lower: 0., upper: 1., max_contributions: 1 }
+    }
+
+    pub fn with_bounds(self, lower: f64, upper: f64) -> Self {
+        assert!(lower <= upper, "lower bound must not exceed upper bound");
+        Self { lower, upper, ..self }
+    }
+
+    pub fn with_max_contributions(self, max_contributions: usize) -> Self {
+        assert!(max_contributions > 0, "contribution bound must be positive");
+        Self { max_contributions, ..self }
+    }
+
+    /// Exact per-bucket aggregate over every bucket in range, of each individual's first
+    /// `max_contributions` events in range.
+    fn exact(&self, individuals: &[TimestampedSeries<f64>], agg: impl Fn(&[f64]) -> f64) -> TimestampedSeries<f64> {
+        let step = self.step as i128;
+        let buckets = ((self.end as i128 - self.start as i128 + step - 1) / step) as usize;
+        let mut values = vec![Vec::new(); buckets];
+        for events in individuals {
+            let timestamps = events.timestamps();
+            let lo = timestamps.partition_point(|&t| t < self.start);
+            let hi = timestamps.partition_point(|&t| t < self.end).max(lo).min(lo.saturating_add(self.max_contributions));
+            for (&t, &v) in timestamps[lo..hi].iter().zip(&events.values().as_slice()[lo..hi]) {
+                values[((t as i128 - self.start as i128) / step) as usize].push(v);
+            }
+        }
+        values.iter().enumerate().map(|(k, bucket)| (self.start + k as i64 * self.step, agg(bucket))).collect()
+    }
+
+    fn noisy(exact: TimestampedSeries<f64>, scale: f64, rng: &mut (impl Rng + ?Sized)) -> TimestampedSeries<f64> {
+        exact.iter().map(|(t, &v)| (t, v + laplace(rng, scale))).collect()
+    }
+
+    /// Noisy number of events per bucket, from one event series per individual.
+    pub fn count<R: Rng + ?Sized>(
+        &self,
+        individuals: &[TimestampedSeries<f64>],
+        epsilon: f64,
+        budget: &mut PrivacyBudget,
+        rng: &mut R,
+    ) -> Result<TimestampedSeries<f64>, Error> {
+        budget.spend("count", epsilon)?;
+        let exact = self.exact(individuals, |v| v.len() as f64);
+        Ok(Self::noisy(exact, self.max_contributions as f64 / epsilon, rng))
+    }
+
+    /// Noisy sum of the clamped values per bucket.
+    pub fn sum<R: Rng + ?Sized>(
+        &self,
+        individuals: &[TimestampedSeries<f64>],
+        epsilon: f64,
+        budget: &mut PrivacyBudget,
+        rng: &mut R,
+    ) -> Result<TimestampedSeries<f64>, Error> {
+        budget.spend("sum", epsilon)?;
+        let (lower, upper) = (self.lower, self.upper);
+        let exact = self.exact(individuals, |v| v.iter().map(|x| x.clamp(lower, upper)).sum());
+        let sensitivity = self.max_contributions as f64 * lower.abs().max(upper.abs());
+        Ok(Self::noisy(exact, sensitivity / epsilon, rng))
+    }
+
+    /// Noisy mean per bucket as noisy sum over noisy count, each using half of
+    /// `epsilon`. The result is clamped to the bounds; buckets whose noisy count is
+    /// below 1 are NaN.
+    pub fn mean<R: Rng + ?Sized>(
+        &self,
+        individuals: &[TimestampedSeries<f64>],
+        epsilon: f64,
+        budget: &mut PrivacyBudget,
+        rng: &mut R,
+    ) -> Result<TimestampedSeries<f64>, Error> {
+        if epsilon > budget.remaining() * (1. + 1e-12) {
+            return Err(Error::InvalidInput(format!(
+                "privacy budget exhausted: mean needs {}, {} left",
+                epsilon,
+                budget.remaining()
+            )));
+        }
+        let sums = self.sum(individuals, epsilon / 2., budget, rng)?;
+        let counts = self.count(individuals, epsilon / 2., budget, rng)?;
+        Ok(sums
+            .iter()
+            .zip(counts.values().as_slice().iter())
+            .map(|((t, &s), &c)| (t, if c >= 1. { (s / c).clamp(self.lower, self.upper) } else { f64::NAN }))
+            .collect())
+    }
+}
@@ -0,0 +1,143 @@
+//! Fitting parametric distributions to the values of a series and testing the fit.
+
+use crate::special::normal_cdf;
+use crate::{Error, TimeSeries};
+
+/// Distribution family to fit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Family {
+    Normal,
+    LogNormal,
+    Exponential,
+}
+
+/// A fully specified distribution.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distribution {
+    Normal { mean: f64, std: f64 },
+    /// `ln X` is normal with mean `mu` and standard deviation `sigma`.
+    LogNormal { mu: f64, sigma: f64 },
+    Exponential { rate: f64 },
+}
+
+impl Distribution {
+    pub fn cdf(&self, x: f64) -> f64 {
+        match *self {
+            Distribution::Normal { mean, std } => normal_cdf((x - mean) / std),
+            Distribution::LogNormal { mu, sigma } => {
+                if x <= 0. {
+                    0.
+                } else {
+                    normal_cdf((x.ln() - mu) / sigma)
+                }
+            }
+            Distribution::Exponential { rate } => {
+                if x <= 0. {
+                    0.
+                } else {
+                    1. - (-rate * x).exp()
+                }
+            }
+        }
+    }
+}
+
+/// Goodness-of-fit statistics of a sample against a distribution.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GoodnessOfFit {
+    /// Kolmogorov-Smirnov statistic: the largest gap between the empirical and fitted CDF.
+    pub ks_statistic: f64,
+    /// Asymptotic KS p-value. It assumes the distribution was specified in advance, so it
+    /// is conservative (too large) when the parameters were estimated from the same data.
+    pub ks_p_value: f64,
+    /// Anderson-Darling statistic, which weights the tails more heavily than KS.
+    pub ad_statistic: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DistributionFit {
+    /// Maximum-likelihood parameters.
+    pub distribution: Distribution,
+    pub goodness_of_fit: GoodnessOfFit,
+}
+
+/// Survival function of the Kolmogorov distribution.
+fn kolmogorov_sf(lambda: f64) -> f64 {
+    if lambda < 0.2 {
+        return 1.;
+    }
+    let sum: f64 = (1..=100)
+        .map(|k| {
+            let k = k as f64;
+            let sign = if k % 2. == 1. { 1. } else { -1. };
+            sign * (-2. * k * k * lambda * lambda).exp()
+        })
+        .sum();
+    (2. * sum).clamp(0., 1.)
+}
+
+pub trait DistributionFitting {
+    /// Maximum-likelihood fit of `family` to the values, with goodness-of-fit statistics.
+    fn fit_distribution(&self, family: Family) -> Result<DistributionFit, Error>;
+    /// Kolmogorov-Smirnov and Anderson-Darling statistics against `distribution`.
+    fn goodness_of_fit(&self, distribution: &Distribution) -> GoodnessOfFit;
+}
+
+impl DistributionFitting for TimeSeries<f64> {
+    fn fit_distribution(&self, family: Family) -> Result<DistributionFit, Error> {
+        trace_span!("fit", model = "distribution", family = ?family, n = self.len());
+        let x = self.as_slice();
+        if x.len() < 2 {
+            return Err(Error::InsufficientData { required: 2, found: x.len() });
+        }
+        let n = x.len() as f64;
+        let mean_std = |values: &mut dyn Iterator<Item = f64>| {
+            let v: Vec<f64> = values.collect();
+            let mean = v.iter().sum::<f64>() / n;
+            let var = v.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+            (mean, var.sqrt())
+        };
+        let distribution = match family {
+            Family::Normal => {
+                let (mean, std) = mean_std(&mut x.iter().copied());
+                Distribution::Normal { mean, std }
+            }
+            Family::LogNormal => {
+                if x.iter().any(|&v| v <= 0.) {
+                    return Err(Error::InvalidInput("log-normal fit needs positive values".into()));
+                }
+                let (mu, sigma) = mean_std(&mut x.iter().map(|v| v.ln()));
+                Distribution::LogNormal { mu, sigma }
+            }
+            Family::Exponential => {
+                if x.iter().any(|&v| v < 0.) {
+                    return Err(Error::InvalidInput("exponential fit needs non-negative values".into()));
+                }
+                Distribution::Exponential { rate: n / x.iter().sum::<f64>() }
+            }
+        };
+        Ok(DistributionFit { distribution, goodness_of_fit: self.goodness_of_fit(&distribution) })
+    }
+
+    fn goodness_of_fit(&self, distribution: &Distribution) -> GoodnessOfFit {
+        let mut sorted = self.as_slice().to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let n = sorted.len() as f64;
+        // Clamped away from 0 and 1 so the Anderson-Darling logarithms stay finite.
+        let cdf: Vec<f64> = sorted.iter().map(|&x| distribution.cdf(x).clamp(1e-300, 1. - 1e-16)).collect();
+        let ks_statistic = cdf
+            .iter()
+            .enumerate()
+            .map(|(i, &f)| (f - i as f64 / n).max((i + 1) as f64 / n - f))
+            .fold(0., f64::max);
+        let sqrt_n = n.sqrt();
+        let ks_p_value = kolmogorov_sf((sqrt_n + 0.12 + 0.11 / sqrt_n) * ks_statistic);
+        let ad_sum: f64 = cdf
+            .iter()
+            .zip(cdf.iter().rev())
+            .enumerate()
+            .map(|(i, (lo, hi))| (2 * i + 1) as f64 * (lo.ln() + (1. - hi).ln()))
+            .sum();
+        GoodnessOfFit { ks_statistic, ks_p_value, ad_statistic: -n - ad_sum / n }
+    }
+}
@@ -0,0 +1,90 @@
+//! Shape-preserving downsampling for display, where plotting every point of a long
+//! series is slow and draws nothing more than a few thousand points would.
+
+use crate::{Interval, Ohlcv, TimestampedSeries};
+
+impl TimestampedSeries<f64> {
+    /// At most `threshold` points chosen by Largest-Triangle-Three-Buckets: the first and
+    /// last points are kept, and from each bucket in between the point forming the
+    /// largest triangle with the previously kept point and the average of the next
+    /// bucket, which keeps peaks and troughs. Non-finite values are skipped.
+    ///
+    /// # Panics
+    /// Panics if `threshold < 3`.
+    pub fn lttb(&self, threshold: usize) -> Self {
+        assert!(threshold >= 3, "threshold must be at least 3");
+        let points: Vec<(i64, f64)> = self.iter().filter(|(_, v)| v.is_finite()).map(|(t, &v)| (t, v)).collect();
+        let n = points.len();
+        if n <= threshold {
+            return points.into_iter().collect();
+        }
+        // Timestamps relative to the first point keep the areas precise for large epochs.
+        let origin = points[0].0;
+        let x = |i: usize| (points[i].0 - origin) as f64;
+        let every = (n - 2) as f64 / (threshold - 2) as f64;
+        let mut kept = vec![0];
+        let mut a = 0;
+        for bucket in 0..threshold - 2 {
+            let next = ((bucket + 1) as f64 * every) as usize + 1..(((bucket + 2) as f64 * every) as usize + 1).min(n);
+            let count = next.len() as f64;
+            let avg_x = next.clone().map(x).sum::<f64>() / count;
+            let avg_y = points[next].iter().map(|p| p.1).sum::<f64>() / count;
+            let range = (bucket as f64 * every) as usize + 1..((bucket + 1) as f64 * every) as usize + 1;
+            let (ax, ay) = (x(a), points[a].1);
+            a = range
+                .max_by(|&i, &j| {
+                    let area = |k: usize| ((ax - avg_x) * (points[k].1 - ay) - (ax - x(k)) * (avg_y - ay)).abs();
+                    area(i).total_cmp(&area(j))
+                })
+                .expect("buckets are not empty");
+            kept.push(a);
+        }
+        kept.push(n - 1);
+        kept.into_iter().map(|i| points[i]).collect()
+    }
+}
+
+impl TimestampedSeries<Ohlcv> {
+    /// At most `max_bars` bars, merging runs of consecutive bars into one: first open,
+    /// highest high, lowest low, last close and total volume, at the first timestamp.
+    ///
+    /// # Panics
+    /// Panics if `max_bars` is zero.
+    pub fn merge_bars(&self, max_bars: usize) -> Self {
+        assert!(max_bars > 0, "max_bars must be positive");
+        let per_bar = self.len().div_ceil(max_bars).max(1);
+        let bars = self.values().as_slice();
+        self.timestamps()
+            .chunks(per_bar)
+            .zip(bars.chunks(per_bar))
+            .map(|(ts, run)| {
+                let merged = Ohlcv {
+                    open: run[0].open,
+                    high: run.iter().map(|b| b.high).fold(f64::NEG_INFINITY, f64::max),
+                    low: run.iter().map(|b| b.low).fold(f64::INFINITY, f64::min),
+                    close: run[run.len() - 1].close,
+                    volume: run.iter().map(|b| b.volume).sum(),
+                };
+                (ts[0], merged)
+            })
+            .collect()
+    }
+}
+
+impl TimestampedSeries<Interval> {
+    /// At most `max_points` intervals, merging runs of consecutive intervals into their
+    /// hull at the first timestamp, so the band never narrows.
+    ///
+    /// # Panics
+    /// Panics if `max_points` is zero.
+    pub fn merge_intervals(&self, max_points: usize) -> Self {
+        assert!(max_points > 0, "max_points must be positive");
+        let per_point = self.len().div_ceil(max_points).max(1);
+        let intervals = self.values().as_slice();
+        self.timestamps()
+            .chunks(per_point)
+            .zip(intervals.chunks(per_point))
+            .map(|(ts, run)| (ts[0], run.iter().skip(1).fold(run[0], |hull, i| hull.hull(i))))
+            .collect()
+    }
+}
@@ -0,0 +1,27 @@
+//! Drawdowns of an equity curve.
+
+use crate::TimeSeries;
+
+pub trait Drawdown {
+    /// Fractional decline from the running peak at each point (0 at a new high, negative below it).
+    fn drawdown(&self) -> TimeSeries<f64>;
+    /// Largest decline from a running peak, as a negative fraction.
+    fn max_drawdown(&self) -> f64;
+}
+
+impl Drawdown for TimeSeries<f64> {
+    fn drawdown(&self) -> TimeSeries<f64> {
+        let mut peak = f64::NEG_INFINITY;
+        self.as_slice()
+            .iter()
+            .map(|&x| {
+                peak = peak.max(x);
+                x / peak - 1.
+            })
+            .collect()
+    }
+
+    fn max_drawdown(&self) -> f64 {
+        self.drawdown().into_vec().into_iter().fold(0., f64::min)
+    }
+}
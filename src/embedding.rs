@@ -0,0 +1,103 @@
+//! Takens delay embedding and the usual heuristics for choosing its parameters.
+
+use crate::TimeSeries;
+
+pub trait Embedding {
+    /// Delay vectors `[x_t, x_{t+delay}, ..., x_{t+(dim-1)delay}]`, one per row.
+    fn embed(&self, dim: usize, delay: usize) -> Vec<Vec<f64>>;
+    /// Mutual information (in nats) between the series and itself shifted by each lag in
+    /// `0..=max_lag`, estimated with `bins` equal-width bins. The first local minimum is
+    /// the customary choice of embedding delay.
+    fn mutual_information(&self, max_lag: usize, bins: usize) -> TimeSeries<f64>;
+    /// Fraction of false nearest neighbours for embedding dimensions `1..=max_dim`
+    /// (Kennel et al.): neighbours whose distance grows by more than `ratio` times when
+    /// the next coordinate is added. The smallest dimension where it drops near zero is
+    /// the customary choice.
+    fn false_nearest_neighbors(&self, max_dim: usize, delay: usize, ratio: f64) -> TimeSeries<f64>;
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum::<f64>().sqrt()
+}
+
+impl Embedding for TimeSeries<f64> {
+    fn embed(&self, dim: usize, delay: usize) -> Vec<Vec<f64>> {
+        assert!(dim > 0 && delay > 0, "dim and delay must be positive");
+        let span = (dim - 1) * delay;
+        if self.len() <= span {
+            return Vec::new();
+        }
+        (0..self.len() - span)
+            .map(|t| (0..dim).map(|k| self.as_slice()[t + k * delay]).collect())
+            .collect()
+    }
+
+    fn mutual_information(&self, max_lag: usize, bins: usize) -> TimeSeries<f64> {
+        assert!(bins > 0, "bins must be positive");
+        let x = self.as_slice();
+        let (lo, hi) = x.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+        let width = (hi - lo) / bins as f64;
+        let bin = |v: f64| if width > 0. { (((v - lo) / width) as usize).min(bins - 1) } else { 0 };
+        let labels: Vec<usize> = x.iter().map(|&v| bin(v)).collect();
+        (0..=max_lag)
+            .map(|lag| {
+                if lag >= labels.len() {
+                    return f64::NAN;
+                }
+                let n = labels.len() - lag;
+                let mut joint = vec![0usize; bins * bins];
+                let mut left = vec![0usize; bins];
+                let mut right = vec![0usize; bins];
+                for t in 0..n {
+                    let (a, b) = (labels[t], labels[t + lag]);
+                    joint[a * bins + b] += 1;
+                    left[a] += 1;
+                    right[b] += 1;
+                }
+                let n = n as f64;
+                joint
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &c)| c > 0)
+                    .map(|(k, &c)| {
+                        let p = c as f64 / n;
+                        p * (p * n * n / (left[k / bins] * right[k % bins]) as f64).ln()
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    fn false_nearest_neighbors(&self, max_dim: usize, delay: usize, ratio: f64) -> TimeSeries<f64> {
+        (1..=max_dim)
+            .map(|dim| {
+                // Only points that still exist in dimension dim + 1 can be tested.
+                let points = self.embed(dim + 1, delay);
+                if points.len() < 2 {
+                    return f64::NAN;
+                }
+                let mut false_count = 0;
+                let mut tested = 0;
+                for (i, p) in points.iter().enumerate() {
+                    let nearest = points
+                        .iter()
+                        .enumerate()
+                        .filter(|&(j, _)| j != i)
+                        .map(|(j, q)| (j, distance(&p[..dim], &q[..dim])))
+                        .min_by(|a, b| a.1.total_cmp(&b.1));
+                    if let Some((j, d)) = nearest.filter(|&(_, d)| d > 0.) {
+                        tested += 1;
+                        if (p[dim] - points[j][dim]).abs() / d > ratio {
+                            false_count += 1;
+                        }
+                    }
+                }
+                if tested == 0 {
+                    f64::NAN
+                } else {
+                    false_count as f64 / tested as f64
+                }
+            })
+            .collect()
+    }
+}
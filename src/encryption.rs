@@ -0,0 +1,169 @@
+//! Authenticated encryption of persisted files, for stores on disks that are not trusted:
+//! the files of a [`ChunkStore`](crate::ChunkStore) and the snapshots of a
+//! [`SeriesSet`](crate::SeriesSet).
+//!
+//! A sealed file starts with a header naming the cipher and the key it was sealed with,
+//! followed by a random nonce and the ciphertext with its tag. Keys are 256-bit and come
+//! from a callback given the key id, e.g. backed by a key management service, so the
+//! current key can be rotated while files sealed under older ids stay readable as long
+//! as the callback still provides those keys. The header and a caller-chosen context,
+//! such as the file's role and name, are authenticated too, so a sealed file cannot be
+//! swapped for another one unnoticed.
+//!
+//! Names derived from contents, such as the chunk files of a store, use a MAC under a
+//! subkey of the current key rather than a plain hash, so that a guess of the contents
+//! cannot be confirmed from the names without the key.
+
+use aes_gcm::aead::{Aead, KeyInit, Nonce, Payload};
+use aes_gcm::Aes256Gcm;
+use chacha20poly1305::ChaCha20Poly1305;
+use crate::sha256::hmac_sha256;
+use std::io;
+use std::sync::Arc;
+
+const MAGIC: &[u8; 4] = b"TSE\x01";
+const NONCE_LEN: usize = 12;
+/// Derivation label of the subkey of [`Encryption::mac`].
+const MAC_LABEL: &[u8] = b"time_series_utils name mac";
+/// Magic, cipher, key id and nonce.
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4 + NONCE_LEN;
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The AEAD construction of sealed files. Both take 256-bit keys and 96-bit nonces;
+/// ChaCha20-Poly1305 is the faster one without AES hardware support.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Cipher {
+    #[default]
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl Cipher {
+    fn id(self) -> u8 {
+        match self {
+            Cipher::Aes256Gcm => 1,
+            Cipher::ChaCha20Poly1305 => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Cipher::Aes256Gcm),
+            2 => Some(Cipher::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    fn seal(self, key: &[u8; 32], nonce: &[u8], msg: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        fn seal_with<C: Aead + KeyInit>(key: &[u8], nonce: &[u8], msg: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+            C::new_from_slice(key).ok()?.encrypt(Nonce::<C>::from_slice(nonce), Payload { msg, aad }).ok()
+        }
+        match self {
+            Cipher::Aes256Gcm => seal_with::<Aes256Gcm>(key, nonce, msg, aad),
+            Cipher::ChaCha20Poly1305 => seal_with::<ChaCha20Poly1305>(key, nonce, msg, aad),
+        }
+    }
+
+    fn open(self, key: &[u8; 32], nonce: &[u8], msg: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        fn open_with<C: Aead + KeyInit>(key: &[u8], nonce: &[u8], msg: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+            C::new_from_slice(key).ok()?.decrypt(Nonce::<C>::from_slice(nonce), Payload { msg, aad }).ok()
+        }
+        match self {
+            Cipher::Aes256Gcm => open_with::<Aes256Gcm>(key, nonce, msg, aad),
+            Cipher::ChaCha20Poly1305 => open_with::<ChaCha20Poly1305>(key, nonce, msg, aad),
+        }
+    }
+}
+
+/// Whether `bytes` look like a sealed file.
+pub fn is_sealed(bytes: &[u8]) -> bool {
+    bytes.len() >= HEADER_LEN && bytes.starts_with(MAGIC)
+}
+
+/// A cipher, the id of the key to seal with, and the callback providing keys by id.
+#[derive(Clone)]
+pub struct Encryption {
+    cipher: Cipher,
+    key_id: u32,
+    keys: Arc<dyn Fn(u32) -> Option<[u8; 32]> + Send + Sync>,
+}
+
+impl std::fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Encryption").field("cipher", &self.cipher).field("key_id", &self.key_id).finish()
+    }
+}
+
+impl Encryption {
+    /// Seals with `cipher` under key `key_id`, asking `keys` for the key of an id
+    /// whenever one is needed; it returns `None` for ids it does not know.
+    pub fn new<F>(cipher: Cipher, key_id: u32, keys: F) -> Self
+    where
+        F: Fn(u32) -> Option<[u8; 32]> + Send + Sync + 'static,
+    {
+        Self { cipher, key_id, keys: Arc::new(keys) }
+    }
+
+    pub fn cipher(&self) -> Cipher {
+        self.cipher
+    }
+
+    pub fn key_id(&self) -> u32 {
+        self.key_id
+    }
+
+    fn key(&self, id: u32) -> io::Result<[u8; 32]> {
+        (self.keys)(id).ok_or_else(|| invalid_data(format!("no key with id {}", id)))
+    }
+
+    /// Encrypts `plaintext`, authenticating `context` with it; the same context must be
+    /// given to [`Encryption::open`].
+    ///
+    /// Fails if the callback has no key for the current key id.
+    pub fn seal(&self, plaintext: &[u8], context: &[u8]) -> io::Result<Vec<u8>> {
+        let key = self.key(self.key_id)?;
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let mut sealed = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+        sealed.extend(MAGIC);
+        sealed.push(self.cipher.id());
+        sealed.extend(self.key_id.to_le_bytes());
+        sealed.extend(nonce);
+        let aad = [&sealed[..], context].concat();
+        let ciphertext =
+            self.cipher.seal(&key, &nonce, plaintext, &aad).ok_or_else(|| invalid_data("encryption failed".into()))?;
+        sealed.extend(ciphertext);
+        Ok(sealed)
+    }
+
+    /// HMAC-SHA-256 of `data` under a subkey of the current key, to name files after
+    /// their contents without revealing them. Names change when the key id does.
+    ///
+    /// Fails if the callback has no key for the current key id.
+    pub fn mac(&self, data: &[u8]) -> io::Result<[u8; 32]> {
+        let subkey = hmac_sha256(&self.key(self.key_id)?, MAC_LABEL);
+        Ok(hmac_sha256(&subkey, data))
+    }
+
+    /// Decrypts bytes from [`Encryption::seal`], with the cipher and key named in their
+    /// header, which may differ from the current ones.
+    ///
+    /// Fails if `sealed` is not a sealed file, the callback has no key for its key id, or
+    /// it does not authenticate: it was altered, sealed under another key, or with
+    /// another context.
+    pub fn open(&self, sealed: &[u8], context: &[u8]) -> io::Result<Vec<u8>> {
+        if !is_sealed(sealed) {
+            return Err(invalid_data("not an encrypted file".into()));
+        }
+        let (header, ciphertext) = sealed.split_at(HEADER_LEN);
+        let cipher = Cipher::from_id(header[4]).ok_or_else(|| invalid_data(format!("unknown cipher {}", header[4])))?;
+        let key_id = u32::from_le_bytes(header[5..9].try_into().expect("four bytes"));
+        let key = self.key(key_id)?;
+        let aad = [header, context].concat();
+        cipher
+            .open(&key, &header[9..], ciphertext, &aad)
+            .ok_or_else(|| invalid_data("decryption failed: wrong key or altered file".into()))
+    }
+}
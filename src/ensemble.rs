@@ -0,0 +1,228 @@
+//! Combining and choosing among forecasters.
+//!
+//! An [`Ensemble`] is itself a [`Forecaster`], so it can be nested or offered to
+//! [`auto_select`] alongside single models. Weights other than the plain mean are
+//! learned on a holdout at the end of the series, after which every member is refitted
+//! on the whole series.
+
+use crate::linalg::least_squares;
+use crate::{Error, Forecaster, TimeSeries};
+
+/// How an [`Ensemble`] weights its members.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Combination {
+    /// Equal weights.
+    Mean,
+    /// Weights proportional to the inverse mean squared error of each member's
+    /// forecast of the last `holdout` points.
+    InverseError { holdout: usize },
+    /// Weights that best reproduce the last `holdout` points from the members'
+    /// forecasts by least squares, with negative weights dropped and the rest scaled to
+    /// sum to one.
+    Stacking { holdout: usize },
+}
+
+/// A weighted combination of forecasters. Quantiles are combined with the same weights,
+/// level by level.
+pub struct Ensemble {
+    members: Vec<Box<dyn Forecaster>>,
+    combination: Combination,
+    weights: Vec<f64>,
+}
+
+impl Ensemble {
+    /// # Panics
+    /// Panics if `members` is empty.
+    pub fn new(members: Vec<Box<dyn Forecaster>>) -> Self {
+        assert!(!members.is_empty(), "an ensemble needs at least one member");
+        Self { members, combination: Combination::Mean, weights: Vec::new() }
+    }
+
+    pub fn with_combination(self, combination: Combination) -> Self {
+        Self { combination, ..self }
+    }
+
+    /// The fitted weight of each member; empty before fitting.
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    pub fn members(&self) -> &[Box<dyn Forecaster>] {
+        &self.members
+    }
+
+    /// Each member's forecast of the last `holdout` points, fitted on the points before.
+    /// A member that fails to fit forecasts NaN.
+    fn holdout_forecasts(&mut self, series: &TimeSeries<f64>, holdout: usize) -> Result<Vec<Vec<f64>>, Error> {
+        if holdout == 0 || series.len() <= holdout {
+            return Err(Error::InsufficientData { required: holdout + 1, found: series.len() });
+        }
+        let train = series.slice(0..series.len() - holdout);
+        Ok(self
+            .members
+            .iter_mut()
+            .map(|m| match m.fit(&train) {
+                Ok(()) => m.predict(holdout).into_vec(),
+                Err(_) => vec![f64::NAN; holdout],
+            })
+            .collect())
+    }
+
+    fn learn_weights(&mut self, series: &TimeSeries<f64>) -> Result<Vec<f64>, Error> {
+        let raw = match self.combination {
+            Combination::Mean => vec![1.; self.members.len()],
+            Combination::InverseError { holdout } => {
+                let actual = &series.as_slice()[series.len().saturating_sub(holdout)..];
+                let forecasts = self.holdout_forecasts(series, holdout)?;
+                forecasts
+                    .iter()
+                    .map(|f| {
+                        let mse = f.iter().zip(actual).map(|(p, a)| (p - a).powi(2)).sum::<f64>() / holdout as f64;
+                        if mse.is_nan() {
+                            0.
+                        } else {
+                            1. / mse
+                        }
+                    })
+                    .collect()
+            }
+            Combination::Stacking { holdout } => {
+                let actual = &series.as_slice()[series.len().saturating_sub(holdout)..];
+                let forecasts = self.holdout_forecasts(series, holdout)?;
+                let design: Vec<Vec<f64>> = (0..holdout).map(|h| forecasts.iter().map(|f| f[h]).collect()).collect();
+                if design.iter().flatten().any(|x| x.is_nan()) {
+                    return Err(Error::InvalidInput("a member failed on the stacking holdout".to_string()));
+                }
+                let beta = least_squares(&design, actual).ok_or(Error::Singular)?;
+                beta.into_iter().map(|b| b.max(0.)).collect()
+            }
+        };
+        let total: f64 = raw.iter().sum();
+        if total == 0. || !total.is_finite() {
+            // Every member failed, or some forecast the holdout exactly: fall back to the
+            // members that did best.
+            let best = raw.iter().copied().fold(0., f64::max);
+            let top: Vec<f64> = raw.iter().map(|&w| if w == best && w > 0. { 1. } else { 0. }).collect();
+            let count: f64 = top.iter().sum();
+            if count == 0. {
+                return Err(Error::InvalidInput("no member could be weighted".to_string()));
+            }
+            return Ok(top.into_iter().map(|w| w / count).collect());
+        }
+        Ok(raw.into_iter().map(|w| w / total).collect())
+    }
+}
+
+impl Forecaster for Ensemble {
+    /// Learns the weights, then fits every member on the whole series. Members that
+    /// fail the final fit get no weight; the fit fails only if all do.
+    fn fit(&mut self, series: &TimeSeries<f64>) -> Result<(), Error> {
+        trace_span!("fit", model = "ensemble", members = self.members.len(), n = series.len());
+        let mut weights = self.learn_weights(series)?;
+        let mut first_error = None;
+        for (m, w) in self.members.iter_mut().zip(&mut weights) {
+            if let Err(e) = m.fit(series) {
+                *w = 0.;
+                first_error.get_or_insert(e);
+            }
+        }
+        let total: f64 = weights.iter().sum();
+        if total == 0. {
+            self.weights.clear();
+            return Err(first_error.unwrap_or(Error::InvalidInput("no member could be weighted".to_string())));
+        }
+        self.weights = weights.into_iter().map(|w| w / total).collect();
+        Ok(())
+    }
+
+    fn predict(&self, horizon: usize) -> TimeSeries<f64> {
+        if self.weights.is_empty() {
+            return TimeSeries(vec![f64::NAN; horizon]);
+        }
+        let mut out = vec![0.; horizon];
+        for (m, &w) in self.members.iter().zip(&self.weights).filter(|(_, &w)| w > 0.) {
+            for (o, p) in out.iter_mut().zip(m.predict(horizon).into_vec()) {
+                *o += w * p;
+            }
+        }
+        TimeSeries(out)
+    }
+
+    fn predict_quantiles(&self, horizon: usize, quantiles: &[f64]) -> Vec<TimeSeries<f64>> {
+        if self.weights.is_empty() {
+            return vec![TimeSeries(vec![f64::NAN; horizon]); quantiles.len()];
+        }
+        let mut out = vec![vec![0.; horizon]; quantiles.len()];
+        for (m, &w) in self.members.iter().zip(&self.weights).filter(|(_, &w)| w > 0.) {
+            for (o, q) in out.iter_mut().zip(m.predict_quantiles(horizon, quantiles)) {
+                for (o, p) in o.iter_mut().zip(q.into_vec()) {
+                    *o += w * p;
+                }
+            }
+        }
+        out.into_iter().map(TimeSeries).collect()
+    }
+}
+
+/// The outcome of [`auto_select`].
+pub struct Selection {
+    /// The best candidate, fitted on the whole series.
+    pub model: Box<dyn Forecaster>,
+    /// Its position among the candidates.
+    pub index: usize,
+    /// Mean squared error of every candidate over the folds; infinite for candidates
+    /// that failed to fit on some fold.
+    pub scores: Vec<f64>,
+}
+
+/// Picks the candidate with the lowest rolling-origin cross-validation error.
+///
+/// Each of the `folds` origins fits on the series up to the origin and scores the next
+/// `horizon` points; the last origin leaves exactly `horizon` points. The winner is
+/// refitted on the whole series.
+///
+/// Fails if the series cannot hold the folds, or no candidate fits on every fold.
+///
+/// # Panics
+/// Panics if `horizon` or `folds` is zero.
+pub fn auto_select(
+    series: &TimeSeries<f64>,
+    mut candidates: Vec<Box<dyn Forecaster>>,
+    horizon: usize,
+    folds: usize,
+) -> Result<Selection, Error> {
+    assert!(horizon > 0 && folds > 0, "horizon and folds must be positive");
+    trace_span!("auto_select", candidates = candidates.len(), n = series.len(), horizon, folds);
+    let n = series.len();
+    let required = horizon * folds + 1;
+    if n < required {
+        return Err(Error::InsufficientData { required, found: n });
+    }
+    let origins: Vec<usize> = (0..folds).map(|k| n - horizon * (folds - k)).collect();
+    let scores: Vec<f64> = candidates
+        .iter_mut()
+        .map(|m| {
+            let mut sse = 0.;
+            for &origin in &origins {
+                if m.fit(&series.slice(0..origin)).is_err() {
+                    return f64::INFINITY;
+                }
+                let actual = &series.as_slice()[origin..origin + horizon];
+                sse += m.predict(horizon).as_slice().iter().zip(actual).map(|(p, a)| (p - a).powi(2)).sum::<f64>();
+            }
+            let mse = sse / (horizon * folds) as f64;
+            if mse.is_nan() {
+                f64::INFINITY
+            } else {
+                mse
+            }
+        })
+        .collect();
+    let index = (0..scores.len())
+        .filter(|&i| scores[i].is_finite())
+        .min_by(|&a, &b| scores[a].total_cmp(&scores[b]))
+        .ok_or_else(|| Error::InvalidInput("no candidate fitted on every fold".to_string()))?;
+    let mut model = candidates.swap_remove(index);
+    model.fit(series)?;
+    Ok(Selection { model, index, scores })
+}
@@ -0,0 +1,275 @@
+//! Serde support for series, and a compact self-describing [`Envelope`] for exchanging
+//! them in binary formats (CBOR with the `cbor` feature, MessagePack with `msgpack`)
+//! over constrained links.
+//!
+//! A [`TimeSeries`] serializes as a sequence of values and a [`TimestampedSeries`] as a
+//! map of `timestamps`, `values` and `unit`. The envelope adds the element type, length
+//! and index type, so a receiver can check what it got before trusting it.
+
+use crate::{Error, TimeSeries, TimeUnit, TimestampedSeries};
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+use serde::de::DeserializeOwned;
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+impl<T: Serialize> Serialize for TimeSeries<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_slice().serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for TimeSeries<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(TimeSeries)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct TimestampedRepr<T> {
+    timestamps: Vec<i64>,
+    values: Vec<T>,
+    #[serde(default)]
+    unit: Option<TimeUnit>,
+}
+
+impl<T: Serialize> Serialize for TimestampedSeries<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Repr<'a, T> {
+            timestamps: &'a [i64],
+            values: &'a [T],
+            unit: Option<TimeUnit>,
+        }
+        Repr { timestamps: self.timestamps(), values: self.values().as_slice(), unit: self.unit() }.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for TimestampedSeries<T> {
+    /// Rejects mismatched lengths and unsorted timestamps.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = TimestampedRepr::deserialize(deserializer)?;
+        checked_parts(repr.timestamps, repr.values, repr.unit).map_err(de::Error::custom)
+    }
+}
+
+fn checked_parts<T>(
+    timestamps: Vec<i64>,
+    values: Vec<T>,
+    unit: Option<TimeUnit>,
+) -> Result<TimestampedSeries<T>, Error> {
+    if timestamps.len() != values.len() {
+        return Err(Error::LengthMismatch { expected: timestamps.len(), found: values.len() });
+    }
+    if timestamps.windows(2).any(|w| w[0] > w[1]) {
+        return Err(Error::InvalidInput("timestamps are not sorted".to_string()));
+    }
+    let series = TimestampedSeries::from_parts(timestamps, TimeSeries(values));
+    Ok(match unit {
+        Some(unit) => series.with_unit(unit),
+        None => series,
+    })
+}
+
+/// Type of the values in an [`Envelope`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElementType {
+    F64,
+    F32,
+    I64,
+    I32,
+    I16,
+    I8,
+    U64,
+    U32,
+    U16,
+    U8,
+    Bool,
+    Str,
+}
+
+/// A value type that can travel in an [`Envelope`].
+pub trait Element {
+    const TYPE: ElementType;
+}
+
+macro_rules! element {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(impl Element for $ty {
+            const TYPE: ElementType = ElementType::$variant;
+        })*
+    };
+}
+
+element!(
+    f64 => F64, f32 => F32, i64 => I64, i32 => I32, i16 => I16, i8 => I8,
+    u64 => U64, u32 => U32, u16 => U16, u8 => U8, bool => Bool, String => Str,
+);
+
+/// How the values of an [`Envelope`] are indexed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexType {
+    /// By position, as in a [`TimeSeries`].
+    Positional,
+    /// By timestamp, as in a [`TimestampedSeries`], in `unit` if it is known.
+    Timestamps { unit: Option<TimeUnit> },
+}
+
+/// A series with a header describing it. Field names are kept short since the envelope
+/// is meant for constrained links: `v` (format version), `type`, `len`, `index`, `t`
+/// (timestamps, when indexed by them) and `x` (values).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Envelope<T> {
+    #[serde(rename = "v")]
+    pub version: u8,
+    #[serde(rename = "type")]
+    pub element: ElementType,
+    pub len: u64,
+    pub index: IndexType,
+    #[serde(rename = "t", default, skip_serializing_if = "Option::is_none")]
+    pub timestamps: Option<Vec<i64>>,
+    #[serde(rename = "x")]
+    pub values: Vec<T>,
+}
+
+impl<T: Element + Clone> Envelope<T> {
+    pub const VERSION: u8 = 1;
+
+    pub fn from_series(series: &TimeSeries<T>) -> Self {
+        Self {
+            version: Self::VERSION,
+            element: T::TYPE,
+            len: series.as_slice().len() as u64,
+            index: IndexType::Positional,
+            timestamps: None,
+            values: series.as_slice().to_vec(),
+        }
+    }
+
+    pub fn from_timestamped(series: &TimestampedSeries<T>) -> Self {
+        Self {
+            version: Self::VERSION,
+            element: T::TYPE,
+            len: series.len() as u64,
+            index: IndexType::Timestamps { unit: series.unit() },
+            timestamps: Some(series.timestamps().to_vec()),
+            values: series.values().as_slice().to_vec(),
+        }
+    }
+
+    /// Checks the header against the payload and the element type `T`.
+    fn check(&self) -> Result<(), Error> {
+        if self.version != Self::VERSION {
+            return Err(Error::InvalidInput(format!("unsupported envelope version {}", self.version)));
+        }
+        if self.element != T::TYPE {
+            return Err(Error::InvalidInput(format!("expected {:?} values, found {:?}", T::TYPE, self.element)));
+        }
+        if self.len != self.values.len() as u64 {
+            return Err(Error::LengthMismatch { expected: self.len as usize, found: self.values.len() });
+        }
+        Ok(())
+    }
+
+    pub fn into_series(self) -> Result<TimeSeries<T>, Error> {
+        self.check()?;
+        match self.index {
+            IndexType::Positional => Ok(TimeSeries(self.values)),
+            IndexType::Timestamps { .. } => Err(Error::InvalidInput("envelope holds a timestamped series".to_string())),
+        }
+    }
+
+    pub fn into_timestamped(self) -> Result<TimestampedSeries<T>, Error> {
+        self.check()?;
+        match (self.index, self.timestamps) {
+            (IndexType::Timestamps { unit }, Some(timestamps)) => checked_parts(timestamps, self.values, unit),
+            _ => Err(Error::InvalidInput("envelope holds no timestamps".to_string())),
+        }
+    }
+
+    /// The envelope as CBOR.
+    #[cfg(feature = "cbor")]
+    pub fn to_cbor(&self) -> Vec<u8>
+    where
+        T: Serialize,
+    {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(self, &mut bytes).expect("writing to a Vec cannot fail");
+        bytes
+    }
+
+    #[cfg(feature = "cbor")]
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, Error>
+    where
+        T: DeserializeOwned,
+    {
+        ciborium::de::from_reader(bytes).map_err(|e| Error::InvalidInput(e.to_string()))
+    }
+
+    /// The envelope as MessagePack, with field names so that it stays self-describing.
+    #[cfg(feature = "msgpack")]
+    pub fn to_msgpack(&self) -> Vec<u8>
+    where
+        T: Serialize,
+    {
+        rmp_serde::to_vec_named(self).expect("envelopes always serialize")
+    }
+
+    #[cfg(feature = "msgpack")]
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, Error>
+    where
+        T: DeserializeOwned,
+    {
+        rmp_serde::from_slice(bytes).map_err(|e| Error::InvalidInput(e.to_string()))
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl<T: Element + Clone + Serialize + DeserializeOwned> TimeSeries<T> {
+    /// The series in a CBOR [`Envelope`].
+    pub fn to_cbor(&self) -> Vec<u8> {
+        Envelope::from_series(self).to_cbor()
+    }
+
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, Error> {
+        Envelope::from_cbor(bytes)?.into_series()
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl<T: Element + Clone + Serialize + DeserializeOwned> TimestampedSeries<T> {
+    /// The series in a CBOR [`Envelope`].
+    pub fn to_cbor(&self) -> Vec<u8> {
+        Envelope::from_timestamped(self).to_cbor()
+    }
+
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, Error> {
+        Envelope::from_cbor(bytes)?.into_timestamped()
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl<T: Element + Clone + Serialize + DeserializeOwned> TimeSeries<T> {
+    /// The series in a MessagePack [`Envelope`].
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        Envelope::from_series(self).to_msgpack()
+    }
+
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, Error> {
+        Envelope::from_msgpack(bytes)?.into_series()
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl<T: Element + Clone + Serialize + DeserializeOwned> TimestampedSeries<T> {
+    /// The series in a MessagePack [`Envelope`].
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        Envelope::from_timestamped(self).to_msgpack()
+    }
+
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, Error> {
+        Envelope::from_msgpack(bytes)?.into_timestamped()
+    }
+}
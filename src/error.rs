@@ -0,0 +1,38 @@
+//! Error type shared by the fallible operations of the crate.
+
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// A series required by the operation was not supplied.
+    MissingSeries(String),
+    /// Series that have to be aligned point by point have different lengths.
+    LengthMismatch { expected: usize, found: usize },
+    /// The series is too short for the operation.
+    InsufficientData { required: usize, found: usize },
+    /// A matrix that has to be inverted is singular.
+    Singular,
+    /// Values fall outside the domain the operation accepts.
+    InvalidInput(String),
+    /// The operation was stopped through a [`CancellationToken`](crate::CancellationToken).
+    Cancelled,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingSeries(name) => write!(f, "missing series `{}`", name),
+            Error::LengthMismatch { expected, found } => {
+                write!(f, "length mismatch: expected {}, found {}", expected, found)
+            }
+            Error::InsufficientData { required, found } => {
+                write!(f, "insufficient data: need at least {} points, got {}", required, found)
+            }
+            Error::Singular => write!(f, "singular matrix"),
+            Error::InvalidInput(reason) => write!(f, "invalid input: {}", reason),
+            Error::Cancelled => write!(f, "operation cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
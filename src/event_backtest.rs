@@ -0,0 +1,208 @@
+//! Event-driven backtesting: a strategy sees each instant of several synchronized feeds
+//! and places orders, which fill against a price series after a latency and at a cost.
+//!
+//! Unlike [`equity_curve`](crate::equity_curve), which takes a finished position series,
+//! the strategy here decides as events arrive and can react to any feed, e.g. signals or
+//! news ticking at other times than the price. Orders fill at the price in force when
+//! they become due, moved against the trader by the slippage, and pay the commission on
+//! the filled notional.
+
+use crate::{CostModel, SyncCursor, SyncStep, TimeSeries, TimestampedSeries};
+
+/// A filled order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Execution {
+    /// Units bought, negative for a sale.
+    pub quantity: f64,
+    /// Fill price, including slippage.
+    pub price: f64,
+    /// Commission paid.
+    pub commission: f64,
+}
+
+/// What a strategy can see of, and do to, its account while handling an event.
+#[derive(Debug)]
+pub struct Broker {
+    timestamp: i64,
+    price: Option<f64>,
+    position: f64,
+    cash: f64,
+    latency: i64,
+    /// Orders placed but not yet filled, as `(due, quantity)`.
+    pending: Vec<(i64, f64)>,
+}
+
+impl Broker {
+    /// The time of the event being handled.
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// The price in force, `None` before the first price.
+    pub fn price(&self) -> Option<f64> {
+        self.price
+    }
+
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    pub fn cash(&self) -> f64 {
+        self.cash
+    }
+
+    /// Cash plus the position at the price in force.
+    pub fn equity(&self) -> f64 {
+        self.cash + self.position * self.price.unwrap_or(0.)
+    }
+
+    /// The position once every pending order has filled.
+    pub fn target_position(&self) -> f64 {
+        self.position + self.pending.iter().map(|&(_, q)| q).sum::<f64>()
+    }
+
+    /// Orders `quantity` units, negative to sell, to fill after the latency.
+    pub fn order(&mut self, quantity: f64) {
+        if quantity != 0. {
+            self.pending.push((self.timestamp.saturating_add(self.latency), quantity));
+        }
+    }
+
+    /// Orders the difference between `position` and the
+    /// [target position](Broker::target_position).
+    pub fn order_target(&mut self, position: f64) {
+        self.order(position - self.target_position());
+    }
+}
+
+/// Decides on orders as events arrive.
+pub trait Strategy {
+    /// Handles one instant of the feeds. Series 0 of `event` is the price; the others are
+    /// the feeds in the order given to [`EventBacktest::run`].
+    fn on_event(&mut self, event: &SyncStep<'_, f64>, broker: &mut Broker);
+}
+
+impl<F> Strategy for F
+where
+    F: FnMut(&SyncStep<'_, f64>, &mut Broker),
+{
+    fn on_event(&mut self, event: &SyncStep<'_, f64>, broker: &mut Broker) {
+        self(event, broker)
+    }
+}
+
+/// Settings of an event-driven backtest.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EventBacktest {
+    initial_capital: f64,
+    /// Delay between placing and filling an order, in timestamp units.
+    latency: i64,
+    costs: CostModel,
+}
+
+#[derive(Clone, Debug)]
+pub struct EventBacktestResult {
+    /// Every fill, at its fill time.
+    pub executions: TimestampedSeries<Execution>,
+    /// Equity at each event once the price is known, after that event's fills.
+    pub equity: TimestampedSeries<f64>,
+    /// Position at the same events as `equity`.
+    pub positions: TimestampedSeries<f64>,
+    /// Orders still pending when the feeds ran out, as `(due, quantity)`.
+    pub unfilled: Vec<(i64, f64)>,
+}
+
+impl EventBacktestResult {
+    /// Return of each event over the previous one, for [`Performance`](crate::Performance)
+    /// and [`Drawdown`](crate::Drawdown).
+    pub fn returns(&self) -> TimeSeries<f64> {
+        TimeSeries(self.equity.values().as_slice().windows(2).map(|w| w[1] / w[0] - 1.).collect())
+    }
+}
+
+impl EventBacktest {
+    /// A backtest without latency or costs.
+    pub fn new(initial_capital: f64) -> Self {
+        Self { initial_capital, latency: 0, costs: CostModel::default() }
+    }
+
+    /// # Panics
+    /// Panics if `latency` is negative.
+    pub fn with_latency(self, latency: i64) -> Self {
+        assert!(latency >= 0, "latency must not be negative");
+        Self { latency, ..self }
+    }
+
+    pub fn with_costs(self, costs: CostModel) -> Self {
+        Self { costs, ..self }
+    }
+
+    /// Replays `price` and `feeds` in time order through `strategy`.
+    ///
+    /// Orders fill at the first event at or after they are due, at the price in force
+    /// when they became due; an order placed without latency fills at once. Orders due
+    /// before the first price fill at it.
+    pub fn run<S>(
+        &self,
+        price: &TimestampedSeries<f64>,
+        feeds: &[&TimestampedSeries<f64>],
+        strategy: &mut S,
+    ) -> EventBacktestResult
+    where
+        S: Strategy,
+    {
+        trace_span!("event_backtest", prices = price.len(), feeds = feeds.len());
+        let mut broker = Broker {
+            timestamp: i64::MIN,
+            price: None,
+            position: 0.,
+            cash: self.initial_capital,
+            latency: self.latency,
+            pending: Vec::new(),
+        };
+        let mut executions = TimestampedSeries::new();
+        let mut equity = TimestampedSeries::new();
+        let mut positions = TimestampedSeries::new();
+        for event in SyncCursor::new(std::iter::once(price).chain(feeds.iter().copied())) {
+            broker.timestamp = event.timestamp;
+            broker.price = event.value(0).copied();
+            self.fill_due(&mut broker, price, &mut executions);
+            strategy.on_event(&event, &mut broker);
+            self.fill_due(&mut broker, price, &mut executions);
+            if broker.price.is_some() {
+                equity.push(event.timestamp, broker.equity());
+                positions.push(event.timestamp, broker.position);
+            }
+        }
+        let (equity, positions, executions) = match price.unit() {
+            Some(unit) => (equity.with_unit(unit), positions.with_unit(unit), executions.with_unit(unit)),
+            None => (equity, positions, executions),
+        };
+        EventBacktestResult { executions, equity, positions, unfilled: broker.pending }
+    }
+
+    fn fill_due(
+        &self,
+        broker: &mut Broker,
+        price: &TimestampedSeries<f64>,
+        executions: &mut TimestampedSeries<Execution>,
+    ) {
+        let now = broker.timestamp;
+        let mut waiting = Vec::new();
+        for (due, quantity) in std::mem::take(&mut broker.pending) {
+            let in_force = if due <= now { price.asof(due).copied().or(broker.price) } else { None };
+            match in_force {
+                Some(p) => {
+                    let fill = p * (1. + quantity.signum() * self.costs.slippage);
+                    let commission = (quantity * fill).abs() * self.costs.commission;
+                    broker.position += quantity;
+                    broker.cash -= quantity * fill + commission;
+                    executions.push(now, Execution { quantity, price: fill, commission });
+                    trace_event!(timestamp = now, quantity, price = fill, "order filled");
+                }
+                None => waiting.push((due, quantity)),
+            }
+        }
+        broker.pending = waiting;
+    }
+}
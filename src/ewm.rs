@@ -0,0 +1,83 @@
+//! Exponentially weighted moving statistics.
+//!
+//! All statistics use the recursive (non-adjusted) form: the first observation seeds the
+//! average and each following point is blended in with weight `alpha`.
+
+use crate::TimeSeries;
+
+pub trait Ewm {
+    /// Exponentially weighted moving average.
+    fn ewm_mean(&self, alpha: f64) -> Self;
+    /// Exponentially weighted moving variance.
+    fn ewm_var(&self, alpha: f64) -> Self;
+    /// Exponentially weighted moving covariance with another series of the same length.
+    fn ewm_cov(&self, other: &Self, alpha: f64) -> Self;
+    /// Exponentially weighted moving correlation with another series of the same length.
+    /// The first point is NaN because the variances are still zero there.
+    fn ewm_corr(&self, other: &Self, alpha: f64) -> Self;
+}
+
+/// Running state of the exponentially weighted mean of two series and their covariance.
+struct EwmState {
+    alpha: f64,
+    mean_x: f64,
+    mean_y: f64,
+    cov: f64,
+}
+
+impl EwmState {
+    fn new(alpha: f64, x: f64, y: f64) -> Self {
+        assert!(alpha > 0. && alpha <= 1., "alpha must be in (0, 1]");
+        Self { alpha, mean_x: x, mean_y: y, cov: 0. }
+    }
+
+    fn update(&mut self, x: f64, y: f64) {
+        let dx = x - self.mean_x;
+        let dy = y - self.mean_y;
+        self.mean_x += self.alpha * dx;
+        self.mean_y += self.alpha * dy;
+        self.cov = (1. - self.alpha) * (self.cov + self.alpha * dx * dy);
+    }
+}
+
+fn ewm_scan<F>(x: &TimeSeries<f64>, y: &TimeSeries<f64>, alpha: f64, f: F) -> TimeSeries<f64>
+where
+    F: Fn(&EwmState) -> f64,
+{
+    let mut pairs = x.as_slice().iter().zip(y.as_slice().iter());
+    let mut out = Vec::with_capacity(x.len().min(y.len()));
+    if let Some((&x0, &y0)) = pairs.next() {
+        let mut state = EwmState::new(alpha, x0, y0);
+        out.push(f(&state));
+        for (&xi, &yi) in pairs {
+            state.update(xi, yi);
+            out.push(f(&state));
+        }
+    }
+    TimeSeries(out)
+}
+
+impl Ewm for TimeSeries<f64> {
+    fn ewm_mean(&self, alpha: f64) -> Self {
+        ewm_scan(self, self, alpha, |s| s.mean_x)
+    }
+
+    fn ewm_var(&self, alpha: f64) -> Self {
+        ewm_scan(self, self, alpha, |s| s.cov)
+    }
+
+    fn ewm_cov(&self, other: &Self, alpha: f64) -> Self {
+        ewm_scan(self, other, alpha, |s| s.cov)
+    }
+
+    fn ewm_corr(&self, other: &Self, alpha: f64) -> Self {
+        let cov = self.ewm_cov(other, alpha);
+        let var_x = self.ewm_var(alpha);
+        let var_y = other.ewm_var(alpha);
+        cov.as_slice()
+            .iter()
+            .zip(var_x.as_slice().iter().zip(var_y.as_slice().iter()))
+            .map(|(c, (vx, vy))| c / (vx * vy).sqrt())
+            .collect()
+    }
+}
@@ -0,0 +1,109 @@
+//! Forecasting with exogenous regressors such as promotions, weather or calendar dummies.
+//!
+//! [`WithRegressors`] regresses the series on the regressors by least squares and models
+//! what the regression leaves with any [`Forecaster`]: regression with ARIMA errors
+//! around an [`Arima`](crate::Arima), or the same with exponential smoothing or a
+//! [`Dlm`](crate::Dlm). The two stages are fitted in turn rather than jointly. Forecasts
+//! need the regressors' future values, one series per regressor covering the horizon.
+
+use crate::linalg::least_squares;
+use crate::{Error, Forecaster, TimeSeries};
+
+/// A forecaster of the part of the series its regressors do not explain.
+#[derive(Clone, Debug)]
+pub struct WithRegressors<F> {
+    model: F,
+    /// Intercept, then one coefficient per regressor.
+    coefficients: Vec<f64>,
+}
+
+fn check_finite(regressors: &[&TimeSeries<f64>]) -> Result<(), Error> {
+    match regressors.iter().position(|r| r.as_slice().iter().any(|x| !x.is_finite())) {
+        Some(i) => Err(Error::InvalidInput(format!("regressor {} has non-finite values", i))),
+        None => Ok(()),
+    }
+}
+
+impl<F: Forecaster> WithRegressors<F> {
+    pub fn new(model: F) -> Self {
+        Self { model, coefficients: Vec::new() }
+    }
+
+    /// The forecaster of the regression residuals.
+    pub fn model(&self) -> &F {
+        &self.model
+    }
+
+    /// The intercept, then one coefficient per regressor; empty before fitting.
+    pub fn coefficients(&self) -> &[f64] {
+        &self.coefficients
+    }
+
+    /// Fits the regression on `regressors`, aligned point by point with `series`, then
+    /// the model on its residuals.
+    ///
+    /// Fails if a regressor's length differs from the series', a value is not finite, or
+    /// the regressors are collinear.
+    pub fn fit(&mut self, series: &TimeSeries<f64>, regressors: &[&TimeSeries<f64>]) -> Result<(), Error> {
+        trace_span!("fit", model = "regressors", regressors = regressors.len(), n = series.len());
+        let n = series.len();
+        if let Some(r) = regressors.iter().find(|r| r.len() != n) {
+            return Err(Error::LengthMismatch { expected: n, found: r.len() });
+        }
+        check_finite(regressors)?;
+        if n <= regressors.len() + 1 {
+            return Err(Error::InsufficientData { required: regressors.len() + 2, found: n });
+        }
+        let design: Vec<Vec<f64>> =
+            (0..n).map(|t| std::iter::once(1.).chain(regressors.iter().map(|r| r[t])).collect()).collect();
+        let coefficients = least_squares(&design, series.as_slice()).ok_or(Error::Singular)?;
+        let residuals: TimeSeries<f64> = design
+            .iter()
+            .zip(series.as_slice())
+            .map(|(row, y)| y - row.iter().zip(&coefficients).map(|(x, b)| x * b).sum::<f64>())
+            .collect();
+        self.model.fit(&residuals)?;
+        self.coefficients = coefficients;
+        Ok(())
+    }
+
+    /// The regression part of the forecast, over the horizon the future values cover.
+    fn regression(&self, future: &[&TimeSeries<f64>]) -> Result<Vec<f64>, Error> {
+        let expected = self.coefficients.len().saturating_sub(1);
+        if future.len() != expected {
+            return Err(Error::LengthMismatch { expected, found: future.len() });
+        }
+        let horizon = future.first().map_or(0, |r| r.len());
+        if let Some(r) = future.iter().find(|r| r.len() != horizon) {
+            return Err(Error::LengthMismatch { expected: horizon, found: r.len() });
+        }
+        check_finite(future)?;
+        Ok((0..horizon)
+            .map(|h| {
+                self.coefficients[0] + future.iter().zip(&self.coefficients[1..]).map(|(r, b)| r[h] * b).sum::<f64>()
+            })
+            .collect())
+    }
+
+    /// Forecasts as many steps as the future regressor values cover.
+    ///
+    /// Fails if the number of future series differs from the number of regressors, or
+    /// their lengths differ. Before fitting, fails for any regressors.
+    pub fn predict(&self, future: &[&TimeSeries<f64>]) -> Result<TimeSeries<f64>, Error> {
+        let regression = self.regression(future)?;
+        let errors = self.model.predict(regression.len());
+        Ok(regression.iter().zip(errors.as_slice()).map(|(r, e)| r + e).collect())
+    }
+
+    /// Forecast quantiles of the model shifted by the regression, treating the
+    /// coefficients as known.
+    pub fn predict_quantiles(
+        &self,
+        future: &[&TimeSeries<f64>],
+        quantiles: &[f64],
+    ) -> Result<Vec<TimeSeries<f64>>, Error> {
+        let regression = self.regression(future)?;
+        let errors = self.model.predict_quantiles(regression.len(), quantiles);
+        Ok(errors.iter().map(|q| regression.iter().zip(q.as_slice()).map(|(r, e)| r + e).collect()).collect())
+    }
+}
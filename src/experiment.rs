@@ -0,0 +1,183 @@
+//! A/B experiment analysis: the relative lift of a treatment arm over a control arm, per
+//! calendar period and over the whole experiment, with confidence intervals.
+//!
+//! Each arm is a series of observations, one per unit and time (e.g. revenue per user
+//! per day), so several points may share a timestamp. CUPED variance reduction takes a
+//! covariate for every observation measured before the experiment started, typically
+//! the same unit's metric over a pre-period, and subtracts the part of the metric it
+//! predicts with one coefficient pooled over both arms. Randomization makes the
+//! covariate independent of the assignment, so the adjustment narrows the intervals
+//! without biasing the lift.
+
+use crate::special::{normal_cdf, normal_ppf};
+use crate::{CalendarPeriod, Error, Interval, TimeSeries, TimeSeriesFrame, TimestampedSeries};
+use std::collections::BTreeMap;
+
+/// The observations of one arm.
+#[derive(Clone, Copy, Debug)]
+pub struct Arm<'a> {
+    metric: &'a TimestampedSeries<f64>,
+    pre_period: Option<&'a TimeSeries<f64>>,
+}
+
+impl<'a> Arm<'a> {
+    pub fn new(metric: &'a TimestampedSeries<f64>) -> Self {
+        Self { metric, pre_period: None }
+    }
+
+    /// Adds the pre-experiment covariate of every observation, by position.
+    pub fn with_pre_period(self, pre_period: &'a TimeSeries<f64>) -> Self {
+        Self { pre_period: Some(pre_period), ..self }
+    }
+}
+
+/// The lift of the treatment over the control in one group of observations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lift {
+    pub treatment_mean: f64,
+    pub control_mean: f64,
+    /// `treatment_mean / control_mean - 1`.
+    pub relative: f64,
+    /// Confidence interval of `relative`, by the delta method.
+    pub interval: Interval,
+    /// Two-sided p-value of no lift.
+    pub p_value: f64,
+}
+
+impl Lift {
+    /// `z` is the normal quantile of the interval half-width.
+    fn estimate(treatment: &[f64], control: &[f64], z: f64) -> Self {
+        let (mt, vt) = mean_var(treatment);
+        let (mc, vc) = mean_var(control);
+        let relative = mt / mc - 1.;
+        let se = (vt / treatment.len() as f64 / (mc * mc) + mt * mt * vc / control.len() as f64 / mc.powi(4)).sqrt();
+        Lift {
+            treatment_mean: mt,
+            control_mean: mc,
+            relative,
+            interval: Interval::new(relative - z * se, relative + z * se),
+            p_value: 2. * normal_cdf(-(relative / se).abs()),
+        }
+    }
+}
+
+fn mean_var(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    (mean, values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.))
+}
+
+/// The outcome of [`Experiment::analyze`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExperimentReport {
+    /// Start of every period with at least two observations in each arm, oldest first.
+    pub periods: Vec<i64>,
+    /// The lift within each of `periods`.
+    pub per_period: Vec<Lift>,
+    /// The lift over every observation.
+    pub overall: Lift,
+    /// The CUPED coefficient, 0 without covariates.
+    pub theta: f64,
+    /// The share of the metric's variance removed by the adjustment.
+    pub variance_reduction: f64,
+}
+
+impl ExperimentReport {
+    /// The per-period relative lift with columns `lift`, `lower`, `upper` and `p_value`,
+    /// indexed by period start.
+    pub fn to_frame(&self) -> TimeSeriesFrame {
+        let column = |f: fn(&Lift) -> f64| self.per_period.iter().map(f).collect::<TimeSeries<f64>>();
+        let columns = [
+            ("lift", column(|l| l.relative)),
+            ("lower", column(|l| l.interval.lower)),
+            ("upper", column(|l| l.interval.upper)),
+            ("p_value", column(|l| l.p_value)),
+        ];
+        let mut frame = TimeSeriesFrame::new(self.periods.clone());
+        for (name, values) in columns {
+            frame.add_column(name, values).expect("one value per period");
+        }
+        frame
+    }
+}
+
+/// Settings of the analysis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Experiment {
+    period: CalendarPeriod,
+    level: f64,
+}
+
+impl Experiment {
+    /// Reports each `period` separately, with 95% intervals.
+    pub fn new(period: CalendarPeriod) -> Self {
+        Self { period, level: 0.95 }
+    }
+
+    /// # Panics
+    /// Panics unless `0 < level < 1`.
+    pub fn with_level(self, level: f64) -> Self {
+        assert!(level > 0. && level < 1., "level must be between 0 and 1");
+        Self { level, ..self }
+    }
+
+    /// Compares the arms, with CUPED if both have a pre-period covariate.
+    ///
+    /// Fails if only one arm has a covariate, a covariate's length differs from its
+    /// metric's, or an arm has fewer than two observations.
+    pub fn analyze(&self, treatment: &Arm, control: &Arm) -> Result<ExperimentReport, Error> {
+        for arm in [treatment, control] {
+            if arm.metric.len() < 2 {
+                return Err(Error::InsufficientData { required: 2, found: arm.metric.len() });
+            }
+            if let Some(pre) = arm.pre_period.filter(|pre| pre.len() != arm.metric.len()) {
+                return Err(Error::LengthMismatch { expected: arm.metric.len(), found: pre.len() });
+            }
+        }
+        let (theta, center) = match (treatment.pre_period, control.pre_period) {
+            (Some(t), Some(c)) => {
+                let y = [treatment.metric.values().as_slice(), control.metric.values().as_slice()].concat();
+                let x = [t.as_slice(), c.as_slice()].concat();
+                let n = y.len() as f64;
+                let (mx, vx) = mean_var(&x);
+                let my = y.iter().sum::<f64>() / n;
+                let cov = x.iter().zip(&y).map(|(a, b)| (a - mx) * (b - my)).sum::<f64>() / (n - 1.);
+                (if vx > 0. { cov / vx } else { 0. }, mx)
+            }
+            (None, None) => (0., 0.),
+            _ => return Err(Error::InvalidInput("both arms or neither need a pre-period covariate".to_string())),
+        };
+        let adjusted = |arm: &Arm| -> Vec<f64> {
+            match arm.pre_period {
+                Some(pre) => arm.metric.values().as_slice().iter().zip(pre.as_slice()).map(|(y, x)| y - theta * (x - center)).collect(),
+                None => arm.metric.values().as_slice().to_vec(),
+            }
+        };
+        let (t_adjusted, c_adjusted) = (adjusted(treatment), adjusted(control));
+        let pooled_var = |a: &[f64], b: &[f64]| mean_var(&[a, b].concat()).1;
+        let raw_var = pooled_var(treatment.metric.values().as_slice(), control.metric.values().as_slice());
+        let variance_reduction = 1. - pooled_var(&t_adjusted, &c_adjusted) / raw_var;
+
+        let z = normal_ppf(0.5 + self.level / 2.);
+        let by_period = |arm: &Arm, values: Vec<f64>| {
+            let mut groups: BTreeMap<i64, Vec<f64>> = BTreeMap::new();
+            for (&t, v) in arm.metric.timestamps().iter().zip(values) {
+                groups.entry(self.period.floor(t)).or_default().push(v);
+            }
+            groups
+        };
+        let overall = Lift::estimate(&t_adjusted, &c_adjusted, z);
+        let (t_groups, mut c_groups) = (by_period(treatment, t_adjusted), by_period(control, c_adjusted));
+        let (mut periods, mut per_period) = (Vec::new(), Vec::new());
+        for (start, t_values) in t_groups {
+            match c_groups.remove(&start) {
+                Some(c_values) if t_values.len() >= 2 && c_values.len() >= 2 => {
+                    periods.push(start);
+                    per_period.push(Lift::estimate(&t_values, &c_values, z));
+                }
+                _ => {}
+            }
+        }
+        Ok(ExperimentReport { periods, per_period, overall, theta, variance_reduction })
+    }
+}
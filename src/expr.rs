@@ -0,0 +1,407 @@
+//! A small expression language for series derived from the columns of a frame.
+//!
+//! Expressions combine column names and numbers with `+ - * / ^`, unary minus,
+//! parentheses and function calls, e.g. `(close - open) / open` or `zscore(x, 30)`.
+//! Numbers broadcast against series, and every result has one value per frame row:
+//! windowed functions are NaN until their window fills, like the rolling statistics.
+//!
+//! Functions: `abs`, `sqrt`, `log`, `exp` of one argument; `lag(x, k)`, `diff(x, k)`;
+//! trailing-window `mean(x, w)`, `std(x, w)`, `min(x, w)`, `max(x, w)`, `zscore(x, w)`;
+//! and `ewma(x, alpha)`.
+
+use crate::rolling::{Agg, Windowed};
+use crate::{Error, Ewm, TimeSeries, TimeSeriesFrame};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+impl BinaryOp {
+    fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            BinaryOp::Add => a + b,
+            BinaryOp::Sub => a - b,
+            BinaryOp::Mul => a * b,
+            BinaryOp::Div => a / b,
+            BinaryOp::Pow => a.powf(b),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Number(f64),
+    Column(String),
+    Negate(Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(String, Vec<Node>),
+}
+
+/// A parsed expression, reusable against any frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expr {
+    root: Node,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, Error> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // Exponent, e.g. `1e-3`.
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text.parse().map_err(|_| parse_error(start, &format!("invalid number `{}`", text)))?;
+            tokens.push((start, Token::Number(value)));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((start, Token::Ident(chars[start..i].iter().collect())));
+        } else if "+-*/^(),".contains(c) {
+            tokens.push((i, Token::Op(c)));
+            i += 1;
+        } else {
+            return Err(parse_error(i, &format!("unexpected character `{}`", c)));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Limits that keep parsing and evaluation of untrusted expressions within a thread's
+/// stack: nesting of parentheses, calls, unary minus and powers, and the number of tokens,
+/// which bounds the depth of `+ - * /` chains.
+const MAX_DEPTH: usize = 64;
+const MAX_TOKENS: usize = 1024;
+
+fn parse_error(position: usize, message: &str) -> Error {
+    Error::InvalidInput(format!("at {}: {}", position, message))
+}
+
+/// Recursive-descent parser; precedence from loosest: `+ -`, `* /`, unary `-`, `^`
+/// (right-associative).
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+    depth: usize,
+}
+
+impl Parser {
+    /// Runs `f` one nesting level deeper, failing past [`MAX_DEPTH`].
+    fn nested(&mut self, f: impl FnOnce(&mut Self) -> Result<Node, Error>) -> Result<Node, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(parse_error(self.position(), &format!("nested more than {} levels deep", MAX_DEPTH)));
+        }
+        self.depth += 1;
+        let node = f(self);
+        self.depth -= 1;
+        node
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(p, _)| *p)
+    }
+
+    fn eat(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: char) -> Result<(), Error> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            Err(parse_error(self.position(), &format!("expected `{}`", op)))
+        }
+    }
+
+    fn sum(&mut self) -> Result<Node, Error> {
+        let mut node = self.product()?;
+        loop {
+            let op = if self.eat('+') {
+                BinaryOp::Add
+            } else if self.eat('-') {
+                BinaryOp::Sub
+            } else {
+                return Ok(node);
+            };
+            node = Node::Binary(op, Box::new(node), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Node, Error> {
+        let mut node = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                BinaryOp::Mul
+            } else if self.eat('/') {
+                BinaryOp::Div
+            } else {
+                return Ok(node);
+            };
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, Error> {
+        if self.eat('-') {
+            return Ok(Node::Negate(Box::new(self.nested(Self::unary)?)));
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Node, Error> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            return Ok(Node::Binary(BinaryOp::Pow, Box::new(base), Box::new(self.nested(Self::unary)?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Node, Error> {
+        let position = self.position();
+        match self.tokens.get(self.pos).map(|(_, t)| t.clone()) {
+            Some(Token::Number(x)) => {
+                self.pos += 1;
+                Ok(Node::Number(x))
+            }
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                if !self.eat('(') {
+                    return Ok(Node::Column(name));
+                }
+                let mut args = vec![self.nested(Self::sum)?];
+                while self.eat(',') {
+                    args.push(self.nested(Self::sum)?);
+                }
+                self.expect(')')?;
+                Ok(Node::Call(name, args))
+            }
+            Some(Token::Op('(')) => {
+                self.pos += 1;
+                let node = self.nested(Self::sum)?;
+                self.expect(')')?;
+                Ok(node)
+            }
+            _ => Err(parse_error(position, "expected a number, column or `(`")),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Value {
+    Scalar(f64),
+    Series(Vec<f64>),
+}
+
+impl Value {
+    fn map(self, f: impl Fn(f64) -> f64) -> Value {
+        match self {
+            Value::Scalar(x) => Value::Scalar(f(x)),
+            Value::Series(v) => Value::Series(v.into_iter().map(f).collect()),
+        }
+    }
+
+    fn into_series(self, rows: usize) -> Vec<f64> {
+        match self {
+            Value::Scalar(x) => vec![x; rows],
+            Value::Series(v) => v,
+        }
+    }
+}
+
+/// Left-pads a rolling output to one value per row.
+fn pad(rolled: TimeSeries<f64>, rows: usize) -> Vec<f64> {
+    let mut out = vec![f64::NAN; rows - rolled.len()];
+    out.extend(rolled.into_vec());
+    out
+}
+
+impl Expr {
+    /// Fails on invalid syntax, and on expressions of more than 1024 tokens or nested
+    /// more than 64 levels deep.
+    pub fn parse(source: &str) -> Result<Self, Error> {
+        let tokens = tokenize(source)?;
+        if let Some((position, _)) = tokens.get(MAX_TOKENS) {
+            return Err(parse_error(*position, &format!("more than {} tokens", MAX_TOKENS)));
+        }
+        let mut parser = Parser { tokens, pos: 0, end: source.chars().count(), depth: 0 };
+        let root = parser.sum()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(parse_error(parser.position(), "unexpected trailing input"));
+        }
+        Ok(Self { root })
+    }
+
+    /// Column names the expression refers to, in order of first use.
+    pub fn columns(&self) -> Vec<&str> {
+        fn walk<'a>(node: &'a Node, out: &mut Vec<&'a str>) {
+            match node {
+                Node::Number(_) => {}
+                Node::Column(name) => {
+                    if !out.contains(&name.as_str()) {
+                        out.push(name);
+                    }
+                }
+                Node::Negate(inner) => walk(inner, out),
+                Node::Binary(_, a, b) => {
+                    walk(a, out);
+                    walk(b, out);
+                }
+                Node::Call(_, args) => args.iter().for_each(|a| walk(a, out)),
+            }
+        }
+        let mut out = Vec::new();
+        walk(&self.root, &mut out);
+        out
+    }
+
+    /// Evaluates against the columns of `frame`, giving one value per row.
+    pub fn eval(&self, frame: &TimeSeriesFrame) -> Result<TimeSeries<f64>, Error> {
+        let rows = frame.len();
+        Ok(TimeSeries(eval_node(&self.root, frame)?.into_series(rows)))
+    }
+}
+
+fn eval_node(node: &Node, frame: &TimeSeriesFrame) -> Result<Value, Error> {
+    Ok(match node {
+        Node::Number(x) => Value::Scalar(*x),
+        Node::Column(name) => {
+            Value::Series(frame.column(name).ok_or_else(|| Error::MissingSeries(name.clone()))?.as_slice().to_vec())
+        }
+        Node::Negate(inner) => eval_node(inner, frame)?.map(|x| -x),
+        Node::Binary(op, a, b) => match (eval_node(a, frame)?, eval_node(b, frame)?) {
+            (Value::Scalar(x), Value::Scalar(y)) => Value::Scalar(op.apply(x, y)),
+            (Value::Series(v), Value::Scalar(y)) => Value::Series(v.into_iter().map(|x| op.apply(x, y)).collect()),
+            (Value::Scalar(x), Value::Series(w)) => Value::Series(w.into_iter().map(|y| op.apply(x, y)).collect()),
+            (Value::Series(v), Value::Series(w)) => {
+                Value::Series(v.into_iter().zip(w).map(|(x, y)| op.apply(x, y)).collect())
+            }
+        },
+        Node::Call(name, args) => call(name, args, frame)?,
+    })
+}
+
+fn call(name: &str, args: &[Node], frame: &TimeSeriesFrame) -> Result<Value, Error> {
+    let rows = frame.len();
+    let arity = |n: usize| {
+        if args.len() == n {
+            Ok(())
+        } else {
+            Err(Error::InvalidInput(format!("`{}` takes {} argument(s), got {}", name, n, args.len())))
+        }
+    };
+    // Second arguments (windows, lags, smoothing factors) must be plain numbers.
+    let constant = |node: &Node| match eval_node(node, frame)? {
+        Value::Scalar(x) => Ok(x),
+        Value::Series(_) => Err(Error::InvalidInput(format!("`{}` needs a constant second argument", name))),
+    };
+    let count = |node: &Node, min: f64| {
+        let x = constant(node)?;
+        if x.fract() == 0. && x >= min {
+            Ok(x as usize)
+        } else {
+            Err(Error::InvalidInput(format!("`{}` needs an integer second argument of at least {}", name, min)))
+        }
+    };
+    let unary: Option<fn(f64) -> f64> = match name {
+        "abs" => Some(f64::abs),
+        "sqrt" => Some(f64::sqrt),
+        "log" => Some(f64::ln),
+        "exp" => Some(f64::exp),
+        _ => None,
+    };
+    if let Some(f) = unary {
+        arity(1)?;
+        return Ok(eval_node(&args[0], frame)?.map(f));
+    }
+    arity(2)?;
+    let x = TimeSeries(eval_node(&args[0], frame)?.into_series(rows));
+    let out = match name {
+        "lag" | "diff" => {
+            let k = count(&args[1], 0.)?.min(rows);
+            let shifted = (0..rows).map(|i| if i >= k { x[i - k] } else { f64::NAN });
+            if name == "lag" {
+                shifted.collect()
+            } else {
+                x.as_slice().iter().zip(shifted).map(|(a, b)| a - b).collect()
+            }
+        }
+        "mean" | "std" | "min" | "max" | "zscore" => {
+            let window = count(&args[1], if name == "std" || name == "zscore" { 2. } else { 1. })?;
+            if window > rows {
+                return Ok(Value::Series(vec![f64::NAN; rows]));
+            }
+            let agg = match name {
+                "mean" => Agg::Mean,
+                "min" => Agg::Min,
+                "max" => Agg::Max,
+                _ => Agg::Std,
+            };
+            if name == "zscore" {
+                let mut stats = x.rolling(window).agg(&[Agg::Mean, Agg::Std]).into_iter().map(|s| pad(s, rows));
+                let (mean, std) = (stats.next().unwrap_or_default(), stats.next().unwrap_or_default());
+                (0..rows).map(|i| (x[i] - mean[i]) / std[i]).collect()
+            } else {
+                pad(x.rolling(window).agg(&[agg]).remove(0), rows)
+            }
+        }
+        "ewma" => {
+            let alpha = constant(&args[1])?;
+            if !(alpha > 0. && alpha <= 1.) {
+                return Err(Error::InvalidInput("`ewma` needs alpha in (0, 1]".to_string()));
+            }
+            x.ewm_mean(alpha).into_vec()
+        }
+        _ => return Err(Error::InvalidInput(format!("unknown function `{}`", name))),
+    };
+    Ok(Value::Series(out))
+}
+
+impl TimeSeriesFrame {
+    /// Parses and evaluates `expression` against this frame's columns; see [`Expr`].
+    pub fn eval(&self, expression: &str) -> Result<TimeSeries<f64>, Error> {
+        Expr::parse(expression)?.eval(self)
+    }
+}
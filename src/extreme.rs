@@ -0,0 +1,234 @@
+//! Extreme value analysis: peaks over threshold with a generalized Pareto tail, block
+//! maxima with a generalized extreme value distribution, and joint tail behaviour of two
+//! series.
+
+use crate::optimize::nelder_mead;
+use crate::{Error, Statistics, TimeSeries};
+
+/// Shape parameters closer to zero than this use the exponential / Gumbel limit.
+const SHAPE_EPS: f64 = 1e-9;
+
+/// Generalized Pareto fit to the excesses over `threshold`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gpd {
+    pub threshold: f64,
+    pub scale: f64,
+    /// Tail index: positive for heavy tails, negative for a bounded tail.
+    pub shape: f64,
+    /// Fraction of observations that are (declustered) exceedances.
+    pub exceedance_rate: f64,
+}
+
+impl Gpd {
+    /// Level exceeded on average once every `period` observations.
+    pub fn return_level(&self, period: f64) -> f64 {
+        let m = period * self.exceedance_rate;
+        if self.shape.abs() < SHAPE_EPS {
+            self.threshold + self.scale * m.ln()
+        } else {
+            self.threshold + self.scale / self.shape * (m.powf(self.shape) - 1.)
+        }
+    }
+}
+
+/// Generalized extreme value fit to block maxima.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gev {
+    pub location: f64,
+    pub scale: f64,
+    pub shape: f64,
+}
+
+impl Gev {
+    /// Level exceeded on average by one block maximum in `period` blocks.
+    pub fn return_level(&self, period: f64) -> f64 {
+        let y = -(1. - 1. / period).ln();
+        if self.shape.abs() < SHAPE_EPS {
+            self.location - self.scale * y.ln()
+        } else {
+            self.location - self.scale / self.shape * (1. - y.powf(-self.shape))
+        }
+    }
+}
+
+fn gpd_neg_log_likelihood(excesses: &[f64], scale: f64, shape: f64) -> f64 {
+    let n = excesses.len() as f64;
+    if shape.abs() < SHAPE_EPS {
+        return n * scale.ln() + excesses.iter().sum::<f64>() / scale;
+    }
+    let mut sum = 0.;
+    for y in excesses {
+        let t = 1. + shape * y / scale;
+        if t <= 0. {
+            return f64::INFINITY;
+        }
+        sum += t.ln();
+    }
+    n * scale.ln() + (1. + 1. / shape) * sum
+}
+
+fn gev_neg_log_likelihood(maxima: &[f64], location: f64, scale: f64, shape: f64) -> f64 {
+    let n = maxima.len() as f64;
+    let mut sum = 0.;
+    for x in maxima {
+        let z = (x - location) / scale;
+        if shape.abs() < SHAPE_EPS {
+            sum += z + (-z).exp();
+        } else {
+            let t = 1. + shape * z;
+            if t <= 0. {
+                return f64::INFINITY;
+            }
+            sum += (1. + 1. / shape) * t.ln() + t.powf(-1. / shape);
+        }
+    }
+    n * scale.ln() + sum
+}
+
+fn mean_var(x: &[f64]) -> (f64, f64) {
+    let n = x.len() as f64;
+    let mean = x.iter().sum::<f64>() / n;
+    (mean, x.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.))
+}
+
+pub trait ExtremeValue {
+    /// Indices of the cluster peaks above `threshold`. Exceedances separated by fewer than
+    /// `run_length` points at or below the threshold form one cluster, so dependent
+    /// extremes of a single storm or crash are counted once; 0 keeps every exceedance.
+    fn peaks_over_threshold(&self, threshold: f64, run_length: usize) -> Vec<usize>;
+    /// Maximum-likelihood generalized Pareto fit to the declustered peaks over `threshold`.
+    fn fit_gpd(&self, threshold: f64, run_length: usize) -> Result<Gpd, Error>;
+    /// Maximum-likelihood generalized extreme value fit to the maxima of consecutive
+    /// blocks of `block` points (an incomplete last block is dropped).
+    fn fit_gev(&self, block: usize) -> Result<Gev, Error>;
+}
+
+impl ExtremeValue for TimeSeries<f64> {
+    fn peaks_over_threshold(&self, threshold: f64, run_length: usize) -> Vec<usize> {
+        let mut peaks: Vec<usize> = Vec::new();
+        let mut last_exceedance: Option<usize> = None;
+        for (i, &x) in self.as_slice().iter().enumerate() {
+            if x <= threshold {
+                continue;
+            }
+            match (last_exceedance, peaks.last_mut()) {
+                (Some(last), Some(peak)) if i - last <= run_length => {
+                    if x > self.as_slice()[*peak] {
+                        *peak = i;
+                    }
+                }
+                _ => peaks.push(i),
+            }
+            last_exceedance = Some(i);
+        }
+        peaks
+    }
+
+    fn fit_gpd(&self, threshold: f64, run_length: usize) -> Result<Gpd, Error> {
+        trace_span!("fit", model = "gpd", n = self.len());
+        let excesses: Vec<f64> = self
+            .peaks_over_threshold(threshold, run_length)
+            .into_iter()
+            .map(|i| self.as_slice()[i] - threshold)
+            .collect();
+        if excesses.len() < 3 {
+            return Err(Error::InsufficientData { required: 3, found: excesses.len() });
+        }
+        // Method-of-moments starting point.
+        let (m, v) = mean_var(&excesses);
+        let ratio = if v > 0. { m * m / v } else { 1. };
+        let start = [(0.5 * m * (ratio + 1.)).ln(), 0.5 * (1. - ratio)];
+        let best = nelder_mead(|p| gpd_neg_log_likelihood(&excesses, p[0].exp(), p[1]), &start, 0.2, 2000);
+        Ok(Gpd {
+            threshold,
+            scale: best[0].exp(),
+            shape: best[1],
+            exceedance_rate: excesses.len() as f64 / self.len() as f64,
+        })
+    }
+
+    fn fit_gev(&self, block: usize) -> Result<Gev, Error> {
+        trace_span!("fit", model = "gev", block, n = self.len());
+        assert!(block > 0, "block must be positive");
+        let maxima: Vec<f64> =
+            self.as_slice().chunks_exact(block).map(|c| c.iter().copied().fold(f64::NEG_INFINITY, f64::max)).collect();
+        if maxima.len() < 3 {
+            return Err(Error::InsufficientData { required: 3 * block, found: self.len() });
+        }
+        // Gumbel moments as the starting point.
+        let (m, v) = mean_var(&maxima);
+        let scale = (6. * v).sqrt() / std::f64::consts::PI;
+        let start = [m - 0.5772 * scale, scale.max(f64::MIN_POSITIVE).ln(), 0.1];
+        let best = nelder_mead(|p| gev_neg_log_likelihood(&maxima, p[0], p[1].exp(), p[2]), &start, 0.2, 3000);
+        Ok(Gev { location: best[0], scale: best[1].exp(), shape: best[2] })
+    }
+}
+
+/// Dependence between the extremes of two aligned series, over their common length.
+pub trait TailDependence {
+    /// Correlation of the points where both series lie beyond their own `quantile`:
+    /// above it for `quantile >= 0.5`, below it otherwise (Longin & Solnik). NaN when
+    /// fewer than two points qualify.
+    fn exceedance_correlation(&self, other: &Self, quantile: f64) -> f64;
+    /// Empirical upper tail dependence at level `quantile`: the probability that `other`
+    /// exceeds its `quantile` given that `self` exceeds its own.
+    fn upper_tail_dependence(&self, other: &Self, quantile: f64) -> f64;
+    /// Empirical lower tail dependence: the probability that `other` is below its
+    /// `quantile` given that `self` is below its own.
+    fn lower_tail_dependence(&self, other: &Self, quantile: f64) -> f64;
+}
+
+/// Thresholds of both series at `quantile`, over their common length.
+fn common_thresholds(
+    x: &TimeSeries<f64>,
+    y: &TimeSeries<f64>,
+    quantile: f64,
+) -> (TimeSeries<f64>, TimeSeries<f64>, f64, f64) {
+    let n = x.len().min(y.len());
+    let (x, y) = (x.slice(0..n), y.slice(0..n));
+    let (qx, qy) = (x.quantile(quantile), y.quantile(quantile));
+    (x, y, qx, qy)
+}
+
+fn conditional_fraction<F>(x: &TimeSeries<f64>, y: &TimeSeries<f64>, quantile: f64, beyond: F) -> f64
+where
+    F: Fn(f64, f64) -> bool,
+{
+    let (x, y, qx, qy) = common_thresholds(x, y, quantile);
+    let (mut given, mut joint) = (0, 0);
+    for (a, b) in x.as_slice().iter().zip(y.as_slice()) {
+        if beyond(*a, qx) {
+            given += 1;
+            if beyond(*b, qy) {
+                joint += 1;
+            }
+        }
+    }
+    joint as f64 / given as f64
+}
+
+impl TailDependence for TimeSeries<f64> {
+    fn exceedance_correlation(&self, other: &Self, quantile: f64) -> f64 {
+        let (x, y, qx, qy) = common_thresholds(self, other, quantile);
+        let upper = quantile >= 0.5;
+        let (a, b): (Vec<f64>, Vec<f64>) = x
+            .as_slice()
+            .iter()
+            .zip(y.as_slice())
+            .filter(|(a, b)| if upper { **a > qx && **b > qy } else { **a < qx && **b < qy })
+            .map(|(a, b)| (*a, *b))
+            .unzip();
+        if a.len() < 2 {
+            return f64::NAN;
+        }
+        TimeSeries(a).corr(&TimeSeries(b))
+    }
+
+    fn upper_tail_dependence(&self, other: &Self, quantile: f64) -> f64 {
+        conditional_fraction(self, other, quantile, |v, q| v > q)
+    }
+
+    fn lower_tail_dependence(&self, other: &Self, quantile: f64) -> f64 {
+        conditional_fraction(self, other, quantile, |v, q| v < q)
+    }
+}
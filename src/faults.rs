@@ -0,0 +1,146 @@
+//! Injection of typical sensor faults into a clean series, with a label for every
+//! corrupted point, to test anomaly detectors and imputation against known truth.
+//!
+//! Faults take any [`rand::Rng`]; a seeded one such as `StdRng::seed_from_u64` makes a
+//! test reproducible. They are applied in order, so a later fault overwrites the value
+//! and the label of an earlier one at the same point, except that missing (NaN) readings
+//! stay missing.
+
+use crate::TimeSeries;
+use rand::Rng;
+use std::ops::Range;
+
+/// The kind of fault that corrupted a point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FaultKind {
+    StuckAt,
+    Drift,
+    Dropout,
+    Spike,
+    Quantization,
+}
+
+/// A fault model. Episodic faults start at each point with probability `rate` and last
+/// a length drawn from `duration`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fault {
+    /// The reading freezes at its value when the episode starts.
+    StuckAt { rate: f64, duration: Range<usize> },
+    /// An offset grows by `slope` per point through the episode.
+    Drift { rate: f64, duration: Range<usize>, slope: f64 },
+    /// The readings are lost (NaN).
+    Dropout { rate: f64, duration: Range<usize> },
+    /// A single reading is off by `magnitude`, up or down at random.
+    Spike { rate: f64, magnitude: f64 },
+    /// Every reading is rounded to a multiple of `step`, as by a coarse ADC; only points
+    /// whose value changes are labeled.
+    Quantization { step: f64 },
+}
+
+impl Fault {
+    pub fn kind(&self) -> FaultKind {
+        match self {
+            Fault::StuckAt { .. } => FaultKind::StuckAt,
+            Fault::Drift { .. } => FaultKind::Drift,
+            Fault::Dropout { .. } => FaultKind::Dropout,
+            Fault::Spike { .. } => FaultKind::Spike,
+            Fault::Quantization { .. } => FaultKind::Quantization,
+        }
+    }
+}
+
+/// A corrupted series and the fault behind each point, `None` where it is clean.
+#[derive(Clone, Debug)]
+pub struct FaultInjection {
+    pub series: TimeSeries<f64>,
+    pub labels: TimeSeries<Option<FaultKind>>,
+}
+
+impl FaultInjection {
+    /// Whether each point is corrupted by any fault.
+    pub fn mask(&self) -> TimeSeries<bool> {
+        self.labels.map(Option::is_some)
+    }
+
+    /// Whether each point is corrupted by a fault of `kind`.
+    pub fn mask_of(&self, kind: FaultKind) -> TimeSeries<bool> {
+        self.labels.map(|label| *label == Some(kind))
+    }
+}
+
+/// Draws the `(start, length)` of each episode, non-overlapping, over `n` points.
+fn episodes<R: Rng + ?Sized>(rng: &mut R, n: usize, rate: f64, duration: &Range<usize>) -> Vec<(usize, usize)> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < n {
+        if rng.gen_bool(rate) {
+            let len = if duration.is_empty() { duration.start } else { rng.gen_range(duration.clone()) }.max(1);
+            out.push((i, len.min(n - i)));
+            i += len;
+        } else {
+            i += 1;
+        }
+    }
+    out
+}
+
+impl TimeSeries<f64> {
+    /// Applies `faults` in order.
+    ///
+    /// # Panics
+    /// Panics if a rate is outside `[0, 1]` or a quantization step is not positive.
+    pub fn inject_faults<R: Rng + ?Sized>(&self, faults: &[Fault], rng: &mut R) -> FaultInjection {
+        let n = self.len();
+        let mut values = self.as_slice().to_vec();
+        let mut labels = vec![None; n];
+        for fault in faults {
+            let kind = Some(fault.kind());
+            match fault {
+                Fault::StuckAt { rate, duration } => {
+                    for (start, len) in episodes(rng, n, *rate, duration) {
+                        let stuck = values[start];
+                        let points = values[start..start + len].iter_mut().zip(&mut labels[start..start + len]);
+                        for (value, label) in points.filter(|(v, _)| !v.is_nan()) {
+                            *value = stuck;
+                            *label = kind;
+                        }
+                    }
+                }
+                Fault::Drift { rate, duration, slope } => {
+                    for (start, len) in episodes(rng, n, *rate, duration) {
+                        let points = values[start..start + len].iter_mut().zip(&mut labels[start..start + len]);
+                        for (k, (value, label)) in points.enumerate().filter(|(_, (v, _))| !v.is_nan()) {
+                            *value += slope * (k + 1) as f64;
+                            *label = kind;
+                        }
+                    }
+                }
+                Fault::Dropout { rate, duration } => {
+                    for (start, len) in episodes(rng, n, *rate, duration) {
+                        values[start..start + len].fill(f64::NAN);
+                        labels[start..start + len].fill(kind);
+                    }
+                }
+                Fault::Spike { rate, magnitude } => {
+                    for (value, label) in values.iter_mut().zip(&mut labels) {
+                        if rng.gen_bool(*rate) && !value.is_nan() {
+                            *value += if rng.gen_bool(0.5) { *magnitude } else { -magnitude };
+                            *label = kind;
+                        }
+                    }
+                }
+                Fault::Quantization { step } => {
+                    assert!(*step > 0., "quantization step must be positive");
+                    for (value, label) in values.iter_mut().zip(&mut labels) {
+                        let rounded = (*value / step).round() * step;
+                        if rounded != *value && !value.is_nan() {
+                            *value = rounded;
+                            *label = kind;
+                        }
+                    }
+                }
+            }
+        }
+        FaultInjection { series: TimeSeries(values), labels: TimeSeries(labels) }
+    }
+}
@@ -0,0 +1,91 @@
+//! Fast Fourier transform of arbitrary length: iterative radix-2 for powers of two and
+//! Bluestein's chirp-z algorithm otherwise.
+
+use crate::spectral::Complex;
+use std::f64::consts::PI;
+
+/// Forward (`e^{-2πikn/N}`) transform, or the inverse without the `1/N` factor.
+pub(crate) fn fft(x: &mut [Complex], inverse: bool) {
+    let n = x.len();
+    if n <= 1 {
+        return;
+    }
+    if n.is_power_of_two() {
+        radix2(x, inverse);
+    } else {
+        bluestein(x, inverse);
+    }
+}
+
+/// Inverse transform scaled by `1/N`.
+pub(crate) fn ifft(x: &mut [Complex]) {
+    fft(x, true);
+    let scale = 1. / x.len() as f64;
+    for v in x.iter_mut() {
+        *v = v.scale(scale);
+    }
+}
+
+fn radix2(x: &mut [Complex], inverse: bool) {
+    let n = x.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            x.swap(i, j);
+        }
+    }
+    let sign = if inverse { 1. } else { -1. };
+    let mut len = 2;
+    while len <= n {
+        let w = Complex::from_polar(1., sign * 2. * PI / len as f64);
+        for chunk in x.chunks_mut(len) {
+            let mut twiddle = Complex::new(1., 0.);
+            let (lo, hi) = chunk.split_at_mut(len / 2);
+            for (a, b) in lo.iter_mut().zip(hi) {
+                let t = *b * twiddle;
+                *b = *a - t;
+                *a = *a + t;
+                twiddle = twiddle * w;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+fn bluestein(x: &mut [Complex], inverse: bool) {
+    let n = x.len();
+    let m = (2 * n - 1).next_power_of_two();
+    let sign = if inverse { 1. } else { -1. };
+    // Chirp w_k = e^{sign·iπk²/N}; k² is reduced mod 2N to keep the angle accurate.
+    let chirp: Vec<Complex> = (0..n)
+        .map(|k| {
+            let k2 = (k as u128 * k as u128 % (2 * n as u128)) as f64;
+            Complex::from_polar(1., sign * PI * k2 / n as f64)
+        })
+        .collect();
+    let mut a = vec![Complex::default(); m];
+    for (a, (x, w)) in a.iter_mut().zip(x.iter().zip(&chirp)) {
+        *a = *x * *w;
+    }
+    let mut b = vec![Complex::default(); m];
+    b[0] = chirp[0].conj();
+    for k in 1..n {
+        b[k] = chirp[k].conj();
+        b[m - k] = chirp[k].conj();
+    }
+    radix2(&mut a, false);
+    radix2(&mut b, false);
+    for (a, b) in a.iter_mut().zip(&b) {
+        *a = *a * *b;
+    }
+    ifft(&mut a);
+    for (k, v) in x.iter_mut().enumerate() {
+        *v = a[k] * chirp[k];
+    }
+}
@@ -0,0 +1,172 @@
+//! Series of a length fixed at compile time, stored inline without heap allocation.
+
+use crate::stats::quantile_sorted;
+use crate::{Error, Statistics, TimeSeries};
+use std::ops::{Add, Div, Index, IndexMut, Mul, Sub};
+
+/// `N` values backed by an array. Arithmetic between two fixed series only compiles when
+/// their lengths agree.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedSeries<T, const N: usize>(pub [T; N]);
+
+impl<T, const N: usize> FixedSeries<T, N> {
+    pub fn new(values: [T; N]) -> Self {
+        Self(values)
+    }
+
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
+
+    pub fn map<U, F>(&self, f: F) -> FixedSeries<U, N>
+    where
+        F: Fn(&T) -> U,
+    {
+        FixedSeries(std::array::from_fn(|i| f(&self.0[i])))
+    }
+}
+
+impl<T: Clone, const N: usize> FixedSeries<T, N> {
+    pub fn to_time_series(&self) -> TimeSeries<T> {
+        TimeSeries(self.0.to_vec())
+    }
+}
+
+impl<T: Default + Copy, const N: usize> Default for FixedSeries<T, N> {
+    fn default() -> Self {
+        Self([T::default(); N])
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for FixedSeries<T, N> {
+    fn from(values: [T; N]) -> Self {
+        Self(values)
+    }
+}
+
+impl<T: Clone, const N: usize> TryFrom<&TimeSeries<T>> for FixedSeries<T, N> {
+    type Error = Error;
+
+    fn try_from(series: &TimeSeries<T>) -> Result<Self, Error> {
+        if series.len() != N {
+            return Err(Error::LengthMismatch { expected: N, found: series.len() });
+        }
+        Ok(Self(std::array::from_fn(|i| series.as_slice()[i].clone())))
+    }
+}
+
+impl<T, const N: usize> Index<usize> for FixedSeries<T, N> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl<T, const N: usize> IndexMut<usize> for FixedSeries<T, N> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl<T, const N: usize> AsRef<[T]> for FixedSeries<T, N> {
+    fn as_ref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a FixedSeries<T, N> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Element-wise arithmetic between fixed series of the same length, owned or borrowed.
+macro_rules! fixed_ops {
+    ($($tr:ident $m:ident),*) => {$(
+        impl<T: Copy + $tr<Output = T>, const N: usize> $tr for FixedSeries<T, N> {
+            type Output = FixedSeries<T, N>;
+            fn $m(self, other: Self) -> Self::Output {
+                FixedSeries(std::array::from_fn(|i| self.0[i].$m(other.0[i])))
+            }
+        }
+
+        impl<T: Copy + $tr<Output = T>, const N: usize> $tr for &FixedSeries<T, N> {
+            type Output = FixedSeries<T, N>;
+            fn $m(self, other: Self) -> Self::Output {
+                (*self).$m(*other)
+            }
+        }
+    )*};
+}
+
+fixed_ops!(Add add, Sub sub, Mul mul, Div div);
+
+impl<const N: usize> Statistics for FixedSeries<f64, N> {
+    fn sum(&self) -> f64 {
+        self.0.iter().sum()
+    }
+
+    fn mean(&self) -> f64 {
+        self.sum() / N as f64
+    }
+
+    fn var(&self) -> f64 {
+        self.cov(self)
+    }
+
+    fn std(&self) -> f64 {
+        self.var().sqrt()
+    }
+
+    fn min(&self) -> f64 {
+        if N == 0 {
+            return f64::NAN;
+        }
+        self.0.iter().copied().fold(f64::INFINITY, f64::min)
+    }
+
+    fn max(&self) -> f64 {
+        if N == 0 {
+            return f64::NAN;
+        }
+        self.0.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    fn cov(&self, other: &Self) -> f64 {
+        if N < 2 {
+            return f64::NAN;
+        }
+        let (mean_x, mean_y) = (self.mean(), other.mean());
+        let s: f64 = self.0.iter().zip(&other.0).map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+        s / (N - 1) as f64
+    }
+
+    fn corr(&self, other: &Self) -> f64 {
+        self.cov(other) / (self.std() * other.std())
+    }
+
+    fn quantile(&self, q: f64) -> f64 {
+        if N == 0 {
+            return f64::NAN;
+        }
+        let mut sorted = self.0;
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        quantile_sorted(&sorted, q)
+    }
+
+    fn median(&self) -> f64 {
+        self.quantile(0.5)
+    }
+}
@@ -0,0 +1,149 @@
+//! Per-point quality flags, after the status codes of industrial historians, and
+//! aggregations that respect them.
+//!
+//! Each point of a `TimestampedSeries<Flagged>` carries a [`QualityFlag`]. A
+//! [`FlagMask`] says which flags an operation accepts; rejected points are skipped by
+//! aggregations, or read as NaN where positions must line up. Unlike `Option<f64>`, a
+//! flag survives serialization with the `serde` feature and keeps a suspect value
+//! available for review.
+
+use crate::TimestampedSeries;
+
+/// Status of a point, from most to least trusted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum QualityFlag {
+    #[default]
+    Good,
+    /// Entered or corrected by hand, e.g. a substituted value.
+    Manual,
+    /// Recorded but doubtful, e.g. out of range or from a sensor under calibration.
+    Suspect,
+    /// Not recorded; the value is usually NaN.
+    Missing,
+}
+
+impl QualityFlag {
+    const ALL: [QualityFlag; 4] = [QualityFlag::Good, QualityFlag::Manual, QualityFlag::Suspect, QualityFlag::Missing];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// A value with its quality flag.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Flagged {
+    pub value: f64,
+    pub flag: QualityFlag,
+}
+
+impl Flagged {
+    pub fn new(value: f64, flag: QualityFlag) -> Self {
+        Self { value, flag }
+    }
+
+    pub fn good(value: f64) -> Self {
+        Self::new(value, QualityFlag::Good)
+    }
+}
+
+/// The set of flags an operation accepts. The default accepts good and manual points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FlagMask(u8);
+
+impl FlagMask {
+    pub fn none() -> Self {
+        Self(0)
+    }
+
+    pub fn all() -> Self {
+        QualityFlag::ALL.into_iter().fold(Self::none(), Self::with)
+    }
+
+    /// Only good points.
+    pub fn strict() -> Self {
+        Self::none().with(QualityFlag::Good)
+    }
+
+    pub fn with(self, flag: QualityFlag) -> Self {
+        Self(self.0 | flag.bit())
+    }
+
+    pub fn without(self, flag: QualityFlag) -> Self {
+        Self(self.0 & !flag.bit())
+    }
+
+    pub fn accepts(&self, flag: QualityFlag) -> bool {
+        self.0 & flag.bit() != 0
+    }
+}
+
+impl Default for FlagMask {
+    fn default() -> Self {
+        Self::strict().with(QualityFlag::Manual)
+    }
+}
+
+impl TimestampedSeries<Flagged> {
+    /// Flags NaN values [`Missing`](QualityFlag::Missing) and every other value
+    /// [`Good`](QualityFlag::Good).
+    pub fn from_values(series: &TimestampedSeries<f64>) -> Self {
+        series.map(|&v| Flagged::new(v, if v.is_nan() { QualityFlag::Missing } else { QualityFlag::Good }))
+    }
+
+    /// Re-flags the points for which `predicate` holds, e.g. values outside a sensor's
+    /// range as [`Suspect`](QualityFlag::Suspect). Missing points stay missing.
+    pub fn flag_where<P>(&self, flag: QualityFlag, predicate: P) -> Self
+    where
+        P: Fn(i64, f64) -> bool,
+    {
+        let flagged = self.iter().map(|(t, p)| {
+            let keep = p.flag == QualityFlag::Missing || !predicate(t, p.value);
+            (t, if keep { *p } else { Flagged::new(p.value, flag) })
+        });
+        let out: Self = flagged.collect();
+        match self.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+
+    /// The accepted points only.
+    pub fn accepted(&self, mask: FlagMask) -> TimestampedSeries<f64> {
+        let out: TimestampedSeries<f64> =
+            self.iter().filter(|(_, p)| mask.accepts(p.flag)).map(|(t, p)| (t, p.value)).collect();
+        match self.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+
+    /// Every point, with rejected ones read as NaN.
+    pub fn masked(&self, mask: FlagMask) -> TimestampedSeries<f64> {
+        self.map(|p| if mask.accepts(p.flag) { p.value } else { f64::NAN })
+    }
+
+    /// Number of points with `flag`.
+    pub fn count(&self, flag: QualityFlag) -> usize {
+        self.values().as_slice().iter().filter(|p| p.flag == flag).count()
+    }
+
+    /// [`resample`](TimestampedSeries::resample) over the accepted points of each
+    /// bucket. A bucket takes the least trusted flag among the points it used, and is
+    /// [`Missing`](QualityFlag::Missing) with a NaN value if it accepted none.
+    pub fn resample_masked<F>(&self, step: i64, mask: FlagMask, agg: F) -> TimestampedSeries<Flagged>
+    where
+        F: Fn(&[f64]) -> f64,
+    {
+        self.resample(step, |points| {
+            let used: Vec<&Flagged> = points.iter().filter(|p| mask.accepts(p.flag)).collect();
+            match used.iter().map(|p| p.flag).max() {
+                Some(flag) => Flagged::new(agg(&used.iter().map(|p| p.value).collect::<Vec<_>>()), flag),
+                None => Flagged::new(f64::NAN, QualityFlag::Missing),
+            }
+        })
+    }
+}
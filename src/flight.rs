@@ -0,0 +1,430 @@
+//! Arrow Flight server and client, for moving frames between processes as Arrow record
+//! batches over gRPC rather than as JSON.
+//!
+//! A [`FlightServer`] holds frames published under a name, which is both the flight's
+//! descriptor path and its ticket, and answers the `ListFlights`, `GetFlightInfo` and
+//! `DoGet` calls of `arrow.flight.protocol.FlightService`; the other calls answer
+//! `UNIMPLEMENTED`. Published frames are kept as record batches over their own buffers
+//! (see [`TimeSeriesFrame::into_record_batch`]) and `DoGet` streams slices of them, so any
+//! Flight client can read them. [`FlightClient`] is a blocking client for the same calls.
+//!
+//! The messages below are the parts of `Flight.proto` these calls use, written out as
+//! `prost` types so that building the crate does not need `protoc`.
+
+// `tonic::Status` is the error of every gRPC call, large as it is.
+#![allow(clippy::result_large_err)]
+
+use crate::arrow_io::row_slices;
+use crate::{Error, TimeSeriesFrame, TimestampedSeries};
+use arrow_array::RecordBatch;
+use arrow_buffer::Buffer;
+use arrow_ipc::convert::try_schema_from_ipc_buffer;
+use arrow_ipc::reader::read_record_batch;
+use arrow_ipc::writer::{DictionaryTracker, IpcDataGenerator, IpcWriteOptions};
+use arrow_ipc::{root_as_message, MessageHeader};
+use arrow_schema::{Schema, SchemaRef};
+use prost::bytes::Bytes;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::net::{TcpListener, ToSocketAddrs};
+use std::sync::{Arc, RwLock};
+use tonic::codec::ProstCodec;
+use tonic::codegen::{empty_body, http, tokio_stream, Body, BoxFuture, BoxStream, Context, Poll, Service, StdError};
+use tonic::server::{Grpc, NamedService, ServerStreamingService, UnaryService};
+use tonic::transport::{server::TcpIncoming, Channel, Server};
+use tonic::{Code, Status};
+
+const SERVICE: &str = "arrow.flight.protocol.FlightService";
+const LIST_FLIGHTS: &str = "/arrow.flight.protocol.FlightService/ListFlights";
+const GET_FLIGHT_INFO: &str = "/arrow.flight.protocol.FlightService/GetFlightInfo";
+const DO_GET: &str = "/arrow.flight.protocol.FlightService/DoGet";
+
+/// Rows per streamed batch unless set with [`FlightServer::with_batch_rows`].
+pub const DEFAULT_BATCH_ROWS: usize = 65_536;
+
+/// Largest message the client accepts; a batch of [`DEFAULT_BATCH_ROWS`] rows of a few
+/// dozen columns fits.
+const MAX_MESSAGE_BYTES: usize = 64 << 20;
+
+/// `FlightDescriptor.DescriptorType.PATH`.
+const PATH: i32 = 1;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FlightDescriptor {
+    #[prost(int32, tag = "1")]
+    pub r#type: i32,
+    #[prost(bytes = "bytes", tag = "2")]
+    pub cmd: Bytes,
+    #[prost(string, repeated, tag = "3")]
+    pub path: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Ticket {
+    #[prost(bytes = "bytes", tag = "1")]
+    pub ticket: Bytes,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Criteria {
+    #[prost(bytes = "bytes", tag = "1")]
+    pub expression: Bytes,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Location {
+    #[prost(string, tag = "1")]
+    pub uri: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FlightEndpoint {
+    #[prost(message, optional, tag = "1")]
+    pub ticket: Option<Ticket>,
+    #[prost(message, repeated, tag = "2")]
+    pub location: Vec<Location>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FlightInfo {
+    #[prost(bytes = "bytes", tag = "1")]
+    pub schema: Bytes,
+    #[prost(message, optional, tag = "2")]
+    pub flight_descriptor: Option<FlightDescriptor>,
+    #[prost(message, repeated, tag = "3")]
+    pub endpoint: Vec<FlightEndpoint>,
+    #[prost(int64, tag = "4")]
+    pub total_records: i64,
+    #[prost(int64, tag = "5")]
+    pub total_bytes: i64,
+    #[prost(bool, tag = "6")]
+    pub ordered: bool,
+    #[prost(bytes = "bytes", tag = "7")]
+    pub app_metadata: Bytes,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FlightData {
+    #[prost(message, optional, tag = "1")]
+    pub flight_descriptor: Option<FlightDescriptor>,
+    #[prost(bytes = "bytes", tag = "2")]
+    pub data_header: Bytes,
+    #[prost(bytes = "bytes", tag = "3")]
+    pub app_metadata: Bytes,
+    #[prost(bytes = "bytes", tag = "1000")]
+    pub data_body: Bytes,
+}
+
+fn runtime() -> io::Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_multi_thread().enable_io().build()
+}
+
+fn transport_error(e: impl std::fmt::Display) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+/// Serves published frames as Arrow Flight streams. Clones share the published frames.
+#[derive(Clone, Debug)]
+pub struct FlightServer {
+    flights: Arc<RwLock<BTreeMap<String, RecordBatch>>>,
+    batch_rows: usize,
+}
+
+impl Default for FlightServer {
+    fn default() -> Self {
+        Self { flights: Arc::default(), batch_rows: DEFAULT_BATCH_ROWS }
+    }
+}
+
+impl FlightServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Streams frames in batches of at most `rows` rows.
+    ///
+    /// # Panics
+    /// If `rows` is zero.
+    pub fn with_batch_rows(mut self, rows: usize) -> Self {
+        assert!(rows > 0, "batch size must be positive");
+        self.batch_rows = rows;
+        self
+    }
+
+    /// Publishes `frame` under `name`, replacing any frame published under it. The frame's
+    /// buffers become the record batch served, without being copied.
+    pub fn publish(&self, name: &str, frame: TimeSeriesFrame) -> Result<(), Error> {
+        let batch = frame.into_record_batch()?;
+        self.flights.write().unwrap().insert(name.to_string(), batch);
+        Ok(())
+    }
+
+    /// Publishes a series as a frame with a `value` column.
+    pub fn publish_series(&self, name: &str, series: TimestampedSeries<f64>) -> Result<(), Error> {
+        let batch = series.into_record_batch()?;
+        self.flights.write().unwrap().insert(name.to_string(), batch);
+        Ok(())
+    }
+
+    /// Stops serving the frame published under `name`; false if there was none.
+    pub fn remove(&self, name: &str) -> bool {
+        self.flights.write().unwrap().remove(name).is_some()
+    }
+
+    /// Names of the published frames, sorted.
+    pub fn names(&self) -> Vec<String> {
+        self.flights.read().unwrap().keys().cloned().collect()
+    }
+
+    fn batch(&self, name: &[u8]) -> Result<RecordBatch, Status> {
+        let name = std::str::from_utf8(name).map_err(|_| Status::invalid_argument("ticket is not UTF-8"))?;
+        self.flights.read().unwrap().get(name).cloned().ok_or_else(|| Status::not_found(format!("no flight {name}")))
+    }
+
+    fn info(&self, name: &str, batch: &RecordBatch) -> FlightInfo {
+        let ticket = Ticket { ticket: Bytes::from(name.as_bytes().to_vec()) };
+        FlightInfo {
+            schema: encode_schema(&batch.schema()),
+            flight_descriptor: Some(FlightDescriptor { r#type: PATH, cmd: Bytes::new(), path: vec![name.to_string()] }),
+            endpoint: vec![FlightEndpoint { ticket: Some(ticket), location: Vec::new() }],
+            total_records: batch.num_rows() as i64,
+            total_bytes: batch.get_array_memory_size() as i64,
+            ordered: true,
+            app_metadata: Bytes::new(),
+        }
+    }
+
+    /// The schema message followed by one message per slice of the batch, encoded as they
+    /// are sent.
+    fn stream(&self, batch: RecordBatch) -> BoxStream<FlightData> {
+        let schema = FlightData { data_header: encode_schema_message(&batch.schema()), ..FlightData::default() };
+        let generator = IpcDataGenerator::default();
+        let options = IpcWriteOptions::default();
+        let mut tracker = DictionaryTracker::new(false);
+        let slices: Vec<RecordBatch> = row_slices(&batch, self.batch_rows).collect();
+        let messages = slices.into_iter().map(move |slice| {
+            let (_, data) = generator
+                .encoded_batch(&slice, &mut tracker, &options)
+                .map_err(|e| Status::internal(e.to_string()))?;
+            Ok(FlightData {
+                data_header: Bytes::from(data.ipc_message),
+                data_body: Bytes::from(data.arrow_data),
+                ..FlightData::default()
+            })
+        });
+        Box::pin(tokio_stream::iter(std::iter::once(Ok(schema)).chain(messages)))
+    }
+
+    /// Serves Flight calls on `addr` until the transport fails.
+    pub fn serve<A: ToSocketAddrs>(&self, addr: A) -> io::Result<()> {
+        self.serve_on(TcpListener::bind(addr)?)
+    }
+
+    /// Serves Flight calls on an already bound listener.
+    pub fn serve_on(&self, listener: TcpListener) -> io::Result<()> {
+        listener.set_nonblocking(true)?;
+        runtime()?.block_on(async {
+            let listener = tokio::net::TcpListener::from_std(listener)?;
+            let incoming = TcpIncoming::from_listener(listener, true, None).map_err(transport_error)?;
+            Server::builder().add_service(self.clone()).serve_with_incoming(incoming).await.map_err(transport_error)
+        })
+    }
+}
+
+/// The schema as a bare IPC message, as `FlightData.data_header` carries it.
+fn encode_schema_message(schema: &Schema) -> Bytes {
+    let mut tracker = DictionaryTracker::new(false);
+    let options = IpcWriteOptions::default();
+    Bytes::from(IpcDataGenerator::default().schema_to_bytes_with_dictionary_tracker(schema, &mut tracker, &options).ipc_message)
+}
+
+/// The schema as an encapsulated IPC message, as `FlightInfo.schema` carries it.
+fn encode_schema(schema: &Schema) -> Bytes {
+    let message = encode_schema_message(schema);
+    let mut bytes = Vec::with_capacity(message.len() + 8);
+    bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+    bytes.extend_from_slice(&(message.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&message);
+    Bytes::from(bytes)
+}
+
+impl NamedService for FlightServer {
+    const NAME: &'static str = SERVICE;
+}
+
+struct ListFlights(FlightServer);
+
+impl ServerStreamingService<Criteria> for ListFlights {
+    type Response = FlightInfo;
+    type ResponseStream = BoxStream<FlightInfo>;
+    type Future = BoxFuture<tonic::Response<Self::ResponseStream>, Status>;
+
+    fn call(&mut self, _: tonic::Request<Criteria>) -> Self::Future {
+        let flights = self.0.flights.read().unwrap();
+        let infos: Vec<_> = flights.iter().map(|(name, batch)| Ok(self.0.info(name, batch))).collect();
+        let stream: BoxStream<FlightInfo> = Box::pin(tokio_stream::iter(infos));
+        Box::pin(async move { Ok(tonic::Response::new(stream)) })
+    }
+}
+
+struct GetFlightInfo(FlightServer);
+
+impl UnaryService<FlightDescriptor> for GetFlightInfo {
+    type Response = FlightInfo;
+    type Future = BoxFuture<tonic::Response<FlightInfo>, Status>;
+
+    fn call(&mut self, request: tonic::Request<FlightDescriptor>) -> Self::Future {
+        let descriptor = request.into_inner();
+        let info = match descriptor.path.as_slice() {
+            [name] if descriptor.r#type == PATH => self.0.batch(name.as_bytes()).map(|batch| self.0.info(name, &batch)),
+            _ => Err(Status::invalid_argument("descriptor must be a path of one name")),
+        };
+        Box::pin(async move { info.map(tonic::Response::new) })
+    }
+}
+
+struct DoGet(FlightServer);
+
+impl ServerStreamingService<Ticket> for DoGet {
+    type Response = FlightData;
+    type ResponseStream = BoxStream<FlightData>;
+    type Future = BoxFuture<tonic::Response<Self::ResponseStream>, Status>;
+
+    fn call(&mut self, request: tonic::Request<Ticket>) -> Self::Future {
+        let stream = self.0.batch(&request.into_inner().ticket).map(|batch| self.0.stream(batch));
+        Box::pin(async move { stream.map(tonic::Response::new) })
+    }
+}
+
+impl<B> Service<http::Request<B>> for FlightServer
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::BoxBody>;
+    type Error = std::convert::Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let server = self.clone();
+        match request.uri().path() {
+            LIST_FLIGHTS => Box::pin(async move {
+                Ok(Grpc::new(ProstCodec::default()).server_streaming(ListFlights(server), request).await)
+            }),
+            GET_FLIGHT_INFO => Box::pin(async move {
+                Ok(Grpc::new(ProstCodec::default()).unary(GetFlightInfo(server), request).await)
+            }),
+            DO_GET => Box::pin(async move {
+                Ok(Grpc::new(ProstCodec::default()).server_streaming(DoGet(server), request).await)
+            }),
+            _ => Box::pin(async move {
+                let mut response = http::Response::new(empty_body());
+                let headers = response.headers_mut();
+                headers.insert(Status::GRPC_STATUS, (Code::Unimplemented as i32).into());
+                headers.insert(http::header::CONTENT_TYPE, tonic::metadata::GRPC_CONTENT_TYPE);
+                Ok(response)
+            }),
+        }
+    }
+}
+
+/// A blocking Flight client.
+pub struct FlightClient {
+    runtime: tokio::runtime::Runtime,
+    grpc: tonic::client::Grpc<Channel>,
+}
+
+impl FlightClient {
+    /// Connects to the Flight service at `uri`, e.g. `http://127.0.0.1:50051`.
+    pub fn connect(uri: &str) -> io::Result<Self> {
+        let runtime = runtime()?;
+        let endpoint = Channel::from_shared(uri.to_string()).map_err(transport_error)?;
+        let channel = runtime.block_on(endpoint.connect()).map_err(transport_error)?;
+        let grpc = tonic::client::Grpc::new(channel).max_decoding_message_size(MAX_MESSAGE_BYTES);
+        Ok(Self { runtime, grpc })
+    }
+
+    /// Names of the flights the server lists.
+    pub fn list(&mut self) -> Result<Vec<String>, Error> {
+        let grpc = &mut self.grpc;
+        self.runtime.block_on(async {
+            grpc.ready().await.map_err(|e| Error::InvalidInput(format!("flight: {e}")))?;
+            let request = tonic::Request::new(Criteria::default());
+            let path = http::uri::PathAndQuery::from_static(LIST_FLIGHTS);
+            let mut stream = grpc
+                .server_streaming::<_, FlightInfo, _>(request, path, ProstCodec::default())
+                .await
+                .map_err(status_error)?
+                .into_inner();
+            let mut names = Vec::new();
+            while let Some(info) = stream.message().await.map_err(status_error)? {
+                names.extend(info.flight_descriptor.map(|descriptor| descriptor.path.join("/")));
+            }
+            Ok(names)
+        })
+    }
+
+    /// Fetches the flight published under `name` as a frame.
+    ///
+    /// Fails if the server has no such flight or its stream is not a frame's layout.
+    pub fn fetch(&mut self, name: &str) -> Result<TimeSeriesFrame, Error> {
+        let grpc = &mut self.grpc;
+        let decoded = self.runtime.block_on(async {
+            grpc.ready().await.map_err(|e| Error::InvalidInput(format!("flight: {e}")))?;
+            let request = tonic::Request::new(Ticket { ticket: Bytes::from(name.as_bytes().to_vec()) });
+            let path = http::uri::PathAndQuery::from_static(DO_GET);
+            let mut stream = grpc
+                .server_streaming::<_, FlightData, _>(request, path, ProstCodec::default())
+                .await
+                .map_err(status_error)?
+                .into_inner();
+            let mut decoder = Decoder::default();
+            while let Some(data) = stream.message().await.map_err(status_error)? {
+                decoder.push(data)?;
+            }
+            Ok::<_, Error>(decoder)
+        })?;
+        let schema = decoded.schema.ok_or_else(|| Error::InvalidInput(format!("flight {name} has no schema")))?;
+        TimeSeriesFrame::from_batches(schema, decoded.batches.into_iter().map(Ok))
+    }
+}
+
+fn status_error(status: Status) -> Error {
+    Error::InvalidInput(format!("flight: {}", status.message()))
+}
+
+/// Decodes the messages of a `DoGet` stream into batches.
+#[derive(Default)]
+struct Decoder {
+    schema: Option<SchemaRef>,
+    batches: Vec<RecordBatch>,
+}
+
+impl Decoder {
+    fn push(&mut self, data: FlightData) -> Result<(), Error> {
+        let invalid = |e: String| Error::InvalidInput(format!("flight: {e}"));
+        let message = root_as_message(&data.data_header).map_err(|e| invalid(e.to_string()))?;
+        match message.header_type() {
+            MessageHeader::Schema => {
+                let schema = try_schema_from_ipc_buffer(&data.data_header)
+                    .or_else(|_| arrow_ipc::convert::try_schema_from_flatbuffer_bytes(&data.data_header))
+                    .map_err(|e| invalid(e.to_string()))?;
+                self.schema = Some(Arc::new(schema));
+            }
+            MessageHeader::RecordBatch => {
+                let schema = self.schema.clone().ok_or_else(|| invalid("batch before schema".to_string()))?;
+                let header = message.header_as_record_batch().ok_or_else(|| invalid("bad batch header".to_string()))?;
+                let body = Buffer::from_bytes(data.data_body.into());
+                let batch = read_record_batch(&body, header, schema, &HashMap::new(), None, &message.version())
+                    .map_err(|e| invalid(e.to_string()))?;
+                self.batches.push(batch);
+            }
+            other => return Err(invalid(format!("unexpected {other:?} message"))),
+        }
+        Ok(())
+    }
+}
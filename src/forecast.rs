@@ -0,0 +1,229 @@
+//! Univariate forecasting models.
+
+use crate::linalg::least_squares;
+use crate::special::normal_ppf;
+use crate::{Error, TimeSeries};
+
+pub trait Forecaster {
+    /// Estimates the model from the observed series.
+    fn fit(&mut self, series: &TimeSeries<f64>) -> Result<(), Error>;
+    /// Point forecasts for the next `horizon` steps. NaN before the model is fitted.
+    fn predict(&self, horizon: usize) -> TimeSeries<f64>;
+    /// Forecast quantiles for the next `horizon` steps, one series per requested level in
+    /// `quantiles` (each in `(0, 1)`).
+    fn predict_quantiles(&self, horizon: usize, quantiles: &[f64]) -> Vec<TimeSeries<f64>>;
+}
+
+/// Quantiles of Gaussian forecasts whose standard deviation at step `h` (1-based) is `sd(h)`.
+pub(crate) fn gaussian_quantiles<F>(mean: &TimeSeries<f64>, quantiles: &[f64], sd: F) -> Vec<TimeSeries<f64>>
+where
+    F: Fn(usize) -> f64,
+{
+    quantiles
+        .iter()
+        .map(|&q| {
+            let z = normal_ppf(q);
+            mean.as_slice().iter().enumerate().map(|(i, m)| m + z * sd(i + 1)).collect()
+        })
+        .collect()
+}
+
+/// Random walk: every future value equals the last observation.
+#[derive(Clone, Debug, Default)]
+pub struct Naive {
+    last: Option<f64>,
+    sigma: f64,
+}
+
+impl Naive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Forecaster for Naive {
+    fn fit(&mut self, series: &TimeSeries<f64>) -> Result<(), Error> {
+        if series.len() < 2 {
+            return Err(Error::InsufficientData { required: 2, found: series.len() });
+        }
+        let steps: Vec<f64> = series.as_slice().windows(2).map(|w| w[1] - w[0]).collect();
+        self.sigma = (steps.iter().map(|d| d * d).sum::<f64>() / steps.len() as f64).sqrt();
+        self.last = series.last();
+        Ok(())
+    }
+
+    fn predict(&self, horizon: usize) -> TimeSeries<f64> {
+        TimeSeries(vec![self.last.unwrap_or(f64::NAN); horizon])
+    }
+
+    fn predict_quantiles(&self, horizon: usize, quantiles: &[f64]) -> Vec<TimeSeries<f64>> {
+        gaussian_quantiles(&self.predict(horizon), quantiles, |h| self.sigma * (h as f64).sqrt())
+    }
+}
+
+/// Simple exponential smoothing, i.e. ETS(A,N,N): a level updated with weight `alpha`.
+#[derive(Clone, Debug)]
+pub struct SimpleExpSmoothing {
+    pub alpha: f64,
+    level: Option<f64>,
+    sigma: f64,
+}
+
+impl SimpleExpSmoothing {
+    pub fn new(alpha: f64) -> Self {
+        assert!(alpha > 0. && alpha <= 1., "alpha must be in (0, 1]");
+        Self { alpha, level: None, sigma: 0. }
+    }
+}
+
+impl Forecaster for SimpleExpSmoothing {
+    fn fit(&mut self, series: &TimeSeries<f64>) -> Result<(), Error> {
+        trace_span!("fit", model = "simple_exp_smoothing", n = series.len());
+        if series.len() < 2 {
+            return Err(Error::InsufficientData { required: 2, found: series.len() });
+        }
+        let mut level = series[0];
+        let mut sse = 0.;
+        for &x in &series.as_slice()[1..] {
+            let error = x - level;
+            sse += error * error;
+            level += self.alpha * error;
+        }
+        self.level = Some(level);
+        self.sigma = (sse / (series.len() - 1) as f64).sqrt();
+        Ok(())
+    }
+
+    fn predict(&self, horizon: usize) -> TimeSeries<f64> {
+        TimeSeries(vec![self.level.unwrap_or(f64::NAN); horizon])
+    }
+
+    /// Gaussian quantiles with the ETS(A,N,N) forecast variance `σ²(1 + (h - 1)α²)`.
+    fn predict_quantiles(&self, horizon: usize, quantiles: &[f64]) -> Vec<TimeSeries<f64>> {
+        let a2 = self.alpha * self.alpha;
+        gaussian_quantiles(&self.predict(horizon), quantiles, |h| {
+            self.sigma * (1. + (h - 1) as f64 * a2).sqrt()
+        })
+    }
+}
+
+/// Random walk with drift: each step adds the mean historical change.
+#[derive(Clone, Debug, Default)]
+pub struct Drift {
+    pub(crate) last: Option<f64>,
+    pub(crate) drift: f64,
+    pub(crate) sigma: f64,
+}
+
+impl Drift {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Forecaster for Drift {
+    fn fit(&mut self, series: &TimeSeries<f64>) -> Result<(), Error> {
+        if series.len() < 3 {
+            return Err(Error::InsufficientData { required: 3, found: series.len() });
+        }
+        let steps: Vec<f64> = series.as_slice().windows(2).map(|w| w[1] - w[0]).collect();
+        self.drift = steps.iter().sum::<f64>() / steps.len() as f64;
+        let ss: f64 = steps.iter().map(|d| (d - self.drift).powi(2)).sum();
+        self.sigma = (ss / (steps.len() - 1) as f64).sqrt();
+        self.last = series.last();
+        Ok(())
+    }
+
+    fn predict(&self, horizon: usize) -> TimeSeries<f64> {
+        let last = self.last.unwrap_or(f64::NAN);
+        (1..=horizon).map(|h| last + h as f64 * self.drift).collect()
+    }
+
+    fn predict_quantiles(&self, horizon: usize, quantiles: &[f64]) -> Vec<TimeSeries<f64>> {
+        gaussian_quantiles(&self.predict(horizon), quantiles, |h| self.sigma * (h as f64).sqrt())
+    }
+}
+
+/// Autoregressive model AR(p) with intercept, fitted by least squares.
+#[derive(Clone, Debug)]
+pub struct Autoregressive {
+    pub order: usize,
+    pub(crate) intercept: f64,
+    pub(crate) coefficients: Vec<f64>,
+    pub(crate) sigma: f64,
+    /// The last `order` observations, oldest first.
+    pub(crate) history: Vec<f64>,
+}
+
+impl Autoregressive {
+    pub fn new(order: usize) -> Self {
+        assert!(order > 0, "order must be positive");
+        Self { order, intercept: f64::NAN, coefficients: Vec::new(), sigma: 0., history: Vec::new() }
+    }
+
+    pub fn intercept(&self) -> f64 {
+        self.intercept
+    }
+
+    /// Lag coefficients, lag 1 first.
+    pub fn coefficients(&self) -> &[f64] {
+        &self.coefficients
+    }
+
+    /// One-step prediction from `history` (oldest first, at least `order` long).
+    pub(crate) fn step(&self, history: &[f64]) -> f64 {
+        let recent = history.iter().rev();
+        self.intercept + self.coefficients.iter().zip(recent).map(|(c, x)| c * x).sum::<f64>()
+    }
+}
+
+impl Forecaster for Autoregressive {
+    fn fit(&mut self, series: &TimeSeries<f64>) -> Result<(), Error> {
+        trace_span!("fit", model = "autoregressive", order = self.order, n = series.len());
+        let p = self.order;
+        if series.len() < 2 * p + 2 {
+            return Err(Error::InsufficientData { required: 2 * p + 2, found: series.len() });
+        }
+        let design: Vec<Vec<f64>> = (p..series.len())
+            .map(|t| std::iter::once(1.).chain((1..=p).map(|lag| series[t - lag])).collect())
+            .collect();
+        let beta = least_squares(&design, &series.as_slice()[p..]).ok_or(Error::Singular)?;
+        self.intercept = beta[0];
+        self.coefficients = beta[1..].to_vec();
+        let ss: f64 = design
+            .iter()
+            .zip(&series.as_slice()[p..])
+            .map(|(row, y)| (y - row.iter().zip(&beta).map(|(x, b)| x * b).sum::<f64>()).powi(2))
+            .sum();
+        self.sigma = (ss / (design.len() - p - 1) as f64).sqrt();
+        self.history = series.as_slice()[series.len() - p..].to_vec();
+        Ok(())
+    }
+
+    fn predict(&self, horizon: usize) -> TimeSeries<f64> {
+        if self.history.is_empty() {
+            return TimeSeries(vec![f64::NAN; horizon]);
+        }
+        let mut history = self.history.clone();
+        for _ in 0..horizon {
+            history.push(self.step(&history));
+        }
+        TimeSeries(history.split_off(self.order))
+    }
+
+    /// Gaussian quantiles using the MA(∞) weights of the fitted process.
+    fn predict_quantiles(&self, horizon: usize, quantiles: &[f64]) -> Vec<TimeSeries<f64>> {
+        let mut psi = vec![1.];
+        for j in 1..horizon {
+            let w = (1..=j.min(self.order)).map(|i| self.coefficients[i - 1] * psi[j - i]).sum();
+            psi.push(w);
+        }
+        let mut cumulative = Vec::with_capacity(horizon);
+        let mut acc = 0.;
+        for w in &psi {
+            acc += w * w;
+            cumulative.push(acc);
+        }
+        gaussian_quantiles(&self.predict(horizon), quantiles, |h| self.sigma * cumulative[h - 1].sqrt())
+    }
+}
@@ -0,0 +1,54 @@
+//! Fractional differencing with a fixed-width window (López de Prado), which removes
+//! enough of a trend to make a series stationary while keeping its long memory.
+
+use crate::TimeSeries;
+
+/// Most weights [`frac_diff_weights`] returns. For `d` close to -1 the weights decay so
+/// slowly that the threshold would only be reached after astronomically many lags.
+pub const MAX_WEIGHTS: usize = 10_000;
+
+/// Weights of `(1 - B)^d` in lag order, truncated before the first weight whose absolute
+/// value drops below `threshold`, and to at most [`MAX_WEIGHTS`] weights.
+///
+/// # Panics
+/// If `d` is not a finite number above -1, for which the weights do not decay, or
+/// `threshold` is not positive.
+pub fn frac_diff_weights(d: f64, threshold: f64) -> Vec<f64> {
+    assert!(d.is_finite() && d > -1., "d must be finite and above -1");
+    assert!(threshold > 0., "threshold must be positive");
+    let mut weights = vec![1.];
+    let mut k = 1.;
+    while weights.len() < MAX_WEIGHTS {
+        let w = -weights[weights.len() - 1] * (d - k + 1.) / k;
+        if w.abs() < threshold {
+            break;
+        }
+        weights.push(w);
+        k += 1.;
+    }
+    weights
+}
+
+pub trait FractionalDiff {
+    /// Differences the series to order `d` (0 keeps it, 1 is the ordinary `diff(1)`),
+    /// using the weights above `threshold`. Like `diff`, the output drops the leading
+    /// points the window does not cover: it has `len - width + 1` points.
+    ///
+    /// # Panics
+    /// As [`frac_diff_weights`].
+    fn frac_diff(&self, d: f64, threshold: f64) -> TimeSeries<f64>;
+}
+
+impl FractionalDiff for TimeSeries<f64> {
+    fn frac_diff(&self, d: f64, threshold: f64) -> TimeSeries<f64> {
+        let weights = frac_diff_weights(d, threshold);
+        let width = weights.len();
+        if self.len() < width {
+            return TimeSeries::new();
+        }
+        self.as_slice()
+            .windows(width)
+            .map(|w| w.iter().rev().zip(&weights).map(|(x, w)| x * w).sum())
+            .collect()
+    }
+}
@@ -0,0 +1,200 @@
+//! A table of aligned numeric series sharing one integer time index.
+
+use crate::{Error, TimeSeries};
+use std::collections::{BTreeSet, HashMap};
+
+/// Which index entries a [`TimeSeriesFrame::join`] keeps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinKind {
+    /// Only index entries present in both frames.
+    Inner,
+    /// Every index entry of the left frame, in its order.
+    Left,
+    /// The sorted union of both indexes.
+    Outer,
+}
+
+/// Named `f64` columns of equal length, aligned on a common index of timestamps (or row
+/// positions when built without one). Missing values are NaN.
+#[derive(Clone, Debug, Default)]
+pub struct TimeSeriesFrame {
+    index: Vec<i64>,
+    names: Vec<String>,
+    columns: Vec<TimeSeries<f64>>,
+}
+
+impl TimeSeriesFrame {
+    /// An empty frame over the given index.
+    pub fn new(index: Vec<i64>) -> Self {
+        Self { index, names: Vec::new(), columns: Vec::new() }
+    }
+
+    /// The index and the named columns, without copying them.
+    #[cfg(feature = "arrow")]
+    pub(crate) fn into_parts(self) -> (Vec<i64>, Vec<(String, TimeSeries<f64>)>) {
+        (self.index, self.names.into_iter().zip(self.columns).collect())
+    }
+
+    /// Builds a frame from named columns, indexed by row position.
+    pub fn from_columns<S: Into<String>>(columns: Vec<(S, TimeSeries<f64>)>) -> Result<Self, Error> {
+        let rows = columns.first().map_or(0, |(_, c)| c.len());
+        let mut frame = Self::new((0..rows as i64).collect());
+        for (name, column) in columns {
+            frame.add_column(name, column)?;
+        }
+        Ok(frame)
+    }
+
+    /// Replaces the index, which must have one entry per row.
+    pub fn with_index(mut self, index: Vec<i64>) -> Result<Self, Error> {
+        if index.len() != self.len() {
+            return Err(Error::LengthMismatch { expected: self.len(), found: index.len() });
+        }
+        self.index = index;
+        Ok(self)
+    }
+
+    /// Adds a column, replacing any existing column of the same name.
+    pub fn add_column<S: Into<String>>(&mut self, name: S, column: TimeSeries<f64>) -> Result<(), Error> {
+        if column.len() != self.len() {
+            return Err(Error::LengthMismatch { expected: self.len(), found: column.len() });
+        }
+        let name = name.into();
+        match self.names.iter().position(|n| *n == name) {
+            Some(i) => self.columns[i] = column,
+            None => {
+                self.names.push(name);
+                self.columns.push(column);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn column(&self, name: &str) -> Option<&TimeSeries<f64>> {
+        self.names.iter().position(|n| n == name).map(|i| &self.columns[i])
+    }
+
+    /// The column called `name`, for column arithmetic such as
+    /// `frame.col("close") / frame.col("open")`.
+    ///
+    /// # Panics
+    /// Panics if there is no such column; use [`column`](Self::column) to check.
+    pub fn col(&self, name: &str) -> &TimeSeries<f64> {
+        self.column(name).unwrap_or_else(|| panic!("no column named `{}`", name))
+    }
+
+    /// Adds a column and returns the frame, for chaining derived columns.
+    pub fn with_column<S: Into<String>>(mut self, name: S, column: TimeSeries<f64>) -> Result<Self, Error> {
+        self.add_column(name, column)?;
+        Ok(self)
+    }
+
+    pub fn drop_column(&mut self, name: &str) -> Option<TimeSeries<f64>> {
+        let i = self.names.iter().position(|n| n == name)?;
+        self.names.remove(i);
+        Some(self.columns.remove(i))
+    }
+
+    /// Renames a column, replacing any other column already called `to`.
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), Error> {
+        if self.column(from).is_none() {
+            return Err(Error::MissingSeries(from.to_string()));
+        }
+        if from != to {
+            self.drop_column(to);
+        }
+        if let Some(name) = self.names.iter_mut().find(|n| *n == from) {
+            *name = to.to_string();
+        }
+        Ok(())
+    }
+
+    /// A frame with only the named columns, in the given order.
+    pub fn select(&self, names: &[&str]) -> Result<Self, Error> {
+        let mut frame = Self::new(self.index.clone());
+        for &name in names {
+            let column = self.column(name).ok_or_else(|| Error::MissingSeries(name.to_string()))?;
+            frame.add_column(name, column.clone())?;
+        }
+        Ok(frame)
+    }
+
+    /// Joins two frames on their index. Rows missing on one side are filled with NaN, and
+    /// right-hand columns whose names are already taken get a `_right` suffix.
+    pub fn join(&self, other: &Self, how: JoinKind) -> Self {
+        trace_span!("join", how = ?how, left = self.len(), right = other.len());
+        let right_rows: HashMap<i64, usize> = other.index.iter().enumerate().map(|(i, &t)| (t, i)).collect();
+        let index: Vec<i64> = match how {
+            JoinKind::Inner => self.index.iter().copied().filter(|t| right_rows.contains_key(t)).collect(),
+            JoinKind::Left => self.index.clone(),
+            JoinKind::Outer => {
+                self.index.iter().chain(&other.index).copied().collect::<BTreeSet<i64>>().into_iter().collect()
+            }
+        };
+        let left_rows: HashMap<i64, usize> = self.index.iter().enumerate().map(|(i, &t)| (t, i)).collect();
+        let take = |column: &TimeSeries<f64>, rows: &HashMap<i64, usize>| -> TimeSeries<f64> {
+            index.iter().map(|t| rows.get(t).map_or(f64::NAN, |&i| column[i])).collect()
+        };
+        let mut frame = Self::new(index.clone());
+        for (name, column) in self.columns() {
+            frame.names.push(name.to_string());
+            frame.columns.push(take(column, &left_rows));
+        }
+        for (name, column) in other.columns() {
+            let name = if frame.column(name).is_some() { format!("{}_right", name) } else { name.to_string() };
+            frame.names.push(name);
+            frame.columns.push(take(column, &right_rows));
+        }
+        frame
+    }
+
+    pub fn column_names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Columns with their names, in insertion order.
+    pub fn columns(&self) -> impl Iterator<Item = (&str, &TimeSeries<f64>)> {
+        self.names.iter().map(String::as_str).zip(self.columns.iter())
+    }
+
+    pub fn index(&self) -> &[i64] {
+        &self.index
+    }
+
+    /// Number of rows.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Number of columns.
+    pub fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Values of every column at row `i`.
+    pub fn row(&self, i: usize) -> Vec<f64> {
+        self.columns.iter().map(|c| c[i]).collect()
+    }
+
+    /// Applies `f` to every row, producing a frame with the same shape and names.
+    pub(crate) fn map_rows<F>(&self, f: F) -> Self
+    where
+        F: Fn(&[f64]) -> Vec<f64>,
+    {
+        let mut columns = vec![Vec::with_capacity(self.len()); self.width()];
+        for i in 0..self.len() {
+            for (column, x) in columns.iter_mut().zip(f(&self.row(i))) {
+                column.push(x);
+            }
+        }
+        Self {
+            index: self.index.clone(),
+            names: self.names.clone(),
+            columns: columns.into_iter().map(TimeSeries).collect(),
+        }
+    }
+}
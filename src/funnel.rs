@@ -0,0 +1,136 @@
+//! Conversion funnels over per-user event-label series, such as visit → cart → purchase.
+//!
+//! A user enters the funnel with an event of the first step and reaches each later step
+//! with an event of that step after the previous one, within the step's time limit if it
+//! has one. Users are counted once per calendar period of entry, at the furthest step
+//! any of their entries in that period reached, which gives one conversion-rate series
+//! per step.
+
+use crate::{CalendarPeriod, SeriesSet, TimeSeriesFrame, TimestampedSeries};
+use std::collections::BTreeMap;
+
+/// An ordered list of steps, each an event label.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Funnel {
+    labels: Vec<String>,
+    /// Longest time allowed since the previous step, for every step after the first.
+    within: Vec<Option<i64>>,
+}
+
+impl Funnel {
+    /// A funnel entered with an event labeled `first`.
+    pub fn new(first: &str) -> Self {
+        Self { labels: vec![first.to_string()], within: Vec::new() }
+    }
+
+    /// Appends a step reached by an event labeled `label`, at most `within` after the
+    /// previous step, or any time later with `None`.
+    ///
+    /// # Panics
+    /// Panics if `within` is negative.
+    pub fn then(mut self, label: &str, within: Option<i64>) -> Self {
+        assert!(within.is_none_or(|w| w >= 0), "step time limit must not be negative");
+        self.labels.push(label.to_string());
+        self.within.push(within);
+        self
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// The furthest step reached from the entries at `starts` (positions in `events`):
+    /// 0 for entering only, `None` without entries.
+    fn depth(&self, events: &TimestampedSeries<String>, starts: &[usize]) -> Option<usize> {
+        if starts.is_empty() {
+            return None;
+        }
+        let timestamps = events.timestamps();
+        let mut reached = starts.to_vec();
+        for (k, (label, within)) in self.labels[1..].iter().zip(&self.within).enumerate() {
+            // Positions of this step after some position reached at the previous step,
+            // within its time limit.
+            let next: Vec<usize> = (reached[0] + 1..events.len())
+                .filter(|&i| events.values()[i] == *label)
+                .filter(|&i| {
+                    reached
+                        .iter()
+                        .take_while(|&&r| r < i)
+                        .any(|&r| within.is_none_or(|w| timestamps[i] - timestamps[r] <= w))
+                })
+                .collect();
+            if next.is_empty() {
+                return Some(k);
+            }
+            reached = next;
+        }
+        Some(self.labels.len() - 1)
+    }
+
+    /// Counts, for every period with entries, the users reaching each step.
+    pub fn evaluate(&self, users: &SeriesSet<String>, period: CalendarPeriod) -> FunnelReport {
+        let mut reached: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+        for (_, events) in users {
+            let mut entries: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+            for (i, (t, label)) in events.iter().enumerate() {
+                if *label == self.labels[0] {
+                    entries.entry(period.floor(t)).or_default().push(i);
+                }
+            }
+            for (start, positions) in entries {
+                if let Some(depth) = self.depth(events, &positions) {
+                    let counts = reached.entry(start).or_insert_with(|| vec![0; self.labels.len()]);
+                    counts[..=depth].iter_mut().for_each(|c| *c += 1);
+                }
+            }
+        }
+        FunnelReport {
+            labels: self.labels.clone(),
+            periods: reached.keys().copied().collect(),
+            reached: (0..self.labels.len()).map(|k| reached.values().map(|counts| counts[k]).collect()).collect(),
+        }
+    }
+}
+
+/// The outcome of [`Funnel::evaluate`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunnelReport {
+    pub labels: Vec<String>,
+    /// Start of every period with entries, oldest first.
+    pub periods: Vec<i64>,
+    /// For each step, the users reaching it from entries in each period; the first step
+    /// counts the users who entered.
+    pub reached: Vec<Vec<usize>>,
+}
+
+impl FunnelReport {
+    /// The share of entering users who reached `step`, per period.
+    ///
+    /// # Panics
+    /// Panics if `step` is not a step of the funnel.
+    pub fn conversion(&self, step: usize) -> TimestampedSeries<f64> {
+        let rates = self.reached[step].iter().zip(&self.reached[0]).map(|(&r, &e)| r as f64 / e as f64);
+        TimestampedSeries::from_parts(self.periods.clone(), rates.collect())
+    }
+
+    /// The share of users at the previous step who went on to `step`, per period.
+    ///
+    /// # Panics
+    /// Panics if `step` is 0 or not a step of the funnel.
+    pub fn step_conversion(&self, step: usize) -> TimestampedSeries<f64> {
+        assert!(step > 0, "the first step has no previous step");
+        let rates = self.reached[step].iter().zip(&self.reached[step - 1]).map(|(&r, &e)| r as f64 / e as f64);
+        TimestampedSeries::from_parts(self.periods.clone(), rates.collect())
+    }
+
+    /// The conversion from entry to each step, one column per step label, indexed by
+    /// period start.
+    pub fn to_frame(&self) -> TimeSeriesFrame {
+        let mut frame = TimeSeriesFrame::new(self.periods.clone());
+        for (k, label) in self.labels.iter().enumerate() {
+            let (_, column) = self.conversion(k).into_parts();
+            frame.add_column(label.as_str(), column).expect("one value per period");
+        }
+        frame
+    }
+}
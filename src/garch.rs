@@ -0,0 +1,112 @@
+//! GARCH(1,1) volatility model for a return series.
+
+use crate::optimize::nelder_mead_monitored;
+use crate::{Error, Forecaster, Monitor, TimeSeries};
+
+/// `r_t = mu + e_t`, `e_t ~ N(0, s²_t)`, `s²_t = omega + alpha e²_{t-1} + beta s²_{t-1}`,
+/// estimated by Gaussian maximum likelihood.
+#[derive(Clone, Debug, Default)]
+pub struct Garch {
+    pub mu: f64,
+    pub omega: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    /// Last residual and conditional variance of the fitted sample.
+    pub(crate) last_residual: f64,
+    pub(crate) last_variance: f64,
+}
+
+impl Garch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Conditional variance of the step after a residual `e` observed at variance `s2`.
+    pub(crate) fn next_variance(&self, e: f64, s2: f64) -> f64 {
+        self.omega + self.alpha * e * e + self.beta * s2
+    }
+
+    /// Forecast of the conditional variance `h` steps ahead (1-based).
+    pub fn variance_forecast(&self, horizon: usize) -> TimeSeries<f64> {
+        let mut v = self.next_variance(self.last_residual, self.last_variance);
+        let mut out = Vec::with_capacity(horizon);
+        for _ in 0..horizon {
+            out.push(v);
+            v = self.omega + (self.alpha + self.beta) * v;
+        }
+        TimeSeries(out)
+    }
+
+    /// [`Forecaster::fit`], reporting the progress of the likelihood search to `monitor`.
+    /// Leaves the model untouched if cancelled.
+    pub fn fit_monitored(&mut self, series: &TimeSeries<f64>, monitor: &mut Monitor) -> Result<(), Error> {
+        trace_span!("fit", model = "garch", n = series.len());
+        let n = series.len();
+        if n < 10 {
+            return Err(Error::InsufficientData { required: 10, found: n });
+        }
+        let mu = series.as_slice().iter().sum::<f64>() / n as f64;
+        let residuals: Vec<f64> = series.as_slice().iter().map(|r| r - mu).collect();
+        let sample_var = residuals.iter().map(|e| e * e).sum::<f64>() / n as f64;
+        let neg_log_likelihood = |p: &[f64]| {
+            let (omega, alpha, beta) = unpack(p);
+            let mut s2 = sample_var;
+            let mut nll = 0.;
+            for &e in &residuals {
+                nll += s2.ln() + e * e / s2;
+                s2 = omega + alpha * e * e + beta * s2;
+            }
+            nll / 2.
+        };
+        // Start from persistence 0.9 split 1:8, with omega targeting the sample variance.
+        let start = [(sample_var * 0.1).ln(), 2.197, -2.079];
+        let best = nelder_mead_monitored(neg_log_likelihood, &start, 0.5, 2000, monitor);
+        monitor.check()?;
+        let (omega, alpha, beta) = unpack(&best);
+        self.mu = mu;
+        self.omega = omega;
+        self.alpha = alpha;
+        self.beta = beta;
+        let mut s2 = sample_var;
+        for &e in &residuals[..n - 1] {
+            s2 = self.next_variance(e, s2);
+        }
+        self.last_residual = residuals[n - 1];
+        self.last_variance = s2;
+        Ok(())
+    }
+}
+
+fn logistic(x: f64) -> f64 {
+    1. / (1. + (-x).exp())
+}
+
+/// Maps unconstrained parameters to `(omega, alpha, beta)` with `alpha + beta < 1`.
+fn unpack(p: &[f64]) -> (f64, f64, f64) {
+    let persistence = logistic(p[1]);
+    let share = logistic(p[2]);
+    (p[0].exp(), persistence * share, persistence * (1. - share))
+}
+
+impl Forecaster for Garch {
+    fn fit(&mut self, series: &TimeSeries<f64>) -> Result<(), Error> {
+        self.fit_monitored(series, &mut Monitor::new())
+    }
+
+    /// The mean return, for every step.
+    fn predict(&self, horizon: usize) -> TimeSeries<f64> {
+        TimeSeries(vec![self.mu; horizon])
+    }
+
+    /// Quantiles of the one-period return at each future step.
+    fn predict_quantiles(&self, horizon: usize, quantiles: &[f64]) -> Vec<TimeSeries<f64>> {
+        let variance = self.variance_forecast(horizon);
+        quantiles
+            .iter()
+            .map(|&q| {
+                let z = crate::special::normal_ppf(q);
+                variance.map(|v| self.mu + z * v.sqrt())
+            })
+            .collect()
+    }
+}
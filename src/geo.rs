@@ -0,0 +1,206 @@
+//! GPS tracks as series of [`GeoPoint`]s: distances, speeds and headings between fixes,
+//! resampling along great circles and Douglas-Peucker simplification.
+//!
+//! Distances are great-circle distances in metres on a spherical Earth, which is within
+//! about 0.5% of the ellipsoid. Speeds need elapsed time in seconds, so timestamps are
+//! read in the series' unit, or as seconds when it is untagged.
+
+use crate::TimestampedSeries;
+
+/// Mean radius of the Earth in metres.
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// A position in degrees of latitude and longitude.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl GeoPoint {
+    /// # Panics
+    /// Panics if `lat` is outside `[-90, 90]`.
+    pub fn new(lat: f64, lon: f64) -> Self {
+        assert!((-90. ..=90.).contains(&lat), "latitude must be within [-90, 90]");
+        Self { lat, lon }
+    }
+
+    /// Central angle to `other` in radians, by the haversine formula.
+    fn angle_to(&self, other: &Self) -> f64 {
+        let (phi1, phi2) = (self.lat.to_radians(), other.lat.to_radians());
+        let dphi = phi2 - phi1;
+        let dlambda = (other.lon - self.lon).to_radians();
+        let h = (dphi / 2.).sin().powi(2) + phi1.cos() * phi2.cos() * (dlambda / 2.).sin().powi(2);
+        2. * h.sqrt().min(1.).asin()
+    }
+
+    /// Great-circle distance to `other` in metres.
+    pub fn distance(&self, other: &Self) -> f64 {
+        self.angle_to(other) * EARTH_RADIUS
+    }
+
+    /// Initial bearing towards `other` in degrees clockwise from north, in `[0, 360)`.
+    pub fn bearing(&self, other: &Self) -> f64 {
+        self.bearing_radians(other).to_degrees().rem_euclid(360.)
+    }
+
+    fn bearing_radians(&self, other: &Self) -> f64 {
+        let (phi1, phi2) = (self.lat.to_radians(), other.lat.to_radians());
+        let dlambda = (other.lon - self.lon).to_radians();
+        let y = dlambda.sin() * phi2.cos();
+        let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * dlambda.cos();
+        y.atan2(x)
+    }
+
+    /// The point a fraction `t` of the way to `other` along the great circle.
+    pub fn interpolate(&self, other: &Self, t: f64) -> Self {
+        let delta = self.angle_to(other);
+        if delta < 1e-12 {
+            return *self;
+        }
+        let (a, b) = (((1. - t) * delta).sin() / delta.sin(), (t * delta).sin() / delta.sin());
+        let (phi1, lambda1) = (self.lat.to_radians(), self.lon.to_radians());
+        let (phi2, lambda2) = (other.lat.to_radians(), other.lon.to_radians());
+        let x = a * phi1.cos() * lambda1.cos() + b * phi2.cos() * lambda2.cos();
+        let y = a * phi1.cos() * lambda1.sin() + b * phi2.cos() * lambda2.sin();
+        let z = a * phi1.sin() + b * phi2.sin();
+        Self { lat: z.atan2(x.hypot(y)).to_degrees(), lon: y.atan2(x).to_degrees() }
+    }
+
+    /// Distance in metres to the great-circle segment from `start` to `end`.
+    pub fn distance_to_segment(&self, start: &Self, end: &Self) -> f64 {
+        let d12 = start.angle_to(end);
+        let d13 = start.angle_to(self);
+        if d12 < 1e-12 {
+            return d13 * EARTH_RADIUS;
+        }
+        let relative = start.bearing_radians(self) - start.bearing_radians(end);
+        if relative.cos() < 0. {
+            return d13 * EARTH_RADIUS;
+        }
+        let cross = (d13.sin() * relative.sin()).asin();
+        let along = (d13.cos() / cross.cos()).clamp(-1., 1.).acos();
+        if along > d12 {
+            return self.distance(end);
+        }
+        cross.abs() * EARTH_RADIUS
+    }
+}
+
+impl TimestampedSeries<GeoPoint> {
+    /// Seconds per timestamp tick.
+    fn seconds_per_tick(&self) -> f64 {
+        1. / self.unit().unwrap_or_default().per_second() as f64
+    }
+
+    /// Distance in metres covered since the previous fix, at the later fix's timestamp.
+    /// The output has `len - 1` points.
+    pub fn segment_distances(&self) -> TimestampedSeries<f64> {
+        let points = self.values().as_slice();
+        let out: TimestampedSeries<f64> =
+            self.iter().skip(1).zip(points).map(|((t, p), prev)| (t, prev.distance(p))).collect();
+        match self.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+
+    /// Total length of the track in metres.
+    pub fn path_length(&self) -> f64 {
+        self.values().as_slice().windows(2).map(|w| w[0].distance(&w[1])).sum()
+    }
+
+    /// Average speed in metres per second since the previous fix; NaN between fixes
+    /// with the same timestamp.
+    pub fn speeds(&self) -> TimestampedSeries<f64> {
+        let per_tick = self.seconds_per_tick();
+        let distances = self.segment_distances();
+        let out: TimestampedSeries<f64> = distances
+            .iter()
+            .zip(self.timestamps())
+            .map(|((t, d), &prev)| {
+                let elapsed = (t - prev) as f64 * per_tick;
+                (t, if elapsed > 0. { d / elapsed } else { f64::NAN })
+            })
+            .collect();
+        match self.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+
+    /// Bearing in degrees from each fix to the next, at the later fix's timestamp.
+    pub fn headings(&self) -> TimestampedSeries<f64> {
+        let points = self.values().as_slice();
+        let out: TimestampedSeries<f64> =
+            self.iter().skip(1).zip(points).map(|((t, p), prev)| (t, prev.bearing(p))).collect();
+        match self.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+
+    /// Positions every `step` from the first timestamp to the last, interpolated along
+    /// the great circle between fixes.
+    ///
+    /// # Panics
+    /// Panics if `step` is not positive.
+    pub fn resample_track(&self, step: i64) -> Self {
+        assert!(step > 0, "step must be positive");
+        let ts = self.timestamps();
+        let points = self.values().as_slice();
+        let mut out = TimestampedSeries::new();
+        if let (Some(&first), Some(&last)) = (ts.first(), ts.last()) {
+            let mut t = first;
+            let mut i = 0;
+            while t <= last {
+                while i + 1 < ts.len() && ts[i + 1] <= t {
+                    i += 1;
+                }
+                let point = if ts[i] == t || i + 1 == ts.len() {
+                    points[i]
+                } else {
+                    points[i].interpolate(&points[i + 1], (t - ts[i]) as f64 / (ts[i + 1] - ts[i]) as f64)
+                };
+                out.push(t, point);
+                t = match t.checked_add(step) {
+                    Some(next) => next,
+                    None => break,
+                };
+            }
+        }
+        match self.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+
+    /// Douglas-Peucker simplification: drops fixes that lie within `tolerance` metres of
+    /// the simplified track. The first and last fixes are always kept.
+    pub fn simplify(&self, tolerance: f64) -> Self {
+        let points = self.values().as_slice();
+        let n = points.len();
+        if n < 3 {
+            return self.clone();
+        }
+        let mut keep = vec![false; n];
+        keep[0] = true;
+        keep[n - 1] = true;
+        let mut stack = vec![(0, n - 1)];
+        while let Some((start, end)) = stack.pop() {
+            let farthest = (start + 1..end)
+                .map(|i| (i, points[i].distance_to_segment(&points[start], &points[end])))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((i, _)) = farthest.filter(|&(_, d)| d > tolerance) {
+                keep[i] = true;
+                stack.push((start, i));
+                stack.push((i, end));
+            }
+        }
+        let out: Self = self.iter().zip(&keep).filter(|(_, &k)| k).map(|((t, p), _)| (t, *p)).collect();
+        match self.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! A global forecasting model: one regressor trained on the lagged windows of many
+//! related series pooled together, then rolled forward for each series.
+//!
+//! Pooling lets short series borrow the dynamics of the rest, which per-series models
+//! cannot. Each series is standardized by its own mean and standard deviation first, so
+//! a series selling thousands and one selling tens share one model. The learner is a
+//! linear autoregression, or gradient-boosted regression trees with the `boosting`
+//! feature.
+
+use crate::linalg::least_squares;
+use crate::{BatchForecast, Embedding, Error, SeriesSet, Statistics, TimeSeries};
+
+/// The regressor that maps a window of lags to the next value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Learner {
+    /// Least squares on the lags with an intercept.
+    Linear,
+    /// Squared-error gradient boosting of regression trees.
+    #[cfg(feature = "boosting")]
+    Boosting {
+        trees: usize,
+        /// Maximum depth of each tree.
+        depth: usize,
+        learning_rate: f64,
+        /// Minimum number of rows in a leaf.
+        min_leaf: usize,
+    },
+}
+
+#[cfg(feature = "boosting")]
+impl Learner {
+    /// 100 trees of depth 3 with learning rate 0.1 and at least 5 rows per leaf.
+    pub fn boosting() -> Self {
+        Learner::Boosting { trees: 100, depth: 3, learning_rate: 0.1, min_leaf: 5 }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Fitted {
+    Linear(Vec<f64>),
+    #[cfg(feature = "boosting")]
+    Boosted {
+        base: f64,
+        learning_rate: f64,
+        trees: Vec<Tree>,
+    },
+}
+
+impl Fitted {
+    fn predict(&self, lags: &[f64]) -> f64 {
+        match self {
+            Fitted::Linear(beta) => beta[0] + lags.iter().zip(&beta[1..]).map(|(x, b)| x * b).sum::<f64>(),
+            #[cfg(feature = "boosting")]
+            Fitted::Boosted { base, learning_rate, trees } => {
+                base + learning_rate * trees.iter().map(|tree| tree.predict(lags)).sum::<f64>()
+            }
+        }
+    }
+}
+
+/// One model for a whole [`SeriesSet`].
+#[derive(Clone, Debug)]
+pub struct GlobalModel {
+    lags: usize,
+    learner: Learner,
+    scale: bool,
+    fitted: Option<Fitted>,
+}
+
+/// Mean and standard deviation that standardize `series`, ignoring NaN; a constant
+/// series is only centred.
+fn scaling(series: &TimeSeries<f64>) -> (f64, f64) {
+    let observed: TimeSeries<f64> = series.as_slice().iter().copied().filter(|x| !x.is_nan()).collect();
+    let std = observed.std();
+    (observed.mean(), if std > 0. { std } else { 1. })
+}
+
+impl GlobalModel {
+    /// A linear model on the last `lags` values, with per-series standardization.
+    ///
+    /// # Panics
+    /// Panics if `lags` is zero.
+    pub fn new(lags: usize) -> Self {
+        assert!(lags > 0, "lags must be positive");
+        Self { lags, learner: Learner::Linear, scale: true, fitted: None }
+    }
+
+    pub fn with_learner(self, learner: Learner) -> Self {
+        Self { learner, ..self }
+    }
+
+    /// Whether to standardize each series by its own mean and standard deviation.
+    pub fn with_scaling(self, scale: bool) -> Self {
+        Self { scale, ..self }
+    }
+
+    pub fn lags(&self) -> usize {
+        self.lags
+    }
+
+    fn normalize(&self, series: &TimeSeries<f64>) -> (TimeSeries<f64>, (f64, f64)) {
+        let (mean, std) = if self.scale { scaling(series) } else { (0., 1.) };
+        (series.map(|x| (x - mean) / std), (mean, std))
+    }
+
+    /// Trains on every window of `lags + 1` consecutive values of every series, skipping
+    /// windows with NaN.
+    ///
+    /// Fails if no series yields a window, or the linear design is singular.
+    pub fn fit(&mut self, set: &SeriesSet<f64>) -> Result<(), Error> {
+        trace_span!("fit", model = "global", series = set.len(), lags = self.lags);
+        let mut rows = Vec::new();
+        let mut targets = Vec::new();
+        for (_, series) in set {
+            let (normalized, _) = self.normalize(series.values());
+            for window in normalized.embed(self.lags + 1, 1).into_iter().filter(|w| w.iter().all(|x| !x.is_nan())) {
+                targets.push(window[self.lags]);
+                rows.push(window[..self.lags].to_vec());
+            }
+        }
+        let required = self.lags + 2;
+        if rows.len() < required {
+            return Err(Error::InsufficientData { required, found: rows.len() });
+        }
+        self.fitted = Some(match self.learner {
+            Learner::Linear => {
+                let design: Vec<Vec<f64>> =
+                    rows.iter().map(|row| std::iter::once(1.).chain(row.iter().copied()).collect()).collect();
+                Fitted::Linear(least_squares(&design, &targets).ok_or(Error::Singular)?)
+            }
+            #[cfg(feature = "boosting")]
+            Learner::Boosting { trees, depth, learning_rate, min_leaf } => {
+                boost(&rows, &targets, trees, depth, learning_rate, min_leaf.max(1))
+            }
+        });
+        Ok(())
+    }
+
+    /// Forecasts `horizon` points after `history`, feeding each forecast back as a lag.
+    /// `history` need not be one of the series the model was fitted on.
+    ///
+    /// Fails if the model is not fitted, or the last `lags` values of `history` are not
+    /// all observed.
+    pub fn predict(&self, history: &TimeSeries<f64>, horizon: usize) -> Result<TimeSeries<f64>, Error> {
+        let fitted = self.fitted.as_ref().ok_or_else(|| Error::InvalidInput("the model is not fitted".to_string()))?;
+        if history.len() < self.lags {
+            return Err(Error::InsufficientData { required: self.lags, found: history.len() });
+        }
+        let (normalized, (mean, std)) = self.normalize(history);
+        let mut window = normalized.as_slice()[history.len() - self.lags..].to_vec();
+        if window.iter().any(|x| x.is_nan()) {
+            return Err(Error::InvalidInput("the last lags of the history have missing values".to_string()));
+        }
+        let mut out = Vec::with_capacity(horizon);
+        for _ in 0..horizon {
+            let next = fitted.predict(&window);
+            out.push(next * std + mean);
+            window.remove(0);
+            window.push(next);
+        }
+        Ok(TimeSeries(out))
+    }
+
+    /// Forecasts every series of `set`, recording the ones [`predict`](Self::predict)
+    /// fails on.
+    pub fn predict_all(&self, set: &SeriesSet<f64>, horizon: usize) -> BatchForecast {
+        let mut out = BatchForecast::default();
+        for (name, series) in set {
+            match self.predict(series.values(), horizon) {
+                Ok(forecast) => {
+                    out.forecasts.insert(name.clone(), forecast);
+                }
+                Err(error) => {
+                    out.failures.insert(name.clone(), error);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// A regression tree stored as a flat list of nodes, the root first.
+#[cfg(feature = "boosting")]
+#[derive(Clone, Debug)]
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+#[cfg(feature = "boosting")]
+#[derive(Clone, Copy, Debug)]
+enum Node {
+    Leaf(f64),
+    /// Rows with `feature <= threshold` go to `left`, the others to `right`.
+    Split {
+        feature: usize,
+        threshold: f64,
+        left: usize,
+        right: usize,
+    },
+}
+
+#[cfg(feature = "boosting")]
+impl Tree {
+    fn predict(&self, x: &[f64]) -> f64 {
+        let mut i = 0;
+        loop {
+            match self.nodes[i] {
+                Node::Leaf(value) => return value,
+                Node::Split { feature, threshold, left, right } => {
+                    i = if x[feature] <= threshold { left } else { right };
+                }
+            }
+        }
+    }
+
+    /// Grows a tree on the residuals `y` of the rows `index` of `x`.
+    fn grow(x: &[Vec<f64>], y: &[f64], index: Vec<usize>, depth: usize, min_leaf: usize) -> Self {
+        let mut tree = Tree { nodes: Vec::new() };
+        tree.grow_node(x, y, index, depth, min_leaf);
+        tree
+    }
+
+    /// Adds the subtree over `index` and returns its position.
+    fn grow_node(&mut self, x: &[Vec<f64>], y: &[f64], index: Vec<usize>, depth: usize, min_leaf: usize) -> usize {
+        let position = self.nodes.len();
+        let mean = index.iter().map(|&i| y[i]).sum::<f64>() / index.len() as f64;
+        self.nodes.push(Node::Leaf(mean));
+        if depth == 0 || index.len() < 2 * min_leaf {
+            return position;
+        }
+        let Some((feature, threshold)) = best_split(x, y, &index, min_leaf) else {
+            return position;
+        };
+        let (left, right): (Vec<usize>, Vec<usize>) = index.into_iter().partition(|&i| x[i][feature] <= threshold);
+        let left = self.grow_node(x, y, left, depth - 1, min_leaf);
+        let right = self.grow_node(x, y, right, depth - 1, min_leaf);
+        self.nodes[position] = Node::Split { feature, threshold, left, right };
+        position
+    }
+}
+
+/// The split of the rows `index` that most reduces the squared error, keeping at least
+/// `min_leaf` rows on each side; `None` if no split does.
+#[cfg(feature = "boosting")]
+fn best_split(x: &[Vec<f64>], y: &[f64], index: &[usize], min_leaf: usize) -> Option<(usize, f64)> {
+    let total: f64 = index.iter().map(|&i| y[i]).sum();
+    let unsplit = total * total / index.len() as f64;
+    (0..x[index[0]].len())
+        .filter_map(|feature| {
+            let mut column: Vec<(f64, f64)> = index.iter().map(|&i| (x[i][feature], y[i])).collect();
+            column.sort_by(|a, b| a.0.total_cmp(&b.0));
+            best_threshold(&column, total, min_leaf).map(|(gain, threshold)| (gain, feature, threshold))
+        })
+        .filter(|&(gain, ..)| gain > unsplit + 1e-12)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, feature, threshold)| (feature, threshold))
+}
+
+/// The best threshold on one feature, given `(value, target)` pairs sorted by value, and
+/// its score `sum_left^2 / n_left + sum_right^2 / n_right`: the higher, the lower the
+/// squared error.
+#[cfg(feature = "boosting")]
+fn best_threshold(column: &[(f64, f64)], total: f64, min_leaf: usize) -> Option<(f64, f64)> {
+    let n = column.len();
+    let mut best: Option<(f64, f64)> = None;
+    let mut left_sum = 0.;
+    for (k, pair) in column.windows(2).enumerate() {
+        let ((below, target), (above, _)) = (pair[0], pair[1]);
+        left_sum += target;
+        let left = k + 1;
+        if left < min_leaf || n - left < min_leaf || below == above {
+            continue;
+        }
+        let right_sum = total - left_sum;
+        let gain = left_sum * left_sum / left as f64 + right_sum * right_sum / (n - left) as f64;
+        if best.is_none_or(|(g, _)| gain > g) {
+            best = Some((gain, (below + above) / 2.));
+        }
+    }
+    best
+}
+
+#[cfg(feature = "boosting")]
+fn boost(x: &[Vec<f64>], y: &[f64], trees: usize, depth: usize, learning_rate: f64, min_leaf: usize) -> Fitted {
+    let base = y.iter().sum::<f64>() / y.len() as f64;
+    let mut residuals: Vec<f64> = y.iter().map(|v| v - base).collect();
+    let mut fitted = Vec::with_capacity(trees);
+    for _ in 0..trees {
+        let tree = Tree::grow(x, &residuals, (0..y.len()).collect(), depth, min_leaf);
+        for (r, row) in residuals.iter_mut().zip(x) {
+            *r -= learning_rate * tree.predict(row);
+        }
+        fitted.push(tree);
+    }
+    Fitted::Boosted { base, learning_rate, trees: fitted }
+}
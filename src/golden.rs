@@ -0,0 +1,295 @@
+//! Comparison of this crate's outputs against golden values produced by reference
+//! implementations (pandas, statsmodels), with configurable tolerances.
+//!
+//! Golden files are JSON documents of the form written by `golden/generate.py`:
+//!
+//! ```text
+//! {"source": "pandas 2.2.2", "cases": [
+//!   {"name": "trend_noise", "operation": "stats", "input": [..], "params": {},
+//!    "expected": {"mean": 1.5, "quantile_0.25": 0.75, ..}},
+//!   {"name": "monthly", "operation": "stl", "input": [..], "params": {"period": 12, ..},
+//!    "expected": {"trend": [..], "seasonal": [..], "resid": [..]}},
+//!   {"name": "monthly", "operation": "acf", "input": [..], "params": {"nlags": 24},
+//!    "expected": {"acf": [..]}}
+//! ]}
+//! ```
+//!
+//! The harness covers `stats`, `stl` and `acf`. Cases for any other operation are reported
+//! as unsupported rather than failing, so a golden set can run ahead of the
+//! implementation. The crate's tests check the files in `golden/`.
+
+use crate::{Error, SerialDependence, Statistics, Stl, TimeSeries};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// Accepted difference between an actual and a golden value: `|a - e| <= absolute +
+/// relative * |e|`. NaN (JSON `null`) only matches NaN.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+    pub absolute: f64,
+    pub relative: f64,
+}
+
+impl Tolerance {
+    pub fn new(absolute: f64, relative: f64) -> Self {
+        assert!(absolute >= 0. && relative >= 0., "tolerances must be non-negative");
+        Self { absolute, relative }
+    }
+
+    pub fn accepts(&self, actual: f64, expected: f64) -> bool {
+        if expected.is_nan() || actual.is_nan() {
+            return expected.is_nan() && actual.is_nan();
+        }
+        actual == expected || (actual - expected).abs() <= self.absolute + self.relative * expected.abs()
+    }
+}
+
+impl Default for Tolerance {
+    /// `1e-9` absolute and relative.
+    fn default() -> Self {
+        Self::new(1e-9, 1e-9)
+    }
+}
+
+/// A golden value the crate did not reproduce.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    pub case: String,
+    /// The `expected` key, e.g. `"mean"` or `"trend"`.
+    pub field: String,
+    /// Position within a series-valued field.
+    pub index: Option<usize>,
+    pub actual: f64,
+    pub expected: f64,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.index {
+            Some(i) => write!(f, "{}: {}[{}] = {}, expected {}", self.case, self.field, i, self.actual, self.expected),
+            None => write!(f, "{}: {} = {}, expected {}", self.case, self.field, self.actual, self.expected),
+        }
+    }
+}
+
+/// Outcome of checking one or more golden files.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GoldenReport {
+    /// Number of individual values that matched.
+    pub passed: usize,
+    pub mismatches: Vec<Mismatch>,
+    /// `case (operation)` for cases whose operation is not implemented.
+    pub unsupported: Vec<String>,
+}
+
+impl GoldenReport {
+    /// Whether every supported value matched.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    pub fn merge(&mut self, other: GoldenReport) {
+        self.passed += other.passed;
+        self.mismatches.extend(other.mismatches);
+        self.unsupported.extend(other.unsupported);
+    }
+}
+
+/// Checks golden files, with a default tolerance and optional per-operation overrides.
+///
+/// STL is iterative and smooths near the ends differently across implementations, so it
+/// usually needs a looser tolerance than plain statistics.
+#[derive(Clone, Debug, Default)]
+pub struct GoldenHarness {
+    tolerance: Tolerance,
+    overrides: HashMap<String, Tolerance>,
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidInput(message)
+}
+
+/// A JSON number, or NaN for `null`.
+fn number(value: &Value, what: &str) -> Result<f64, Error> {
+    match value {
+        Value::Null => Ok(f64::NAN),
+        Value::Number(n) => n.as_f64().ok_or_else(|| invalid(format!("{} is out of range", what))),
+        _ => Err(invalid(format!("{} must be a number or null", what))),
+    }
+}
+
+fn numbers(value: &Value, what: &str) -> Result<Vec<f64>, Error> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid(format!("{} must be an array", what)))?
+        .iter()
+        .map(|v| number(v, what))
+        .collect()
+}
+
+fn param(params: &Map<String, Value>, key: &str) -> Result<Option<usize>, Error> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => v.as_u64().map(|n| Some(n as usize)).ok_or_else(|| invalid(format!("`{}` must be an integer", key))),
+    }
+}
+
+fn statistic(series: &TimeSeries<f64>, key: &str) -> Result<f64, Error> {
+    Ok(match key {
+        "count" => series.len() as f64,
+        "sum" => series.sum(),
+        "mean" => series.mean(),
+        "var" => series.var(),
+        "std" => series.std(),
+        "min" => series.min(),
+        "max" => series.max(),
+        "median" => series.median(),
+        _ => match key.strip_prefix("quantile_").and_then(|q| q.parse::<f64>().ok()) {
+            Some(q) => series.quantile(q),
+            None => return Err(invalid(format!("unknown statistic `{}`", key))),
+        },
+    })
+}
+
+/// STL with statsmodels' parameter names: `period`, `seasonal`, `trend`, `robust`,
+/// `inner_iter` and `outer_iter`.
+fn stl(params: &Map<String, Value>) -> Result<Stl, Error> {
+    let period = param(params, "period")?.ok_or_else(|| invalid("stl case needs `period`".to_string()))?;
+    if period < 2 {
+        return Err(invalid("`period` must be at least 2".to_string()));
+    }
+    let mut stl = Stl::new(period);
+    if params.get("robust").and_then(Value::as_bool).unwrap_or(false) {
+        stl = stl.robust();
+    }
+    Ok(Stl {
+        seasonal_window: param(params, "seasonal")?.unwrap_or(stl.seasonal_window),
+        trend_window: param(params, "trend")?.or(stl.trend_window),
+        inner_iterations: param(params, "inner_iter")?.unwrap_or(stl.inner_iterations),
+        robust_iterations: param(params, "outer_iter")?.unwrap_or(stl.robust_iterations),
+        ..stl
+    })
+}
+
+impl GoldenHarness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tolerance(self, tolerance: Tolerance) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Uses `tolerance` for cases of `operation` (e.g. `"stl"`) instead of the default.
+    pub fn with_operation_tolerance<S: Into<String>>(mut self, operation: S, tolerance: Tolerance) -> Self {
+        self.overrides.insert(operation.into(), tolerance);
+        self
+    }
+
+    pub fn tolerance(&self, operation: &str) -> Tolerance {
+        self.overrides.get(operation).copied().unwrap_or(self.tolerance)
+    }
+
+    pub fn check_file<P: AsRef<Path>>(&self, path: P) -> Result<GoldenReport, Error> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| invalid(format!("{}: {}", path.display(), e)))?;
+        self.check_str(&text)
+    }
+
+    /// Checks every `*.json` file in `dir`, in name order.
+    pub fn check_dir<P: AsRef<Path>>(&self, dir: P) -> Result<GoldenReport, Error> {
+        let entries = std::fs::read_dir(dir.as_ref()).map_err(|e| invalid(e.to_string()))?;
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        let mut report = GoldenReport::default();
+        for path in paths {
+            report.merge(self.check_file(path)?);
+        }
+        Ok(report)
+    }
+
+    pub fn check_str(&self, json: &str) -> Result<GoldenReport, Error> {
+        let document: Value = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+        let cases = document
+            .get("cases")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid("golden file needs a `cases` array".to_string()))?;
+        let mut report = GoldenReport::default();
+        for case in cases {
+            self.check_case(case, &mut report)?;
+        }
+        Ok(report)
+    }
+
+    fn check_case(&self, case: &Value, report: &mut GoldenReport) -> Result<(), Error> {
+        let name = case.get("name").and_then(Value::as_str).unwrap_or("unnamed");
+        let operation = case
+            .get("operation")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid(format!("case `{}` needs an `operation`", name)))?;
+        let input = TimeSeries(numbers(case.get("input").unwrap_or(&Value::Null), "`input`")?);
+        let empty = Map::new();
+        let params = case.get("params").and_then(Value::as_object).unwrap_or(&empty);
+        let expected = case
+            .get("expected")
+            .and_then(Value::as_object)
+            .ok_or_else(|| invalid(format!("case `{}` needs an `expected` object", name)))?;
+        let tolerance = self.tolerance(operation);
+        let mut compare = |field: &str, index: Option<usize>, actual: f64, expected: f64| {
+            if tolerance.accepts(actual, expected) {
+                report.passed += 1;
+            } else {
+                report.mismatches.push(Mismatch { case: name.to_string(), field: field.to_string(), index, actual, expected });
+            }
+        };
+
+        match operation {
+            "stats" => {
+                for (key, value) in expected {
+                    compare(key, None, statistic(&input, key)?, number(value, key)?);
+                }
+            }
+            "stl" => {
+                let result = stl(params)?.decompose(&input);
+                for (key, value) in expected {
+                    let actual = match key.as_str() {
+                        "trend" => &result.trend,
+                        "seasonal" => &result.seasonal,
+                        "resid" => &result.remainder,
+                        other => return Err(invalid(format!("unknown stl component `{}`", other))),
+                    };
+                    let golden = numbers(value, key)?;
+                    if golden.len() != actual.len() {
+                        return Err(Error::LengthMismatch { expected: golden.len(), found: actual.len() });
+                    }
+                    for (i, (&a, &e)) in actual.as_slice().iter().zip(&golden).enumerate() {
+                        compare(key, Some(i), a, e);
+                    }
+                }
+            }
+            "acf" => {
+                let nlags = param(params, "nlags")?.ok_or_else(|| invalid("acf case needs `nlags`".to_string()))?;
+                for (key, value) in expected {
+                    if key != "acf" {
+                        return Err(invalid(format!("unknown acf output `{}`", key)));
+                    }
+                    let golden = numbers(value, key)?;
+                    let actual = input.autocorrelations(nlags);
+                    if golden.len() != actual.len() {
+                        return Err(Error::LengthMismatch { expected: golden.len(), found: actual.len() });
+                    }
+                    for (i, (&a, &e)) in actual.iter().zip(&golden).enumerate() {
+                        compare(key, Some(i), a, e);
+                    }
+                }
+            }
+            other => report.unsupported.push(format!("{} ({})", name, other)),
+        }
+        Ok(())
+    }
+}
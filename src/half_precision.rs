@@ -0,0 +1,97 @@
+//! Statistics over 16-bit float series (`half::f16` and `half::bf16`).
+//!
+//! Values are widened to `f32` inside each reduction, so storage stays at two bytes per
+//! point while sums do not lose precision to the narrow format.
+
+use crate::stats::quantile_sorted;
+use crate::{Statistics, TimeSeries};
+use half::{bf16, f16};
+
+/// Conversion between `f64` series and a 16-bit storage format.
+pub trait HalfPrecision: Sized {
+    fn from_f64_series(series: &TimeSeries<f64>) -> TimeSeries<Self>;
+    fn to_f64_series(series: &TimeSeries<Self>) -> TimeSeries<f64>;
+}
+
+macro_rules! half_statistics {
+    ($($t:ty),*) => {$(
+        impl HalfPrecision for $t {
+            fn from_f64_series(series: &TimeSeries<f64>) -> TimeSeries<Self> {
+                series.map(|&x| <$t>::from_f64(x))
+            }
+
+            fn to_f64_series(series: &TimeSeries<Self>) -> TimeSeries<f64> {
+                series.map(|x| x.to_f64())
+            }
+        }
+
+        impl Statistics for TimeSeries<$t> {
+            fn sum(&self) -> f64 {
+                self.as_slice().iter().map(|x| x.to_f32()).sum::<f32>() as f64
+            }
+
+            fn mean(&self) -> f64 {
+                self.sum() / self.len() as f64
+            }
+
+            fn var(&self) -> f64 {
+                self.cov(self)
+            }
+
+            fn std(&self) -> f64 {
+                self.var().sqrt()
+            }
+
+            fn min(&self) -> f64 {
+                if self.is_empty() {
+                    return f64::NAN;
+                }
+                self.as_slice().iter().map(|x| x.to_f32()).fold(f32::INFINITY, f32::min) as f64
+            }
+
+            fn max(&self) -> f64 {
+                if self.is_empty() {
+                    return f64::NAN;
+                }
+                self.as_slice().iter().map(|x| x.to_f32()).fold(f32::NEG_INFINITY, f32::max) as f64
+            }
+
+            fn cov(&self, other: &Self) -> f64 {
+                let n = self.len().min(other.len());
+                if n < 2 {
+                    return f64::NAN;
+                }
+                let mean_x = self.as_slice()[..n].iter().map(|x| x.to_f32()).sum::<f32>() / n as f32;
+                let mean_y = other.as_slice()[..n].iter().map(|y| y.to_f32()).sum::<f32>() / n as f32;
+                let s: f32 = self.as_slice()[..n]
+                    .iter()
+                    .zip(&other.as_slice()[..n])
+                    .map(|(x, y)| (x.to_f32() - mean_x) * (y.to_f32() - mean_y))
+                    .sum();
+                s as f64 / (n - 1) as f64
+            }
+
+            fn corr(&self, other: &Self) -> f64 {
+                let n = self.len().min(other.len());
+                let x = self.slice(0..n);
+                let y = other.slice(0..n);
+                x.cov(&y) / (x.std() * y.std())
+            }
+
+            fn quantile(&self, q: f64) -> f64 {
+                if self.is_empty() {
+                    return f64::NAN;
+                }
+                let mut sorted: Vec<f64> = self.as_slice().iter().map(|x| x.to_f64()).collect();
+                sorted.sort_by(|a, b| a.total_cmp(b));
+                quantile_sorted(&sorted, q)
+            }
+
+            fn median(&self) -> f64 {
+                self.quantile(0.5)
+            }
+        }
+    )*};
+}
+
+half_statistics!(f16, bf16);
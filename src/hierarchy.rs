@@ -0,0 +1,307 @@
+//! Coherent reconciliation of forecasts for series organized in a hierarchy
+//! (e.g. total → regions → stores), where every parent is the sum of its children, and
+//! checks that stored rollups keep to that rule.
+
+use crate::linalg::solve;
+use crate::{Error, TimeSeries, TimestampedSeries};
+use std::collections::{BTreeSet, HashMap};
+
+/// Parent/child relations between named series.
+#[derive(Clone, Debug, Default)]
+pub struct Hierarchy {
+    nodes: Vec<String>,
+    children: HashMap<String, Vec<String>>,
+}
+
+/// How base forecasts are made coherent by [`Hierarchy::reconcile`].
+#[derive(Clone, Copy, Debug)]
+pub enum Reconciliation<'a> {
+    /// Sum the leaf forecasts upwards; forecasts of inner nodes are ignored.
+    BottomUp,
+    /// Split the root forecast by each leaf's share of the historical root total.
+    TopDown { history: &'a HashMap<String, TimeSeries<f64>> },
+    /// Minimum-trace (MinT) reconciliation, using the covariance of the in-sample forecast
+    /// residuals of every node shrunk towards its diagonal.
+    MinT { residuals: &'a HashMap<String, TimeSeries<f64>> },
+}
+
+impl Hierarchy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares `child` as a component of `parent`.
+    pub fn add(&mut self, parent: &str, child: &str) -> &mut Self {
+        for name in [parent, child] {
+            if !self.nodes.iter().any(|n| n == name) {
+                self.nodes.push(name.to_string());
+            }
+        }
+        self.children.entry(parent.to_string()).or_default().push(child.to_string());
+        self
+    }
+
+    /// All nodes in insertion order.
+    pub fn nodes(&self) -> &[String] {
+        &self.nodes
+    }
+
+    /// Nodes without children, i.e. the bottom level.
+    pub fn leaves(&self) -> Vec<&str> {
+        self.nodes.iter().filter(|n| !self.children.contains_key(*n)).map(String::as_str).collect()
+    }
+
+    /// The node that is nobody's child.
+    pub fn root(&self) -> Option<&str> {
+        self.nodes
+            .iter()
+            .find(|n| !self.children.values().any(|c| c.contains(n)))
+            .map(String::as_str)
+    }
+
+    fn leaves_under<'a>(&'a self, node: &'a str, out: &mut Vec<&'a str>) {
+        match self.children.get(node) {
+            Some(children) => children.iter().for_each(|c| self.leaves_under(c, out)),
+            None => out.push(node),
+        }
+    }
+
+    /// Summing matrix: one row per node, one column per leaf.
+    fn summing_matrix(&self) -> Vec<Vec<f64>> {
+        let leaves = self.leaves();
+        self.nodes
+            .iter()
+            .map(|node| {
+                let mut under = Vec::new();
+                self.leaves_under(node, &mut under);
+                leaves.iter().map(|l| if under.contains(l) { 1. } else { 0. }).collect()
+            })
+            .collect()
+    }
+
+    /// Checks every parent against its children with [`check_consistency`], by name.
+    ///
+    /// Fails if a node has no series.
+    ///
+    /// # Panics
+    /// Panics if `tolerance` is negative.
+    pub fn check_consistency(
+        &self,
+        series: &HashMap<String, TimestampedSeries<f64>>,
+        tolerance: f64,
+    ) -> Result<HashMap<String, ConsistencyReport>, Error> {
+        let lookup = |name: &str| series.get(name).ok_or_else(|| Error::MissingSeries(name.to_string()));
+        self.nodes
+            .iter()
+            .filter_map(|node| self.children.get(node).map(|children| (node, children)))
+            .map(|(node, children)| {
+                let children = children.iter().map(|c| lookup(c)).collect::<Result<Vec<_>, _>>()?;
+                Ok((node.clone(), check_consistency(lookup(node)?, &children, tolerance)))
+            })
+            .collect()
+    }
+
+    /// Reconciles base forecasts (one series per node, all of the same horizon) so that
+    /// every parent equals the sum of its children.
+    pub fn reconcile(
+        &self,
+        forecasts: &HashMap<String, TimeSeries<f64>>,
+        method: Reconciliation,
+    ) -> Result<HashMap<String, TimeSeries<f64>>, Error> {
+        let leaves = self.leaves();
+        let s = self.summing_matrix();
+        let lookup = |set: &HashMap<String, TimeSeries<f64>>, name: &str| {
+            set.get(name).cloned().ok_or_else(|| Error::MissingSeries(name.to_string()))
+        };
+        let bottom: Vec<TimeSeries<f64>> = match method {
+            Reconciliation::BottomUp => {
+                leaves.iter().map(|l| lookup(forecasts, l)).collect::<Result<_, _>>()?
+            }
+            Reconciliation::TopDown { history } => {
+                let root = self.root().ok_or_else(|| Error::MissingSeries("root".to_string()))?;
+                let top = lookup(forecasts, root)?;
+                let totals = leaves
+                    .iter()
+                    .map(|l| lookup(history, l).map(|h| h.as_slice().iter().sum::<f64>()))
+                    .collect::<Result<Vec<f64>, _>>()?;
+                let grand_total: f64 = totals.iter().sum();
+                totals.iter().map(|t| top.map(|x| x * t / grand_total)).collect()
+            }
+            Reconciliation::MinT { residuals } => {
+                let base: Vec<TimeSeries<f64>> =
+                    self.nodes.iter().map(|n| lookup(forecasts, n)).collect::<Result<_, _>>()?;
+                let res: Vec<TimeSeries<f64>> =
+                    self.nodes.iter().map(|n| lookup(residuals, n)).collect::<Result<_, _>>()?;
+                mint(&s, &base, &shrunk_covariance(&res)?)?
+            }
+        };
+        let horizon = bottom.iter().map(|b| b.len()).min().unwrap_or(0);
+        Ok(self
+            .nodes
+            .iter()
+            .zip(&s)
+            .map(|(node, row)| {
+                let series = (0..horizon)
+                    .map(|h| row.iter().zip(&bottom).map(|(w, b)| w * b[h]).sum())
+                    .collect();
+                (node.clone(), series)
+            })
+            .collect())
+    }
+}
+
+/// A run of consecutive buckets where a parent is not the sum of its children.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Inconsistency {
+    /// Timestamps of the first and last bucket of the run.
+    pub start: i64,
+    pub end: i64,
+    pub buckets: usize,
+    /// Largest absolute difference over the run's buckets where every series has a value.
+    pub max_difference: f64,
+    /// Whether the parent or a child lacks a value in some bucket of the run.
+    pub missing: bool,
+}
+
+/// The outcome of [`check_consistency`].
+#[derive(Clone, Debug)]
+pub struct ConsistencyReport {
+    /// The parent minus the sum of the children at every bucket of any of them; NaN
+    /// where one of them has no value.
+    pub differences: TimestampedSeries<f64>,
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+}
+
+/// The value of `series` at exactly `timestamp`.
+fn value_at(series: &TimestampedSeries<f64>, timestamp: i64) -> Option<f64> {
+    let i = series.asof_index(timestamp)?;
+    (series.timestamps()[i] == timestamp).then(|| series.values()[i])
+}
+
+/// Checks that the children add up to the parent in every bucket (timestamp) of any of
+/// them, within `tolerance` in absolute terms, as a rollup should. A bucket missing from
+/// the parent or from a child is a violation, as is a NaN value; consecutive violating
+/// buckets are reported as one run.
+///
+/// # Panics
+/// Panics if `tolerance` is negative.
+pub fn check_consistency(
+    parent: &TimestampedSeries<f64>,
+    children: &[&TimestampedSeries<f64>],
+    tolerance: f64,
+) -> ConsistencyReport {
+    assert!(tolerance >= 0., "tolerance must not be negative");
+    let buckets: BTreeSet<i64> =
+        std::iter::once(parent).chain(children.iter().copied()).flat_map(|s| s.timestamps().iter().copied()).collect();
+    let mut differences = TimestampedSeries::new();
+    let mut inconsistencies: Vec<Inconsistency> = Vec::new();
+    let mut in_run = false;
+    for t in buckets {
+        let parts = children.iter().map(|c| value_at(c, t)).sum::<Option<f64>>();
+        let difference = match (value_at(parent, t), parts) {
+            (Some(p), Some(c)) => p - c,
+            _ => f64::NAN,
+        };
+        differences.push(t, difference);
+        // A NaN difference fails the comparison too.
+        if difference.abs() <= tolerance {
+            in_run = false;
+            continue;
+        }
+        match inconsistencies.last_mut().filter(|_| in_run) {
+            Some(run) => {
+                run.end = t;
+                run.buckets += 1;
+                run.max_difference = run.max_difference.max(difference.abs());
+                run.missing |= difference.is_nan();
+            }
+            None => inconsistencies.push(Inconsistency {
+                start: t,
+                end: t,
+                buckets: 1,
+                max_difference: difference.abs(),
+                missing: difference.is_nan(),
+            }),
+        }
+        in_run = true;
+    }
+    let differences = match parent.unit() {
+        Some(unit) => differences.with_unit(unit),
+        None => differences,
+    };
+    ConsistencyReport { differences, inconsistencies }
+}
+
+/// Bottom-level forecasts `(S' W⁻¹ S)⁻¹ S' W⁻¹ ŷ` for each horizon step.
+fn mint(s: &[Vec<f64>], base: &[TimeSeries<f64>], w: &[Vec<f64>]) -> Result<Vec<TimeSeries<f64>>, Error> {
+    let n = s.len();
+    let m = s[0].len();
+    // W⁻¹ S, one column at a time.
+    let mut w_inv_s = vec![vec![0.; m]; n];
+    for j in 0..m {
+        let column = solve(w.to_vec(), s.iter().map(|row| row[j]).collect()).ok_or(Error::Singular)?;
+        for i in 0..n {
+            w_inv_s[i][j] = column[i];
+        }
+    }
+    let a: Vec<Vec<f64>> = (0..m)
+        .map(|i| (0..m).map(|j| (0..n).map(|k| s[k][i] * w_inv_s[k][j]).sum()).collect())
+        .collect();
+    let horizon = base.iter().map(|b| b.len()).min().unwrap_or(0);
+    let mut bottom = vec![Vec::with_capacity(horizon); m];
+    for y in (0..horizon).map(|h| base.iter().map(|b| b[h]).collect::<Vec<f64>>()) {
+        let rhs = (0..m).map(|j| (0..n).map(|k| w_inv_s[k][j] * y[k]).sum()).collect();
+        let beta = solve(a.clone(), rhs).ok_or(Error::Singular)?;
+        for (b, x) in bottom.iter_mut().zip(beta) {
+            b.push(x);
+        }
+    }
+    Ok(bottom.into_iter().map(TimeSeries).collect())
+}
+
+/// Sample covariance of the residuals shrunk towards its diagonal with the
+/// Schäfer–Strimmer intensity.
+fn shrunk_covariance(residuals: &[TimeSeries<f64>]) -> Result<Vec<Vec<f64>>, Error> {
+    let k = residuals.len();
+    let t = residuals.iter().map(|r| r.len()).min().unwrap_or(0);
+    if t < 2 {
+        return Err(Error::LengthMismatch { expected: 2, found: t });
+    }
+    let means: Vec<f64> = residuals.iter().map(|r| r.as_slice()[..t].iter().sum::<f64>() / t as f64).collect();
+    let sds: Vec<f64> = residuals
+        .iter()
+        .zip(&means)
+        .map(|(r, m)| (r.as_slice()[..t].iter().map(|x| (x - m).powi(2)).sum::<f64>() / (t - 1) as f64).sqrt())
+        .collect();
+    let z = |i: usize, s: usize| (residuals[i][s] - means[i]) / sds[i];
+    let mut cov = vec![vec![0.; k]; k];
+    let (mut num, mut den) = (0., 0.);
+    for i in 0..k {
+        for j in 0..k {
+            let w: Vec<f64> = (0..t).map(|s| z(i, s) * z(j, s)).collect();
+            let w_mean = w.iter().sum::<f64>() / t as f64;
+            let r = w_mean * t as f64 / (t - 1) as f64;
+            cov[i][j] = r * sds[i] * sds[j];
+            if i != j {
+                let var_w: f64 = w.iter().map(|x| (x - w_mean).powi(2)).sum();
+                num += t as f64 / ((t - 1) as f64).powi(3) * var_w;
+                den += r * r;
+            }
+        }
+    }
+    let lambda = if den > 0. { (num / den).clamp(0., 1.) } else { 1. };
+    for (i, row) in cov.iter_mut().enumerate() {
+        for (j, c) in row.iter_mut().enumerate() {
+            if i != j {
+                *c *= 1. - lambda;
+            }
+        }
+    }
+    Ok(cov)
+}
@@ -0,0 +1,371 @@
+//! Technical indicators on OHLCV bars.
+//!
+//! Like the rolling statistics, each indicator drops the leading points where it is not
+//! yet defined, so outputs are shorter than the input and aligned to its end. Smoothed
+//! indicators use Wilder's averaging, matching TA-Lib.
+
+use crate::{CalendarPeriod, TimeSeries, TimestampedSeries};
+
+/// One price bar.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Ohlcv {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+impl Ohlcv {
+    /// `(high + low + close) / 3`.
+    pub fn typical_price(&self) -> f64 {
+        (self.high + self.low + self.close) / 3.
+    }
+
+    /// Range of the bar extended to the previous close, so gaps count as volatility.
+    pub fn true_range(&self, previous_close: f64) -> f64 {
+        (self.high - self.low)
+            .max((self.high - previous_close).abs())
+            .max((self.low - previous_close).abs())
+    }
+}
+
+/// Stochastic oscillator lines, both in `[0, 100]` and of equal length.
+#[derive(Clone, Debug)]
+pub struct Stochastic {
+    /// Position of the close within the recent high-low range (%K).
+    pub k: TimeSeries<f64>,
+    /// Simple moving average of %K (%D).
+    pub d: TimeSeries<f64>,
+}
+
+/// Directional movement system, all of equal length.
+#[derive(Clone, Debug)]
+pub struct Dmi {
+    pub plus_di: TimeSeries<f64>,
+    pub minus_di: TimeSeries<f64>,
+    /// Average directional index: trend strength regardless of direction.
+    pub adx: TimeSeries<f64>,
+}
+
+/// Ichimoku Kinko Hyo lines.
+///
+/// Unlike the other indicators these are aligned with the input bars, with NaN where a
+/// line is not yet defined, because the spans are displaced in time: `senkou_a` and
+/// `senkou_b` extend `displacement` bars past the last bar, and `chikou` ends
+/// `displacement` bars early.
+#[derive(Clone, Debug)]
+pub struct Ichimoku {
+    /// Conversion line: midpoint of the `tenkan`-bar high and low.
+    pub tenkan: TimeSeries<f64>,
+    /// Base line: midpoint of the `kijun`-bar high and low.
+    pub kijun: TimeSeries<f64>,
+    /// Leading span A: mean of tenkan and kijun, plotted ahead.
+    pub senkou_a: TimeSeries<f64>,
+    /// Leading span B: midpoint of the `senkou`-bar high and low, plotted ahead.
+    pub senkou_b: TimeSeries<f64>,
+    /// Lagging span: the close plotted `displacement` bars back.
+    pub chikou: TimeSeries<f64>,
+}
+
+/// Floor-trader pivot levels for a period, derived from the previous period's bar.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PivotLevels {
+    pub pivot: f64,
+    pub r1: f64,
+    pub r2: f64,
+    pub r3: f64,
+    pub s1: f64,
+    pub s2: f64,
+    pub s3: f64,
+}
+
+impl PivotLevels {
+    pub fn from_bar(bar: &Ohlcv) -> Self {
+        let p = bar.typical_price();
+        let range = bar.high - bar.low;
+        Self {
+            pivot: p,
+            r1: 2. * p - bar.low,
+            r2: p + range,
+            r3: bar.high + 2. * (p - bar.low),
+            s1: 2. * p - bar.high,
+            s2: p - range,
+            s3: bar.low - 2. * (bar.high - p),
+        }
+    }
+}
+
+/// A Renko brick of fixed price height, rising when `close > open`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Brick {
+    /// Index of the input bar whose close completed the brick.
+    pub index: usize,
+    pub open: f64,
+    pub close: f64,
+}
+
+pub trait Indicators {
+    /// Stochastic oscillator over `k_period` bars with a `d_period` signal line.
+    /// Outputs have `len - k_period - d_period + 2` points.
+    fn stochastic(&self, k_period: usize, d_period: usize) -> Stochastic;
+    /// Williams %R in `[-100, 0]`: distance of the close below the `period` high.
+    fn williams_r(&self, period: usize) -> TimeSeries<f64>;
+    /// True range of each bar after the first (`len - 1` points).
+    fn true_range(&self) -> TimeSeries<f64>;
+    /// Average true range, Wilder-smoothed over `period` (`len - period` points).
+    fn atr(&self, period: usize) -> TimeSeries<f64>;
+    /// +DI, -DI and ADX over `period` (`len - 2 * period + 1` points).
+    fn dmi(&self, period: usize) -> Dmi;
+    /// Commodity channel index: deviation of the typical price from its `period` mean in
+    /// units of 0.015 mean absolute deviations (`len - period + 1` points).
+    fn cci(&self, period: usize) -> TimeSeries<f64>;
+    /// On-balance volume, starting from the first bar's volume.
+    fn obv(&self) -> TimeSeries<f64>;
+    /// Ichimoku lines with the given periods; the classic setting is `(9, 26, 52, 26)`.
+    fn ichimoku(&self, tenkan: usize, kijun: usize, senkou: usize, displacement: usize) -> Ichimoku;
+    /// Heikin-Ashi candles: averaged bars that smooth out noise, one per input bar.
+    fn to_heikin_ashi(&self) -> TimeSeries<Ohlcv>;
+    /// Renko bricks of height `brick_size` from the closes, which drop the time axis and
+    /// only record price moves. Continuing needs one brick height beyond the last brick's
+    /// close, a reversal two.
+    fn to_renko(&self, brick_size: f64) -> Vec<Brick>;
+}
+
+/// Midpoint of the high and low over each trailing `period`, NaN before it fills.
+fn midpoint(bars: &[Ohlcv], period: usize) -> Vec<f64> {
+    (0..bars.len())
+        .map(|i| {
+            if i + 1 < period {
+                return f64::NAN;
+            }
+            let w = &bars[i + 1 - period..=i];
+            let high = w.iter().map(|b| b.high).fold(f64::NEG_INFINITY, f64::max);
+            let low = w.iter().map(|b| b.low).fold(f64::INFINITY, f64::min);
+            (high + low) / 2.
+        })
+        .collect()
+}
+
+/// Aggregates bars into calendar periods: first open, highest high, lowest low, last
+/// close and total volume, keyed by the period start.
+pub fn aggregate_bars(bars: &TimestampedSeries<Ohlcv>, period: CalendarPeriod) -> TimestampedSeries<Ohlcv> {
+    let mut out: Vec<(i64, Ohlcv)> = Vec::new();
+    for (t, bar) in bars.iter() {
+        let start = period.floor(t);
+        match out.last_mut() {
+            Some((s, agg)) if *s == start => {
+                agg.high = agg.high.max(bar.high);
+                agg.low = agg.low.min(bar.low);
+                agg.close = bar.close;
+                agg.volume += bar.volume;
+            }
+            _ => out.push((start, *bar)),
+        }
+    }
+    out.into_iter().collect()
+}
+
+/// Pivot levels in force during each calendar period, keyed by the period start and
+/// computed from the preceding period's aggregated bar. The first period has none.
+pub fn pivot_points(bars: &TimestampedSeries<Ohlcv>, period: CalendarPeriod) -> TimestampedSeries<PivotLevels> {
+    let aggregated = aggregate_bars(bars, period);
+    let timestamps = aggregated.timestamps();
+    aggregated
+        .values()
+        .as_slice()
+        .windows(2)
+        .zip(&timestamps[1..])
+        .map(|(w, &t)| (t, PivotLevels::from_bar(&w[0])))
+        .collect()
+}
+
+fn sma(x: &[f64], period: usize) -> Vec<f64> {
+    if x.len() < period {
+        return Vec::new();
+    }
+    x.windows(period).map(|w| w.iter().sum::<f64>() / period as f64).collect()
+}
+
+/// Wilder's moving average: seeded with the mean of the first `period` values, then
+/// `avg = avg + (x - avg) / period`. Has `len - period + 1` points.
+fn wilder(x: &[f64], period: usize) -> Vec<f64> {
+    if x.len() < period {
+        return Vec::new();
+    }
+    let mut avg = x[..period].iter().sum::<f64>() / period as f64;
+    let mut out = vec![avg];
+    for v in &x[period..] {
+        avg += (v - avg) / period as f64;
+        out.push(avg);
+    }
+    out
+}
+
+impl Indicators for TimeSeries<Ohlcv> {
+    fn stochastic(&self, k_period: usize, d_period: usize) -> Stochastic {
+        assert!(k_period > 0 && d_period > 0, "periods must be positive");
+        let bars = self.as_slice();
+        let k: Vec<f64> = if bars.len() < k_period {
+            Vec::new()
+        } else {
+            bars.windows(k_period)
+                .map(|w| {
+                    let high = w.iter().map(|b| b.high).fold(f64::NEG_INFINITY, f64::max);
+                    let low = w.iter().map(|b| b.low).fold(f64::INFINITY, f64::min);
+                    100. * (w[k_period - 1].close - low) / (high - low)
+                })
+                .collect()
+        };
+        let d = sma(&k, d_period);
+        let k = k[k.len() - d.len()..].to_vec();
+        Stochastic { k: TimeSeries(k), d: TimeSeries(d) }
+    }
+
+    fn williams_r(&self, period: usize) -> TimeSeries<f64> {
+        assert!(period > 0, "period must be positive");
+        if self.len() < period {
+            return TimeSeries::new();
+        }
+        self.as_slice()
+            .windows(period)
+            .map(|w| {
+                let high = w.iter().map(|b| b.high).fold(f64::NEG_INFINITY, f64::max);
+                let low = w.iter().map(|b| b.low).fold(f64::INFINITY, f64::min);
+                -100. * (high - w[period - 1].close) / (high - low)
+            })
+            .collect()
+    }
+
+    fn true_range(&self) -> TimeSeries<f64> {
+        self.as_slice().windows(2).map(|w| w[1].true_range(w[0].close)).collect()
+    }
+
+    fn atr(&self, period: usize) -> TimeSeries<f64> {
+        assert!(period > 0, "period must be positive");
+        TimeSeries(wilder(self.true_range().as_slice(), period))
+    }
+
+    fn dmi(&self, period: usize) -> Dmi {
+        assert!(period > 0, "period must be positive");
+        let tr = self.true_range().into_vec();
+        let (plus_dm, minus_dm): (Vec<f64>, Vec<f64>) = self
+            .as_slice()
+            .windows(2)
+            .map(|w| {
+                let up = w[1].high - w[0].high;
+                let down = w[0].low - w[1].low;
+                (
+                    if up > down && up > 0. { up } else { 0. },
+                    if down > up && down > 0. { down } else { 0. },
+                )
+            })
+            .unzip();
+        let tr = wilder(&tr, period);
+        let plus_di: Vec<f64> = wilder(&plus_dm, period).iter().zip(&tr).map(|(dm, tr)| 100. * dm / tr).collect();
+        let minus_di: Vec<f64> = wilder(&minus_dm, period).iter().zip(&tr).map(|(dm, tr)| 100. * dm / tr).collect();
+        let dx: Vec<f64> = plus_di
+            .iter()
+            .zip(&minus_di)
+            .map(|(p, m)| if p + m == 0. { 0. } else { 100. * (p - m).abs() / (p + m) })
+            .collect();
+        let adx = wilder(&dx, period);
+        let skip = plus_di.len() - adx.len();
+        Dmi {
+            plus_di: TimeSeries(plus_di[skip..].to_vec()),
+            minus_di: TimeSeries(minus_di[skip..].to_vec()),
+            adx: TimeSeries(adx),
+        }
+    }
+
+    fn cci(&self, period: usize) -> TimeSeries<f64> {
+        assert!(period > 0, "period must be positive");
+        let tp: Vec<f64> = self.as_slice().iter().map(Ohlcv::typical_price).collect();
+        if tp.len() < period {
+            return TimeSeries::new();
+        }
+        tp.windows(period)
+            .map(|w| {
+                let mean = w.iter().sum::<f64>() / period as f64;
+                let mean_deviation = w.iter().map(|x| (x - mean).abs()).sum::<f64>() / period as f64;
+                (w[period - 1] - mean) / (0.015 * mean_deviation)
+            })
+            .collect()
+    }
+
+    fn obv(&self) -> TimeSeries<f64> {
+        let mut out = Vec::with_capacity(self.len());
+        let mut total = 0.;
+        for (i, bar) in self.as_slice().iter().enumerate() {
+            if i == 0 {
+                total = bar.volume;
+            } else if bar.close > self.as_slice()[i - 1].close {
+                total += bar.volume;
+            } else if bar.close < self.as_slice()[i - 1].close {
+                total -= bar.volume;
+            }
+            out.push(total);
+        }
+        TimeSeries(out)
+    }
+
+    fn ichimoku(&self, tenkan: usize, kijun: usize, senkou: usize, displacement: usize) -> Ichimoku {
+        assert!(tenkan > 0 && kijun > 0 && senkou > 0, "periods must be positive");
+        let n = self.len();
+        let tenkan_line = midpoint(self.as_slice(), tenkan);
+        let kijun_line = midpoint(self.as_slice(), kijun);
+        let lead = |values: Vec<f64>| {
+            let mut shifted = vec![f64::NAN; displacement];
+            shifted.extend(values);
+            TimeSeries(shifted)
+        };
+        let senkou_a = lead(tenkan_line.iter().zip(&kijun_line).map(|(t, k)| (t + k) / 2.).collect());
+        let senkou_b = lead(midpoint(self.as_slice(), senkou));
+        let chikou = (0..n).map(|i| self.as_slice().get(i + displacement).map_or(f64::NAN, |b| b.close)).collect();
+        Ichimoku {
+            tenkan: TimeSeries(tenkan_line),
+            kijun: TimeSeries(kijun_line),
+            senkou_a,
+            senkou_b,
+            chikou,
+        }
+    }
+
+    fn to_heikin_ashi(&self) -> TimeSeries<Ohlcv> {
+        let mut out: Vec<Ohlcv> = Vec::with_capacity(self.len());
+        for bar in self.as_slice() {
+            let close = (bar.open + bar.high + bar.low + bar.close) / 4.;
+            let open = match out.last() {
+                Some(prev) => (prev.open + prev.close) / 2.,
+                None => (bar.open + bar.close) / 2.,
+            };
+            out.push(Ohlcv {
+                open,
+                high: bar.high.max(open).max(close),
+                low: bar.low.min(open).min(close),
+                close,
+                volume: bar.volume,
+            });
+        }
+        TimeSeries(out)
+    }
+
+    fn to_renko(&self, brick_size: f64) -> Vec<Brick> {
+        assert!(brick_size > 0., "brick size must be positive");
+        let mut bricks: Vec<Brick> = Vec::new();
+        let Some(first) = self.as_slice().first() else { return bricks };
+        // Bottom and top of the last brick; both start at the first close.
+        let (mut low, mut high) = (first.close, first.close);
+        for (index, bar) in self.as_slice().iter().enumerate().skip(1) {
+            while bar.close >= high + brick_size {
+                bricks.push(Brick { index, open: high, close: high + brick_size });
+                (low, high) = (high, high + brick_size);
+            }
+            while bar.close <= low - brick_size {
+                bricks.push(Brick { index, open: low, close: low - brick_size });
+                (low, high) = (low - brick_size, low);
+            }
+        }
+        bricks
+    }
+}
@@ -0,0 +1,328 @@
+//! Scheduled polling of industrial devices into series of readings with quality flags.
+//!
+//! A [`Poller`] reads a set of named points from a [`PollSource`] at a fixed interval.
+//! [`ModbusTcp`] is a built-in source for holding/input registers, coils and discrete
+//! inputs over Modbus TCP; other protocols such as OPC-UA plug in by implementing
+//! [`PollSource`] over their client library, with node ids as addresses.
+
+use crate::{CancellationToken, Clock, Error, Flagged, QualityFlag, TimestampedSeries};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Trust in a reading, after OPC-UA status codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataQuality {
+    Good,
+    /// Read, but outside the point's valid range, e.g. a sensor at its rail.
+    Uncertain,
+    /// The read failed; the value is NaN.
+    Bad,
+}
+
+/// One polled value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reading {
+    pub value: f64,
+    pub quality: DataQuality,
+}
+
+impl From<Reading> for Flagged {
+    /// Uncertain readings become [`Suspect`](QualityFlag::Suspect) and bad ones
+    /// [`Missing`](QualityFlag::Missing).
+    fn from(reading: Reading) -> Self {
+        let flag = match reading.quality {
+            DataQuality::Good => QualityFlag::Good,
+            DataQuality::Uncertain => QualityFlag::Suspect,
+            DataQuality::Bad => QualityFlag::Missing,
+        };
+        Flagged::new(reading.value, flag)
+    }
+}
+
+/// A device that can be read one point at a time.
+pub trait PollSource {
+    /// Identifies a point on the device, e.g. a register or an OPC-UA node id.
+    type Address;
+    fn read(&mut self, address: &Self::Address) -> Result<f64, Error>;
+}
+
+/// The Modbus table a register lives in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegisterKind {
+    Coil,
+    DiscreteInput,
+    Holding,
+    Input,
+}
+
+/// How the 16-bit words of a register value are interpreted. Multi-word values are
+/// big-endian unless `word_swap` is set on the [`Register`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegisterFormat {
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+}
+
+impl RegisterFormat {
+    fn words(&self) -> u16 {
+        match self {
+            RegisterFormat::U16 | RegisterFormat::I16 => 1,
+            _ => 2,
+        }
+    }
+}
+
+/// A Modbus point: table, address and decoding, with `value = raw * scale + offset`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Register {
+    pub kind: RegisterKind,
+    pub address: u16,
+    pub format: RegisterFormat,
+    pub word_swap: bool,
+    pub scale: f64,
+    pub offset: f64,
+}
+
+impl Register {
+    /// An unscaled `U16` point; coils and discrete inputs read as 0 or 1.
+    pub fn new(kind: RegisterKind, address: u16) -> Self {
+        Self { kind, address, format: RegisterFormat::U16, word_swap: false, scale: 1., offset: 0. }
+    }
+
+    pub fn holding(address: u16) -> Self {
+        Self::new(RegisterKind::Holding, address)
+    }
+
+    pub fn input(address: u16) -> Self {
+        Self::new(RegisterKind::Input, address)
+    }
+
+    pub fn with_format(self, format: RegisterFormat) -> Self {
+        Self { format, ..self }
+    }
+
+    /// Takes the low word first in multi-word values, as many PLCs store them.
+    pub fn with_word_swap(self) -> Self {
+        Self { word_swap: true, ..self }
+    }
+
+    pub fn with_scale(self, scale: f64, offset: f64) -> Self {
+        Self { scale, offset, ..self }
+    }
+
+    fn decode(&self, words: &[u16]) -> f64 {
+        let (hi, lo) = match words {
+            [w] => (0, *w),
+            [a, b] if self.word_swap => (*b, *a),
+            [a, b] => (*a, *b),
+            _ => unreachable!("registers span one or two words"),
+        };
+        let wide = (hi as u32) << 16 | lo as u32;
+        let raw = match self.format {
+            RegisterFormat::U16 => lo as f64,
+            RegisterFormat::I16 => lo as i16 as f64,
+            RegisterFormat::U32 => wide as f64,
+            RegisterFormat::I32 => wide as i32 as f64,
+            RegisterFormat::F32 => f32::from_bits(wide) as f64,
+        };
+        raw * self.scale + self.offset
+    }
+}
+
+/// A Modbus TCP client. The connection is opened on the first read and reopened after
+/// a failure.
+#[derive(Debug)]
+pub struct ModbusTcp {
+    addr: SocketAddr,
+    unit: u8,
+    timeout: Duration,
+    stream: Option<TcpStream>,
+    transaction: u16,
+}
+
+impl ModbusTcp {
+    /// A client for unit (slave) `unit` of the device at `addr`, with a 1 s timeout.
+    pub fn new(addr: SocketAddr, unit: u8) -> Self {
+        Self { addr, unit, timeout: Duration::from_secs(1), stream: None, transaction: 0 }
+    }
+
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    fn connect(&mut self) -> std::io::Result<&mut TcpStream> {
+        if self.stream.is_none() {
+            let stream = TcpStream::connect_timeout(&self.addr, self.timeout)?;
+            stream.set_read_timeout(Some(self.timeout))?;
+            stream.set_write_timeout(Some(self.timeout))?;
+            self.stream = Some(stream);
+        }
+        Ok(self.stream.as_mut().expect("connected above"))
+    }
+
+    /// Sends one read request and returns the data bytes of the response.
+    fn request(&mut self, function: u8, address: u16, count: u16) -> Result<Vec<u8>, Error> {
+        let io_error = |e: std::io::Error| Error::InvalidInput(format!("modbus: {}", e));
+        self.transaction = self.transaction.wrapping_add(1);
+        let transaction = self.transaction;
+        let mut frame = Vec::with_capacity(12);
+        frame.extend_from_slice(&transaction.to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0, 6, self.unit, function]);
+        frame.extend_from_slice(&address.to_be_bytes());
+        frame.extend_from_slice(&count.to_be_bytes());
+        let result = (|| {
+            let stream = self.connect()?;
+            stream.write_all(&frame)?;
+            let mut header = [0; 7];
+            stream.read_exact(&mut header)?;
+            let len = u16::from_be_bytes([header[4], header[5]]) as usize;
+            let mut pdu = vec![0; len.saturating_sub(1)];
+            stream.read_exact(&mut pdu)?;
+            Ok((header, pdu))
+        })();
+        let (header, pdu) = result.map_err(|e| {
+            // The stream may be out of step with the device; start over next time.
+            self.stream = None;
+            io_error(e)
+        })?;
+        if u16::from_be_bytes([header[0], header[1]]) != transaction || pdu.is_empty() {
+            self.stream = None;
+            return Err(Error::InvalidInput("modbus: unexpected response".to_string()));
+        }
+        if pdu[0] == function | 0x80 {
+            let code = pdu.get(1).copied().unwrap_or(0);
+            return Err(Error::InvalidInput(format!("modbus exception {}", code)));
+        }
+        match pdu.get(1) {
+            Some(&n) if pdu[0] == function && pdu.len() >= 2 + n as usize => Ok(pdu[2..2 + n as usize].to_vec()),
+            _ => Err(Error::InvalidInput("modbus: malformed response".to_string())),
+        }
+    }
+}
+
+impl PollSource for ModbusTcp {
+    type Address = Register;
+
+    fn read(&mut self, register: &Register) -> Result<f64, Error> {
+        match register.kind {
+            RegisterKind::Coil | RegisterKind::DiscreteInput => {
+                let function = if register.kind == RegisterKind::Coil { 1 } else { 2 };
+                let data = self.request(function, register.address, 1)?;
+                let bit = data.first().ok_or_else(|| Error::InvalidInput("modbus: empty response".to_string()))?;
+                Ok((bit & 1) as f64)
+            }
+            RegisterKind::Holding | RegisterKind::Input => {
+                let function = if register.kind == RegisterKind::Holding { 3 } else { 4 };
+                let count = register.format.words();
+                let data = self.request(function, register.address, count)?;
+                if data.len() != 2 * count as usize {
+                    return Err(Error::InvalidInput("modbus: short response".to_string()));
+                }
+                let words: Vec<u16> = data.chunks(2).map(|w| u16::from_be_bytes([w[0], w[1]])).collect();
+                Ok(register.decode(&words))
+            }
+        }
+    }
+}
+
+/// A named point with an optional range of plausible values.
+#[derive(Clone, Debug, PartialEq)]
+struct Point<A> {
+    name: String,
+    address: A,
+    valid: Option<(f64, f64)>,
+}
+
+/// Reads points from a source every `interval` into one series of [`Reading`]s per
+/// point, timestamped by a [`Clock`].
+#[derive(Debug)]
+pub struct Poller<S: PollSource> {
+    source: S,
+    interval: Duration,
+    points: Vec<Point<S::Address>>,
+    series: BTreeMap<String, TimestampedSeries<Reading>>,
+}
+
+impl<S: PollSource> Poller<S> {
+    /// # Panics
+    /// Panics if `interval` is zero.
+    pub fn new(source: S, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "interval must be positive");
+        Self { source, interval, points: Vec::new(), series: BTreeMap::new() }
+    }
+
+    pub fn point<N: Into<String>>(mut self, name: N, address: S::Address) -> Self {
+        self.points.push(Point { name: name.into(), address, valid: None });
+        self
+    }
+
+    /// Adds a point whose readings outside `min..=max` are flagged
+    /// [`Uncertain`](DataQuality::Uncertain).
+    pub fn point_in_range<N: Into<String>>(mut self, name: N, address: S::Address, min: f64, max: f64) -> Self {
+        self.points.push(Point { name: name.into(), address, valid: Some((min, max)) });
+        self
+    }
+
+    pub fn source(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Readings of point `name` so far.
+    pub fn series(&self, name: &str) -> Option<&TimestampedSeries<Reading>> {
+        self.series.get(name)
+    }
+
+    /// The values of point `name` read with [`Good`](DataQuality::Good) quality.
+    pub fn good_values(&self, name: &str) -> Option<TimestampedSeries<f64>> {
+        self.series
+            .get(name)
+            .map(|s| s.iter().filter(|(_, r)| r.quality == DataQuality::Good).map(|(t, r)| (t, r.value)).collect())
+    }
+
+    /// Reads every point once, appends the readings at `timestamp` and returns them in
+    /// point order.
+    ///
+    /// # Panics
+    /// Panics if `timestamp` is earlier than the previous poll.
+    pub fn poll(&mut self, timestamp: i64) -> Vec<Reading> {
+        let mut readings = Vec::with_capacity(self.points.len());
+        for point in &self.points {
+            let reading = match self.source.read(&point.address) {
+                Ok(value) if point.valid.is_none_or(|(lo, hi)| (lo..=hi).contains(&value)) => {
+                    Reading { value, quality: DataQuality::Good }
+                }
+                Ok(value) => Reading { value, quality: DataQuality::Uncertain },
+                Err(_) => Reading { value: f64::NAN, quality: DataQuality::Bad },
+            };
+            self.series.entry(point.name.clone()).or_default().push(timestamp, reading);
+            readings.push(reading);
+        }
+        readings
+    }
+
+    /// Polls on schedule until `token` is cancelled. A poll that overruns the interval
+    /// is followed immediately by the next one rather than by a burst of catch-up polls.
+    /// Should `clock` step back, polls keep the previous timestamp.
+    pub fn run_until<C: Clock>(&mut self, clock: &C, token: &CancellationToken) {
+        let mut next = Instant::now();
+        let mut last = i64::MIN;
+        while !token.is_cancelled() {
+            last = clock.now().max(last);
+            self.poll(last);
+            next = (next + self.interval).max(Instant::now());
+            while !token.is_cancelled() {
+                let now = Instant::now();
+                if now >= next {
+                    break;
+                }
+                thread::sleep((next - now).min(Duration::from_millis(50)));
+            }
+        }
+    }
+}
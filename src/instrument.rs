@@ -0,0 +1,19 @@
+//! Internal instrumentation hooks. With the `tracing` feature these emit `tracing` spans
+//! and events; without it they expand to nothing.
+
+/// Enters a span named `$name` for the rest of the enclosing block. Subscribers get the
+/// duration from the span's lifetime.
+macro_rules! trace_span {
+    ($name:expr $(, $($fields:tt)*)?) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($name $(, $($fields)*)?).entered();
+    };
+}
+
+/// Emits a debug-level event.
+macro_rules! trace_event {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($args)*);
+    };
+}
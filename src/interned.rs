@@ -0,0 +1,123 @@
+//! Label series backed by a dictionary: each distinct string is stored once and points
+//! hold integer codes, so status-label series are compact and compare by integer.
+
+use crate::TimeSeries;
+use std::collections::HashMap;
+
+/// A series of string labels stored as codes into a dictionary of distinct labels, in
+/// order of first appearance.
+#[derive(Clone, Debug, Default)]
+pub struct InternedSeries {
+    labels: Vec<String>,
+    lookup: HashMap<String, u32>,
+    codes: TimeSeries<u32>,
+}
+
+impl InternedSeries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The code of `label`, adding it to the dictionary if new.
+    pub fn intern(&mut self, label: &str) -> u32 {
+        if let Some(&code) = self.lookup.get(label) {
+            return code;
+        }
+        let code = u32::try_from(self.labels.len()).expect("more than u32::MAX distinct labels");
+        self.labels.push(label.to_string());
+        self.lookup.insert(label.to_string(), code);
+        code
+    }
+
+    pub fn push(&mut self, label: &str) {
+        let code = self.intern(label);
+        self.codes.push(code);
+    }
+
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.codes.as_slice().get(index).map(|&code| self.labels[code as usize].as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.codes.as_slice().iter().map(|&code| self.labels[code as usize].as_str())
+    }
+
+    /// Codes of every point. Label analyses such as
+    /// [`StateSeries`](crate::StateSeries) run on these as integers; map the results back
+    /// with [`label`](Self::label).
+    pub fn codes(&self) -> &TimeSeries<u32> {
+        &self.codes
+    }
+
+    /// Distinct labels, indexed by code.
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn label(&self, code: u32) -> Option<&str> {
+        self.labels.get(code as usize).map(String::as_str)
+    }
+
+    /// The code of `label`, if it occurs.
+    pub fn code(&self, label: &str) -> Option<u32> {
+        self.lookup.get(label).copied()
+    }
+
+    /// `true` where the point equals `label`, comparing codes.
+    pub fn eq_label(&self, label: &str) -> TimeSeries<bool> {
+        match self.code(label) {
+            Some(code) => self.codes.map(|&c| c == code),
+            None => TimeSeries(vec![false; self.len()]),
+        }
+    }
+
+    /// Replaces labels through `f`, touching each distinct label once. Labels mapped to
+    /// the same string merge.
+    pub fn map_labels<F: Fn(&str) -> String>(&self, f: F) -> Self {
+        let mut out = Self::new();
+        let recode: Vec<u32> = self.labels.iter().map(|l| out.intern(&f(l))).collect();
+        out.codes = self.codes.map(|&c| recode[c as usize]);
+        out
+    }
+
+    pub fn to_time_series(&self) -> TimeSeries<String> {
+        TimeSeries(self.iter().map(str::to_string).collect())
+    }
+}
+
+/// Series are equal when their labels are, whatever the codes.
+impl PartialEq for InternedSeries {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for InternedSeries {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut series = Self::new();
+        for label in iter {
+            series.push(label.as_ref());
+        }
+        series
+    }
+}
+
+impl From<&TimeSeries<String>> for InternedSeries {
+    fn from(series: &TimeSeries<String>) -> Self {
+        series.as_slice().iter().collect()
+    }
+}
+
+impl From<&InternedSeries> for TimeSeries<String> {
+    fn from(series: &InternedSeries) -> Self {
+        series.to_time_series()
+    }
+}
@@ -0,0 +1,184 @@
+//! Interval-valued series, such as forecast bands or sensor tolerance bands, with
+//! interval arithmetic.
+//!
+//! Arithmetic is conservative: the result of an operation contains every value the
+//! operation can give for operands anywhere in their intervals. Dividing by an interval
+//! that contains zero gives the whole real line.
+
+use crate::{Error, TimeSeries, TimestampedSeries};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// The closed interval `[lower, upper]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interval {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Interval {
+    /// # Panics
+    /// Panics if `lower > upper`.
+    pub fn new(lower: f64, upper: f64) -> Self {
+        assert!(lower <= upper || lower.is_nan() || upper.is_nan(), "lower bound must not exceed the upper bound");
+        Self { lower, upper }
+    }
+
+    /// The degenerate interval `[x, x]`.
+    pub fn point(x: f64) -> Self {
+        Self { lower: x, upper: x }
+    }
+
+    /// `[center - radius, center + radius]`, e.g. a reading and its tolerance.
+    pub fn around(center: f64, radius: f64) -> Self {
+        Self::new(center - radius.abs(), center + radius.abs())
+    }
+
+    /// The whole real line.
+    pub fn entire() -> Self {
+        Self { lower: f64::NEG_INFINITY, upper: f64::INFINITY }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.upper - self.lower
+    }
+
+    pub fn midpoint(&self) -> f64 {
+        self.lower + (self.upper - self.lower) / 2.
+    }
+
+    pub fn contains(&self, x: f64) -> bool {
+        self.lower <= x && x <= self.upper
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.lower <= other.upper && other.lower <= self.upper
+    }
+
+    /// The smallest interval containing both.
+    pub fn hull(&self, other: &Self) -> Self {
+        Self { lower: self.lower.min(other.lower), upper: self.upper.max(other.upper) }
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        self.overlaps(other).then(|| Self { lower: self.lower.max(other.lower), upper: self.upper.min(other.upper) })
+    }
+
+    fn from_corners(corners: [f64; 4]) -> Self {
+        Self {
+            lower: corners.iter().copied().fold(f64::INFINITY, f64::min),
+            upper: corners.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+macro_rules! interval_op {
+    ($trait:ident, $method:ident, |$a:ident, $b:ident| $body:expr) => {
+        impl $trait for Interval {
+            type Output = Interval;
+            fn $method(self, rhs: Interval) -> Interval {
+                let ($a, $b) = (self, rhs);
+                $body
+            }
+        }
+
+        impl $trait for &Interval {
+            type Output = Interval;
+            fn $method(self, rhs: &Interval) -> Interval {
+                $trait::$method(*self, *rhs)
+            }
+        }
+
+        impl $trait<f64> for Interval {
+            type Output = Interval;
+            fn $method(self, rhs: f64) -> Interval {
+                $trait::$method(self, Interval::point(rhs))
+            }
+        }
+    };
+}
+
+interval_op!(Add, add, |a, b| Interval { lower: a.lower + b.lower, upper: a.upper + b.upper });
+interval_op!(Sub, sub, |a, b| Interval { lower: a.lower - b.upper, upper: a.upper - b.lower });
+interval_op!(Mul, mul, |a, b| Interval::from_corners([
+    a.lower * b.lower,
+    a.lower * b.upper,
+    a.upper * b.lower,
+    a.upper * b.upper
+]));
+interval_op!(Div, div, |a, b| if b.contains(0.) {
+    Interval::entire()
+} else {
+    a * Interval { lower: 1. / b.upper, upper: 1. / b.lower }
+});
+
+impl Neg for Interval {
+    type Output = Interval;
+    fn neg(self) -> Interval {
+        Interval { lower: -self.upper, upper: -self.lower }
+    }
+}
+
+impl TimestampedSeries<Interval> {
+    /// Pairs bounds at equal timestamps, e.g. the lower and upper quantiles of a
+    /// forecast.
+    ///
+    /// Fails if the timestamps differ or a lower bound exceeds its upper bound.
+    pub fn from_bounds(lower: &TimestampedSeries<f64>, upper: &TimestampedSeries<f64>) -> Result<Self, Error> {
+        if lower.timestamps() != upper.timestamps() {
+            return Err(Error::InvalidInput("bounds must share their timestamps".to_string()));
+        }
+        let mut out = TimestampedSeries::new();
+        for ((t, &lo), &hi) in lower.iter().zip(upper.values().as_slice()) {
+            if lo > hi {
+                return Err(Error::InvalidInput(format!("lower bound exceeds upper bound at {}", t)));
+            }
+            out.push(t, Interval { lower: lo, upper: hi });
+        }
+        Ok(match lower.unit().or(upper.unit()) {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        })
+    }
+
+    pub fn lower(&self) -> TimestampedSeries<f64> {
+        self.map(|i| i.lower)
+    }
+
+    pub fn upper(&self) -> TimestampedSeries<f64> {
+        self.map(|i| i.upper)
+    }
+
+    pub fn midpoints(&self) -> TimestampedSeries<f64> {
+        self.map(Interval::midpoint)
+    }
+
+    pub fn widths(&self) -> TimestampedSeries<f64> {
+        self.map(Interval::width)
+    }
+
+    /// Fraction of the points of `actual` that fall inside the band at the same
+    /// timestamp, e.g. to check that a 90% forecast band covers about 90% of outcomes.
+    /// NaN when no timestamps match.
+    pub fn coverage(&self, actual: &TimestampedSeries<f64>) -> f64 {
+        let (mut inside, mut matched) = (0, 0);
+        for (t, &x) in actual.iter() {
+            let i = self.timestamps().partition_point(|&s| s < t);
+            if self.timestamps().get(i) == Some(&t) {
+                matched += 1;
+                inside += self.values().as_slice()[i].contains(x) as usize;
+            }
+        }
+        if matched == 0 {
+            f64::NAN
+        } else {
+            inside as f64 / matched as f64
+        }
+    }
+}
+
+impl TimeSeries<Interval> {
+    /// The smallest interval containing every point; `None` for an empty series.
+    pub fn hull(&self) -> Option<Interval> {
+        self.as_slice().iter().copied().reduce(|a, b| a.hull(&b))
+    }
+}
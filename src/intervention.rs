@@ -0,0 +1,209 @@
+//! Known one-off events and holidays as regressors: pulse and step dummies whose effect is
+//! estimated alongside a decomposition or a forecaster.
+//!
+//! Each [`Intervention`] becomes a 0/1 dummy series. In [`Stl::decompose_with_interventions`]
+//! the effects are estimated by backfitting, alternating STL on the series without the
+//! effects and least squares on what STL leaves. [`WithInterventions`] fits them as
+//! regressors with any [`Forecaster`] on the rest, and extends the dummies over the
+//! horizon, so a holiday listed ahead of time shows up in the forecast.
+
+use crate::calendar::{parse_date, SECONDS_PER_DAY};
+use crate::linalg::least_squares;
+use crate::{Decomposition, Error, Forecaster, Stl, TimeSeries, WithRegressors};
+
+/// An event at known positions of the series.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Intervention {
+    /// A one-off effect on the point at `at`.
+    Pulse { at: usize },
+    /// A lasting level shift from the point at `at` on.
+    Step { at: usize },
+    /// The same effect on every listed point, such as each year's public holiday.
+    /// Positions past the end of the history mark holidays in the forecast.
+    Holiday { at: Vec<usize> },
+}
+
+impl Intervention {
+    /// A holiday on the points whose timestamp (Unix seconds) falls on one of `dates`,
+    /// written `YYYY-MM-DD`. Include future timestamps to mark holidays to forecast.
+    pub fn holidays(timestamps: &[i64], dates: &[&str]) -> Result<Self, Error> {
+        let days = dates.iter().map(|d| parse_date(d)).collect::<Result<Vec<_>, _>>()?;
+        let at = timestamps
+            .iter()
+            .enumerate()
+            .filter(|(_, &t)| days.contains(&(t.div_euclid(SECONDS_PER_DAY) * SECONDS_PER_DAY)))
+            .map(|(i, _)| i)
+            .collect();
+        Ok(Intervention::Holiday { at })
+    }
+
+    /// The 0/1 dummy over `n` points.
+    pub fn dummy(&self, n: usize) -> TimeSeries<f64> {
+        let mut out = vec![0.; n];
+        match self {
+            Intervention::Pulse { at } => {
+                if let Some(x) = out.get_mut(*at) {
+                    *x = 1.;
+                }
+            }
+            Intervention::Step { at } => out[(*at).min(n)..].fill(1.),
+            Intervention::Holiday { at } => {
+                for &i in at.iter().filter(|&&i| i < n) {
+                    out[i] = 1.;
+                }
+            }
+        }
+        TimeSeries(out)
+    }
+}
+
+/// The estimated effect of an intervention, in units of the series.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Effect {
+    pub intervention: Intervention,
+    /// The size of the pulse or holiday bump, or of the level shift.
+    pub magnitude: f64,
+}
+
+/// Dummies over `n` points, failing on any that is zero throughout.
+fn dummies(interventions: &[Intervention], n: usize) -> Result<Vec<TimeSeries<f64>>, Error> {
+    interventions
+        .iter()
+        .enumerate()
+        .map(|(i, intervention)| {
+            let dummy = intervention.dummy(n);
+            if dummy.as_slice().contains(&1.) {
+                Ok(dummy)
+            } else {
+                Err(Error::InvalidInput(format!("intervention {} does not touch the history", i)))
+            }
+        })
+        .collect()
+}
+
+/// STL with intervention effects taken out first.
+#[derive(Clone, Debug)]
+pub struct InterventionDecomposition {
+    /// Trend and seasonal of the series without the effects; the remainder excludes them.
+    pub decomposition: Decomposition,
+    /// The combined effect of all interventions at each point.
+    pub effect: TimeSeries<f64>,
+    pub effects: Vec<Effect>,
+}
+
+impl Stl {
+    /// Decomposes `series` into trend, seasonal, intervention effects and remainder.
+    ///
+    /// Fails if an intervention has no point in the series or the dummies are collinear,
+    /// as two steps at the same point are.
+    pub fn decompose_with_interventions(
+        &self,
+        series: &TimeSeries<f64>,
+        interventions: &[Intervention],
+    ) -> Result<InterventionDecomposition, Error> {
+        trace_span!("decompose_with_interventions", interventions = interventions.len(), n = series.len());
+        let n = series.len();
+        let dummies = dummies(interventions, n)?;
+        let design: Vec<Vec<f64>> = (0..n).map(|t| dummies.iter().map(|d| d[t]).collect()).collect();
+        let mut magnitudes = vec![0.; interventions.len()];
+        let mut effect = vec![0.; n];
+        let mut iterations = 0;
+        let mut decomposition = loop {
+            let adjusted: TimeSeries<f64> = series.as_slice().iter().zip(&effect).map(|(y, e)| y - e).collect();
+            let decomposition = self.decompose(&adjusted);
+            iterations += 1;
+            if interventions.is_empty() || iterations == 50 {
+                break decomposition;
+            }
+            let rest: Vec<f64> =
+                (0..n).map(|t| series[t] - decomposition.trend[t] - decomposition.seasonal[t]).collect();
+            let next = least_squares(&design, &rest).ok_or(Error::Singular)?;
+            let change = next.iter().zip(&magnitudes).map(|(a, b)| (a - b).abs()).fold(0., f64::max);
+            magnitudes = next;
+            effect = design.iter().map(|row| row.iter().zip(&magnitudes).map(|(x, b)| x * b).sum()).collect();
+            if change <= 1e-9 * (1. + magnitudes.iter().fold(0., |m: f64, b| m.max(b.abs()))) {
+                break decomposition;
+            }
+        };
+        let adjusted: Vec<f64> = series.as_slice().iter().zip(&effect).map(|(y, e)| y - e).collect();
+        for ((r, y), (trend, seasonal)) in decomposition
+            .remainder
+            .as_mut_slice()
+            .iter_mut()
+            .zip(&adjusted)
+            .zip(decomposition.trend.as_slice().iter().zip(decomposition.seasonal.as_slice()))
+        {
+            *r = y - trend - seasonal;
+        }
+        let effects = interventions
+            .iter()
+            .zip(magnitudes)
+            .map(|(intervention, magnitude)| Effect { intervention: intervention.clone(), magnitude })
+            .collect();
+        Ok(InterventionDecomposition { decomposition, effect: TimeSeries(effect), effects })
+    }
+}
+
+/// A forecaster of the series without its intervention effects, which are added back
+/// into the forecast wherever the dummies extend over the horizon.
+#[derive(Clone, Debug)]
+pub struct WithInterventions<F> {
+    regression: WithRegressors<F>,
+    interventions: Vec<Intervention>,
+    /// Length of the series fitted on, where the forecast dummies start.
+    observations: usize,
+}
+
+impl<F: Forecaster> WithInterventions<F> {
+    /// # Panics
+    /// Panics if `interventions` is empty.
+    pub fn new(model: F, interventions: Vec<Intervention>) -> Self {
+        assert!(!interventions.is_empty(), "at least one intervention expected");
+        Self { regression: WithRegressors::new(model), interventions, observations: 0 }
+    }
+
+    pub fn model(&self) -> &F {
+        self.regression.model()
+    }
+
+    /// The estimated effect of each intervention; empty before fitting.
+    pub fn effects(&self) -> Vec<Effect> {
+        let magnitudes = self.regression.coefficients().iter().skip(1);
+        self.interventions
+            .iter()
+            .zip(magnitudes)
+            .map(|(intervention, &magnitude)| Effect { intervention: intervention.clone(), magnitude })
+            .collect()
+    }
+
+    fn future_dummies(&self, horizon: usize) -> Vec<TimeSeries<f64>> {
+        let end = self.observations + horizon;
+        self.interventions.iter().map(|i| i.dummy(end).slice(self.observations..end)).collect()
+    }
+}
+
+impl<F: Forecaster> Forecaster for WithInterventions<F> {
+    /// Fails if an intervention has no point in the series, besides the failures of
+    /// [`WithRegressors::fit`].
+    fn fit(&mut self, series: &TimeSeries<f64>) -> Result<(), Error> {
+        let dummies = dummies(&self.interventions, series.len())?;
+        let regressors: Vec<&TimeSeries<f64>> = dummies.iter().collect();
+        self.regression.fit(series, &regressors)?;
+        self.observations = series.len();
+        Ok(())
+    }
+
+    fn predict(&self, horizon: usize) -> TimeSeries<f64> {
+        let future = self.future_dummies(horizon);
+        let future: Vec<&TimeSeries<f64>> = future.iter().collect();
+        self.regression.predict(&future).unwrap_or_else(|_| TimeSeries(vec![f64::NAN; horizon]))
+    }
+
+    fn predict_quantiles(&self, horizon: usize, quantiles: &[f64]) -> Vec<TimeSeries<f64>> {
+        let future = self.future_dummies(horizon);
+        let future: Vec<&TimeSeries<f64>> = future.iter().collect();
+        self.regression
+            .predict_quantiles(&future, quantiles)
+            .unwrap_or_else(|_| vec![TimeSeries(vec![f64::NAN; horizon]); quantiles.len()])
+    }
+}
@@ -0,0 +1,104 @@
+//! Plain-text persistence of timestamped series as two-column CSV (`timestamp,value`).
+
+use crate::{TimestampParser, TimestampedSeries};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+fn invalid_data(line: usize, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, message))
+}
+
+/// The file name of a series or key `name`: letters, digits, `-`, `_` and non-leading `.`
+/// are kept and every other byte is written `%XX`, so that no name can reach outside the
+/// directory, e.g. `plant/line1` becomes `plant%2Fline1` and `..` becomes `%2E.`.
+///
+/// Fails for the empty name, which has no file name.
+pub(crate) fn encode_file_name(name: &str) -> io::Result<String> {
+    if name.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty name"));
+    }
+    let mut encoded = String::with_capacity(name.len());
+    for (i, &b) in name.as_bytes().iter().enumerate() {
+        if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || (b == b'.' && i > 0) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    Ok(encoded)
+}
+
+/// The name [`encode_file_name`] gave `encoded`, or `None` if it is not such a file name.
+pub(crate) fn decode_file_name(encoded: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(b);
+            rest = tail;
+        }
+    }
+    let name = String::from_utf8(bytes).ok()?;
+    (encode_file_name(&name).ok()? == encoded).then_some(name)
+}
+
+impl<T: Display> TimestampedSeries<T> {
+    /// Writes a `timestamp,value` header followed by one line per point.
+    pub fn write_csv<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = BufWriter::new(writer);
+        writeln!(writer, "timestamp,value")?;
+        for (t, v) in self.iter() {
+            writeln!(writer, "{},{}", t, v)?;
+        }
+        writer.flush()
+    }
+
+    pub fn save_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_csv(File::create(path)?)
+    }
+}
+
+impl<T: FromStr> TimestampedSeries<T> {
+    /// Reads the format produced by [`write_csv`](Self::write_csv). A header line is
+    /// optional; blank lines are skipped.
+    pub fn read_csv<R: Read>(reader: R) -> io::Result<Self> {
+        Self::read_csv_impl(reader, |t| t.parse().ok())
+    }
+
+    /// Like [`read_csv`](Self::read_csv), with timestamps in any format `parser`
+    /// accepts, e.g. ISO 8601 dates or millisecond epochs. The series is tagged with the
+    /// parser's unit.
+    pub fn read_csv_with<R: Read>(reader: R, parser: &TimestampParser) -> io::Result<Self> {
+        Ok(Self::read_csv_impl(reader, |t| parser.parse(t).ok())?.with_unit(parser.unit))
+    }
+
+    fn read_csv_impl<R: Read, F: Fn(&str) -> Option<i64>>(reader: R, parse_timestamp: F) -> io::Result<Self> {
+        let mut series = Self::new();
+        for (n, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || (n == 0 && line.starts_with("timestamp")) {
+                continue;
+            }
+            let (t, v) = line.split_once(',').ok_or_else(|| invalid_data(n + 1, "expected two fields"))?;
+            let t = parse_timestamp(t.trim()).ok_or_else(|| invalid_data(n + 1, "invalid timestamp"))?;
+            let v: T = v.trim().parse().map_err(|_| invalid_data(n + 1, "invalid value"))?;
+            if series.timestamps().last().is_some_and(|&last| t < last) {
+                return Err(invalid_data(n + 1, "timestamps are not sorted"));
+            }
+            series.push(t, v);
+        }
+        Ok(series)
+    }
+
+    pub fn load_csv<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_csv(File::open(path)?)
+    }
+}
@@ -0,0 +1,115 @@
+//! Windowed, labeled datasets built from raw series and an anomaly-flag series, to train
+//! classifiers on the output of detectors or on hand-labeled incidents.
+//!
+//! Each example is a window of consecutive points of every input series. Its label says
+//! whether any flagged point falls in the window itself (detection, the default) or in
+//! the `horizon` points that follow it (early warning).
+
+use crate::{Error, TimeSeries};
+
+/// Counts of positive and negative examples.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClassBalance {
+    pub positive: usize,
+    pub negative: usize,
+}
+
+impl ClassBalance {
+    pub fn total(&self) -> usize {
+        self.positive + self.negative
+    }
+
+    /// NaN when there are no examples.
+    pub fn positive_fraction(&self) -> f64 {
+        self.positive as f64 / self.total() as f64
+    }
+
+    /// The weight that makes the positives count as much as the negatives in a loss,
+    /// `negative / positive`; infinite without positives.
+    pub fn positive_weight(&self) -> f64 {
+        self.negative as f64 / self.positive as f64
+    }
+}
+
+/// Examples as rows of features with a label each.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LabeledWindows {
+    /// One row per example: the window of the first series, then of the second, and so on.
+    pub features: Vec<Vec<f64>>,
+    pub labels: Vec<bool>,
+    /// Index of the last point of each window.
+    pub ends: Vec<usize>,
+}
+
+impl LabeledWindows {
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    pub fn balance(&self) -> ClassBalance {
+        let positive = self.labels.iter().filter(|&&l| l).count();
+        ClassBalance { positive, negative: self.len() - positive }
+    }
+}
+
+/// Cuts series into labeled windows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowLabeler {
+    window: usize,
+    horizon: usize,
+    stride: usize,
+}
+
+impl WindowLabeler {
+    /// Windows of `window` points, one starting at every point, labeled by the flags
+    /// inside them.
+    ///
+    /// # Panics
+    /// Panics if `window` is zero.
+    pub fn new(window: usize) -> Self {
+        assert!(window > 0, "window must be positive");
+        Self { window, horizon: 0, stride: 1 }
+    }
+
+    /// Labels each window by the flags of the `horizon` points after it instead, so a
+    /// classifier learns to warn ahead; zero labels by the window itself.
+    pub fn with_horizon(self, horizon: usize) -> Self {
+        Self { horizon, ..self }
+    }
+
+    /// # Panics
+    /// Panics if `stride` is zero.
+    pub fn with_stride(self, stride: usize) -> Self {
+        assert!(stride > 0, "stride must be positive");
+        Self { stride, ..self }
+    }
+
+    /// Windows of one series.
+    pub fn label(&self, series: &TimeSeries<f64>, flags: &TimeSeries<bool>) -> Result<LabeledWindows, Error> {
+        self.label_many(&[series], flags)
+    }
+
+    /// Windows of several aligned series, side by side in each row.
+    ///
+    /// Fails if a series is not as long as `flags`.
+    pub fn label_many(&self, series: &[&TimeSeries<f64>], flags: &TimeSeries<bool>) -> Result<LabeledWindows, Error> {
+        let n = flags.len();
+        if let Some(s) = series.iter().find(|s| s.len() != n) {
+            return Err(Error::LengthMismatch { expected: n, found: s.len() });
+        }
+        let span = self.window + self.horizon;
+        let mut out = LabeledWindows::default();
+        for start in (0..(n + 1).saturating_sub(span)).step_by(self.stride) {
+            let end = start + self.window;
+            let labeled = if self.horizon == 0 { start..end } else { end..end + self.horizon };
+            out.features.push(series.iter().flat_map(|s| s.as_slice()[start..end].iter().copied()).collect());
+            out.labels.push(flags.as_slice()[labeled].iter().any(|&f| f));
+            out.ends.push(end - 1);
+        }
+        Ok(out)
+    }
+}
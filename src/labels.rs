@@ -0,0 +1,139 @@
+//! Analysis of categorical series (job status, device mode, ...): runs, transitions and
+//! time spent in each state. States are reported in order of first appearance.
+
+use crate::{TimeSeries, TimestampedSeries};
+
+/// A maximal stretch of consecutive equal labels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Run<T> {
+    pub value: T,
+    pub start: usize,
+    pub length: usize,
+}
+
+/// Counts of consecutive label pairs: `counts[i][j]` is the number of steps from
+/// `states[i]` to `states[j]`; the diagonal counts steps that stay in the same state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransitionMatrix<T> {
+    pub states: Vec<T>,
+    pub counts: Vec<Vec<usize>>,
+}
+
+impl<T: PartialEq> TransitionMatrix<T> {
+    pub fn count(&self, from: &T, to: &T) -> usize {
+        match (self.states.iter().position(|s| s == from), self.states.iter().position(|s| s == to)) {
+            (Some(i), Some(j)) => self.counts[i][j],
+            _ => 0,
+        }
+    }
+
+    /// Row-normalized transition probabilities (NaN rows for states never left).
+    pub fn probabilities(&self) -> Vec<Vec<f64>> {
+        self.counts
+            .iter()
+            .map(|row| {
+                let total: usize = row.iter().sum();
+                row.iter().map(|&c| c as f64 / total as f64).collect()
+            })
+            .collect()
+    }
+}
+
+/// Run-length statistics of one state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunSummary<T> {
+    pub value: T,
+    pub runs: usize,
+    pub total_length: usize,
+    pub max_length: usize,
+    pub mean_length: f64,
+}
+
+fn position_or_insert<T: PartialEq + Clone>(states: &mut Vec<T>, value: &T) -> usize {
+    match states.iter().position(|s| s == value) {
+        Some(i) => i,
+        None => {
+            states.push(value.clone());
+            states.len() - 1
+        }
+    }
+}
+
+pub trait StateSeries<T> {
+    /// Run-length encoding of the series.
+    fn runs(&self) -> Vec<Run<T>>;
+    fn transitions(&self) -> TransitionMatrix<T>;
+    /// Number of points spent in each state.
+    fn time_in_state(&self) -> Vec<(T, usize)>;
+    fn run_summary(&self) -> Vec<RunSummary<T>>;
+}
+
+impl<T: PartialEq + Clone> StateSeries<T> for TimeSeries<T> {
+    fn runs(&self) -> Vec<Run<T>> {
+        let mut runs: Vec<Run<T>> = Vec::new();
+        for (i, value) in self.as_slice().iter().enumerate() {
+            match runs.last_mut() {
+                Some(run) if run.value == *value => run.length += 1,
+                _ => runs.push(Run { value: value.clone(), start: i, length: 1 }),
+            }
+        }
+        runs
+    }
+
+    fn transitions(&self) -> TransitionMatrix<T> {
+        let mut states = Vec::new();
+        let codes: Vec<usize> = self.as_slice().iter().map(|v| position_or_insert(&mut states, v)).collect();
+        let mut counts = vec![vec![0; states.len()]; states.len()];
+        for pair in codes.windows(2) {
+            counts[pair[0]][pair[1]] += 1;
+        }
+        TransitionMatrix { states, counts }
+    }
+
+    fn time_in_state(&self) -> Vec<(T, usize)> {
+        let mut totals: Vec<(T, usize)> = Vec::new();
+        for value in self.as_slice() {
+            match totals.iter_mut().find(|(s, _)| s == value) {
+                Some((_, n)) => *n += 1,
+                None => totals.push((value.clone(), 1)),
+            }
+        }
+        totals
+    }
+
+    fn run_summary(&self) -> Vec<RunSummary<T>> {
+        let mut summaries: Vec<RunSummary<T>> = Vec::new();
+        for run in self.runs() {
+            let i = match summaries.iter().position(|s| s.value == run.value) {
+                Some(i) => i,
+                None => {
+                    summaries.push(RunSummary { value: run.value, runs: 0, total_length: 0, max_length: 0, mean_length: 0. });
+                    summaries.len() - 1
+                }
+            };
+            let summary = &mut summaries[i];
+            summary.runs += 1;
+            summary.total_length += run.length;
+            summary.max_length = summary.max_length.max(run.length);
+            summary.mean_length = summary.total_length as f64 / summary.runs as f64;
+        }
+        summaries
+    }
+}
+
+impl<T: PartialEq + Clone> TimestampedSeries<T> {
+    /// Time spent in each state, where every label holds until the next timestamp and the
+    /// last one until `end`.
+    pub fn duration_in_state(&self, end: i64) -> Vec<(T, i64)> {
+        let mut totals: Vec<(T, i64)> = Vec::new();
+        let timestamps = self.timestamps();
+        for (i, (t, value)) in self.iter().enumerate() {
+            let until = timestamps.get(i + 1).copied().unwrap_or(end.max(t));
+            match totals.iter_mut().find(|(s, _)| s == value) {
+                Some((_, d)) => *d += until - t,
+                None => totals.push((value.clone(), until - t)),
+            }
+        }
+        totals
+    }
+}
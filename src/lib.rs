@@ -3,16 +3,23 @@
 //! # Getting Started
 //! import the module in your Rust code using:
 //! ```rust
-//! use time_series::TimeSeries;
+//! use time_series_utils::TimeSeries;
+//! ```
+//! or bring the common types and extension traits into scope at once with the [`prelude`]:
+//! ```rust
+//! use time_series_utils::prelude::*;
 //! ```
 //! # Creating a new TimeSeries
 //! To create a new TimeSeries, use the new() method:
 //! ```rust
+//! # use time_series_utils::TimeSeries;
 //! let mut ts: TimeSeries<f64> = TimeSeries::new();
 //! ```
 //! This creates a new TimeSeries that can hold floating-point numbers.
 //! You can add elements to the TimeSeries using the push() method:
 //! ```rust
+//! # use time_series_utils::TimeSeries;
+//! # let mut ts: TimeSeries<f64> = TimeSeries::new();
 //! ts.push(1.0);
 //! ```
 //! # Arithmetic operations
@@ -22,9 +29,11 @@
 //! - TimeSeries\<T\> @ &TimeSeries\<T\>
 //! - &TimeSeries\<T\> @ TimeSeries\<T\>
 //! - &TimeSeries\<T\> @ &TimeSeries\<T\>
+//!
 //! However, @ refers to the four arithmetic operations +, -, *, /.
 //! For example, to add two TimeSeries, use the + operator:
 //! ```rust
+//! # use time_series_utils::TimeSeries;
 //! let ts1: TimeSeries<f64> = TimeSeries::new();
 //! let ts2: TimeSeries<f64> = TimeSeries::new();
 //! let ts3 = &ts1 + &ts2;
@@ -32,9 +41,11 @@
 //! ```
 //! ### Attention.
 //! I don't know why, but it seems that an error is detected by rust-analyzer regarding TimeSeries\<T\> @ TimeSeries\<T\>. It actually works, but may be a bit of a hindrance when coding.
+//! The operators work in place on the left operand's buffer (a copy of it when borrowed) in a plain slice loop, which compiles to vectorized code for primitive numbers. Integer division by a zero element panics.
 //! # Mapping
 //! You can apply a function to each element of a TimeSeries using the map() method. For example:
 //! ```rust
+//! # use time_series_utils::TimeSeries;
 //! let mut ts:TimeSeries<f64> = TimeSeries::new();
 //! ts.push(1.);
 //! ts.push(2.);
@@ -43,22 +54,367 @@
 //! dbg!(ts2);
 //! ```
 
+
 use std::ops::*;
 use std::iter::{IntoIterator, Iterator};
 use std::iter::FromIterator;
 
+#[macro_use]
+mod instrument;
+
+pub mod aggregator;
+pub mod align;
+pub mod allan;
+pub mod anomaly;
+pub mod arima;
+#[cfg(feature = "arrow")]
+pub mod arrow_io;
+#[cfg(feature = "avro")]
+pub mod avro;
+pub mod backtest;
+pub mod barrier;
+pub mod batch;
+pub mod bitmap;
+pub mod burst;
+pub mod cache;
+pub mod calendar;
+pub mod causal;
+pub mod changepoint;
+pub mod chunk_store;
+pub mod chunked;
+pub mod circular;
+pub mod clock;
+pub mod cohort;
+pub mod complexity;
+pub mod control;
+pub mod cross_section;
+pub mod cross_validation;
+pub mod cursor;
+pub mod decomposition;
+pub mod density;
+pub mod dependence;
+pub mod describe;
+#[cfg(feature = "random")]
+pub mod differential_privacy;
+pub mod distribution;
+pub mod downsample;
+pub mod drawdown;
+pub mod embedding;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+pub mod ensemble;
+#[cfg(feature = "serde")]
+pub mod envelope;
+pub mod error;
+pub mod event_backtest;
+pub mod ewm;
+pub mod exogenous;
+pub mod experiment;
+pub mod expr;
+pub mod extreme;
+#[cfg(feature = "random")]
+pub mod faults;
+pub mod fixed;
+mod fft;
+pub mod flags;
+#[cfg(feature = "arrow")]
+pub mod flight;
+pub mod forecast;
+pub mod fractional;
+pub mod frame;
+pub mod funnel;
+pub mod garch;
+pub mod geo;
+pub mod global;
+#[cfg(feature = "golden")]
+pub mod golden;
+#[cfg(feature = "half")]
+pub mod half_precision;
+pub mod hierarchy;
+pub mod indicators;
+#[cfg(feature = "industrial")]
+pub mod industrial;
+pub mod interned;
+pub mod interval;
+pub mod intervention;
+pub mod io;
+pub mod labeled;
+pub mod labels;
+pub mod lineage;
+mod linalg;
+#[cfg(feature = "logs")]
+pub mod logs;
+pub mod materialized;
+pub mod microstructure;
+pub mod midas;
+pub mod monitor;
+#[cfg(feature = "monitoring")]
+pub mod monitoring;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod multivalue;
+mod optimize;
+pub mod overlay;
+pub mod paired;
+pub mod panel;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod patch;
+pub mod performance;
+#[cfg(feature = "config")]
+pub mod pipeline_config;
+#[cfg(feature = "plot")]
+pub mod plot;
+pub mod polyphase;
+pub mod prelude;
+#[cfg(feature = "random")]
+pub mod privacy;
+#[cfg(feature = "proto")]
+pub mod proto;
+#[cfg(feature = "random")]
+mod random;
+pub mod quality;
+pub mod quantile_regression;
+pub mod query;
+pub mod records;
+pub mod recurrence;
+pub mod regression;
+pub mod reshape;
+pub mod revision;
+pub mod rolling;
+pub mod schema;
+pub mod seasonal;
+pub mod series_set;
+#[cfg(feature = "service")]
+pub mod service;
+pub mod sessions;
+mod sha256;
+pub mod signals;
+#[cfg(feature = "random")]
+pub mod simulation;
+pub mod sink;
+pub mod sketch;
+pub mod sla;
+pub mod sparkline;
+pub mod sparse;
+mod special;
+pub mod spectral;
+pub mod state_space;
+pub mod stats;
+pub mod step;
+pub mod streaming;
+pub mod swinging_door;
+pub mod sync;
+pub mod tenant;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod time_weighted;
+pub mod timestamp;
+pub mod timestamped;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod uncertainty;
+pub mod vega;
+pub mod walk_forward;
+pub mod warmup;
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
+pub use aggregator::{Aggregate, Aggregator, Moments};
+pub use align::{align_all, AlignPolicy, AlignReport, Fill, FrequencyRule};
+pub use allan::AllanVariance;
+pub use anomaly::{AnomalyScores, Esd, EsdResult, SpectralResidual};
+pub use arima::{auto_arima, Arima, ArimaOrder, AutoArima, InformationCriterion};
+#[cfg(feature = "avro")]
+pub use avro::AvroSchema;
+pub use backtest::{equity_curve, BacktestResult, CostModel};
+pub use barrier::{triple_barrier_labels, BarrierLabel};
+pub use batch::{forecast_many, BatchForecast};
+pub use bitmap::BitmapSeries;
+pub use burst::{Baseline, BurstDetect};
+pub use cache::SeriesCache;
+pub use calendar::{parse_date, parse_duration, CalendarComparison, CalendarPeriod};
+pub use causal::{
+    causal_impact, difference_in_differences, interrupted_time_series, synthetic_control, CausalImpact, Estimate,
+    SegmentedRegression, SyntheticControl,
+};
+pub use changepoint::OnlineChangepoint;
+pub use chunk_store::{ChunkHash, ChunkRef, ChunkStore, GcReport, Manifest, StoreUsage};
+pub use chunked::{ChunkSummary, ChunkedSeries};
+pub use circular::{wrap_angle, Circular, Quaternion};
+pub use clock::{Clock, MockClock, SystemClock};
+pub use cohort::{cohort_retention, CohortRetention};
+pub use complexity::{Complexity, Dfa};
+pub use control::{ControlChart, CusumChart, EwmaChart, InControl};
+pub use cross_section::CrossSectional;
+pub use cross_validation::{Fold, PurgedKFold};
+pub use cursor::{SyncCursor, SyncStep};
+pub use decomposition::{Decompose, Decomposition, Stl};
+pub use density::{Bandwidth, Density, DensityGrid, Ecdf, Kde};
+pub use dependence::{BlockScheme, SerialDependence};
+#[cfg(feature = "random")]
+pub use dependence::BlockBootstrap;
+pub use describe::{infer_frequency, Description};
+#[cfg(feature = "random")]
+pub use differential_privacy::{DpAggregator, PrivacyBudget};
+pub use distribution::{Distribution, DistributionFit, DistributionFitting, Family, GoodnessOfFit};
+pub use drawdown::Drawdown;
+pub use embedding::Embedding;
+#[cfg(feature = "encryption")]
+pub use encryption::{is_sealed, Cipher, Encryption};
+pub use ensemble::{auto_select, Combination, Ensemble, Selection};
+#[cfg(feature = "serde")]
+pub use envelope::{Element, ElementType, Envelope, IndexType};
+pub use error::Error;
+pub use event_backtest::{Broker, EventBacktest, EventBacktestResult, Execution, Strategy};
+pub use ewm::Ewm;
+pub use exogenous::WithRegressors;
+pub use experiment::{Arm, Experiment, ExperimentReport, Lift};
+pub use expr::Expr;
+pub use extreme::{ExtremeValue, Gev, Gpd, TailDependence};
+#[cfg(feature = "random")]
+pub use faults::{Fault, FaultInjection, FaultKind};
+pub use fixed::FixedSeries;
+#[cfg(feature = "arrow")]
+pub use flight::{FlightClient, FlightServer};
+pub use flags::{FlagMask, Flagged, QualityFlag};
+pub use forecast::{Autoregressive, Drift, Forecaster, Naive, SimpleExpSmoothing};
+pub use fractional::{frac_diff_weights, FractionalDiff};
+pub use frame::{JoinKind, TimeSeriesFrame};
+pub use funnel::{Funnel, FunnelReport};
+pub use garch::Garch;
+pub use geo::GeoPoint;
+pub use global::{GlobalModel, Learner};
+#[cfg(feature = "golden")]
+pub use golden::{GoldenHarness, GoldenReport, Mismatch, Tolerance};
+#[cfg(feature = "half")]
+pub use half_precision::HalfPrecision;
+pub use hierarchy::{check_consistency, ConsistencyReport, Hierarchy, Inconsistency, Reconciliation};
+pub use indicators::{aggregate_bars, pivot_points, Brick, Dmi, Ichimoku, Indicators, Ohlcv, PivotLevels, Stochastic};
+#[cfg(feature = "industrial")]
+pub use industrial::{DataQuality, ModbusTcp, PollSource, Poller, Reading, Register};
+pub use interned::InternedSeries;
+pub use interval::Interval;
+pub use intervention::{Effect, Intervention, InterventionDecomposition, WithInterventions};
+pub use labeled::{ClassBalance, LabeledWindows, WindowLabeler};
+pub use labels::{Run, RunSummary, StateSeries, TransitionMatrix};
+pub use lineage::{LineageNode, Traced};
+#[cfg(feature = "logs")]
+pub use logs::LogExtraction;
+pub use materialized::{Rollup, RollupView};
+pub use microstructure::{classify_trades, Quote, QuoteSeries, SignRule, Trade};
+pub use midas::{LagWeighting, Midas, MidasFit};
+pub use monitor::{CancellationToken, Monitor};
+#[cfg(feature = "monitoring")]
+pub use monitoring::{parse_graphite, parse_influxdb, parse_prometheus, LabeledSeries};
+#[cfg(feature = "mqtt")]
+pub use mqtt::{MqttIngest, PayloadFormat, Subscription};
+pub use multivalue::MultiValueSeries;
+pub use overlay::{Adjustment, Overlay, Scenario};
+pub use paired::{ForecastLoss, PairedComparison, TestResult};
+pub use panel::{FixedEffects, Panel};
+#[cfg(feature = "parallel")]
+pub use parallel::ParallelReduction;
+pub use patch::{diff_series, SeriesPatch};
+pub use performance::{Performance, Shrinkage};
+#[cfg(feature = "config")]
+pub use pipeline_config::{OpSpec, PipelineSpec};
+#[cfg(feature = "plot")]
+pub use plot::{stack_svg, Chart};
+pub use polyphase::Polyphase;
+#[cfg(feature = "random")]
+pub use privacy::Anonymize;
+pub use quality::{FrameQuality, QualityReport};
+pub use quantile_regression::{quantile_regression, QuantileAutoregressive, QuantileFit, QuantileRegression};
+pub use query::{ChunkAction, Query, QueryPlan, ValueFilter};
+pub use records::FrameRecord;
+pub use recurrence::{Recurrence, RecurrenceMatrix};
+pub use regression::{Isotonic, LinearFit, MultipleFit, Regression, RollingCoefficients};
+pub use reshape::{pivot, LongRecord};
+pub use revision::RevisionLog;
+pub use rolling::{Agg, FeatureKind, Rolling, RollingFeatures, RollingState, Windowed};
+pub use schema::{OnViolation, Schema, TimestampOrder, ValidationReport, ValueType, Violation, ViolationKind};
+pub use seasonal::{Seasonal, SeasonalAdjustment, SeasonalMatrix};
+pub use series_set::SeriesSet;
+#[cfg(feature = "service")]
+pub use service::{Catalog, MultiTenantService, QueryService, Response, ServerLimits};
+pub use sessions::Session;
+pub use signals::{signal_to_positions, Crossover, PositionRules};
+#[cfg(feature = "random")]
+pub use simulation::{percentile_envelope, simulate_paths, simulate_paths_monitored, Simulate};
+pub use sink::{Checkpoint, ExactlyOnce, MemorySink, Sequenced, Sink};
+pub use sketch::{merge_digests, Digest, HeavyHitter, HyperLogLog, TDigest, TopK};
+pub use sla::{Availability, Outage};
+pub use sparse::SparseSeries;
+pub use spectral::{Complex, Spectral, Spectrogram, Spectrum, Window};
+pub use state_space::{Dlm, Impute, Structural};
+pub use stats::Statistics;
+pub use step::StepSeries;
+pub use streaming::{
+    BucketStatus, BucketUpdate, Chain, CumulativeOp, DiffOp, EventTimeResample, EwmaOp, FillNaOp, MapOp, Pipeline,
+    ResampleOp, RollingMeanOp, StreamOp, ThresholdOp, ZScoreOp,
+};
+pub use swinging_door::SwingingDoor;
+pub use sync::{SeriesUpdate, SeriesVersion, SyncClient, SyncRequest, SyncResponse, SyncServer};
+pub use tenant::{TenantPolicy, TenantStore, Tenants};
+pub use time_weighted::TimeWeighted;
+pub use timestamp::{parse_timestamps, TimeUnit, Timestamp, TimestampFormat, TimestampParser};
+pub use timestamped::TimestampedSeries;
+#[cfg(feature = "tui")]
+pub use tui::{LiveSeries, SeriesSparkline};
+pub use uncertainty::{inverse_variance_mean, Measurement};
+pub use vega::{VegaLite, VegaMark};
+pub use walk_forward::{WalkForward, WalkForwardResult, WalkForwardStep};
+#[cfg(feature = "random")]
+pub use walk_forward::random_candidates;
+pub use warmup::{Stabilization, WarmUp};
+#[cfg(feature = "websocket")]
+pub use websocket::Broadcaster;
+
 #[derive(Clone, Debug)]
-pub struct TimeSeries<T>(pub Vec<T>);
+pub struct TimeSeries<T>(
+    #[deprecated(note = "use `as_slice`, `as_mut_slice`, `into_vec` or `from_vec`; the storage may change")]
+    pub Vec<T>,
+);
+
+#[allow(deprecated)]
+impl<T> TimeSeries<T> {
+    pub fn from_vec(values: Vec<T>) -> Self {
+	Self(values)
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+	&self.0
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+	&mut self.0
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+	self.0
+    }
+
+    pub fn push(&mut self, value: T) {
+	self.0.push(value);
+    }
+
+    pub fn len(&self) -> usize {
+	self.0.len()
+    }
 
+    pub fn truncate(&mut self, len: usize) {
+	self.0.truncate(len);
+    }
+}
+
+#[allow(deprecated)]
 impl<T> TimeSeries<T>
 where T:Clone,
 {
     pub fn new() -> Self {
 	Self(Vec::new())
     }
-    pub fn push(&mut self, value: T) {
-	self.0.push(value);
-    }
     pub fn pop(&mut self) -> Option<T>{
 	if self.0.is_empty() {
 	    return None;
@@ -104,10 +460,6 @@ where T:Clone,
 	TimeSeries(self.0.iter().map(f).collect())
     }
 
-    pub fn len(&self) -> usize{
-	self.0.len()
-    }
-
     pub fn slice(&self, range: std::ops::Range<usize>) -> Self{
 	Self(self.0[range].to_vec())
     }
@@ -150,7 +502,7 @@ impl<T, E> TimeSeries<Result<T, E>> {
     pub fn unwrap(self) -> Result<TimeSeries<T>, E> {
         let mut vec = Vec::new();
         
-        for item in self.0.into_iter() {
+        for item in self.into_vec() {
             match item {
                 Ok(val) => vec.push(val),
                 Err(err) => return Err(err),
@@ -168,14 +520,12 @@ where
     for<'a> &'a T: Add<Output = T>,
 {
     type Output = TimeSeries<T>;
-    fn add(self, other: &Self) -> Self::Output {
-        TimeSeries(
-            self.0
-                .iter()
-                .zip(other.0.iter())
-                .map(|(a, b)| a + b)
-                .collect(),
-        )
+    fn add(mut self, other: &Self) -> Self::Output {
+        self.truncate(self.len().min(other.len()));
+        for (a, b) in self.as_mut_slice().iter_mut().zip(other.as_slice()) {
+            *a = &*a + b;
+        }
+        self
     }
 }
 
@@ -186,14 +536,12 @@ where
     for<'a> &'a T: Sub<Output = T>,
 {
     type Output = TimeSeries<T>;
-    fn sub(self, other: &Self) -> Self::Output {
-        TimeSeries(
-            self.0
-                .iter()
-                .zip(other.0.iter())
-                .map(|(a, b)| a - b)
-                .collect(),
-        )
+    fn sub(mut self, other: &Self) -> Self::Output {
+        self.truncate(self.len().min(other.len()));
+        for (a, b) in self.as_mut_slice().iter_mut().zip(other.as_slice()) {
+            *a = &*a - b;
+        }
+        self
     }
 }
 #[auto_impl_ops::auto_ops]
@@ -202,35 +550,34 @@ where
     for<'a> &'a T: Mul<Output = T>,
 {
     type Output = TimeSeries<T>;
-    fn mul(self, other: &Self) -> Self::Output {
-        TimeSeries(
-            self.0
-                .iter()
-                .zip(other.0.iter())
-                .map(|(a, b)| a * b)
-                .collect(),
-        )
+    fn mul(mut self, other: &Self) -> Self::Output {
+        self.truncate(self.len().min(other.len()));
+        for (a, b) in self.as_mut_slice().iter_mut().zip(other.as_slice()) {
+            *a = &*a * b;
+        }
+        self
     }
 }
 
+/// # Panics
+/// For integer element types, if an element of `other` within the shorter length is zero.
 #[auto_impl_ops::auto_ops]
 impl<T> Div<&TimeSeries<T>> for TimeSeries<T>
 where
     for<'a> &'a T: Div<Output = T>,
 {
     type Output = TimeSeries<T>;
-    fn div(self, other: &Self) -> Self::Output {
-        TimeSeries(
-            self.0
-                .iter()
-                .zip(other.0.iter())
-                .map(|(a, b)| a / b)
-                .collect(),
-        )
+    fn div(mut self, other: &Self) -> Self::Output {
+        self.truncate(self.len().min(other.len()));
+        for (a, b) in self.as_mut_slice().iter_mut().zip(other.as_slice()) {
+            *a = &*a / b;
+        }
+        self
     }
 }
 
 
+#[allow(deprecated)]
 impl<T> Index<usize> for TimeSeries<T> {
     type Output = T;
 
@@ -239,11 +586,13 @@ impl<T> Index<usize> for TimeSeries<T> {
     }
 }
 
+#[allow(deprecated)]
 impl<T> IndexMut<usize> for TimeSeries<T> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         &mut self.0[index]
     }
 }
+#[allow(deprecated)]
 impl<T> Index<std::ops::Range<usize>> for TimeSeries<T> {
     type Output = [T];
 
@@ -252,12 +601,14 @@ impl<T> Index<std::ops::Range<usize>> for TimeSeries<T> {
     }
 }
 
+#[allow(deprecated)]
 impl<T> IndexMut<std::ops::Range<usize>> for TimeSeries<T> {
     fn index_mut(&mut self, range: std::ops::Range<usize>) -> &mut Self::Output {
         &mut self.0[range]
     }
 }
 
+#[allow(deprecated)]
 impl<T> Index<RangeFrom<usize>> for TimeSeries<T> {
     type Output = [T];
 
@@ -266,11 +617,13 @@ impl<T> Index<RangeFrom<usize>> for TimeSeries<T> {
     }
 }
 
+#[allow(deprecated)]
 impl<T> IndexMut<RangeFrom<usize>> for TimeSeries<T> {
     fn index_mut(&mut self, range: RangeFrom<usize>) -> &mut Self::Output {
         &mut self.0[range]
     }
 }
+#[allow(deprecated)]
 impl<T> Index<RangeTo<usize>> for TimeSeries<T> {
     type Output = [T];
 
@@ -279,6 +632,7 @@ impl<T> Index<RangeTo<usize>> for TimeSeries<T> {
     }
 }
 
+#[allow(deprecated)]
 impl<T> IndexMut<RangeTo<usize>> for TimeSeries<T> {
     fn index_mut(&mut self, range: RangeTo<usize>) -> &mut Self::Output {
         &mut self.0[range]
@@ -299,12 +653,14 @@ impl<T> IndexMut<RangeTo<usize>> for TimeSeries<T> {
 //     }
 // }
 
+#[allow(deprecated)]
 impl<T> AsRef<[T]> for TimeSeries<T> {
     fn as_ref(&self) -> &[T] {
 	self.0.as_ref()
     }
 }
 
+#[allow(deprecated)]
 impl<T> AsMut<[T]> for TimeSeries<T> {
     fn as_mut(&mut self) -> &mut [T] {
 	self.0.as_mut()
@@ -312,6 +668,7 @@ impl<T> AsMut<[T]> for TimeSeries<T> {
 }
 
 
+#[allow(deprecated)]
 impl<T> IntoIterator for TimeSeries<T> {
     type Item = T;
     type IntoIter = std::vec::IntoIter<Self::Item>;
@@ -321,6 +678,7 @@ impl<T> IntoIterator for TimeSeries<T> {
     }
 }
 
+#[allow(deprecated)]
 impl<'a, T> IntoIterator for &'a TimeSeries<T> {
     type Item = &'a T;
     type IntoIter = std::slice::Iter<'a, T>;
@@ -330,6 +688,7 @@ impl<'a, T> IntoIterator for &'a TimeSeries<T> {
     }
 }
 
+#[allow(deprecated)]
 impl<'a, T> IntoIterator for &'a mut TimeSeries<T> {
     type Item = &'a mut T;
     type IntoIter = std::slice::IterMut<'a, T>;
@@ -347,6 +706,19 @@ impl<T> FromIterator<T> for TimeSeries<T> {
     }
 }
 
+impl<T> From<Vec<T>> for TimeSeries<T> {
+    fn from(values: Vec<T>) -> Self {
+	Self::from_vec(values)
+    }
+}
+
+impl<T> From<TimeSeries<T>> for Vec<T> {
+    fn from(series: TimeSeries<T>) -> Self {
+	series.into_vec()
+    }
+}
+
+#[allow(deprecated)]
 impl<T> Extend<T> for TimeSeries<T> {
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
         self.0.extend(iter);
@@ -0,0 +1,278 @@
+//! Provenance of derived series: which sources, operations and parameters produced them.
+//!
+//! A [`Traced`] value carries its series together with a lineage graph. Deriving a new
+//! series through [`Traced::derive`] or [`Traced::combine`] records the step, sharing the
+//! graphs of the inputs, so any published number can be traced back to its sources.
+
+use crate::TimestampedSeries;
+use std::collections::HashMap;
+use std::fmt::{Display, Write as _};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Operation name of the leaves of a lineage graph.
+pub const SOURCE: &str = "source";
+
+/// One step of a lineage graph: a named source, or an operation applied to earlier steps.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineageNode {
+    pub operation: String,
+    /// Parameters in the order given; sources carry their `name`.
+    pub params: Vec<(String, String)>,
+    pub inputs: Vec<Arc<LineageNode>>,
+}
+
+impl LineageNode {
+    pub fn source<S: Into<String>>(name: S) -> Self {
+        Self { operation: SOURCE.to_string(), params: vec![("name".to_string(), name.into())], inputs: Vec::new() }
+    }
+
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    pub fn is_source(&self) -> bool {
+        self.operation == SOURCE
+    }
+
+    /// Every distinct step, inputs before the steps that use them, ending with `self`.
+    pub fn steps(&self) -> Vec<&LineageNode> {
+        fn visit<'a>(node: &'a LineageNode, seen: &mut Vec<&'a LineageNode>) {
+            if seen.iter().any(|s| std::ptr::eq(*s, node)) {
+                return;
+            }
+            for input in &node.inputs {
+                visit(input, seen);
+            }
+            seen.push(node);
+        }
+        let mut seen = Vec::new();
+        visit(self, &mut seen);
+        seen
+    }
+
+    /// Names of the sources this step depends on, in first-use order.
+    pub fn sources(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for name in self.steps().into_iter().filter(|s| s.is_source()).filter_map(|s| s.param("name")) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    pub fn depends_on(&self, source: &str) -> bool {
+        self.sources().contains(&source)
+    }
+
+    /// Names of the operations applied, in the order of [`steps`](Self::steps).
+    pub fn operations(&self) -> Vec<&str> {
+        self.steps().into_iter().filter(|s| !s.is_source()).map(|s| s.operation.as_str()).collect()
+    }
+
+    /// The graph as JSON: `{"nodes": [{"id", "operation", "params", "inputs"}], "output": id}`
+    /// with nodes in [`steps`](Self::steps) order and inputs given by id.
+    pub fn to_json(&self) -> String {
+        let steps = self.steps();
+        let mut out = String::from("{\"nodes\":[");
+        for (id, step) in steps.iter().enumerate() {
+            if id > 0 {
+                out.push(',');
+            }
+            let _ = write!(out, "{{\"id\":{},\"operation\":{},\"params\":{{", id, json_string(&step.operation));
+            for (i, (k, v)) in step.params.iter().enumerate() {
+                let _ = write!(out, "{}{}:{}", if i > 0 { "," } else { "" }, json_string(k), json_string(v));
+            }
+            let inputs: Vec<String> = step.inputs.iter().map(|input| id_of(&steps, input).to_string()).collect();
+            let _ = write!(out, "}},\"inputs\":[{}]}}", inputs.join(","));
+        }
+        let _ = write!(out, "],\"output\":{}}}", steps.len() - 1);
+        out
+    }
+
+    /// One line per step as written in CSV headers by [`Traced::write_csv`]:
+    /// `id<TAB>operation<TAB>input ids<TAB>key=value...`, with tabs, newlines, `=` and
+    /// backslashes escaped.
+    fn to_lines(&self) -> Vec<String> {
+        let steps = self.steps();
+        steps
+            .iter()
+            .enumerate()
+            .map(|(id, step)| {
+                let inputs: Vec<String> = step.inputs.iter().map(|input| id_of(&steps, input).to_string()).collect();
+                let mut line = format!("{}\t{}\t{}", id, escape(&step.operation), inputs.join(","));
+                for (k, v) in &step.params {
+                    let _ = write!(line, "\t{}={}", escape(k), escape(v));
+                }
+                line
+            })
+            .collect()
+    }
+
+    /// Rebuilds a graph from [`to_lines`](Self::to_lines) output; the last line is the output.
+    fn from_lines(lines: &[String]) -> Result<Arc<Self>, String> {
+        let mut nodes: HashMap<usize, Arc<LineageNode>> = HashMap::new();
+        let mut last = None;
+        for line in lines {
+            let mut fields = line.split('\t');
+            let id: usize = fields.next().and_then(|f| f.parse().ok()).ok_or("invalid lineage id")?;
+            let operation = unescape(fields.next().ok_or("missing lineage operation")?);
+            let inputs = fields
+                .next()
+                .unwrap_or("")
+                .split(',')
+                .filter(|f| !f.is_empty())
+                .map(|f| f.parse().ok().and_then(|i: usize| nodes.get(&i).cloned()).ok_or("unknown lineage input"))
+                .collect::<Result<Vec<_>, _>>()?;
+            let params = fields
+                .map(|f| f.split_once('=').map(|(k, v)| (unescape(k), unescape(v))).ok_or("invalid lineage parameter"))
+                .collect::<Result<Vec<_>, _>>()?;
+            let node = Arc::new(LineageNode { operation, params, inputs });
+            nodes.insert(id, node.clone());
+            last = Some(node);
+        }
+        last.ok_or_else(|| "empty lineage".to_string())
+    }
+}
+
+fn id_of(steps: &[&LineageNode], node: &LineageNode) -> usize {
+    steps.iter().position(|s| std::ptr::eq(*s, node)).expect("inputs precede their uses")
+}
+
+/// `s` as a quoted JSON string.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('=', "\\e")
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('e') => out.push('='),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// A value (usually a series) together with the lineage that produced it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Traced<T> {
+    pub value: T,
+    lineage: Arc<LineageNode>,
+}
+
+impl<T> Traced<T> {
+    /// A source series, the leaf of a lineage graph.
+    pub fn source<S: Into<String>>(name: S, value: T) -> Self {
+        Self { value, lineage: Arc::new(LineageNode::source(name)) }
+    }
+
+    /// Attaches an existing lineage to `value`.
+    pub fn with_lineage(value: T, lineage: Arc<LineageNode>) -> Self {
+        Self { value, lineage }
+    }
+
+    pub fn lineage(&self) -> &Arc<LineageNode> {
+        &self.lineage
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Applies `f`, recording it as `operation` with `params`, e.g.
+    /// `prices.derive("rolling_mean", &[("window", &20)], |s| ...)`.
+    pub fn derive<U, F>(&self, operation: &str, params: &[(&str, &dyn Display)], f: F) -> Traced<U>
+    where
+        F: FnOnce(&T) -> U,
+    {
+        Traced { value: f(&self.value), lineage: step(operation, params, vec![self.lineage.clone()]) }
+    }
+
+    /// Applies a binary `f` to `self` and `other`, recording both as inputs.
+    pub fn combine<U, V, F>(&self, other: &Traced<U>, operation: &str, params: &[(&str, &dyn Display)], f: F) -> Traced<V>
+    where
+        F: FnOnce(&T, &U) -> V,
+    {
+        Traced {
+            value: f(&self.value, &other.value),
+            lineage: step(operation, params, vec![self.lineage.clone(), other.lineage.clone()]),
+        }
+    }
+}
+
+fn step(operation: &str, params: &[(&str, &dyn Display)], inputs: Vec<Arc<LineageNode>>) -> Arc<LineageNode> {
+    Arc::new(LineageNode {
+        operation: operation.to_string(),
+        params: params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        inputs,
+    })
+}
+
+const LINEAGE_PREFIX: &str = "# lineage\t";
+
+impl<T: Display> Traced<TimestampedSeries<T>> {
+    /// Writes the lineage as `# lineage` comment lines, followed by the series as in
+    /// [`TimestampedSeries::write_csv`].
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for line in self.lineage.to_lines() {
+            writeln!(writer, "{}{}", LINEAGE_PREFIX, line)?;
+        }
+        self.value.write_csv(writer)
+    }
+}
+
+impl<T: FromStr> Traced<TimestampedSeries<T>> {
+    /// Reads what [`write_csv`](Self::write_csv) writes. A file without lineage lines
+    /// reads as a source named `source`.
+    pub fn read_csv<R: Read>(reader: R) -> io::Result<Self> {
+        let mut reader = BufReader::new(reader);
+        let mut lineage_lines = Vec::new();
+        let mut body = String::new();
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 {
+            match line.strip_prefix(LINEAGE_PREFIX) {
+                Some(rest) if body.is_empty() => lineage_lines.push(rest.trim_end_matches(['\r', '\n']).to_string()),
+                _ => body.push_str(&line),
+            }
+            line.clear();
+        }
+        let value = TimestampedSeries::read_csv(body.as_bytes())?;
+        let lineage = if lineage_lines.is_empty() {
+            Arc::new(LineageNode::source(SOURCE))
+        } else {
+            LineageNode::from_lines(&lineage_lines).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        };
+        Ok(Self { value, lineage })
+    }
+}
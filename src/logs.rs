@@ -0,0 +1,92 @@
+//! Series from timestamped log lines: numbers and labels pulled out with regular
+//! expressions, and counts of matching lines per time bucket.
+//!
+//! Patterns use the [`regex`] syntax. Extraction reads the first capture group, or the
+//! whole match for a pattern without groups, so `latency=(\d+)ms` yields the latency of
+//! each line that mentions one. Lines that do not match are skipped.
+
+use crate::{Error, SeriesSet, TimestampedSeries};
+use regex::Regex;
+
+fn compile(pattern: &str) -> Result<Regex, Error> {
+    Regex::new(pattern).map_err(|e| Error::InvalidInput(format!("invalid pattern `{}`: {}", pattern, e)))
+}
+
+/// The first capture group of `pattern` in `line`, or the whole match.
+fn capture<'a>(pattern: &Regex, line: &'a str) -> Option<&'a str> {
+    let captures = pattern.captures(line)?;
+    captures.get(1).or_else(|| captures.get(0)).map(|m| m.as_str())
+}
+
+pub trait LogExtraction {
+    /// The captured text of each matching line parsed as a number; lines whose capture
+    /// does not parse are skipped too.
+    ///
+    /// Fails if `pattern` is not a valid regular expression.
+    fn extract_values(&self, pattern: &str) -> Result<TimestampedSeries<f64>, Error>;
+    /// The captured text of each matching line, e.g. a status or a user id.
+    ///
+    /// Fails if `pattern` is not a valid regular expression.
+    fn extract_labels(&self, pattern: &str) -> Result<TimestampedSeries<String>, Error>;
+    /// For each `(name, pattern)`, the number of matching lines in every `step`-long
+    /// bucket aligned to timestamp 0, keyed by the bucket start. Every series covers the
+    /// buckets from the first line to the last, with zeros where nothing matched.
+    ///
+    /// Fails if a pattern is not a valid regular expression.
+    ///
+    /// # Panics
+    /// Panics if `step` is not positive.
+    fn count_events(&self, patterns: &[(&str, &str)], step: i64) -> Result<SeriesSet<f64>, Error>;
+}
+
+impl LogExtraction for TimestampedSeries<String> {
+    fn extract_values(&self, pattern: &str) -> Result<TimestampedSeries<f64>, Error> {
+        let pattern = compile(pattern)?;
+        let out: TimestampedSeries<f64> =
+            self.iter().filter_map(|(t, line)| capture(&pattern, line)?.trim().parse().ok().map(|v| (t, v))).collect();
+        Ok(match self.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        })
+    }
+
+    fn extract_labels(&self, pattern: &str) -> Result<TimestampedSeries<String>, Error> {
+        let pattern = compile(pattern)?;
+        let out: TimestampedSeries<String> =
+            self.iter().filter_map(|(t, line)| capture(&pattern, line).map(|c| (t, c.to_string()))).collect();
+        Ok(match self.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        })
+    }
+
+    fn count_events(&self, patterns: &[(&str, &str)], step: i64) -> Result<SeriesSet<f64>, Error> {
+        assert!(step > 0, "step must be positive");
+        let compiled = patterns.iter().map(|(_, p)| compile(p)).collect::<Result<Vec<_>, _>>()?;
+        let bucket = |t: i64| t.div_euclid(step) * step;
+        let first = self.timestamps().first().map_or(0, |&t| bucket(t));
+        let buckets = self.timestamps().last().map_or(0, |&t| ((bucket(t) - first) / step + 1) as usize);
+        let mut counts = vec![vec![0.; buckets]; patterns.len()];
+        for (t, line) in self.iter() {
+            let k = ((bucket(t) - first) / step) as usize;
+            for (count, pattern) in counts.iter_mut().zip(&compiled) {
+                if pattern.is_match(line) {
+                    count[k] += 1.;
+                }
+            }
+        }
+        Ok(patterns
+            .iter()
+            .zip(counts)
+            .map(|((name, _), count)| {
+                let series: TimestampedSeries<f64> =
+                    count.into_iter().enumerate().map(|(k, c)| (first + k as i64 * step, c)).collect();
+                let series = match self.unit() {
+                    Some(unit) => series.with_unit(unit),
+                    None => series,
+                };
+                (name.to_string(), series)
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,135 @@
+//! Derived series maintained point by point as their source grows, so that dashboards
+//! read aggregates instead of recomputing them on every request.
+//!
+//! A [`Rollup`] reduces a source series over `step`-long buckets aligned to timestamp 0
+//! with an [`Agg`]; its output is always `source.resample_with(step, &agg)`, the bucket
+//! still filling included. A [`RollupView`] keeps the state of that last bucket, so each
+//! new source point updates the output in constant time. The service [`Catalog`] keeps
+//! views up to date on ingest and persists them with the raw series.
+//!
+//! [`Catalog`]: crate::Catalog
+
+use crate::{Agg, Aggregator, Moments, TimestampedSeries};
+
+/// A bucketed aggregate of a source series.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rollup {
+    step: i64,
+    agg: Agg,
+}
+
+impl Rollup {
+    /// # Panics
+    /// Panics if `step` is not positive.
+    pub fn new(step: i64, agg: Agg) -> Self {
+        assert!(step > 0, "step must be positive");
+        Self { step, agg }
+    }
+
+    pub fn step(&self) -> i64 {
+        self.step
+    }
+
+    pub fn agg(&self) -> Agg {
+        self.agg
+    }
+
+    fn bucket(&self, timestamp: i64) -> i64 {
+        timestamp.div_euclid(self.step) * self.step
+    }
+
+    /// The rollup of `source` from scratch.
+    pub fn compute(&self, source: &TimestampedSeries<f64>) -> TimestampedSeries<f64> {
+        source.resample_with(self.step, &self.agg)
+    }
+
+    /// `<step> <agg>`, as stored with persisted views.
+    #[cfg(feature = "service")]
+    pub(crate) fn to_text(self) -> String {
+        let agg = match self.agg {
+            Agg::Sum => "sum",
+            Agg::Mean => "mean",
+            Agg::Var => "var",
+            Agg::Std => "std",
+            Agg::Min => "min",
+            Agg::Max => "max",
+        };
+        format!("{} {}", self.step, agg)
+    }
+
+    #[cfg(feature = "service")]
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        let (step, agg) = text.split_once(' ')?;
+        let agg = match agg {
+            "sum" => Agg::Sum,
+            "mean" => Agg::Mean,
+            "var" => Agg::Var,
+            "std" => Agg::Std,
+            "min" => Agg::Min,
+            "max" => Agg::Max,
+            _ => return None,
+        };
+        Some(Self { step: step.parse().ok().filter(|&s| s > 0)?, agg })
+    }
+}
+
+/// The incremental state of a [`Rollup`]: the start and state of its last bucket.
+#[derive(Clone, Debug)]
+pub struct RollupView {
+    rollup: Rollup,
+    open: Option<(i64, Moments)>,
+}
+
+impl RollupView {
+    /// A view of a source without points yet.
+    pub fn new(rollup: Rollup) -> Self {
+        Self { rollup, open: None }
+    }
+
+    /// A view of `source` and its output so far.
+    pub fn build(rollup: Rollup, source: &TimestampedSeries<f64>) -> (Self, TimestampedSeries<f64>) {
+        (Self::resume(rollup, source), rollup.compute(source))
+    }
+
+    /// A view continuing an output already computed from `source`, e.g. after loading
+    /// both from disk; only the points of the last bucket are read.
+    pub fn resume(rollup: Rollup, source: &TimestampedSeries<f64>) -> Self {
+        let open = source.timestamps().last().map(|&last| {
+            let start = rollup.bucket(last);
+            let first = source.timestamps().partition_point(|&t| t < start);
+            (start, rollup.agg.fold(&source.values().as_slice()[first..]))
+        });
+        Self { rollup, open }
+    }
+
+    pub fn rollup(&self) -> Rollup {
+        self.rollup
+    }
+
+    /// Folds a point appended to the source into `output`, updating its last bucket or
+    /// starting a new one.
+    ///
+    /// # Panics
+    /// Panics if `timestamp` falls before the last bucket.
+    pub fn push(&mut self, output: &mut TimestampedSeries<f64>, timestamp: i64, value: f64) {
+        let agg = self.rollup.agg;
+        let bucket = self.rollup.bucket(timestamp);
+        match &mut self.open {
+            Some((start, state)) if *start == bucket => {
+                agg.update(state, &value);
+                let result = agg.finish(state);
+                match output.timestamps().last() {
+                    Some(&last) if last == bucket => *output.last_mut().expect("the output is not empty") = result,
+                    _ => output.push(bucket, result),
+                }
+            }
+            open => {
+                assert!(open.as_ref().is_none_or(|(start, _)| *start < bucket), "points must be pushed in order");
+                let mut state = agg.init();
+                agg.update(&mut state, &value);
+                output.push(bucket, agg.finish(&state));
+                *open = Some((bucket, state));
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Market-microstructure element types and derived series.
+
+use crate::{TimeSeries, TimestampedSeries};
+
+/// Top-of-book quote.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quote {
+    pub bid: f64,
+    pub ask: f64,
+    pub bid_size: f64,
+    pub ask_size: f64,
+}
+
+impl Quote {
+    pub fn mid(&self) -> f64 {
+        (self.bid + self.ask) / 2.
+    }
+
+    pub fn spread(&self) -> f64 {
+        self.ask - self.bid
+    }
+
+    /// Size-weighted mid price, leaning towards the side with less resting size.
+    /// Falls back to the mid when both sizes are zero.
+    pub fn microprice(&self) -> f64 {
+        let depth = self.bid_size + self.ask_size;
+        if depth == 0. {
+            return self.mid();
+        }
+        (self.bid * self.ask_size + self.ask * self.bid_size) / depth
+    }
+}
+
+/// Derived price series over a series of quotes.
+pub trait QuoteSeries {
+    type Output;
+    fn mid_price(&self) -> Self::Output;
+    fn spread(&self) -> Self::Output;
+    fn microprice(&self) -> Self::Output;
+}
+
+impl QuoteSeries for TimeSeries<Quote> {
+    type Output = TimeSeries<f64>;
+
+    fn mid_price(&self) -> Self::Output {
+        self.map(Quote::mid)
+    }
+
+    fn spread(&self) -> Self::Output {
+        self.map(Quote::spread)
+    }
+
+    fn microprice(&self) -> Self::Output {
+        self.map(Quote::microprice)
+    }
+}
+
+impl QuoteSeries for TimestampedSeries<Quote> {
+    type Output = TimestampedSeries<f64>;
+
+    fn mid_price(&self) -> Self::Output {
+        self.map(Quote::mid)
+    }
+
+    fn spread(&self) -> Self::Output {
+        self.map(Quote::spread)
+    }
+
+    fn microprice(&self) -> Self::Output {
+        self.map(Quote::microprice)
+    }
+}
+
+/// A printed trade.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Trade {
+    pub price: f64,
+    pub size: f64,
+}
+
+/// Rule used by [`classify_trades`] to decide whether a trade was buyer or seller initiated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignRule {
+    /// Compare each price with the last different trade price.
+    TickRule,
+    /// Compare each price with the prevailing quote mid, looked up `quote_delay` time units
+    /// before the trade, and fall back to the tick rule at the mid.
+    LeeReady { quote_delay: i64 },
+}
+
+/// Signs each trade (+1 buy, -1 sell, 0 undetermined) and returns the signed volume series.
+///
+/// Trades that cannot be classified (the first trades before any price change, with no
+/// usable quote) get a signed volume of zero.
+pub fn classify_trades(
+    trades: &TimestampedSeries<Trade>,
+    quotes: &TimestampedSeries<Quote>,
+    rule: SignRule,
+) -> TimestampedSeries<f64> {
+    let mut last_price: Option<f64> = None;
+    let mut tick_sign = 0.;
+    trades
+        .iter()
+        .map(|(t, trade)| {
+            if let Some(prev) = last_price {
+                if trade.price > prev {
+                    tick_sign = 1.;
+                } else if trade.price < prev {
+                    tick_sign = -1.;
+                }
+            }
+            last_price = Some(trade.price);
+            let sign = match rule {
+                SignRule::TickRule => tick_sign,
+                SignRule::LeeReady { quote_delay } => match quotes.asof(t - quote_delay) {
+                    Some(quote) if trade.price > quote.mid() => 1.,
+                    Some(quote) if trade.price < quote.mid() => -1.,
+                    _ => tick_sign,
+                },
+            };
+            (t, sign * trade.size)
+        })
+        .collect()
+}
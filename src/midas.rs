@@ -0,0 +1,181 @@
+//! Mixed-frequency regression (MIDAS): a low-frequency target such as quarterly GDP
+//! explained by high-frequency regressors such as monthly or daily indicators.
+//!
+//! Each target point is matched with the last `lags` points of every regressor at or
+//! before its timestamp, so the series need no common frequency or resampling. The lag
+//! coefficients follow a smooth polynomial in the lag, which keeps the parameter count
+//! small with many high-frequency lags, or are left free for a few lags. Predicting at a
+//! timestamp in a period still in progress uses the regressor data so far: a nowcast.
+
+use crate::linalg::least_squares;
+use crate::{Error, TimeUnit, TimestampedSeries};
+
+/// How the coefficients of the lags of one regressor are tied together.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LagWeighting {
+    /// One free coefficient per lag (U-MIDAS).
+    Unrestricted,
+    /// Coefficients on a polynomial of degree `degree` in the lag (Almon), `degree + 1`
+    /// parameters per regressor however many lags it has.
+    Almon { degree: usize },
+}
+
+/// Settings of a MIDAS regression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Midas {
+    lags: usize,
+    weighting: LagWeighting,
+}
+
+impl Midas {
+    /// `lags` lags of every regressor, on a quadratic Almon polynomial when there are more
+    /// than three and free otherwise.
+    ///
+    /// # Panics
+    /// Panics if `lags` is zero.
+    pub fn new(lags: usize) -> Self {
+        assert!(lags > 0, "lags must be positive");
+        let weighting = if lags > 3 { LagWeighting::Almon { degree: 2 } } else { LagWeighting::Unrestricted };
+        Self { lags, weighting }
+    }
+
+    /// # Panics
+    /// Panics if an Almon polynomial has as many parameters as there are lags or more.
+    pub fn with_weighting(self, weighting: LagWeighting) -> Self {
+        if let LagWeighting::Almon { degree } = weighting {
+            assert!(degree < self.lags, "the Almon degree must be less than the number of lags");
+        }
+        Self { weighting, ..self }
+    }
+
+    pub fn lags(&self) -> usize {
+        self.lags
+    }
+
+    /// The weight of each lag in each parameter's regressor.
+    fn basis(&self) -> Vec<Vec<f64>> {
+        match self.weighting {
+            LagWeighting::Unrestricted => {
+                (0..self.lags).map(|k| (0..self.lags).map(|j| if j == k { 1. } else { 0. }).collect()).collect()
+            }
+            LagWeighting::Almon { degree } => (0..=degree as i32)
+                .map(|k| (0..self.lags).map(|j| (j as f64 / self.lags as f64).powi(k)).collect())
+                .collect(),
+        }
+    }
+
+    /// Fits `target` on the lags of `regressors` by least squares, over the target points
+    /// with `lags` regressor points at or before them and no NaN.
+    ///
+    /// Fails if there are no regressors, there are no more usable target points than
+    /// parameters, or the design is singular.
+    pub fn fit(
+        &self,
+        target: &TimestampedSeries<f64>,
+        regressors: &[&TimestampedSeries<f64>],
+    ) -> Result<MidasFit, Error> {
+        if regressors.is_empty() {
+            return Err(Error::InvalidInput("MIDAS needs at least one regressor".to_string()));
+        }
+        let basis = self.basis();
+        let (mut rows, mut y, mut timestamps) = (Vec::new(), Vec::new(), Vec::new());
+        for (t, &v) in target.iter() {
+            let Some(lags) = lag_values(regressors, self.lags, t) else { continue };
+            let row: Vec<f64> = std::iter::once(1.)
+                .chain(lags.iter().flat_map(|x| basis.iter().map(move |b| b.iter().zip(x).map(|(w, x)| w * x).sum())))
+                .collect();
+            if v.is_finite() && row.iter().all(|x| x.is_finite()) {
+                rows.push(row);
+                y.push(v);
+                timestamps.push(t);
+            }
+        }
+        let parameters = 1 + regressors.len() * basis.len();
+        if rows.len() <= parameters {
+            return Err(Error::InsufficientData { required: parameters + 1, found: rows.len() });
+        }
+        let theta = least_squares(&rows, &y).ok_or(Error::Singular)?;
+        let lag_coefficients = theta[1..]
+            .chunks(basis.len())
+            .map(|params| (0..self.lags).map(|j| params.iter().zip(&basis).map(|(p, b)| p * b[j]).sum()).collect())
+            .collect();
+        let fitted: Vec<f64> = rows.iter().map(|row| row.iter().zip(&theta).map(|(x, b)| x * b).sum()).collect();
+        let sse: f64 = fitted.iter().zip(&y).map(|(f, v)| (v - f).powi(2)).sum();
+        let fitted = TimestampedSeries::from_parts(timestamps, fitted.into_iter().collect());
+        Ok(MidasFit {
+            intercept: theta[0],
+            lag_coefficients,
+            sigma: (sse / (y.len() - parameters) as f64).sqrt(),
+            fitted: match target.unit() {
+                Some(unit) => fitted.with_unit(unit),
+                None => fitted,
+            },
+            unit: target.unit(),
+        })
+    }
+}
+
+/// The last `lags` values of every regressor at or before `timestamp`, most recent
+/// first, or `None` if one has fewer points.
+fn lag_values(regressors: &[&TimestampedSeries<f64>], lags: usize, timestamp: i64) -> Option<Vec<Vec<f64>>> {
+    regressors
+        .iter()
+        .map(|r| {
+            let last = r.asof_index(timestamp).filter(|&i| i + 1 >= lags)?;
+            Some((0..lags).map(|j| r.values().as_slice()[last - j]).collect())
+        })
+        .collect()
+}
+
+/// A fitted [`Midas`] regression.
+#[derive(Clone, Debug)]
+pub struct MidasFit {
+    pub intercept: f64,
+    /// The coefficient of every lag of every regressor, most recent lag first.
+    pub lag_coefficients: Vec<Vec<f64>>,
+    /// Standard deviation of the residuals.
+    pub sigma: f64,
+    /// The fitted values at the target points used in the fit.
+    pub fitted: TimestampedSeries<f64>,
+    unit: Option<TimeUnit>,
+}
+
+impl MidasFit {
+    /// The target predicted at each of `timestamps` from the regressor points at or
+    /// before it, with the regressors in the order of the fit.
+    ///
+    /// Fails if the number of regressors differs from the fit's, or a regressor has
+    /// fewer than `lags` points at or before a timestamp.
+    ///
+    /// # Panics
+    /// Panics if `timestamps` are not sorted.
+    pub fn predict(
+        &self,
+        regressors: &[&TimestampedSeries<f64>],
+        timestamps: &[i64],
+    ) -> Result<TimestampedSeries<f64>, Error> {
+        if regressors.len() != self.lag_coefficients.len() {
+            return Err(Error::LengthMismatch { expected: self.lag_coefficients.len(), found: regressors.len() });
+        }
+        let lags = self.lag_coefficients[0].len();
+        let out = timestamps
+            .iter()
+            .map(|&t| {
+                let values = lag_values(regressors, lags, t).ok_or_else(|| {
+                    let found = regressors.iter().map(|r| r.asof_index(t).map_or(0, |i| i + 1)).min().unwrap_or(0);
+                    Error::InsufficientData { required: lags, found }
+                })?;
+                let effect: f64 = values
+                    .iter()
+                    .zip(&self.lag_coefficients)
+                    .map(|(x, c)| x.iter().zip(c).map(|(x, c)| x * c).sum::<f64>())
+                    .sum();
+                Ok((t, self.intercept + effect))
+            })
+            .collect::<Result<TimestampedSeries<f64>, Error>>()?;
+        Ok(match self.unit {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        })
+    }
+}
@@ -0,0 +1,123 @@
+//! Progress reporting and cancellation for long computations such as walk-forward
+//! optimization and maximum-likelihood fits.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A shared flag for stopping a computation from another thread, e.g. a GUI's cancel
+/// button or a server dropping a request. Clones refer to the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every computation watching this token to stop at its next checkpoint.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Observes a long computation, passed to the `*_monitored` variants of expensive
+/// operations. A default monitor does nothing.
+///
+/// When its token is cancelled, operations that can return something useful stop early
+/// with a partial result (documented on each); the others return [`Error::Cancelled`].
+///
+/// [`Error::Cancelled`]: crate::Error::Cancelled
+pub struct Monitor<'a> {
+    progress: Option<Box<dyn FnMut(f64) + 'a>>,
+    token: Option<CancellationToken>,
+    granularity: f64,
+    reported: f64,
+}
+
+impl Default for Monitor<'_> {
+    fn default() -> Self {
+        Self { progress: None, token: None, granularity: 0.01, reported: f64::NEG_INFINITY }
+    }
+}
+
+impl std::fmt::Debug for Monitor<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Monitor")
+            .field("progress", &self.progress.is_some())
+            .field("token", &self.token)
+            .field("granularity", &self.granularity)
+            .finish()
+    }
+}
+
+impl<'a> Monitor<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls `callback` with the completed fraction in `[0, 1]` as the work advances,
+    /// ending with exactly one call with `1.0` when it finishes (none if it is cancelled).
+    pub fn with_progress<F: FnMut(f64) + 'a>(self, callback: F) -> Self {
+        Self { progress: Some(Box::new(callback)), ..self }
+    }
+
+    /// Only reports once the fraction has grown by at least `step` since the last report
+    /// (default 0.01).
+    pub fn with_granularity(self, step: f64) -> Self {
+        assert!((0. ..=1.).contains(&step), "granularity must lie in [0, 1]");
+        Self { granularity: step, ..self }
+    }
+
+    /// Stops the computation once `token` is cancelled.
+    pub fn with_cancellation(self, token: CancellationToken) -> Self {
+        Self { token: Some(token), ..self }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.as_ref().is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// A monitor watching the same token that reports nothing, for the steps of a
+    /// computation whose progress is reported as a whole.
+    pub(crate) fn cancellation_only(&self) -> Monitor<'static> {
+        Monitor { token: self.token.clone(), ..Monitor::default() }
+    }
+
+    /// `Err(Cancelled)` once the token is cancelled.
+    pub(crate) fn check(&self) -> Result<(), crate::Error> {
+        if self.is_cancelled() {
+            Err(crate::Error::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Records that `done` of `total` units of work are complete.
+    pub(crate) fn report(&mut self, done: usize, total: usize) {
+        let fraction = if total == 0 { 1. } else { (done as f64 / total as f64).min(1.) };
+        if fraction >= 1. {
+            return self.finish();
+        }
+        if fraction - self.reported >= self.granularity {
+            self.emit(fraction);
+        }
+    }
+
+    /// Records that the work is complete, which can be earlier than its upper bound.
+    pub(crate) fn finish(&mut self) {
+        if self.reported < 1. {
+            self.emit(1.);
+        }
+    }
+
+    fn emit(&mut self, fraction: f64) {
+        self.reported = fraction;
+        if let Some(callback) = &mut self.progress {
+            callback(fraction);
+        }
+    }
+}
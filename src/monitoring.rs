@@ -0,0 +1,197 @@
+//! Series from the JSON query responses of monitoring systems: Prometheus
+//! (`/api/v1/query_range` and `/api/v1/query`), InfluxDB 1.x (`/query`, also served by
+//! InfluxDB 2 for compatibility) and the Graphite render API (`format=json`).
+//!
+//! Every series of a response comes with its metric name and labels (Prometheus labels,
+//! InfluxDB tags, Graphite tags). Series are keyed the way Prometheus prints them,
+//! `name{label="value",...}`, or by the values of chosen labels, to gather them in a
+//! [`SeriesSet`] or as the columns of a frame. Missing values (`null`) become NaN.
+
+use crate::reshape::{pivot, LongRecord};
+use crate::{Error, SeriesSet, TimeSeriesFrame, TimeUnit, TimestampFormat, TimestampParser, TimestampedSeries};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// One series of a query response.
+#[derive(Clone, Debug)]
+pub struct LabeledSeries {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub series: TimestampedSeries<f64>,
+}
+
+impl LabeledSeries {
+    pub fn label(&self, key: &str) -> Option<&str> {
+        self.labels.get(key).map(String::as_str)
+    }
+
+    /// `name{label="value",...}` with the labels sorted, or just the name without labels.
+    pub fn key(&self) -> String {
+        if self.labels.is_empty() {
+            return self.name.clone();
+        }
+        let labels: Vec<String> = self.labels.iter().map(|(k, v)| format!("{}={:?}", k, v)).collect();
+        format!("{}{{{}}}", self.name, labels.join(","))
+    }
+
+    /// The values of `labels` joined by `/`, empty for a missing label; the full
+    /// [`key`](Self::key) when `labels` is empty.
+    pub fn key_by(&self, labels: &[&str]) -> String {
+        if labels.is_empty() {
+            return self.key();
+        }
+        labels.iter().map(|l| self.label(l).unwrap_or_default()).collect::<Vec<_>>().join("/")
+    }
+
+    /// Whether the series has every `(label, value)` of `matchers`.
+    pub fn matches(&self, matchers: &[(&str, &str)]) -> bool {
+        matchers.iter().all(|&(k, v)| self.label(k) == Some(v))
+    }
+}
+
+/// The series keyed by [`LabeledSeries::key_by`]; of series with the same key, the last
+/// one wins.
+pub fn to_series_set(series: &[LabeledSeries], labels: &[&str]) -> SeriesSet<f64> {
+    series.iter().map(|s| (s.key_by(labels), s.series.clone())).collect()
+}
+
+/// One column per series, named by [`LabeledSeries::key_by`], over the union of their
+/// timestamps; NaN where a series has no point.
+pub fn to_frame(series: &[LabeledSeries], labels: &[&str]) -> TimeSeriesFrame {
+    pivot(series.iter().flat_map(|s| {
+        let key = s.key_by(labels);
+        s.series.iter().map(move |(timestamp, &value)| LongRecord { timestamp, key: key.clone(), value })
+    }))
+}
+
+fn malformed(system: &str, what: &str) -> Error {
+    Error::InvalidInput(format!("malformed {} response: {}", system, what))
+}
+
+/// A series from points in any order, sorted by timestamp.
+fn sorted_series(mut points: Vec<(i64, f64)>, unit: TimeUnit) -> TimestampedSeries<f64> {
+    points.sort_by_key(|&(t, _)| t);
+    points.into_iter().collect::<TimestampedSeries<f64>>().with_unit(unit)
+}
+
+/// A JSON number or numeric string; `null` and other text are NaN.
+fn number(value: &Value) -> f64 {
+    match value {
+        Value::Number(n) => n.as_f64().unwrap_or(f64::NAN),
+        Value::String(s) => s.parse().unwrap_or(f64::NAN),
+        Value::Bool(b) => f64::from(u8::from(*b)),
+        _ => f64::NAN,
+    }
+}
+
+fn string_map(value: Option<&Value>) -> BTreeMap<String, String> {
+    let Some(Value::Object(map)) = value else { return BTreeMap::new() };
+    map.iter().map(|(k, v)| (k.clone(), v.as_str().map_or_else(|| v.to_string(), str::to_string))).collect()
+}
+
+/// Parses a Prometheus API response with a `matrix` (range query), `vector` or `scalar`
+/// result. Timestamps are in milliseconds; the `__name__` label becomes the name.
+///
+/// Fails if `json` is not such a response, or reports an error.
+pub fn parse_prometheus(json: &str) -> Result<Vec<LabeledSeries>, Error> {
+    let system = "Prometheus";
+    let response: Value = serde_json::from_str(json).map_err(|e| malformed(system, &e.to_string()))?;
+    if response["status"] != "success" {
+        let error = response["error"].as_str().unwrap_or("unknown error");
+        return Err(Error::InvalidInput(format!("Prometheus query failed: {}", error)));
+    }
+    let data = &response["data"];
+    let point = |pair: &Value| -> Result<(i64, f64), Error> {
+        let t = pair[0].as_f64().ok_or_else(|| malformed(system, "timestamps must be numbers"))?;
+        Ok(((t * 1000.).round() as i64, number(&pair[1])))
+    };
+    let entry = |metric: Option<&Value>, points: Vec<(i64, f64)>| {
+        let mut labels = string_map(metric);
+        let name = labels.remove("__name__").unwrap_or_default();
+        LabeledSeries { name, labels, series: sorted_series(points, TimeUnit::Millis) }
+    };
+    let results = || data["result"].as_array().ok_or_else(|| malformed(system, "`data.result` must be an array"));
+    match data["resultType"].as_str() {
+        Some("matrix") => results()?
+            .iter()
+            .map(|r| {
+                let values = r["values"].as_array().ok_or_else(|| malformed(system, "`values` must be an array"))?;
+                Ok(entry(r.get("metric"), values.iter().map(point).collect::<Result<_, _>>()?))
+            })
+            .collect(),
+        Some("vector") => results()?.iter().map(|r| Ok(entry(r.get("metric"), vec![point(&r["value"])?]))).collect(),
+        Some("scalar") => Ok(vec![entry(None, vec![point(&data["result"])?])]),
+        Some(other) => Err(malformed(system, &format!("unsupported result type `{}`", other))),
+        None => Err(malformed(system, "missing `data.resultType`")),
+    }
+}
+
+/// Parses an InfluxDB 1.x `/query` response into one series per value column of each
+/// returned series, named after the measurement, with the tags as labels and the column
+/// name as label `_field`. Times given as RFC 3339 text are converted to `unit`; numeric
+/// times (queried with `epoch=`) are taken to be in `unit` already. Booleans become 1 and
+/// 0, and text values that are not numbers NaN.
+///
+/// Fails if `json` is not such a response, or a statement reports an error.
+pub fn parse_influxdb(json: &str, unit: TimeUnit) -> Result<Vec<LabeledSeries>, Error> {
+    let system = "InfluxDB";
+    let response: Value = serde_json::from_str(json).map_err(|e| malformed(system, &e.to_string()))?;
+    if let Some(error) = response["error"].as_str() {
+        return Err(Error::InvalidInput(format!("InfluxDB query failed: {}", error)));
+    }
+    let results = response["results"].as_array().ok_or_else(|| malformed(system, "`results` must be an array"))?;
+    let parser = TimestampParser::new(TimestampFormat::Iso8601).with_unit(unit);
+    let mut out = Vec::new();
+    for result in results {
+        if let Some(error) = result["error"].as_str() {
+            return Err(Error::InvalidInput(format!("InfluxDB query failed: {}", error)));
+        }
+        for series in result["series"].as_array().map(Vec::as_slice).unwrap_or_default() {
+            let columns =
+                series["columns"].as_array().ok_or_else(|| malformed(system, "`columns` must be an array"))?;
+            let time = columns.iter().position(|c| c == "time").ok_or_else(|| malformed(system, "no `time` column"))?;
+            let rows = series["values"].as_array().map(Vec::as_slice).unwrap_or_default();
+            let timestamps = rows
+                .iter()
+                .map(|row| match &row[time] {
+                    Value::String(text) => parser.parse(text),
+                    t => t.as_f64().map(|t| t.round() as i64).ok_or_else(|| malformed(system, "invalid time")),
+                })
+                .collect::<Result<Vec<i64>, Error>>()?;
+            let tags = string_map(series.get("tags"));
+            let name = series["name"].as_str().unwrap_or_default();
+            for (column, field) in columns.iter().enumerate().filter(|&(c, _)| c != time) {
+                let mut labels = tags.clone();
+                labels.insert("_field".to_string(), field.as_str().unwrap_or_default().to_string());
+                let points = timestamps.iter().zip(rows).map(|(&t, row)| (t, number(&row[column]))).collect();
+                out.push(LabeledSeries { name: name.to_string(), labels, series: sorted_series(points, unit) });
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Parses a Graphite render API response (`format=json`): one series per target, named
+/// after it, with its tags other than `name` as labels. Timestamps are in seconds.
+///
+/// Fails if `json` is not such a response.
+pub fn parse_graphite(json: &str) -> Result<Vec<LabeledSeries>, Error> {
+    let system = "Graphite";
+    let response: Value = serde_json::from_str(json).map_err(|e| malformed(system, &e.to_string()))?;
+    let targets = response.as_array().ok_or_else(|| malformed(system, "expected an array of targets"))?;
+    targets
+        .iter()
+        .map(|target| {
+            let name = target["target"].as_str().ok_or_else(|| malformed(system, "missing `target`"))?;
+            let datapoints =
+                target["datapoints"].as_array().ok_or_else(|| malformed(system, "`datapoints` must be an array"))?;
+            let points = datapoints
+                .iter()
+                .map(|p| Ok((p[1].as_i64().ok_or_else(|| malformed(system, "invalid timestamp"))?, number(&p[0]))))
+                .collect::<Result<Vec<_>, Error>>()?;
+            let mut labels = string_map(target.get("tags"));
+            labels.remove("name");
+            Ok(LabeledSeries { name: name.to_string(), labels, series: sorted_series(points, TimeUnit::Seconds) })
+        })
+        .collect()
+}
@@ -0,0 +1,320 @@
+//! Ingestion of IoT telemetry from an MQTT broker into per-topic series of a
+//! [`Catalog`].
+//!
+//! Each [`Subscription`] pairs a topic filter (with `+` and `#` wildcards) with a QoS
+//! and a [`PayloadFormat`] saying where the value, and optionally the timestamp, sit in
+//! the payload. Messages are appended to the series named after their topic; messages
+//! without a timestamp are stamped with the ingest [`Clock`]. The connection is
+//! re-established after failures and subscriptions are renewed on every connect.
+
+use crate::service::Catalog;
+use crate::{CancellationToken, Clock, Error, SystemClock, TimestampParser};
+use rumqttc::{Client, ConnectionError, Event, MqttOptions, Packet, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub use rumqttc::QoS;
+
+/// How a payload is encoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// The payload is the value as text, e.g. `21.5`.
+    #[default]
+    Text,
+    Json,
+    Cbor,
+}
+
+/// Where the value and timestamp of a message are found. Paths are dot-separated map
+/// keys and array indices into a JSON or CBOR document, e.g. `readings.0.temp`; the
+/// empty path is the whole document.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PayloadFormat {
+    pub encoding: Encoding,
+    pub value: String,
+    /// Path of the timestamp; messages are stamped on arrival when `None`.
+    pub timestamp: Option<String>,
+    /// Parses timestamps found in payloads, text and numbers alike.
+    pub parser: TimestampParser,
+}
+
+/// A scalar pulled out of a JSON or CBOR document. Integers are kept as text so that
+/// nanosecond timestamps survive beyond the precision of `f64`.
+enum Scalar {
+    Number(f64),
+    Text(String),
+}
+
+impl PayloadFormat {
+    /// A plain-text number.
+    pub fn text() -> Self {
+        Self::default()
+    }
+
+    /// A JSON document with the value at `value_path`.
+    pub fn json<S: Into<String>>(value_path: S) -> Self {
+        Self { encoding: Encoding::Json, value: value_path.into(), ..Self::default() }
+    }
+
+    /// A CBOR document with the value at `value_path`.
+    pub fn cbor<S: Into<String>>(value_path: S) -> Self {
+        Self { encoding: Encoding::Cbor, value: value_path.into(), ..Self::default() }
+    }
+
+    pub fn with_timestamp<S: Into<String>>(self, path: S) -> Self {
+        Self { timestamp: Some(path.into()), ..self }
+    }
+
+    pub fn with_parser(self, parser: TimestampParser) -> Self {
+        Self { parser, ..self }
+    }
+
+    /// The timestamp (if the format has one) and value of `payload`.
+    pub fn extract(&self, payload: &[u8]) -> Result<(Option<i64>, f64), Error> {
+        let invalid = |what: &str| Error::InvalidInput(format!("payload has no {}", what));
+        let (value, timestamp) = match self.encoding {
+            Encoding::Text => {
+                let text = std::str::from_utf8(payload).map_err(|_| invalid("text value"))?;
+                (Some(Scalar::Text(text.trim().to_string())), None)
+            }
+            Encoding::Json => {
+                let document: serde_json::Value =
+                    serde_json::from_slice(payload).map_err(|e| Error::InvalidInput(e.to_string()))?;
+                let timestamp = self.timestamp.as_deref().map(|path| json_scalar(&document, path));
+                (json_scalar(&document, &self.value), timestamp)
+            }
+            Encoding::Cbor => {
+                let document: ciborium::Value =
+                    ciborium::de::from_reader(payload).map_err(|e| Error::InvalidInput(e.to_string()))?;
+                let timestamp = self.timestamp.as_deref().map(|path| cbor_scalar(&document, path));
+                (cbor_scalar(&document, &self.value), timestamp)
+            }
+        };
+        let value = match value.ok_or_else(|| invalid("value"))? {
+            Scalar::Number(v) => v,
+            Scalar::Text(text) => text.parse().map_err(|_| invalid("numeric value"))?,
+        };
+        let timestamp = match timestamp {
+            None => None,
+            Some(None) => return Err(invalid("timestamp")),
+            Some(Some(Scalar::Number(t))) => Some(self.parser.parse(&t.to_string())?),
+            Some(Some(Scalar::Text(t))) => Some(self.parser.parse(&t)?),
+        };
+        Ok((timestamp, value))
+    }
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('.').filter(|s| !s.is_empty())
+}
+
+fn json_scalar(document: &serde_json::Value, path: &str) -> Option<Scalar> {
+    use serde_json::Value;
+    let mut node = document;
+    for segment in segments(path) {
+        node = match node {
+            Value::Object(map) => map.get(segment)?,
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    match node {
+        Value::Number(n) => Some(Scalar::Text(n.to_string())),
+        Value::Bool(b) => Some(Scalar::Number(*b as u8 as f64)),
+        Value::String(s) => Some(Scalar::Text(s.clone())),
+        _ => None,
+    }
+}
+
+fn cbor_scalar(document: &ciborium::Value, path: &str) -> Option<Scalar> {
+    use ciborium::Value;
+    let mut node = document;
+    for segment in segments(path) {
+        node = match node {
+            Value::Map(entries) => &entries.iter().find(|(k, _)| k.as_text() == Some(segment))?.1,
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    // Tagged values, e.g. epoch times (tag 1), carry the scalar inside.
+    while let Value::Tag(_, inner) = node {
+        node = &**inner;
+    }
+    match node {
+        Value::Integer(i) => Some(Scalar::Text(i128::from(*i).to_string())),
+        Value::Float(f) => Some(Scalar::Number(*f)),
+        Value::Bool(b) => Some(Scalar::Number(*b as u8 as f64)),
+        Value::Text(s) => Some(Scalar::Text(s.clone())),
+        _ => None,
+    }
+}
+
+/// Whether `topic` matches the MQTT topic `filter`, where `+` matches one level and a
+/// trailing `#` any number of levels.
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    let mut levels = topic.split('/');
+    for pattern in filter.split('/') {
+        match (pattern, levels.next()) {
+            ("#", _) => return true,
+            (_, None) => return false,
+            ("+", Some(_)) => {}
+            (pattern, Some(level)) if pattern == level => {}
+            _ => return false,
+        }
+    }
+    levels.next().is_none()
+}
+
+/// A topic filter with its QoS and payload layout.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subscription {
+    pub filter: String,
+    pub qos: QoS,
+    pub format: PayloadFormat,
+}
+
+impl Subscription {
+    pub fn new<S: Into<String>>(filter: S, qos: QoS, format: PayloadFormat) -> Self {
+        Self { filter: filter.into(), qos, format }
+    }
+}
+
+/// Counters of an ingest run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IngestStats {
+    pub received: u64,
+    pub appended: u64,
+    /// Messages that did not parse or arrived out of order.
+    pub rejected: u64,
+    pub reconnects: u64,
+}
+
+/// An MQTT client appending telemetry to a [`Catalog`].
+#[derive(Debug)]
+pub struct MqttIngest<C: Clock = SystemClock> {
+    host: String,
+    port: u16,
+    client_id: String,
+    keep_alive: Duration,
+    /// Pause after the first failed connection attempt; doubles up to a minute.
+    reconnect_delay: Duration,
+    subscriptions: Vec<Subscription>,
+    catalog: Arc<Catalog>,
+    clock: C,
+    stats: IngestStats,
+}
+
+impl MqttIngest<SystemClock> {
+    /// A client for the broker at `host:port`, stamping messages without a timestamp
+    /// with the system time in milliseconds.
+    pub fn new<H: Into<String>, I: Into<String>>(host: H, port: u16, client_id: I, catalog: Arc<Catalog>) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            client_id: client_id.into(),
+            keep_alive: Duration::from_secs(30),
+            reconnect_delay: Duration::from_secs(1),
+            subscriptions: Vec::new(),
+            catalog,
+            clock: SystemClock::millis(),
+            stats: IngestStats::default(),
+        }
+    }
+}
+
+impl<C: Clock> MqttIngest<C> {
+    pub fn subscribe(mut self, subscription: Subscription) -> Self {
+        self.subscriptions.push(subscription);
+        self
+    }
+
+    pub fn with_keep_alive(self, keep_alive: Duration) -> Self {
+        Self { keep_alive, ..self }
+    }
+
+    pub fn with_reconnect_delay(self, reconnect_delay: Duration) -> Self {
+        Self { reconnect_delay, ..self }
+    }
+
+    /// Stamps messages without a timestamp with `clock`.
+    pub fn with_clock<D: Clock>(self, clock: D) -> MqttIngest<D> {
+        MqttIngest {
+            host: self.host,
+            port: self.port,
+            client_id: self.client_id,
+            keep_alive: self.keep_alive,
+            reconnect_delay: self.reconnect_delay,
+            subscriptions: self.subscriptions,
+            catalog: self.catalog,
+            clock,
+            stats: self.stats,
+        }
+    }
+
+    pub fn catalog(&self) -> &Arc<Catalog> {
+        &self.catalog
+    }
+
+    pub fn stats(&self) -> IngestStats {
+        self.stats
+    }
+
+    /// Appends one message received on `topic`, using the format of the first matching
+    /// subscription.
+    pub fn handle(&mut self, topic: &str, payload: &[u8]) -> Result<(), Error> {
+        self.stats.received += 1;
+        let result = self.append(topic, payload);
+        match result {
+            Ok(()) => self.stats.appended += 1,
+            Err(_) => self.stats.rejected += 1,
+        }
+        result
+    }
+
+    fn append(&self, topic: &str, payload: &[u8]) -> Result<(), Error> {
+        let subscription = self
+            .subscriptions
+            .iter()
+            .find(|s| topic_matches(&s.filter, topic))
+            .ok_or_else(|| Error::InvalidInput(format!("no subscription matches `{}`", topic)))?;
+        let (timestamp, value) = subscription.format.extract(payload)?;
+        self.catalog.try_push(topic, timestamp.unwrap_or_else(|| self.clock.now()), value)
+    }
+
+    /// Connects and ingests until `token` is cancelled, reconnecting after failures.
+    /// Bad messages are counted in [`stats`](Self::stats) and skipped.
+    pub fn run_until(&mut self, token: &CancellationToken) -> Result<IngestStats, Error> {
+        let mut options = MqttOptions::new(self.client_id.clone(), self.host.clone(), self.port);
+        options.set_keep_alive(self.keep_alive);
+        let (client, mut connection) = Client::new(options, 64);
+        let mut delay = self.reconnect_delay;
+        while !token.is_cancelled() {
+            match connection.recv_timeout(Duration::from_millis(200)) {
+                Ok(Ok(Event::Incoming(Packet::ConnAck(_)))) => {
+                    delay = self.reconnect_delay;
+                    for s in &self.subscriptions {
+                        client.subscribe(s.filter.clone(), s.qos).map_err(|e| Error::InvalidInput(e.to_string()))?;
+                    }
+                }
+                Ok(Ok(Event::Incoming(Packet::Publish(publish)))) => {
+                    let _ = self.handle(&publish.topic, &publish.payload);
+                }
+                Ok(Ok(_)) | Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+                Ok(Err(ConnectionError::RequestsDone)) => break,
+                Ok(Err(_)) => {
+                    // The next poll reconnects; back off so a down broker is not hammered.
+                    self.stats.reconnects += 1;
+                    let until = Instant::now() + delay;
+                    while !token.is_cancelled() && Instant::now() < until {
+                        thread::sleep(Duration::from_millis(50));
+                    }
+                    delay = (delay * 2).min(Duration::from_secs(60));
+                }
+            }
+        }
+        let _ = client.disconnect();
+        Ok(self.stats)
+    }
+}
@@ -0,0 +1,140 @@
+//! Timestamped points of several values each, such as the x, y and z of an
+//! accelerometer, stored struct-of-arrays.
+//!
+//! Where a `TimestampedSeries<[f64; 3]>` interleaves the axes, a [`MultiValueSeries`]
+//! keeps each axis contiguous, so per-axis analytics scan one dense column and every
+//! `TimeSeries<f64>` operation applies to an axis as is.
+
+use crate::{TimeSeries, TimestampedSeries};
+
+/// Points of `N` values each, with one column per axis.
+#[derive(Clone, Debug)]
+pub struct MultiValueSeries<const N: usize> {
+    timestamps: Vec<i64>,
+    axes: [TimeSeries<f64>; N],
+}
+
+impl<const N: usize> MultiValueSeries<N> {
+    pub fn new() -> Self {
+        Self { timestamps: Vec::new(), axes: std::array::from_fn(|_| TimeSeries(Vec::new())) }
+    }
+
+    /// # Panics
+    /// Panics if an axis is not as long as `timestamps`, or `timestamps` is not sorted.
+    pub fn from_axes(timestamps: Vec<i64>, axes: [TimeSeries<f64>; N]) -> Self {
+        for axis in &axes {
+            assert_eq!(timestamps.len(), axis.len(), "every axis must have one value per timestamp");
+        }
+        assert!(timestamps.windows(2).all(|w| w[0] <= w[1]), "timestamps must be sorted");
+        Self { timestamps, axes }
+    }
+
+    /// # Panics
+    /// Panics if `timestamp` is earlier than the last one.
+    pub fn push(&mut self, timestamp: i64, point: [f64; N]) {
+        assert!(self.timestamps.last().is_none_or(|&last| last <= timestamp), "timestamps must be pushed in order");
+        self.timestamps.push(timestamp);
+        for (axis, v) in self.axes.iter_mut().zip(point) {
+            axis.push(v);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    pub fn timestamps(&self) -> &[i64] {
+        &self.timestamps
+    }
+
+    /// The values of axis `i`.
+    ///
+    /// # Panics
+    /// Panics if `i >= N`.
+    pub fn axis(&self, i: usize) -> &TimeSeries<f64> {
+        &self.axes[i]
+    }
+
+    pub fn axes(&self) -> &[TimeSeries<f64>; N] {
+        &self.axes
+    }
+
+    /// Axis `i` paired with the timestamps.
+    pub fn axis_series(&self, i: usize) -> TimestampedSeries<f64> {
+        TimestampedSeries::from_parts(self.timestamps.clone(), self.axes[i].clone())
+    }
+
+    /// The values of point `index`, gathered across the axes.
+    pub fn point(&self, index: usize) -> Option<[f64; N]> {
+        (index < self.len()).then(|| std::array::from_fn(|a| self.axes[a].as_slice()[index]))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (i64, [f64; N])> + '_ {
+        self.timestamps.iter().enumerate().map(|(i, &t)| (t, std::array::from_fn(|a| self.axes[a].as_slice()[i])))
+    }
+
+    /// Applies `f` to every axis, e.g. a filter or a rolling mean.
+    ///
+    /// # Panics
+    /// Panics if `f` changes the length of an axis.
+    pub fn map_axes<F>(&self, f: F) -> Self
+    where
+        F: Fn(&TimeSeries<f64>) -> TimeSeries<f64>,
+    {
+        Self::from_axes(self.timestamps.clone(), std::array::from_fn(|a| f(&self.axes[a])))
+    }
+
+    /// Reduces every axis to one number, e.g. `series.reduce_axes(|a| a.mean())`.
+    pub fn reduce_axes<F>(&self, f: F) -> [f64; N]
+    where
+        F: Fn(&TimeSeries<f64>) -> f64,
+    {
+        std::array::from_fn(|a| f(&self.axes[a]))
+    }
+
+    /// Combines the axes point by point, e.g. into a weighted sum.
+    pub fn combine<F>(&self, f: F) -> TimestampedSeries<f64>
+    where
+        F: Fn([f64; N]) -> f64,
+    {
+        self.iter().map(|(t, point)| (t, f(point))).collect()
+    }
+
+    /// The Euclidean magnitude of each point, such as the total acceleration.
+    pub fn norm(&self) -> TimestampedSeries<f64> {
+        self.combine(|point| point.iter().map(|v| v * v).sum::<f64>().sqrt())
+    }
+
+    /// The points interleaved, array-of-structs.
+    pub fn to_points(&self) -> TimestampedSeries<[f64; N]> {
+        self.iter().collect()
+    }
+}
+
+impl<const N: usize> Default for MultiValueSeries<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> From<&TimestampedSeries<[f64; N]>> for MultiValueSeries<N> {
+    fn from(points: &TimestampedSeries<[f64; N]>) -> Self {
+        points.iter().map(|(t, point)| (t, *point)).collect()
+    }
+}
+
+impl<const N: usize> FromIterator<(i64, [f64; N])> for MultiValueSeries<N> {
+    /// # Panics
+    /// Panics if the timestamps are not sorted.
+    fn from_iter<I: IntoIterator<Item = (i64, [f64; N])>>(iter: I) -> Self {
+        let mut series = Self::new();
+        for (t, point) in iter {
+            series.push(t, point);
+        }
+        series
+    }
+}
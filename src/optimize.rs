@@ -0,0 +1,81 @@
+//! Derivative-free minimization used for maximum-likelihood fits.
+
+use crate::Monitor;
+
+/// Minimizes `f` with the Nelder–Mead simplex method, starting from `x0` with an initial
+/// simplex of edge `step`. Stops after `max_iter` iterations or when the spread of the
+/// function values across the simplex drops below `1e-10`.
+pub(crate) fn nelder_mead<F>(f: F, x0: &[f64], step: f64, max_iter: usize) -> Vec<f64>
+where
+    F: Fn(&[f64]) -> f64,
+{
+    nelder_mead_monitored(f, x0, step, max_iter, &mut Monitor::new())
+}
+
+/// [`nelder_mead`], reporting iterations against `max_iter` to `monitor`. Stops early
+/// with the best point so far if the monitor is cancelled; callers check for that.
+pub(crate) fn nelder_mead_monitored<F>(f: F, x0: &[f64], step: f64, max_iter: usize, monitor: &mut Monitor) -> Vec<f64>
+where
+    F: Fn(&[f64]) -> f64,
+{
+    let n = x0.len();
+    let eval = |x: &[f64]| {
+        let v = f(x);
+        if v.is_nan() {
+            f64::INFINITY
+        } else {
+            v
+        }
+    };
+    let mut simplex: Vec<(Vec<f64>, f64)> = Vec::with_capacity(n + 1);
+    simplex.push((x0.to_vec(), eval(x0)));
+    for i in 0..n {
+        let mut x = x0.to_vec();
+        x[i] += step;
+        let v = eval(&x);
+        simplex.push((x, v));
+    }
+    let blend = |a: &[f64], b: &[f64], t: f64| -> Vec<f64> {
+        a.iter().zip(b).map(|(a, b)| a + t * (b - a)).collect()
+    };
+    for iteration in 0..max_iter {
+        if monitor.is_cancelled() {
+            break;
+        }
+        monitor.report(iteration, max_iter);
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        if (simplex[n].1 - simplex[0].1).abs() < 1e-10 {
+            break;
+        }
+        let centroid: Vec<f64> = (0..n)
+            .map(|j| simplex[..n].iter().map(|(x, _)| x[j]).sum::<f64>() / n as f64)
+            .collect();
+        let worst = simplex[n].clone();
+        let reflected = blend(&centroid, &worst.0, -1.);
+        let fr = eval(&reflected);
+        if fr < simplex[0].1 {
+            let expanded = blend(&centroid, &worst.0, -2.);
+            let fe = eval(&expanded);
+            simplex[n] = if fe < fr { (expanded, fe) } else { (reflected, fr) };
+        } else if fr < simplex[n - 1].1 {
+            simplex[n] = (reflected, fr);
+        } else {
+            let contracted = blend(&centroid, &worst.0, 0.5);
+            let fc = eval(&contracted);
+            if fc < worst.1 {
+                simplex[n] = (contracted, fc);
+            } else {
+                let best = simplex[0].0.clone();
+                for vertex in simplex.iter_mut().skip(1) {
+                    vertex.0 = blend(&best, &vertex.0, 0.5);
+                    vertex.1 = eval(&vertex.0);
+                }
+            }
+        }
+    }
+    if !monitor.is_cancelled() {
+        monitor.finish();
+    }
+    simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+    simplex.swap_remove(0).0
+}
@@ -0,0 +1,118 @@
+//! What-if scenarios: named adjustments layered over a base series without touching it.
+//!
+//! A [`Scenario`] only borrows its base and records its overlays; the adjusted series is
+//! computed when asked for, over the whole base or just a time range, with the enabled
+//! overlays applied in the order they were added. Overlays can be listed, switched off
+//! and on again, or removed, to compare plans side by side.
+
+use crate::TimestampedSeries;
+
+/// A change to the points of a series, by timestamp.
+#[derive(Clone, Debug)]
+pub enum Adjustment {
+    /// Changes the values with `start <= timestamp < end` by `percent` percent.
+    Scale { start: i64, end: i64, percent: f64 },
+    /// Adds `amount` to every value from `from` on.
+    Offset { from: i64, amount: f64 },
+    /// Replaces the points with `start <= timestamp < end` by those of `with` in the same
+    /// range; an empty `with` deletes them.
+    Replace { start: i64, end: i64, with: TimestampedSeries<f64> },
+}
+
+impl Adjustment {
+    pub fn apply(&self, series: &TimestampedSeries<f64>) -> TimestampedSeries<f64> {
+        let out: TimestampedSeries<f64> = match self {
+            Adjustment::Scale { start, end, percent } => series
+                .iter()
+                .map(|(t, &v)| (t, if (*start..*end).contains(&t) { v + v * percent / 100. } else { v }))
+                .collect(),
+            Adjustment::Offset { from, amount } => {
+                series.iter().map(|(t, &v)| (t, if t >= *from { v + amount } else { v })).collect()
+            }
+            Adjustment::Replace { start, end, with } => {
+                let before = series.iter().take_while(|&(t, _)| t < *start);
+                let inside = with.iter().filter(|&(t, _)| (*start..*end).contains(&t));
+                let after = series.iter().skip_while(|&(t, _)| t < *end);
+                before.chain(inside).chain(after).map(|(t, &v)| (t, v)).collect()
+            }
+        };
+        match series.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+}
+
+/// A named adjustment of a [`Scenario`].
+#[derive(Clone, Debug)]
+pub struct Overlay {
+    pub name: String,
+    pub adjustment: Adjustment,
+    pub enabled: bool,
+}
+
+/// A base series with overlays on top.
+#[derive(Clone, Debug)]
+pub struct Scenario<'a> {
+    base: &'a TimestampedSeries<f64>,
+    overlays: Vec<Overlay>,
+}
+
+impl<'a> Scenario<'a> {
+    pub fn new(base: &'a TimestampedSeries<f64>) -> Self {
+        Self { base, overlays: Vec::new() }
+    }
+
+    pub fn base(&self) -> &TimestampedSeries<f64> {
+        self.base
+    }
+
+    /// Adds an enabled overlay on top of the others, replacing any overlay of the same
+    /// name in place.
+    pub fn add(&mut self, name: &str, adjustment: Adjustment) -> &mut Self {
+        let overlay = Overlay { name: name.to_string(), adjustment, enabled: true };
+        match self.overlays.iter_mut().find(|o| o.name == name) {
+            Some(existing) => *existing = overlay,
+            None => self.overlays.push(overlay),
+        }
+        self
+    }
+
+    /// The overlays from the bottom up.
+    pub fn overlays(&self) -> &[Overlay] {
+        &self.overlays
+    }
+
+    /// Switches the overlay `name` on or off, returning false if there is none.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        self.overlays.iter_mut().find(|o| o.name == name).map(|o| o.enabled = enabled).is_some()
+    }
+
+    /// Switches the overlay `name` to the other state, returning its new state, or `None`
+    /// if there is no such overlay.
+    pub fn toggle(&mut self, name: &str) -> Option<bool> {
+        let overlay = self.overlays.iter_mut().find(|o| o.name == name)?;
+        overlay.enabled = !overlay.enabled;
+        Some(overlay.enabled)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Overlay> {
+        let i = self.overlays.iter().position(|o| o.name == name)?;
+        Some(self.overlays.remove(i))
+    }
+
+    fn apply_enabled(&self, series: TimestampedSeries<f64>) -> TimestampedSeries<f64> {
+        self.overlays.iter().filter(|o| o.enabled).fold(series, |s, o| o.adjustment.apply(&s))
+    }
+
+    /// The base with every enabled overlay applied.
+    pub fn evaluate(&self) -> TimestampedSeries<f64> {
+        self.apply_enabled(self.base.clone())
+    }
+
+    /// The adjusted points with `start <= timestamp < end`, computed from that range of
+    /// the base only.
+    pub fn evaluate_between(&self, start: i64, end: i64) -> TimestampedSeries<f64> {
+        self.apply_enabled(self.base.between(start, end)).between(start, end)
+    }
+}
@@ -0,0 +1,137 @@
+//! Paired comparison of two aligned series: a metric under pipeline A and pipeline B on
+//! the same days, or the forecast errors of two models over the same test period.
+//!
+//! Point `i` of one series is paired with point `i` of the other, so both must have the
+//! same length; pairs with NaN on either side are dropped. Every p-value is two-sided.
+
+use crate::special::{normal_cdf, student_t_two_sided};
+use crate::{Error, TimeSeries};
+
+/// The outcome of a paired test.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TestResult {
+    /// The standardized statistic, positive when the first series is larger (or has the
+    /// larger loss).
+    pub statistic: f64,
+    pub p_value: f64,
+    /// Number of pairs the test used.
+    pub n: usize,
+}
+
+impl TestResult {
+    /// Whether the difference is significant at level `alpha`.
+    pub fn rejects(&self, alpha: f64) -> bool {
+        self.p_value < alpha
+    }
+}
+
+/// The loss of a forecast error compared by [`PairedComparison::diebold_mariano`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ForecastLoss {
+    #[default]
+    Squared,
+    Absolute,
+}
+
+impl ForecastLoss {
+    fn apply(&self, error: f64) -> f64 {
+        match self {
+            ForecastLoss::Squared => error * error,
+            ForecastLoss::Absolute => error.abs(),
+        }
+    }
+}
+
+/// `f(a) - f(b)` over the pairs where it is not NaN.
+fn differences<F>(a: &TimeSeries<f64>, b: &TimeSeries<f64>, f: F) -> Result<Vec<f64>, Error>
+where
+    F: Fn(f64) -> f64,
+{
+    if a.len() != b.len() {
+        return Err(Error::LengthMismatch { expected: a.len(), found: b.len() });
+    }
+    let d: Vec<f64> = a.as_slice().iter().zip(b.as_slice()).map(|(&x, &y)| f(x) - f(y)).filter(|d| !d.is_nan()).collect();
+    if d.len() < 2 {
+        return Err(Error::InsufficientData { required: 2, found: d.len() });
+    }
+    Ok(d)
+}
+
+pub trait PairedComparison {
+    /// Student's t-test of a zero mean for the differences `self - other`, with `n - 1`
+    /// degrees of freedom; the statistic is t.
+    ///
+    /// Fails if the lengths differ or fewer than two pairs remain.
+    fn paired_t_test(&self, other: &Self) -> Result<TestResult, Error>;
+    /// Wilcoxon's signed-rank test of differences symmetric about zero, which unlike the
+    /// t-test is not thrown off by a few outlying pairs. Zero differences are dropped and
+    /// tied magnitudes share their average rank. The statistic is the rank sum of the
+    /// positive differences standardized with the normal approximation, with tie and
+    /// continuity corrections, reasonable from about 10 nonzero differences.
+    ///
+    /// Fails if the lengths differ or fewer than two nonzero differences remain.
+    fn wilcoxon_signed_rank(&self, other: &Self) -> Result<TestResult, Error>;
+    /// The Diebold–Mariano test of equal accuracy for two series of `horizon`-step-ahead
+    /// forecast errors. The long-run variance of the loss differential includes its
+    /// autocovariances up to lag `horizon - 1`, falling back to the variance alone if that
+    /// is not positive, and the statistic gets the Harvey–Leybourne–Newbold small-sample
+    /// correction against Student's t with `n - 1` degrees of freedom. A negative
+    /// statistic favors `self`.
+    ///
+    /// Fails if the lengths differ or fewer than two pairs remain.
+    ///
+    /// # Panics
+    /// Panics if `horizon` is zero.
+    fn diebold_mariano(&self, other: &Self, horizon: usize, loss: ForecastLoss) -> Result<TestResult, Error>;
+}
+
+impl PairedComparison for TimeSeries<f64> {
+    fn paired_t_test(&self, other: &Self) -> Result<TestResult, Error> {
+        let d = differences(self, other, |x| x)?;
+        let n = d.len() as f64;
+        let mean = d.iter().sum::<f64>() / n;
+        let var = d.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.);
+        let statistic = mean / (var / n).sqrt();
+        Ok(TestResult { statistic, p_value: student_t_two_sided(statistic, n - 1.), n: d.len() })
+    }
+
+    fn wilcoxon_signed_rank(&self, other: &Self) -> Result<TestResult, Error> {
+        let mut d = differences(self, other, |x| x)?;
+        d.retain(|&x| x != 0.);
+        if d.len() < 2 {
+            return Err(Error::InsufficientData { required: 2, found: d.len() });
+        }
+        d.sort_by(|a, b| a.abs().total_cmp(&b.abs()));
+        let (mut positive_ranks, mut tie_correction) = (0., 0.);
+        let mut start = 0;
+        while start < d.len() {
+            let end = start + d[start..].iter().take_while(|x| x.abs() == d[start].abs()).count();
+            let rank = (start + end + 1) as f64 / 2.;
+            positive_ranks += rank * d[start..end].iter().filter(|&&x| x > 0.).count() as f64;
+            let ties = (end - start) as f64;
+            tie_correction += ties.powi(3) - ties;
+            start = end;
+        }
+        let n = d.len() as f64;
+        let mean = n * (n + 1.) / 4.;
+        let sd = (n * (n + 1.) * (2. * n + 1.) / 24. - tie_correction / 48.).sqrt();
+        let deviation = positive_ranks - mean;
+        let statistic = (deviation.abs() - 0.5).max(0.).copysign(deviation) / sd;
+        Ok(TestResult { statistic, p_value: 2. * normal_cdf(-statistic.abs()), n: d.len() })
+    }
+
+    fn diebold_mariano(&self, other: &Self, horizon: usize, loss: ForecastLoss) -> Result<TestResult, Error> {
+        assert!(horizon > 0, "horizon must be positive");
+        let d = differences(self, other, |e| loss.apply(e))?;
+        let n = d.len() as f64;
+        let mean = d.iter().sum::<f64>() / n;
+        let autocovariance = |k: usize| d.iter().zip(&d[k..]).map(|(a, b)| (a - mean) * (b - mean)).sum::<f64>() / n;
+        let variance = autocovariance(0);
+        let long_run = variance + 2. * (1..horizon.min(d.len())).map(autocovariance).sum::<f64>();
+        let long_run = if long_run > 0. { long_run } else { variance };
+        let h = horizon as f64;
+        let correction = ((n + 1. - 2. * h + h * (h - 1.) / n) / n).sqrt();
+        let statistic = correction * mean / (long_run / n).sqrt();
+        Ok(TestResult { statistic, p_value: student_t_two_sided(statistic, n - 1.), n: d.len() })
+    }
+}
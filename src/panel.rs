@@ -0,0 +1,198 @@
+//! Panel data: one metric observed for many entities (stores, countries, users) over
+//! time, with the fixed-effect transformations of panel econometrics.
+//!
+//! A [`Panel`] keeps one series per entity in a [`SeriesSet`]. In a balanced panel every
+//! entity is observed at the same timestamps; the effects below handle unbalanced panels
+//! too. NaN values count as missing observations.
+
+use crate::reshape::{pivot, LongRecord};
+use crate::{SeriesSet, TimeSeriesFrame, TimestampedSeries};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Additive entity and time effects, `y ≈ mean + entity[i] + time[t]`, each set
+/// averaging zero.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FixedEffects {
+    pub mean: f64,
+    pub entity: BTreeMap<String, f64>,
+    pub time: BTreeMap<i64, f64>,
+}
+
+impl FixedEffects {
+    /// The value the effects predict for `entity` at `timestamp`, if both were observed.
+    pub fn fitted(&self, entity: &str, timestamp: i64) -> Option<f64> {
+        Some(self.mean + self.entity.get(entity)? + self.time.get(&timestamp)?)
+    }
+}
+
+fn with_unit_of(source: &TimestampedSeries<f64>, series: TimestampedSeries<f64>) -> TimestampedSeries<f64> {
+    match source.unit() {
+        Some(unit) => series.with_unit(unit),
+        None => series,
+    }
+}
+
+/// Entity × time observations.
+#[derive(Clone, Debug, Default)]
+pub struct Panel {
+    entities: SeriesSet<f64>,
+}
+
+impl Panel {
+    /// A panel with one entity per series of `entities`.
+    pub fn new(entities: SeriesSet<f64>) -> Self {
+        Self { entities }
+    }
+
+    /// A panel from long records keyed by entity, in any order. When an entity repeats a
+    /// timestamp, the last record wins.
+    pub fn from_records<I: IntoIterator<Item = LongRecord>>(records: I) -> Self {
+        let mut cells: BTreeMap<String, BTreeMap<i64, f64>> = BTreeMap::new();
+        for record in records {
+            cells.entry(record.key).or_default().insert(record.timestamp, record.value);
+        }
+        Self::new(cells.into_iter().map(|(entity, points)| (entity, points.into_iter().collect())).collect())
+    }
+
+    pub fn entities(&self) -> &SeriesSet<f64> {
+        &self.entities
+    }
+
+    pub fn into_entities(self) -> SeriesSet<f64> {
+        self.entities
+    }
+
+    fn observed(&self) -> impl Iterator<Item = (&str, i64, f64)> + '_ {
+        self.entities
+            .iter()
+            .flat_map(|(entity, series)| series.iter().map(move |(t, &v)| (entity.as_str(), t, v)))
+            .filter(|(_, _, v)| !v.is_nan())
+    }
+
+    /// The number of observations.
+    pub fn observations(&self) -> usize {
+        self.observed().count()
+    }
+
+    /// Every timestamp with an observation of some entity, in order.
+    pub fn periods(&self) -> Vec<i64> {
+        self.observed().map(|(_, t, _)| t).collect::<BTreeSet<i64>>().into_iter().collect()
+    }
+
+    /// For every entity not observed in some of [`periods`](Self::periods), the periods
+    /// it lacks.
+    pub fn missing(&self) -> BTreeMap<String, Vec<i64>> {
+        let periods = self.periods();
+        self.entities
+            .iter()
+            .filter_map(|(entity, series)| {
+                let seen: BTreeSet<i64> = series.iter().filter(|(_, v)| !v.is_nan()).map(|(t, _)| t).collect();
+                let lacking: Vec<i64> = periods.iter().copied().filter(|t| !seen.contains(t)).collect();
+                (!lacking.is_empty()).then(|| (entity.clone(), lacking))
+            })
+            .collect()
+    }
+
+    /// Whether every entity is observed in every period.
+    pub fn is_balanced(&self) -> bool {
+        self.missing().is_empty()
+    }
+
+    /// The observations in the periods where every entity is observed.
+    pub fn balanced(&self) -> Panel {
+        let missing: BTreeSet<i64> = self.missing().into_values().flatten().collect();
+        Self::new(self.entities.apply_all(|series| {
+            let kept = series.iter().filter(|&(t, v)| !v.is_nan() && !missing.contains(&t)).map(|(t, &v)| (t, v));
+            with_unit_of(series, kept.collect())
+        }))
+    }
+
+    /// Each entity's series minus its own mean: the within transformation, which removes
+    /// entity fixed effects.
+    pub fn demean_entities(&self) -> Panel {
+        Self::new(self.entities.apply_all(|series| {
+            let values: Vec<f64> = series.values().as_slice().iter().copied().filter(|v| !v.is_nan()).collect();
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            series.map(|v| v - mean)
+        }))
+    }
+
+    /// Each observation minus the grand mean and its entity and time effects, which
+    /// removes both kinds of fixed effects.
+    pub fn demean_two_way(&self) -> Panel {
+        let effects = self.fixed_effects();
+        let mut out = SeriesSet::new();
+        for (entity, series) in self.entities.iter() {
+            let residuals: TimestampedSeries<f64> =
+                series.iter().map(|(t, &v)| (t, effects.fitted(entity, t).map_or(f64::NAN, |f| v - f))).collect();
+            out.insert(entity.as_str(), with_unit_of(series, residuals));
+        }
+        Self::new(out)
+    }
+
+    /// The two-way fixed effects by least squares, found by alternately averaging out
+    /// the entity and the time effects; one pass suffices for a balanced panel.
+    pub fn fixed_effects(&self) -> FixedEffects {
+        let entities: Vec<&str> = self.entities.names().collect();
+        let periods = self.periods();
+        let observations: Vec<(usize, usize, f64)> = self
+            .observed()
+            .map(|(entity, t, v)| {
+                let i = entities.binary_search(&entity).expect("entities are in name order");
+                (i, periods.binary_search(&t).expect("periods cover every observation"), v)
+            })
+            .collect();
+        if observations.is_empty() {
+            return FixedEffects::default();
+        }
+        let mut mean = observations.iter().map(|&(_, _, v)| v).sum::<f64>() / observations.len() as f64;
+        let scale = observations.iter().map(|&(_, _, v)| (v - mean).abs()).fold(0., f64::max);
+        let (mut entity, mut time) = (vec![0.; entities.len()], vec![0.; periods.len()]);
+        let mut counts = (vec![0.; entities.len()], vec![0.; periods.len()]);
+        for &(i, t, _) in &observations {
+            counts.0[i] += 1.;
+            counts.1[t] += 1.;
+        }
+        for _ in 0..1000 {
+            let mut sums = vec![0.; entities.len()];
+            for &(i, t, v) in &observations {
+                sums[i] += v - mean - time[t];
+            }
+            let next_entity: Vec<f64> =
+                sums.iter().zip(&counts.0).map(|(s, &c)| if c > 0. { s / c } else { 0. }).collect();
+            let mut sums = vec![0.; periods.len()];
+            for &(i, t, v) in &observations {
+                sums[t] += v - mean - next_entity[i];
+            }
+            let next_time: Vec<f64> = sums.iter().zip(&counts.1).map(|(s, c)| s / c).collect();
+            let change = next_entity
+                .iter()
+                .zip(&entity)
+                .chain(next_time.iter().zip(&time))
+                .map(|(a, b)| (a - b).abs())
+                .fold(0., f64::max);
+            (entity, time) = (next_entity, next_time);
+            if change <= 1e-12 * scale {
+                break;
+            }
+        }
+        // Center both sets of effects, moving their means into the grand mean.
+        let observed_entities: Vec<usize> = (0..entities.len()).filter(|&i| counts.0[i] > 0.).collect();
+        let entity_mean = observed_entities.iter().map(|&i| entity[i]).sum::<f64>() / observed_entities.len() as f64;
+        let time_mean = time.iter().sum::<f64>() / time.len() as f64;
+        mean += entity_mean + time_mean;
+        FixedEffects {
+            mean,
+            entity: observed_entities.iter().map(|&i| (entities[i].to_string(), entity[i] - entity_mean)).collect(),
+            time: periods.iter().zip(&time).map(|(&t, g)| (t, g - time_mean)).collect(),
+        }
+    }
+
+    /// A wide frame indexed by period with one column per entity in name order, NaN where
+    /// an entity is not observed.
+    pub fn to_frame(&self) -> TimeSeriesFrame {
+        pivot(self.entities.iter().flat_map(|(entity, series)| {
+            series.iter().map(move |(timestamp, &value)| LongRecord { timestamp, key: entity.clone(), value })
+        }))
+    }
+}
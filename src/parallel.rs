@@ -0,0 +1,125 @@
+//! Multi-threaded reductions over large series (rayon).
+//!
+//! In deterministic mode the data is cut into chunks of a fixed length, each chunk is
+//! summed sequentially and the partial sums are combined by a pairwise tree whose shape
+//! depends only on the number of chunks. Results are then bit-identical across runs and
+//! thread counts. Otherwise rayon's adaptive splitting is used, which is slightly faster
+//! but may round differently from run to run.
+
+use crate::TimeSeries;
+use rayon::prelude::*;
+
+const DEFAULT_CHUNK: usize = 4096;
+
+/// Configuration of the parallel reductions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParallelReduction {
+    deterministic: bool,
+    chunk_size: usize,
+}
+
+impl Default for ParallelReduction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Combines partial sums pairwise, in a shape fixed by their count.
+fn tree_sum(mut partials: Vec<f64>) -> f64 {
+    if partials.is_empty() {
+        return 0.;
+    }
+    while partials.len() > 1 {
+        partials = partials.chunks(2).map(|pair| pair.iter().sum()).collect();
+    }
+    partials[0]
+}
+
+impl ParallelReduction {
+    /// Deterministic reductions over chunks of 4096 points.
+    pub fn new() -> Self {
+        Self { deterministic: true, chunk_size: DEFAULT_CHUNK }
+    }
+
+    pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Points per chunk in deterministic mode. Results are reproducible for a given chunk
+    /// size, not across chunk sizes.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Sum of `f` applied to each value.
+    fn map_sum<F>(&self, values: &[f64], f: F) -> f64
+    where
+        F: Fn(f64) -> f64 + Sync,
+    {
+        if self.deterministic {
+            let partials = values.par_chunks(self.chunk_size).map(|c| c.iter().map(|&x| f(x)).sum()).collect();
+            tree_sum(partials)
+        } else {
+            values.par_iter().map(|&x| f(x)).sum()
+        }
+    }
+
+    pub fn sum(&self, series: &TimeSeries<f64>) -> f64 {
+        self.map_sum(series.as_slice(), |x| x)
+    }
+
+    pub fn mean(&self, series: &TimeSeries<f64>) -> f64 {
+        self.sum(series) / series.len() as f64
+    }
+
+    /// Sample variance (denominator `n - 1`), by two passes.
+    pub fn var(&self, series: &TimeSeries<f64>) -> f64 {
+        if series.len() < 2 {
+            return f64::NAN;
+        }
+        let mean = self.mean(series);
+        self.map_sum(series.as_slice(), |x| (x - mean).powi(2)) / (series.len() - 1) as f64
+    }
+
+    pub fn std(&self, series: &TimeSeries<f64>) -> f64 {
+        self.var(series).sqrt()
+    }
+
+    /// Sum of the products of the two series over their common length.
+    pub fn dot(&self, a: &TimeSeries<f64>, b: &TimeSeries<f64>) -> f64 {
+        let n = a.len().min(b.len());
+        let (a, b) = (&a.as_slice()[..n], &b.as_slice()[..n]);
+        if self.deterministic {
+            let partials = a
+                .par_chunks(self.chunk_size)
+                .zip(b.par_chunks(self.chunk_size))
+                .map(|(x, y)| x.iter().zip(y).map(|(x, y)| x * y).sum())
+                .collect();
+            tree_sum(partials)
+        } else {
+            a.par_iter().zip(b).map(|(x, y)| x * y).sum()
+        }
+    }
+
+    /// Minimum and maximum ignore rounding entirely, so they are exact in either mode.
+    pub fn min(&self, series: &TimeSeries<f64>) -> f64 {
+        if series.is_empty() {
+            return f64::NAN;
+        }
+        series.as_slice().par_iter().copied().reduce(|| f64::INFINITY, f64::min)
+    }
+
+    pub fn max(&self, series: &TimeSeries<f64>) -> f64 {
+        if series.is_empty() {
+            return f64::NAN;
+        }
+        series.as_slice().par_iter().copied().reduce(|| f64::NEG_INFINITY, f64::max)
+    }
+}
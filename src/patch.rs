@@ -0,0 +1,107 @@
+//! Differences between two versions of a series, as compact patches to apply elsewhere,
+//! e.g. to bring a client's copy of a series up to date with the server's.
+//!
+//! Points are matched by timestamp, which is taken to be unique within each version, and
+//! values are compared with `==`, so a NaN point always shows as modified.
+
+use crate::{Error, TimestampedSeries};
+use std::collections::BTreeMap;
+
+/// The changes turning one version of a series into another, from [`diff_series`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeriesPatch<T> {
+    /// Length and last timestamp of the version the patch applies to.
+    pub base_len: usize,
+    pub base_last: Option<i64>,
+    /// Timestamps of the old version that the new one lacks.
+    pub deleted: Vec<i64>,
+    /// Points of the new version up to the old version's last timestamp that are new or
+    /// have a different value, in timestamp order.
+    pub modified: Vec<(i64, T)>,
+    /// Points of the new version after the old version's last timestamp, in order.
+    pub appended: Vec<(i64, T)>,
+}
+
+impl<T> SeriesPatch<T> {
+    /// Whether the two versions are the same.
+    pub fn is_empty(&self) -> bool {
+        self.deleted.is_empty() && self.modified.is_empty() && self.appended.is_empty()
+    }
+
+    /// The number of points deleted, modified or appended.
+    pub fn len(&self) -> usize {
+        self.deleted.len() + self.modified.len() + self.appended.len()
+    }
+
+    /// Whether the patch only appends points, so applying it needs no rewrite.
+    pub fn is_append_only(&self) -> bool {
+        self.deleted.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl<T: Clone> SeriesPatch<T> {
+    /// The new version from the old one.
+    ///
+    /// Fails if `series` does not have the length and last timestamp of the version the
+    /// patch was computed from.
+    pub fn apply(&self, series: &TimestampedSeries<T>) -> Result<TimestampedSeries<T>, Error> {
+        if series.len() != self.base_len || series.timestamps().last().copied() != self.base_last {
+            return Err(Error::InvalidInput("the patch was computed from another version of the series".to_string()));
+        }
+        let out: TimestampedSeries<T> = if self.is_append_only() {
+            series.iter().map(|(t, v)| (t, v.clone())).chain(self.appended.iter().cloned()).collect()
+        } else {
+            let mut points: BTreeMap<i64, T> = series.iter().map(|(t, v)| (t, v.clone())).collect();
+            for t in &self.deleted {
+                points.remove(t);
+            }
+            points.extend(self.modified.iter().cloned());
+            points.into_iter().chain(self.appended.iter().cloned()).collect()
+        };
+        Ok(match series.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        })
+    }
+}
+
+/// The patch turning `old` into `new`.
+pub fn diff_series<T: Clone + PartialEq>(old: &TimestampedSeries<T>, new: &TimestampedSeries<T>) -> SeriesPatch<T> {
+    let base_last = old.timestamps().last().copied();
+    let split = base_last.map_or(0, |last| new.timestamps().partition_point(|&t| t <= last));
+    let (mut deleted, mut modified) = (Vec::new(), Vec::new());
+    let (mut before, mut after) = (old.iter().peekable(), new.iter().take(split).peekable());
+    // Merge the two sorted point lists up to the old version's end.
+    loop {
+        match (before.peek(), after.peek()) {
+            (Some(&(t, _)), Some(&(u, _))) if t < u => {
+                deleted.push(t);
+                before.next();
+            }
+            (Some(&(t, v)), Some(&(u, w))) if t == u => {
+                if v != w {
+                    modified.push((u, w.clone()));
+                }
+                before.next();
+                after.next();
+            }
+            (_, Some(&(u, w))) => {
+                modified.push((u, w.clone()));
+                after.next();
+            }
+            (Some(&(t, _)), None) => {
+                deleted.push(t);
+                before.next();
+            }
+            (None, None) => break,
+        }
+    }
+    SeriesPatch {
+        base_len: old.len(),
+        base_last,
+        deleted,
+        modified,
+        appended: new.iter().skip(split).map(|(t, v)| (t, v.clone())).collect(),
+    }
+}
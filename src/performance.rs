@@ -0,0 +1,91 @@
+//! Performance analytics for a series of periodic returns.
+//!
+//! Figures are per period; annualize by scaling with the number of periods per year
+//! (e.g. tracking error by `sqrt(252)` for daily returns).
+
+use crate::{LinearFit, Regression, Statistics, TimeSeries};
+
+/// How much [`Performance::rolling_beta`] pulls each window's beta towards the
+/// full-sample beta.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shrinkage {
+    /// Fixed intensity in `[0, 1]`: 0 keeps the raw window beta, 1 the full-sample beta.
+    Fixed(f64),
+    /// Per-window intensity `se² / (se² + tau²)`, where `se²` is the sampling variance of
+    /// the window beta and `tau²` the dispersion of the window betas around the target,
+    /// so noisy windows are shrunk harder.
+    Auto,
+}
+
+pub trait Performance {
+    /// Sensitivity of the returns to the benchmark returns (OLS slope).
+    fn beta(&self, benchmark: &Self) -> f64;
+    /// Return not explained by the benchmark (OLS intercept), per period.
+    fn alpha(&self, benchmark: &Self) -> f64;
+    /// Alpha and beta over each trailing window, as `intercept` and `slope` of the fit.
+    fn rolling_alpha_beta(&self, benchmark: &Self, window: usize) -> TimeSeries<LinearFit>;
+    /// Beta over each trailing window, shrunk towards the full-sample beta to stabilise
+    /// short-window estimates. Windows that cannot be fitted are NaN.
+    ///
+    /// # Panics
+    /// Panics if a [`Shrinkage::Fixed`] intensity is outside `[0, 1]`, or with
+    /// [`Shrinkage::Auto`] if `window < 3`, which leaves no degrees of freedom for the
+    /// residual variance.
+    fn rolling_beta(&self, benchmark: &Self, window: usize, shrinkage: Shrinkage) -> TimeSeries<f64>;
+    /// Standard deviation of the active returns `self - benchmark`.
+    fn tracking_error(&self, benchmark: &Self) -> f64;
+    /// Mean active return divided by the tracking error.
+    fn information_ratio(&self, benchmark: &Self) -> f64;
+}
+
+impl Performance for TimeSeries<f64> {
+    fn beta(&self, benchmark: &Self) -> f64 {
+        self.linear_fit(benchmark).map_or(f64::NAN, |fit| fit.slope)
+    }
+
+    fn alpha(&self, benchmark: &Self) -> f64 {
+        self.linear_fit(benchmark).map_or(f64::NAN, |fit| fit.intercept)
+    }
+
+    fn rolling_alpha_beta(&self, benchmark: &Self, window: usize) -> TimeSeries<LinearFit> {
+        self.rolling_linear_fit(benchmark, window)
+    }
+
+    fn rolling_beta(&self, benchmark: &Self, window: usize, shrinkage: Shrinkage) -> TimeSeries<f64> {
+        let target = self.beta(benchmark);
+        let fits = self.rolling_linear_fit(benchmark, window);
+        match shrinkage {
+            Shrinkage::Fixed(intensity) => {
+                assert!((0. ..=1.).contains(&intensity), "shrinkage intensity must be in [0, 1]");
+                fits.map(|fit| (1. - intensity) * fit.slope + intensity * target)
+            }
+            Shrinkage::Auto => {
+                assert!(window >= 3, "automatic shrinkage needs a window of at least 3");
+                let betas: Vec<f64> = fits.as_slice().iter().map(|fit| fit.slope).filter(|b| b.is_finite()).collect();
+                let tau2 = betas.iter().map(|b| (b - target).powi(2)).sum::<f64>() / betas.len() as f64;
+                let w = window as f64;
+                (0..fits.len())
+                    .map(|i| {
+                        let fit = fits[i];
+                        let y = self.slice(i..i + window);
+                        let x = benchmark.slice(i..i + window);
+                        // Sampling variance of the OLS slope: residual variance over sum of squares of x.
+                        let residual_var = (1. - fit.r_squared) * y.var() * (w - 1.) / (w - 2.);
+                        let se2 = residual_var / ((w - 1.) * x.var());
+                        let intensity = if tau2 > 0. { se2 / (se2 + tau2) } else { 1. };
+                        (1. - intensity) * fit.slope + intensity * target
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn tracking_error(&self, benchmark: &Self) -> f64 {
+        (self - benchmark).std()
+    }
+
+    fn information_ratio(&self, benchmark: &Self) -> f64 {
+        let active = self - benchmark;
+        active.mean() / active.std()
+    }
+}
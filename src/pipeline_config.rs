@@ -0,0 +1,131 @@
+//! Declarative pipeline definitions, deserialized from JSON, YAML (with the `yaml`
+//! feature) or any serde format, and built into a streaming [`Pipeline`] at runtime or run
+//! over a whole series at once.
+//!
+//! ```json
+//! [
+//!   {"op": "resample", "every": 60},
+//!   {"op": "fillna", "value": 0.0, "forward": true},
+//!   {"op": "ewma", "alpha": 0.1},
+//!   {"op": "zscore", "window": 30},
+//!   {"op": "threshold", "level": 3.0}
+//! ]
+//! ```
+//!
+//! or in YAML:
+//!
+//! ```yaml
+//! - op: resample
+//!   every: 60
+//! - op: zscore
+//!   window: 30
+//! ```
+
+use crate::streaming::{
+    DiffOp, EwmaOp, FillNaOp, Pipeline, ResampleOp, RollingMeanOp, StreamOp, ThresholdOp, ZScoreOp,
+};
+use crate::{Error, TimeSeries};
+use serde::{Deserialize, Serialize};
+
+/// Largest window, block size or lag accepted, so that a definition from an untrusted
+/// source cannot make an operator allocate without bound.
+pub const MAX_WINDOW: usize = 1 << 20;
+
+/// One stage of a pipeline definition, tagged by `op`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum OpSpec {
+    /// Mean of each block of `every` points.
+    Resample { every: usize },
+    /// NaN replacement, forward-filling by default.
+    Fillna {
+        #[serde(default)]
+        value: f64,
+        #[serde(default = "default_forward")]
+        forward: bool,
+    },
+    Ewma { alpha: f64 },
+    RollingMean { window: usize },
+    Diff {
+        #[serde(default = "default_lag")]
+        lag: usize,
+    },
+    Zscore { window: usize },
+    Threshold { level: f64 },
+}
+
+fn default_forward() -> bool {
+    true
+}
+
+fn default_lag() -> usize {
+    1
+}
+
+/// An ordered list of stages.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PipelineSpec {
+    pub ops: Vec<OpSpec>,
+}
+
+impl PipelineSpec {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|e| Error::InvalidInput(e.to_string()))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("pipeline specs always serialize")
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> Result<Self, Error> {
+        serde_yaml::from_str(yaml).map_err(|e| Error::InvalidInput(e.to_string()))
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(self).expect("pipeline specs always serialize")
+    }
+
+    /// Runs the pipeline over the whole of `series` as a batch, with the same outputs as
+    /// feeding it point by point.
+    ///
+    /// Fails like [`build`](Self::build).
+    pub fn run(&self, series: &TimeSeries<f64>) -> Result<TimeSeries<f64>, Error> {
+        Ok(self.build()?.process(series))
+    }
+
+    /// Builds the streaming pipeline, rejecting out-of-range parameters instead of
+    /// panicking in the operator constructors, and windows, block sizes and lags above
+    /// [`MAX_WINDOW`].
+    pub fn build(&self) -> Result<Pipeline, Error> {
+        let invalid = |i: usize, what: &str| Err(Error::InvalidInput(format!("stage {}: {}", i, what)));
+        let mut pipeline = Pipeline::new();
+        for (i, op) in self.ops.iter().enumerate() {
+            let size = match *op {
+                OpSpec::Resample { every: n } | OpSpec::RollingMean { window: n } | OpSpec::Zscore { window: n } => n,
+                OpSpec::Diff { lag } => lag,
+                _ => 0,
+            };
+            if size > MAX_WINDOW {
+                return invalid(i, &format!("{} is larger than the maximum of {}", size, MAX_WINDOW));
+            }
+            pipeline = match *op {
+                OpSpec::Resample { every: 0 } => return invalid(i, "block size must be positive"),
+                OpSpec::Resample { every } => pipeline.then(ResampleOp::new(every)),
+                OpSpec::Fillna { value, forward: true } => pipeline.then(FillNaOp::forward(value)),
+                OpSpec::Fillna { value, forward: false } => pipeline.then(FillNaOp::constant(value)),
+                OpSpec::Ewma { alpha } if !(alpha > 0. && alpha <= 1.) => return invalid(i, "alpha must be in (0, 1]"),
+                OpSpec::Ewma { alpha } => pipeline.then(EwmaOp::new(alpha)),
+                OpSpec::RollingMean { window: 0 } => return invalid(i, "window must be positive"),
+                OpSpec::RollingMean { window } => pipeline.then(RollingMeanOp::new(window)),
+                OpSpec::Diff { lag } => pipeline.then(DiffOp::new(lag)),
+                OpSpec::Zscore { window } if window < 2 => return invalid(i, "window must be at least 2"),
+                OpSpec::Zscore { window } => pipeline.then(ZScoreOp::new(window)),
+                OpSpec::Threshold { level } => pipeline.then(ThresholdOp::new(level)),
+            };
+        }
+        Ok(pipeline)
+    }
+}
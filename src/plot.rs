@@ -0,0 +1,248 @@
+//! SVG charts through `plotters`: overlaid lines with a legend, an optional secondary
+//! y-axis, candlesticks for OHLC bars, shaded interval bands, and charts stacked as
+//! subplots.
+//!
+//! Long series are downsampled before drawing, lines with [`lttb`](TimestampedSeries::lttb),
+//! bars with [`merge_bars`](TimestampedSeries::merge_bars) and bands with
+//! [`merge_intervals`](TimestampedSeries::merge_intervals), so rendering cost does not
+//! grow with the data.
+
+use crate::{Error, Interval, Ohlcv, TimeSeriesFrame, TimestampedSeries};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use std::ops::Range;
+use std::path::Path;
+
+fn plot_error<E: std::fmt::Display>(e: E) -> Error {
+    Error::InvalidInput(e.to_string())
+}
+
+#[derive(Clone, Debug)]
+enum Layer {
+    Line { name: String, series: TimestampedSeries<f64>, secondary: bool },
+    Candles { name: String, bars: TimestampedSeries<Ohlcv> },
+    Band { name: String, bands: TimestampedSeries<Interval> },
+}
+
+/// A chart of one or more series over a shared time axis.
+#[derive(Clone, Debug)]
+pub struct Chart {
+    title: String,
+    width: u32,
+    height: u32,
+    /// Points per line (bars per candle layer) drawn at most; `None` draws everything.
+    max_points: Option<usize>,
+    layers: Vec<Layer>,
+}
+
+impl Chart {
+    /// An empty 1024x480 chart drawing at most 2000 points per layer.
+    pub fn new<S: Into<String>>(title: S) -> Self {
+        Self { title: title.into(), width: 1024, height: 480, max_points: Some(2000), layers: Vec::new() }
+    }
+
+    pub fn with_size(self, width: u32, height: u32) -> Self {
+        Self { width, height, ..self }
+    }
+
+    /// # Panics
+    /// Panics if `max_points < 3`.
+    pub fn with_max_points(self, max_points: usize) -> Self {
+        assert!(max_points >= 3, "max_points must be at least 3");
+        Self { max_points: Some(max_points), ..self }
+    }
+
+    /// Draws every point, however long the series.
+    pub fn without_downsampling(self) -> Self {
+        Self { max_points: None, ..self }
+    }
+
+    /// Adds a line on the left y-axis.
+    pub fn line<S: Into<String>>(mut self, name: S, series: &TimestampedSeries<f64>) -> Self {
+        self.layers.push(Layer::Line { name: name.into(), series: series.clone(), secondary: false });
+        self
+    }
+
+    /// Adds a line on a right y-axis with its own scale, e.g. volume or an oscillator
+    /// next to prices.
+    pub fn secondary_line<S: Into<String>>(mut self, name: S, series: &TimestampedSeries<f64>) -> Self {
+        self.layers.push(Layer::Line { name: name.into(), series: series.clone(), secondary: true });
+        self
+    }
+
+    /// Adds every column of `frame` as a line on the left y-axis.
+    pub fn frame(self, frame: &TimeSeriesFrame) -> Self {
+        frame.columns().fold(self, |chart, (name, column)| {
+            let series = TimestampedSeries::from_parts(frame.index().to_vec(), column.clone());
+            chart.line(name, &series)
+        })
+    }
+
+    /// Adds candlesticks on the left y-axis, green when the bar closes at or above its
+    /// open and red otherwise.
+    pub fn candles<S: Into<String>>(mut self, name: S, bars: &TimestampedSeries<Ohlcv>) -> Self {
+        self.layers.push(Layer::Candles { name: name.into(), bars: bars.clone() });
+        self
+    }
+
+    /// Adds a shaded band between the bounds of each interval on the left y-axis, e.g. a
+    /// forecast interval under the forecast's line. Non-finite bounds leave gaps.
+    pub fn band<S: Into<String>>(mut self, name: S, bands: &TimestampedSeries<Interval>) -> Self {
+        self.layers.push(Layer::Band { name: name.into(), bands: bands.clone() });
+        self
+    }
+
+    /// The chart as an SVG document.
+    pub fn to_svg(&self) -> Result<String, Error> {
+        stack_svg(std::slice::from_ref(self))
+    }
+
+    pub fn save_svg<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        std::fs::write(path, self.to_svg()?).map_err(plot_error)
+    }
+
+    /// The layers as drawn, after downsampling.
+    fn downsampled(&self) -> Vec<Layer> {
+        let Some(max) = self.max_points else {
+            return self.layers.clone();
+        };
+        self.layers
+            .iter()
+            .map(|layer| match layer {
+                Layer::Line { name, series, secondary } => {
+                    Layer::Line { name: name.clone(), series: series.lttb(max), secondary: *secondary }
+                }
+                Layer::Candles { name, bars } => Layer::Candles { name: name.clone(), bars: bars.merge_bars(max) },
+                Layer::Band { name, bands } => Layer::Band { name: name.clone(), bands: bands.merge_intervals(max) },
+            })
+            .collect()
+    }
+
+    fn draw(&self, area: &DrawingArea<SVGBackend<'_>, Shift>) -> Result<(), Error> {
+        let layers = self.downsampled();
+        let mut times = Vec::new();
+        let (mut primary, mut secondary) = (Vec::new(), Vec::new());
+        for layer in &layers {
+            match layer {
+                Layer::Line { series, secondary: right, .. } => {
+                    times.extend_from_slice(series.timestamps());
+                    let values = series.values().as_slice().iter().copied();
+                    if *right {
+                        secondary.extend(values)
+                    } else {
+                        primary.extend(values)
+                    }
+                }
+                Layer::Candles { bars, .. } => {
+                    times.extend_from_slice(bars.timestamps());
+                    primary.extend(bars.values().as_slice().iter().flat_map(|b| [b.low, b.high]));
+                }
+                Layer::Band { bands, .. } => {
+                    times.extend_from_slice(bands.timestamps());
+                    primary.extend(bands.values().as_slice().iter().flat_map(|i| [i.lower, i.upper]));
+                }
+            }
+        }
+        let x_range = match (times.iter().min(), times.iter().max()) {
+            (Some(&lo), Some(&hi)) if hi > lo => lo..hi,
+            (Some(&t), _) => t - 1..t + 1,
+            _ => 0..1,
+        };
+        let y_range = value_range(&primary);
+        let has_secondary = !secondary.is_empty();
+
+        let mut builder = ChartBuilder::on(area);
+        builder.caption(&self.title, ("sans-serif", 20)).margin(10).x_label_area_size(30).y_label_area_size(60);
+        if has_secondary {
+            builder.right_y_label_area_size(60);
+        }
+        let mut chart = builder
+            .build_cartesian_2d(x_range.clone(), y_range)
+            .map_err(plot_error)?
+            .set_secondary_coord(x_range, value_range(&secondary));
+        chart.configure_mesh().draw().map_err(plot_error)?;
+        if has_secondary {
+            chart.configure_secondary_axes().draw().map_err(plot_error)?;
+        }
+
+        let candle_width = (self.width as usize * 7 / 10 / times.len().max(1)).clamp(1, 12) as u32;
+        for (i, layer) in layers.iter().enumerate() {
+            let color = Palette99::pick(i).to_rgba();
+            let legend = move |(x, y): (i32, i32)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2));
+            match layer {
+                Layer::Line { name, series, secondary } => {
+                    let points = series.iter().filter(|(_, v)| v.is_finite()).map(|(t, &v)| (t, v));
+                    let line = LineSeries::new(points, color.stroke_width(1));
+                    let anno = if *secondary {
+                        chart.draw_secondary_series(line).map_err(plot_error)?
+                    } else {
+                        chart.draw_series(line).map_err(plot_error)?
+                    };
+                    anno.label(name.as_str()).legend(legend);
+                }
+                Layer::Candles { name, bars } => {
+                    let candles = bars.iter().map(|(t, b)| {
+                        CandleStick::new(t, b.open, b.high, b.low, b.close, GREEN.filled(), RED.filled(), candle_width)
+                    });
+                    chart.draw_series(candles).map_err(plot_error)?.label(name.as_str()).legend(legend);
+                }
+                Layer::Band { name, bands } => {
+                    let fill = color.mix(0.25).filled();
+                    let legend = move |(x, y): (i32, i32)| Rectangle::new([(x, y - 4), (x + 20, y + 4)], fill);
+                    // One polygon per run of finite intervals: the upper bounds forward,
+                    // then the lower bounds back.
+                    let points: Vec<(i64, Interval)> = bands.iter().map(|(t, i)| (t, *i)).collect();
+                    let polygons = points
+                        .split(|(_, i)| !(i.lower.is_finite() && i.upper.is_finite()))
+                        .filter(|run| !run.is_empty())
+                        .map(|run| {
+                            let upper = run.iter().map(|(t, i)| (*t, i.upper));
+                            let lower = run.iter().rev().map(|(t, i)| (*t, i.lower));
+                            Polygon::new(upper.chain(lower).collect::<Vec<_>>(), fill)
+                        });
+                    chart.draw_series(polygons).map_err(plot_error)?.label(name.as_str()).legend(legend);
+                }
+            }
+        }
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()
+            .map_err(plot_error)?;
+        Ok(())
+    }
+}
+
+/// Finite extent of `values` with 5% padding; `0..1` when there is none.
+fn value_range(values: &[f64]) -> Range<f64> {
+    let (lo, hi) = values
+        .iter()
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    if lo > hi {
+        return 0.0..1.0;
+    }
+    let pad = if hi > lo { (hi - lo) * 0.05 } else { 1. };
+    lo - pad..hi + pad
+}
+
+/// `charts` stacked top to bottom in one SVG document, each at its own height, e.g.
+/// prices with overlays above an oscillator.
+pub fn stack_svg(charts: &[Chart]) -> Result<String, Error> {
+    let width = charts.iter().map(|c| c.width).max().unwrap_or(0);
+    let height = charts.iter().map(|c| c.height).sum();
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, (width, height)).into_drawing_area();
+        root.fill(&WHITE).map_err(plot_error)?;
+        let mut rest = root;
+        for chart in charts {
+            let (area, below) = rest.split_vertically(chart.height);
+            chart.draw(&area)?;
+            rest = below;
+        }
+        rest.present().map_err(plot_error)?;
+    }
+    Ok(svg)
+}
@@ -0,0 +1,88 @@
+//! Sample-rate conversion by a rational factor with a polyphase FIR filter.
+
+use crate::TimeSeries;
+use std::f64::consts::PI;
+
+/// Filter taps on each side of the centre, per phase. Longer filters give a sharper
+/// transition band at proportionally higher cost.
+const HALF_TAPS: usize = 10;
+/// Kaiser window shape; 5 gives roughly 55 dB of stopband attenuation.
+const KAISER_BETA: f64 = 5.;
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Modified Bessel function of the first kind, order zero, by its power series.
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.;
+    let mut term = 1.;
+    let mut k = 1.;
+    while term > 1e-12 * sum {
+        term *= (x / (2. * k)).powi(2);
+        sum += term;
+        k += 1.;
+    }
+    sum
+}
+
+/// Kaiser-windowed sinc low-pass with cutoff `1 / (2 factor)` of the upsampled rate and
+/// a passband gain of `up`, so that zero-stuffing does not lose amplitude.
+fn design_filter(up: usize, factor: usize) -> Vec<f64> {
+    let half = HALF_TAPS * factor;
+    let len = 2 * half + 1;
+    let cutoff = 0.5 / factor as f64;
+    (0..len)
+        .map(|i| {
+            let t = i as f64 - half as f64;
+            let sinc = if t == 0. { 2. * cutoff } else { (2. * PI * cutoff * t).sin() / (PI * t) };
+            let r = t / half as f64;
+            let window = bessel_i0(KAISER_BETA * (1. - r * r).max(0.).sqrt()) / bessel_i0(KAISER_BETA);
+            up as f64 * sinc * window
+        })
+        .collect()
+}
+
+pub trait Polyphase {
+    /// Changes the sampling rate by `up / down` (e.g. `147, 160` for 48 kHz to 44.1 kHz):
+    /// conceptually upsample by `up`, low-pass below the lower Nyquist frequency and keep
+    /// every `down`-th point, computing only the outputs that are kept. The result has
+    /// `ceil(len * up / down)` points aligned with the input (no filter delay).
+    fn resample_rate(&self, up: usize, down: usize) -> TimeSeries<f64>;
+}
+
+impl Polyphase for TimeSeries<f64> {
+    fn resample_rate(&self, up: usize, down: usize) -> TimeSeries<f64> {
+        assert!(up > 0 && down > 0, "up and down must be positive");
+        let g = gcd(up, down);
+        let (up, down) = (up / g, down / g);
+        if up == 1 && down == 1 {
+            return self.clone();
+        }
+        let h = design_filter(up, up.max(down));
+        let delay = (h.len() - 1) / 2;
+        let x = self.as_slice();
+        let out_len = (x.len() * up).div_ceil(down);
+        (0..out_len)
+            .map(|m| {
+                // Position in the upsampled signal, shifted to undo the filter delay.
+                let position = m * down + delay;
+                // Only taps landing on non-zero (original) samples contribute.
+                let mut k = position % up;
+                let mut acc = 0.;
+                while k < h.len() && k <= position {
+                    let i = (position - k) / up;
+                    if i < x.len() {
+                        acc += h[k] * x[i];
+                    }
+                    k += up;
+                }
+                acc
+            })
+            .collect()
+    }
+}
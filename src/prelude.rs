@@ -0,0 +1,33 @@
+//! The common types and traits in one import.
+//!
+//! Most of the crate's functionality is provided by extension traits on
+//! [`TimeSeries`] and [`TimestampedSeries`], which must be in scope to call their
+//! methods:
+//! ```rust
+//! use time_series_utils::prelude::*;
+//!
+//! let ts = TimeSeries::from_vec(vec![1., 2., 4.]);
+//! let growth = ts.pct_change(1);
+//! let mean = ts.mean();
+//! ```
+//! Items are only added here, never removed, so a glob import keeps compiling as the
+//! crate grows.
+
+pub use crate::{Error, TimeSeries, TimeSeriesFrame, TimeUnit, TimestampedSeries};
+
+pub use crate::{Rolling, StepSeries};
+
+pub use crate::{
+    Aggregate, Aggregator, AllanVariance, Availability, BurstDetect, CalendarComparison, Circular, Complexity,
+    ControlChart, CrossSectional, Crossover, Decompose, Density, DistributionFitting, Drawdown, Embedding, Esd, Ewm,
+    ExtremeValue, Forecaster, FractionalDiff, Impute, Indicators, Isotonic, PairedComparison, Performance, Polyphase,
+    QuantileRegression, QuoteSeries, Recurrence, Regression, RollingFeatures, Seasonal, SerialDependence, Sink,
+    Spectral, Stabilization, Statistics, StreamOp, TailDependence, TimeWeighted, Variation, Windowed,
+};
+
+#[cfg(feature = "half")]
+pub use crate::HalfPrecision;
+#[cfg(feature = "logs")]
+pub use crate::LogExtraction;
+#[cfg(feature = "random")]
+pub use crate::{Anonymize, Simulate};
@@ -0,0 +1,66 @@
+//! Privacy transforms that produce series safe to share externally: calibrated noise on
+//! values, shifted or coarsened timestamps, and k-anonymous value bucketing.
+
+use crate::random::laplace;
+use crate::{TimeSeries, TimestampedSeries};
+use rand::Rng;
+
+pub trait Anonymize: Sized {
+    /// Adds Laplace noise of scale `sensitivity / epsilon` to every value: the
+    /// epsilon-differentially private release of each point when one individual can
+    /// change a value by at most `sensitivity`. Smaller `epsilon` means more noise.
+    fn jitter<R: Rng + ?Sized>(&self, sensitivity: f64, epsilon: f64, rng: &mut R) -> Self;
+
+    /// Moves every timestamp by `offset`, hiding the absolute time of events. Timestamps
+    /// saturate at the ends of the `i64` range.
+    fn shift_time(&self, offset: i64) -> Self;
+
+    /// Rounds every timestamp down to a multiple of `step`, so events are only known to
+    /// the nearest `step`. Timestamps saturate at `i64::MIN`.
+    fn coarsen_time(&self, step: i64) -> Self;
+
+    /// Replaces each value by the mean of its group, where groups are runs of at least
+    /// `k` points adjacent in value order (the last group absorbs any remainder), so no
+    /// released value describes fewer than `k` points. With fewer than `k` values in all,
+    /// every value is suppressed to NaN. NaN values stay NaN.
+    fn bucket_values(&self, k: usize) -> Self;
+}
+
+impl Anonymize for TimestampedSeries<f64> {
+    fn jitter<R: Rng + ?Sized>(&self, sensitivity: f64, epsilon: f64, rng: &mut R) -> Self {
+        assert!(sensitivity >= 0., "sensitivity must be non-negative");
+        assert!(epsilon > 0., "epsilon must be positive");
+        let scale = sensitivity / epsilon;
+        let noisy: TimeSeries<f64> = self.values().as_slice().iter().map(|v| v + laplace(rng, scale)).collect();
+        TimestampedSeries::from_parts(self.timestamps().to_vec(), noisy)
+    }
+
+    fn shift_time(&self, offset: i64) -> Self {
+        self.iter().map(|(t, &v)| (t.saturating_add(offset), v)).collect()
+    }
+
+    fn coarsen_time(&self, step: i64) -> Self {
+        assert!(step > 0, "step must be positive");
+        self.iter().map(|(t, &v)| (t.saturating_sub(t.rem_euclid(step)), v)).collect()
+    }
+
+    fn bucket_values(&self, k: usize) -> Self {
+        assert!(k > 0, "k must be positive");
+        let values = self.values().as_slice();
+        let mut order: Vec<usize> = (0..values.len()).filter(|&i| !values[i].is_nan()).collect();
+        order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+        let mut bucketed = values.to_vec();
+        if order.len() < k {
+            return self.map(|_| f64::NAN);
+        }
+        let groups = order.len() / k;
+        for g in 0..groups {
+            let members = &order[g * k..if g + 1 == groups { order.len() } else { (g + 1) * k }];
+            let mean = members.iter().map(|&i| values[i]).sum::<f64>() / members.len() as f64;
+            for &i in members {
+                bucketed[i] = mean;
+            }
+        }
+        TimestampedSeries::from_parts(self.timestamps().to_vec(), TimeSeries(bucketed))
+    }
+}
@@ -0,0 +1,148 @@
+//! Protobuf messages mirroring series and frames, with conversions to and from the
+//! crate's types, so gRPC services can take and return series without mapping code.
+//!
+//! The messages are defined in `proto/time_series.proto` (package `time_series.v1`).
+//! The types below are what `prost-build` generates for that file, checked in so that
+//! building the crate does not need `protoc`.
+
+use crate::Error;
+
+/// For `encode_to_vec` and `decode` on the messages.
+pub use prost::Message;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum TimeUnit {
+    Unspecified = 0,
+    Seconds = 1,
+    Millis = 2,
+    Micros = 3,
+    Nanos = 4,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Series {
+    #[prost(double, repeated, tag = "1")]
+    pub values: Vec<f64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TimestampedSeries {
+    #[prost(int64, repeated, tag = "1")]
+    pub timestamps: Vec<i64>,
+    #[prost(double, repeated, tag = "2")]
+    pub values: Vec<f64>,
+    #[prost(enumeration = "TimeUnit", tag = "3")]
+    pub unit: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Column {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(double, repeated, tag = "2")]
+    pub values: Vec<f64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Frame {
+    #[prost(int64, repeated, tag = "1")]
+    pub index: Vec<i64>,
+    #[prost(message, repeated, tag = "2")]
+    pub columns: Vec<Column>,
+}
+
+impl From<Option<crate::TimeUnit>> for TimeUnit {
+    fn from(unit: Option<crate::TimeUnit>) -> Self {
+        match unit {
+            None => TimeUnit::Unspecified,
+            Some(crate::TimeUnit::Seconds) => TimeUnit::Seconds,
+            Some(crate::TimeUnit::Millis) => TimeUnit::Millis,
+            Some(crate::TimeUnit::Micros) => TimeUnit::Micros,
+            Some(crate::TimeUnit::Nanos) => TimeUnit::Nanos,
+        }
+    }
+}
+
+impl From<TimeUnit> for Option<crate::TimeUnit> {
+    fn from(unit: TimeUnit) -> Self {
+        match unit {
+            TimeUnit::Unspecified => None,
+            TimeUnit::Seconds => Some(crate::TimeUnit::Seconds),
+            TimeUnit::Millis => Some(crate::TimeUnit::Millis),
+            TimeUnit::Micros => Some(crate::TimeUnit::Micros),
+            TimeUnit::Nanos => Some(crate::TimeUnit::Nanos),
+        }
+    }
+}
+
+impl From<&crate::TimeSeries<f64>> for Series {
+    fn from(series: &crate::TimeSeries<f64>) -> Self {
+        Self { values: series.as_slice().to_vec() }
+    }
+}
+
+impl From<Series> for crate::TimeSeries<f64> {
+    fn from(message: Series) -> Self {
+        crate::TimeSeries(message.values)
+    }
+}
+
+impl From<&crate::TimestampedSeries<f64>> for TimestampedSeries {
+    fn from(series: &crate::TimestampedSeries<f64>) -> Self {
+        Self {
+            timestamps: series.timestamps().to_vec(),
+            values: series.values().as_slice().to_vec(),
+            unit: TimeUnit::from(series.unit()) as i32,
+        }
+    }
+}
+
+impl TryFrom<TimestampedSeries> for crate::TimestampedSeries<f64> {
+    type Error = Error;
+
+    /// Fails on mismatched lengths, unsorted timestamps or an unknown unit.
+    fn try_from(message: TimestampedSeries) -> Result<Self, Error> {
+        let unit = TimeUnit::try_from(message.unit)
+            .map_err(|_| Error::InvalidInput(format!("unknown time unit {}", message.unit)))?;
+        if message.timestamps.len() != message.values.len() {
+            return Err(Error::LengthMismatch { expected: message.timestamps.len(), found: message.values.len() });
+        }
+        if message.timestamps.windows(2).any(|w| w[0] > w[1]) {
+            return Err(Error::InvalidInput("timestamps are not sorted".to_string()));
+        }
+        let series = crate::TimestampedSeries::from_parts(message.timestamps, crate::TimeSeries(message.values));
+        Ok(match Option::<crate::TimeUnit>::from(unit) {
+            Some(unit) => series.with_unit(unit),
+            None => series,
+        })
+    }
+}
+
+impl From<&crate::TimeSeriesFrame> for Frame {
+    fn from(frame: &crate::TimeSeriesFrame) -> Self {
+        Self {
+            index: frame.index().to_vec(),
+            columns: frame
+                .columns()
+                .map(|(name, column)| Column { name: name.to_string(), values: column.as_slice().to_vec() })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<Frame> for crate::TimeSeriesFrame {
+    type Error = Error;
+
+    /// Fails on a column shorter or longer than the index, or a repeated column name.
+    fn try_from(message: Frame) -> Result<Self, Error> {
+        let mut frame = crate::TimeSeriesFrame::new(message.index);
+        for column in message.columns {
+            if frame.column(&column.name).is_some() {
+                return Err(Error::InvalidInput(format!("column `{}` appears twice", column.name)));
+            }
+            frame.add_column(column.name, crate::TimeSeries(column.values))?;
+        }
+        Ok(frame)
+    }
+}
@@ -0,0 +1,173 @@
+//! Data-quality summaries for triaging feeds: completeness, gaps, duplicate and
+//! out-of-order timestamps, outliers and staleness.
+
+use crate::clock::Clock;
+use crate::lineage::json_string;
+use crate::stats::quantile_sorted;
+use crate::{TimeSeriesFrame, TimestampedSeries};
+use std::fmt::Write as _;
+
+/// Quality summary of one series.
+///
+/// The expected sampling step is taken to be the median positive step between
+/// timestamps; a step of more than twice that counts as a gap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QualityReport {
+    pub points: usize,
+    /// NaN values.
+    pub missing: usize,
+    /// Non-missing points over the number expected between the first and last timestamp
+    /// at the median step, capped at 1. NaN for fewer than two timestamps.
+    pub completeness: f64,
+    /// Median positive step; 0 when there is none.
+    pub median_step: i64,
+    pub gap_count: usize,
+    /// Longest step between consecutive timestamps.
+    pub max_gap: i64,
+    /// Timestamps equal to the previous one.
+    pub duplicates: usize,
+    /// Timestamps earlier than the previous one.
+    pub out_of_order: usize,
+    /// Fraction of non-missing values more than 3.5 robust z-scores (median/MAD) from
+    /// the median.
+    pub outlier_fraction: f64,
+    pub first_timestamp: Option<i64>,
+    pub last_timestamp: Option<i64>,
+}
+
+impl QualityReport {
+    /// Summarizes raw records, which need not be sorted.
+    ///
+    /// # Panics
+    /// Panics if the lengths differ.
+    pub fn from_records(timestamps: &[i64], values: &[f64]) -> Self {
+        assert_eq!(timestamps.len(), values.len(), "timestamps and values must have the same length");
+        let steps: Vec<i64> = timestamps.windows(2).map(|w| w[1] - w[0]).collect();
+        let duplicates = steps.iter().filter(|&&s| s == 0).count();
+        let out_of_order = steps.iter().filter(|&&s| s < 0).count();
+        let mut positive: Vec<i64> = steps.iter().copied().filter(|&s| s > 0).collect();
+        positive.sort_unstable();
+        let median_step = positive.get(positive.len() / 2).copied().unwrap_or(0);
+        let gap_count = if median_step > 0 { positive.iter().filter(|&&s| s > 2 * median_step).count() } else { 0 };
+        let max_gap = positive.last().copied().unwrap_or(0);
+
+        let mut observed: Vec<f64> = values.iter().copied().filter(|v| !v.is_nan()).collect();
+        let missing = values.len() - observed.len();
+        let first = timestamps.iter().min().copied();
+        let last = timestamps.iter().max().copied();
+        let completeness = match (first, last) {
+            (Some(first), Some(last)) if median_step > 0 => {
+                let expected = ((last - first) / median_step + 1) as f64;
+                (observed.len() as f64 / expected).min(1.)
+            }
+            _ => f64::NAN,
+        };
+
+        observed.sort_by(|a, b| a.total_cmp(b));
+        let outlier_fraction = if observed.is_empty() {
+            f64::NAN
+        } else {
+            let median = quantile_sorted(&observed, 0.5);
+            let mut deviations: Vec<f64> = observed.iter().map(|v| (v - median).abs()).collect();
+            deviations.sort_by(|a, b| a.total_cmp(b));
+            let mad = quantile_sorted(&deviations, 0.5);
+            let outliers = if mad > 0. {
+                observed.iter().filter(|v| 0.6745 * (*v - median).abs() / mad > 3.5).count()
+            } else {
+                observed.iter().filter(|&&v| v != median).count()
+            };
+            outliers as f64 / observed.len() as f64
+        };
+
+        Self {
+            points: values.len(),
+            missing,
+            completeness,
+            median_step,
+            gap_count,
+            max_gap,
+            duplicates,
+            out_of_order,
+            outlier_fraction,
+            first_timestamp: first,
+            last_timestamp: last,
+        }
+    }
+
+    /// Time since the last point as of `now`.
+    pub fn staleness(&self, now: i64) -> Option<i64> {
+        self.last_timestamp.map(|last| now - last)
+    }
+
+    /// Time since the last point as of `clock`, which must use the series' time unit.
+    pub fn staleness_by<C: Clock + ?Sized>(&self, clock: &C) -> Option<i64> {
+        self.staleness(clock.now())
+    }
+
+    /// The report as a JSON object, with `staleness` included when `now` is given.
+    /// NaN becomes `null`.
+    pub fn to_json(&self, now: Option<i64>) -> String {
+        let number = |v: f64| if v.is_finite() { v.to_string() } else { "null".to_string() };
+        let optional = |v: Option<i64>| v.map_or("null".to_string(), |v| v.to_string());
+        let mut out = format!(
+            "{{\"points\":{},\"missing\":{},\"completeness\":{},\"median_step\":{},\"gap_count\":{},\
+             \"max_gap\":{},\"duplicates\":{},\"out_of_order\":{},\"outlier_fraction\":{},\
+             \"first_timestamp\":{},\"last_timestamp\":{}",
+            self.points,
+            self.missing,
+            number(self.completeness),
+            self.median_step,
+            self.gap_count,
+            self.max_gap,
+            self.duplicates,
+            self.out_of_order,
+            number(self.outlier_fraction),
+            optional(self.first_timestamp),
+            optional(self.last_timestamp),
+        );
+        if let Some(now) = now {
+            let _ = write!(out, ",\"staleness\":{}", optional(self.staleness(now)));
+        }
+        out.push('}');
+        out
+    }
+}
+
+impl TimestampedSeries<f64> {
+    pub fn quality_report(&self) -> QualityReport {
+        QualityReport::from_records(self.timestamps(), self.values().as_slice())
+    }
+}
+
+/// Quality summaries of each column of a frame, in column order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameQuality {
+    pub columns: Vec<(String, QualityReport)>,
+}
+
+impl FrameQuality {
+    pub fn column(&self, name: &str) -> Option<&QualityReport> {
+        self.columns.iter().find(|(n, _)| n == name).map(|(_, r)| r)
+    }
+
+    /// A JSON object keyed by column name.
+    pub fn to_json(&self, now: Option<i64>) -> String {
+        let entries: Vec<String> = self
+            .columns
+            .iter()
+            .map(|(name, report)| format!("{}:{}", json_string(name), report.to_json(now)))
+            .collect();
+        format!("{{{}}}", entries.join(","))
+    }
+}
+
+impl TimeSeriesFrame {
+    pub fn quality_report(&self) -> FrameQuality {
+        FrameQuality {
+            columns: self
+                .columns()
+                .map(|(name, column)| (name.to_string(), QualityReport::from_records(self.index(), column.as_slice())))
+                .collect(),
+        }
+    }
+}
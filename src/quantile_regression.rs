@@ -0,0 +1,119 @@
+//! Linear quantile regression, and an autoregressive forecaster built on it that predicts
+//! conditional quantiles directly instead of assuming Gaussian errors.
+
+use crate::linalg::least_squares;
+use crate::{Error, Forecaster, TimeSeries};
+
+/// Residuals smaller than this are treated as this size when reweighting.
+const IRLS_EPS: f64 = 1e-6;
+const IRLS_ITERATIONS: usize = 200;
+
+/// Coefficients minimizing the check (pinball) loss at quantile `q` of `y` on the rows of
+/// the design matrix `x`, by iteratively reweighted least squares. Returns `None` when
+/// the design is rank deficient.
+pub fn quantile_regression(x: &[Vec<f64>], y: &[f64], q: f64) -> Option<Vec<f64>> {
+    assert!(q > 0. && q < 1., "quantile must be in (0, 1)");
+    let mut beta = least_squares(x, y)?;
+    for _ in 0..IRLS_ITERATIONS {
+        let (rows, targets): (Vec<Vec<f64>>, Vec<f64>) = x
+            .iter()
+            .zip(y)
+            .map(|(row, &yi)| {
+                let r = yi - row.iter().zip(&beta).map(|(a, b)| a * b).sum::<f64>();
+                let w = if r >= 0. { q } else { 1. - q } / r.abs().max(IRLS_EPS);
+                let s = w.sqrt();
+                (row.iter().map(|v| v * s).collect(), yi * s)
+            })
+            .unzip();
+        let next = least_squares(&rows, &targets)?;
+        let change = next.iter().zip(&beta).map(|(a, b)| (a - b).abs()).fold(0., f64::max);
+        beta = next;
+        if change < 1e-10 {
+            break;
+        }
+    }
+    Some(beta)
+}
+
+/// Result of fitting the `q` quantile of `y` as `intercept + sum(coefficients[k] * x_k)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuantileFit {
+    pub quantile: f64,
+    pub intercept: f64,
+    pub coefficients: Vec<f64>,
+}
+
+pub trait QuantileRegression {
+    /// Regress the `q` quantile of `self` on several series, over their common length.
+    fn quantile_fit(&self, regressors: &[&Self], q: f64) -> Option<QuantileFit>;
+}
+
+impl QuantileRegression for TimeSeries<f64> {
+    fn quantile_fit(&self, regressors: &[&Self], q: f64) -> Option<QuantileFit> {
+        let n = regressors.iter().map(|x| x.len()).fold(self.len(), usize::min);
+        if n <= regressors.len() + 1 {
+            return None;
+        }
+        let design: Vec<Vec<f64>> =
+            (0..n).map(|t| std::iter::once(1.).chain(regressors.iter().map(|x| x[t])).collect()).collect();
+        let beta = quantile_regression(&design, &self.as_slice()[..n], q)?;
+        Some(QuantileFit { quantile: q, intercept: beta[0], coefficients: beta[1..].to_vec() })
+    }
+}
+
+/// Quantile autoregression: each forecast quantile at each horizon `h` is a separate
+/// linear quantile regression of `y_{t+h-1}` on the `order` values before `t` (the direct
+/// strategy), so skewed or heteroscedastic errors are reflected in the intervals.
+#[derive(Clone, Debug)]
+pub struct QuantileAutoregressive {
+    pub order: usize,
+    series: Vec<f64>,
+}
+
+impl QuantileAutoregressive {
+    pub fn new(order: usize) -> Self {
+        assert!(order > 0, "order must be positive");
+        Self { order, series: Vec::new() }
+    }
+
+    fn forecast_quantile(&self, horizon: usize, q: f64) -> f64 {
+        let p = self.order;
+        let y = &self.series;
+        if y.len() < p + horizon {
+            return f64::NAN;
+        }
+        let (design, targets): (Vec<Vec<f64>>, Vec<f64>) = (p..=y.len() - horizon)
+            .map(|t| {
+                let row = std::iter::once(1.).chain((1..=p).map(|lag| y[t - lag])).collect();
+                (row, y[t + horizon - 1])
+            })
+            .unzip();
+        if design.len() <= p + 1 {
+            return f64::NAN;
+        }
+        let Some(beta) = quantile_regression(&design, &targets, q) else { return f64::NAN };
+        let recent = y.iter().rev();
+        beta[0] + beta[1..].iter().zip(recent).map(|(b, x)| b * x).sum::<f64>()
+    }
+}
+
+impl Forecaster for QuantileAutoregressive {
+    fn fit(&mut self, series: &TimeSeries<f64>) -> Result<(), Error> {
+        trace_span!("fit", model = "quantile_autoregressive", order = self.order, n = series.len());
+        let required = 2 * self.order + 2;
+        if series.len() < required {
+            return Err(Error::InsufficientData { required, found: series.len() });
+        }
+        self.series = series.as_slice().to_vec();
+        Ok(())
+    }
+
+    /// The forecast median.
+    fn predict(&self, horizon: usize) -> TimeSeries<f64> {
+        (1..=horizon).map(|h| self.forecast_quantile(h, 0.5)).collect()
+    }
+
+    fn predict_quantiles(&self, horizon: usize, quantiles: &[f64]) -> Vec<TimeSeries<f64>> {
+        quantiles.iter().map(|&q| (1..=horizon).map(|h| self.forecast_quantile(h, q)).collect()).collect()
+    }
+}
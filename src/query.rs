@@ -0,0 +1,313 @@
+//! Filtered reads of chunked series, planned from the chunk summaries.
+//!
+//! A [`Query`] selects the points in a time range whose values pass every value filter,
+//! optionally up to a limit. Before touching any point it classifies each chunk from its
+//! time span and summary: chunks outside the range or whose extremes rule out a filter
+//! are skipped, chunks whose points all match are taken whole, and only the rest are
+//! scanned point by point. For a [`ChunkStore`] the summaries come from the manifest, so
+//! skipped chunks are never read from disk, and downsampled queries take a whole chunk
+//! lying within one bucket from its summary alone.
+//!
+//! NaN values never match, since no filter holds for them.
+
+use crate::{ChunkStore, ChunkSummary, ChunkedSeries, TimeUnit, TimestampedSeries};
+use std::convert::Infallible;
+use std::io;
+
+/// A condition on point values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueFilter {
+    /// `value > x`.
+    Greater(f64),
+    /// `value >= x`.
+    AtLeast(f64),
+    /// `value < x`.
+    Less(f64),
+    /// `value <= x`.
+    AtMost(f64),
+}
+
+impl ValueFilter {
+    fn threshold(self) -> f64 {
+        match self {
+            ValueFilter::Greater(x) | ValueFilter::AtLeast(x) | ValueFilter::Less(x) | ValueFilter::AtMost(x) => x,
+        }
+    }
+
+    fn matches(self, value: f64) -> bool {
+        match self {
+            ValueFilter::Greater(x) => value > x,
+            ValueFilter::AtLeast(x) => value >= x,
+            ValueFilter::Less(x) => value < x,
+            ValueFilter::AtMost(x) => value <= x,
+        }
+    }
+
+    /// Whether some, and whether all, values between `min` and `max` match.
+    fn bounds(self, min: f64, max: f64) -> (bool, bool) {
+        match self {
+            ValueFilter::Greater(x) => (max > x, min > x),
+            ValueFilter::AtLeast(x) => (max >= x, min >= x),
+            ValueFilter::Less(x) => (min < x, max < x),
+            ValueFilter::AtMost(x) => (min <= x, max <= x),
+        }
+    }
+}
+
+/// What a query does with one chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkAction {
+    /// No point can match.
+    Skip,
+    /// Every point matches.
+    Take,
+    /// Some points may match; each is checked.
+    Scan,
+}
+
+/// How a query would treat the chunks of a series, from [`Query::plan`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryPlan {
+    /// The action for each chunk, in time order.
+    pub actions: Vec<ChunkAction>,
+}
+
+impl QueryPlan {
+    pub fn count(&self, action: ChunkAction) -> usize {
+        self.actions.iter().filter(|&&a| a == action).count()
+    }
+}
+
+/// The time span and summary of a chunk, as the planner sees it.
+struct ChunkMeta {
+    first: i64,
+    last: i64,
+    points: usize,
+    summary: ChunkSummary,
+}
+
+/// A time range, value filters and a limit, applied to chunked series.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    start: i64,
+    end: i64,
+    filters: Vec<ValueFilter>,
+    limit: Option<usize>,
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Self { start: i64::MIN, end: i64::MAX, filters: Vec::new(), limit: None }
+    }
+}
+
+impl Query {
+    /// Every point.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the points with `start <= timestamp < end`.
+    pub fn with_range(self, start: i64, end: i64) -> Self {
+        Self { start, end, ..self }
+    }
+
+    /// Keeps the points whose value also passes `filter`.
+    ///
+    /// # Panics
+    /// Panics if the filter's threshold is NaN.
+    pub fn with_filter(mut self, filter: ValueFilter) -> Self {
+        assert!(!filter.threshold().is_nan(), "filter threshold must not be NaN");
+        self.filters.push(filter);
+        self
+    }
+
+    /// Returns at most the first `limit` points, or buckets when downsampling.
+    pub fn with_limit(self, limit: usize) -> Self {
+        Self { limit: Some(limit), ..self }
+    }
+
+    fn matches(&self, t: i64, v: f64) -> bool {
+        (self.start..self.end).contains(&t) && self.filters.iter().all(|f| f.matches(v))
+    }
+
+    fn action(&self, chunk: &ChunkMeta) -> ChunkAction {
+        if chunk.last < self.start || chunk.first >= self.end || chunk.summary.count == 0 {
+            return ChunkAction::Skip;
+        }
+        let mut all = chunk.first >= self.start && chunk.last < self.end && chunk.summary.count == chunk.points;
+        for filter in &self.filters {
+            let (some, every) = filter.bounds(chunk.summary.min, chunk.summary.max);
+            if !some {
+                return ChunkAction::Skip;
+            }
+            all &= every;
+        }
+        if all {
+            ChunkAction::Take
+        } else {
+            ChunkAction::Scan
+        }
+    }
+
+    /// The matching points of the chunks, reading a chunk's points with `read` only when
+    /// they are needed.
+    fn run<E>(
+        &self,
+        chunks: &[ChunkMeta],
+        mut read: impl FnMut(usize) -> Result<Vec<(i64, f64)>, E>,
+    ) -> Result<Vec<(i64, f64)>, E> {
+        let limit = self.limit.unwrap_or(usize::MAX);
+        let mut out = Vec::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            if out.len() >= limit || chunk.first >= self.end {
+                break;
+            }
+            match self.action(chunk) {
+                ChunkAction::Skip => {}
+                ChunkAction::Take => out.extend(read(i)?.into_iter().take(limit - out.len())),
+                ChunkAction::Scan => {
+                    let matching = read(i)?.into_iter().filter(|&(t, v)| self.matches(t, v));
+                    out.extend(matching.take(limit - out.len()));
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// The summaries of the matching points in `step`-long buckets aligned to timestamp
+    /// 0, keyed by bucket start, for the buckets with matching points.
+    fn run_buckets<E>(
+        &self,
+        chunks: &[ChunkMeta],
+        step: i64,
+        mut read: impl FnMut(usize) -> Result<Vec<(i64, f64)>, E>,
+    ) -> Result<Vec<(i64, ChunkSummary)>, E> {
+        assert!(step > 0, "step must be positive");
+        let limit = self.limit.unwrap_or(usize::MAX);
+        let bucket = |t: i64| t.div_euclid(step) * step;
+        let mut out: Vec<(i64, ChunkSummary)> = Vec::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            // The limit is reached once a bucket after the last one kept has started.
+            if out.len() > limit || chunk.first >= self.end {
+                break;
+            }
+            match self.action(chunk) {
+                ChunkAction::Skip => {}
+                ChunkAction::Take if bucket(chunk.first) == bucket(chunk.last) => {
+                    add_to_bucket(&mut out, bucket(chunk.first), &chunk.summary)
+                }
+                _ => {
+                    for (t, v) in read(i)?.into_iter().filter(|&(t, v)| self.matches(t, v)) {
+                        let mut summary = ChunkSummary::empty();
+                        summary.add(v);
+                        add_to_bucket(&mut out, bucket(t), &summary);
+                    }
+                }
+            }
+        }
+        out.truncate(limit);
+        Ok(out)
+    }
+
+    /// How the query would treat each chunk of `series`.
+    pub fn plan(&self, series: &ChunkedSeries) -> QueryPlan {
+        QueryPlan { actions: chunk_metas(series).iter().map(|c| self.action(c)).collect() }
+    }
+
+    /// How the query would treat each chunk of the stored series `name`, from its
+    /// manifest.
+    ///
+    /// Fails if there is no such series or its manifest is malformed.
+    pub fn plan_stored(&self, store: &ChunkStore, name: &str) -> io::Result<QueryPlan> {
+        let (metas, _) = stored_metas(store, name)?;
+        Ok(QueryPlan { actions: metas.iter().map(|c| self.action(c)).collect() })
+    }
+}
+
+/// Merges `summary` into the bucket starting at `start`, the last one or a new one.
+fn add_to_bucket(buckets: &mut Vec<(i64, ChunkSummary)>, start: i64, summary: &ChunkSummary) {
+    match buckets.last_mut().filter(|(b, _)| *b == start) {
+        Some((_, acc)) => acc.merge(summary),
+        None => buckets.push((start, *summary)),
+    }
+}
+
+fn read_points(chunks: &[(&[i64], &[f64])], i: usize) -> Result<Vec<(i64, f64)>, Infallible> {
+    Ok(chunks[i].0.iter().copied().zip(chunks[i].1.iter().copied()).collect())
+}
+
+fn chunk_metas(series: &ChunkedSeries) -> Vec<ChunkMeta> {
+    series
+        .chunk_points()
+        .zip(series.chunk_summaries())
+        .map(|((t, _), summary)| ChunkMeta { first: t[0], last: t[t.len() - 1], points: t.len(), summary: *summary })
+        .collect()
+}
+
+fn stored_metas(store: &ChunkStore, name: &str) -> io::Result<(Vec<ChunkMeta>, crate::Manifest)> {
+    let manifest = store.manifest(name)?;
+    let metas = manifest
+        .chunks
+        .iter()
+        .map(|c| ChunkMeta { first: c.first, last: c.last, points: c.points, summary: c.summary })
+        .collect();
+    Ok((metas, manifest))
+}
+
+fn with_unit<T>(series: TimestampedSeries<T>, unit: Option<TimeUnit>) -> TimestampedSeries<T> {
+    match unit {
+        Some(unit) => series.with_unit(unit),
+        None => series,
+    }
+}
+
+impl ChunkedSeries {
+    /// The points matching `query`.
+    pub fn query(&self, query: &Query) -> TimestampedSeries<f64> {
+        let chunks: Vec<(&[i64], &[f64])> = self.chunk_points().collect();
+        let Ok(points) = query.run(&chunk_metas(self), |i| read_points(&chunks, i));
+        with_unit(points.into_iter().collect(), self.unit())
+    }
+
+    /// The summaries of the points matching `query` in `step`-long buckets aligned to
+    /// timestamp 0, keyed by bucket start; buckets without matching points are left out.
+    ///
+    /// # Panics
+    /// Panics if `step` is not positive.
+    pub fn query_downsampled(&self, query: &Query, step: i64) -> TimestampedSeries<ChunkSummary> {
+        let chunks: Vec<(&[i64], &[f64])> = self.chunk_points().collect();
+        let Ok(buckets) = query.run_buckets(&chunk_metas(self), step, |i| read_points(&chunks, i));
+        with_unit(buckets.into_iter().collect(), self.unit())
+    }
+}
+
+impl ChunkStore {
+    /// The points of the stored series `name` matching `query`, reading only the chunks
+    /// that may hold some.
+    ///
+    /// Fails if there is no such series or a file it needs is missing or corrupt.
+    pub fn query(&self, name: &str, query: &Query) -> io::Result<TimestampedSeries<f64>> {
+        let (metas, manifest) = stored_metas(self, name)?;
+        let points = query.run(&metas, |i| self.read_chunk(manifest.chunks[i].hash))?;
+        Ok(with_unit(points.into_iter().collect(), manifest.unit))
+    }
+
+    /// [`ChunkedSeries::query_downsampled`] over the stored series `name`, reading only
+    /// the chunks that may hold matching points and do not fit in one bucket.
+    ///
+    /// Fails if there is no such series or a file it needs is missing or corrupt.
+    ///
+    /// # Panics
+    /// Panics if `step` is not positive.
+    pub fn query_downsampled(
+        &self,
+        name: &str,
+        query: &Query,
+        step: i64,
+    ) -> io::Result<TimestampedSeries<ChunkSummary>> {
+        let (metas, manifest) = stored_metas(self, name)?;
+        let buckets = query.run_buckets(&metas, step, |i| self.read_chunk(manifest.chunks[i].hash))?;
+        Ok(with_unit(buckets.into_iter().collect(), manifest.unit))
+    }
+}
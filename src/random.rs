@@ -0,0 +1,22 @@
+//! Random variate helpers on top of `rand`.
+
+use rand::Rng;
+
+/// Standard normal draw via the Box–Muller transform.
+pub(crate) fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    let u1: f64 = 1. - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2. * u1.ln()).sqrt() * (2. * std::f64::consts::PI * u2).cos()
+}
+
+/// Laplace draw with location 0 and the given `scale`, by inverting the CDF.
+pub(crate) fn laplace<R: Rng + ?Sized>(rng: &mut R, scale: f64) -> f64 {
+    // u = -0.5 would give ln(0); redraw to sample the open interval (-0.5, 0.5).
+    let u = loop {
+        let u: f64 = rng.gen::<f64>() - 0.5;
+        if u > -0.5 {
+            break u;
+        }
+    };
+    -scale * u.signum() * (1. - 2. * u.abs()).ln()
+}
@@ -0,0 +1,92 @@
+//! Conversion between iterators of user structs and frames, one column per field.
+//!
+//! Implement [`FrameRecord`] by hand or with [`frame_record!`](crate::frame_record):
+//!
+//! ```ignore
+//! struct Reading { ts: i64, temp: f64, pressure: f32 }
+//! frame_record!(Reading { ts; temp, pressure });
+//! let frame = TimeSeriesFrame::from_records(readings);
+//! let readings: Vec<Reading> = frame.to_records()?;
+//! ```
+
+use crate::{Error, TimeSeries, TimeSeriesFrame};
+
+/// A struct that maps to one frame row: a timestamp plus one value per column.
+pub trait FrameRecord: Sized {
+    /// Column names, in the order of [`values`](Self::values).
+    const COLUMNS: &'static [&'static str];
+
+    fn timestamp(&self) -> i64;
+    fn values(&self) -> Vec<f64>;
+    /// Rebuilds a record from a row; `values` follows [`COLUMNS`](Self::COLUMNS).
+    fn from_row(timestamp: i64, values: &[f64]) -> Self;
+}
+
+/// Implements [`FrameRecord`] for a struct with numeric fields:
+/// `frame_record!(Reading { ts; temp, pressure })` names the timestamp field before the
+/// semicolon and the columns after it. Fields are converted with `as`, so integer fields
+/// read back truncated.
+#[macro_export]
+macro_rules! frame_record {
+    ($ty:ident { $ts:ident; $($field:ident),+ $(,)? }) => {
+        impl $crate::FrameRecord for $ty {
+            const COLUMNS: &'static [&'static str] = &[$(stringify!($field)),+];
+
+            fn timestamp(&self) -> i64 {
+                self.$ts as i64
+            }
+
+            fn values(&self) -> Vec<f64> {
+                vec![$(self.$field as f64),+]
+            }
+
+            fn from_row(timestamp: i64, values: &[f64]) -> Self {
+                let mut values = values.iter().copied();
+                Self { $ts: timestamp as _, $($field: values.next().unwrap_or(f64::NAN) as _),+ }
+            }
+        }
+    };
+}
+
+impl TimeSeriesFrame {
+    /// A frame indexed by the record timestamps with one column per record field.
+    pub fn from_records<R, I>(records: I) -> Self
+    where
+        R: FrameRecord,
+        I: IntoIterator<Item = R>,
+    {
+        let mut index = Vec::new();
+        let mut columns = vec![Vec::new(); R::COLUMNS.len()];
+        for record in records {
+            index.push(record.timestamp());
+            for (column, x) in columns.iter_mut().zip(record.values()) {
+                column.push(x);
+            }
+        }
+        let mut frame = Self::new(index);
+        for (name, column) in R::COLUMNS.iter().zip(columns) {
+            frame.add_column(*name, TimeSeries(column)).expect("columns have one value per record");
+        }
+        frame
+    }
+
+    /// One record per row, reading the columns named by `R`; other columns are ignored.
+    pub fn to_records<R: FrameRecord>(&self) -> Result<Vec<R>, Error> {
+        let columns = R::COLUMNS
+            .iter()
+            .map(|name| self.column(name).ok_or_else(|| Error::MissingSeries(name.to_string())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut row = vec![0.; columns.len()];
+        Ok(self
+            .index()
+            .iter()
+            .enumerate()
+            .map(|(i, &t)| {
+                for (x, column) in row.iter_mut().zip(&columns) {
+                    *x = column[i];
+                }
+                R::from_row(t, &row)
+            })
+            .collect())
+    }
+}
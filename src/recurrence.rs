@@ -0,0 +1,130 @@
+//! Recurrence plots and recurrence quantification analysis (RQA).
+//!
+//! The line of identity (`i == j`) is always recurrent and carries no information, so all
+//! measures below leave it out.
+
+use crate::TimeSeries;
+
+/// Square boolean matrix stored as one bit per cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecurrenceMatrix {
+    size: usize,
+    words_per_row: usize,
+    bits: Vec<u64>,
+}
+
+impl RecurrenceMatrix {
+    /// Builds a `size` by `size` matrix from a predicate on index pairs.
+    pub fn from_fn<F>(size: usize, mut recurrent: F) -> Self
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        let words_per_row = size.div_ceil(64);
+        let mut bits = vec![0u64; size * words_per_row];
+        for i in 0..size {
+            for j in 0..size {
+                if recurrent(i, j) {
+                    bits[i * words_per_row + j / 64] |= 1 << (j % 64);
+                }
+            }
+        }
+        Self { size, words_per_row, bits }
+    }
+
+    /// Marks the pairs of points, e.g. delay vectors from
+    /// [`Embedding::embed`](crate::Embedding::embed), within Euclidean distance `threshold`.
+    pub fn from_points(points: &[Vec<f64>], threshold: f64) -> Self {
+        Self::from_fn(points.len(), |i, j| {
+            let d2: f64 = points[i].iter().zip(&points[j]).map(|(a, b)| (a - b).powi(2)).sum();
+            d2.sqrt() <= threshold
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> bool {
+        assert!(i < self.size && j < self.size, "index out of bounds");
+        self.bits[i * self.words_per_row + j / 64] >> (j % 64) & 1 == 1
+    }
+
+    /// Number of recurrent cells off the line of identity.
+    fn recurrent_points(&self) -> usize {
+        let total: usize = self.bits.iter().map(|w| w.count_ones() as usize).sum();
+        total - (0..self.size).filter(|&i| self.get(i, i)).count()
+    }
+
+    /// Fraction of recurrent cells.
+    pub fn recurrence_rate(&self) -> f64 {
+        if self.size < 2 {
+            return f64::NAN;
+        }
+        self.recurrent_points() as f64 / (self.size * (self.size - 1)) as f64
+    }
+
+    /// Fraction of recurrent cells lying on diagonal lines of at least `min_length`
+    /// points, i.e. stretches where the trajectory revisits the same path.
+    pub fn determinism(&self, min_length: usize) -> f64 {
+        let n = self.size;
+        let mut on_lines = 0;
+        for offset in 1..n {
+            // The matrix need not be symmetric, so both diagonals are scanned.
+            let upper = (0..n - offset).map(|i| self.get(i, i + offset));
+            let lower = (0..n - offset).map(|i| self.get(i + offset, i));
+            on_lines += points_on_lines(upper, min_length) + points_on_lines(lower, min_length);
+        }
+        self.line_fraction(on_lines)
+    }
+
+    /// Fraction of recurrent cells lying on vertical lines of at least `min_length`
+    /// points, i.e. stretches where the state barely changes.
+    pub fn laminarity(&self, min_length: usize) -> f64 {
+        let n = self.size;
+        let on_lines: usize = (0..n)
+            .map(|j| points_on_lines((0..n).map(|i| i != j && self.get(i, j)), min_length))
+            .sum();
+        self.line_fraction(on_lines)
+    }
+
+    fn line_fraction(&self, on_lines: usize) -> f64 {
+        let total = self.recurrent_points();
+        if total == 0 {
+            return f64::NAN;
+        }
+        on_lines as f64 / total as f64
+    }
+}
+
+/// Total length of the runs of `true` that are at least `min_length` long.
+fn points_on_lines<I: Iterator<Item = bool>>(cells: I, min_length: usize) -> usize {
+    let mut total = 0;
+    let mut run = 0;
+    for cell in cells.chain(std::iter::once(false)) {
+        if cell {
+            run += 1;
+        } else {
+            if run >= min_length.max(1) {
+                total += run;
+            }
+            run = 0;
+        }
+    }
+    total
+}
+
+pub trait Recurrence {
+    /// Marks the pairs of points closer than `threshold`.
+    fn recurrence_matrix(&self, threshold: f64) -> RecurrenceMatrix;
+}
+
+impl Recurrence for TimeSeries<f64> {
+    fn recurrence_matrix(&self, threshold: f64) -> RecurrenceMatrix {
+        let x = self.as_slice();
+        RecurrenceMatrix::from_fn(x.len(), |i, j| (x[i] - x[j]).abs() <= threshold)
+    }
+}
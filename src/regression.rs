@@ -0,0 +1,170 @@
+//! Ordinary least squares regression of a series on one or several other series, and
+//! isotonic regression.
+
+use crate::linalg::least_squares;
+use crate::{Statistics, TimeSeries};
+
+/// Result of fitting `y = intercept + slope * x`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinearFit {
+    pub intercept: f64,
+    pub slope: f64,
+    pub r_squared: f64,
+}
+
+/// Result of fitting `y = intercept + sum(coefficients[k] * x_k)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultipleFit {
+    pub intercept: f64,
+    pub coefficients: Vec<f64>,
+    pub r_squared: f64,
+}
+
+/// Coefficients of a rolling multiple regression, one series per regressor.
+#[derive(Clone, Debug)]
+pub struct RollingCoefficients {
+    pub intercept: TimeSeries<f64>,
+    pub coefficients: Vec<TimeSeries<f64>>,
+    pub r_squared: TimeSeries<f64>,
+}
+
+pub trait Regression {
+    /// Regress `self` on `x` over their common length. Returns `None` when there are fewer
+    /// than two points or `x` is constant.
+    fn linear_fit(&self, x: &Self) -> Option<LinearFit>;
+    /// Regress each trailing window of `self` on the matching window of `x`.
+    /// The output has `len - window + 1` points; windows that cannot be fitted are NaN.
+    fn rolling_linear_fit(&self, x: &Self, window: usize) -> TimeSeries<LinearFit>;
+    /// Regress `self` on several series at once, over their common length. Returns `None`
+    /// when there are no more points than parameters or the regressors are collinear.
+    fn multiple_fit(&self, regressors: &[&Self]) -> Option<MultipleFit>;
+    /// Multiple regression over each trailing window, with `len - window + 1` points per
+    /// output series; windows that cannot be fitted are NaN.
+    fn rolling_multiple_fit(&self, regressors: &[&Self], window: usize) -> RollingCoefficients;
+}
+
+fn fit_rows(y: &[f64], regressors: &[&TimeSeries<f64>], range: std::ops::Range<usize>) -> Option<MultipleFit> {
+    let p = regressors.len() + 1;
+    if range.len() <= p {
+        return None;
+    }
+    let design: Vec<Vec<f64>> = range
+        .clone()
+        .map(|t| std::iter::once(1.).chain(regressors.iter().map(|x| x[t])).collect())
+        .collect();
+    let y = &y[range];
+    let beta = least_squares(&design, y)?;
+    let mean = y.iter().sum::<f64>() / y.len() as f64;
+    let total: f64 = y.iter().map(|v| (v - mean).powi(2)).sum();
+    let residual: f64 = design
+        .iter()
+        .zip(y)
+        .map(|(row, v)| (v - row.iter().zip(&beta).map(|(a, b)| a * b).sum::<f64>()).powi(2))
+        .sum();
+    Some(MultipleFit { intercept: beta[0], coefficients: beta[1..].to_vec(), r_squared: 1. - residual / total })
+}
+
+impl Regression for TimeSeries<f64> {
+    fn linear_fit(&self, x: &Self) -> Option<LinearFit> {
+        let n = self.len().min(x.len());
+        if n < 2 {
+            return None;
+        }
+        let y = self.slice(0..n);
+        let x = x.slice(0..n);
+        let var_x = x.var();
+        if var_x == 0. {
+            return None;
+        }
+        let slope = y.cov(&x) / var_x;
+        let intercept = y.mean() - slope * x.mean();
+        let r = y.corr(&x);
+        Some(LinearFit { intercept, slope, r_squared: r * r })
+    }
+
+    fn rolling_linear_fit(&self, x: &Self, window: usize) -> TimeSeries<LinearFit> {
+        assert!(window > 0, "window must be positive");
+        let n = self.len().min(x.len());
+        if n < window {
+            return TimeSeries::new();
+        }
+        (window..=n)
+            .map(|end| {
+                self.slice(end - window..end)
+                    .linear_fit(&x.slice(end - window..end))
+                    .unwrap_or(LinearFit {
+                        intercept: f64::NAN,
+                        slope: f64::NAN,
+                        r_squared: f64::NAN,
+                    })
+            })
+            .collect()
+    }
+
+    fn multiple_fit(&self, regressors: &[&Self]) -> Option<MultipleFit> {
+        let n = regressors.iter().map(|x| x.len()).fold(self.len(), usize::min);
+        fit_rows(self.as_slice(), regressors, 0..n)
+    }
+
+    fn rolling_multiple_fit(&self, regressors: &[&Self], window: usize) -> RollingCoefficients {
+        assert!(window > 0, "window must be positive");
+        let n = regressors.iter().map(|x| x.len()).fold(self.len(), usize::min);
+        let mut out = RollingCoefficients {
+            intercept: TimeSeries::new(),
+            coefficients: vec![TimeSeries::new(); regressors.len()],
+            r_squared: TimeSeries::new(),
+        };
+        for end in window..=n {
+            match fit_rows(self.as_slice(), regressors, end - window..end) {
+                Some(fit) => {
+                    out.intercept.push(fit.intercept);
+                    for (series, c) in out.coefficients.iter_mut().zip(fit.coefficients) {
+                        series.push(c);
+                    }
+                    out.r_squared.push(fit.r_squared);
+                }
+                None => {
+                    out.intercept.push(f64::NAN);
+                    for series in out.coefficients.iter_mut() {
+                        series.push(f64::NAN);
+                    }
+                    out.r_squared.push(f64::NAN);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Least-squares fits under an order constraint.
+pub trait Isotonic {
+    /// Closest non-decreasing series in the least-squares sense (pool adjacent violators).
+    fn isotonic_fit(&self) -> TimeSeries<f64>;
+    /// Closest non-increasing series.
+    fn antitonic_fit(&self) -> TimeSeries<f64>;
+}
+
+impl Isotonic for TimeSeries<f64> {
+    fn isotonic_fit(&self) -> TimeSeries<f64> {
+        // Blocks of pooled points as (mean, count); a new point merges backwards while it
+        // would break monotonicity.
+        let mut blocks: Vec<(f64, usize)> = Vec::new();
+        for &y in self.as_slice() {
+            let mut block = (y, 1);
+            while let Some(&(mean, count)) = blocks.last() {
+                if mean <= block.0 {
+                    break;
+                }
+                blocks.pop();
+                let total = count + block.1;
+                block = ((mean * count as f64 + block.0 * block.1 as f64) / total as f64, total);
+            }
+            blocks.push(block);
+        }
+        blocks.into_iter().flat_map(|(mean, count)| std::iter::repeat_n(mean, count)).collect()
+    }
+
+    fn antitonic_fit(&self) -> TimeSeries<f64> {
+        self.map(|x| -x).isotonic_fit().map(|x| -x)
+    }
+}
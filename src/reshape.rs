@@ -0,0 +1,51 @@
+//! Conversion between long records `(timestamp, key, value)` and wide frames with one
+//! column per key.
+
+use crate::{TimeSeries, TimeSeriesFrame};
+use std::collections::{BTreeMap, HashMap};
+
+/// One observation in long format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LongRecord {
+    pub timestamp: i64,
+    pub key: String,
+    pub value: f64,
+}
+
+/// Pivots long records into a frame indexed by the sorted distinct timestamps, with one
+/// column per key in order of first appearance. Missing combinations are NaN; when a
+/// `(timestamp, key)` pair repeats, the last record wins.
+pub fn pivot<I: IntoIterator<Item = LongRecord>>(records: I) -> TimeSeriesFrame {
+    let mut keys: Vec<String> = Vec::new();
+    let mut key_positions: HashMap<String, usize> = HashMap::new();
+    let mut cells: BTreeMap<i64, HashMap<usize, f64>> = BTreeMap::new();
+    for record in records {
+        let column = *key_positions.entry(record.key.clone()).or_insert_with(|| {
+            keys.push(record.key);
+            keys.len() - 1
+        });
+        cells.entry(record.timestamp).or_default().insert(column, record.value);
+    }
+    let mut frame = TimeSeriesFrame::new(cells.keys().copied().collect());
+    for (i, key) in keys.into_iter().enumerate() {
+        let column: TimeSeries<f64> = cells.values().map(|row| row.get(&i).copied().unwrap_or(f64::NAN)).collect();
+        frame.add_column(key, column).expect("column built over the frame index");
+    }
+    frame
+}
+
+impl TimeSeriesFrame {
+    /// Unpivots the frame into long records, row by row, skipping NaN cells so that
+    /// `pivot(frame.melt())` reproduces the frame.
+    pub fn melt(&self) -> Vec<LongRecord> {
+        let mut records = Vec::new();
+        for (row, &timestamp) in self.index().iter().enumerate() {
+            for (key, column) in self.columns() {
+                if !column[row].is_nan() {
+                    records.push(LongRecord { timestamp, key: key.to_string(), value: column[row] });
+                }
+            }
+        }
+        records
+    }
+}
@@ -0,0 +1,107 @@
+//! Bitemporal series: every value is recorded with the time it describes and the time it
+//! became known, so corrections and restatements never overwrite what was known before.
+
+use crate::TimestampedSeries;
+use std::collections::BTreeMap;
+
+/// An append-only log of revisions keyed by valid time.
+///
+/// A revision of point `time` made at `knowledge_time` either sets a value or retracts
+/// the point. Queries ask what the series looked like as of a given knowledge time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RevisionLog<T> {
+    /// Revisions of each point, sorted by knowledge time.
+    points: BTreeMap<i64, Vec<(i64, Option<T>)>>,
+}
+
+impl<T> Default for RevisionLog<T> {
+    fn default() -> Self {
+        Self { points: BTreeMap::new() }
+    }
+}
+
+impl<T> RevisionLog<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(&mut self, time: i64, knowledge_time: i64, value: Option<T>) {
+        let revisions = self.points.entry(time).or_default();
+        // After any revision with the same knowledge time, so the later record wins.
+        let at = revisions.partition_point(|&(k, _)| k <= knowledge_time);
+        revisions.insert(at, (knowledge_time, value));
+    }
+
+    /// Records that the value at `time` was `value` as of `knowledge_time`. Revisions may
+    /// arrive in any order; of two with the same knowledge time the later one wins.
+    pub fn record(&mut self, time: i64, knowledge_time: i64, value: T) {
+        self.insert(time, knowledge_time, Some(value));
+    }
+
+    /// Records that the point at `time` was withdrawn as of `knowledge_time`.
+    pub fn retract(&mut self, time: i64, knowledge_time: i64) {
+        self.insert(time, knowledge_time, None);
+    }
+
+    /// The value at `time` as it was known at `knowledge_time`.
+    pub fn value_as_of(&self, time: i64, knowledge_time: i64) -> Option<&T> {
+        let revisions = self.points.get(&time)?;
+        let known = revisions.partition_point(|&(k, _)| k <= knowledge_time);
+        revisions[..known].last().and_then(|(_, v)| v.as_ref())
+    }
+
+    /// The latest known value at `time`.
+    pub fn latest(&self, time: i64) -> Option<&T> {
+        self.points.get(&time)?.last().and_then(|(_, v)| v.as_ref())
+    }
+
+    /// Every revision of `time` as `(knowledge_time, value)`, oldest first; `None` marks
+    /// a retraction.
+    pub fn revisions(&self, time: i64) -> &[(i64, Option<T>)] {
+        self.points.get(&time).map_or(&[], Vec::as_slice)
+    }
+
+    /// Valid times whose value changed at a knowledge time in `(since, until]`, i.e. what
+    /// a consumer who last synced at `since` has to reload.
+    pub fn revised_between(&self, since: i64, until: i64) -> Vec<i64> {
+        self.points
+            .iter()
+            .filter(|(_, revisions)| revisions.iter().any(|&(k, _)| k > since && k <= until))
+            .map(|(&t, _)| t)
+            .collect()
+    }
+
+    /// Number of distinct valid times with at least one revision.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Total number of revisions recorded.
+    pub fn revision_count(&self) -> usize {
+        self.points.values().map(Vec::len).sum()
+    }
+}
+
+impl<T: Clone> RevisionLog<T> {
+    /// The series as it was known at `knowledge_time`, without retracted points.
+    pub fn snapshot(&self, knowledge_time: i64) -> TimestampedSeries<T> {
+        self.points.keys().filter_map(|&t| self.value_as_of(t, knowledge_time).map(|v| (t, v.clone()))).collect()
+    }
+
+    /// The series with every revision applied.
+    pub fn current(&self) -> TimestampedSeries<T> {
+        self.points.iter().filter_map(|(&t, revisions)| revisions.last()?.1.clone().map(|v| (t, v))).collect()
+    }
+
+    /// Records every point of `series` as known at `knowledge_time`, e.g. one vintage of
+    /// a restated dataset.
+    pub fn record_series(&mut self, series: &TimestampedSeries<T>, knowledge_time: i64) {
+        for (t, v) in series.iter() {
+            self.record(t, knowledge_time, v.clone());
+        }
+    }
+}
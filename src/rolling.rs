@@ -0,0 +1,310 @@
+//! Statistics over trailing fixed-size windows.
+//!
+//! Outputs have one value per full window, `len - window + 1` in all, aligned so the
+//! last value describes the window ending at the last point.
+
+use crate::stats::quantile_sorted;
+use crate::{TimeSeries, TimeSeriesFrame};
+use std::collections::VecDeque;
+
+/// A per-window feature computed by [`RollingFeatures::rolling_features`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FeatureKind {
+    Mean,
+    /// Sample standard deviation.
+    Std,
+    Min,
+    Max,
+    Median,
+    /// OLS slope of the values against their position in the window, per step.
+    Slope,
+    /// Lag-1 autocorrelation about the window mean.
+    Autocorrelation,
+    /// Sample skewness (biased, moment-based).
+    Skewness,
+    /// Last value of the window minus the first.
+    Change,
+}
+
+impl FeatureKind {
+    /// Column name used in the feature frame.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FeatureKind::Mean => "mean",
+            FeatureKind::Std => "std",
+            FeatureKind::Min => "min",
+            FeatureKind::Max => "max",
+            FeatureKind::Median => "median",
+            FeatureKind::Slope => "slope",
+            FeatureKind::Autocorrelation => "autocorr_1",
+            FeatureKind::Skewness => "skewness",
+            FeatureKind::Change => "change",
+        }
+    }
+}
+
+/// A statistic computed by [`Rolling::agg`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Agg {
+    Sum,
+    Mean,
+    /// Sample variance.
+    Var,
+    /// Sample standard deviation.
+    Std,
+    Min,
+    Max,
+}
+
+/// Trailing windows of a series, from [`Windowed::rolling`].
+#[derive(Clone, Copy, Debug)]
+pub struct Rolling<'a> {
+    series: &'a TimeSeries<f64>,
+    window: usize,
+}
+
+/// Candidates for the window extreme as `(position, value)`, kept monotonic so the front
+/// is the extreme itself.
+#[derive(Clone, Debug, PartialEq)]
+struct ExtremeQueue {
+    entries: VecDeque<(usize, f64)>,
+    /// Track the maximum rather than the minimum.
+    max: bool,
+}
+
+impl ExtremeQueue {
+    fn new(max: bool) -> Self {
+        Self { entries: VecDeque::new(), max }
+    }
+
+    fn push(&mut self, i: usize, v: f64) {
+        let dominates = |old: f64| if self.max { v >= old } else { v <= old };
+        while self.entries.back().is_some_and(|&(_, old)| dominates(old)) {
+            self.entries.pop_back();
+        }
+        self.entries.push_back((i, v));
+    }
+
+    fn front(&mut self, start: usize) -> f64 {
+        while self.entries.front().is_some_and(|&(j, _)| j < start) {
+            self.entries.pop_front();
+        }
+        self.entries.front().map_or(f64::NAN, |&(_, v)| v)
+    }
+}
+
+/// Running state of [`Rolling::agg`]: the last `window` points and the running sums and
+/// extremes over them. Feeding it new points with [`update_with`](Self::update_with)
+/// extends earlier outputs in O(new points), exactly as recomputing over the whole series.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RollingState {
+    window: usize,
+    aggs: Vec<Agg>,
+    /// The last `window` points.
+    values: VecDeque<f64>,
+    seen: usize,
+    /// First non-NaN value, subtracted in the running sums to limit cancellation.
+    shift: Option<f64>,
+    s1: f64,
+    s2: f64,
+    nans: usize,
+    /// Points since the running sums were last recomputed from the window.
+    since_refresh: usize,
+    min_queue: ExtremeQueue,
+    max_queue: ExtremeQueue,
+}
+
+impl RollingState {
+    pub fn new(window: usize, aggs: &[Agg]) -> Self {
+        assert!(window > 0, "window must be positive");
+        Self {
+            window,
+            aggs: aggs.to_vec(),
+            values: VecDeque::new(),
+            seen: 0,
+            shift: None,
+            s1: 0.,
+            s2: 0.,
+            nans: 0,
+            since_refresh: 0,
+            min_queue: ExtremeQueue::new(false),
+            max_queue: ExtremeQueue::new(true),
+        }
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    pub fn aggs(&self) -> &[Agg] {
+        &self.aggs
+    }
+
+    /// Number of points fed so far.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    /// Appends the outputs for `new_points` to `outputs`, one series per aggregate.
+    ///
+    /// # Panics
+    /// Panics if `outputs` does not hold one series per aggregate.
+    pub fn update_with(&mut self, outputs: &mut [TimeSeries<f64>], new_points: &[f64]) {
+        assert_eq!(outputs.len(), self.aggs.len(), "one output series per aggregate expected");
+        let window = self.window;
+        let w = window as f64;
+        for &v in new_points {
+            let i = self.seen;
+            self.seen += 1;
+            if v.is_nan() {
+                self.nans += 1;
+            } else {
+                let shift = *self.shift.get_or_insert(v);
+                self.s1 += v - shift;
+                self.s2 += (v - shift).powi(2);
+                self.min_queue.push(i, v);
+                self.max_queue.push(i, v);
+            }
+            self.values.push_back(v);
+            if self.values.len() > window {
+                let old = self.values.pop_front().unwrap_or(f64::NAN);
+                if old.is_nan() {
+                    self.nans -= 1;
+                } else {
+                    let shift = self.shift.unwrap_or(0.);
+                    self.s1 -= old - shift;
+                    self.s2 -= (old - shift).powi(2);
+                }
+            }
+            // Recompute once per window length so rounding errors in the running sums
+            // cannot accumulate without bound.
+            self.since_refresh += 1;
+            if self.since_refresh >= window {
+                let shift = self.shift.unwrap_or(0.);
+                let deviations = self.values.iter().filter(|v| !v.is_nan()).map(|v| v - shift);
+                (self.s1, self.s2) = deviations.fold((0., 0.), |(s1, s2), d| (s1 + d, s2 + d * d));
+                self.since_refresh = 0;
+            }
+            if self.values.len() < window {
+                continue;
+            }
+            let start = i + 1 - window;
+            let shift = self.shift.unwrap_or(0.);
+            let (s1, s2) = (self.s1, self.s2);
+            let var = if window > 1 { ((s2 - s1 * s1 / w) / (w - 1.)).max(0.) } else { f64::NAN };
+            for (column, agg) in outputs.iter_mut().zip(&self.aggs) {
+                column.push(if self.nans > 0 {
+                    f64::NAN
+                } else {
+                    match agg {
+                        Agg::Sum => s1 + shift * w,
+                        Agg::Mean => s1 / w + shift,
+                        Agg::Var => var,
+                        Agg::Std => var.sqrt(),
+                        Agg::Min => self.min_queue.front(start),
+                        Agg::Max => self.max_queue.front(start),
+                    }
+                });
+            }
+        }
+    }
+}
+
+impl Rolling<'_> {
+    /// Computes all of `aggs` over each window in one pass over the data, returning one
+    /// series per aggregate in the same order. Running sums are shifted by the first value
+    /// to limit cancellation in the variance. Windows containing NaN are NaN.
+    pub fn agg(&self, aggs: &[Agg]) -> Vec<TimeSeries<f64>> {
+        self.agg_with_state(aggs).0
+    }
+
+    /// [`agg`](Self::agg), also returning the state to extend the outputs with later
+    /// points.
+    pub fn agg_with_state(&self, aggs: &[Agg]) -> (Vec<TimeSeries<f64>>, RollingState) {
+        let rows = (self.series.len() + 1).saturating_sub(self.window);
+        let mut outputs = vec![TimeSeries(Vec::with_capacity(rows)); aggs.len()];
+        let mut state = RollingState::new(self.window, aggs);
+        state.update_with(&mut outputs, self.series.as_slice());
+        (outputs, state)
+    }
+}
+
+pub trait Windowed {
+    /// Trailing windows of `window` points, to aggregate with [`Rolling::agg`].
+    fn rolling(&self, window: usize) -> Rolling<'_>;
+}
+
+impl Windowed for TimeSeries<f64> {
+    fn rolling(&self, window: usize) -> Rolling<'_> {
+        assert!(window > 0, "window must be positive");
+        Rolling { series: self, window }
+    }
+}
+
+pub trait RollingFeatures {
+    /// Computes every feature in `features` over each trailing window in a single pass,
+    /// one frame column per feature named by [`FeatureKind::name`]. The frame index holds
+    /// the position of the last point of each window, so rows line up with the source.
+    /// Windows containing NaN are NaN in every column.
+    fn rolling_features(&self, window: usize, features: &[FeatureKind]) -> TimeSeriesFrame;
+}
+
+impl RollingFeatures for TimeSeries<f64> {
+    fn rolling_features(&self, window: usize, features: &[FeatureKind]) -> TimeSeriesFrame {
+        assert!(window > 0, "window must be positive");
+        let rows = (self.len() + 1).saturating_sub(window);
+        let mut columns = vec![Vec::with_capacity(rows); features.len()];
+        let needs_sorted = features.contains(&FeatureKind::Median);
+        let w = window as f64;
+        let x_mean = (w - 1.) / 2.;
+        let sxx = w * (w * w - 1.) / 12.;
+        let mut sorted = Vec::with_capacity(window.min(self.len()));
+        for values in self.as_slice().windows(window) {
+            let mean = values.iter().sum::<f64>() / w;
+            let (mut m2, mut m3, mut sxy, mut lag) = (0., 0., 0., 0.);
+            let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+            for (i, &v) in values.iter().enumerate() {
+                let d = v - mean;
+                m2 += d * d;
+                m3 += d * d * d;
+                sxy += (i as f64 - x_mean) * d;
+                if i > 0 {
+                    lag += d * (values[i - 1] - mean);
+                }
+                min = min.min(v);
+                max = max.max(v);
+            }
+            if values.iter().any(|v| v.is_nan()) {
+                columns.iter_mut().for_each(|column| column.push(f64::NAN));
+                continue;
+            }
+            if needs_sorted {
+                sorted.clear();
+                sorted.extend_from_slice(values);
+                sorted.sort_by(|a, b| a.total_cmp(b));
+            }
+            for (column, kind) in columns.iter_mut().zip(features) {
+                column.push(match kind {
+                    FeatureKind::Mean => mean,
+                    FeatureKind::Std if window > 1 => (m2 / (w - 1.)).sqrt(),
+                    FeatureKind::Std => f64::NAN,
+                    FeatureKind::Min => min,
+                    FeatureKind::Max => max,
+                    FeatureKind::Median => quantile_sorted(&sorted, 0.5),
+                    FeatureKind::Slope if window > 1 => sxy / sxx,
+                    FeatureKind::Slope => f64::NAN,
+                    FeatureKind::Autocorrelation => lag / m2,
+                    FeatureKind::Skewness => (m3 / w) / (m2 / w).powf(1.5),
+                    FeatureKind::Change => values[window - 1] - values[0],
+                });
+            }
+        }
+        let mut frame = TimeSeriesFrame::new((window as i64 - 1..self.len() as i64).collect());
+        for (column, kind) in columns.into_iter().zip(features) {
+            frame
+                .add_column(kind.name(), TimeSeries(column))
+                .expect("feature columns have one value per window");
+        }
+        frame
+    }
+}
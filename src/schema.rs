@@ -0,0 +1,282 @@
+//! Validation of ingested records against an expected schema: value type, allowed range,
+//! missing values, timestamp order and maximum gap.
+//!
+//! Violations are collected into a [`ValidationReport`]. A schema either fails the load
+//! on the first violation or quarantines offending records and keeps the rest.
+
+use crate::{Error, TimestampParser, TimestampedSeries};
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+
+/// How raw values are parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueType {
+    Float,
+    /// Whole numbers only.
+    Integer,
+    /// `true`/`false` or `1`/`0`, stored as 1.0 and 0.0.
+    Boolean,
+}
+
+/// Required order of consecutive timestamps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimestampOrder {
+    /// Repeated timestamps are allowed.
+    NonDecreasing,
+    /// Every timestamp is later than the previous one.
+    Increasing,
+}
+
+/// What to do with a record that violates the schema.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnViolation {
+    /// Fail the whole load with the first violation.
+    Fail,
+    /// Set the record aside in the report and continue.
+    Quarantine,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ViolationKind {
+    /// The record does not have a timestamp and a value.
+    Malformed,
+    /// The value does not parse as the expected type.
+    WrongType(String),
+    /// NaN where missing values are not allowed.
+    Missing,
+    OutOfRange(f64),
+    /// The timestamp is earlier than the previous accepted one.
+    OutOfOrder { previous: i64 },
+    /// The timestamp repeats the previous one under [`TimestampOrder::Increasing`].
+    Duplicate,
+    /// The distance to the previous accepted timestamp exceeds the maximum gap. The
+    /// record itself is kept.
+    Gap { gap: i64 },
+}
+
+/// A violation found at a record: `line` is the 1-based CSV line, or the 1-based
+/// position when validating a series.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    pub line: usize,
+    pub timestamp: Option<i64>,
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ViolationKind::Malformed => write!(f, "expected `timestamp,value`"),
+            ViolationKind::WrongType(raw) => write!(f, "value `{}` has the wrong type", raw),
+            ViolationKind::Missing => write!(f, "missing value"),
+            ViolationKind::OutOfRange(v) => write!(f, "value {} out of range", v),
+            ViolationKind::OutOfOrder { previous } => {
+                write!(f, "timestamp {} before {}", self.timestamp.unwrap_or_default(), previous)
+            }
+            ViolationKind::Duplicate => write!(f, "duplicate timestamp {}", self.timestamp.unwrap_or_default()),
+            ViolationKind::Gap { gap } => write!(f, "gap of {} before timestamp {}", gap, self.timestamp.unwrap_or_default()),
+        }
+    }
+}
+
+/// Result of a load: the accepted points plus everything that was wrong.
+#[derive(Clone, Debug, Default)]
+pub struct ValidationReport {
+    pub series: TimestampedSeries<f64>,
+    pub violations: Vec<Violation>,
+    /// Quarantined records as `(line, raw record)`.
+    pub quarantined: Vec<(usize, String)>,
+}
+
+impl ValidationReport {
+    /// Whether no violation was found.
+    pub fn is_clean(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Expected shape of ingested `timestamp,value` records.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schema {
+    pub value_type: ValueType,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub allow_missing: bool,
+    pub max_gap: Option<i64>,
+    pub order: TimestampOrder,
+    pub on_violation: OnViolation,
+    /// How CSV timestamps are parsed; plain integers when `None`.
+    pub timestamps: Option<TimestampParser>,
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Self {
+            value_type: ValueType::Float,
+            min: None,
+            max: None,
+            allow_missing: false,
+            max_gap: None,
+            order: TimestampOrder::Increasing,
+            on_violation: OnViolation::Fail,
+            timestamps: None,
+        }
+    }
+}
+
+impl Schema {
+    /// Float values, no range limits, no NaN, strictly increasing timestamps, failing on
+    /// the first violation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_type(self, value_type: ValueType) -> Self {
+        Self { value_type, ..self }
+    }
+
+    /// Allowed values, inclusive.
+    pub fn with_range(self, min: f64, max: f64) -> Self {
+        assert!(min <= max, "min must not exceed max");
+        Self { min: Some(min), max: Some(max), ..self }
+    }
+
+    /// Accepts NaN (and empty values in CSV) as missing.
+    pub fn allow_missing(self) -> Self {
+        Self { allow_missing: true, ..self }
+    }
+
+    pub fn with_max_gap(self, max_gap: i64) -> Self {
+        assert!(max_gap > 0, "max gap must be positive");
+        Self { max_gap: Some(max_gap), ..self }
+    }
+
+    pub fn with_order(self, order: TimestampOrder) -> Self {
+        Self { order, ..self }
+    }
+
+    /// Parses CSV timestamps with `parser`; unparseable ones are malformed records.
+    pub fn with_timestamps(self, parser: TimestampParser) -> Self {
+        Self { timestamps: Some(parser), ..self }
+    }
+
+    /// Quarantines bad records instead of failing.
+    pub fn quarantine(self) -> Self {
+        Self { on_violation: OnViolation::Quarantine, ..self }
+    }
+
+    fn parse_timestamp(&self, raw: &str) -> Option<i64> {
+        match &self.timestamps {
+            Some(parser) => parser.parse(raw).ok(),
+            None => raw.parse().ok(),
+        }
+    }
+
+    fn parse_value(&self, raw: &str) -> Result<f64, ViolationKind> {
+        let wrong = || ViolationKind::WrongType(raw.to_string());
+        if raw.is_empty() || raw.eq_ignore_ascii_case("nan") {
+            return Ok(f64::NAN);
+        }
+        match self.value_type {
+            ValueType::Float => raw.parse().map_err(|_| wrong()),
+            ValueType::Integer => raw.parse::<i64>().map(|v| v as f64).map_err(|_| wrong()),
+            ValueType::Boolean => match raw {
+                "true" | "1" => Ok(1.),
+                "false" | "0" => Ok(0.),
+                _ => Err(wrong()),
+            },
+        }
+    }
+
+    /// Checks a parsed point against the previous accepted timestamp. The first error
+    /// rejects the point; a gap is returned separately since the point is still kept.
+    fn check(&self, t: i64, v: f64, previous: Option<i64>) -> (Option<ViolationKind>, Option<ViolationKind>) {
+        if v.is_nan() {
+            if !self.allow_missing {
+                return (Some(ViolationKind::Missing), None);
+            }
+        } else if self.min.is_some_and(|min| v < min) || self.max.is_some_and(|max| v > max) {
+            return (Some(ViolationKind::OutOfRange(v)), None);
+        }
+        let Some(previous) = previous else { return (None, None) };
+        if t < previous {
+            return (Some(ViolationKind::OutOfOrder { previous }), None);
+        }
+        if t == previous && self.order == TimestampOrder::Increasing {
+            return (Some(ViolationKind::Duplicate), None);
+        }
+        let gap = self.max_gap.filter(|&max| t - previous > max).map(|_| ViolationKind::Gap { gap: t - previous });
+        (None, gap)
+    }
+
+    /// Adds one record to `report`, or fails under [`OnViolation::Fail`].
+    fn ingest(
+        &self,
+        report: &mut ValidationReport,
+        line: usize,
+        raw: &str,
+        parsed: Result<(i64, f64), ViolationKind>,
+    ) -> Result<(), Error> {
+        let previous = report.series.timestamps().last().copied();
+        let (rejected, gap, timestamp) = match &parsed {
+            &Ok((t, v)) => {
+                let (rejected, gap) = self.check(t, v, previous);
+                (rejected, gap, Some(t))
+            }
+            Err(kind) => (Some(kind.clone()), None, None),
+        };
+        for kind in rejected.iter().chain(&gap) {
+            let violation = Violation { line, timestamp, kind: kind.clone() };
+            if self.on_violation == OnViolation::Fail {
+                return Err(Error::InvalidInput(violation.to_string()));
+            }
+            report.violations.push(violation);
+        }
+        match (rejected, parsed) {
+            (None, Ok((t, v))) => report.series.push(t, v),
+            _ => report.quarantined.push((line, raw.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Loads `timestamp,value` CSV, skipping blank lines and a leading header.
+    pub fn ingest_csv<R: Read>(&self, reader: R) -> Result<ValidationReport, Error> {
+        let mut report = ValidationReport::default();
+        for (n, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line.map_err(|e| Error::InvalidInput(e.to_string()))?;
+            let record = line.trim();
+            if record.is_empty() || (n == 0 && record.starts_with("timestamp")) {
+                continue;
+            }
+            let parsed = match record.split_once(',') {
+                Some((t, v)) => match self.parse_timestamp(t.trim()) {
+                    Some(t) => self.parse_value(v.trim()).map(|v| (t, v)),
+                    None => Err(ViolationKind::Malformed),
+                },
+                None => Err(ViolationKind::Malformed),
+            };
+            self.ingest(&mut report, n + 1, record, parsed)?;
+        }
+        if let Some(parser) = &self.timestamps {
+            report.series = report.series.with_unit(parser.unit);
+        }
+        Ok(report)
+    }
+
+    /// Checks an already parsed series. Integer and boolean types require whole and
+    /// 0/1 values respectively.
+    pub fn validate(&self, series: &TimestampedSeries<f64>) -> Result<ValidationReport, Error> {
+        let mut report = ValidationReport::default();
+        for (i, (t, &v)) in series.iter().enumerate() {
+            let typed = match self.value_type {
+                ValueType::Float => true,
+                ValueType::Integer => v.is_nan() || v.fract() == 0.,
+                ValueType::Boolean => v.is_nan() || v == 0. || v == 1.,
+            };
+            let parsed = if typed { Ok((t, v)) } else { Err(ViolationKind::WrongType(v.to_string())) };
+            self.ingest(&mut report, i + 1, &format!("{},{}", t, v), parsed)?;
+        }
+        Ok(report)
+    }
+}
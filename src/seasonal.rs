@@ -0,0 +1,127 @@
+//! Additive seasonal adjustment by regression on the seasonal phase.
+//!
+//! The phase of a point is its position modulo the period, so the series is assumed to
+//! start at the beginning of a cycle.
+
+use crate::linalg::least_squares;
+use crate::TimeSeries;
+use std::f64::consts::PI;
+
+#[derive(Clone, Debug)]
+pub struct SeasonalAdjustment {
+    /// The series with the seasonal component removed.
+    pub adjusted: TimeSeries<f64>,
+    /// Estimated seasonal effect of each phase, summing to zero over a cycle.
+    pub factors: TimeSeries<f64>,
+}
+
+/// A series reshaped into one row per cycle and one column per phase. Phases missing from
+/// an incomplete last cycle are NaN.
+#[derive(Clone, Debug)]
+pub struct SeasonalMatrix {
+    pub values: Vec<Vec<f64>>,
+    /// Cycle number of each row.
+    pub row_labels: Vec<usize>,
+    /// Phase of each column.
+    pub col_labels: Vec<usize>,
+}
+
+impl SeasonalMatrix {
+    /// Observed values of one phase across cycles.
+    pub fn phase(&self, phase: usize) -> TimeSeries<f64> {
+        self.values.iter().map(|row| row[phase]).filter(|x| !x.is_nan()).collect()
+    }
+
+    /// Mean of each phase across cycles.
+    pub fn phase_means(&self) -> TimeSeries<f64> {
+        self.col_labels
+            .iter()
+            .map(|&p| {
+                let values = self.phase(p);
+                values.as_slice().iter().sum::<f64>() / values.len() as f64
+            })
+            .collect()
+    }
+}
+
+pub trait Seasonal {
+    /// Removes the mean of each phase (regression on seasonal dummies).
+    fn seasonally_adjust(&self, period: usize) -> SeasonalAdjustment;
+    /// Removes a seasonal component fitted with `harmonics` Fourier terms, which stays smooth
+    /// for long periods where per-phase means are noisy. `harmonics` is capped at
+    /// `(p - 1) / 2` for `p` the number of phases observed (the period, unless the series
+    /// is shorter), beyond which the terms are no longer independent.
+    fn seasonally_adjust_fourier(&self, period: usize, harmonics: usize) -> SeasonalAdjustment;
+    /// Reshapes the series into cycles × phases, e.g. weeks × day-of-week, for heatmaps and
+    /// phase-wise statistics.
+    fn to_seasonal_matrix(&self, period: usize) -> SeasonalMatrix;
+}
+
+impl Seasonal for TimeSeries<f64> {
+    fn seasonally_adjust(&self, period: usize) -> SeasonalAdjustment {
+        assert!(period > 0, "period must be positive");
+        let mut sums = vec![0.; period];
+        let mut counts = vec![0usize; period];
+        for (i, x) in self.as_slice().iter().enumerate() {
+            sums[i % period] += x;
+            counts[i % period] += 1;
+        }
+        let means: Vec<f64> = sums
+            .iter()
+            .zip(&counts)
+            .map(|(s, &c)| if c == 0 { 0. } else { s / c as f64 })
+            .collect();
+        let observed = counts.iter().filter(|&&c| c > 0).count().max(1) as f64;
+        let level = means.iter().sum::<f64>() / observed;
+        let factors = means
+            .iter()
+            .zip(&counts)
+            .map(|(m, &c)| if c == 0 { 0. } else { m - level })
+            .collect();
+        adjust(self, TimeSeries(factors))
+    }
+
+    fn seasonally_adjust_fourier(&self, period: usize, harmonics: usize) -> SeasonalAdjustment {
+        assert!(period > 0, "period must be positive");
+        // The sine of the harmonic `p / 2` is zero at every phase, and more than `(p - 1) / 2`
+        // harmonics need more coefficients than there are phases: either makes the fit singular.
+        let harmonics = harmonics.min((period.min(self.len()).max(1) - 1) / 2);
+        let terms = |phase: usize| -> Vec<f64> {
+            let mut row = vec![1.];
+            for k in 1..=harmonics {
+                let angle = 2. * PI * (k * phase) as f64 / period as f64;
+                row.push(angle.sin());
+                row.push(angle.cos());
+            }
+            row
+        };
+        let design: Vec<Vec<f64>> = (0..self.len()).map(|i| terms(i % period)).collect();
+        let factors = match least_squares(&design, self.as_slice()) {
+            Some(beta) => (0..period)
+                .map(|phase| terms(phase).iter().zip(&beta).skip(1).map(|(x, b)| x * b).sum())
+                .collect(),
+            None => vec![0.; period],
+        };
+        adjust(self, TimeSeries(factors))
+    }
+
+    fn to_seasonal_matrix(&self, period: usize) -> SeasonalMatrix {
+        assert!(period > 0, "period must be positive");
+        let values: Vec<Vec<f64>> = self
+            .as_slice()
+            .chunks(period)
+            .map(|cycle| {
+                let mut row = cycle.to_vec();
+                row.resize(period, f64::NAN);
+                row
+            })
+            .collect();
+        SeasonalMatrix { row_labels: (0..values.len()).collect(), col_labels: (0..period).collect(), values }
+    }
+}
+
+fn adjust(series: &TimeSeries<f64>, factors: TimeSeries<f64>) -> SeasonalAdjustment {
+    let period = factors.len();
+    let adjusted = series.as_slice().iter().enumerate().map(|(i, x)| x - factors[i % period]).collect();
+    SeasonalAdjustment { adjusted: TimeSeries(adjusted), factors }
+}
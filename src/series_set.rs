@@ -0,0 +1,185 @@
+//! A named collection of timestamped series with batch operations.
+
+use crate::io::{decode_file_name, encode_file_name};
+#[cfg(feature = "encryption")]
+use crate::Encryption;
+use crate::TimestampedSeries;
+use std::collections::btree_map::{self, BTreeMap};
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Timestamped series keyed by name (e.g. one per symbol), iterated in name order.
+#[derive(Clone, Debug)]
+pub struct SeriesSet<T> {
+    series: BTreeMap<String, TimestampedSeries<T>>,
+}
+
+impl<T> Default for SeriesSet<T> {
+    fn default() -> Self {
+        Self { series: BTreeMap::new() }
+    }
+}
+
+impl<T> SeriesSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a series, returning the one previously stored under `name`.
+    pub fn insert<S: Into<String>>(&mut self, name: S, series: TimestampedSeries<T>) -> Option<TimestampedSeries<T>> {
+        self.series.insert(name.into(), series)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TimestampedSeries<T>> {
+        self.series.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut TimestampedSeries<T>> {
+        self.series.get_mut(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<TimestampedSeries<T>> {
+        self.series.remove(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.series.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.series.keys().map(String::as_str)
+    }
+
+    pub fn iter(&self) -> btree_map::Iter<'_, String, TimestampedSeries<T>> {
+        self.series.iter()
+    }
+
+    /// Applies `f` to every series, keeping the names.
+    pub fn apply_all<U, F>(&self, f: F) -> SeriesSet<U>
+    where
+        F: Fn(&TimestampedSeries<T>) -> TimestampedSeries<U>,
+    {
+        SeriesSet { series: self.series.iter().map(|(k, v)| (k.clone(), f(v))).collect() }
+    }
+
+    /// Restricts every series to the timestamps present in all of them.
+    pub fn align(&self) -> SeriesSet<T>
+    where
+        T: Clone,
+    {
+        let mut common: Option<BTreeSet<i64>> = None;
+        for s in self.series.values() {
+            let stamps: BTreeSet<i64> = s.timestamps().iter().copied().collect();
+            common = Some(match common {
+                Some(c) => c.intersection(&stamps).copied().collect(),
+                None => stamps,
+            });
+        }
+        let common = common.unwrap_or_default();
+        self.apply_all(|s| s.iter().filter(|(t, _)| common.contains(t)).map(|(t, v)| (t, v.clone())).collect())
+    }
+}
+
+impl<T: Display> SeriesSet<T> {
+    /// Writes every series to `<dir>/<name>.csv`, creating `dir` if needed. Characters
+    /// other than letters, digits, `-`, `_` and non-leading `.` are written `%XX` in the
+    /// file name, so e.g. `plant/line1` is saved as `plant%2Fline1.csv`, inside `dir`.
+    ///
+    /// Fails if a series has the empty name, before anything is written.
+    pub fn save_dir<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        let dir = dir.as_ref();
+        let files = self.series.keys().map(|name| encode_file_name(name)).collect::<io::Result<Vec<_>>>()?;
+        std::fs::create_dir_all(dir)?;
+        for (file, series) in files.iter().zip(self.series.values()) {
+            series.save_csv(dir.join(format!("{}.csv", file)))?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: FromStr> SeriesSet<T> {
+    /// Loads every `*.csv` file of `dir`, named after the file stem, decoded as written by
+    /// [`save_dir`](SeriesSet::save_dir) if it was.
+    pub fn load_dir<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let mut set = Self::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "csv") {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    let name = decode_file_name(stem).unwrap_or_else(|| stem.to_string());
+                    set.insert(name, TimestampedSeries::load_csv(&path)?);
+                }
+            }
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl<T: Display> SeriesSet<T> {
+    /// [`save_dir`](SeriesSet::save_dir) with every file sealed by `encryption`, as
+    /// `<name>.csv.sealed`, for snapshots kept on disks that are not trusted. Each file is
+    /// authenticated with its name, so files cannot be swapped unnoticed.
+    ///
+    /// Fails if a series has the empty name or the current key is missing, before
+    /// anything is written.
+    pub fn save_dir_sealed<P: AsRef<Path>>(&self, dir: P, encryption: &Encryption) -> io::Result<()> {
+        let dir = dir.as_ref();
+        let mut files = Vec::with_capacity(self.series.len());
+        for (name, series) in &self.series {
+            let file = format!("{}.csv", encode_file_name(name)?);
+            let mut csv = Vec::new();
+            series.write_csv(&mut csv)?;
+            files.push((encryption.seal(&csv, file.as_bytes())?, file));
+        }
+        std::fs::create_dir_all(dir)?;
+        for (sealed, file) in files {
+            std::fs::write(dir.join(format!("{}.sealed", file)), sealed)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl<T: FromStr> SeriesSet<T> {
+    /// Loads every `*.csv.sealed` file of `dir` written by
+    /// [`save_dir_sealed`](SeriesSet::save_dir_sealed).
+    ///
+    /// Fails if a file cannot be read, opened or parsed.
+    pub fn load_dir_sealed<P: AsRef<Path>>(dir: P, encryption: &Encryption) -> io::Result<Self> {
+        let mut set = Self::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(file) = path.file_name().and_then(|s| s.to_str()).and_then(|f| f.strip_suffix(".sealed")) else {
+                continue;
+            };
+            if let Some(name) = file.strip_suffix(".csv").and_then(decode_file_name) {
+                let csv = encryption.open(&std::fs::read(&path)?, file.as_bytes())?;
+                set.insert(name, TimestampedSeries::read_csv(&csv[..])?);
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl<T> FromIterator<(String, TimestampedSeries<T>)> for SeriesSet<T> {
+    fn from_iter<I: IntoIterator<Item = (String, TimestampedSeries<T>)>>(iter: I) -> Self {
+        Self { series: iter.into_iter().collect() }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SeriesSet<T> {
+    type Item = (&'a String, &'a TimestampedSeries<T>);
+    type IntoIter = btree_map::Iter<'a, String, TimestampedSeries<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.series.iter()
+    }
+}
@@ -0,0 +1,753 @@
+//! A minimal HTTP/JSON query service over an in-memory catalog of timestamped series.
+//!
+//! Endpoints (all `GET`):
+//! - `/series`: names of the stored series.
+//! - `/series/{name}?start=&end=`: points with `start <= timestamp < end`; both bounds
+//!   are optional.
+//! - `/series/{name}/resample?step=&agg=&start=&end=`: points aggregated into
+//!   `step`-long buckets aligned to timestamp 0, keyed by bucket start; `agg` is one of
+//!   `mean` (default), `sum`, `min`, `max`, `first`, `last`, `count`.
+//! - `/series/{name}/latest`: the last point.
+//!
+//! Responses are JSON objects with `timestamps` and `values` arrays (NaN becomes `null`),
+//! or `{"error": ...}` with a 4xx status.
+//!
+//! Derived series registered with [`Catalog::materialize`] are served like the others and
+//! kept up to date as points arrive.
+//!
+//! Each client address may hold a bounded number of connections, within a bound for all
+//! clients, and a request must arrive whole within a timeout; see [`ServerLimits`].
+//!
+//! A [`MultiTenantService`] serves one catalog per tenant, each with its own quotas and
+//! retention, under `/tenants/{tenant}/...` for requests bearing the tenant's token.
+
+use crate::io::{decode_file_name, encode_file_name};
+use crate::sha256::sha256;
+use crate::tenant::check_name;
+use crate::{ChunkStore, ChunkedSeries, Error, Rollup, RollupView, TenantPolicy, TimestampedSeries};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Metadata key of the view definitions saved by [`Catalog::save_to`].
+const VIEWS_KEY: &str = "views";
+
+/// Pause after a failed accept before accepting again.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// A derived series of a catalog and the series it is derived from.
+#[derive(Clone, Debug)]
+struct View {
+    source: String,
+    view: RollupView,
+}
+
+/// Named series shared between the service and the code that fills it, held to the
+/// quotas and retention of its policy.
+#[derive(Debug, Default)]
+pub struct Catalog {
+    series: RwLock<HashMap<String, TimestampedSeries<f64>>>,
+    /// Derived series by name; locked after `series` when both are needed.
+    views: RwLock<BTreeMap<String, View>>,
+    policy: TenantPolicy,
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_policy(self, policy: TenantPolicy) -> Self {
+        Self { policy, ..self }
+    }
+
+    pub fn policy(&self) -> &TenantPolicy {
+        &self.policy
+    }
+
+    /// Checks the catalog against the quotas after `name` changes from `before` points to
+    /// `after`.
+    fn check_quota(
+        &self,
+        series: &HashMap<String, TimestampedSeries<f64>>,
+        name: &str,
+        before: usize,
+        after: usize,
+    ) -> Result<(), Error> {
+        let count = series.len() + usize::from(!series.contains_key(name));
+        let points = series.values().map(TimestampedSeries::len).sum::<usize>() - before + after;
+        self.policy.check("the catalog", count, points).map_err(Error::InvalidInput)
+    }
+
+    /// Stores `series` under `name`, replacing any previous one.
+    ///
+    /// # Panics
+    /// Panics if the catalog would exceed its quotas.
+    pub fn insert<S: Into<String>>(&self, name: S, series: TimestampedSeries<f64>) {
+        if let Err(error) = self.try_insert(name, series) {
+            panic!("{}", error);
+        }
+    }
+
+    /// [`insert`](Self::insert) that fails instead of exceeding the quotas, or replacing
+    /// a derived series. Points past retention are not stored, and the views of `name` are
+    /// recomputed.
+    pub fn try_insert<S: Into<String>>(&self, name: S, mut series: TimestampedSeries<f64>) -> Result<(), Error> {
+        let name = name.into();
+        if let Some(&last) = series.timestamps().last() {
+            series.drop_before(self.policy.cutoff(last));
+        }
+        let mut stored = self.series.write().expect("catalog lock poisoned");
+        let mut views = self.views.write().expect("catalog lock poisoned");
+        if let Some(view) = views.get(&name) {
+            return Err(Error::InvalidInput(format!("`{}` is derived from `{}`", name, view.source)));
+        }
+        let before = stored.get(&name).map_or(0, TimestampedSeries::len);
+        self.check_quota(&stored, &name, before, series.len())?;
+        for (derived, view) in views.iter_mut().filter(|(_, v)| v.source == name) {
+            let (rebuilt, output) = RollupView::build(view.view.rollup(), &series);
+            view.view = rebuilt;
+            stored.insert(derived.clone(), output);
+        }
+        stored.insert(name, series);
+        Ok(())
+    }
+
+    /// Appends a point, creating the series if needed, and drops the points of the series
+    /// that fall out of retention.
+    ///
+    /// # Panics
+    /// Panics if `timestamp` is earlier than the last stored point, or the catalog would
+    /// exceed its quotas.
+    pub fn push(&self, name: &str, timestamp: i64, value: f64) {
+        if let Err(error) = self.try_push(name, timestamp, value) {
+            panic!("{}", error);
+        }
+    }
+
+    /// [`push`](Self::push) that rejects a point earlier than the last stored one,
+    /// beyond the quotas or to a derived series instead of panicking, for feeds that may
+    /// deliver out of order. The views of `name` are updated with the point.
+    pub fn try_push(&self, name: &str, timestamp: i64, value: f64) -> Result<(), Error> {
+        let mut stored = self.series.write().expect("catalog lock poisoned");
+        let mut views = self.views.write().expect("catalog lock poisoned");
+        if let Some(view) = views.get(name) {
+            return Err(Error::InvalidInput(format!("`{}` is derived from `{}`", name, view.source)));
+        }
+        let (before, expired) = stored.get(name).map_or((0, 0), |s| {
+            let cutoff = self.policy.cutoff(timestamp);
+            (s.len(), s.timestamps().partition_point(|&t| t < cutoff))
+        });
+        if let Some(&last) = stored.get(name).and_then(|s| s.timestamps().last()) {
+            if timestamp < last {
+                return Err(Error::InvalidInput(format!("point at {} precedes the last point at {}", timestamp, last)));
+            }
+        }
+        self.check_quota(&stored, name, before, before - expired + 1)?;
+        let series = stored.entry(name.to_string()).or_default();
+        series.drop_before(self.policy.cutoff(timestamp));
+        series.push(timestamp, value);
+        for (derived, view) in views.iter_mut().filter(|(_, v)| v.source == name) {
+            let output = stored.entry(derived.clone()).or_default();
+            view.view.push(output, timestamp, value);
+            output.drop_before(self.policy.cutoff(timestamp));
+        }
+        Ok(())
+    }
+
+    /// Removes `name`, and the series derived from it. Removing a derived series stops
+    /// maintaining it.
+    pub fn remove(&self, name: &str) -> Option<TimestampedSeries<f64>> {
+        let mut stored = self.series.write().expect("catalog lock poisoned");
+        let mut views = self.views.write().expect("catalog lock poisoned");
+        views.remove(name);
+        views.retain(|derived, view| {
+            let keep = view.source != name;
+            if !keep {
+                stored.remove(derived);
+            }
+            keep
+        });
+        stored.remove(name)
+    }
+
+    /// Maintains `name` as `rollup` of `source` from now on: computed from the points of
+    /// `source` stored so far, then updated by each point pushed to it, and served like
+    /// any other series. Replaces a previous view of that name.
+    ///
+    /// Derived series count towards the series quota when created and follow the
+    /// retention period, but their growth is never refused.
+    ///
+    /// Fails if `name` is a series that is not derived, `source` is itself derived, or the
+    /// catalog would exceed its series quota.
+    pub fn materialize(&self, name: &str, source: &str, rollup: Rollup) -> Result<(), Error> {
+        let mut stored = self.series.write().expect("catalog lock poisoned");
+        let mut views = self.views.write().expect("catalog lock poisoned");
+        if let Some(view) = views.get(source) {
+            return Err(Error::InvalidInput(format!("`{}` is derived from `{}`", source, view.source)));
+        }
+        if name == source || (stored.contains_key(name) && !views.contains_key(name)) {
+            return Err(Error::InvalidInput(format!("`{}` is already a series", name)));
+        }
+        let (view, output) = match stored.get(source) {
+            Some(series) => RollupView::build(rollup, series),
+            None => (RollupView::new(rollup), TimestampedSeries::new()),
+        };
+        let before = stored.get(name).map_or(0, TimestampedSeries::len);
+        self.check_quota(&stored, name, before, output.len())?;
+        stored.insert(name.to_string(), output);
+        views.insert(name.to_string(), View { source: source.to_string(), view });
+        Ok(())
+    }
+
+    /// The derived series as `(name, source, rollup)`, sorted by name.
+    pub fn views(&self) -> Vec<(String, String, Rollup)> {
+        let views = self.views.read().expect("catalog lock poisoned");
+        views.iter().map(|(name, v)| (name.clone(), v.source.clone(), v.view.rollup())).collect()
+    }
+
+    /// Stored names, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.series.read().expect("catalog lock poisoned").keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Runs `f` on the named series under the read lock.
+    pub fn with_series<R>(&self, name: &str, f: impl FnOnce(&TimestampedSeries<f64>) -> R) -> Option<R> {
+        self.series.read().expect("catalog lock poisoned").get(name).map(f)
+    }
+
+    /// Makes `store` hold the catalog's series, derived ones included, in chunks of
+    /// `chunk_size` points, with the view definitions; series of the store missing from
+    /// the catalog are removed. Names are encoded by the store, so any name stays inside it.
+    ///
+    /// Fails if a series has the empty name, before anything is written.
+    ///
+    /// # Panics
+    /// Panics if `chunk_size` is zero.
+    pub fn save_to(&self, store: &ChunkStore, chunk_size: usize) -> io::Result<()> {
+        let stored = self.series.read().expect("catalog lock poisoned");
+        let views = self.views.read().expect("catalog lock poisoned");
+        let definitions = views
+            .iter()
+            .map(|(name, v)| {
+                Ok(format!(
+                    "{}\t{}\t{}\n",
+                    encode_file_name(name)?,
+                    encode_file_name(&v.source)?,
+                    v.view.rollup().to_text()
+                ))
+            })
+            .collect::<io::Result<String>>()?;
+        if stored.contains_key("") {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty series name"));
+        }
+        for (name, series) in stored.iter() {
+            store.save(name, &ChunkedSeries::from_series(series, chunk_size))?;
+        }
+        for name in store.names()? {
+            if !stored.contains_key(&name) {
+                store.remove(&name)?;
+            }
+        }
+        store.save_metadata(VIEWS_KEY, definitions.as_bytes())
+    }
+
+    /// A catalog of the series and views saved in `store` by [`save_to`](Self::save_to),
+    /// without policy. The views carry on from the saved series.
+    ///
+    /// Fails if a file cannot be read or the view definitions are malformed.
+    pub fn load_from(store: &ChunkStore) -> io::Result<Self> {
+        let mut series = HashMap::new();
+        for name in store.names()? {
+            let loaded = store.load(&name)?.to_series();
+            series.insert(name, loaded);
+        }
+        let definitions = store.load_metadata(VIEWS_KEY)?.unwrap_or_default();
+        let definitions = String::from_utf8(definitions).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut views = BTreeMap::new();
+        for line in definitions.lines() {
+            let malformed = || io::Error::new(io::ErrorKind::InvalidData, format!("malformed view `{}`", line));
+            let [name, source, rollup] = line.split('\t').collect::<Vec<_>>()[..] else { return Err(malformed()) };
+            let (name, source) = decode_file_name(name).zip(decode_file_name(source)).ok_or_else(malformed)?;
+            let rollup = Rollup::parse(rollup).ok_or_else(malformed)?;
+            let view = match series.get(&source) {
+                Some(source) => RollupView::resume(rollup, source),
+                None => RollupView::new(rollup),
+            };
+            series.entry(name.clone()).or_default();
+            views.insert(name, View { source, view });
+        }
+        Ok(Self { series: RwLock::new(series), views: RwLock::new(views), policy: TenantPolicy::default() })
+    }
+}
+
+/// Status code and JSON body of a response.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body: body.to_string() }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self { status, body: json!({ "error": message }).to_string() }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            _ => "Error",
+        }
+    }
+}
+
+/// Decodes `%XX` escapes and `+` in a query component.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match bytes[i] {
+            b'%' if escaped.is_some() => {
+                out.extend(escaped);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn points(series: &TimestampedSeries<f64>) -> Value {
+    json!({ "timestamps": series.timestamps(), "values": series.values().as_slice() })
+}
+
+fn aggregate(values: &[f64], agg: &str) -> f64 {
+    match agg {
+        "sum" => values.iter().sum(),
+        "min" => values.iter().copied().fold(f64::INFINITY, f64::min),
+        "max" => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        "first" => values[0],
+        "last" => values[values.len() - 1],
+        "count" => values.len() as f64,
+        _ => values.iter().sum::<f64>() / values.len() as f64,
+    }
+}
+
+/// Bounds on what clients of a service can hold, so that one client cannot starve the
+/// others of threads, memory or time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServerLimits {
+    /// Connections served at once, over all clients; more are answered with 503.
+    pub max_connections: usize,
+    /// Connections served at once from one client address.
+    pub max_connections_per_client: usize,
+    /// Largest request line and headers, in bytes; larger requests get a 431.
+    pub max_request_bytes: u64,
+    /// Time for the whole request to arrive, and for each write of the response.
+    pub timeout: Duration,
+    /// Series one WebSocket connection may be subscribed to at once; a subscription
+    /// beyond it is refused with an error message.
+    pub max_subscriptions: usize,
+}
+
+impl Default for ServerLimits {
+    fn default() -> Self {
+        Self {
+            max_connections: 256,
+            max_connections_per_client: 16,
+            max_request_bytes: 16 * 1024,
+            timeout: Duration::from_secs(10),
+            max_subscriptions: 1024,
+        }
+    }
+}
+
+/// Answers queries against a [`Catalog`].
+#[derive(Clone, Debug)]
+pub struct QueryService {
+    catalog: Arc<Catalog>,
+    limits: ServerLimits,
+}
+
+impl QueryService {
+    pub fn new(catalog: Arc<Catalog>) -> Self {
+        Self { catalog, limits: ServerLimits::default() }
+    }
+
+    pub fn with_limits(self, limits: ServerLimits) -> Self {
+        Self { limits, ..self }
+    }
+
+    pub fn catalog(&self) -> &Arc<Catalog> {
+        &self.catalog
+    }
+
+    /// Handles one request given its method and target (path plus query string).
+    pub fn handle(&self, method: &str, target: &str) -> Response {
+        if method != "GET" {
+            return Response::error(405, "only GET is supported");
+        }
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let params: HashMap<String, String> = query
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                let (k, v) = p.split_once('=').unwrap_or((p, ""));
+                (percent_decode(k), percent_decode(v))
+            })
+            .collect();
+        let bound = |key: &str, default: i64| -> Result<i64, Response> {
+            params.get(key).map_or(Ok(default), |v| {
+                v.parse().map_err(|_| Response::error(400, &format!("`{}` must be an integer", key)))
+            })
+        };
+        let segments: Vec<String> = path.trim_matches('/').split('/').map(percent_decode).collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        let range = || -> Result<(i64, i64), Response> { Ok((bound("start", i64::MIN)?, bound("end", i64::MAX)?)) };
+        let result = match segments.as_slice() {
+            ["series"] => Ok(Response::ok(json!({ "series": self.catalog.names() }))),
+            ["series", name] => range().and_then(|(start, end)| {
+                self.catalog
+                    .with_series(name, |s| Response::ok(points(&s.between(start, end))))
+                    .ok_or_else(|| Response::error(404, "no such series"))
+            }),
+            ["series", name, "latest"] => self
+                .catalog
+                .with_series(name, |s| match s.iter().last() {
+                    Some((t, v)) => Response::ok(json!({ "timestamp": t, "value": v })),
+                    None => Response::error(404, "series is empty"),
+                })
+                .ok_or_else(|| Response::error(404, "no such series")),
+            ["series", name, "resample"] => range().and_then(|(start, end)| {
+                let step = bound("step", 0)?;
+                if step <= 0 {
+                    return Err(Response::error(400, "`step` must be a positive integer"));
+                }
+                let agg = params.get("agg").map_or("mean", String::as_str);
+                if !["mean", "sum", "min", "max", "first", "last", "count"].contains(&agg) {
+                    return Err(Response::error(400, "unknown `agg`"));
+                }
+                self.catalog
+                    .with_series(name, |s| {
+                        Response::ok(points(&s.between(start, end).resample(step, |v| aggregate(v, agg))))
+                    })
+                    .ok_or_else(|| Response::error(404, "no such series"))
+            }),
+            _ => Err(Response::error(404, "unknown endpoint")),
+        };
+        result.unwrap_or_else(|error| error)
+    }
+
+    /// Serves requests on `addr`, one thread per connection within the service's
+    /// [`ServerLimits`]. Fails only if `addr` cannot be bound; failed accepts are retried.
+    pub fn serve<A: ToSocketAddrs>(&self, addr: A) -> io::Result<()> {
+        self.serve_on(TcpListener::bind(addr)?)
+    }
+
+    /// Serves requests on an already bound listener.
+    pub fn serve_on(&self, listener: TcpListener) -> io::Result<()> {
+        let service = self.clone();
+        serve_connections(listener, self.limits, move |method, target, _| service.handle(method, target))
+    }
+}
+
+/// Reads from a stream until a deadline, however slowly the peer sends, so that a client
+/// trickling bytes cannot hold a connection past it.
+pub(crate) struct DeadlineReader {
+    pub(crate) stream: TcpStream,
+    pub(crate) deadline: Instant,
+}
+
+impl Read for DeadlineReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "request not received in time"));
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+/// Reads one line of at most the bytes left in `reader`, failing with
+/// [`io::ErrorKind::InvalidInput`] if it ends before the line does.
+pub(crate) fn read_bounded_line<R: BufRead>(reader: &mut io::Take<R>, line: &mut String) -> io::Result<()> {
+    reader.read_line(line)?;
+    if !line.ends_with('\n') && reader.limit() == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "request too large"));
+    }
+    Ok(())
+}
+
+/// Reads the request line and the bearer token of one request, within `limits`.
+fn read_request(stream: &TcpStream, limits: &ServerLimits) -> io::Result<(String, Option<String>)> {
+    let deadline = Instant::now() + limits.timeout;
+    let reader = DeadlineReader { stream: stream.try_clone()?, deadline };
+    let mut reader = BufReader::new(reader).take(limits.max_request_bytes);
+    let mut request_line = String::new();
+    read_bounded_line(&mut reader, &mut request_line)?;
+    // Read the headers up to the blank line; requests carry no body.
+    let mut token = None;
+    let mut header = String::new();
+    loop {
+        read_bounded_line(&mut reader, &mut header)?;
+        if header.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("authorization") {
+                token = value.trim().strip_prefix("Bearer ").map(|t| t.trim().to_string());
+            }
+        }
+        header.clear();
+    }
+    Ok((request_line, token))
+}
+
+/// Reads one HTTP/1.1 request from `stream`, answers it with `handle` given the method,
+/// the target and any bearer token, and writes the response.
+fn respond(
+    stream: TcpStream,
+    limits: &ServerLimits,
+    handle: impl Fn(&str, &str, Option<&str>) -> Response,
+) -> io::Result<()> {
+    stream.set_write_timeout(Some(limits.timeout))?;
+    let response = match read_request(&stream, limits) {
+        Ok((request_line, token)) => {
+            let mut parts = request_line.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some(method), Some(target)) => handle(method, target, token.as_deref()),
+                _ => Response::error(400, "malformed request line"),
+            }
+        }
+        Err(e) if e.kind() == io::ErrorKind::InvalidInput => Response::error(431, "request too large"),
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => Response::error(400, "malformed request"),
+        Err(e) => return Err(e),
+    };
+    write_response(stream, &response)
+}
+
+fn write_response(mut stream: TcpStream, response: &Response) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+/// Connections being served, in all and by client address.
+#[derive(Debug, Default)]
+struct ConnectionCount {
+    total: usize,
+    by_client: HashMap<IpAddr, usize>,
+}
+
+/// A connection counted in a [`ConnectionCount`] until dropped.
+struct ConnectionSlot {
+    count: Arc<Mutex<ConnectionCount>>,
+    client: IpAddr,
+}
+
+impl ConnectionSlot {
+    /// A slot for a connection from `client`, or `None` if that would exceed `limits`.
+    fn acquire(count: &Arc<Mutex<ConnectionCount>>, client: IpAddr, limits: &ServerLimits) -> Option<Self> {
+        let mut guard = count.lock().expect("connection count poisoned");
+        let from_client = guard.by_client.get(&client).copied().unwrap_or(0);
+        if guard.total >= limits.max_connections || from_client >= limits.max_connections_per_client {
+            return None;
+        }
+        guard.total += 1;
+        guard.by_client.insert(client, from_client + 1);
+        Some(Self { count: count.clone(), client })
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        let mut guard = self.count.lock().expect("connection count poisoned");
+        guard.total -= 1;
+        if let Some(n) = guard.by_client.get_mut(&self.client) {
+            *n -= 1;
+            if *n == 0 {
+                guard.by_client.remove(&self.client);
+            }
+        }
+    }
+}
+
+/// Answers the connections of `listener` until it fails, one thread per connection up to
+/// the connection limits; connections beyond them get a 503 and are closed.
+fn serve_connections<F>(listener: TcpListener, limits: ServerLimits, handle: F) -> io::Result<()>
+where
+    F: Fn(&str, &str, Option<&str>) -> Response + Clone + Send + 'static,
+{
+    accept_connections(listener, limits, move |stream| {
+        // A client hanging up mid-request only affects its own connection.
+        let _ = respond(stream, &limits, handle.clone());
+    })
+}
+
+/// Hands each connection of `listener` to `serve` on a thread of its own, up to the
+/// connection limits; connections beyond them get a 503 and are closed. A failed accept,
+/// e.g. when out of file descriptors, is retried after [`ACCEPT_BACKOFF`].
+pub(crate) fn accept_connections<F>(listener: TcpListener, limits: ServerLimits, serve: F) -> io::Result<()>
+where
+    F: Fn(TcpStream) + Clone + Send + 'static,
+{
+    let count = Arc::new(Mutex::new(ConnectionCount::default()));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            // Out of descriptors or a connection reset before it was accepted: the
+            // listener itself is fine, so wait for connections to close and go on.
+            Err(_error) => {
+                trace_event!(error = %_error, "accept failed");
+                thread::sleep(ACCEPT_BACKOFF);
+                continue;
+            }
+        };
+        let Ok(client) = stream.peer_addr().map(|a| a.ip()) else { continue };
+        let Some(slot) = ConnectionSlot::acquire(&count, client, &limits) else {
+            // Busy: the response fits the socket buffer, so this does not block accepting.
+            let _ = stream
+                .set_write_timeout(Some(Duration::from_secs(1)))
+                .and_then(|_| write_response(stream, &Response::error(503, "too many connections")));
+            continue;
+        };
+        let serve = serve.clone();
+        thread::spawn(move || {
+            let _slot = slot;
+            serve(stream);
+        });
+    }
+    Ok(())
+}
+
+/// Compares tokens in time independent of their contents and lengths, by comparing their
+/// hashes.
+fn tokens_match(a: &str, b: &str) -> bool {
+    let (a, b) = (sha256(a.as_bytes()), sha256(b.as_bytes()));
+    a.iter().zip(&b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[derive(Clone, Debug)]
+struct Tenant {
+    token: String,
+    service: QueryService,
+}
+
+/// Serves the catalog of every registered tenant under `/tenants/{tenant}`, with the
+/// endpoints of [`QueryService`] below it, e.g. `/tenants/acme/series`.
+///
+/// Requests must carry the tenant's token as `Authorization: Bearer {token}`. An unknown
+/// tenant gets the same 401 response as a wrong token, so tenant ids cannot be probed.
+///
+/// Connections are held to the service's [`ServerLimits`] before their tenant is known,
+/// so a client of one tenant cannot hold more than its share of connections, nor hold
+/// one past the timeout, and the other tenants' clients keep being served.
+#[derive(Clone, Debug, Default)]
+pub struct MultiTenantService {
+    tenants: Arc<RwLock<HashMap<String, Tenant>>>,
+    limits: ServerLimits,
+}
+
+impl MultiTenantService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limits(self, limits: ServerLimits) -> Self {
+        Self { limits, ..self }
+    }
+
+    /// Serves `catalog` as `tenant` to requests bearing `token`, replacing any previous
+    /// registration of `tenant`.
+    ///
+    /// Fails if `tenant` is not made of letters, digits, `-`, `_` and non-leading `.`, or
+    /// `token` is empty.
+    pub fn register(&self, tenant: &str, token: &str, catalog: Arc<Catalog>) -> Result<(), Error> {
+        check_name("tenant id", tenant).map_err(Error::InvalidInput)?;
+        if token.is_empty() {
+            return Err(Error::InvalidInput("tenant token must not be empty".into()));
+        }
+        let entry = Tenant { token: token.to_string(), service: QueryService::new(catalog) };
+        self.tenants.write().expect("tenant lock poisoned").insert(tenant.to_string(), entry);
+        Ok(())
+    }
+
+    /// Stops serving `tenant`, returning its catalog.
+    pub fn unregister(&self, tenant: &str) -> Option<Arc<Catalog>> {
+        self.tenants.write().expect("tenant lock poisoned").remove(tenant).map(|t| t.service.catalog)
+    }
+
+    /// Registered tenant ids, sorted.
+    pub fn tenants(&self) -> Vec<String> {
+        let mut tenants: Vec<String> = self.tenants.read().expect("tenant lock poisoned").keys().cloned().collect();
+        tenants.sort();
+        tenants
+    }
+
+    pub fn catalog(&self, tenant: &str) -> Option<Arc<Catalog>> {
+        self.tenants.read().expect("tenant lock poisoned").get(tenant).map(|t| t.service.catalog.clone())
+    }
+
+    /// Handles one request given its method, target and bearer token.
+    pub fn handle(&self, method: &str, target: &str, token: Option<&str>) -> Response {
+        let Some(path) = target.strip_prefix("/tenants/") else {
+            return Response::error(404, "unknown endpoint");
+        };
+        let (tenant, rest) = path.split_once('/').unwrap_or((path, ""));
+        let tenant = percent_decode(tenant);
+        let tenants = self.tenants.read().expect("tenant lock poisoned");
+        match (tenants.get(&tenant), token) {
+            (Some(entry), Some(token)) if tokens_match(&entry.token, token) => {
+                entry.service.handle(method, &format!("/{}", rest))
+            }
+            _ => Response::error(401, "unknown tenant or invalid token"),
+        }
+    }
+
+    /// Serves requests on `addr`, one thread per connection within the service's
+    /// [`ServerLimits`]. Fails only if `addr` cannot be bound; failed accepts are retried.
+    pub fn serve<A: ToSocketAddrs>(&self, addr: A) -> io::Result<()> {
+        self.serve_on(TcpListener::bind(addr)?)
+    }
+
+    /// Serves requests on an already bound listener.
+    pub fn serve_on(&self, listener: TcpListener) -> io::Result<()> {
+        let service = self.clone();
+        serve_connections(listener, self.limits, move |method, target, token| service.handle(method, target, token))
+    }
+}
@@ -0,0 +1,68 @@
+//! Sessions of activity in an event series: runs of events separated by gaps of
+//! inactivity longer than a timeout, as product analytics counts visits.
+
+use crate::{TimeSeries, TimeSeriesFrame, TimestampedSeries};
+use std::ops::Range;
+
+/// A run of events no more than the timeout apart.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Session {
+    /// Timestamps of the first and last event.
+    pub start: i64,
+    pub end: i64,
+    /// Positions of the session's events in the series.
+    pub events: Range<usize>,
+}
+
+impl Session {
+    pub fn duration(&self) -> i64 {
+        self.end - self.start
+    }
+
+    pub fn count(&self) -> usize {
+        self.events.len()
+    }
+}
+
+impl<T> TimestampedSeries<T> {
+    /// Splits the events into sessions wherever more than `gap_timeout` separates two
+    /// consecutive events.
+    ///
+    /// # Panics
+    /// Panics if `gap_timeout` is negative.
+    pub fn sessions(&self, gap_timeout: i64) -> Vec<Session> {
+        assert!(gap_timeout >= 0, "gap timeout must not be negative");
+        let timestamps = self.timestamps();
+        let mut out: Vec<Session> = Vec::new();
+        for (i, &t) in timestamps.iter().enumerate() {
+            match out.last_mut().filter(|s| t - s.end <= gap_timeout) {
+                Some(session) => {
+                    session.end = t;
+                    session.events.end = i + 1;
+                }
+                None => out.push(Session { start: t, end: t, events: i..i + 1 }),
+            }
+        }
+        out
+    }
+
+    /// One row per session, indexed by its start, with columns `end`, `duration` and
+    /// `count`.
+    ///
+    /// # Panics
+    /// Panics if `gap_timeout` is negative.
+    pub fn sessionize(&self, gap_timeout: i64) -> TimeSeriesFrame {
+        let sessions = self.sessions(gap_timeout);
+        let column = |f: fn(&Session) -> f64| sessions.iter().map(f).collect::<TimeSeries<f64>>();
+        let columns = [
+            ("end", column(|s| s.end as f64)),
+            ("duration", column(|s| s.duration() as f64)),
+            ("count", column(|s| s.count() as f64)),
+        ];
+        let mut frame = TimeSeriesFrame::new(sessions.iter().map(|s| s.start).collect());
+        for (name, values) in columns {
+            frame.add_column(name, values).expect("one value per session");
+        }
+        frame
+    }
+}
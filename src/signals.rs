@@ -0,0 +1,151 @@
+//! Trading signal primitives and the rules turning signals into positions.
+
+use crate::TimeSeries;
+
+pub trait Crossover {
+    /// `true` where `self` moves from at or below `other` to strictly above it, over their
+    /// common length. The first point is always `false`, so the flags line up with the
+    /// inputs; comparisons involving NaN never count as a cross.
+    fn cross_over(&self, other: &Self) -> TimeSeries<bool>;
+    /// `true` where `self` moves from at or above `other` to strictly below it.
+    fn cross_under(&self, other: &Self) -> TimeSeries<bool>;
+    /// Indices of the points flagged by [`cross_over`](Crossover::cross_over).
+    fn cross_over_indices(&self, other: &Self) -> Vec<usize>;
+    /// Indices of the points flagged by [`cross_under`](Crossover::cross_under).
+    fn cross_under_indices(&self, other: &Self) -> Vec<usize>;
+}
+
+/// Flags points where `before` held at the previous point and `after` holds now.
+fn crossings<B, A>(x: &[f64], y: &[f64], before: B, after: A) -> TimeSeries<bool>
+where
+    B: Fn(f64, f64) -> bool,
+    A: Fn(f64, f64) -> bool,
+{
+    let n = x.len().min(y.len());
+    (0..n).map(|i| i > 0 && before(x[i - 1], y[i - 1]) && after(x[i], y[i])).collect()
+}
+
+fn flagged(flags: TimeSeries<bool>) -> Vec<usize> {
+    flags.as_slice().iter().enumerate().filter(|(_, &f)| f).map(|(i, _)| i).collect()
+}
+
+impl Crossover for TimeSeries<f64> {
+    fn cross_over(&self, other: &Self) -> TimeSeries<bool> {
+        crossings(self.as_slice(), other.as_slice(), |a, b| a <= b, |a, b| a > b)
+    }
+
+    fn cross_under(&self, other: &Self) -> TimeSeries<bool> {
+        crossings(self.as_slice(), other.as_slice(), |a, b| a >= b, |a, b| a < b)
+    }
+
+    fn cross_over_indices(&self, other: &Self) -> Vec<usize> {
+        flagged(self.cross_over(other))
+    }
+
+    fn cross_under_indices(&self, other: &Self) -> Vec<usize> {
+        flagged(self.cross_under(other))
+    }
+}
+
+/// Rules applied by [`signal_to_positions`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionRules {
+    /// Bars between a signal and the position it opens; 1 acts on the next bar, avoiding
+    /// look-ahead when signals are computed from closes.
+    pub delay: usize,
+    /// Position size per unit of signal direction, as a fraction of equity.
+    pub size: f64,
+    /// Close a position after this many bars.
+    pub max_holding: Option<usize>,
+    /// Close a position once its return from the entry close falls to `-stop_loss`.
+    pub stop_loss: Option<f64>,
+    /// Close a position once its return from the entry close reaches `take_profit`.
+    pub take_profit: Option<f64>,
+}
+
+impl Default for PositionRules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PositionRules {
+    /// Full-size positions one bar after the signal, with no exits other than the signal.
+    pub fn new() -> Self {
+        Self { delay: 1, size: 1., max_holding: None, stop_loss: None, take_profit: None }
+    }
+
+    pub fn with_delay(self, delay: usize) -> Self {
+        Self { delay, ..self }
+    }
+
+    pub fn with_size(self, size: f64) -> Self {
+        Self { size, ..self }
+    }
+
+    pub fn with_max_holding(self, bars: usize) -> Self {
+        Self { max_holding: Some(bars), ..self }
+    }
+
+    pub fn with_stop_loss(self, fraction: f64) -> Self {
+        Self { stop_loss: Some(fraction), ..self }
+    }
+
+    pub fn with_take_profit(self, fraction: f64) -> Self {
+        Self { take_profit: Some(fraction), ..self }
+    }
+}
+
+/// Turns a signal series (positive long, negative short, zero flat; booleans map to 1/0)
+/// into the position series expected by [`equity_curve`](crate::equity_curve).
+///
+/// Only the sign of the signal is used. After an exit forced by the holding period, stop
+/// loss or take profit, the position stays flat until the signal changes. The output has
+/// the common length of `signal` and `prices`.
+pub fn signal_to_positions(
+    signal: &TimeSeries<f64>,
+    prices: &TimeSeries<f64>,
+    rules: &PositionRules,
+) -> TimeSeries<f64> {
+    let n = signal.len().min(prices.len());
+    let mut out = Vec::with_capacity(n);
+    let mut position = 0.;
+    let mut entry = (0., 0);
+    let mut last_desired = 0.;
+    let mut blocked = false;
+    for t in 0..n {
+        let desired = if t >= rules.delay {
+            let s = signal[t - rules.delay];
+            if s > 0. {
+                1.
+            } else if s < 0. {
+                -1.
+            } else {
+                0.
+            }
+        } else {
+            0.
+        };
+        if desired != last_desired {
+            blocked = false;
+            last_desired = desired;
+        }
+        if position != 0. {
+            let (entry_price, entry_bar) = entry;
+            let r = position * (prices[t] / entry_price - 1.);
+            let stopped = rules.stop_loss.is_some_and(|sl| r <= -sl);
+            let taken = rules.take_profit.is_some_and(|tp| r >= tp);
+            let expired = rules.max_holding.is_some_and(|m| t - entry_bar >= m);
+            if stopped || taken || expired {
+                position = 0.;
+                blocked = true;
+            }
+        }
+        if !blocked && desired != position {
+            position = desired;
+            entry = (prices[t], t);
+        }
+        out.push(position * rules.size);
+    }
+    TimeSeries(out)
+}
@@ -0,0 +1,103 @@
+//! Monte Carlo simulation of future paths from fitted models.
+
+use crate::random::standard_normal;
+use crate::stats::quantile_sorted;
+use crate::{Autoregressive, Drift, Garch, Monitor, TimeSeries};
+use rand::Rng;
+
+/// A fitted model that can draw random future trajectories.
+pub trait Simulate {
+    /// Draws one path of `horizon` future values.
+    fn sample_path<R: Rng + ?Sized>(&self, horizon: usize, rng: &mut R) -> TimeSeries<f64>;
+}
+
+impl Simulate for Drift {
+    fn sample_path<R: Rng + ?Sized>(&self, horizon: usize, rng: &mut R) -> TimeSeries<f64> {
+        let mut x = self.last.unwrap_or(f64::NAN);
+        (0..horizon)
+            .map(|_| {
+                x += self.drift + self.sigma * standard_normal(rng);
+                x
+            })
+            .collect()
+    }
+}
+
+impl Simulate for Autoregressive {
+    fn sample_path<R: Rng + ?Sized>(&self, horizon: usize, rng: &mut R) -> TimeSeries<f64> {
+        if self.history.is_empty() {
+            return TimeSeries(vec![f64::NAN; horizon]);
+        }
+        let mut history = self.history.clone();
+        for _ in 0..horizon {
+            let next = self.step(&history) + self.sigma * standard_normal(rng);
+            history.push(next);
+        }
+        TimeSeries(history.split_off(self.order))
+    }
+}
+
+/// Paths of returns with simulated volatility clustering.
+impl Simulate for Garch {
+    fn sample_path<R: Rng + ?Sized>(&self, horizon: usize, rng: &mut R) -> TimeSeries<f64> {
+        let mut e = self.last_residual;
+        let mut s2 = self.last_variance;
+        (0..horizon)
+            .map(|_| {
+                s2 = self.next_variance(e, s2);
+                e = s2.sqrt() * standard_normal(rng);
+                self.mu + e
+            })
+            .collect()
+    }
+}
+
+/// Draws `n_paths` independent future paths of length `horizon` from `model`.
+pub fn simulate_paths<M, R>(model: &M, horizon: usize, n_paths: usize, rng: &mut R) -> Vec<TimeSeries<f64>>
+where
+    M: Simulate,
+    R: Rng + ?Sized,
+{
+    simulate_paths_monitored(model, horizon, n_paths, rng, &mut Monitor::new())
+}
+
+/// [`simulate_paths`], reporting progress to `monitor` after each path. If cancelled,
+/// returns the paths simulated so far.
+pub fn simulate_paths_monitored<M, R>(
+    model: &M,
+    horizon: usize,
+    n_paths: usize,
+    rng: &mut R,
+    monitor: &mut Monitor,
+) -> Vec<TimeSeries<f64>>
+where
+    M: Simulate,
+    R: Rng + ?Sized,
+{
+    let mut paths = Vec::with_capacity(n_paths);
+    for done in 1..=n_paths {
+        if monitor.is_cancelled() {
+            return paths;
+        }
+        paths.push(model.sample_path(horizon, rng));
+        monitor.report(done, n_paths);
+    }
+    monitor.finish();
+    paths
+}
+
+/// Cross-sectional quantiles of simulated paths at each step, one series per level.
+pub fn percentile_envelope(paths: &[TimeSeries<f64>], quantiles: &[f64]) -> Vec<TimeSeries<f64>> {
+    let horizon = paths.iter().map(|p| p.len()).min().unwrap_or(0);
+    let sorted_steps: Vec<Vec<f64>> = (0..horizon)
+        .map(|h| {
+            let mut step: Vec<f64> = paths.iter().map(|p| p[h]).collect();
+            step.sort_by(|a, b| a.total_cmp(b));
+            step
+        })
+        .collect();
+    quantiles
+        .iter()
+        .map(|&q| sorted_steps.iter().map(|step| quantile_sorted(step, q)).collect())
+        .collect()
+}
@@ -0,0 +1,141 @@
+//! Exactly-once delivery of streaming outputs across crashes and restarts.
+//!
+//! Outputs get consecutive sequence numbers. A [`Checkpoint`] captures the operator
+//! state together with the input offset and the next sequence number; after a restart
+//! the input is replayed from that offset, the deterministic operator regenerates the
+//! same outputs under the same numbers, and those the sink already committed are
+//! skipped. Sinks store the highest sequence number atomically with the data (in the
+//! same transaction, or as the Kafka producer sequence), so retried writes are
+//! idempotent too.
+
+use crate::Error;
+
+/// An output with its position in the output stream.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sequenced<O> {
+    pub seq: u64,
+    pub value: O,
+}
+
+/// A downstream store that remembers how far it has committed.
+pub trait Sink<O> {
+    /// Highest sequence number committed, or `None` if nothing was.
+    fn committed(&self) -> Option<u64>;
+    /// Durably writes `records`, in order, together with the highest sequence number.
+    /// Records at or below [`committed`](Self::committed) must be ignored.
+    fn write(&mut self, records: &[Sequenced<O>]) -> Result<(), Error>;
+}
+
+/// A sink collecting records in memory, for tests and in-process consumers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemorySink<O> {
+    pub records: Vec<Sequenced<O>>,
+}
+
+impl<O> Default for MemorySink<O> {
+    fn default() -> Self {
+        Self { records: Vec::new() }
+    }
+}
+
+impl<O> MemorySink<O> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<O: Clone> Sink<O> for MemorySink<O> {
+    fn committed(&self) -> Option<u64> {
+        self.records.last().map(|r| r.seq)
+    }
+
+    fn write(&mut self, records: &[Sequenced<O>]) -> Result<(), Error> {
+        let committed = self.committed();
+        self.records.extend(records.iter().filter(|r| committed.is_none_or(|c| r.seq > c)).cloned());
+        Ok(())
+    }
+}
+
+/// Everything needed to resume a stream: operator state, inputs consumed and the next
+/// output sequence number. Persist it in whatever form suits the operator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checkpoint<S> {
+    pub state: S,
+    /// Number of input records consumed; replay the input from here.
+    pub input_offset: u64,
+    pub next_seq: u64,
+}
+
+/// A deterministic streaming operator whose outputs are numbered so that each reaches
+/// the sink exactly once.
+#[derive(Clone, Debug)]
+pub struct ExactlyOnce<S> {
+    state: S,
+    input_offset: u64,
+    next_seq: u64,
+    /// Highest sequence number the sink is known to hold.
+    committed: Option<u64>,
+}
+
+impl<S: Clone> ExactlyOnce<S> {
+    /// A fresh stream over `state`, e.g. a [`StreamOp`](crate::StreamOp) or an
+    /// [`EventTimeResample`](crate::EventTimeResample).
+    pub fn new(state: S) -> Self {
+        Self { state, input_offset: 0, next_seq: 0, committed: None }
+    }
+
+    /// Resumes from `checkpoint` against a sink that holds everything up to `committed`.
+    pub fn restore(checkpoint: Checkpoint<S>, committed: Option<u64>) -> Self {
+        Self { state: checkpoint.state, input_offset: checkpoint.input_offset, next_seq: checkpoint.next_seq, committed }
+    }
+
+    /// Resumes from `checkpoint` against `sink`.
+    pub fn restore_for<O, K: Sink<O>>(checkpoint: Checkpoint<S>, sink: &K) -> Self {
+        Self::restore(checkpoint, sink.committed())
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint<S> {
+        Checkpoint { state: self.state.clone(), input_offset: self.input_offset, next_seq: self.next_seq }
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Input records consumed so far, counting from the start of the stream.
+    pub fn input_offset(&self) -> u64 {
+        self.input_offset
+    }
+
+    pub fn committed(&self) -> Option<u64> {
+        self.committed
+    }
+
+    /// Feeds one input record through `step`, numbering its outputs and returning those
+    /// the sink does not hold yet.
+    pub fn process<O, I, F>(&mut self, step: F) -> Vec<Sequenced<O>>
+    where
+        I: IntoIterator<Item = O>,
+        F: FnOnce(&mut S) -> I,
+    {
+        self.input_offset += 1;
+        let mut out = Vec::new();
+        for value in step(&mut self.state) {
+            let seq = self.next_seq;
+            self.next_seq += 1;
+            if self.committed.is_none_or(|c| seq > c) {
+                out.push(Sequenced { seq, value });
+            }
+        }
+        out
+    }
+
+    /// Writes `records` to `sink` and records them as committed.
+    pub fn deliver<O, K: Sink<O>>(&mut self, sink: &mut K, records: &[Sequenced<O>]) -> Result<(), Error> {
+        sink.write(records)?;
+        if let Some(last) = records.last() {
+            self.committed = Some(self.committed.map_or(last.seq, |c| c.max(last.seq)));
+        }
+        Ok(())
+    }
+}
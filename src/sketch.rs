@@ -0,0 +1,462 @@
+//! Bounded-memory approximate summaries (distinct counts, heavy hitters, quantiles),
+//! usable as [`Aggregator`]s per time bucket.
+
+use crate::{Aggregator, TimeSeriesFrame, TimestampedSeries};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+
+/// Approximate distinct counts with HyperLogLog over `2^precision` one-byte registers.
+/// The relative standard error is about `1.04 / sqrt(2^precision)`, e.g. 1.6% at the
+/// default precision of 12 (4 KiB per state).
+///
+/// Values are hashed with the fixed-key standard hasher, so states from different runs
+/// of the same build can be merged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HyperLogLog {
+    precision: u8,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new(12)
+    }
+}
+
+impl HyperLogLog {
+    /// # Panics
+    /// Panics unless `4 <= precision <= 18`.
+    pub fn new(precision: u8) -> Self {
+        assert!((4..=18).contains(&precision), "precision must be between 4 and 18");
+        Self { precision }
+    }
+
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        // Finalize with a 64-bit mixer so low-entropy inputs spread over all bits.
+        let mut h = hasher.finish();
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        h ^ (h >> 33)
+    }
+}
+
+impl<T: Hash> Aggregator<T> for HyperLogLog {
+    type State = Vec<u8>;
+    type Output = f64;
+
+    fn init(&self) -> Vec<u8> {
+        vec![0; 1 << self.precision]
+    }
+
+    fn update(&self, registers: &mut Vec<u8>, value: &T) {
+        let h = Self::hash(value);
+        let p = self.precision as u32;
+        let index = (h >> (64 - p)) as usize;
+        // Rank of the first set bit among the remaining 64 - p bits.
+        let rank = ((h << p) | (1 << (p - 1))).leading_zeros() as u8 + 1;
+        registers[index] = registers[index].max(rank);
+    }
+
+    fn merge(&self, registers: &mut Vec<u8>, other: &Vec<u8>) {
+        for (r, &o) in registers.iter_mut().zip(other) {
+            *r = (*r).max(o);
+        }
+    }
+
+    fn finish(&self, registers: &Vec<u8>) -> f64 {
+        let m = registers.len() as f64;
+        let alpha = match registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1. + 1.079 / m),
+        };
+        let harmonic: f64 = registers.iter().map(|&r| (-(r as f64)).exp2()).sum();
+        let estimate = alpha * m * m / harmonic;
+        let zeros = registers.iter().filter(|&&r| r == 0).count();
+        // Linear counting is more accurate while many registers are still empty.
+        if estimate <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        }
+    }
+}
+
+/// A value reported by [`TopK`] with its estimated count. The true count lies in
+/// `count - error ..= count`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeavyHitter<T> {
+    pub value: T,
+    pub count: u64,
+    pub error: u64,
+}
+
+/// Approximate `k` most frequent values with the SpaceSaving algorithm, tracking
+/// `capacity` counters. Every value occurring more than `n / capacity` times in `n`
+/// values is tracked; more counters give tighter counts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TopK {
+    k: usize,
+    capacity: usize,
+}
+
+impl TopK {
+    /// Tracks `10 * k` counters.
+    pub fn new(k: usize) -> Self {
+        Self::with_capacity(k, 10 * k)
+    }
+
+    /// # Panics
+    /// Panics if `k` is zero or exceeds `capacity`.
+    pub fn with_capacity(k: usize, capacity: usize) -> Self {
+        assert!(k > 0, "k must be positive");
+        assert!(capacity >= k, "capacity must be at least k");
+        Self { k, capacity }
+    }
+
+    /// Smallest count of a full summary: the most an untracked value can have occurred.
+    fn floor<T>(&self, counters: &BTreeMap<T, (u64, u64)>) -> u64 {
+        if counters.len() < self.capacity {
+            return 0;
+        }
+        counters.values().map(|&(count, _)| count).min().unwrap_or(0)
+    }
+}
+
+impl<T: Ord + Clone> Aggregator<T> for TopK {
+    /// Counters as `value -> (count, error)`.
+    type State = BTreeMap<T, (u64, u64)>;
+    type Output = Vec<HeavyHitter<T>>;
+
+    fn init(&self) -> Self::State {
+        BTreeMap::new()
+    }
+
+    fn update(&self, counters: &mut Self::State, value: &T) {
+        if let Some((count, _)) = counters.get_mut(value) {
+            *count += 1;
+            return;
+        }
+        if counters.len() < self.capacity {
+            counters.insert(value.clone(), (1, 0));
+            return;
+        }
+        // Evict the smallest counter; the newcomer inherits its count as error.
+        let (evicted, &(min, _)) = counters.iter().min_by_key(|(_, &(count, _))| count).expect("capacity is positive");
+        let evicted = evicted.clone();
+        counters.remove(&evicted);
+        counters.insert(value.clone(), (min + 1, min));
+    }
+
+    fn merge(&self, counters: &mut Self::State, other: &Self::State) {
+        let (floor, other_floor) = (self.floor(counters), self.floor(other));
+        let mut merged: Vec<(T, (u64, u64))> = counters
+            .iter()
+            .map(|(v, &(c, e))| {
+                let (oc, oe) = other.get(v).copied().unwrap_or((other_floor, other_floor));
+                (v.clone(), (c + oc, e + oe))
+            })
+            .collect();
+        let unseen = other.iter().filter(|(v, _)| !counters.contains_key(*v));
+        merged.extend(unseen.map(|(v, &(c, e))| (v.clone(), (c + floor, e + floor))));
+        merged.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then_with(|| a.0.cmp(&b.0)));
+        merged.truncate(self.capacity);
+        *counters = merged.into_iter().collect();
+    }
+
+    /// The `k` largest counters, most frequent first; ties in value order.
+    fn finish(&self, counters: &Self::State) -> Vec<HeavyHitter<T>> {
+        let mut hitters: Vec<HeavyHitter<T>> = counters
+            .iter()
+            .map(|(v, &(count, error))| HeavyHitter { value: v.clone(), count, error })
+            .collect();
+        hitters.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        hitters.truncate(self.k);
+        hitters
+    }
+}
+
+/// Approximate quantiles with a merging t-digest of at most about `compression`
+/// centroids. Centroids near the tails hold few values, so extreme quantiles such as
+/// p99 stay accurate to a fraction of a percent while the middle is summarized coarsely.
+///
+/// The output of a bucket is its [`Digest`] rather than a number, so per-bucket digests
+/// from several shards or processes can be merged with [`merge_digests`] before asking
+/// for percentiles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TDigest {
+    compression: f64,
+}
+
+impl Default for TDigest {
+    fn default() -> Self {
+        Self::new(100.)
+    }
+}
+
+impl TDigest {
+    /// # Panics
+    /// Panics unless `compression` is finite and at least 10.
+    pub fn new(compression: f64) -> Self {
+        assert!(compression.is_finite() && compression >= 10., "compression must be finite and at least 10");
+        Self { compression }
+    }
+
+    pub fn compression(&self) -> f64 {
+        self.compression
+    }
+}
+
+impl Aggregator<f64> for TDigest {
+    type State = Digest;
+    type Output = Digest;
+
+    fn init(&self) -> Digest {
+        Digest::new(self.compression)
+    }
+
+    fn update(&self, digest: &mut Digest, &value: &f64) {
+        digest.add(value);
+    }
+
+    fn merge(&self, digest: &mut Digest, other: &Digest) {
+        digest.merge(other);
+    }
+
+    fn finish(&self, digest: &Digest) -> Digest {
+        let mut digest = digest.clone();
+        digest.compress();
+        digest
+    }
+}
+
+/// The weighted centroids of a [`TDigest`] plus the exact count and extremes of the
+/// values it has seen.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Digest {
+    compression: f64,
+    /// Compressed centroids as `(mean, weight)`, sorted by mean.
+    centroids: Vec<(f64, f64)>,
+    /// Values and centroids added since the last compression.
+    pending: Vec<(f64, f64)>,
+    count: u64,
+    min: f64,
+    max: f64,
+}
+
+impl Digest {
+    /// An empty digest.
+    ///
+    /// # Panics
+    /// Panics unless `compression` is finite and at least 10.
+    pub fn new(compression: f64) -> Self {
+        let compression = TDigest::new(compression).compression;
+        Self {
+            compression,
+            centroids: Vec::new(),
+            pending: Vec::new(),
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    pub fn compression(&self) -> f64 {
+        self.compression
+    }
+
+    /// Number of values seen.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Smallest value seen, NaN if empty.
+    pub fn min(&self) -> f64 {
+        if self.is_empty() {
+            f64::NAN
+        } else {
+            self.min
+        }
+    }
+
+    /// Largest value seen, NaN if empty.
+    pub fn max(&self) -> f64 {
+        if self.is_empty() {
+            f64::NAN
+        } else {
+            self.max
+        }
+    }
+
+    /// Adds one value; NaN is ignored.
+    pub fn add(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.pending.push((value, 1.));
+        if self.pending.len() >= 5 * self.compression.ceil() as usize {
+            self.compress();
+        }
+    }
+
+    /// Folds `other` into this digest, keeping this digest's compression.
+    pub fn merge(&mut self, other: &Digest) {
+        if other.is_empty() {
+            return;
+        }
+        self.count += other.count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.pending.extend_from_slice(&other.centroids);
+        self.pending.extend_from_slice(&other.pending);
+        self.compress();
+    }
+
+    /// Merges the pending values into the centroids, letting each centroid cover at most
+    /// one unit of the arcsine scale `compression / (2π) · asin(2q - 1)`.
+    fn compress(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let mut all = std::mem::take(&mut self.centroids);
+        all.append(&mut self.pending);
+        all.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let total: f64 = all.iter().map(|&(_, w)| w).sum();
+        // Largest quantile the centroid starting at quantile `q` may reach.
+        let limit = |q: f64| {
+            let k = self.compression / std::f64::consts::TAU * (2. * q - 1.).asin() + 1.;
+            let angle = (k * std::f64::consts::TAU / self.compression).min(std::f64::consts::FRAC_PI_2);
+            (angle.sin() + 1.) / 2.
+        };
+        let mut merged = Vec::new();
+        let (mut mean, mut weight) = all[0];
+        let mut before = 0.;
+        let mut bound = limit(0.) * total;
+        for &(m, w) in &all[1..] {
+            if before + weight + w <= bound {
+                weight += w;
+                mean += (m - mean) * w / weight;
+            } else {
+                merged.push((mean, weight));
+                before += weight;
+                bound = limit(before / total) * total;
+                (mean, weight) = (m, w);
+            }
+        }
+        merged.push((mean, weight));
+        self.centroids = merged;
+    }
+
+    /// The approximate `q`-quantile, interpolated between centroid means like
+    /// [`Statistics::quantile`](crate::Statistics::quantile), so it is exact while every
+    /// centroid holds a single value. NaN if empty.
+    ///
+    /// # Panics
+    /// Panics unless `0 <= q <= 1`.
+    pub fn quantile(&self, q: f64) -> f64 {
+        assert!((0. ..=1.).contains(&q), "quantile must be between 0 and 1");
+        if self.is_empty() {
+            return f64::NAN;
+        }
+        if !self.pending.is_empty() {
+            let mut compressed = self.clone();
+            compressed.compress();
+            return compressed.quantile(q);
+        }
+        // Each centroid sits at the middle of its weight, between the exact extremes at
+        // both ends.
+        let total = self.count as f64;
+        let mut before = 0.;
+        let mut points = vec![(0., self.min)];
+        for &(mean, weight) in &self.centroids {
+            points.push((before + weight / 2., mean));
+            before += weight;
+        }
+        points.push((total, self.max));
+        let target = q * (total - 1.) + 0.5;
+        let i = points.partition_point(|&(position, _)| position < target).clamp(1, points.len() - 1);
+        let ((p0, v0), (p1, v1)) = (points[i - 1], points[i]);
+        if p1 <= p0 {
+            return v1;
+        }
+        v0 + (v1 - v0) * (target - p0) / (p1 - p0)
+    }
+}
+
+/// Merges per-bucket digests from several shards into one series over the union of
+/// their buckets.
+pub fn merge_digests(shards: &[&TimestampedSeries<Digest>]) -> TimestampedSeries<Digest> {
+    let mut buckets: BTreeMap<i64, Digest> = BTreeMap::new();
+    for (t, digest) in shards.iter().flat_map(|shard| shard.iter()) {
+        match buckets.get_mut(&t) {
+            Some(merged) => merged.merge(digest),
+            None => {
+                buckets.insert(t, digest.clone());
+            }
+        }
+    }
+    let out: TimestampedSeries<Digest> = buckets.into_iter().collect();
+    match shards.first().and_then(|shard| shard.unit()) {
+        Some(unit) => out.with_unit(unit),
+        None => out,
+    }
+}
+
+impl TimestampedSeries<Digest> {
+    /// The `q`-quantile of every bucket.
+    ///
+    /// # Panics
+    /// Panics unless `0 <= q <= 1`.
+    pub fn quantile_series(&self, q: f64) -> TimestampedSeries<f64> {
+        let out: TimestampedSeries<f64> = self.iter().map(|(t, digest)| (t, digest.quantile(q))).collect();
+        match self.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+
+    /// One column per quantile, named by its percentile (`p50`, `p99.9`, ...), indexed by
+    /// bucket start.
+    ///
+    /// # Panics
+    /// Panics unless every quantile is between 0 and 1.
+    pub fn percentiles(&self, quantiles: &[f64]) -> TimeSeriesFrame {
+        let mut frame = TimeSeriesFrame::new(self.timestamps().to_vec());
+        for &q in quantiles {
+            let (_, column) = self.quantile_series(q).into_parts();
+            let name = format!("p{}", (q * 1e4).round() / 100.);
+            frame.add_column(name.as_str(), column).expect("one value per bucket");
+        }
+        frame
+    }
+}
+
+impl TimestampedSeries<f64> {
+    /// The p50, p95 and p99 of the values in each `step`-long bucket, e.g. of request
+    /// durations, from a default [`TDigest`] per bucket. Use
+    /// [`resample_with`](Self::resample_with) and [`merge_digests`] to combine shards
+    /// first.
+    ///
+    /// # Panics
+    /// Panics if `step` is not positive.
+    pub fn latency_percentiles(&self, step: i64) -> TimeSeriesFrame {
+        self.resample_with(step, &TDigest::default()).percentiles(&[0.5, 0.95, 0.99])
+    }
+}
@@ -0,0 +1,80 @@
+//! Availability reporting over status series, where each status holds until the next
+//! timestamp (`true` = up).
+
+use crate::TimestampedSeries;
+use std::collections::BTreeMap;
+
+/// A maximal period of downtime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Outage {
+    pub start: i64,
+    /// Timestamp at which the service was seen up again, or the last timestamp when
+    /// the outage is still ongoing.
+    pub end: i64,
+    pub ongoing: bool,
+}
+
+impl Outage {
+    pub fn duration(&self) -> i64 {
+        self.end - self.start
+    }
+}
+
+pub trait Availability {
+    /// Fraction of up time within each `window`-long bucket aligned to timestamp 0, keyed
+    /// by the bucket start. Buckets that the series does not cover are omitted.
+    fn availability(&self, window: i64) -> TimestampedSeries<f64>;
+    fn outages(&self) -> Vec<Outage>;
+    /// Error-budget burn rate per bucket: the observed unavailability divided by the
+    /// unavailability allowed by the objective `slo` (e.g. 0.999). A rate of 1 spends the
+    /// budget exactly over the SLO period.
+    fn burn_rate(&self, slo: f64, window: i64) -> TimestampedSeries<f64>;
+}
+
+impl Availability for TimestampedSeries<bool> {
+    fn availability(&self, window: i64) -> TimestampedSeries<f64> {
+        assert!(window > 0, "window must be positive");
+        // bucket start -> (up time, covered time)
+        let mut buckets: BTreeMap<i64, (i64, i64)> = BTreeMap::new();
+        let timestamps = self.timestamps();
+        for (i, (start, &up)) in self.iter().enumerate() {
+            let Some(&end) = timestamps.get(i + 1) else { break };
+            let mut t = start;
+            while t < end {
+                let bucket = t.div_euclid(window) * window;
+                let until = end.min(bucket + window);
+                let entry = buckets.entry(bucket).or_insert((0, 0));
+                if up {
+                    entry.0 += until - t;
+                }
+                entry.1 += until - t;
+                t = until;
+            }
+        }
+        buckets.into_iter().map(|(b, (up, covered))| (b, up as f64 / covered as f64)).collect()
+    }
+
+    fn outages(&self) -> Vec<Outage> {
+        let mut outages = Vec::new();
+        let mut down_since: Option<i64> = None;
+        for (t, &up) in self.iter() {
+            match (up, down_since) {
+                (false, None) => down_since = Some(t),
+                (true, Some(start)) => {
+                    outages.push(Outage { start, end: t, ongoing: false });
+                    down_since = None;
+                }
+                _ => {}
+            }
+        }
+        if let (Some(start), Some(&last)) = (down_since, self.timestamps().last()) {
+            outages.push(Outage { start, end: last, ongoing: true });
+        }
+        outages
+    }
+
+    fn burn_rate(&self, slo: f64, window: i64) -> TimestampedSeries<f64> {
+        assert!(slo > 0. && slo < 1., "slo must be in (0, 1)");
+        self.availability(window).map(|a| (1. - a) / (1. - slo))
+    }
+}
@@ -0,0 +1,64 @@
+//! One-line charts from Unicode block characters, for logs, CLI output and terminals.
+
+use crate::{TimeSeries, TimestampedSeries};
+
+/// Block characters from lowest to highest.
+pub(crate) const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Mean of each of `width` consecutive, near-equal runs of `values`; `values` itself if
+/// it is no longer than `width`. Runs ignore non-finite values and are NaN when empty.
+pub(crate) fn column_means(values: &[f64], width: usize) -> Vec<f64> {
+    let columns = values.len().min(width);
+    (0..columns)
+        .map(|c| {
+            let bin = &values[c * values.len() / columns..(c + 1) * values.len() / columns];
+            let finite = bin.iter().filter(|v| v.is_finite());
+            let count = finite.clone().count();
+            if count == 0 {
+                f64::NAN
+            } else {
+                finite.sum::<f64>() / count as f64
+            }
+        })
+        .collect()
+}
+
+fn render(values: &[f64]) -> String {
+    let (lo, hi) = values
+        .iter()
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    values
+        .iter()
+        .map(|&v| match v {
+            v if !v.is_finite() => ' ',
+            _ if hi == lo => BLOCKS[3],
+            v => BLOCKS[((v - lo) / (hi - lo) * 7.).round() as usize],
+        })
+        .collect()
+}
+
+impl TimeSeries<f64> {
+    /// One block character per value, scaled between the minimum and the maximum, e.g.
+    /// `▁▂▄█▆▃`. Non-finite values are blanks; a constant series is a flat middle line.
+    pub fn sparkline(&self) -> String {
+        render(self.as_slice())
+    }
+
+    /// [`sparkline`](Self::sparkline) at most `width` characters wide, averaging runs of
+    /// consecutive values into one character.
+    pub fn sparkline_width(&self, width: usize) -> String {
+        render(&column_means(self.as_slice(), width))
+    }
+}
+
+impl TimestampedSeries<f64> {
+    /// Sparkline of the values; see [`TimeSeries::sparkline`].
+    pub fn sparkline(&self) -> String {
+        self.values().sparkline()
+    }
+
+    pub fn sparkline_width(&self, width: usize) -> String {
+        self.values().sparkline_width(width)
+    }
+}
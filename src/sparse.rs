@@ -0,0 +1,258 @@
+//! Sparse series that store only the points differing from a common fill value, for
+//! series that are mostly zero (event counts) or mostly constant.
+
+use crate::{Statistics, TimeSeries};
+use std::ops::{Add, Div, Mul, Sub};
+
+/// A series of `len` points equal to `fill` except at the stored `(position, value)`
+/// entries, which are sorted by position and never equal to `fill`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseSeries<T> {
+    len: usize,
+    fill: T,
+    entries: Vec<(usize, T)>,
+}
+
+impl<T: Clone + PartialEq> SparseSeries<T> {
+    /// `len` points all equal to `fill`.
+    pub fn new(len: usize, fill: T) -> Self {
+        Self { len, fill, entries: Vec::new() }
+    }
+
+    /// Stores the points of `series` that differ from `fill`. A NaN fill matches nothing,
+    /// so every point would be stored.
+    pub fn from_dense(series: &TimeSeries<T>, fill: T) -> Self {
+        let entries = series.as_slice().iter().cloned().enumerate().filter(|(_, v)| *v != fill).collect();
+        Self { len: series.len(), fill, entries }
+    }
+
+    /// Builds a series from `(position, value)` entries in any order; of repeated
+    /// positions the last wins.
+    ///
+    /// # Panics
+    /// Panics if a position is not below `len`.
+    pub fn from_entries<I: IntoIterator<Item = (usize, T)>>(len: usize, fill: T, entries: I) -> Self {
+        let mut series = Self::new(len, fill);
+        for (i, v) in entries {
+            series.set(i, v);
+        }
+        series
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn fill(&self) -> &T {
+        &self.fill
+    }
+
+    /// The stored points as `(position, value)`, by position.
+    pub fn entries(&self) -> &[(usize, T)] {
+        &self.entries
+    }
+
+    /// Number of stored points.
+    pub fn nnz(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Fraction of points stored; NaN for an empty series.
+    pub fn density(&self) -> f64 {
+        self.entries.len() as f64 / self.len as f64
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        Some(match self.entries.binary_search_by_key(&index, |&(i, _)| i) {
+            Ok(at) => &self.entries[at].1,
+            Err(_) => &self.fill,
+        })
+    }
+
+    /// Sets the point at `index`, storing it only if it differs from the fill.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, value: T) {
+        assert!(index < self.len, "index {} out of bounds for length {}", index, self.len);
+        match self.entries.binary_search_by_key(&index, |&(i, _)| i) {
+            Ok(at) if value == self.fill => {
+                self.entries.remove(at);
+            }
+            Ok(at) => self.entries[at].1 = value,
+            Err(_) if value == self.fill => {}
+            Err(at) => self.entries.insert(at, (index, value)),
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        if value != self.fill {
+            self.entries.push((self.len, value));
+        }
+        self.len += 1;
+    }
+
+    /// Every point in order, fill included.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let mut entries = self.entries.iter().peekable();
+        (0..self.len).map(move |i| match entries.next_if(|&&(j, _)| j == i) {
+            Some((_, v)) => v,
+            None => &self.fill,
+        })
+    }
+
+    pub fn to_dense(&self) -> TimeSeries<T> {
+        TimeSeries(self.iter().cloned().collect())
+    }
+
+    /// Applies `f` to the fill and the stored points only, so `f` must be pure.
+    pub fn map<U, F>(&self, f: F) -> SparseSeries<U>
+    where
+        U: Clone + PartialEq,
+        F: Fn(&T) -> U,
+    {
+        let fill = f(&self.fill);
+        let entries = self.entries.iter().map(|(i, v)| (*i, f(v))).filter(|(_, v)| *v != fill).collect();
+        SparseSeries { len: self.len, fill, entries }
+    }
+
+    /// Combines two series point by point over their common length, visiting only the
+    /// positions stored in either.
+    pub fn zip_with<F>(&self, other: &Self, f: F) -> Self
+    where
+        F: Fn(&T, &T) -> T,
+    {
+        let len = self.len.min(other.len);
+        let fill = f(&self.fill, &other.fill);
+        let mut entries = Vec::new();
+        let (mut a, mut b) = (self.entries.iter().peekable(), other.entries.iter().peekable());
+        loop {
+            let (i, v) = match (a.peek(), b.peek()) {
+                (Some(&&(i, ref x)), Some(&&(j, ref y))) if i == j => {
+                    a.next();
+                    b.next();
+                    (i, f(x, y))
+                }
+                (Some(&&(i, ref x)), Some(&&(j, _))) if i < j => {
+                    a.next();
+                    (i, f(x, &other.fill))
+                }
+                (Some(&&(i, ref x)), None) => {
+                    a.next();
+                    (i, f(x, &other.fill))
+                }
+                (_, Some(&&(j, ref y))) => {
+                    b.next();
+                    (j, f(&self.fill, y))
+                }
+                (None, None) => break,
+            };
+            if i >= len {
+                break;
+            }
+            if v != fill {
+                entries.push((i, v));
+            }
+        }
+        Self { len, fill, entries }
+    }
+}
+
+impl<T: Clone + PartialEq> From<&SparseSeries<T>> for TimeSeries<T> {
+    fn from(series: &SparseSeries<T>) -> Self {
+        series.to_dense()
+    }
+}
+
+/// Element-wise arithmetic over the common length, touching only stored points.
+macro_rules! sparse_ops {
+    ($($tr:ident $m:ident),*) => {$(
+        impl<T: Clone + PartialEq + $tr<Output = T>> $tr for &SparseSeries<T> {
+            type Output = SparseSeries<T>;
+            fn $m(self, other: Self) -> Self::Output {
+                self.zip_with(other, |x, y| x.clone().$m(y.clone()))
+            }
+        }
+
+        impl<T: Clone + PartialEq + $tr<Output = T>> $tr for SparseSeries<T> {
+            type Output = SparseSeries<T>;
+            fn $m(self, other: Self) -> Self::Output {
+                (&self).$m(&other)
+            }
+        }
+    )*};
+}
+
+sparse_ops!(Add add, Sub sub, Mul mul, Div div);
+
+impl Statistics for SparseSeries<f64> {
+    fn sum(&self) -> f64 {
+        let unstored = (self.len - self.entries.len()) as f64;
+        let fill = if unstored > 0. { self.fill * unstored } else { 0. };
+        fill + self.entries.iter().map(|(_, v)| v).sum::<f64>()
+    }
+
+    fn mean(&self) -> f64 {
+        self.sum() / self.len as f64
+    }
+
+    fn var(&self) -> f64 {
+        if self.len < 2 {
+            return f64::NAN;
+        }
+        let mean = self.mean();
+        let unstored = (self.len - self.entries.len()) as f64;
+        let fill = if unstored > 0. { unstored * (self.fill - mean).powi(2) } else { 0. };
+        let stored: f64 = self.entries.iter().map(|(_, v)| (v - mean).powi(2)).sum();
+        (fill + stored) / (self.len - 1) as f64
+    }
+
+    fn std(&self) -> f64 {
+        self.var().sqrt()
+    }
+
+    fn min(&self) -> f64 {
+        if self.is_empty() {
+            return f64::NAN;
+        }
+        let fill = if self.entries.len() < self.len { self.fill } else { f64::INFINITY };
+        self.entries.iter().map(|&(_, v)| v).fold(fill, f64::min)
+    }
+
+    fn max(&self) -> f64 {
+        if self.is_empty() {
+            return f64::NAN;
+        }
+        let fill = if self.entries.len() < self.len { self.fill } else { f64::NEG_INFINITY };
+        self.entries.iter().map(|&(_, v)| v).fold(fill, f64::max)
+    }
+
+    fn cov(&self, other: &Self) -> f64 {
+        let n = self.len.min(other.len);
+        if n < 2 {
+            return f64::NAN;
+        }
+        let (mean_x, mean_y) = (self.zip_with(other, |x, _| *x).mean(), other.zip_with(self, |y, _| *y).mean());
+        self.zip_with(other, |x, y| (x - mean_x) * (y - mean_y)).sum() / (n - 1) as f64
+    }
+
+    fn corr(&self, other: &Self) -> f64 {
+        let (x, y) = (self.zip_with(other, |x, _| *x), other.zip_with(self, |y, _| *y));
+        x.cov(&y) / (x.std() * y.std())
+    }
+
+    fn quantile(&self, q: f64) -> f64 {
+        self.to_dense().quantile(q)
+    }
+
+    fn median(&self) -> f64 {
+        self.quantile(0.5)
+    }
+}
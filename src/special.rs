@@ -0,0 +1,178 @@
+//! Special functions and distribution helpers used by the statistical code.
+
+/// Standard normal quantile function (Acklam's rational approximation).
+pub(crate) fn normal_ppf(p: f64) -> f64 {
+    if p <= 0. {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1. {
+        return f64::INFINITY;
+    }
+    const A: [f64; 6] = [
+        -3.969683028665376e1, 2.209460984245205e2, -2.759285104469687e2,
+        1.38357751867269e2, -3.066479806614716e1, 2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1, 1.615858368580409e2, -1.556989798598866e2,
+        6.680131188771972e1, -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3, -3.223964580411365e-1, -2.400758277161838,
+        -2.549732539343734, 4.374664141464968, 2.938163982698783,
+    ];
+    const D: [f64; 4] = [7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996, 3.754408661907416];
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.)
+    };
+    if p < 0.02425 {
+        tail((-2. * p.ln()).sqrt())
+    } else if p > 1. - 0.02425 {
+        -tail((-2. * (1. - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.)
+    }
+}
+
+/// Complementary error function (Numerical Recipes' Chebyshev fit, relative error below
+/// 1.2e-7 everywhere).
+pub(crate) fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1. / (1. + 0.5 * z);
+    let poly = -z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807
+                            + t * (-1.13520398 + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let r = t * poly.exp();
+    if x >= 0. {
+        r
+    } else {
+        2. - r
+    }
+}
+
+pub(crate) fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+/// Student t quantile with `df` degrees of freedom, inverting the t distribution
+/// function (an incomplete beta function) by bisection to full precision. NaN unless
+/// `df > 0`.
+pub(crate) fn student_t_ppf(p: f64, df: f64) -> f64 {
+    if p.is_nan() || df.is_nan() || df <= 0. {
+        return f64::NAN;
+    }
+    if p <= 0. || p >= 1. {
+        return if p <= 0. { f64::NEG_INFINITY } else { f64::INFINITY };
+    }
+    if p == 0.5 {
+        return 0.;
+    }
+    if df.is_infinite() {
+        return normal_ppf(p);
+    }
+    // P(|T| >= t) = I_x(df / 2, 1 / 2) with x = df / (df + t²), and 1 - I_x = I_{1-x}(1 / 2, df / 2).
+    // Solve for whichever of x and 1 - x is the smaller, so that t keeps its precision.
+    let tail = 2. * p.min(1. - p);
+    let t = if tail < 0.5 {
+        let x = invert_increasing(|x| incomplete_beta(x, df / 2., 0.5), tail);
+        (df * (1. - x) / x).sqrt()
+    } else {
+        let y = invert_increasing(|y| incomplete_beta(y, 0.5, df / 2.), 1. - tail);
+        (df * y / (1. - y)).sqrt()
+    };
+    if p < 0.5 {
+        -t
+    } else {
+        t
+    }
+}
+
+/// The `x` in `(0, 1)` where the increasing `f` reaches `target`, by bisection, halving
+/// the ratio of the bracket instead of its width while it spans orders of magnitude so
+/// that tiny solutions are found to full relative precision too.
+fn invert_increasing(f: impl Fn(f64) -> f64, target: f64) -> f64 {
+    let (mut lo, mut hi) = (f64::MIN_POSITIVE, 1.);
+    for _ in 0..200 {
+        let mid = if hi > 4. * lo { (lo * hi).sqrt() } else { lo + (hi - lo) / 2. };
+        if mid <= lo || mid >= hi {
+            break;
+        }
+        if f(mid) < target {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    lo + (hi - lo) / 2.
+}
+
+/// Natural log of the gamma function for `x > 0` (Lanczos approximation, g = 7).
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    const G: [f64; 9] = [
+        0.999_999_999_999_809_9, 676.520_368_121_885_1, -1_259.139_216_722_402_8,
+        771.323_428_777_653_1, -176.615_029_162_140_6, 12.507_343_278_686_905,
+        -0.138_571_095_265_720_12, 9.984_369_578_019_572e-6, 1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection formula.
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1. - x);
+    }
+    let x = x - 1.;
+    let t = x + 7.5;
+    let sum = G[1..].iter().enumerate().fold(G[0], |acc, (i, g)| acc + g / (x + i as f64 + 1.));
+    0.5 * (2. * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`, by the continued fraction of
+/// Numerical Recipes evaluated with Lentz's method.
+pub(crate) fn incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0. {
+        return 0.;
+    }
+    if x >= 1. {
+        return 1.;
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1. - x).ln()).exp();
+    // The fraction converges quickly only below the mean; use the symmetry otherwise.
+    if x < (a + 1.) / (a + b + 2.) {
+        front * beta_fraction(x, a, b) / a
+    } else {
+        1. - front * beta_fraction(1. - x, b, a) / b
+    }
+}
+
+fn beta_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let nonzero = |v: f64| if v.abs() < TINY { TINY } else { v };
+    let mut c = 1.;
+    let mut d = 1. / nonzero(1. - (a + b) * x / (a + 1.));
+    let mut h = d;
+    for m in 1..=300 {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2. * m - 1.) * (a + 2. * m));
+        d = 1. / nonzero(1. + even * d);
+        c = nonzero(1. + even / c);
+        h *= d * c;
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2. * m) * (a + 2. * m + 1.));
+        d = 1. / nonzero(1. + odd * d);
+        c = nonzero(1. + odd / c);
+        h *= d * c;
+        if (d * c - 1.).abs() < 1e-15 {
+            break;
+        }
+    }
+    h
+}
+
+/// Two-sided tail probability `P(|T| >= |t|)` of Student's t with `df` degrees of freedom.
+pub(crate) fn student_t_two_sided(t: f64, df: f64) -> f64 {
+    incomplete_beta(df / (df + t * t), df / 2., 0.5)
+}
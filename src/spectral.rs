@@ -0,0 +1,269 @@
+//! Spectral estimates: Welch power and cross-spectral densities, coherence and the
+//! short-time Fourier transform.
+//!
+//! Frequencies are in cycles per sample, from 0 to the Nyquist frequency 0.5; multiply by
+//! the sampling rate to get Hz.
+
+use crate::fft::{fft, ifft};
+use crate::{Error, TimeSeries};
+use std::f64::consts::PI;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// Complex number used for spectra and analytic signals.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub fn from_polar(r: f64, theta: f64) -> Self {
+        Self { re: r * theta.cos(), im: r * theta.sin() }
+    }
+
+    pub fn conj(&self) -> Self {
+        Self { re: self.re, im: -self.im }
+    }
+
+    pub fn norm_sqr(&self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    pub fn abs(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    pub fn arg(&self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn scale(&self, k: f64) -> Self {
+        Self { re: self.re * k, im: self.im * k }
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self { re: self.re + rhs.re, im: self.im + rhs.im }
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self { re: self.re - rhs.re, im: self.im - rhs.im }
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            re: self.re * rhs.re - self.im * rhs.im,
+            im: self.re * rhs.im + self.im * rhs.re,
+        }
+    }
+}
+
+impl Neg for Complex {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self { re: -self.re, im: -self.im }
+    }
+}
+
+/// Values indexed by frequency.
+#[derive(Clone, Debug)]
+pub struct Spectrum<T> {
+    pub frequencies: Vec<f64>,
+    pub values: TimeSeries<T>,
+}
+
+/// Taper applied to each segment before transforming it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl Window {
+    /// Periodic (DFT-even) window coefficients of length `len`.
+    pub fn coefficients(&self, len: usize) -> Vec<f64> {
+        (0..len)
+            .map(|i| {
+                let phase = 2. * PI * i as f64 / len as f64;
+                match self {
+                    Window::Rectangular => 1.,
+                    Window::Hann => 0.5 - 0.5 * phase.cos(),
+                    Window::Hamming => 0.54 - 0.46 * phase.cos(),
+                    Window::Blackman => 0.42 - 0.5 * phase.cos() + 0.08 * (2. * phase).cos(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Time-frequency matrix from [`Spectral::stft`]: one frame per window position.
+#[derive(Clone, Debug)]
+pub struct Spectrogram {
+    pub frequencies: Vec<f64>,
+    /// Index of the first point of each frame.
+    pub offsets: Vec<usize>,
+    /// One-sided transform of each frame, `frames[t][k]` at `frequencies[k]`.
+    pub frames: Vec<Vec<Complex>>,
+}
+
+impl Spectrogram {
+    /// Squared magnitudes, the usual spectrogram image.
+    pub fn power(&self) -> Vec<Vec<f64>> {
+        self.frames.iter().map(|f| f.iter().map(Complex::norm_sqr).collect()).collect()
+    }
+
+    pub fn magnitude(&self) -> Vec<Vec<f64>> {
+        self.frames.iter().map(|f| f.iter().map(Complex::abs).collect()).collect()
+    }
+}
+
+/// Transforms of the mean-removed, Hann-tapered segments of `x` (half overlapping).
+fn welch_segments(x: &[f64], segment_len: usize) -> Vec<Vec<Complex>> {
+    let window = Window::Hann.coefficients(segment_len);
+    let hop = (segment_len / 2).max(1);
+    (0..=x.len() - segment_len)
+        .step_by(hop)
+        .map(|start| {
+            let segment = &x[start..start + segment_len];
+            let mean = segment.iter().sum::<f64>() / segment_len as f64;
+            let mut buf: Vec<Complex> =
+                segment.iter().zip(&window).map(|(v, w)| Complex::new((v - mean) * w, 0.)).collect();
+            fft(&mut buf, false);
+            buf.truncate(segment_len / 2 + 1);
+            buf
+        })
+        .collect()
+}
+
+/// One-sided Welch cross-spectral density `E[conj(X) Y]`.
+fn welch_csd(x: &[f64], y: &[f64], segment_len: usize) -> Result<Spectrum<Complex>, Error> {
+    if x.len() != y.len() {
+        return Err(Error::LengthMismatch { expected: x.len(), found: y.len() });
+    }
+    if segment_len < 2 || x.len() < segment_len {
+        return Err(Error::InsufficientData { required: segment_len.max(2), found: x.len() });
+    }
+    let sx = welch_segments(x, segment_len);
+    let sy = welch_segments(y, segment_len);
+    let bins = segment_len / 2 + 1;
+    let power: f64 = Window::Hann.coefficients(segment_len).iter().map(|w| w * w).sum();
+    let scale = 1. / (power * sx.len() as f64);
+    let values = (0..bins)
+        .map(|k| {
+            let sum = sx.iter().zip(&sy).fold(Complex::default(), |acc, (a, b)| acc + a[k].conj() * b[k]);
+            // Fold negative frequencies onto positive ones, except DC and Nyquist.
+            let one_sided = if k == 0 || (segment_len.is_multiple_of(2) && k == bins - 1) { 1. } else { 2. };
+            sum.scale(scale * one_sided)
+        })
+        .collect();
+    let frequencies = (0..bins).map(|k| k as f64 / segment_len as f64).collect();
+    Ok(Spectrum { frequencies, values })
+}
+
+pub trait Spectral {
+    /// Welch estimate of the one-sided power spectral density, averaging half-overlapping
+    /// Hann-windowed segments of `segment_len` points.
+    fn power_spectral_density(&self, segment_len: usize) -> Result<Spectrum<f64>, Error>;
+    /// Welch estimate of the one-sided cross-spectral density with `other`.
+    fn cross_spectral_density(&self, other: &Self, segment_len: usize) -> Result<Spectrum<Complex>, Error>;
+    /// Magnitude-squared coherence with `other`, in `[0, 1]` at each frequency: how well
+    /// one series is explained by a linear filter of the other at that frequency.
+    fn coherence(&self, other: &Self, segment_len: usize) -> Result<Spectrum<f64>, Error>;
+    /// Short-time Fourier transform over frames of `window` points tapered by `taper`,
+    /// starting every `hop` points. Trailing points that do not fill a frame are dropped.
+    fn stft(&self, window: usize, hop: usize, taper: Window) -> Spectrogram;
+    /// Analytic signal `x + i H(x)`, where `H` is the Hilbert transform, computed by
+    /// zeroing the negative frequencies of the whole series.
+    fn hilbert(&self) -> TimeSeries<Complex>;
+    /// Instantaneous amplitude, the magnitude of the analytic signal.
+    fn envelope(&self) -> TimeSeries<f64>;
+    /// Instantaneous frequency in cycles per sample, from the unwrapped phase difference
+    /// of consecutive analytic-signal points. The output has `len - 1` points.
+    fn instantaneous_frequency(&self) -> TimeSeries<f64>;
+}
+
+impl Spectral for TimeSeries<f64> {
+    fn power_spectral_density(&self, segment_len: usize) -> Result<Spectrum<f64>, Error> {
+        let csd = welch_csd(self.as_slice(), self.as_slice(), segment_len)?;
+        Ok(Spectrum { frequencies: csd.frequencies, values: csd.values.map(|v| v.re) })
+    }
+
+    fn cross_spectral_density(&self, other: &Self, segment_len: usize) -> Result<Spectrum<Complex>, Error> {
+        welch_csd(self.as_slice(), other.as_slice(), segment_len)
+    }
+
+    fn coherence(&self, other: &Self, segment_len: usize) -> Result<Spectrum<f64>, Error> {
+        let pxy = welch_csd(self.as_slice(), other.as_slice(), segment_len)?;
+        let pxx = welch_csd(self.as_slice(), self.as_slice(), segment_len)?;
+        let pyy = welch_csd(other.as_slice(), other.as_slice(), segment_len)?;
+        let values = (0..pxy.values.len())
+            .map(|k| pxy.values[k].norm_sqr() / (pxx.values[k].re * pyy.values[k].re))
+            .collect();
+        Ok(Spectrum { frequencies: pxy.frequencies, values })
+    }
+
+    fn stft(&self, window: usize, hop: usize, taper: Window) -> Spectrogram {
+        assert!(window > 0 && hop > 0, "window and hop must be positive");
+        let coefficients = taper.coefficients(window);
+        let offsets: Vec<usize> =
+            if self.len() < window { Vec::new() } else { (0..=self.len() - window).step_by(hop).collect() };
+        let frames = offsets
+            .iter()
+            .map(|&start| {
+                let mut buf: Vec<Complex> = self.as_slice()[start..start + window]
+                    .iter()
+                    .zip(&coefficients)
+                    .map(|(v, w)| Complex::new(v * w, 0.))
+                    .collect();
+                fft(&mut buf, false);
+                buf.truncate(window / 2 + 1);
+                buf
+            })
+            .collect();
+        let frequencies = (0..window / 2 + 1).map(|k| k as f64 / window as f64).collect();
+        Spectrogram { frequencies, offsets, frames }
+    }
+
+    fn hilbert(&self) -> TimeSeries<Complex> {
+        let n = self.len();
+        let mut buf: Vec<Complex> = self.as_slice().iter().map(|&v| Complex::new(v, 0.)).collect();
+        fft(&mut buf, false);
+        // Keep DC (and Nyquist for even lengths), double positive and drop negative frequencies.
+        for (k, v) in buf.iter_mut().enumerate().skip(1) {
+            if 2 * k < n {
+                *v = v.scale(2.);
+            } else if 2 * k > n {
+                *v = Complex::default();
+            }
+        }
+        ifft(&mut buf);
+        TimeSeries(buf)
+    }
+
+    fn envelope(&self) -> TimeSeries<f64> {
+        self.hilbert().map(Complex::abs)
+    }
+
+    fn instantaneous_frequency(&self) -> TimeSeries<f64> {
+        let analytic = self.hilbert();
+        analytic
+            .as_slice()
+            .windows(2)
+            .map(|w| (w[1] * w[0].conj()).arg() / (2. * PI))
+            .collect()
+    }
+}
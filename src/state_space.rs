@@ -0,0 +1,396 @@
+//! Linear Gaussian structural state-space models (level, trend, seasonal), with Kalman
+//! filtering, disturbance smoothing and maximum-likelihood variance estimation.
+//!
+//! Notation follows Durbin & Koopman: `y_t = Z a_t + e_t`, `a_{t+1} = T a_t + n_t` with
+//! `Var(e_t) = H` and a diagonal `Var(n_t) = Q`.
+
+use crate::forecast::gaussian_quantiles;
+use crate::optimize::nelder_mead_monitored;
+use crate::{Error, Forecaster, Monitor, TimeSeries};
+
+/// Components of a structural time-series model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Structural {
+    /// Include a stochastic slope in addition to the level.
+    pub trend: bool,
+    /// Include a dummy-seasonal component with this period.
+    pub period: Option<usize>,
+}
+
+impl Structural {
+    /// Random-walk level plus noise.
+    pub fn local_level() -> Self {
+        Self { trend: false, period: None }
+    }
+
+    /// Level whose slope is itself a random walk.
+    pub fn local_linear_trend() -> Self {
+        Self { trend: true, period: None }
+    }
+
+    /// Adds a seasonal component of the given period.
+    pub fn with_seasonal(self, period: usize) -> Self {
+        assert!(period >= 2, "seasonal period must be at least 2");
+        Self { period: Some(period), ..self }
+    }
+
+    pub(crate) fn state_dim(&self) -> usize {
+        1 + self.trend as usize + self.period.map_or(0, |s| s - 1)
+    }
+
+    /// Number of variance parameters: observation noise plus one per component.
+    pub(crate) fn n_variances(&self) -> usize {
+        2 + self.trend as usize + self.period.is_some() as usize
+    }
+
+    fn seasonal_offset(&self) -> usize {
+        1 + self.trend as usize
+    }
+
+    /// Builds the system matrices for the given variances
+    /// `[observation, level, (slope), (seasonal)]`.
+    pub(crate) fn system(&self, variances: &[f64]) -> Ssm {
+        let m = self.state_dim();
+        let mut z = vec![0.; m];
+        let mut t = vec![vec![0.; m]; m];
+        let mut q = vec![0.; m];
+        z[0] = 1.;
+        t[0][0] = 1.;
+        q[0] = variances[1];
+        let mut next = 2;
+        if self.trend {
+            t[0][1] = 1.;
+            t[1][1] = 1.;
+            q[1] = variances[next];
+            next += 1;
+        }
+        if let Some(s) = self.period {
+            let o = self.seasonal_offset();
+            z[o] = 1.;
+            for j in 0..s - 1 {
+                t[o][o + j] = -1.;
+            }
+            for j in 1..s - 1 {
+                t[o + j][o + j - 1] = 1.;
+            }
+            q[o] = variances[next];
+        }
+        Ssm { z, t, q, h: variances[0] }
+    }
+}
+
+pub(crate) type Matrix = Vec<Vec<f64>>;
+
+/// System matrices of a univariate state-space model.
+#[derive(Clone, Debug)]
+pub(crate) struct Ssm {
+    pub z: Vec<f64>,
+    pub t: Matrix,
+    pub q: Vec<f64>,
+    pub h: f64,
+}
+
+/// Variance of the (approximately diffuse) initial state.
+pub(crate) const DIFFUSE: f64 = 1e7;
+
+pub(crate) struct Filtered {
+    /// Predicted state means `a_t` and covariances `P_t`.
+    pub a: Vec<Vec<f64>>,
+    pub p: Vec<Matrix>,
+    /// Innovations and their variances; `None` at missing observations.
+    pub v: Vec<Option<(f64, f64)>>,
+    pub loglik: f64,
+}
+
+pub(crate) fn mat_vec(m: &[Vec<f64>], v: &[f64]) -> Vec<f64> {
+    m.iter().map(|row| row.iter().zip(v).map(|(a, b)| a * b).sum()).collect()
+}
+
+fn mat_mul(a: &[Vec<f64>], b: &[Vec<f64>]) -> Matrix {
+    let n = b[0].len();
+    a.iter()
+        .map(|row| (0..n).map(|j| row.iter().zip(b).map(|(x, b_row)| x * b_row[j]).sum()).collect())
+        .collect()
+}
+
+fn transpose(a: &[Vec<f64>]) -> Matrix {
+    (0..a[0].len()).map(|j| a.iter().map(|row| row[j]).collect()).collect()
+}
+
+impl Ssm {
+    pub(crate) fn initial_state(&self, y: &[Option<f64>]) -> (Vec<f64>, Matrix) {
+        let m = self.z.len();
+        let mut a = vec![0.; m];
+        a[0] = y.iter().flatten().next().copied().unwrap_or(0.);
+        let p = (0..m).map(|i| (0..m).map(|j| if i == j { DIFFUSE } else { 0. }).collect()).collect();
+        (a, p)
+    }
+
+    /// One Kalman step: returns the innovation (if observed) and the next predicted state.
+    pub(crate) fn step(
+        &self,
+        a: &[f64],
+        p: &[Vec<f64>],
+        y: Option<f64>,
+    ) -> (Option<(f64, f64)>, Vec<f64>, Matrix) {
+        let ta = mat_vec(&self.t, a);
+        let tpt = mat_mul(&mat_mul(&self.t, p), &transpose(&self.t));
+        let (innovation, a_next, mut p_next) = match y {
+            Some(y) => {
+                let pz = mat_vec(p, &self.z);
+                let f = self.z.iter().zip(&pz).map(|(z, x)| z * x).sum::<f64>() + self.h;
+                let v = y - self.z.iter().zip(a).map(|(z, x)| z * x).sum::<f64>();
+                let k = mat_vec(&self.t, &pz).into_iter().map(|x| x / f).collect::<Vec<f64>>();
+                let a_next = ta.iter().zip(&k).map(|(x, k)| x + k * v).collect();
+                // P_{t+1} = T P T' - K F K' + Q
+                let p_next = tpt
+                    .iter()
+                    .enumerate()
+                    .map(|(i, row)| row.iter().enumerate().map(|(j, x)| x - k[i] * k[j] * f).collect())
+                    .collect();
+                (Some((v, f)), a_next, p_next)
+            }
+            None => (None, ta, tpt),
+        };
+        for (i, q) in self.q.iter().enumerate() {
+            p_next[i][i] += q;
+        }
+        (innovation, a_next, p_next)
+    }
+
+    pub(crate) fn filter(&self, y: &[Option<f64>]) -> Filtered {
+        let (mut a, mut p) = self.initial_state(y);
+        let m = self.z.len();
+        let mut out = Filtered { a: Vec::new(), p: Vec::new(), v: Vec::new(), loglik: 0. };
+        let mut observed = 0;
+        for &obs in y {
+            let (innovation, a_next, p_next) = self.step(&a, &p, obs);
+            if let Some((v, f)) = innovation {
+                observed += 1;
+                // The first `m` observations only pin down the diffuse initial state.
+                if observed > m {
+                    out.loglik -= 0.5 * ((2. * std::f64::consts::PI * f).ln() + v * v / f);
+                }
+            }
+            out.a.push(std::mem::replace(&mut a, a_next));
+            out.p.push(std::mem::replace(&mut p, p_next));
+            out.v.push(innovation);
+        }
+        out
+    }
+
+    /// Smoothed state means given all observations.
+    pub(crate) fn smooth(&self, filtered: &Filtered) -> Vec<Vec<f64>> {
+        let m = self.z.len();
+        let n = filtered.a.len();
+        let mut r = vec![0.; m];
+        let mut smoothed = vec![Vec::new(); n];
+        for t in (0..n).rev() {
+            let p = &filtered.p[t];
+            // r_{t-1} = Z' v/F + L' r_t with L = T - K Z, or T' r_t when y_t is missing.
+            let tr = mat_vec(&transpose(&self.t), &r);
+            r = match filtered.v[t] {
+                Some((v, f)) => {
+                    let pz = mat_vec(p, &self.z);
+                    let k = mat_vec(&self.t, &pz).into_iter().map(|x| x / f).collect::<Vec<f64>>();
+                    let kr: f64 = k.iter().zip(&r).map(|(k, r)| k * r).sum();
+                    self.z.iter().zip(&tr).map(|(z, tr)| z * (v / f - kr) + tr).collect()
+                }
+                None => tr,
+            };
+            let pr = mat_vec(p, &r);
+            smoothed[t] = filtered.a[t].iter().zip(&pr).map(|(a, x)| a + x).collect();
+        }
+        smoothed
+    }
+}
+
+/// Maximum-likelihood estimate of the variances of `model`, searched on the log scale.
+pub(crate) fn estimate_variances(
+    model: &Structural,
+    y: &[Option<f64>],
+    monitor: &mut Monitor,
+) -> Result<Vec<f64>, Error> {
+    let observed: Vec<f64> = y.iter().flatten().copied().collect();
+    let required = model.state_dim() + 2;
+    if observed.len() < required {
+        return Err(Error::InsufficientData { required, found: observed.len() });
+    }
+    let mean = observed.iter().sum::<f64>() / observed.len() as f64;
+    let scale = (observed.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / observed.len() as f64)
+        .max(f64::MIN_POSITIVE);
+    let objective = |log_var: &[f64]| {
+        let variances: Vec<f64> = log_var.iter().map(|x| scale * x.exp()).collect();
+        -model.system(&variances).filter(y).loglik
+    };
+    let start = vec![(0.1f64).ln(); model.n_variances()];
+    let best = nelder_mead_monitored(objective, &start, 1., 500 * model.n_variances(), monitor);
+    monitor.check()?;
+    Ok(best.iter().map(|x| scale * x.exp()).collect())
+}
+
+/// Dynamic linear model updated one observation at a time, for streaming nowcasts.
+///
+/// The state is the filtered posterior of the structural components; each [`update`]
+/// is a closed-form Kalman step, so the cost per point does not grow with history.
+///
+/// [`update`]: Dlm::update
+#[derive(Clone, Debug)]
+pub struct Dlm {
+    model: Structural,
+    system: Ssm,
+    /// Predicted state mean and covariance for the next observation.
+    a: Vec<f64>,
+    p: Matrix,
+    /// Filtered state mean after the last update.
+    filtered: Vec<f64>,
+    observations: usize,
+}
+
+impl Dlm {
+    /// Model with known variances `[observation, level, (slope), (seasonal)]` and a
+    /// diffuse prior centred on `initial_level`.
+    pub fn new(model: Structural, variances: &[f64], initial_level: f64) -> Self {
+        assert_eq!(variances.len(), model.n_variances(), "one variance per component expected");
+        let system = model.system(variances);
+        let (a, p) = system.initial_state(&[Some(initial_level)]);
+        Self { model, system, filtered: a.clone(), a, p, observations: 0 }
+    }
+
+    /// Estimates the variances from `history` by maximum likelihood and filters through
+    /// it, ready to continue with new points.
+    pub fn fit(model: Structural, history: &TimeSeries<Option<f64>>) -> Result<Self, Error> {
+        Self::fit_monitored(model, history, &mut Monitor::new())
+    }
+
+    /// [`fit`](Self::fit), reporting the progress of the likelihood search to `monitor`.
+    pub fn fit_monitored(
+        model: Structural,
+        history: &TimeSeries<Option<f64>>,
+        monitor: &mut Monitor,
+    ) -> Result<Self, Error> {
+        trace_span!("fit", model = "structural", n = history.len());
+        let variances = estimate_variances(&model, history.as_slice(), monitor)?;
+        let first = history.as_slice().iter().flatten().next().copied().unwrap_or(0.);
+        let mut dlm = Self::new(model, &variances, first);
+        for &y in history.as_slice() {
+            dlm.update(y);
+        }
+        Ok(dlm)
+    }
+
+    pub fn model(&self) -> Structural {
+        self.model
+    }
+
+    /// Number of observed (non-missing) points absorbed so far.
+    pub fn observations(&self) -> usize {
+        self.observations
+    }
+
+    /// Absorbs the next point; `None` advances time without an observation.
+    pub fn update(&mut self, y: Option<f64>) {
+        let m = self.a.len();
+        self.filtered = match y {
+            Some(y) => {
+                let pz = mat_vec(&self.p, &self.system.z);
+                let f = self.system.z.iter().zip(&pz).map(|(z, x)| z * x).sum::<f64>() + self.system.h;
+                let v = y - self.system.z.iter().zip(&self.a).map(|(z, x)| z * x).sum::<f64>();
+                (0..m).map(|i| self.a[i] + pz[i] * v / f).collect()
+            }
+            None => self.a.clone(),
+        };
+        let (_, a_next, p_next) = self.system.step(&self.a, &self.p, y);
+        self.a = a_next;
+        self.p = p_next;
+        self.observations += y.is_some() as usize;
+    }
+
+    /// Filtered level after the last update.
+    pub fn level(&self) -> f64 {
+        self.filtered[0]
+    }
+
+    /// Filtered slope, for models with a trend.
+    pub fn slope(&self) -> Option<f64> {
+        self.model.trend.then(|| self.filtered[1])
+    }
+
+    /// One-step-ahead forecast mean and variance of the next observation.
+    pub fn one_step_forecast(&self) -> (f64, f64) {
+        let z = &self.system.z;
+        let mean = z.iter().zip(&self.a).map(|(z, a)| z * a).sum();
+        let pz = mat_vec(&self.p, z);
+        let var = z.iter().zip(&pz).map(|(z, x)| z * x).sum::<f64>() + self.system.h;
+        (mean, var)
+    }
+
+    /// Forecast means for the next `horizon` observations.
+    pub fn forecast(&self, horizon: usize) -> TimeSeries<f64> {
+        let mut a = self.a.clone();
+        let mut out = Vec::with_capacity(horizon);
+        for _ in 0..horizon {
+            out.push(self.system.z.iter().zip(&a).map(|(z, a)| z * a).sum());
+            a = mat_vec(&self.system.t, &a);
+        }
+        TimeSeries(out)
+    }
+
+    /// Variances of the forecasts of the next `horizon` observations.
+    pub fn forecast_variances(&self, horizon: usize) -> TimeSeries<f64> {
+        let z = &self.system.z;
+        let t_transposed = transpose(&self.system.t);
+        let mut p = self.p.clone();
+        let mut out = Vec::with_capacity(horizon);
+        for _ in 0..horizon {
+            let pz = mat_vec(&p, z);
+            out.push(z.iter().zip(&pz).map(|(z, x)| z * x).sum::<f64>() + self.system.h);
+            p = mat_mul(&mat_mul(&self.system.t, &p), &t_transposed);
+            for (i, q) in self.system.q.iter().enumerate() {
+                p[i][i] += q;
+            }
+        }
+        TimeSeries(out)
+    }
+}
+
+impl Forecaster for Dlm {
+    /// Re-estimates the variances of the same structural model on `series`, reading
+    /// NaN as missing.
+    fn fit(&mut self, series: &TimeSeries<f64>) -> Result<(), Error> {
+        let history = series.map(|&y| (!y.is_nan()).then_some(y));
+        *self = Dlm::fit_monitored(self.model, &history, &mut Monitor::new())?;
+        Ok(())
+    }
+
+    fn predict(&self, horizon: usize) -> TimeSeries<f64> {
+        self.forecast(horizon)
+    }
+
+    fn predict_quantiles(&self, horizon: usize, quantiles: &[f64]) -> Vec<TimeSeries<f64>> {
+        let variances = self.forecast_variances(horizon);
+        gaussian_quantiles(&self.forecast(horizon), quantiles, |h| variances[h - 1].sqrt())
+    }
+}
+
+/// Missing-value filling on top of a state-space model.
+pub trait Impute {
+    /// Fills missing points with the Kalman-smoothed signal of a structural model whose
+    /// variances are estimated by maximum likelihood, so gaps follow the fitted
+    /// level, trend and seasonality rather than a straight line.
+    fn impute_kalman(&self, model: Structural) -> Result<TimeSeries<f64>, Error>;
+}
+
+impl Impute for TimeSeries<Option<f64>> {
+    fn impute_kalman(&self, model: Structural) -> Result<TimeSeries<f64>, Error> {
+        let variances = estimate_variances(&model, self.as_slice(), &mut Monitor::new())?;
+        let system = model.system(&variances);
+        let smoothed = system.smooth(&system.filter(self.as_slice()));
+        Ok(self
+            .as_slice()
+            .iter()
+            .zip(&smoothed)
+            .map(|(y, state)| y.unwrap_or_else(|| system.z.iter().zip(state).map(|(z, a)| z * a).sum()))
+            .collect())
+    }
+}
@@ -0,0 +1,99 @@
+//! Descriptive statistics over the values of a series.
+//!
+//! Undefined results (e.g. the mean of an empty series) are reported as NaN rather than
+//! panicking, so statistics can be chained over windows without special-casing.
+
+use crate::TimeSeries;
+
+pub trait Statistics {
+    fn sum(&self) -> f64;
+    fn mean(&self) -> f64;
+    /// Sample variance (denominator `n - 1`).
+    fn var(&self) -> f64;
+    /// Sample standard deviation.
+    fn std(&self) -> f64;
+    fn min(&self) -> f64;
+    fn max(&self) -> f64;
+    /// Sample covariance with another series, over their common length.
+    fn cov(&self, other: &Self) -> f64;
+    /// Pearson correlation with another series, over their common length.
+    fn corr(&self, other: &Self) -> f64;
+    /// Quantile `q` in `[0, 1]` using linear interpolation between order statistics.
+    fn quantile(&self, q: f64) -> f64;
+    fn median(&self) -> f64;
+}
+
+impl Statistics for TimeSeries<f64> {
+    fn sum(&self) -> f64 {
+        self.as_slice().iter().sum()
+    }
+
+    fn mean(&self) -> f64 {
+        self.sum() / self.len() as f64
+    }
+
+    fn var(&self) -> f64 {
+        self.cov(self)
+    }
+
+    fn std(&self) -> f64 {
+        self.var().sqrt()
+    }
+
+    fn min(&self) -> f64 {
+        if self.is_empty() {
+            return f64::NAN;
+        }
+        self.as_slice().iter().copied().fold(f64::INFINITY, f64::min)
+    }
+
+    fn max(&self) -> f64 {
+        if self.is_empty() {
+            return f64::NAN;
+        }
+        self.as_slice().iter().copied().fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    fn cov(&self, other: &Self) -> f64 {
+        let n = self.len().min(other.len());
+        if n < 2 {
+            return f64::NAN;
+        }
+        let mean_x = self.as_slice()[..n].iter().sum::<f64>() / n as f64;
+        let mean_y = other.as_slice()[..n].iter().sum::<f64>() / n as f64;
+        let s: f64 = self.as_slice()[..n]
+            .iter()
+            .zip(other.as_slice()[..n].iter())
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        s / (n - 1) as f64
+    }
+
+    fn corr(&self, other: &Self) -> f64 {
+        let n = self.len().min(other.len());
+        let x = self.slice(0..n);
+        let y = other.slice(0..n);
+        x.cov(&y) / (x.std() * y.std())
+    }
+
+    fn quantile(&self, q: f64) -> f64 {
+        if self.is_empty() {
+            return f64::NAN;
+        }
+        let mut sorted = self.as_slice().to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        quantile_sorted(&sorted, q)
+    }
+
+    fn median(&self) -> f64 {
+        self.quantile(0.5)
+    }
+}
+
+/// Linear-interpolated quantile of already sorted, non-empty data.
+pub(crate) fn quantile_sorted(sorted: &[f64], q: f64) -> f64 {
+    let pos = q.clamp(0., 1.) * (sorted.len() - 1) as f64;
+    let lo = pos.floor() as usize;
+    let hi = pos.ceil() as usize;
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
@@ -0,0 +1,172 @@
+//! Step-function series, where each value holds from its timestamp until the next one,
+//! as setpoints, prices and configuration values do.
+//!
+//! A [`StepSeries`] reads values by hold-last rather than interpolation: it is undefined
+//! before its first point and holds its last value indefinitely. Integrals and
+//! time-weighted statistics are taken over an explicit `[start, end)` window so that
+//! the open-ended last step gets a duration.
+
+use crate::TimestampedSeries;
+
+/// A series read as a piecewise-constant function of time.
+#[derive(Clone, Debug)]
+pub struct StepSeries<T>(TimestampedSeries<T>);
+
+impl<T> StepSeries<T> {
+    pub fn new(series: TimestampedSeries<T>) -> Self {
+        Self(series)
+    }
+
+    pub fn series(&self) -> &TimestampedSeries<T> {
+        &self.0
+    }
+
+    pub fn into_series(self) -> TimestampedSeries<T> {
+        self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The value in force at `timestamp`; `None` before the first step.
+    pub fn value_at(&self, timestamp: i64) -> Option<&T> {
+        self.0.asof(timestamp)
+    }
+
+    /// The steps in force during `[start, end)`, each clipped to the window, as
+    /// `(from, to, value)`.
+    fn segments(&self, start: i64, end: i64) -> impl Iterator<Item = (i64, i64, &T)> + '_ {
+        let ts = self.0.timestamps();
+        let first = self.0.asof_index(start).unwrap_or(0);
+        (first..ts.len()).map_while(move |i| {
+            let from = ts[i].max(start);
+            let to = ts.get(i + 1).map_or(end, |&next| next.min(end));
+            (from < end).then_some((from, to, &self.0.values().as_slice()[i]))
+        })
+    }
+
+    /// Time during `[start, end)` for which `f` holds of the value in force.
+    pub fn duration_where<F>(&self, start: i64, end: i64, f: F) -> i64
+    where
+        F: Fn(&T) -> bool,
+    {
+        self.segments(start, end).filter(|(_, _, v)| f(v)).map(|(from, to, _)| to - from).sum()
+    }
+}
+
+impl<T: Clone> StepSeries<T> {
+    /// The values in force at `start`, `start + step`, ... up to `end`, skipping grid
+    /// points before the first step.
+    ///
+    /// # Panics
+    /// Panics if `step` is not positive.
+    pub fn sample(&self, start: i64, end: i64, step: i64) -> TimestampedSeries<T> {
+        assert!(step > 0, "step must be positive");
+        let mut out = TimestampedSeries::new();
+        let mut t = start;
+        while t <= end {
+            if let Some(v) = self.value_at(t) {
+                out.push(t, v.clone());
+            }
+            t = match t.checked_add(step) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        match self.0.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+
+    /// Both series on the union of their timestamps, each holding its last value, from
+    /// the first timestamp at which both are defined.
+    pub fn join<U: Clone>(&self, other: &StepSeries<U>) -> StepSeries<(T, U)> {
+        let mut timestamps: Vec<i64> = self.0.timestamps().iter().chain(other.0.timestamps()).copied().collect();
+        timestamps.sort_unstable();
+        timestamps.dedup();
+        let joined: TimestampedSeries<(T, U)> = timestamps
+            .into_iter()
+            .filter_map(|t| Some((t, (self.value_at(t)?.clone(), other.value_at(t)?.clone()))))
+            .collect();
+        StepSeries(match self.0.unit().or(other.0.unit()) {
+            Some(unit) => joined.with_unit(unit),
+            None => joined,
+        })
+    }
+}
+
+impl<T: Clone + PartialEq> StepSeries<T> {
+    /// Drops points that repeat the value already in force, which describe the same
+    /// function.
+    pub fn compact(&self) -> Self {
+        let mut out = TimestampedSeries::new();
+        let mut current: Option<&T> = None;
+        for (t, v) in self.0.iter() {
+            if current != Some(v) {
+                out.push(t, v.clone());
+                current = Some(v);
+            }
+        }
+        StepSeries(match self.0.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        })
+    }
+}
+
+impl StepSeries<f64> {
+    /// The area under the function over `[start, end)`, in value x timestamp units.
+    /// Time before the first step contributes nothing.
+    pub fn integral(&self, start: i64, end: i64) -> f64 {
+        self.segments(start, end).map(|(from, to, v)| v * (to - from) as f64).sum()
+    }
+
+    /// The time-weighted mean over `[start, end)`, counting only time after the first
+    /// step. NaN if the window holds no defined time.
+    pub fn time_weighted_mean(&self, start: i64, end: i64) -> f64 {
+        let defined: i64 = self.segments(start, end).map(|(from, to, _)| to - from).sum();
+        self.integral(start, end) / defined as f64
+    }
+
+    /// The smallest and largest values in force during `[start, end)`; NaN if none is.
+    pub fn range(&self, start: i64, end: i64) -> (f64, f64) {
+        self.segments(start, end)
+            .filter(|(from, to, _)| to > from)
+            .fold((f64::NAN, f64::NAN), |(lo, hi), (_, _, &v)| (lo.min(v), hi.max(v)))
+    }
+
+    /// The time-weighted mean of each `step`-long bucket from `start` to `end`, keyed by
+    /// bucket start. Buckets before the first step are left out.
+    ///
+    /// # Panics
+    /// Panics if `step` is not positive.
+    pub fn resample_mean(&self, start: i64, end: i64, step: i64) -> TimestampedSeries<f64> {
+        assert!(step > 0, "step must be positive");
+        let mut out = TimestampedSeries::new();
+        let mut t = start;
+        while t < end {
+            let bucket_end = t.saturating_add(step).min(end);
+            let mean = self.time_weighted_mean(t, bucket_end);
+            if !mean.is_nan() {
+                out.push(t, mean);
+            }
+            t = bucket_end;
+        }
+        match self.0.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+}
+
+impl<T> From<TimestampedSeries<T>> for StepSeries<T> {
+    fn from(series: TimestampedSeries<T>) -> Self {
+        Self(series)
+    }
+}
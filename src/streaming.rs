@@ -0,0 +1,567 @@
+//! Point-at-a-time operators for live data, the streaming counterparts of the batch API.
+//!
+//! Each operator keeps O(1) or O(window) state and returns `None` while it is still
+//! warming up, so a chain of operators emits exactly the points the equivalent batch
+//! computation would, in the same order.
+
+use crate::{Aggregator, Clock, TimeSeries};
+use std::collections::{BTreeMap, VecDeque};
+
+pub trait StreamOp {
+    /// Feeds the next point, returning the output for it once one is available.
+    fn push(&mut self, x: f64) -> Option<f64>;
+    /// Forgets all state, as if no point had been pushed.
+    fn reset(&mut self);
+
+    /// Feeds every point of `series`, collecting the outputs.
+    fn process(&mut self, series: &TimeSeries<f64>) -> TimeSeries<f64> {
+        let output: TimeSeries<f64> = series.as_slice().iter().filter_map(|&x| self.push(x)).collect();
+        trace_event!(op = std::any::type_name::<Self>(), input = series.len(), output = output.len(), "batch");
+        output
+    }
+
+    /// Appends the outputs for `new_points` to `output`, the result of the points fed
+    /// so far, in O(new points). The operator's state carries over between calls, so
+    /// keep it alongside the output (cloning it snapshots both).
+    fn update_with(&mut self, output: &mut TimeSeries<f64>, new_points: &[f64]) {
+        output.extend(new_points.iter().filter_map(|&x| self.push(x)));
+    }
+
+    /// Chains `next` after this operator.
+    fn then<O: StreamOp>(self, next: O) -> Chain<Self, O>
+    where
+        Self: Sized,
+    {
+        Chain { first: self, second: next }
+    }
+}
+
+/// Two operators applied in sequence, built with [`StreamOp::then`].
+#[derive(Clone, Debug)]
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: StreamOp, B: StreamOp> StreamOp for Chain<A, B> {
+    fn push(&mut self, x: f64) -> Option<f64> {
+        self.first.push(x).and_then(|y| self.second.push(y))
+    }
+
+    fn reset(&mut self) {
+        self.first.reset();
+        self.second.reset();
+    }
+}
+
+/// Applies a function to each point.
+#[derive(Clone, Debug)]
+pub struct MapOp<F> {
+    f: F,
+}
+
+impl<F: FnMut(f64) -> f64> MapOp<F> {
+    pub fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<F: FnMut(f64) -> f64> StreamOp for MapOp<F> {
+    fn push(&mut self, x: f64) -> Option<f64> {
+        Some((self.f)(x))
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Exponentially weighted moving average, seeded by the first point like
+/// [`Ewm::ewm_mean`](crate::Ewm::ewm_mean).
+#[derive(Clone, Debug, PartialEq)]
+pub struct EwmaOp {
+    alpha: f64,
+    mean: Option<f64>,
+}
+
+impl EwmaOp {
+    pub fn new(alpha: f64) -> Self {
+        assert!(alpha > 0. && alpha <= 1., "alpha must be in (0, 1]");
+        Self { alpha, mean: None }
+    }
+
+    /// Resumes from a previously computed average, e.g. the last point of a stored
+    /// [`Ewm::ewm_mean`](crate::Ewm::ewm_mean) output.
+    pub fn resume(alpha: f64, mean: f64) -> Self {
+        Self { mean: Some(mean), ..Self::new(alpha) }
+    }
+
+    /// The current average, `None` before the first point.
+    pub fn mean(&self) -> Option<f64> {
+        self.mean
+    }
+}
+
+impl StreamOp for EwmaOp {
+    fn push(&mut self, x: f64) -> Option<f64> {
+        let mean = match self.mean {
+            Some(m) => m + self.alpha * (x - m),
+            None => x,
+        };
+        self.mean = Some(mean);
+        Some(mean)
+    }
+
+    fn reset(&mut self) {
+        self.mean = None;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Accumulate {
+    Sum,
+    Product,
+    Min,
+    Max,
+}
+
+/// Running sum, product, minimum or maximum of every point so far, like
+/// `Iterator::scan`. NaN propagates to every later output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CumulativeOp {
+    kind: Accumulate,
+    total: Option<f64>,
+}
+
+impl CumulativeOp {
+    pub fn sum() -> Self {
+        Self { kind: Accumulate::Sum, total: None }
+    }
+
+    pub fn product() -> Self {
+        Self { kind: Accumulate::Product, total: None }
+    }
+
+    pub fn min() -> Self {
+        Self { kind: Accumulate::Min, total: None }
+    }
+
+    pub fn max() -> Self {
+        Self { kind: Accumulate::Max, total: None }
+    }
+
+    /// The accumulated value, `None` before the first point.
+    pub fn total(&self) -> Option<f64> {
+        self.total
+    }
+}
+
+impl StreamOp for CumulativeOp {
+    fn push(&mut self, x: f64) -> Option<f64> {
+        let total = match self.total {
+            None => x,
+            Some(t) if t.is_nan() || x.is_nan() => f64::NAN,
+            Some(t) => match self.kind {
+                Accumulate::Sum => t + x,
+                Accumulate::Product => t * x,
+                Accumulate::Min => t.min(x),
+                Accumulate::Max => t.max(x),
+            },
+        };
+        self.total = Some(total);
+        Some(total)
+    }
+
+    fn reset(&mut self) {
+        self.total = None;
+    }
+}
+
+/// Mean of the trailing `window` points, emitted once the window is full. Windows
+/// containing NaN are NaN.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RollingMeanOp {
+    window: usize,
+    values: VecDeque<f64>,
+    /// Sum of the non-NaN values in the window.
+    sum: f64,
+    nans: usize,
+    /// Pushes since the sum was last recomputed from scratch.
+    since_refresh: usize,
+}
+
+impl RollingMeanOp {
+    pub fn new(window: usize) -> Self {
+        assert!(window > 0, "window must be positive");
+        Self { window, values: VecDeque::with_capacity(window + 1), sum: 0., nans: 0, since_refresh: 0 }
+    }
+
+    fn add(&mut self, x: f64, sign: f64) {
+        if x.is_nan() {
+            if sign > 0. {
+                self.nans += 1;
+            } else {
+                self.nans -= 1;
+            }
+        } else {
+            self.sum += sign * x;
+        }
+    }
+}
+
+impl StreamOp for RollingMeanOp {
+    fn push(&mut self, x: f64) -> Option<f64> {
+        self.values.push_back(x);
+        self.add(x, 1.);
+        if self.values.len() > self.window {
+            let old = self.values.pop_front().unwrap_or(0.);
+            self.add(old, -1.);
+        }
+        // Recompute once per window length so rounding errors in the running sum cannot
+        // accumulate without bound.
+        self.since_refresh += 1;
+        if self.since_refresh >= self.window {
+            self.sum = self.values.iter().filter(|v| !v.is_nan()).sum();
+            self.since_refresh = 0;
+        }
+        if self.values.len() < self.window {
+            return None;
+        }
+        Some(if self.nans > 0 { f64::NAN } else { self.sum / self.window as f64 })
+    }
+
+    fn reset(&mut self) {
+        self.values.clear();
+        self.sum = 0.;
+        self.nans = 0;
+        self.since_refresh = 0;
+    }
+}
+
+/// Difference from the point `lag` steps earlier, like [`Variation::diff`](crate::Variation::diff).
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffOp {
+    lag: usize,
+    history: VecDeque<f64>,
+}
+
+impl DiffOp {
+    pub fn new(lag: usize) -> Self {
+        Self { lag, history: VecDeque::with_capacity(lag + 1) }
+    }
+}
+
+impl StreamOp for DiffOp {
+    fn push(&mut self, x: f64) -> Option<f64> {
+        self.history.push_back(x);
+        if self.history.len() > self.lag {
+            self.history.pop_front().map(|old| x - old)
+        } else {
+            None
+        }
+    }
+
+    fn reset(&mut self) {
+        self.history.clear();
+    }
+}
+
+/// Mean of each consecutive block of `every` points, emitted when the block completes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResampleOp {
+    every: usize,
+    sum: f64,
+    count: usize,
+}
+
+impl ResampleOp {
+    pub fn new(every: usize) -> Self {
+        assert!(every > 0, "block size must be positive");
+        Self { every, sum: 0., count: 0 }
+    }
+}
+
+impl StreamOp for ResampleOp {
+    fn push(&mut self, x: f64) -> Option<f64> {
+        self.sum += x;
+        self.count += 1;
+        if self.count < self.every {
+            return None;
+        }
+        let mean = self.sum / self.every as f64;
+        self.reset();
+        Some(mean)
+    }
+
+    fn reset(&mut self) {
+        self.sum = 0.;
+        self.count = 0;
+    }
+}
+
+/// Replaces NaN with the last valid point, or with `fill` before there is one (and
+/// always, when `forward` is off).
+#[derive(Clone, Debug, PartialEq)]
+pub struct FillNaOp {
+    fill: f64,
+    forward: bool,
+    last: Option<f64>,
+}
+
+impl FillNaOp {
+    /// Forward fill, using `fill` until the first valid point.
+    pub fn forward(fill: f64) -> Self {
+        Self { fill, forward: true, last: None }
+    }
+
+    /// Replaces every NaN with `fill`.
+    pub fn constant(fill: f64) -> Self {
+        Self { fill, forward: false, last: None }
+    }
+}
+
+impl StreamOp for FillNaOp {
+    fn push(&mut self, x: f64) -> Option<f64> {
+        if !x.is_nan() {
+            self.last = Some(x);
+            return Some(x);
+        }
+        Some(if self.forward { self.last.unwrap_or(self.fill) } else { self.fill })
+    }
+
+    fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+/// Z-score of each point against the mean and sample standard deviation of the trailing
+/// `window` points including it, emitted once the window is full.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZScoreOp {
+    window: usize,
+    values: VecDeque<f64>,
+}
+
+impl ZScoreOp {
+    pub fn new(window: usize) -> Self {
+        assert!(window > 1, "window must be at least 2");
+        Self { window, values: VecDeque::with_capacity(window + 1) }
+    }
+}
+
+impl StreamOp for ZScoreOp {
+    fn push(&mut self, x: f64) -> Option<f64> {
+        self.values.push_back(x);
+        if self.values.len() > self.window {
+            self.values.pop_front();
+        }
+        if self.values.len() < self.window {
+            return None;
+        }
+        let n = self.window as f64;
+        let mean = self.values.iter().sum::<f64>() / n;
+        let var = self.values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.);
+        Some((x - mean) / var.sqrt())
+    }
+
+    fn reset(&mut self) {
+        self.values.clear();
+    }
+}
+
+/// 1 where the point is above `level`, 0 otherwise (including NaN).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThresholdOp {
+    level: f64,
+}
+
+impl ThresholdOp {
+    pub fn new(level: f64) -> Self {
+        Self { level }
+    }
+}
+
+impl StreamOp for ThresholdOp {
+    fn push(&mut self, x: f64) -> Option<f64> {
+        Some(if x > self.level { 1. } else { 0. })
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// A chain of operators assembled at runtime.
+#[derive(Default)]
+pub struct Pipeline {
+    ops: Vec<Box<dyn StreamOp + Send>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an operator to the end of the chain.
+    pub fn then<O: StreamOp + Send + 'static>(mut self, op: O) -> Self {
+        self.ops.push(Box::new(op));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+impl StreamOp for Pipeline {
+    fn push(&mut self, x: f64) -> Option<f64> {
+        self.ops.iter_mut().try_fold(x, |y, op| op.push(y))
+    }
+
+    fn reset(&mut self) {
+        self.ops.iter_mut().for_each(|op| op.reset());
+    }
+}
+
+/// Why an [`EventTimeResample`] reported a bucket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BucketStatus {
+    /// The watermark passed the end of the bucket; late points may still revise it.
+    Emitted,
+    /// A late point changed a bucket that was already emitted.
+    Revised,
+    /// The allowed lateness expired (or the stream was flushed); the value is final.
+    Finalized,
+}
+
+/// A bucket result from [`EventTimeResample`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BucketUpdate<O> {
+    /// Start of the bucket.
+    pub bucket: i64,
+    pub value: O,
+    pub status: BucketStatus,
+}
+
+/// Bucketed aggregation of timestamped points arriving out of order, driven by an
+/// event-time watermark.
+///
+/// The watermark trails the latest timestamp seen by `delay`. A bucket `[b, b + step)`
+/// is emitted once the watermark reaches `b + step` and stays open for revisions until
+/// it reaches `b + step + allowed_lateness`, when it is finalized and its state dropped.
+/// Points arriving for a finalized bucket are dropped and counted. With no lateness a
+/// bucket is reported once, as finalized.
+#[derive(Clone, Debug)]
+pub struct EventTimeResample<A: Aggregator<f64>> {
+    agg: A,
+    step: i64,
+    delay: i64,
+    allowed_lateness: i64,
+    watermark: Option<i64>,
+    /// Open buckets with their state and whether they were emitted.
+    open: BTreeMap<i64, (A::State, bool)>,
+    dropped: usize,
+}
+
+impl<A: Aggregator<f64>> EventTimeResample<A> {
+    /// Buckets of `step` with no delay and no allowed lateness.
+    pub fn new(step: i64, agg: A) -> Self {
+        assert!(step > 0, "step must be positive");
+        Self { agg, step, delay: 0, allowed_lateness: 0, watermark: None, open: BTreeMap::new(), dropped: 0 }
+    }
+
+    /// How far the watermark trails the latest timestamp, i.e. the disorder tolerated
+    /// before buckets are first emitted.
+    pub fn with_delay(self, delay: i64) -> Self {
+        assert!(delay >= 0, "delay must be non-negative");
+        Self { delay, ..self }
+    }
+
+    /// How long past the watermark emitted buckets accept late points as revisions.
+    pub fn with_allowed_lateness(self, allowed_lateness: i64) -> Self {
+        assert!(allowed_lateness >= 0, "allowed lateness must be non-negative");
+        Self { allowed_lateness, ..self }
+    }
+
+    pub fn watermark(&self) -> Option<i64> {
+        self.watermark
+    }
+
+    /// Points dropped for arriving after their bucket was finalized.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Buckets not yet finalized.
+    pub fn open_buckets(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Feeds a point, returning the buckets it emitted, revised or finalized.
+    pub fn push(&mut self, t: i64, x: f64) -> Vec<BucketUpdate<A::Output>> {
+        let bucket = t.div_euclid(self.step) * self.step;
+        if self.watermark.is_some_and(|w| bucket + self.step + self.allowed_lateness <= w) {
+            self.dropped += 1;
+            return Vec::new();
+        }
+        let (state, emitted) = self.open.entry(bucket).or_insert_with(|| (self.agg.init(), false));
+        self.agg.update(state, &x);
+        let mut updates = Vec::new();
+        if *emitted {
+            updates.push(BucketUpdate { bucket, value: self.agg.finish(state), status: BucketStatus::Revised });
+        }
+        updates.extend(self.advance_watermark(t - self.delay));
+        updates
+    }
+
+    /// Moves the watermark forward to `watermark` (it never moves back), e.g. when a
+    /// source is idle, returning the buckets this emits or finalizes.
+    pub fn advance_watermark(&mut self, watermark: i64) -> Vec<BucketUpdate<A::Output>> {
+        let watermark = self.watermark.map_or(watermark, |w| w.max(watermark));
+        self.watermark = Some(watermark);
+        let mut updates = Vec::new();
+        let mut finalized = Vec::new();
+        for (&bucket, (state, emitted)) in self.open.iter_mut() {
+            let end = bucket + self.step;
+            if end > watermark {
+                break;
+            }
+            if end + self.allowed_lateness <= watermark {
+                finalized.push(bucket);
+                updates.push(BucketUpdate { bucket, value: self.agg.finish(state), status: BucketStatus::Finalized });
+            } else if !*emitted {
+                *emitted = true;
+                updates.push(BucketUpdate { bucket, value: self.agg.finish(state), status: BucketStatus::Emitted });
+            }
+        }
+        for bucket in finalized {
+            self.open.remove(&bucket);
+        }
+        updates
+    }
+
+    /// Advances the watermark to the clock time minus `delay`, so buckets still close
+    /// when the source goes quiet. Event times and `clock` must share a time unit.
+    pub fn advance_by_clock<C: Clock + ?Sized>(&mut self, clock: &C) -> Vec<BucketUpdate<A::Output>> {
+        self.advance_watermark(clock.now() - self.delay)
+    }
+
+    /// Finalizes every open bucket, e.g. at the end of the stream.
+    pub fn flush(&mut self) -> Vec<BucketUpdate<A::Output>> {
+        std::mem::take(&mut self.open)
+            .into_iter()
+            .map(|(bucket, (state, _))| BucketUpdate {
+                bucket,
+                value: self.agg.finish(&state),
+                status: BucketStatus::Finalized,
+            })
+            .collect()
+    }
+
+    /// Forgets all buckets, the watermark and the dropped count.
+    pub fn reset(&mut self) {
+        self.open.clear();
+        self.watermark = None;
+        self.dropped = 0;
+    }
+}
@@ -0,0 +1,127 @@
+//! Swinging-door trending, the deadband compression of process historians: a
+//! high-rate series is stored as the few points between which linear interpolation
+//! stays within a deviation of every dropped point.
+//!
+//! From each archived point, a door of slopes is kept that the line to the next
+//! archived point may take while passing within the deviation of every point since.
+//! When a new point's slope falls outside the door, the previous point is archived and
+//! a new door opens from it. Unlike the classic algorithm, the end point's own slope
+//! must also lie within the door, so the error bound is strict.
+
+use crate::TimestampedSeries;
+
+/// Streaming swinging-door compressor: feed it points in time order and store what it
+/// emits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwingingDoor {
+    deviation: f64,
+    archived: Option<(i64, f64)>,
+    last: Option<(i64, f64)>,
+    /// The door: slopes from the archived point that pass within `deviation` of every
+    /// point since.
+    lower: f64,
+    upper: f64,
+}
+
+impl SwingingDoor {
+    /// # Panics
+    /// Panics if `deviation` is negative or NaN.
+    pub fn new(deviation: f64) -> Self {
+        assert!(deviation >= 0., "deviation must not be negative");
+        Self { deviation, archived: None, last: None, lower: f64::NEG_INFINITY, upper: f64::INFINITY }
+    }
+
+    /// Feeds the next point, returning the point to archive if this one closed the door.
+    /// The first point is always archived.
+    pub fn push(&mut self, timestamp: i64, value: f64) -> Option<(i64, f64)> {
+        let Some(archived) = self.archived else {
+            self.archived = Some((timestamp, value));
+            return Some((timestamp, value));
+        };
+        let mut emitted = None;
+        if let Some(last) = self.last {
+            let (lower, upper) = self.slopes(archived, last);
+            self.lower = self.lower.max(lower);
+            self.upper = self.upper.min(upper);
+            let dt = timestamp - archived.0;
+            let slope = (value - archived.1) / dt as f64;
+            if dt == 0 || !(self.lower..=self.upper).contains(&slope) {
+                self.archived = Some(last);
+                self.lower = f64::NEG_INFINITY;
+                self.upper = f64::INFINITY;
+                emitted = Some(last);
+            }
+        }
+        self.last = Some((timestamp, value));
+        emitted
+    }
+
+    /// The last point, which ends the final segment. The compressor starts over after.
+    pub fn finish(&mut self) -> Option<(i64, f64)> {
+        let last = self.last.take();
+        *self = Self::new(self.deviation);
+        last
+    }
+
+    /// Range of slopes from `from` that pass within the deviation of `point`.
+    fn slopes(&self, from: (i64, f64), point: (i64, f64)) -> (f64, f64) {
+        let dt = (point.0 - from.0) as f64;
+        let dv = point.1 - from.1;
+        if dt == 0. {
+            return if dv.abs() <= self.deviation {
+                (f64::NEG_INFINITY, f64::INFINITY)
+            } else {
+                (f64::INFINITY, f64::NEG_INFINITY)
+            };
+        }
+        ((dv - self.deviation) / dt, (dv + self.deviation) / dt)
+    }
+}
+
+impl TimestampedSeries<f64> {
+    /// The points to keep so that linear interpolation between them is within
+    /// `deviation` of every point of the series. NaN values are skipped.
+    ///
+    /// # Panics
+    /// Panics if `deviation` is negative or NaN.
+    pub fn swinging_door(&self, deviation: f64) -> Self {
+        let mut door = SwingingDoor::new(deviation);
+        let mut kept = Vec::new();
+        for (t, &v) in self.iter().filter(|(_, v)| !v.is_nan()) {
+            kept.extend(door.push(t, v));
+        }
+        kept.extend(door.finish());
+        let out: Self = kept.into_iter().collect();
+        match self.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+
+    /// Values at `timestamps` by linear interpolation between the points, e.g. to read
+    /// back a compressed series on the original grid. NaN outside the series.
+    ///
+    /// # Panics
+    /// Panics if `timestamps` is not sorted.
+    pub fn interpolate_at(&self, timestamps: &[i64]) -> Self {
+        let ts = self.timestamps();
+        let values = self.values().as_slice();
+        let mut out = TimestampedSeries::new();
+        for &t in timestamps {
+            let i = ts.partition_point(|&s| s <= t);
+            let v = if i > 0 && ts[i - 1] == t {
+                values[i - 1]
+            } else if i == 0 || i == ts.len() {
+                f64::NAN
+            } else {
+                let w = (t - ts[i - 1]) as f64 / (ts[i] - ts[i - 1]) as f64;
+                values[i - 1] + w * (values[i] - values[i - 1])
+            };
+            out.push(t, v);
+        }
+        match self.unit() {
+            Some(unit) => out.with_unit(unit),
+            None => out,
+        }
+    }
+}
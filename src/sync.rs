@@ -0,0 +1,239 @@
+//! Delta synchronization of series mirrors, e.g. edge devices keeping local copies of
+//! server series up to date over unreliable links.
+//!
+//! The server numbers the versions of each series it publishes and keeps the
+//! [`SeriesPatch`]es between the most recent ones. A client sends the version of every
+//! mirror it holds; the server answers with the patches since then, or with the whole
+//! series when it no longer has them or they would be larger. Requests and responses are
+//! plain data, serializable with the `serde` feature, to carry over any transport.
+//!
+//! Version numbers restart with each generation of a series, a new one whenever the
+//! server restarts or the series is removed and published again, and a mirror of an
+//! earlier generation is always replaced whole.
+//!
+//! Responses can be lost, repeated or arrive late: the client applies an update only to
+//! the version it was computed from, and a mirror that cannot be patched is fetched whole
+//! on the next request.
+
+use crate::{diff_series, SeriesPatch, SeriesSet, TimestampedSeries};
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, VecDeque};
+use std::hash::{BuildHasher, Hasher};
+
+/// A version of a published series: its generation and the number of the version within
+/// it, counting from 1. The default, number 0, stands for no copy at all.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeriesVersion {
+    pub generation: u64,
+    pub number: u64,
+}
+
+/// The versions of a client's mirrors, the default for a series it has no copy of yet.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyncRequest {
+    pub versions: BTreeMap<String, SeriesVersion>,
+}
+
+/// How to bring one mirror up to date.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SeriesUpdate<T> {
+    /// Patches to apply in order, taking the mirror from version `from` to `to`.
+    Patches { from: SeriesVersion, to: SeriesVersion, patches: Vec<SeriesPatch<T>> },
+    /// The whole series at `version`, for a mirror at version `from`.
+    Snapshot { from: SeriesVersion, version: SeriesVersion, points: Vec<(i64, T)> },
+    /// The server has no series of that name.
+    Missing,
+}
+
+/// The updates for every requested mirror that is not up to date.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyncResponse<T> {
+    pub updates: BTreeMap<String, SeriesUpdate<T>>,
+}
+
+#[derive(Clone, Debug)]
+struct Published<T> {
+    series: TimestampedSeries<T>,
+    version: SeriesVersion,
+    /// The patches leading to the last versions, each with the version number it produces.
+    patches: VecDeque<(u64, SeriesPatch<T>)>,
+}
+
+/// The authoritative side: the current version of every series and its recent patches.
+#[derive(Clone, Debug)]
+pub struct SyncServer<T> {
+    published: BTreeMap<String, Published<T>>,
+    history: usize,
+    /// The generation of the next series published, starting from a random value so that
+    /// generations differ between runs.
+    next_generation: u64,
+}
+
+impl<T: Clone + PartialEq> SyncServer<T> {
+    /// Keeps the patches of the last `history` versions of each series; clients further
+    /// behind get the whole series.
+    pub fn new(history: usize) -> Self {
+        let next_generation = RandomState::new().build_hasher().finish();
+        Self { published: BTreeMap::new(), history, next_generation }
+    }
+
+    /// Makes `series` the current version of `name` and returns its version, unchanged if
+    /// the series is the same as before. A series not published yet starts a new generation.
+    pub fn publish(&mut self, name: &str, series: TimestampedSeries<T>) -> SeriesVersion {
+        let Some(published) = self.published.get_mut(name) else {
+            let version = SeriesVersion { generation: self.next_generation, number: 1 };
+            self.next_generation = self.next_generation.wrapping_add(1);
+            self.published.insert(name.to_string(), Published { series, version, patches: VecDeque::new() });
+            return version;
+        };
+        let patch = diff_series(&published.series, &series);
+        if patch.is_empty() {
+            return published.version;
+        }
+        published.version.number += 1;
+        published.patches.push_back((published.version.number, patch));
+        while published.patches.len() > self.history {
+            published.patches.pop_front();
+        }
+        published.series = series;
+        published.version
+    }
+
+    /// Stops serving `name`; clients drop their mirrors on the next sync.
+    pub fn remove(&mut self, name: &str) -> Option<TimestampedSeries<T>> {
+        self.published.remove(name).map(|p| p.series)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TimestampedSeries<T>> {
+        self.published.get(name).map(|p| &p.series)
+    }
+
+    /// The current version of `name`, the default if it is not published.
+    pub fn version(&self, name: &str) -> SeriesVersion {
+        self.published.get(name).map_or_else(SeriesVersion::default, |p| p.version)
+    }
+
+    /// The updates bringing the mirrors of `request` to the current versions.
+    pub fn respond(&self, request: &SyncRequest) -> SyncResponse<T> {
+        let mut updates = BTreeMap::new();
+        for (name, &from) in &request.versions {
+            let Some(published) = self.published.get(name) else {
+                updates.insert(name.clone(), SeriesUpdate::Missing);
+                continue;
+            };
+            if from == published.version {
+                continue;
+            }
+            let current = published.version.number;
+            let same_generation = from.generation == published.version.generation;
+            let patches: Vec<SeriesPatch<T>> = published
+                .patches
+                .iter()
+                .filter(|(number, _)| same_generation && *number > from.number)
+                .map(|(_, p)| p.clone())
+                .collect();
+            let covered = same_generation
+                && from.number > 0
+                && from.number < current
+                && patches.len() as u64 == current - from.number;
+            let update = if covered && patches.iter().map(SeriesPatch::len).sum::<usize>() < published.series.len() {
+                SeriesUpdate::Patches { from, to: published.version, patches }
+            } else {
+                let points = published.series.iter().map(|(t, v)| (t, v.clone())).collect();
+                SeriesUpdate::Snapshot { from, version: published.version, points }
+            };
+            updates.insert(name.clone(), update);
+        }
+        SyncResponse { updates }
+    }
+}
+
+/// The mirroring side.
+#[derive(Clone, Debug)]
+pub struct SyncClient<T> {
+    mirrors: SeriesSet<T>,
+    versions: BTreeMap<String, SeriesVersion>,
+}
+
+impl<T> Default for SyncClient<T> {
+    fn default() -> Self {
+        Self { mirrors: SeriesSet::new(), versions: BTreeMap::new() }
+    }
+}
+
+impl<T: Clone> SyncClient<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks for `name` from the next request on.
+    pub fn subscribe(&mut self, name: &str) {
+        self.versions.entry(name.to_string()).or_default();
+    }
+
+    /// Stops mirroring `name`, returning the mirror.
+    pub fn unsubscribe(&mut self, name: &str) -> Option<TimestampedSeries<T>> {
+        self.versions.remove(name);
+        self.mirrors.remove(name)
+    }
+
+    pub fn mirrors(&self) -> &SeriesSet<T> {
+        &self.mirrors
+    }
+
+    /// The version of the mirror of `name`, the default if there is none.
+    pub fn version(&self, name: &str) -> SeriesVersion {
+        self.versions.get(name).copied().unwrap_or_default()
+    }
+
+    pub fn request(&self) -> SyncRequest {
+        SyncRequest { versions: self.versions.clone() }
+    }
+
+    /// Applies the updates of `response` meant for the current mirrors, returning the
+    /// names of the mirrors that changed. Patches and snapshots computed from another
+    /// version than the mirror's, such as late responses to earlier requests, and updates
+    /// of unsubscribed series are ignored; a snapshot may be of another generation, as
+    /// when the server has restarted or republished the series. A mirror whose patches do
+    /// not apply is reset so the next request fetches it whole.
+    pub fn apply(&mut self, response: &SyncResponse<T>) -> Vec<String> {
+        let mut changed = Vec::new();
+        for (name, update) in &response.updates {
+            let Some(&version) = self.versions.get(name) else { continue };
+            match update {
+                SeriesUpdate::Patches { from, to, patches } if *from == version => {
+                    let Some(mirror) = self.mirrors.get(name) else { continue };
+                    let patched = patches.iter().try_fold(mirror.clone(), |series, patch| patch.apply(&series));
+                    match patched {
+                        Ok(series) => {
+                            self.mirrors.insert(name.as_str(), series);
+                            self.versions.insert(name.clone(), *to);
+                            changed.push(name.clone());
+                        }
+                        Err(_) => {
+                            self.mirrors.remove(name);
+                            self.versions.insert(name.clone(), SeriesVersion::default());
+                        }
+                    }
+                }
+                SeriesUpdate::Snapshot { from, version: latest, points } if *from == version => {
+                    self.mirrors.insert(name.as_str(), points.iter().cloned().collect());
+                    self.versions.insert(name.clone(), *latest);
+                    changed.push(name.clone());
+                }
+                SeriesUpdate::Missing => {
+                    if self.mirrors.remove(name).is_some() {
+                        changed.push(name.clone());
+                    }
+                    self.versions.insert(name.clone(), SeriesVersion::default());
+                }
+                _ => {}
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,281 @@
+//! Tenants sharing one storage root, each confined to its own directory with its own
+//! quotas and retention period.
+//!
+//! A tenant's series live in a [`ChunkStore`] under `<root>/<tenant>/`; chunks are not
+//! shared between tenants, so one tenant cannot learn from deduplication what another
+//! stores. Tenant ids and series names are restricted to lowercase letters, digits, `-`,
+//! `_` and `.` (not leading), so no name can reach outside its directory and no two names
+//! share a directory on a case-insensitive filesystem.
+//!
+//! Saves of one tenant's series are serialized through a lock file in its directory, so
+//! concurrent saves, from other processes too, cannot together exceed its quotas.
+//!
+//! Retention keeps the points within the period before a series' last point. The store
+//! applies it a chunk at a time, so up to one chunk of older points may remain.
+
+use crate::{ChunkStore, ChunkedSeries, GcReport, Manifest, StoreUsage};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const POLICY_FILE: &str = "policy";
+const LOCK_FILE: &str = "lock";
+
+/// Checks that `name` can be used as a directory or file name as is.
+pub(crate) fn check_name(kind: &str, name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 128
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("invalid {} `{}`: use lowercase letters, digits, `-`, `_` and non-leading `.`", kind, name))
+    }
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// Limits on what one tenant stores; all unlimited by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TenantPolicy {
+    max_series: Option<usize>,
+    max_points: Option<usize>,
+    retention: Option<i64>,
+}
+
+impl TenantPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_series(self, max_series: usize) -> Self {
+        Self { max_series: Some(max_series), ..self }
+    }
+
+    /// Limits the points of all series together.
+    pub fn with_max_points(self, max_points: usize) -> Self {
+        Self { max_points: Some(max_points), ..self }
+    }
+
+    /// Keeps the points at most `retention` before the last point of their series.
+    ///
+    /// # Panics
+    /// Panics if `retention` is negative.
+    pub fn with_retention(self, retention: i64) -> Self {
+        assert!(retention >= 0, "retention must not be negative");
+        Self { retention: Some(retention), ..self }
+    }
+
+    pub fn max_series(&self) -> Option<usize> {
+        self.max_series
+    }
+
+    pub fn max_points(&self) -> Option<usize> {
+        self.max_points
+    }
+
+    pub fn retention(&self) -> Option<i64> {
+        self.retention
+    }
+
+    /// The earliest timestamp retention keeps in a series ending at `last`.
+    pub(crate) fn cutoff(&self, last: i64) -> i64 {
+        self.retention.map_or(i64::MIN, |r| last.saturating_sub(r))
+    }
+
+    /// Checks the totals of `owner` after a change against the quotas.
+    pub(crate) fn check(&self, owner: &str, series: usize, points: usize) -> Result<(), String> {
+        if let Some(max) = self.max_series.filter(|&max| series > max) {
+            return Err(format!("{} would exceed its quota of {} series", owner, max));
+        }
+        if let Some(max) = self.max_points.filter(|&max| points > max) {
+            return Err(format!("{} would exceed its quota of {} points", owner, max));
+        }
+        Ok(())
+    }
+
+    fn to_text(self) -> String {
+        let mut text = String::new();
+        let limits = [
+            ("max_series", self.max_series.map(|v| v as i64)),
+            ("max_points", self.max_points.map(|v| v as i64)),
+            ("retention", self.retention),
+        ];
+        for (key, value) in limits {
+            if let Some(value) = value {
+                writeln!(text, "{} {}", key, value).expect("writing to a string");
+            }
+        }
+        text
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut policy = Self::default();
+        for line in text.lines() {
+            let (key, value) = line.split_once(' ')?;
+            match key {
+                "max_series" => policy.max_series = Some(value.parse().ok()?),
+                "max_points" => policy.max_points = Some(value.parse().ok()?),
+                "retention" => policy.retention = Some(value.parse().ok().filter(|&r: &i64| r >= 0)?),
+                _ => return None,
+            }
+        }
+        Some(policy)
+    }
+}
+
+/// The tenants of one storage root.
+#[derive(Clone, Debug)]
+pub struct Tenants {
+    root: PathBuf,
+}
+
+impl Tenants {
+    /// Opens the tenants under `root`, creating it if needed.
+    pub fn open<P: AsRef<Path>>(root: P) -> io::Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        std::fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    fn dir(&self, tenant: &str) -> io::Result<PathBuf> {
+        check_name("tenant id", tenant).map_err(invalid_input)?;
+        Ok(self.root.join(tenant))
+    }
+
+    /// Creates `tenant`, or changes its policy if it exists, and returns its store. A
+    /// tighter retention applies to the stored series from the next
+    /// [`TenantStore::enforce_retention`] on.
+    ///
+    /// Fails if `tenant` is not a valid id.
+    pub fn create(&self, tenant: &str, policy: TenantPolicy) -> io::Result<TenantStore> {
+        let dir = self.dir(tenant)?;
+        let store = ChunkStore::open(&dir)?;
+        std::fs::write(dir.join(POLICY_FILE), policy.to_text())?;
+        Ok(TenantStore { tenant: tenant.to_string(), store, policy })
+    }
+
+    /// The store of an existing tenant.
+    ///
+    /// Fails if there is no such tenant or its policy is malformed.
+    pub fn store(&self, tenant: &str) -> io::Result<TenantStore> {
+        let dir = self.dir(tenant)?;
+        let text = std::fs::read_to_string(dir.join(POLICY_FILE))?;
+        let policy = TenantPolicy::parse(&text)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("malformed policy of `{}`", tenant)))?;
+        Ok(TenantStore { tenant: tenant.to_string(), store: ChunkStore::open(&dir)?, policy })
+    }
+
+    /// The tenant ids, sorted.
+    pub fn tenants(&self) -> io::Result<Vec<String>> {
+        let mut tenants = Vec::new();
+        for entry in std::fs::read_dir(&self.root)? {
+            let path = entry?.path();
+            if path.join(POLICY_FILE).is_file() {
+                tenants.extend(path.file_name().and_then(|n| n.to_str()).map(str::to_string));
+            }
+        }
+        tenants.sort();
+        Ok(tenants)
+    }
+
+    /// Deletes `tenant` with all its series.
+    pub fn remove(&self, tenant: &str) -> io::Result<()> {
+        std::fs::remove_dir_all(self.dir(tenant)?)
+    }
+}
+
+/// One tenant's series, saved within its quotas and retention period.
+#[derive(Clone, Debug)]
+pub struct TenantStore {
+    tenant: String,
+    store: ChunkStore,
+    policy: TenantPolicy,
+}
+
+impl TenantStore {
+    pub fn tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    pub fn policy(&self) -> &TenantPolicy {
+        &self.policy
+    }
+
+    /// Stores `series` as `name` without the chunks past retention, replacing any
+    /// previous version, and returns the number of new chunks written.
+    ///
+    /// Fails if `name` is not a valid series name, or with [`io::ErrorKind::QuotaExceeded`]
+    /// if the tenant would hold more series or points than its quotas allow; nothing is
+    /// written then. The quotas are checked and the series written under the tenant's lock.
+    pub fn save(&self, name: &str, series: &ChunkedSeries) -> io::Result<usize> {
+        check_name("series name", name).map_err(invalid_input)?;
+        // Released when the file is closed.
+        let lock = File::create(self.store.root().join(LOCK_FILE))?;
+        lock.lock()?;
+        let from = series.iter().last().map_or(i64::MIN, |(t, _)| self.policy.cutoff(t));
+        let points: usize =
+            series.chunk_points().map(|(t, _)| t).filter(|t| t[t.len() - 1] >= from).map(<[_]>::len).sum();
+        let mut names = self.store.names()?;
+        let mut total = points;
+        for other in names.iter().filter(|other| *other != name) {
+            total += self.store.manifest(other)?.len();
+        }
+        if !names.iter().any(|other| other == name) {
+            names.push(name.to_string());
+        }
+        self.policy
+            .check(&format!("tenant `{}`", self.tenant), names.len(), total)
+            .map_err(|message| io::Error::new(io::ErrorKind::QuotaExceeded, message))?;
+        self.store.save_from(name, series, from)
+    }
+
+    /// Fails if `name` is not a valid series name or there is no such series.
+    pub fn load(&self, name: &str) -> io::Result<ChunkedSeries> {
+        check_name("series name", name).map_err(invalid_input)?;
+        self.store.load(name)
+    }
+
+    /// Fails if `name` is not a valid series name or there is no such series.
+    pub fn manifest(&self, name: &str) -> io::Result<Manifest> {
+        check_name("series name", name).map_err(invalid_input)?;
+        self.store.manifest(name)
+    }
+
+    pub fn names(&self) -> io::Result<Vec<String>> {
+        self.store.names()
+    }
+
+    /// Fails if `name` is not a valid series name or there is no such series.
+    pub fn remove(&self, name: &str) -> io::Result<()> {
+        check_name("series name", name).map_err(invalid_input)?;
+        self.store.remove(name)
+    }
+
+    /// The tenant's disk usage, to bill or report against its quotas.
+    pub fn usage(&self) -> io::Result<StoreUsage> {
+        self.store.usage()
+    }
+
+    /// Total points of the tenant's series.
+    pub fn points(&self) -> io::Result<usize> {
+        self.names()?.iter().map(|name| Ok(self.store.manifest(name)?.len())).sum()
+    }
+
+    /// Drops the chunks past retention from every series and deletes the chunk files
+    /// no longer used.
+    pub fn enforce_retention(&self) -> io::Result<GcReport> {
+        if self.policy.retention.is_some() {
+            for name in self.names()? {
+                if let Some(last) = self.store.manifest(&name)?.chunks.last().map(|c| c.last) {
+                    self.store.drop_chunks_before(&name, self.policy.cutoff(last))?;
+                }
+            }
+        }
+        self.store.gc()
+    }
+}
@@ -0,0 +1,219 @@
+//! Generators and invariant checks for property-based tests of code built on this crate.
+//!
+//! Generators take any [`rand::Rng`], so they plug into proptest (via a seeded RNG from a
+//! strategy) or quickcheck alike. Checks return `Err` with a description of the first
+//! violation, ready for `prop_assert!(check(..).is_ok(), ..)` or `?` in a test body.
+
+use crate::rolling::{Agg, Windowed};
+use crate::{Statistics, StreamOp, TimeSeries, TimestampedSeries, Variation};
+use rand::Rng;
+use std::ops::Range;
+
+/// A series with a length drawn from `len` and values uniform in `values`.
+pub fn arbitrary_series<R: Rng + ?Sized>(rng: &mut R, len: Range<usize>, values: Range<f64>) -> TimeSeries<f64> {
+    let n = if len.is_empty() { len.start } else { rng.gen_range(len) };
+    (0..n).map(|_| rng.gen_range(values.clone())).collect()
+}
+
+/// Like [`arbitrary_series`], with each value replaced by NaN with probability
+/// `nan_probability`.
+pub fn arbitrary_series_with_nans<R: Rng + ?Sized>(
+    rng: &mut R,
+    len: Range<usize>,
+    values: Range<f64>,
+    nan_probability: f64,
+) -> TimeSeries<f64> {
+    let series = arbitrary_series(rng, len, values);
+    series.into_iter().map(|x| if rng.gen_bool(nan_probability) { f64::NAN } else { x }).collect()
+}
+
+/// A timestamped series on a regular `step` grid starting at `start`, where each gap
+/// between points skips up to `max_gap` extra steps with probability `gap_probability`.
+pub fn arbitrary_timestamped<R: Rng + ?Sized>(
+    rng: &mut R,
+    len: Range<usize>,
+    start: i64,
+    step: i64,
+    gap_probability: f64,
+    max_gap: usize,
+) -> TimestampedSeries<f64> {
+    assert!(step > 0, "step must be positive");
+    let n = if len.is_empty() { len.start } else { rng.gen_range(len) };
+    let mut t = start;
+    let mut series = TimestampedSeries::new();
+    for _ in 0..n {
+        series.push(t, rng.gen_range(-100.0..100.0));
+        let skipped = if max_gap > 0 && rng.gen_bool(gap_probability) { rng.gen_range(1..=max_gap) } else { 0 };
+        t += step * (1 + skipped as i64);
+    }
+    series
+}
+
+/// Whether two values agree within `tolerance` (absolute, or relative for large values),
+/// treating two NaNs as equal.
+pub fn approx_eq(a: f64, b: f64, tolerance: f64) -> bool {
+    (a.is_nan() && b.is_nan()) || a == b || (a - b).abs() <= tolerance * 1f64.max(a.abs()).max(b.abs())
+}
+
+/// Checks two series point by point with [`approx_eq`].
+pub fn check_close(actual: &TimeSeries<f64>, expected: &TimeSeries<f64>, tolerance: f64) -> Result<(), String> {
+    if actual.len() != expected.len() {
+        return Err(format!("length {} != expected {}", actual.len(), expected.len()));
+    }
+    match (0..actual.len()).find(|&i| !approx_eq(actual[i], expected[i], tolerance)) {
+        Some(i) => Err(format!("at {}: {} != expected {}", i, actual[i], expected[i])),
+        None => Ok(()),
+    }
+}
+
+/// A point-by-point comparison of a series with the values it is expected to have, for
+/// numerical regression tests. Points agree when `|actual - expected| <= atol + rtol *
+/// |expected|`, or both are NaN; lengths must match too.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeriesDiff {
+    pub actual_len: usize,
+    pub expected_len: usize,
+    /// Points of the common length that disagree.
+    pub divergent: usize,
+    pub first_divergent: Option<usize>,
+    /// Largest absolute difference and where it is, over points where both are finite.
+    pub max_abs_error: f64,
+    pub max_abs_error_index: Option<usize>,
+    /// Mean, standard deviation and root mean square of `actual - expected` over points
+    /// where both are finite; NaN if there are none.
+    pub mean_error: f64,
+    pub std_error: f64,
+    pub rms_error: f64,
+}
+
+impl SeriesDiff {
+    pub fn new(actual: &TimeSeries<f64>, expected: &TimeSeries<f64>, rtol: f64, atol: f64) -> Self {
+        let agree = |a: f64, e: f64| (a.is_nan() && e.is_nan()) || a == e || (a - e).abs() <= atol + rtol * e.abs();
+        let pairs = || actual.as_slice().iter().zip(expected.as_slice()).map(|(&a, &e)| (a, e));
+        let mut divergent = pairs().enumerate().filter(|&(_, (a, e))| !agree(a, e)).map(|(i, _)| i);
+        let first_divergent = divergent.next();
+        let errors: Vec<(usize, f64)> =
+            pairs().map(|(a, e)| a - e).enumerate().filter(|(_, d)| d.is_finite()).collect();
+        let (max_abs_error_index, max_abs_error) = errors
+            .iter()
+            .map(|&(i, d)| (Some(i), d.abs()))
+            .fold((None, f64::NAN), |best, (i, d)| if best.1 >= d { best } else { (i, d) });
+        let n = errors.len() as f64;
+        let mean_error = errors.iter().map(|(_, d)| d).sum::<f64>() / n;
+        let rms_error = (errors.iter().map(|(_, d)| d * d).sum::<f64>() / n).sqrt();
+        let std_error = (errors.iter().map(|(_, d)| (d - mean_error).powi(2)).sum::<f64>() / n).sqrt();
+        Self {
+            actual_len: actual.len(),
+            expected_len: expected.len(),
+            divergent: first_divergent.map_or(0, |_| 1 + divergent.count()),
+            first_divergent,
+            max_abs_error,
+            max_abs_error_index,
+            mean_error,
+            std_error,
+            rms_error,
+        }
+    }
+
+    /// Whether the lengths match and every point agrees.
+    pub fn is_close(&self) -> bool {
+        self.actual_len == self.expected_len && self.divergent == 0
+    }
+}
+
+impl std::fmt::Display for SeriesDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.actual_len != self.expected_len {
+            writeln!(f, "length {} != expected {}", self.actual_len, self.expected_len)?;
+        }
+        match self.first_divergent {
+            Some(i) => writeln!(f, "{} divergent points, the first at {}", self.divergent, i)?,
+            None => writeln!(f, "no divergent points")?,
+        }
+        match self.max_abs_error_index {
+            Some(i) => writeln!(f, "max abs error {:e} at {}", self.max_abs_error, i)?,
+            None => writeln!(f, "no finite points to compare")?,
+        }
+        write!(f, "error mean {:e}, std {:e}, rms {:e}", self.mean_error, self.std_error, self.rms_error)
+    }
+}
+
+/// Asserts that two `TimeSeries<f64>` agree within a relative and an absolute tolerance,
+/// as [`SeriesDiff`] defines it, and panics with the diff report otherwise. Tolerances
+/// default to `rtol = 1e-9`, `atol = 0`.
+#[macro_export]
+macro_rules! assert_series_close {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::assert_series_close!($actual, $expected, 1e-9, 0.0)
+    };
+    ($actual:expr, $expected:expr, $rtol:expr, $atol:expr $(,)?) => {{
+        let diff = $crate::testing::SeriesDiff::new(&$actual, &$expected, $rtol, $atol);
+        if !diff.is_close() {
+            panic!("series are not close (rtol {}, atol {}):\n{}", $rtol, $atol, diff);
+        }
+    }};
+}
+
+/// `diff(cumsum(x), 1)` recovers `x` after its first point.
+pub fn check_diff_inverts_cumsum(series: &TimeSeries<f64>, tolerance: f64) -> Result<(), String> {
+    if series.len() < 2 {
+        return Ok(());
+    }
+    let cumsum: TimeSeries<f64> = series
+        .as_slice()
+        .iter()
+        .scan(0., |acc, &x| {
+            *acc += x;
+            Some(*acc)
+        })
+        .collect();
+    check_close(&cumsum.diff(1), &series.slice(1..series.len()), tolerance)
+}
+
+/// Resampling a series that is constant within each `step` bucket and reading it back
+/// as of each original timestamp reproduces it.
+pub fn check_resample_round_trip(series: &TimestampedSeries<f64>, step: i64) -> Result<(), String> {
+    // Make the series constant per bucket, using each bucket's first value.
+    let firsts = series.resample(step, |v| v[0]);
+    let constant: TimeSeries<f64> =
+        series.timestamps().iter().map(|&t| *firsts.asof(t).expect("every point has a bucket")).collect();
+    let constant = TimestampedSeries::from_parts(series.timestamps().to_vec(), constant);
+    let means = constant.resample(step, |v| v.iter().sum::<f64>() / v.len() as f64);
+    let upsampled: TimeSeries<f64> =
+        series.timestamps().iter().map(|&t| means.asof(t).copied().unwrap_or(f64::NAN)).collect();
+    check_close(&upsampled, constant.values(), 1e-12)
+}
+
+/// Fused rolling aggregates agree with statistics computed window by window.
+pub fn check_rolling_matches_naive(series: &TimeSeries<f64>, window: usize, tolerance: f64) -> Result<(), String> {
+    let aggs = [Agg::Sum, Agg::Mean, Agg::Std, Agg::Min, Agg::Max];
+    let fused = series.rolling(window).agg(&aggs);
+    let windows = || series.as_slice().windows(window).map(|w| TimeSeries(w.to_vec()));
+    let with_nan = |w: &TimeSeries<f64>, value: f64| if w.as_slice().iter().any(|x| x.is_nan()) { f64::NAN } else { value };
+    let naive: [TimeSeries<f64>; 5] = [
+        windows().map(|w| with_nan(&w, w.sum())).collect(),
+        windows().map(|w| with_nan(&w, w.mean())).collect(),
+        windows().map(|w| with_nan(&w, w.std())).collect(),
+        windows().map(|w| with_nan(&w, w.min())).collect(),
+        windows().map(|w| with_nan(&w, w.max())).collect(),
+    ];
+    for ((agg, fused), naive) in aggs.iter().zip(&fused).zip(&naive) {
+        check_close(fused, naive, tolerance).map_err(|e| format!("{:?}: {}", agg, e))?;
+    }
+    Ok(())
+}
+
+/// A streaming operator fed point by point emits the same values as the batch
+/// computation `batch`, e.g. `DiffOp::new(1)` against `|s| s.diff(1)`.
+pub fn check_streaming_matches_batch<O, F>(
+    mut op: O,
+    batch: F,
+    series: &TimeSeries<f64>,
+    tolerance: f64,
+) -> Result<(), String>
+where
+    O: StreamOp,
+    F: Fn(&TimeSeries<f64>) -> TimeSeries<f64>,
+{
+    check_close(&op.process(series), &batch(series), tolerance)
+}
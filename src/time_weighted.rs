@@ -0,0 +1,126 @@
+//! Statistics of irregularly sampled series where each value is weighted by how long it
+//! persisted, i.e. until the next timestamp. The last point has no known duration and
+//! therefore no weight.
+
+use crate::{TimeSeries, TimeSeriesFrame, TimestampedSeries};
+
+impl<T> TimestampedSeries<T> {
+    /// How long each value held: the gap to the next timestamp, 0 for the last point.
+    pub fn durations(&self) -> Vec<i64> {
+        let t = self.timestamps();
+        (0..t.len()).map(|i| t.get(i + 1).map_or(0, |next| next - t[i])).collect()
+    }
+
+    /// Fraction of the covered time during which `f` holds, e.g. the share of time a status
+    /// series reported "up". NaN when the series spans no time.
+    pub fn fraction_of_time<F>(&self, f: F) -> f64
+    where
+        F: Fn(&T) -> bool,
+    {
+        let durations = self.durations();
+        let total: i64 = durations.iter().sum();
+        let matching: i64 = self.values().as_slice().iter().zip(&durations).filter(|(v, _)| f(v)).map(|(_, d)| d).sum();
+        matching as f64 / total as f64
+    }
+}
+
+pub trait TimeWeighted {
+    fn time_weighted_mean(&self) -> f64;
+    /// Value below which the series spent a fraction `q` of the time.
+    fn time_weighted_quantile(&self, q: f64) -> f64;
+}
+
+impl TimeWeighted for TimestampedSeries<f64> {
+    fn time_weighted_mean(&self) -> f64 {
+        let durations = self.durations();
+        let total: i64 = durations.iter().sum();
+        let weighted: f64 = self.values().as_slice().iter().zip(&durations).map(|(v, &d)| v * d as f64).sum();
+        weighted / total as f64
+    }
+
+    fn time_weighted_quantile(&self, q: f64) -> f64 {
+        let mut weighted: Vec<(f64, i64)> = self
+            .values()
+            .as_slice()
+            .iter()
+            .copied()
+            .zip(self.durations())
+            .filter(|&(_, d)| d > 0)
+            .collect();
+        if weighted.is_empty() {
+            return f64::NAN;
+        }
+        weighted.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let total: i64 = weighted.iter().map(|(_, d)| d).sum();
+        let target = q.clamp(0., 1.) * total as f64;
+        let mut cumulative = 0.;
+        for &(v, d) in &weighted {
+            cumulative += d as f64;
+            if cumulative >= target {
+                return v;
+            }
+        }
+        weighted[weighted.len() - 1].0
+    }
+}
+
+impl TimestampedSeries<f64> {
+    /// Summarizes each `step`-long bucket, aligned to timestamp 0, into the columns
+    /// `first`, `last`, `min`, `max`, `mean`, `time_weighted_mean` and `count`, indexed by
+    /// bucket start. Empty buckets are left out and NaN values are ignored.
+    ///
+    /// The time-weighted mean holds each value until the next point or the end of the
+    /// bucket, and holds the previous bucket's last value until the bucket's first point.
+    ///
+    /// # Panics
+    /// Panics if `step` is not positive.
+    pub fn summarize_bucket(&self, step: i64) -> TimeSeriesFrame {
+        assert!(step > 0, "step must be positive");
+        let ts = self.timestamps();
+        let values = self.values().as_slice();
+        let names = ["first", "last", "min", "max", "mean", "time_weighted_mean", "count"];
+        let mut index = Vec::new();
+        let mut columns = vec![Vec::new(); names.len()];
+        let mut start = 0;
+        while start < ts.len() {
+            let bucket = ts[start].div_euclid(step) * step;
+            let bucket_end = bucket.saturating_add(step);
+            let end = start + ts[start..].partition_point(|&t| t < bucket_end);
+            let valid: Vec<f64> = values[start..end].iter().copied().filter(|v| !v.is_nan()).collect();
+            let (mut weighted, mut total) = (0., 0);
+            let carried = values[..start].iter().rev().find(|v| !v.is_nan());
+            if let Some(&v) = carried.filter(|_| ts[start] > bucket) {
+                weighted += v * (ts[start] - bucket) as f64;
+                total += ts[start] - bucket;
+            }
+            for i in start..end {
+                // The last point of the series has no known duration.
+                let Some(&next) = ts.get(i + 1) else { break };
+                let held = next.min(bucket_end) - ts[i];
+                if !values[i].is_nan() {
+                    weighted += values[i] * held as f64;
+                    total += held;
+                }
+            }
+            let row = [
+                valid.first().copied().unwrap_or(f64::NAN),
+                valid.last().copied().unwrap_or(f64::NAN),
+                valid.iter().copied().fold(f64::NAN, f64::min),
+                valid.iter().copied().fold(f64::NAN, f64::max),
+                valid.iter().sum::<f64>() / valid.len() as f64,
+                weighted / total as f64,
+                valid.len() as f64,
+            ];
+            index.push(bucket);
+            for (column, v) in columns.iter_mut().zip(row) {
+                column.push(v);
+            }
+            start = end;
+        }
+        let mut frame = TimeSeriesFrame::new(index);
+        for (name, column) in names.into_iter().zip(columns) {
+            frame.add_column(name, TimeSeries(column)).expect("columns match the index");
+        }
+        frame
+    }
+}
@@ -0,0 +1,388 @@
+//! Epoch timestamps with an explicit unit, and parsing of textual timestamps: ISO 8601 /
+//! RFC 3339 dates and date-times, Unix epochs with the unit detected from their
+//! magnitude, and `strftime`-style patterns.
+
+use crate::calendar::{days_from_civil, days_in_month};
+use crate::Error;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Resolution of parsed timestamps, counted from the Unix epoch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeUnit {
+    #[default]
+    Seconds,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl TimeUnit {
+    /// Ticks of this unit in one second.
+    pub fn per_second(&self) -> i64 {
+        (NANOS_PER_SECOND / self.nanos()) as i64
+    }
+
+    pub(crate) fn suffix(&self) -> &'static str {
+        match self {
+            TimeUnit::Seconds => "s",
+            TimeUnit::Millis => "ms",
+            TimeUnit::Micros => "us",
+            TimeUnit::Nanos => "ns",
+        }
+    }
+
+    fn nanos(&self) -> i128 {
+        match self {
+            TimeUnit::Seconds => 1_000_000_000,
+            TimeUnit::Millis => 1_000_000,
+            TimeUnit::Micros => 1_000,
+            TimeUnit::Nanos => 1,
+        }
+    }
+
+    /// The unit of an integer epoch, guessed from its magnitude: seconds up to 1e11
+    /// (year 5138), then milliseconds, microseconds and nanoseconds.
+    fn detect(epoch: i64) -> Self {
+        match epoch.unsigned_abs() {
+            0..=99_999_999_999 => TimeUnit::Seconds,
+            100_000_000_000..=99_999_999_999_999 => TimeUnit::Millis,
+            100_000_000_000_000..=99_999_999_999_999_999 => TimeUnit::Micros,
+            _ => TimeUnit::Nanos,
+        }
+    }
+}
+
+/// An epoch timestamp tagged with its unit, so that values from feeds in different
+/// units are converted rather than compared as bare integers. Comparisons between
+/// units compare the instants.
+#[derive(Clone, Copy, Debug)]
+pub struct Timestamp {
+    value: i64,
+    unit: TimeUnit,
+}
+
+impl Timestamp {
+    pub fn new(value: i64, unit: TimeUnit) -> Self {
+        Self { value, unit }
+    }
+
+    pub fn seconds(value: i64) -> Self {
+        Self::new(value, TimeUnit::Seconds)
+    }
+
+    pub fn millis(value: i64) -> Self {
+        Self::new(value, TimeUnit::Millis)
+    }
+
+    pub fn micros(value: i64) -> Self {
+        Self::new(value, TimeUnit::Micros)
+    }
+
+    pub fn nanos(value: i64) -> Self {
+        Self::new(value, TimeUnit::Nanos)
+    }
+
+    pub fn value(&self) -> i64 {
+        self.value
+    }
+
+    pub fn unit(&self) -> TimeUnit {
+        self.unit
+    }
+
+    /// The same instant in `unit`, rounded down when `unit` is coarser, or `None` if it
+    /// does not fit in an `i64`.
+    pub fn checked_to(&self, unit: TimeUnit) -> Option<Self> {
+        let value = i64::try_from(self.as_nanos().div_euclid(unit.nanos())).ok()?;
+        Some(Self { value, unit })
+    }
+
+    /// # Panics
+    /// Panics if the converted value does not fit in an `i64`.
+    pub fn to(&self, unit: TimeUnit) -> Self {
+        self.checked_to(unit).expect("timestamp out of range for unit")
+    }
+
+    fn as_nanos(&self) -> i128 {
+        self.value as i128 * self.unit.nanos()
+    }
+}
+
+impl PartialEq for Timestamp {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_nanos() == other.as_nanos()
+    }
+}
+
+impl Eq for Timestamp {}
+
+impl PartialOrd for Timestamp {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timestamp {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_nanos().cmp(&other.as_nanos())
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.value, self.unit.suffix())
+    }
+}
+
+/// How the text of a timestamp is laid out.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// A Unix epoch if the text is numeric, ISO 8601 otherwise.
+    #[default]
+    Auto,
+    /// `YYYY-MM-DD`, optionally followed by `T` (or a space) and `HH:MM[:SS[.fraction]]`
+    /// and a `Z` or `±HH[:MM]` offset. Times without an offset are taken as UTC; RFC 3339
+    /// timestamps are a subset.
+    Iso8601,
+    /// A Unix epoch, in the given unit or detected from the magnitude when `None`.
+    /// A fractional epoch is always in seconds.
+    Unix(Option<TimeUnit>),
+    /// A `strftime`-style pattern using `%Y` (year), `%y` (two-digit year, 1969-2068),
+    /// `%m`, `%d`, `%H`, `%M`, `%S`, `%f` (fraction of a second), `%b` (month name
+    /// abbreviation), `%z` (`Z` or `±HH[:MM]`), `%s` (epoch seconds) and `%%`. Other
+    /// characters must match literally.
+    Pattern(String),
+}
+
+/// Parses timestamps of one [`TimestampFormat`] into integers of one [`TimeUnit`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TimestampParser {
+    pub format: TimestampFormat,
+    pub unit: TimeUnit,
+}
+
+impl TimestampParser {
+    /// Parses `format` into Unix seconds.
+    pub fn new(format: TimestampFormat) -> Self {
+        Self { format, unit: TimeUnit::Seconds }
+    }
+
+    /// Parses `strftime`-style `pattern` into Unix seconds.
+    pub fn pattern<S: Into<String>>(pattern: S) -> Self {
+        Self::new(TimestampFormat::Pattern(pattern.into()))
+    }
+
+    pub fn with_unit(self, unit: TimeUnit) -> Self {
+        Self { unit, ..self }
+    }
+
+    pub fn parse(&self, text: &str) -> Result<i64, Error> {
+        let text = text.trim();
+        let nanos = match &self.format {
+            TimestampFormat::Auto if is_numeric(text) => parse_epoch(text, None),
+            TimestampFormat::Auto | TimestampFormat::Iso8601 => parse_iso(text),
+            TimestampFormat::Unix(unit) => parse_epoch(text, *unit),
+            TimestampFormat::Pattern(pattern) => parse_pattern(text, pattern),
+        };
+        let nanos = nanos.ok_or_else(|| Error::InvalidInput(format!("invalid timestamp `{}`", text)))?;
+        i64::try_from(nanos.div_euclid(self.unit.nanos()))
+            .map_err(|_| Error::InvalidInput(format!("timestamp `{}` out of range", text)))
+    }
+
+    /// [`parse`](Self::parse), tagged with the parser's unit.
+    pub fn parse_timestamp(&self, text: &str) -> Result<Timestamp, Error> {
+        self.parse(text).map(|value| Timestamp::new(value, self.unit))
+    }
+}
+
+/// Parses every entry of `raw`, failing on the first that does not parse.
+pub fn parse_timestamps<S: AsRef<str>>(raw: &[S], parser: &TimestampParser) -> Result<Vec<i64>, Error> {
+    raw.iter().map(|s| parser.parse(s.as_ref())).collect()
+}
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+fn is_numeric(text: &str) -> bool {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit() || b == b'.') && digits.bytes().any(|b| b != b'.')
+}
+
+fn parse_epoch(text: &str, unit: Option<TimeUnit>) -> Option<i128> {
+    if let Ok(epoch) = text.parse::<i64>() {
+        return Some(epoch as i128 * unit.unwrap_or_else(|| TimeUnit::detect(epoch)).nanos());
+    }
+    let (whole, fraction) = text.split_once('.')?;
+    let negative = whole.starts_with('-');
+    let seconds: i128 = if whole == "-" || whole.is_empty() { 0 } else { whole.parse().ok()? };
+    let mut cursor = Cursor::new(fraction);
+    let fraction = cursor.fraction()?;
+    cursor.is_done().then_some(seconds * NANOS_PER_SECOND + if negative { -fraction } else { fraction })
+}
+
+/// Nanoseconds since the epoch of a civil date-time and offset.
+fn civil_nanos(year: i64, month: i64, day: i64, seconds_of_day: i64, nanos: i128, offset: i64) -> Option<i128> {
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month as u32) as i64 {
+        return None;
+    }
+    let days = days_from_civil(year, month as u32, day as u32) as i128;
+    Some((days * 86_400 + (seconds_of_day - offset) as i128) * NANOS_PER_SECOND + nanos)
+}
+
+fn parse_iso(text: &str) -> Option<i128> {
+    let mut c = Cursor::new(text);
+    let year = c.number(4, 4)?;
+    c.expect(b'-')?;
+    let month = c.number(2, 2)?;
+    c.expect(b'-')?;
+    let day = c.number(2, 2)?;
+    if c.is_done() {
+        return civil_nanos(year, month, day, 0, 0, 0);
+    }
+    if !(c.eat(b'T') || c.eat(b't') || c.eat(b' ')) {
+        return None;
+    }
+    let hour = c.number(2, 2)?;
+    c.expect(b':')?;
+    let minute = c.number(2, 2)?;
+    let second = if c.eat(b':') { c.number(2, 2)? } else { 0 };
+    let nanos = if c.eat(b'.') || c.eat(b',') { c.fraction()? } else { 0 };
+    let offset = if c.is_done() { 0 } else { c.offset()? };
+    if !c.is_done() || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    civil_nanos(year, month, day, hour * 3600 + minute * 60 + second, nanos, offset)
+}
+
+fn parse_pattern(text: &str, pattern: &str) -> Option<i128> {
+    const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+    let mut c = Cursor::new(text);
+    let (mut year, mut month, mut day) = (1970, 1, 1);
+    let (mut hour, mut minute, mut second, mut nanos, mut offset) = (0, 0, 0, 0, 0);
+    let mut epoch = None;
+    let mut spec = pattern.bytes();
+    while let Some(p) = spec.next() {
+        if p != b'%' {
+            c.expect(p)?;
+            continue;
+        }
+        match spec.next()? {
+            b'Y' => {
+                let negative = c.eat(b'-');
+                year = c.number(4, 4)? * if negative { -1 } else { 1 };
+            }
+            b'y' => {
+                let short = c.number(2, 2)?;
+                year = if short < 69 { 2000 + short } else { 1900 + short };
+            }
+            b'm' => month = c.number(1, 2)?,
+            b'd' => day = c.number(1, 2)?,
+            b'H' => hour = c.number(1, 2)?,
+            b'M' => minute = c.number(1, 2)?,
+            b'S' => second = c.number(1, 2)?,
+            b'f' => nanos = c.fraction()?,
+            b'z' => offset = c.offset()?,
+            b'b' => {
+                let name = c.take(3)?.to_ascii_lowercase();
+                month = MONTHS.iter().position(|m| m.as_bytes() == name.as_slice())? as i64 + 1;
+            }
+            b's' => {
+                let negative = c.eat(b'-');
+                epoch = Some(c.number(1, 19)? * if negative { -1 } else { 1 });
+            }
+            b'%' => c.expect(b'%')?,
+            _ => return None,
+        }
+    }
+    if !c.is_done() {
+        return None;
+    }
+    if let Some(epoch) = epoch {
+        return Some(epoch as i128 * NANOS_PER_SECOND + nanos);
+    }
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    civil_nanos(year, month, day, hour * 3600 + minute * 60 + second, nanos, offset)
+}
+
+/// Position in the text being parsed.
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { bytes: text.as_bytes(), pos: 0 }
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        let matched = self.bytes.get(self.pos) == Some(&b);
+        self.pos += matched as usize;
+        matched
+    }
+
+    fn expect(&mut self, b: u8) -> Option<()> {
+        self.eat(b).then_some(())
+    }
+
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let taken = self.bytes.get(self.pos..self.pos + n)?;
+        self.pos += n;
+        Some(taken)
+    }
+
+    /// Between `min` and `max` decimal digits.
+    fn number(&mut self, min: usize, max: usize) -> Option<i64> {
+        let digits = self.bytes[self.pos..].iter().take(max).take_while(|b| b.is_ascii_digit()).count();
+        if digits < min {
+            return None;
+        }
+        let value = std::str::from_utf8(&self.bytes[self.pos..self.pos + digits]).ok()?.parse().ok()?;
+        self.pos += digits;
+        Some(value)
+    }
+
+    /// Digits of a decimal fraction as nanoseconds; digits past the ninth are dropped.
+    fn fraction(&mut self) -> Option<i128> {
+        let digits = self.bytes[self.pos..].iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return None;
+        }
+        let nanos = self.bytes[self.pos..self.pos + digits.min(9)]
+            .iter()
+            .chain(std::iter::repeat(&b'0'))
+            .take(9)
+            .fold(0, |acc, &b| acc * 10 + (b - b'0') as i128);
+        self.pos += digits;
+        Some(nanos)
+    }
+
+    /// `Z` or `±HH[[:]MM]`, in seconds east of UTC.
+    fn offset(&mut self) -> Option<i64> {
+        if self.eat(b'Z') || self.eat(b'z') {
+            return Some(0);
+        }
+        let sign = if self.eat(b'+') {
+            1
+        } else if self.eat(b'-') {
+            -1
+        } else {
+            return None;
+        };
+        let hours = self.number(2, 2)?;
+        let colon = self.eat(b':');
+        let minutes = match self.number(2, 2) {
+            Some(m) => m,
+            None if colon => return None,
+            None => 0,
+        };
+        (hours <= 23 && minutes <= 59).then_some(sign * (hours * 3600 + minutes * 60))
+    }
+}
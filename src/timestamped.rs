@@ -0,0 +1,230 @@
+//! Series whose points carry an integer timestamp.
+//!
+//! Timestamps are plain `i64` values in whatever unit the caller uses consistently
+//! (seconds, milliseconds, ...). They are kept in non-decreasing order, which the
+//! lookups and joins below rely on. A series may be tagged with its [`TimeUnit`], after
+//! which joins refuse series in another unit and [`Timestamp`]s are converted into it.
+
+use crate::{Error, TimeSeries, TimeUnit, Timestamp};
+
+#[derive(Clone, Debug)]
+pub struct TimestampedSeries<T> {
+    timestamps: Vec<i64>,
+    values: TimeSeries<T>,
+    unit: Option<TimeUnit>,
+}
+
+impl<T> Default for TimestampedSeries<T> {
+    fn default() -> Self {
+        Self { timestamps: Vec::new(), values: TimeSeries::default(), unit: None }
+    }
+}
+
+impl<T> TimestampedSeries<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a series from matching timestamps and values.
+    ///
+    /// # Panics
+    /// Panics if the lengths differ or the timestamps are not sorted.
+    pub fn from_parts(timestamps: Vec<i64>, values: TimeSeries<T>) -> Self {
+        assert_eq!(timestamps.len(), values.as_slice().len(), "timestamps and values must have the same length");
+        assert!(timestamps.windows(2).all(|w| w[0] <= w[1]), "timestamps must be sorted");
+        Self { timestamps, values, unit: None }
+    }
+
+    /// Tags the timestamps as counted in `unit`, without converting them.
+    pub fn with_unit(self, unit: TimeUnit) -> Self {
+        Self { unit: Some(unit), ..self }
+    }
+
+    /// The unit the timestamps are counted in, if the series was tagged.
+    pub fn unit(&self) -> Option<TimeUnit> {
+        self.unit
+    }
+
+    /// The series with its timestamps converted to `unit`, rounding down when `unit` is
+    /// coarser.
+    ///
+    /// # Panics
+    /// Panics if the series has no unit or a timestamp does not fit in `unit`.
+    pub fn to_unit(self, unit: TimeUnit) -> Self {
+        let from = self.unit.expect("series has no time unit");
+        let timestamps = self.timestamps.iter().map(|&t| Timestamp::new(t, from).to(unit).value()).collect();
+        Self { timestamps, values: self.values, unit: Some(unit) }
+    }
+
+    /// The timestamp of point `i` with the series' unit, `None` if there is no such point
+    /// or the series has no unit.
+    pub fn timestamp(&self, i: usize) -> Option<Timestamp> {
+        let unit = self.unit?;
+        self.timestamps.get(i).map(|&t| Timestamp::new(t, unit))
+    }
+
+    /// Appends a point at `timestamp`, converted to the series' unit. The first point
+    /// pushed into an untagged, empty series sets the unit.
+    ///
+    /// # Panics
+    /// Panics if the series is untagged but not empty, or as [`push`](Self::push) does.
+    pub fn push_timestamp(&mut self, timestamp: Timestamp, value: T) {
+        if self.unit.is_none() {
+            assert!(self.is_empty(), "series has no time unit");
+            self.unit = Some(timestamp.unit());
+        }
+        let unit = self.unit.expect("unit was set above");
+        self.push(timestamp.to(unit).value(), value);
+    }
+
+    /// Appends a point.
+    ///
+    /// # Panics
+    /// Panics if `timestamp` is earlier than the last timestamp.
+    pub fn push(&mut self, timestamp: i64, value: T) {
+        if let Some(&last) = self.timestamps.last() {
+            assert!(timestamp >= last, "timestamps must be pushed in order");
+        }
+        self.timestamps.push(timestamp);
+        self.values.push(value);
+    }
+
+    /// The value of the last point, to update in place.
+    pub(crate) fn last_mut(&mut self) -> Option<&mut T> {
+        self.values.as_mut_slice().last_mut()
+    }
+
+    /// Removes the points earlier than `timestamp`, returning how many there were.
+    pub fn drop_before(&mut self, timestamp: i64) -> usize {
+        let n = self.timestamps.partition_point(|&t| t < timestamp);
+        self.timestamps.drain(..n);
+        let mut values = std::mem::replace(&mut self.values, TimeSeries::from_vec(Vec::new())).into_vec();
+        values.drain(..n);
+        self.values = TimeSeries::from_vec(values);
+        n
+    }
+
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    pub fn timestamps(&self) -> &[i64] {
+        &self.timestamps
+    }
+
+    pub fn values(&self) -> &TimeSeries<T> {
+        &self.values
+    }
+
+    pub fn into_parts(self) -> (Vec<i64>, TimeSeries<T>) {
+        (self.timestamps, self.values)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (i64, &T)> {
+        self.timestamps.iter().copied().zip(self.values.as_slice().iter())
+    }
+
+    pub fn map<U, F>(&self, f: F) -> TimestampedSeries<U>
+    where
+        F: Fn(&T) -> U,
+    {
+        TimestampedSeries {
+            timestamps: self.timestamps.clone(),
+            values: TimeSeries(self.values.as_slice().iter().map(f).collect()),
+            unit: self.unit,
+        }
+    }
+
+    /// Index of the last point at or before `timestamp`.
+    pub fn asof_index(&self, timestamp: i64) -> Option<usize> {
+        self.timestamps.partition_point(|&t| t <= timestamp).checked_sub(1)
+    }
+
+    /// Value of the last point at or before `timestamp`.
+    pub fn asof(&self, timestamp: i64) -> Option<&T> {
+        self.asof_index(timestamp).map(|i| &self.values.as_slice()[i])
+    }
+
+    /// Points with `start <= timestamp < end`.
+    pub fn between(&self, start: i64, end: i64) -> Self
+    where
+        T: Clone,
+    {
+        let lo = self.timestamps.partition_point(|&t| t < start);
+        let hi = self.timestamps.partition_point(|&t| t < end).max(lo);
+        Self {
+            timestamps: self.timestamps[lo..hi].to_vec(),
+            values: self.values.slice(lo..hi),
+            unit: self.unit,
+        }
+    }
+
+    /// Pairs every point of `self` with the latest point of `other` at or before it,
+    /// e.g. each trade with the quote prevailing when it printed.
+    ///
+    /// Fails if the series are tagged with different units, or only one of them is tagged,
+    /// as their timestamps would not be comparable; convert with [`to_unit`](Self::to_unit)
+    /// or tag with [`with_unit`](Self::with_unit) first.
+    pub fn asof_join<U>(&self, other: &TimestampedSeries<U>) -> Result<TimestampedSeries<(T, Option<U>)>, Error>
+    where
+        T: Clone,
+        U: Clone,
+    {
+        trace_span!("asof_join", left = self.len(), right = other.len());
+        if self.unit != other.unit {
+            let name = |unit: Option<TimeUnit>| unit.map_or_else(|| "no unit".to_string(), |u| format!("{:?}", u));
+            return Err(Error::InvalidInput(format!(
+                "cannot join series in {} with series in {}",
+                name(self.unit),
+                name(other.unit)
+            )));
+        }
+        let joined: TimestampedSeries<_> =
+            self.iter().map(|(t, v)| (t, (v.clone(), other.asof(t).cloned()))).collect();
+        Ok(TimestampedSeries { unit: self.unit, ..joined })
+    }
+}
+
+impl<T: Clone> TimestampedSeries<T> {
+    /// Groups the points into `step`-long buckets aligned to timestamp 0 and reduces each
+    /// non-empty bucket with `agg`, keyed by the bucket start. Bucket bounds saturate at the
+    /// ends of the timestamp range: a bucket starting before `i64::MIN` is keyed by
+    /// `i64::MIN`.
+    pub fn resample<U, F>(&self, step: i64, agg: F) -> TimestampedSeries<U>
+    where
+        F: Fn(&[T]) -> U,
+    {
+        assert!(step > 0, "step must be positive");
+        let mut out = TimestampedSeries::new();
+        let mut start = 0;
+        while start < self.len() {
+            let first = self.timestamps[start];
+            let offset = first.rem_euclid(step);
+            let bucket = first.saturating_sub(offset);
+            let end = match first.checked_add(step - offset) {
+                Some(next) => start + self.timestamps[start..].partition_point(|&t| t < next),
+                None => self.len(),
+            };
+            out.push(bucket, agg(&self.values.as_slice()[start..end]));
+            start = end;
+        }
+        out.unit = self.unit;
+        out
+    }
+}
+
+impl<T> FromIterator<(i64, T)> for TimestampedSeries<T> {
+    /// # Panics
+    /// Panics if the timestamps are not sorted.
+    fn from_iter<I: IntoIterator<Item = (i64, T)>>(iter: I) -> Self {
+        let mut series = Self::new();
+        for (t, v) in iter {
+            series.push(t, v);
+        }
+        series
+    }
+}
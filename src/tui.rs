@@ -0,0 +1,105 @@
+//! A `ratatui` widget drawing a series as a multi-row sparkline, and a bounded buffer
+//! for feeding it from a live stream.
+
+use crate::sparkline::BLOCKS;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::widgets::{Block, Widget};
+
+/// The latest values of a series, oldest first. Draw it with [`SeriesSparkline`] on each
+/// frame, which shows as many of the newest values as the area is wide.
+#[derive(Clone, Debug)]
+pub struct LiveSeries {
+    values: Vec<f64>,
+    capacity: usize,
+}
+
+impl LiveSeries {
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        Self { values: Vec::with_capacity(2 * capacity), capacity }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        // Shift out old values only once the buffer has doubled, so pushes stay O(1)
+        // amortized.
+        if self.values.len() == 2 * self.capacity {
+            self.values.drain(..self.capacity);
+        }
+        self.values.push(value);
+    }
+
+    /// At most `capacity` values, oldest first.
+    pub fn values(&self) -> &[f64] {
+        &self.values[self.values.len().saturating_sub(self.capacity)..]
+    }
+
+    pub fn sparkline(&self) -> SeriesSparkline<'_> {
+        SeriesSparkline::new(self.values())
+    }
+}
+
+/// Bars of eighth-cell resolution across the full height of the area, one column per
+/// value, right-aligned so the newest values stay visible. Bars are scaled between the
+/// minimum and maximum of the values shown; non-finite values are gaps.
+#[derive(Clone, Debug, Default)]
+pub struct SeriesSparkline<'a> {
+    values: &'a [f64],
+    style: Style,
+    block: Option<Block<'a>>,
+}
+
+impl<'a> SeriesSparkline<'a> {
+    pub fn new(values: &'a [f64]) -> Self {
+        Self { values, style: Style::default(), block: None }
+    }
+
+    pub fn with_style(self, style: Style) -> Self {
+        Self { style, ..self }
+    }
+
+    /// Draws the bars inside `block`, e.g. a bordered box with a title.
+    pub fn with_block(self, block: Block<'a>) -> Self {
+        Self { block: Some(block), ..self }
+    }
+}
+
+impl Widget for SeriesSparkline<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = match self.block {
+            Some(block) => {
+                let inner = block.inner(area);
+                block.render(area, buf);
+                inner
+            }
+            None => area,
+        };
+        if area.is_empty() {
+            return;
+        }
+        let shown = &self.values[self.values.len().saturating_sub(area.width as usize)..];
+        let (lo, hi) = shown
+            .iter()
+            .filter(|v| v.is_finite())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+        let eighths = area.height as f64 * 8.;
+        let x0 = area.right() - shown.len() as u16;
+        for (x, &v) in (x0..).zip(shown).filter(|(_, v)| v.is_finite()) {
+            let fraction = if hi > lo { (v - lo) / (hi - lo) } else { 0.5 };
+            // At least one eighth, so the minimum still shows.
+            let mut level = ((fraction * eighths).round() as usize).max(1);
+            for y in (area.top()..area.bottom()).rev() {
+                if level == 0 {
+                    break;
+                }
+                if let Some(cell) = buf.cell_mut((x, y)) {
+                    cell.set_char(BLOCKS[level.min(8) - 1]).set_style(self.style);
+                }
+                level = level.saturating_sub(8);
+            }
+        }
+    }
+}
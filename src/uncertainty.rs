@@ -0,0 +1,142 @@
+//! Measurements with standard errors, and the propagation of those errors through
+//! arithmetic, rolling means and resampling.
+//!
+//! Errors are one standard deviation and assumed independent between points, so sums
+//! combine them in quadrature and products to first order. Averages weight each point
+//! by its inverse variance, which is the minimum-variance unbiased combination of
+//! independent measurements of one quantity.
+
+use crate::{TimeSeries, TimestampedSeries};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A value with its standard error.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Measurement {
+    pub value: f64,
+    pub error: f64,
+}
+
+impl Measurement {
+    /// # Panics
+    /// Panics if `error` is negative.
+    pub fn new(value: f64, error: f64) -> Self {
+        assert!(error >= 0. || error.is_nan(), "standard error must not be negative");
+        Self { value, error }
+    }
+
+    /// A value known without error, such as a constant.
+    pub fn exact(value: f64) -> Self {
+        Self { value, error: 0. }
+    }
+
+    /// Inverse variance; infinite for exact values.
+    pub fn weight(&self) -> f64 {
+        1. / (self.error * self.error)
+    }
+
+    pub fn relative_error(&self) -> f64 {
+        self.error / self.value.abs()
+    }
+}
+
+/// The inverse-variance weighted mean of `measurements` and its standard error. Exact
+/// values outweigh all others; an empty slice gives NaN.
+pub fn inverse_variance_mean(measurements: &[Measurement]) -> Measurement {
+    let exact: Vec<f64> = measurements.iter().filter(|m| m.error == 0.).map(|m| m.value).collect();
+    if !exact.is_empty() {
+        return Measurement::exact(exact.iter().sum::<f64>() / exact.len() as f64);
+    }
+    if measurements.is_empty() {
+        return Measurement { value: f64::NAN, error: f64::NAN };
+    }
+    let (sum_w, sum_wx) = measurements.iter().fold((0., 0.), |(w, wx), m| (w + m.weight(), wx + m.weight() * m.value));
+    Measurement { value: sum_wx / sum_w, error: sum_w.sqrt().recip() }
+}
+
+macro_rules! propagate {
+    ($trait:ident, $method:ident, |$a:ident, $b:ident| $body:expr) => {
+        impl $trait for Measurement {
+            type Output = Measurement;
+            fn $method(self, rhs: Measurement) -> Measurement {
+                let ($a, $b) = (self, rhs);
+                $body
+            }
+        }
+
+        impl $trait for &Measurement {
+            type Output = Measurement;
+            fn $method(self, rhs: &Measurement) -> Measurement {
+                $trait::$method(*self, *rhs)
+            }
+        }
+
+        impl $trait<f64> for Measurement {
+            type Output = Measurement;
+            fn $method(self, rhs: f64) -> Measurement {
+                $trait::$method(self, Measurement::exact(rhs))
+            }
+        }
+    };
+}
+
+propagate!(Add, add, |a, b| Measurement { value: a.value + b.value, error: a.error.hypot(b.error) });
+propagate!(Sub, sub, |a, b| Measurement { value: a.value - b.value, error: a.error.hypot(b.error) });
+propagate!(Mul, mul, |a, b| Measurement {
+    value: a.value * b.value,
+    error: (b.value * a.error).hypot(a.value * b.error),
+});
+propagate!(Div, div, |a, b| Measurement {
+    value: a.value / b.value,
+    error: (a.error / b.value).hypot(a.value * b.error / (b.value * b.value)),
+});
+
+impl Neg for Measurement {
+    type Output = Measurement;
+    fn neg(self) -> Measurement {
+        Measurement { value: -self.value, error: self.error }
+    }
+}
+
+impl TimeSeries<f64> {
+    /// Pairs each value with the standard error at the same position.
+    ///
+    /// # Panics
+    /// Panics if the lengths differ or an error is negative.
+    pub fn with_errors(&self, errors: &TimeSeries<f64>) -> TimeSeries<Measurement> {
+        assert_eq!(self.len(), errors.len(), "one error per value");
+        TimeSeries(self.as_slice().iter().zip(errors.as_slice()).map(|(&v, &e)| Measurement::new(v, e)).collect())
+    }
+}
+
+impl TimeSeries<Measurement> {
+    /// The values without their errors.
+    pub fn nominal(&self) -> TimeSeries<f64> {
+        self.map(|m| m.value)
+    }
+
+    pub fn errors(&self) -> TimeSeries<f64> {
+        self.map(|m| m.error)
+    }
+
+    /// The [inverse-variance mean](inverse_variance_mean) of the whole series.
+    pub fn weighted_mean(&self) -> Measurement {
+        inverse_variance_mean(self.as_slice())
+    }
+
+    /// Inverse-variance means of trailing windows, one per full window.
+    ///
+    /// # Panics
+    /// Panics if `window` is zero.
+    pub fn rolling_weighted_mean(&self, window: usize) -> TimeSeries<Measurement> {
+        assert!(window > 0, "window must be positive");
+        TimeSeries(self.as_slice().windows(window).map(inverse_variance_mean).collect())
+    }
+}
+
+impl TimestampedSeries<Measurement> {
+    /// [`resample`](TimestampedSeries::resample) by the inverse-variance mean of each
+    /// bucket, so precise points dominate and the bucket's error shrinks with its size.
+    pub fn resample_weighted(&self, step: i64) -> TimestampedSeries<Measurement> {
+        self.resample(step, inverse_variance_mean)
+    }
+}
@@ -0,0 +1,171 @@
+//! Vega-Lite chart specifications for series and frames, so a web frontend can render
+//! interactive charts from data the crate only serializes.
+//!
+//! Rows have a `timestamp` field and, for a series, a `value` field or, for a frame, one
+//! field per column that the spec folds into `series` and `value`. Non-finite values are
+//! written as `null`, which Vega-Lite leaves as gaps.
+
+use crate::lineage::json_string;
+use crate::{TimeSeriesFrame, TimeUnit, TimestampedSeries};
+use std::fmt::Write as _;
+
+const SCHEMA: &str = "https://vega.github.io/schema/vega-lite/v5.json";
+
+/// How each row is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VegaMark {
+    #[default]
+    Line,
+    Point,
+    Bar,
+    Area,
+}
+
+impl VegaMark {
+    fn name(&self) -> &'static str {
+        match self {
+            VegaMark::Line => "line",
+            VegaMark::Point => "point",
+            VegaMark::Bar => "bar",
+            VegaMark::Area => "area",
+        }
+    }
+}
+
+/// Builder of Vega-Lite specs. Without a [`TimeUnit`] the x axis is the raw timestamp
+/// as a number; with one it is a time axis.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VegaLite {
+    pub title: Option<String>,
+    pub mark: VegaMark,
+    /// URL of the rows (CSV or JSON, as Vega-Lite infers from the extension); the rows
+    /// are inlined when `None`.
+    pub url: Option<String>,
+    pub unit: Option<TimeUnit>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl VegaLite {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_title<S: Into<String>>(self, title: S) -> Self {
+        Self { title: Some(title.into()), ..self }
+    }
+
+    pub fn with_mark(self, mark: VegaMark) -> Self {
+        Self { mark, ..self }
+    }
+
+    /// References the rows at `url` instead of inlining them, e.g. a CSV written with
+    /// [`TimestampedSeries::save_csv`].
+    pub fn with_url<S: Into<String>>(self, url: S) -> Self {
+        Self { url: Some(url.into()), ..self }
+    }
+
+    /// Draws the x axis as time, reading timestamps as counted in `unit` since the epoch.
+    pub fn with_unit(self, unit: TimeUnit) -> Self {
+        Self { unit: Some(unit), ..self }
+    }
+
+    pub fn with_size(self, width: u32, height: u32) -> Self {
+        Self { width: Some(width), height: Some(height), ..self }
+    }
+
+    /// The spec of a single series.
+    pub fn series(&self, series: &TimestampedSeries<f64>) -> String {
+        let rows = series.iter().map(|(t, &v)| format!("{{\"timestamp\":{},\"value\":{}}}", t, number(v)));
+        self.spec(rows, None, "")
+    }
+
+    /// The spec of every column of `frame`, one colored line (or mark) per column.
+    pub fn frame(&self, frame: &TimeSeriesFrame) -> String {
+        let names: Vec<&str> = frame.columns().map(|(name, _)| name).collect();
+        let rows = frame.index().iter().enumerate().map(|(i, t)| {
+            let mut row = format!("{{\"timestamp\":{}", t);
+            for (name, column) in frame.columns() {
+                let _ = write!(row, ",{}:{}", json_string(name), number(column.as_slice()[i]));
+            }
+            row.push('}');
+            row
+        });
+        let fields: Vec<String> = names.iter().map(|name| json_string(name)).collect();
+        let fold = format!("{{\"fold\":[{}],\"as\":[\"series\",\"value\"]}}", fields.join(","));
+        let color = ",\"color\":{\"field\":\"series\",\"type\":\"nominal\"}";
+        self.spec(rows, Some(fold), color)
+    }
+
+    fn spec<I: Iterator<Item = String>>(&self, rows: I, fold: Option<String>, color: &str) -> String {
+        let mut out = format!("{{\"$schema\":{}", json_string(SCHEMA));
+        if let Some(title) = &self.title {
+            let _ = write!(out, ",\"title\":{}", json_string(title));
+        }
+        if let (Some(width), Some(height)) = (self.width, self.height) {
+            let _ = write!(out, ",\"width\":{},\"height\":{}", width, height);
+        }
+        match &self.url {
+            Some(url) => {
+                let _ = write!(out, ",\"data\":{{\"url\":{}}}", json_string(url));
+            }
+            None => {
+                let _ = write!(out, ",\"data\":{{\"values\":[{}]}}", rows.collect::<Vec<_>>().join(","));
+            }
+        }
+        let mut transforms: Vec<String> = fold.into_iter().collect();
+        // Vega-Lite times are milliseconds since the epoch.
+        let x = match self.unit {
+            Some(unit) => {
+                let millis = match unit {
+                    TimeUnit::Seconds => "datum.timestamp * 1000",
+                    TimeUnit::Millis => "datum.timestamp",
+                    TimeUnit::Micros => "datum.timestamp / 1000",
+                    TimeUnit::Nanos => "datum.timestamp / 1000000",
+                };
+                transforms.push(format!("{{\"calculate\":{},\"as\":\"time\"}}", json_string(millis)));
+                "{\"field\":\"time\",\"type\":\"temporal\",\"title\":\"time\"}"
+            }
+            None => "{\"field\":\"timestamp\",\"type\":\"quantitative\"}",
+        };
+        if !transforms.is_empty() {
+            let _ = write!(out, ",\"transform\":[{}]", transforms.join(","));
+        }
+        let _ = write!(
+            out,
+            ",\"mark\":{{\"type\":{},\"tooltip\":true}},\"encoding\":{{\"x\":{},\
+             \"y\":{{\"field\":\"value\",\"type\":\"quantitative\"}}{}}}}}",
+            json_string(self.mark.name()),
+            x,
+            color
+        );
+        out
+    }
+}
+
+fn number(v: f64) -> String {
+    if v.is_finite() {
+        v.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+impl TimestampedSeries<f64> {
+    /// A line chart spec with the rows inlined, on a time axis if the series is tagged
+    /// with a unit. Use [`VegaLite`] for other marks or referenced data.
+    pub fn to_vega_lite(&self) -> String {
+        let builder = VegaLite::new();
+        match self.unit() {
+            Some(unit) => builder.with_unit(unit).series(self),
+            None => builder.series(self),
+        }
+    }
+}
+
+impl TimeSeriesFrame {
+    /// A line chart spec of every column with the rows inlined.
+    pub fn to_vega_lite(&self) -> String {
+        VegaLite::new().frame(self)
+    }
+}
@@ -0,0 +1,137 @@
+//! Walk-forward parameter optimization: choose parameters on each in-sample window and
+//! score them only on the following, unseen window.
+
+use crate::Monitor;
+#[cfg(feature = "random")]
+use rand::Rng;
+use std::ops::Range;
+
+/// Window layout of a walk-forward run, in points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WalkForward {
+    /// Length of each in-sample (optimization) window.
+    pub train: usize,
+    /// Length of each out-of-sample (evaluation) window.
+    pub test: usize,
+    /// Grow the in-sample window from the start instead of rolling it.
+    pub anchored: bool,
+}
+
+impl WalkForward {
+    pub fn new(train: usize, test: usize) -> Self {
+        assert!(train > 0 && test > 0, "window lengths must be positive");
+        Self { train, test, anchored: false }
+    }
+
+    pub fn anchored(self) -> Self {
+        Self { anchored: true, ..self }
+    }
+
+    /// `(in-sample, out-of-sample)` index ranges over a series of `len` points. Windows
+    /// advance by `test` points so the out-of-sample ranges tile the data without overlap.
+    pub fn splits(&self, len: usize) -> Vec<(Range<usize>, Range<usize>)> {
+        let mut out = Vec::new();
+        let mut start = 0;
+        while start + self.train + self.test <= len {
+            let split = start + self.train;
+            let train_start = if self.anchored { 0 } else { start };
+            out.push((train_start..split, split..split + self.test));
+            start += self.test;
+        }
+        out
+    }
+
+    /// Picks, on each in-sample range, the candidate with the highest `score`, then scores
+    /// it on the out-of-sample range. `score(params, range)` evaluates a parameter set on
+    /// the points in `range`; NaN scores are never selected.
+    pub fn optimize<P, F>(&self, len: usize, candidates: &[P], score: F) -> WalkForwardResult<P>
+    where
+        P: Clone,
+        F: Fn(&P, Range<usize>) -> f64,
+    {
+        self.optimize_monitored(len, candidates, score, &mut Monitor::new())
+    }
+
+    /// [`optimize`](Self::optimize), reporting progress to `monitor` after each window.
+    /// If cancelled, returns the windows completed so far.
+    pub fn optimize_monitored<P, F>(
+        &self,
+        len: usize,
+        candidates: &[P],
+        score: F,
+        monitor: &mut Monitor,
+    ) -> WalkForwardResult<P>
+    where
+        P: Clone,
+        F: Fn(&P, Range<usize>) -> f64,
+    {
+        trace_span!("walk_forward", n = len, candidates = candidates.len());
+        let splits = self.splits(len);
+        let total = splits.len();
+        let mut steps = Vec::with_capacity(total);
+        for (done, (train, test)) in splits.into_iter().enumerate() {
+            if monitor.is_cancelled() {
+                return WalkForwardResult { steps };
+            }
+            let best = candidates
+                .iter()
+                .map(|p| (p, score(p, train.clone())))
+                .filter(|(_, s)| !s.is_nan())
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((best, in_sample)) = best {
+                let out_of_sample = score(best, test.clone());
+                steps.push(WalkForwardStep { train, test, params: best.clone(), in_sample, out_of_sample });
+            }
+            monitor.report(done + 1, total);
+        }
+        monitor.finish();
+        WalkForwardResult { steps }
+    }
+}
+
+/// `n` parameter sets drawn with `sample`, for random search with
+/// [`WalkForward::optimize`].
+#[cfg(feature = "random")]
+pub fn random_candidates<P, R, F>(n: usize, rng: &mut R, mut sample: F) -> Vec<P>
+where
+    R: Rng + ?Sized,
+    F: FnMut(&mut R) -> P,
+{
+    (0..n).map(|_| sample(rng)).collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct WalkForwardStep<P> {
+    pub train: Range<usize>,
+    pub test: Range<usize>,
+    /// Parameters chosen on the in-sample window.
+    pub params: P,
+    pub in_sample: f64,
+    pub out_of_sample: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct WalkForwardResult<P> {
+    pub steps: Vec<WalkForwardStep<P>>,
+}
+
+impl<P: Clone> WalkForwardResult<P> {
+    /// Chosen parameters, one per step.
+    pub fn parameter_path(&self) -> Vec<P> {
+        self.steps.iter().map(|s| s.params.clone()).collect()
+    }
+
+    pub fn mean_in_sample(&self) -> f64 {
+        self.steps.iter().map(|s| s.in_sample).sum::<f64>() / self.steps.len() as f64
+    }
+
+    pub fn mean_out_of_sample(&self) -> f64 {
+        self.steps.iter().map(|s| s.out_of_sample).sum::<f64>() / self.steps.len() as f64
+    }
+
+    /// Mean out-of-sample over mean in-sample score; values well below 1 point to
+    /// overfitting.
+    pub fn efficiency(&self) -> f64 {
+        self.mean_out_of_sample() / self.mean_in_sample()
+    }
+}
@@ -0,0 +1,65 @@
+//! Warm-up detection: where a simulation or benchmark output settles into its steady
+//! state, so the initial transient can be dropped before computing statistics.
+//!
+//! The steady state is taken to be the behavior of the last window of the series. The
+//! warm-up ends at the first trailing window from which every later window has a mean and
+//! standard deviation within a tolerance of that reference.
+
+use crate::{Agg, Error, TimeSeries, Windowed};
+
+/// The outcome of [`Stabilization::trim_warmup`].
+#[derive(Clone, Debug)]
+pub struct WarmUp {
+    /// Number of leading points dropped.
+    pub length: usize,
+    /// The points from the end of the warm-up on.
+    pub steady: TimeSeries<f64>,
+}
+
+pub trait Stabilization {
+    /// The number of leading points before the series settles. A window of `window`
+    /// points is settled when its mean is within `tolerance` reference standard
+    /// deviations of the reference mean and its standard deviation within a relative
+    /// `tolerance` of the reference one, the reference being the last window.
+    ///
+    /// Fails with [`Error::InsufficientData`] for fewer than `2 * window` points, and
+    /// with [`Error::InvalidInput`] if a value is not finite.
+    ///
+    /// # Panics
+    /// Panics if `window` is less than 2 or `tolerance` is negative.
+    fn warmup_length(&self, window: usize, tolerance: f64) -> Result<usize, Error>;
+    /// Drops the warm-up found by [`warmup_length`](Self::warmup_length).
+    ///
+    /// # Panics
+    /// Panics if `window` is less than 2 or `tolerance` is negative.
+    fn trim_warmup(&self, window: usize, tolerance: f64) -> Result<WarmUp, Error>;
+}
+
+impl Stabilization for TimeSeries<f64> {
+    fn warmup_length(&self, window: usize, tolerance: f64) -> Result<usize, Error> {
+        assert!(window >= 2, "window must hold at least two points");
+        assert!(tolerance >= 0., "tolerance must not be negative");
+        if self.len() < 2 * window {
+            return Err(Error::InsufficientData { required: 2 * window, found: self.len() });
+        }
+        if let Some(i) = self.as_slice().iter().position(|v| !v.is_finite()) {
+            return Err(Error::InvalidInput(format!("value at {} is not finite", i)));
+        }
+        let stats = self.rolling(window).agg(&[Agg::Mean, Agg::Std]);
+        let (means, stds) = (stats[0].as_slice(), stats[1].as_slice());
+        let (reference_mean, reference_std) = (means[means.len() - 1], stds[stds.len() - 1]);
+        let settled = |k: usize| {
+            (means[k] - reference_mean).abs() <= tolerance * reference_std
+                && (stds[k] - reference_std).abs() <= tolerance * reference_std
+        };
+        // Window `k` covers points `k..k + window`, so the first window of the settled
+        // run starts right after the warm-up.
+        let unsettled = (0..means.len()).rev().find(|&k| !settled(k));
+        Ok(unsettled.map_or(0, |k| k + 1))
+    }
+
+    fn trim_warmup(&self, window: usize, tolerance: f64) -> Result<WarmUp, Error> {
+        let length = self.warmup_length(window, tolerance)?;
+        Ok(WarmUp { length, steady: self.slice(length..self.len()) })
+    }
+}
@@ -0,0 +1,417 @@
+//! Push updates of live series to WebSocket subscribers, e.g. dashboards.
+//!
+//! Clients send text frames `{"subscribe": ["name", ...]}` or
+//! `{"unsubscribe": [...]}`, where `"*"` stands for every series, and are answered with
+//! `{"type": "subscribed", "series": [...]}`. Subscribers then receive
+//! `{"type": "point", "series", "timestamp", "value"}` for each appended point and
+//! `{"type": "update", "series", "timestamps", "values"}` for batches such as a
+//! recomputed derived series. With a [`Catalog`] attached, a subscription first
+//! receives a `{"type": "snapshot", ...}` of the stored points. NaN becomes `null`.
+//! A message that would take a connection past [`ServerLimits::max_subscriptions`] is
+//! answered with `{"type": "error", ...}` and changes nothing.
+
+use crate::service::{accept_connections, read_bounded_line, Catalog, DeadlineReader, ServerLimits};
+use crate::TimestampedSeries;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Largest client frame accepted; subscription messages are far smaller.
+const MAX_FRAME: u64 = 64 * 1024;
+/// Frames queued for one subscriber; a subscriber further behind is disconnected rather
+/// than slowing down publishing to the others.
+const QUEUE_FRAMES: usize = 1024;
+/// Silence after which a client is pinged, and after which a pinged client is dropped.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+#[derive(Debug)]
+struct Subscriber {
+    id: u64,
+    /// Frames for the subscriber's writer thread, so that no write happens under the lock.
+    outbox: SyncSender<Arc<[u8]>>,
+    series: HashSet<String>,
+}
+
+impl Subscriber {
+    fn wants(&self, series: &str) -> bool {
+        self.series.contains(series) || self.series.contains("*")
+    }
+}
+
+/// Fans out series updates to WebSocket clients. Clones share the same subscribers.
+#[derive(Clone, Debug, Default)]
+pub struct Broadcaster {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    next_id: Arc<AtomicU64>,
+    catalog: Option<Arc<Catalog>>,
+    limits: ServerLimits,
+}
+
+impl Broadcaster {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores pushed points in `catalog` and sends new subscribers a snapshot from it.
+    pub fn with_catalog(self, catalog: Arc<Catalog>) -> Self {
+        Self { catalog: Some(catalog), ..self }
+    }
+
+    /// Bounds connections and the handshake by `limits`; its timeout also bounds each write
+    /// to a client and the arrival of each frame once it starts.
+    pub fn with_limits(self, limits: ServerLimits) -> Self {
+        Self { limits, ..self }
+    }
+
+    /// Number of connected clients.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().expect("subscriber lock poisoned").len()
+    }
+
+    /// Appends a point to the catalog, if any, and sends it to the subscribers of `series`.
+    ///
+    /// # Panics
+    /// Panics if the catalog holds a later point of `series` or would exceed its quotas.
+    pub fn push(&self, series: &str, timestamp: i64, value: f64) {
+        if let Some(catalog) = &self.catalog {
+            catalog.push(series, timestamp, value);
+        }
+        self.send(series, &json!({ "type": "point", "series": series, "timestamp": timestamp, "value": value }));
+    }
+
+    /// Sends a batch of points of `series`, e.g. a derived series after recomputation.
+    pub fn publish(&self, series: &str, points: &TimestampedSeries<f64>) {
+        self.send(series, &update("update", series, points));
+    }
+
+    /// Queues `message` for every subscriber of `series`, dropping clients that have gone
+    /// or fallen too far behind.
+    fn send(&self, series: &str, message: &Value) {
+        let frame: Arc<[u8]> = encode_frame(0x1, message.to_string().as_bytes()).into();
+        let mut subscribers = self.subscribers.lock().expect("subscriber lock poisoned");
+        subscribers.retain(|s| !s.wants(series) || s.outbox.try_send(frame.clone()).is_ok());
+    }
+
+    /// Queues a frame for the subscriber `id`.
+    fn reply_frame(&self, id: u64, opcode: u8, payload: &[u8]) -> io::Result<()> {
+        let frame: Arc<[u8]> = encode_frame(opcode, payload).into();
+        let subscribers = self.subscribers.lock().expect("subscriber lock poisoned");
+        match subscribers.iter().find(|s| s.id == id) {
+            Some(s) => s.outbox.try_send(frame).map_err(|_| io::Error::new(io::ErrorKind::WouldBlock, "client behind")),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        }
+    }
+
+    /// Queues `message` for the subscriber `id`.
+    fn reply(&self, id: u64, message: &Value) -> io::Result<()> {
+        self.reply_frame(id, 0x1, message.to_string().as_bytes())
+    }
+
+    /// Applies a subscription message and returns the reply.
+    fn handle_message(&self, id: u64, text: &str) -> Value {
+        let Ok(message) = serde_json::from_str::<Value>(text) else {
+            return json!({ "type": "error", "error": "messages must be JSON objects" });
+        };
+        let names = |key: &str| -> Vec<String> {
+            let list = message.get(key).and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+            list.iter().filter_map(Value::as_str).map(str::to_string).collect()
+        };
+        let (subscribe, unsubscribe) = (names("subscribe"), names("unsubscribe"));
+        if subscribe.is_empty() && unsubscribe.is_empty() {
+            return json!({ "type": "error", "error": "expected `subscribe` or `unsubscribe` lists" });
+        }
+        let mut subscribers = self.subscribers.lock().expect("subscriber lock poisoned");
+        let Some(subscriber) = subscribers.iter_mut().find(|s| s.id == id) else {
+            return Value::Null;
+        };
+        let mut subscribed = subscriber.series.clone();
+        subscribed.extend(subscribe);
+        for name in &unsubscribe {
+            subscribed.remove(name);
+        }
+        if subscribed.len() > self.limits.max_subscriptions {
+            let error = format!("at most {} subscriptions per connection", self.limits.max_subscriptions);
+            return json!({ "type": "error", "error": error });
+        }
+        subscriber.series = subscribed;
+        let mut series: Vec<&String> = subscriber.series.iter().collect();
+        series.sort();
+        json!({ "type": "subscribed", "series": series })
+    }
+
+    /// Snapshots of the catalog's series that subscriber `id` just subscribed to.
+    fn snapshots(&self, id: u64, before: &HashSet<String>) -> Vec<Value> {
+        let Some(catalog) = &self.catalog else {
+            return Vec::new();
+        };
+        let subscribers = self.subscribers.lock().expect("subscriber lock poisoned");
+        let Some(subscriber) = subscribers.iter().find(|s| s.id == id) else {
+            return Vec::new();
+        };
+        let was_subscribed = |name: &str| before.contains(name) || before.contains("*");
+        let added: Vec<String> =
+            catalog.names().into_iter().filter(|name| subscriber.wants(name) && !was_subscribed(name)).collect();
+        drop(subscribers);
+        added.iter().filter_map(|name| catalog.with_series(name, |s| update("snapshot", name, s))).collect()
+    }
+
+    fn subscriptions(&self, id: u64) -> HashSet<String> {
+        let subscribers = self.subscribers.lock().expect("subscriber lock poisoned");
+        subscribers.iter().find(|s| s.id == id).map(|s| s.series.clone()).unwrap_or_default()
+    }
+
+    /// Performs the handshake on `stream`, then serves its subscription messages until
+    /// it closes or goes silent.
+    fn connect(&self, stream: TcpStream) -> io::Result<()> {
+        let limits = self.limits;
+        let deadline = Instant::now() + limits.timeout;
+        let mut reader = BufReader::new(DeadlineReader { stream: stream.try_clone()?, deadline });
+        let key = read_handshake(&mut (&mut reader).take(limits.max_request_bytes));
+        let mut writer = stream;
+        writer.set_write_timeout(Some(limits.timeout))?;
+        let key = match key {
+            Ok(Some(key)) => key,
+            Ok(None) => {
+                writer.write_all(b"HTTP/1.1 400 Bad Request\r\nConnection: close\r\nContent-Length: 0\r\n\r\n")?;
+                return Ok(());
+            }
+            Err(e) if e.kind() == io::ErrorKind::InvalidInput => {
+                writer.write_all(
+                    b"HTTP/1.1 431 Request Header Fields Too Large\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+                )?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        write!(
+            writer,
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\n\r\n",
+            accept_key(&key)
+        )?;
+        // A client that stops reading must not stall publishing to everyone else: frames
+        // go through a bounded queue that its own thread writes out.
+        let (outbox, frames) = mpsc::sync_channel(QUEUE_FRAMES);
+        thread::spawn(move || write_frames(writer, frames));
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let subscriber = Subscriber { id, outbox, series: HashSet::new() };
+        self.subscribers.lock().expect("subscriber lock poisoned").push(subscriber);
+        let result = self.serve_client(id, &mut reader);
+        // Dropping the queue lets the writer finish and close the connection.
+        self.subscribers.lock().expect("subscriber lock poisoned").retain(|s| s.id != id);
+        result
+    }
+
+    fn serve_client(&self, id: u64, reader: &mut BufReader<DeadlineReader>) -> io::Result<()> {
+        let mut pinged = false;
+        loop {
+            // Wait for the start of a frame; silence is met with a ping, then a hang-up.
+            reader.get_mut().deadline = Instant::now() + IDLE_TIMEOUT;
+            match reader.fill_buf() {
+                Ok([]) => return Ok(()),
+                Ok(_) => pinged = false,
+                Err(e) if matches!(e.kind(), io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock) && !pinged => {
+                    self.reply_frame(id, 0x9, &[])?;
+                    pinged = true;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+            reader.get_mut().deadline = Instant::now() + self.limits.timeout;
+            let (opcode, payload) = read_frame(reader)?;
+            match opcode {
+                0x1 => {
+                    let before = self.subscriptions(id);
+                    let reply = self.handle_message(id, &String::from_utf8_lossy(&payload));
+                    self.reply(id, &reply)?;
+                    for snapshot in self.snapshots(id, &before) {
+                        self.reply(id, &snapshot)?;
+                    }
+                }
+                0x8 => {
+                    let _ = self.reply_frame(id, 0x8, &[]);
+                    return Ok(());
+                }
+                0x9 => self.reply_frame(id, 0xA, &payload)?,
+                // Pongs and binary frames carry nothing for us.
+                _ => {}
+            }
+        }
+    }
+
+    /// Accepts WebSocket clients on `addr`, one thread per connection within the
+    /// broadcaster's [`ServerLimits`]. Fails only if `addr` cannot be bound; failed accepts
+    /// are retried.
+    pub fn serve<A: ToSocketAddrs>(&self, addr: A) -> io::Result<()> {
+        self.serve_on(TcpListener::bind(addr)?)
+    }
+
+    /// Accepts WebSocket clients on an already bound listener.
+    pub fn serve_on(&self, listener: TcpListener) -> io::Result<()> {
+        let broadcaster = self.clone();
+        accept_connections(listener, self.limits, move |stream| {
+            // A client hanging up only affects its own connection.
+            let _ = broadcaster.connect(stream);
+        })
+    }
+}
+
+/// Writes queued frames to a client until its queue closes or a write fails, then closes
+/// the connection, which also ends the reading side.
+fn write_frames(mut stream: TcpStream, frames: Receiver<Arc<[u8]>>) {
+    for frame in frames {
+        if stream.write_all(&frame).is_err() {
+            break;
+        }
+    }
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+fn update(kind: &str, series: &str, points: &TimestampedSeries<f64>) -> Value {
+    json!({ "type": kind, "series": series, "timestamps": points.timestamps(), "values": points.values().as_slice() })
+}
+
+/// Reads the HTTP upgrade request and returns its `Sec-WebSocket-Key`, if any, failing
+/// with [`io::ErrorKind::InvalidInput`] if it is longer than `reader` allows.
+fn read_handshake<R: BufRead>(reader: &mut io::Take<R>) -> io::Result<Option<String>> {
+    let mut key = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        read_bounded_line(reader, &mut line)?;
+        if line.trim_end().is_empty() {
+            return Ok(key);
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("sec-websocket-key") {
+                key = Some(value.trim().to_string());
+            }
+        }
+    }
+}
+
+/// The `Sec-WebSocket-Accept` answer to a client key (RFC 6455, section 4.2.2).
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{}{}", key, HANDSHAKE_GUID).as_bytes()))
+}
+
+/// An unmasked, unfragmented server frame.
+fn encode_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        n if n < 126 => frame.push(n as u8),
+        n if n <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            frame.push(127);
+            frame.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Reads one client frame and returns its opcode and unmasked payload. Fragmented
+/// messages are not supported.
+fn read_frame<R: Read>(reader: &mut R) -> io::Result<(u8, Vec<u8>)> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut header = [0; 2];
+    reader.read_exact(&mut header)?;
+    if header[0] & 0x80 == 0 || header[0] & 0x0F == 0 {
+        return Err(invalid("fragmented frames are not supported"));
+    }
+    let len = match header[1] & 0x7F {
+        126 => {
+            let mut len = [0; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_be_bytes(len) as u64
+        }
+        127 => {
+            let mut len = [0; 8];
+            reader.read_exact(&mut len)?;
+            u64::from_be_bytes(len)
+        }
+        n => n as u64,
+    };
+    if len > MAX_FRAME {
+        return Err(invalid("frame too large"));
+    }
+    let mut mask = [0; 4];
+    if header[1] & 0x80 != 0 {
+        reader.read_exact(&mut mask)?;
+    }
+    let mut payload = vec![0; len as usize];
+    reader.read_exact(&mut payload)?;
+    for (i, b) in payload.iter_mut().enumerate() {
+        *b ^= mask[i % 4];
+    }
+    Ok((header[0] & 0x0F, payload))
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476, 0xC3D2_E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let t = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = t;
+        }
+        for (h, v) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(v);
+        }
+    }
+    let mut digest = [0; 20];
+    for (out, word) in digest.chunks_mut(4).zip(h) {
+        out.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,34 @@
+//! Checks the crate against the golden values in `golden/`, regenerated with
+//! `golden/generate.py` from pandas and statsmodels.
+
+use time_series_utils::{GoldenHarness, Tolerance};
+
+fn harness() -> GoldenHarness {
+    GoldenHarness::new()
+        .with_tolerance(Tolerance::new(1e-9, 1e-9))
+        .with_operation_tolerance("stl", Tolerance::new(1e-6, 1e-6))
+}
+
+#[test]
+fn golden_files_match() {
+    let report = harness().check_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/golden")).unwrap();
+    let mismatches: Vec<String> = report.mismatches.iter().map(ToString::to_string).collect();
+    assert!(report.is_ok(), "{} mismatches:\n{}", mismatches.len(), mismatches.join("\n"));
+    assert!(report.passed > 0, "no golden values checked");
+}
+
+#[test]
+fn acf_cases_are_checked() {
+    // Deviations -1, 0, 1: autocovariances 2/3, 0 and -1/3.
+    let golden = r#"{"cases": [{"name": "ramp", "operation": "acf", "input": [1, 2, 3], "params": {"nlags": 2},
+        "expected": {"acf": [1, 0, -0.5]}}]}"#;
+    let report = harness().check_str(golden).unwrap();
+    assert_eq!((report.passed, report.mismatches.len()), (3, 0));
+}
+
+#[test]
+fn unimplemented_operations_are_reported() {
+    let golden = r#"{"cases": [{"name": "monthly", "operation": "prophet", "input": [1, 2], "expected": {}}]}"#;
+    let report = harness().check_str(golden).unwrap();
+    assert_eq!(report.unsupported, ["monthly (prophet)"]);
+}